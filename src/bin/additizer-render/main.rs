@@ -0,0 +1,262 @@
+use std::{
+    env,
+    fs::{self, File},
+    io::BufReader,
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+use additizer::{
+    Additizer,
+    preset::Preset,
+    synth_engine::{Expression, NUM_CHANNELS, Sample, SynthEngine},
+};
+use hound::{SampleFormat, WavSpec, WavWriter};
+
+mod midi_file;
+mod note_list;
+
+const USAGE: &str = "\
+Usage: additizer-render <preset.json> <notes.mid | notes.txt> <output.wav> [options]
+
+Options:
+  --sample-rate <hz>    Sample rate to render at (default 48000)
+  --tail <seconds>      Time rendered after the last event (default 2)
+
+A note list has one note per line: <start> <note> <velocity> <duration>,
+times in seconds, velocity 0-1. Lines starting with # are skipped.";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Event {
+    NoteOn {
+        channel: u8,
+        note: u8,
+        velocity: Sample,
+    },
+    NoteOff {
+        channel: u8,
+        note: u8,
+        velocity: Sample,
+    },
+    PitchBend {
+        channel: u8,
+        value: Sample,
+    },
+    MidiCc {
+        cc: u8,
+        value: Sample,
+    },
+    ChannelPressure {
+        channel: u8,
+        pressure: Sample,
+    },
+}
+
+impl Event {
+    // Same order as the plugin applies simultaneous events in, releases before new notes
+    fn priority(&self) -> u8 {
+        match self {
+            Self::NoteOff { .. } => 0,
+            Self::NoteOn { .. } => 1,
+            _ => 2,
+        }
+    }
+
+    fn apply(&self, engine: &mut SynthEngine) {
+        match *self {
+            Self::NoteOn {
+                channel,
+                note,
+                velocity,
+            } => engine.handle_note_on(channel, note, velocity),
+            Self::NoteOff {
+                channel,
+                note,
+                velocity,
+            } => engine.handle_note_off(channel, note, velocity),
+            Self::PitchBend { channel, value } => engine.handle_pitch_bend(channel, value),
+            Self::MidiCc { cc, value } => engine.handle_midi_cc(cc, value),
+            Self::ChannelPressure { channel, pressure } => {
+                engine.handle_channel_expression(channel, Expression::Pressure, pressure)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TimedEvent {
+    pub time: f64, // Seconds
+    pub event: Event,
+}
+
+struct Args {
+    preset: PathBuf,
+    notes: PathBuf,
+    output: PathBuf,
+    sample_rate: u32,
+    tail: f64,
+}
+
+impl Args {
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut paths = Vec::new();
+        let mut sample_rate = 48_000;
+        let mut tail = 2.0;
+
+        while let Some(arg) = args.next() {
+            let mut value = |name: &str| {
+                args.next()
+                    .ok_or_else(|| format!("Missing value for {name}."))
+            };
+
+            match arg.as_str() {
+                "--sample-rate" => {
+                    sample_rate = value(&arg)?
+                        .parse()
+                        .ok()
+                        .filter(|rate| *rate > 0)
+                        .ok_or("Invalid sample rate.")?;
+                }
+                "--tail" => {
+                    tail = value(&arg)?
+                        .parse()
+                        .ok()
+                        .filter(|tail: &f64| *tail >= 0.0)
+                        .ok_or("Invalid tail time.")?;
+                }
+                "-h" | "--help" => return Err(USAGE.to_string()),
+                _ if arg.starts_with("--") => return Err(format!("Unknown option {arg}.")),
+                _ => paths.push(PathBuf::from(arg)),
+            }
+        }
+
+        let [preset, notes, output] = <[PathBuf; 3]>::try_from(paths)
+            .map_err(|_| format!("Expected preset, notes and output paths.\n\n{USAGE}"))?;
+
+        Ok(Self {
+            preset,
+            notes,
+            output,
+            sample_rate,
+            tail,
+        })
+    }
+}
+
+fn read_preset(path: &Path) -> Result<Preset, String> {
+    let file = File::open(path).map_err(|err| format!("{}: {err}", path.display()))?;
+
+    serde_json::from_reader(BufReader::new(file))
+        .map_err(|err| format!("{}: {err}", path.display()))
+}
+
+fn read_events(path: &Path) -> Result<Vec<TimedEvent>, String> {
+    let data = fs::read(path).map_err(|err| format!("{}: {err}", path.display()))?;
+
+    let mut events = if data.starts_with(b"MThd") {
+        midi_file::parse(&data)
+    } else {
+        String::from_utf8(data)
+            .map_err(|err| err.to_string())
+            .and_then(|text| note_list::parse(&text))
+    }
+    .map_err(|err| format!("{}: {err}", path.display()))?;
+
+    events.sort_by(|a, b| {
+        a.time
+            .total_cmp(&b.time)
+            .then(a.event.priority().cmp(&b.event.priority()))
+    });
+
+    Ok(events)
+}
+
+fn process_range(
+    engine: &mut SynthEngine,
+    output: &mut [Vec<Sample>; NUM_CHANNELS],
+    mut sample_from: usize,
+    sample_to: usize,
+) {
+    let [left, right] = output;
+
+    while sample_from < sample_to {
+        let samples = engine.block_size().min(sample_to - sample_from);
+        let range = sample_from..sample_from + samples;
+
+        engine.process(
+            samples,
+            false,
+            [&mut left[range.clone()], &mut right[range]].into_iter(),
+        );
+        sample_from += samples;
+    }
+}
+
+// Events are applied at their exact sample, like the plugin splits host buffers at note events
+fn render(
+    engine: &mut SynthEngine,
+    events: &[TimedEvent],
+    sample_rate: u32,
+    tail: f64,
+) -> [Vec<Sample>; NUM_CHANNELS] {
+    let to_samples = |time: f64| (time * sample_rate as f64).round() as usize;
+    let length = events.last().map_or(0, |event| to_samples(event.time)) + to_samples(tail);
+    let mut output = [vec![0.0; length], vec![0.0; length]];
+    let mut sample_from = 0;
+
+    for event in events {
+        let sample_to = to_samples(event.time);
+
+        process_range(engine, &mut output, sample_from, sample_to);
+        event.event.apply(engine);
+        sample_from = sample_to;
+    }
+
+    process_range(engine, &mut output, sample_from, length);
+    output
+}
+
+fn write_wav(
+    path: &Path,
+    output: &[Vec<Sample>; NUM_CHANNELS],
+    sample_rate: u32,
+) -> Result<(), String> {
+    let spec = WavSpec {
+        channels: NUM_CHANNELS as u16,
+        sample_rate,
+        bits_per_sample: 32,
+        sample_format: SampleFormat::Float,
+    };
+    let [left, right] = output;
+    let mut writer = WavWriter::create(path, spec).map_err(|err| err.to_string())?;
+
+    for (left, right) in left.iter().zip(right) {
+        writer.write_sample(*left).map_err(|err| err.to_string())?;
+        writer.write_sample(*right).map_err(|err| err.to_string())?;
+    }
+
+    writer.finalize().map_err(|err| err.to_string())
+}
+
+fn run() -> Result<(), String> {
+    let args = Args::parse(env::args().skip(1))?;
+    let preset = read_preset(&args.preset)?;
+    let events = read_events(&args.notes)?;
+    let mut engine = Additizer::offline_engine(&preset.engine, args.sample_rate as Sample)
+        .ok_or_else(|| format!("{}: invalid patch.", args.preset.display()))?;
+
+    let output = render(&mut engine, &events, args.sample_rate, args.tail);
+
+    write_wav(&args.output, &output, args.sample_rate)
+        .map_err(|err| format!("{}: {err}", args.output.display()))
+}
+
+fn main() -> ExitCode {
+    match run() {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("{err}");
+            ExitCode::FAILURE
+        }
+    }
+}
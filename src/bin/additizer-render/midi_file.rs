@@ -0,0 +1,275 @@
+use additizer::synth_engine::Sample;
+
+use crate::{Event, TimedEvent};
+
+const DEFAULT_TEMPO: u32 = 500_000; // Microseconds per quarter note, 120 BPM
+const RELEASE_VELOCITY: Sample = 0.5; // Note ons with zero velocity have none
+
+enum TrackEvent {
+    Event(Event),
+    Tempo(u32),
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0 }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.pos >= self.data.len()
+    }
+
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8], String> {
+        let bytes = self
+            .data
+            .get(self.pos..self.pos + len)
+            .ok_or("Unexpected end of MIDI file.")?;
+
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn data_byte(&mut self) -> Result<u8, String> {
+        Ok(self.u8()? & 0x7F)
+    }
+
+    fn u16(&mut self) -> Result<u16, String> {
+        Ok(u16::from_be_bytes(self.bytes(2)?.try_into().unwrap()))
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        Ok(u32::from_be_bytes(self.bytes(4)?.try_into().unwrap()))
+    }
+
+    // Variable-length quantity, 7 bits per byte with the high bit set on all but the last one
+    fn var_len(&mut self) -> Result<usize, String> {
+        let mut value = 0;
+
+        for _ in 0..4 {
+            let byte = self.u8()?;
+
+            value = (value << 7) | usize::from(byte & 0x7F);
+
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+
+        Err("Invalid variable-length value.".to_string())
+    }
+}
+
+fn parse_track(mut reader: Reader, events: &mut Vec<(u64, TrackEvent)>) -> Result<(), String> {
+    let mut tick = 0u64;
+    let mut running_status = None;
+
+    while !reader.is_empty() {
+        tick += reader.var_len()? as u64;
+
+        let status = match reader.u8()? {
+            status if status >= 0x80 => status,
+            _ => {
+                reader.pos -= 1;
+                running_status.ok_or("Data byte without a running status.")?
+            }
+        };
+
+        match status {
+            0xFF => {
+                let meta_type = reader.u8()?;
+                let len = reader.var_len()?;
+                let data = reader.bytes(len)?;
+
+                running_status = None;
+
+                match (meta_type, data) {
+                    (0x51, &[a, b, c]) => {
+                        events.push((tick, TrackEvent::Tempo(u32::from_be_bytes([0, a, b, c]))));
+                    }
+                    (0x2F, _) => break,
+                    _ => (),
+                }
+            }
+            0xF0 | 0xF7 => {
+                let len = reader.var_len()?;
+
+                reader.bytes(len)?;
+                running_status = None;
+            }
+            0x80..=0xEF => {
+                let channel = status & 0x0F;
+
+                running_status = Some(status);
+
+                let event = match status & 0xF0 {
+                    0x80 => Some(Event::NoteOff {
+                        channel,
+                        note: reader.data_byte()?,
+                        velocity: Sample::from(reader.data_byte()?) / 127.0,
+                    }),
+                    0x90 => {
+                        let note = reader.data_byte()?;
+
+                        Some(match reader.data_byte()? {
+                            0 => Event::NoteOff {
+                                channel,
+                                note,
+                                velocity: RELEASE_VELOCITY,
+                            },
+                            velocity => Event::NoteOn {
+                                channel,
+                                note,
+                                velocity: Sample::from(velocity) / 127.0,
+                            },
+                        })
+                    }
+                    0xB0 => Some(Event::MidiCc {
+                        cc: reader.data_byte()?,
+                        value: Sample::from(reader.data_byte()?) / 127.0,
+                    }),
+                    0xD0 => Some(Event::ChannelPressure {
+                        channel,
+                        pressure: Sample::from(reader.data_byte()?) / 127.0,
+                    }),
+                    0xE0 => {
+                        let lsb = u16::from(reader.data_byte()?);
+                        let msb = u16::from(reader.data_byte()?);
+
+                        Some(Event::PitchBend {
+                            channel,
+                            value: Sample::from((msb << 7) | lsb) / 16383.0,
+                        })
+                    }
+                    // Program change has a single data byte, poly aftertouch has two
+                    0xC0 => {
+                        reader.data_byte()?;
+                        None
+                    }
+                    _ => {
+                        reader.bytes(2)?;
+                        None
+                    }
+                };
+
+                if let Some(event) = event {
+                    events.push((tick, TrackEvent::Event(event)));
+                }
+            }
+            _ => return Err(format!("Unexpected MIDI status {status:#04X}.")),
+        }
+    }
+
+    Ok(())
+}
+
+// Standard MIDI file, all tracks are merged and the ticks converted to seconds
+pub fn parse(data: &[u8]) -> Result<Vec<TimedEvent>, String> {
+    let mut reader = Reader::new(data);
+
+    if reader.bytes(4)? != b"MThd" {
+        return Err("Not a MIDI file.".to_string());
+    }
+
+    let header_len = reader.u32()? as usize;
+    let mut header = Reader::new(reader.bytes(header_len)?);
+    let _format = header.u16()?;
+    let num_tracks = header.u16()?;
+    let division = header.u16()?;
+    let mut track_events = Vec::new();
+
+    for _ in 0..num_tracks {
+        let chunk_type = reader.bytes(4)?;
+        let chunk_len = reader.u32()? as usize;
+        let chunk = reader.bytes(chunk_len)?;
+
+        // Unknown chunks are skipped, they don't count as tracks
+        if chunk_type == b"MTrk" {
+            parse_track(Reader::new(chunk), &mut track_events)?;
+        }
+    }
+
+    // Stable, so simultaneous events keep their order within a track
+    track_events.sort_by_key(|(tick, _)| *tick);
+
+    // SMPTE division is a fixed tick length, otherwise ticks are per quarter note
+    let smpte = division & 0x8000 != 0;
+    let tick_length = |tempo: u32| {
+        if smpte {
+            let frames_per_second = -f64::from((division >> 8) as u8 as i8);
+
+            1.0 / (frames_per_second * (division & 0xFF) as f64)
+        } else {
+            tempo as f64 / 1_000_000.0 / division.max(1) as f64
+        }
+    };
+
+    let mut seconds_per_tick = tick_length(DEFAULT_TEMPO);
+    let mut last_tick = 0;
+    let mut time = 0.0;
+    let mut events = Vec::new();
+
+    for (tick, event) in track_events {
+        time += (tick - last_tick) as f64 * seconds_per_tick;
+        last_tick = tick;
+
+        match event {
+            TrackEvent::Event(event) => events.push(TimedEvent { time, event }),
+            TrackEvent::Tempo(tempo) => seconds_per_tick = tick_length(tempo),
+        }
+    }
+
+    Ok(events)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_notes_with_tempo_and_running_status() {
+        let data = [
+            b"MThd".as_slice(),
+            &[0, 0, 0, 6, 0, 0, 0, 1, 0, 96],
+            b"MTrk",
+            &[0, 0, 0, 18],
+            &[0x00, 0xFF, 0x51, 0x03, 0x03, 0xD0, 0x90], // 250000 us per quarter
+            &[0x00, 0x90, 60, 127],
+            &[0x60, 60, 0], // A quarter later, running note on without velocity
+            &[0x00, 0xFF, 0x2F, 0x00],
+        ]
+        .concat();
+
+        let events = parse(&data).unwrap();
+
+        assert_eq!(
+            events,
+            [
+                TimedEvent {
+                    time: 0.0,
+                    event: Event::NoteOn {
+                        channel: 0,
+                        note: 60,
+                        velocity: 1.0
+                    }
+                },
+                TimedEvent {
+                    time: 0.25,
+                    event: Event::NoteOff {
+                        channel: 0,
+                        note: 60,
+                        velocity: RELEASE_VELOCITY
+                    }
+                },
+            ]
+        );
+    }
+}
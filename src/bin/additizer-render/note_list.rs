@@ -0,0 +1,78 @@
+use additizer::synth_engine::Sample;
+
+use crate::{Event, TimedEvent};
+
+const RELEASE_VELOCITY: Sample = 0.5;
+
+fn field<T: std::str::FromStr>(value: Option<&str>, name: &str, line: usize) -> Result<T, String> {
+    value
+        .and_then(|value| value.parse().ok())
+        .ok_or_else(|| format!("line {line}: invalid {name}."))
+}
+
+// One note per line: start, MIDI note, velocity and duration, played on the first channel
+pub fn parse(text: &str) -> Result<Vec<TimedEvent>, String> {
+    let mut events = Vec::new();
+
+    for (idx, line) in text.lines().enumerate() {
+        let line = line.trim();
+
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let line_number = idx + 1;
+        let mut values = line.split_whitespace();
+        let start: f64 = field(values.next(), "start time", line_number)?;
+        let note: u8 = field(values.next(), "note", line_number)?;
+        let velocity: Sample = field(values.next(), "velocity", line_number)?;
+        let duration: f64 = field(values.next(), "duration", line_number)?;
+
+        if start < 0.0 || duration < 0.0 || note > 127 {
+            return Err(format!("line {line_number}: value out of range."));
+        }
+
+        events.push(TimedEvent {
+            time: start,
+            event: Event::NoteOn {
+                channel: 0,
+                note,
+                velocity: velocity.clamp(0.0, 1.0),
+            },
+        });
+        events.push(TimedEvent {
+            time: start + duration,
+            event: Event::NoteOff {
+                channel: 0,
+                note,
+                velocity: RELEASE_VELOCITY,
+            },
+        });
+    }
+
+    Ok(events)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn notes_become_note_on_off_pairs() {
+        let events = parse("# chord\n0 60 1 0.5\n\n0.25 64 0.5 1\n").unwrap();
+
+        assert_eq!(events.len(), 4);
+        assert_eq!(events[1].time, 0.5);
+        assert_eq!(
+            events[2].event,
+            Event::NoteOn {
+                channel: 0,
+                note: 64,
+                velocity: 0.5
+            }
+        );
+        assert_eq!(events[3].time, 1.25);
+        assert!(parse("0 128 1 1").is_err());
+        assert!(parse("0 60 1").is_err());
+    }
+}
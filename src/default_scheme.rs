@@ -3,14 +3,15 @@ use rustc_hash::FxHashMap;
 use crate::{
     preset::{Preset, PresetInfo},
     synth_engine::{
-        EngineConfig, Input, LinkConfig, ModuleConfig, ModuleId, OUTPUT_MODULE_ID, StereoSample,
+        EngineConfig, Input, LinkConfig, LinkCurve, LinkMode, LinkPolarity, ModuleConfig, ModuleId,
+        OUTPUT_MODULE_ID, StereoSample,
         amplifier::AmplifierConfig,
         envelope::EnvelopeConfig,
         harmonic_editor::HarmonicEditorConfig,
         oscillator::OscillatorConfig,
         spectral_filter::SpectralFilterConfig,
         ui_bridge::{
-            GridVec,
+            GridVec, ModuleColor,
             ui_config::{UiConfig, UiModuleConfig},
         },
     },
@@ -53,11 +54,18 @@ fn default_ui_config() -> UiConfig {
                     x: grid_x,
                     y: grid_y,
                 },
+                unlinked_channels: Vec::new(),
+                randomize_locked: false,
+                locked_params: Vec::new(),
+                color: ModuleColor::None,
             },
         );
     }
 
-    UiConfig { modules }
+    UiConfig {
+        modules,
+        groups: Vec::new(),
+    }
 }
 
 fn default_engine_config() -> EngineConfig {
@@ -113,6 +121,10 @@ fn default_engine_config() -> EngineConfig {
                 dst_input: Input::Spectrum,
                 amount: StereoSample::ONE,
                 modulator_id: None,
+                curve: LinkCurve::Linear,
+                polarity: LinkPolarity::Unchanged,
+                offset: 0.0,
+                mode: LinkMode::Add,
             },
             LinkConfig {
                 src_id: FILTER_ENV_ID,
@@ -120,6 +132,10 @@ fn default_engine_config() -> EngineConfig {
                 dst_input: Input::Cutoff,
                 amount: st_to_octave(64.0).into(),
                 modulator_id: None,
+                curve: LinkCurve::Linear,
+                polarity: LinkPolarity::Unchanged,
+                offset: 0.0,
+                mode: LinkMode::Add,
             },
             LinkConfig {
                 src_id: FILTER_ID,
@@ -127,6 +143,10 @@ fn default_engine_config() -> EngineConfig {
                 dst_input: Input::Spectrum,
                 amount: StereoSample::ONE,
                 modulator_id: None,
+                curve: LinkCurve::Linear,
+                polarity: LinkPolarity::Unchanged,
+                offset: 0.0,
+                mode: LinkMode::Add,
             },
             LinkConfig {
                 src_id: OSC_ID,
@@ -134,6 +154,10 @@ fn default_engine_config() -> EngineConfig {
                 dst_input: Input::Audio,
                 amount: StereoSample::ONE,
                 modulator_id: None,
+                curve: LinkCurve::Linear,
+                polarity: LinkPolarity::Unchanged,
+                offset: 0.0,
+                mode: LinkMode::Add,
             },
             LinkConfig {
                 src_id: AMP_ENV_ID,
@@ -141,6 +165,10 @@ fn default_engine_config() -> EngineConfig {
                 dst_input: Input::Gain,
                 amount: StereoSample::ONE,
                 modulator_id: None,
+                curve: LinkCurve::Linear,
+                polarity: LinkPolarity::Unchanged,
+                offset: 0.0,
+                mode: LinkMode::Add,
             },
             LinkConfig {
                 src_id: AMP_ID,
@@ -148,8 +176,15 @@ fn default_engine_config() -> EngineConfig {
                 dst_input: Input::Audio,
                 amount: StereoSample::ONE,
                 modulator_id: None,
+                curve: LinkCurve::Linear,
+                polarity: LinkPolarity::Unchanged,
+                offset: 0.0,
+                mode: LinkMode::Add,
             },
         ],
+        automation: vec![],
+        midi_mappings: vec![],
+        output_trims: vec![],
     }
 }
 
@@ -1,75 +1,233 @@
-use std::sync::Arc;
+use std::{
+    mem,
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
 
-use egui::{CentralPanel, ComboBox, Frame, Id, Panel, ScrollArea, Ui, Vec2, vec2};
-use nih_plug::editor::Editor;
+use egui::{
+    Button, CentralPanel, Color32, ComboBox, Event, Frame, Id, Key, Modal, Modifiers, Panel,
+    RichText, ScrollArea, Sides, Slider, TextEdit, Ui, Vec2, vec2,
+};
+use nih_plug::{context::gui::ParamSetter, editor::Editor};
 use nih_plug_egui::{EguiState, create_egui_editor, resizable_window::ResizableWindow};
+use parking_lot::Mutex;
+use rfd::FileDialog;
+use serde::{Deserialize, Serialize};
 
 use crate::{
     editor::{
         grid::GridEvent,
         modules_ui::{
-            AmplifierUI, EnvelopeUI, ExpressionsUi, ExternalParamUI, HarmonicEditorUI, LfoUi,
-            MixerUi, OscillatorUI, OutputUi, ParamsUi, SpectralBlendUi, SpectralFilterUI,
-            SpectralMixerUi, WaveShaperUi,
+            AmplifierUI, AnalyzerUi, ChorusUi, DelayUi, EnvelopeFollowerUi, EnvelopeUI,
+            ExpressionsUi, ExternalParamUI, FilterUi, HarmonicCombUi, HarmonicEditorUI,
+            HarmonicEnvelopesUi, HarmonicShiftUi, HarmonicStretchUi, LfoUi, MathUi, MidiControlUi,
+            MixerUi, OscillatorUI, OutputUi, PannerUi, ParamsUi, PresetBrowserUi, ReverbUi,
+            RingModUi, SampleResynthUi, ScopeUi, SpectralAnalyzerUi, SpectralBlendUi,
+            SpectralDynamicsUi, SpectralFilterUI, SpectralMixerUi, SpectralTiltUi, WaveShaperUi,
         },
+        undo_history::UndoHistory,
+    },
+    engine_factory::{CompareSlot, EngineFactory},
+    presets::Presets,
+    synth_engine::{
+        ModuleId, ModuleType, RandomParam, Sample,
+        ui_bridge::{HostParamEdit, UiBridge},
     },
-    engine_factory::EngineFactory,
-    synth_engine::{ModuleId, ModuleType, ui_bridge::UiBridge},
 };
 
 mod db_slider;
 mod direct_input;
+mod envelope_plot;
 mod gain_slider;
 mod grid;
 mod modulation_input;
 mod module_label;
 mod modules_ui;
+mod piano_keyboard;
 mod routing_ui_ext;
 mod stereo_slider;
+mod undo_history;
 mod utils;
+mod value_entry;
+mod view_state;
 mod waveform;
 
+pub use view_state::ViewState;
+
+const DEFAULT_DETAIL_HEIGHT: f32 = 300.0;
+const UI_SCALES: [f32; 6] = [0.75, 1.0, 1.25, 1.5, 1.75, 2.0];
+const DEFAULT_RANDOMIZE_AMOUNT: Sample = 0.5;
+
 pub trait ModuleUi {
     fn module_id(&self) -> Option<ModuleId>;
+
+    fn detail_key(&self) -> DetailViewKey {
+        match self.module_id() {
+            Some(id) => DetailViewKey::Module(id),
+            None => DetailViewKey::Params,
+        }
+    }
+
     fn ui(&mut self, bridge: &mut UiBridge, ui: &mut Ui);
 }
 
 type ModuleUIBox = Box<dyn ModuleUi + Send>;
 
-#[derive(Clone, Copy, PartialEq, Eq, Hash)]
-enum DetailViewKey {
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum DetailViewKey {
     Params,
+    Presets,
+    Scope,
+    Analyzer,
     Module(ModuleId),
 }
 
-impl DetailViewKey {
-    fn from_view(view: &ModuleUIBox) -> Self {
-        match view.module_id() {
-            Some(id) => DetailViewKey::Module(id),
-            None => DetailViewKey::Params,
-        }
-    }
-}
-
 struct EditorState {
     engine_factory: Arc<EngineFactory>,
     ui_bridge: UiBridge,
     grid_module_ui: Option<ModuleUIBox>,
     grid: grid::Grid,
+    capture_error: Option<String>,
+    group_name: Option<String>,
+    remove_module_id: Option<ModuleId>,
+    undo_history: UndoHistory,
+    standby_undo_history: Option<UndoHistory>,
+    view_state: Arc<Mutex<ViewState>>,
+    detail_height: f32,
+    ui_scale: f32,
+    keyboard: piano_keyboard::PianoKeyboard,
+    show_keyboard: bool,
+    randomize_amount: Sample,
 }
 
 impl EditorState {
-    pub fn new(engine_factory: Arc<EngineFactory>) -> Self {
-        let bridge =
-            UiBridge::create(engine_factory.get_engine(), engine_factory.get_ui_config()).unwrap();
+    pub fn new(engine_factory: Arc<EngineFactory>, view_state: Arc<Mutex<ViewState>>) -> Self {
+        let bridge = engine_factory.take_ui_bridge().unwrap();
+        let saved = view_state.lock().clone();
+        let mut grid = grid::Grid::new();
+
+        grid.restore_scroll_offset(vec2(saved.grid_offset[0], saved.grid_offset[1]));
 
         Self {
             engine_factory: engine_factory.clone(),
+            grid_module_ui: saved
+                .detail
+                .and_then(|key| detail_ui(key, &engine_factory, &bridge)),
             ui_bridge: bridge,
-            grid_module_ui: None,
-            grid: grid::Grid::new(),
+            grid,
+            capture_error: None,
+            group_name: None,
+            remove_module_id: None,
+            undo_history: UndoHistory::new(&bridge.preset()),
+            standby_undo_history: None,
+            view_state,
+            detail_height: saved.detail_height.unwrap_or(DEFAULT_DETAIL_HEIGHT),
+            ui_scale: saved
+                .ui_scale
+                .unwrap_or(1.0)
+                .clamp(UI_SCALES[0], UI_SCALES[5]),
+            keyboard: piano_keyboard::PianoKeyboard::new(),
+            show_keyboard: saved.keyboard,
+            randomize_amount: DEFAULT_RANDOMIZE_AMOUNT,
+        }
+    }
+
+    // Master tune and transpose edits go through the host, which then hands them to the engine
+    fn apply_host_edits(&mut self, setter: &ParamSetter) {
+        let edits = self.ui_bridge.take_host_edits();
+        let params = self.ui_bridge.external_params();
+
+        for edit in edits {
+            match edit {
+                HostParamEdit::MasterTune(master_tune) => {
+                    setter.begin_set_parameter(params.master_tune.as_ref());
+                    setter.set_parameter(params.master_tune.as_ref(), master_tune);
+                    setter.end_set_parameter(params.master_tune.as_ref());
+                }
+                HostParamEdit::Transpose(transpose) => {
+                    setter.begin_set_parameter(params.transpose.as_ref());
+                    setter.set_parameter(params.transpose.as_ref(), transpose);
+                    setter.end_set_parameter(params.transpose.as_ref());
+                }
+            }
         }
     }
+
+    fn save_view_state(&self) {
+        let offset = self.grid.scroll_offset();
+
+        *self.view_state.lock() = ViewState {
+            detail: self.grid_module_ui.as_ref().map(|panel| panel.detail_key()),
+            detail_height: Some(self.detail_height),
+            grid_offset: [offset.x, offset.y],
+            ui_scale: Some(self.ui_scale),
+            keyboard: self.show_keyboard,
+        };
+    }
+
+    // The factory made the bridge along with the engine, the editor doesn't lock the engine
+    fn reload_bridge(&mut self) {
+        if let Some(bridge) = self.engine_factory.take_ui_bridge() {
+            self.ui_bridge = bridge;
+            self.grid = grid::Grid::new();
+        }
+    }
+
+    // Keeps the opened panel, it's dropped later if the module is gone
+    fn undo(&mut self) {
+        if self
+            .undo_history
+            .undo(&self.engine_factory, &self.ui_bridge)
+        {
+            self.reload_bridge();
+        }
+    }
+
+    fn redo(&mut self) {
+        if self.undo_history.redo(&self.engine_factory) {
+            self.reload_bridge();
+        }
+    }
+
+    // Each compare slot keeps its own undo history
+    fn switch_compare_slot(&mut self) {
+        let current = self.ui_bridge.preset();
+
+        if self.engine_factory.switch_compare_slot(&current) {
+            // The first switch starts B as a copy of A
+            let history = self
+                .standby_undo_history
+                .take()
+                .unwrap_or_else(|| UndoHistory::new(&current));
+
+            self.standby_undo_history = Some(mem::replace(&mut self.undo_history, history));
+        }
+    }
+
+    // A new seed each time, the bridge is reloaded once the randomized engine is swapped in
+    fn randomize(&mut self) {
+        let seed = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |time| time.as_nanos() as u64);
+
+        self.engine_factory
+            .randomize(self.ui_bridge.preset(), self.randomize_amount, seed);
+    }
+
+    fn copy_a_to_b(&mut self) {
+        if self.engine_factory.copy_a_to_b(&self.ui_bridge.preset())
+            && self.engine_factory.compare_slot() == CompareSlot::A
+        {
+            self.standby_undo_history = None;
+        }
+    }
+}
+
+// Closing the window keeps the state, the engine releases the key on its own then
+impl Drop for EditorState {
+    fn drop(&mut self) {
+        self.keyboard.release(&mut self.ui_bridge);
+    }
 }
 
 impl ModuleType {
@@ -88,6 +246,23 @@ impl ModuleType {
             Self::SpectralMixer => Box::new(SpectralMixerUi::new(id)),
             Self::WaveShaper => Box::new(WaveShaperUi::new(id)),
             Self::Expressions => Box::new(ExpressionsUi::new(id)),
+            Self::MidiControl => Box::new(MidiControlUi::new(id)),
+            Self::Chorus => Box::new(ChorusUi::new(id)),
+            Self::Delay => Box::new(DelayUi::new(id)),
+            Self::Reverb => Box::new(ReverbUi::new(id)),
+            Self::RingMod => Box::new(RingModUi::new(id)),
+            Self::Panner => Box::new(PannerUi::new(id)),
+            Self::Filter => Box::new(FilterUi::new(id)),
+            Self::SpectralTilt => Box::new(SpectralTiltUi::new(id)),
+            Self::HarmonicStretch => Box::new(HarmonicStretchUi::new(id)),
+            Self::HarmonicShift => Box::new(HarmonicShiftUi::new(id)),
+            Self::SpectralDynamics => Box::new(SpectralDynamicsUi::new(id)),
+            Self::HarmonicEnvelopes => Box::new(HarmonicEnvelopesUi::new(id)),
+            Self::SampleResynth => Box::new(SampleResynthUi::new(id)),
+            Self::Math => Box::new(MathUi::new(id)),
+            Self::HarmonicComb => Box::new(HarmonicCombUi::new(id)),
+            Self::EnvelopeFollower => Box::new(EnvelopeFollowerUi::new(id)),
+            Self::SpectralAnalyzer => Box::new(SpectralAnalyzerUi::new(id)),
         }
     }
 }
@@ -100,84 +275,468 @@ fn module_ui_for_id(bridge: &UiBridge, id: ModuleId) -> Option<ModuleUIBox> {
         .map(|module| module.module_type.ui(module.id))
 }
 
+fn detail_ui(
+    key: DetailViewKey,
+    factory: &Arc<EngineFactory>,
+    bridge: &UiBridge,
+) -> Option<ModuleUIBox> {
+    match key {
+        DetailViewKey::Params => Some(Box::new(ParamsUi::new())),
+        DetailViewKey::Presets => Some(Box::new(PresetBrowserUi::new(factory.clone()))),
+        DetailViewKey::Scope => Some(Box::new(ScopeUi::new())),
+        DetailViewKey::Analyzer => Some(Box::new(AnalyzerUi::new())),
+        DetailViewKey::Module(id) => module_ui_for_id(bridge, id),
+    }
+}
+
+// Add Module menu sections in display order
+const ADD_MODULE_MENU: &[(&str, &[ModuleType])] = &[
+    (
+        "Generators",
+        &[
+            ModuleType::HarmonicEditor,
+            ModuleType::HarmonicComb,
+            ModuleType::Oscillator,
+            ModuleType::SampleResynth,
+        ],
+    ),
+    (
+        "Modulators",
+        &[
+            ModuleType::Envelope,
+            ModuleType::Lfo,
+            ModuleType::EnvelopeFollower,
+            ModuleType::ExternalParam,
+            ModuleType::Expressions,
+            ModuleType::MidiControl,
+            ModuleType::Math,
+        ],
+    ),
+    (
+        "Spectral",
+        &[
+            ModuleType::SpectralFilter,
+            ModuleType::SpectralBlend,
+            ModuleType::SpectralMixer,
+            ModuleType::SpectralTilt,
+            ModuleType::SpectralDynamics,
+            ModuleType::HarmonicStretch,
+            ModuleType::HarmonicShift,
+            ModuleType::HarmonicEnvelopes,
+            ModuleType::SpectralAnalyzer,
+        ],
+    ),
+    (
+        "Utility",
+        &[
+            ModuleType::Filter,
+            ModuleType::WaveShaper,
+            ModuleType::Amplifier,
+            ModuleType::Mixer,
+            ModuleType::RingMod,
+            ModuleType::Panner,
+            ModuleType::Chorus,
+            ModuleType::Delay,
+            ModuleType::Reverb,
+        ],
+    ),
+];
+
 fn show_add_module_menu(ui: &mut Ui, bridge: &mut UiBridge) {
     ComboBox::from_id_salt("add-module-dropdown")
         .selected_text("Add Module")
         .show_ui(ui, |ui| {
-            if ui.selectable_label(false, "Harmonic Editor").clicked() {
-                bridge.add_module(ModuleType::HarmonicEditor);
-            }
-            if ui.selectable_label(false, "Oscillator").clicked() {
-                bridge.add_module(ModuleType::Oscillator);
-            }
-            if ui.selectable_label(false, "Envelope").clicked() {
-                bridge.add_module(ModuleType::Envelope);
-            }
-            if ui.selectable_label(false, "LFO").clicked() {
-                bridge.add_module(ModuleType::Lfo);
-            }
-            if ui.selectable_label(false, "Spectral Filter").clicked() {
-                bridge.add_module(ModuleType::SpectralFilter);
-            }
-            if ui.selectable_label(false, "Spectral Blend").clicked() {
-                bridge.add_module(ModuleType::SpectralBlend);
-            }
-            if ui.selectable_label(false, "Spectral Mixer").clicked() {
-                bridge.add_module(ModuleType::SpectralMixer);
-            }
-            if ui.selectable_label(false, "External Parameter").clicked() {
-                bridge.add_module(ModuleType::ExternalParam);
+            for (idx, (category, module_types)) in ADD_MODULE_MENU.iter().enumerate() {
+                if idx > 0 {
+                    ui.separator();
+                }
+
+                ui.label(RichText::new(*category).small().weak());
+
+                for module_type in *module_types {
+                    if ui.selectable_label(false, module_type.label()).clicked() {
+                        bridge.add_module(*module_type);
+                    }
+                }
             }
-            if ui.selectable_label(false, "Expressions").clicked() {
-                bridge.add_module(ModuleType::Expressions);
+
+            let sub_patches = Presets::new()
+                .map(|presets| presets.read_sub_patches_list())
+                .unwrap_or_default();
+
+            if !sub_patches.is_empty() {
+                ui.separator();
+                ui.label(RichText::new("Sub-patches").small().weak());
             }
-            if ui.selectable_label(false, "Waveshaper").clicked() {
-                bridge.add_module(ModuleType::WaveShaper);
+
+            for (name, path) in sub_patches {
+                if ui.selectable_label(false, name).clicked()
+                    && let Some(sub_patch) = Presets::read_sub_patch(&path)
+                    && let Err(err) = bridge.insert_sub_patch(&sub_patch)
+                {
+                    println!("Failed to insert sub-patch: {err}");
+                }
             }
-            if ui.selectable_label(false, "Amplifier").clicked() {
-                bridge.add_module(ModuleType::Amplifier);
+        });
+}
+
+// Groups the modules selected with Shift+click and saves them as a sub-patch
+fn show_group_controls(ui: &mut Ui, editor_state: &mut EditorState) {
+    let selection = editor_state.grid.group_selection();
+
+    if ui
+        .add_enabled(!selection.is_empty(), Button::new("Group"))
+        .on_hover_text("Group the modules selected with Shift+click and save them as a sub-patch")
+        .clicked()
+    {
+        editor_state.group_name = Some(String::new());
+    }
+
+    let Some(name) = editor_state.group_name.as_mut() else {
+        return;
+    };
+
+    let trimmed = name.trim().to_string();
+
+    let modal = Modal::new(Id::new("group-modules-modal")).show(ui.ctx(), |ui| {
+        ui.set_width(280.0);
+        ui.heading("Group Modules");
+        ui.add_space(16.0);
+        ui.add(TextEdit::singleline(name).hint_text("Sub-patch name"))
+            .request_focus();
+        ui.add_space(32.0);
+
+        Sides::new().show(
+            ui,
+            |_ui| {},
+            |ui| {
+                let save_clicked = ui
+                    .add_enabled(!trimmed.is_empty(), Button::new("Save"))
+                    .clicked();
+
+                if (save_clicked || ui.input(|i| i.key_pressed(Key::Enter))) && !trimmed.is_empty()
+                {
+                    let saved = editor_state
+                        .ui_bridge
+                        .create_group(&trimmed, &selection)
+                        .and_then(|sub_patch| {
+                            Presets::new()
+                                .and_then(|presets| presets.write_sub_patch(&sub_patch))
+                                .ok_or_else(|| "Can't write the sub-patch.".to_string())
+                        });
+
+                    if let Err(err) = saved {
+                        println!("Failed to group modules: {err}");
+                    }
+
+                    editor_state.grid.clear_group_selection();
+                    ui.close();
+                }
+
+                if ui.button("Discard").clicked() {
+                    ui.close();
+                }
+            },
+        );
+    });
+
+    if modal.should_close() {
+        editor_state.group_name = None;
+    }
+}
+
+fn show_capture_controls(ui: &mut Ui, editor_state: &mut EditorState) {
+    let bridge = &mut editor_state.ui_bridge;
+    let capturing = bridge.is_capturing();
+
+    if ui
+        .selectable_label(capturing, "Record")
+        .on_hover_text("Capture the output to a WAV file")
+        .clicked()
+    {
+        if capturing {
+            editor_state.capture_error = bridge.stop_capture().err();
+        } else if let Some(path) = FileDialog::new()
+            .add_filter("WAV", &["wav"])
+            .set_file_name("capture.wav")
+            .save_file()
+        {
+            editor_state.capture_error = bridge.start_capture(&path).err();
+        }
+    }
+
+    if bridge.is_capturing() {
+        ui.label(RichText::new("Recording").color(Color32::RED));
+    } else if let Some(err) = &editor_state.capture_error {
+        ui.label(RichText::new(err).color(Color32::RED));
+    }
+}
+
+// Locked modules and parameters keep their values
+fn show_randomize_menu(ui: &mut Ui, editor_state: &mut EditorState) {
+    ui.menu_button("Randomize", |ui| {
+        ui.add(Slider::new(&mut editor_state.randomize_amount, 0.0..=1.0).text("Amount"))
+            .on_hover_text("How far the parameters move towards the random values");
+        ui.separator();
+
+        let bridge = &mut editor_state.ui_bridge;
+
+        for module in bridge.get_modules() {
+            let params = RandomParam::for_module(module.module_type);
+
+            if params.is_empty() {
+                continue;
             }
-            if ui.selectable_label(false, "Mixer").clicked() {
-                bridge.add_module(ModuleType::Mixer);
+
+            ui.horizontal(|ui| {
+                let mut locked = bridge.randomize_locked(module.id);
+
+                if ui
+                    .toggle_value(&mut locked, "🔒")
+                    .on_hover_text("Lock the whole module")
+                    .changed()
+                {
+                    bridge.set_randomize_locked(module.id, locked);
+                }
+
+                ui.label(&module.label);
+
+                ui.add_enabled_ui(!locked, |ui| {
+                    for param in params {
+                        let mut param_locked = bridge.param_locked(module.id, *param);
+
+                        if ui
+                            .toggle_value(&mut param_locked, param.label())
+                            .on_hover_text("Lock the parameter")
+                            .changed()
+                        {
+                            bridge.set_param_locked(module.id, *param, param_locked);
+                        }
+                    }
+                });
+            });
+        }
+
+        ui.separator();
+
+        if ui.button("Randomize").clicked() {
+            editor_state.randomize();
+        }
+    });
+}
+
+fn show_compare_controls(ui: &mut Ui, editor_state: &mut EditorState) {
+    let slot = editor_state.engine_factory.compare_slot();
+
+    for (label, target) in [("A", CompareSlot::A), ("B", CompareSlot::B)] {
+        if ui.selectable_label(slot == target, label).clicked() && slot != target {
+            editor_state.switch_compare_slot();
+        }
+    }
+
+    if ui
+        .button("A → B")
+        .on_hover_text("Copy the A patch to the B slot")
+        .clicked()
+    {
+        editor_state.copy_a_to_b();
+    }
+}
+
+// Zoom on top of the host scale factor, so the editor stays readable on high-DPI screens
+fn show_ui_scale_menu(ui: &mut Ui, editor_state: &mut EditorState) {
+    ComboBox::from_id_salt("ui-scale-dropdown")
+        .selected_text(format!("{:.0}%", editor_state.ui_scale * 100.0))
+        .show_ui(ui, |ui| {
+            for scale in UI_SCALES {
+                ui.selectable_value(
+                    &mut editor_state.ui_scale,
+                    scale,
+                    format!("{:.0}%", scale * 100.0),
+                );
             }
-        });
+        })
+        .response
+        .on_hover_text("Editor zoom");
 }
 
 fn show_top_bar(ui: &mut Ui, editor_state: &mut EditorState) {
     Frame::new().inner_margin(vec2(8.0, 4.0)).show(ui, |ui| {
         ui.horizontal(|ui| {
-            let showing_params = editor_state
+            let detail_key = editor_state
                 .grid_module_ui
                 .as_ref()
-                .is_some_and(|panel| panel.module_id().is_none());
+                .map(|panel| panel.detail_key());
+            let showing_params = detail_key == Some(DetailViewKey::Params);
+            let showing_presets = detail_key == Some(DetailViewKey::Presets);
+            let showing_scope = detail_key == Some(DetailViewKey::Scope);
+            let showing_analyzer = detail_key == Some(DetailViewKey::Analyzer);
 
-            if ui.selectable_label(showing_params, "Parameters").clicked() {
+            if ui
+                .selectable_label(showing_params, "Parameters")
+                .on_hover_text("1")
+                .clicked()
+            {
                 if showing_params {
                     editor_state.grid_module_ui = None;
                 } else {
-                    editor_state.grid_module_ui =
-                        Some(Box::new(ParamsUi::new(editor_state.engine_factory.clone())));
+                    editor_state.grid_module_ui = Some(Box::new(ParamsUi::new()));
+                }
+            }
+
+            if ui
+                .selectable_label(showing_presets, "Presets")
+                .on_hover_text("2")
+                .clicked()
+            {
+                if showing_presets {
+                    editor_state.grid_module_ui = None;
+                } else {
+                    editor_state.grid_module_ui = Some(Box::new(PresetBrowserUi::new(
+                        editor_state.engine_factory.clone(),
+                    )));
+                }
+            }
+
+            if ui
+                .selectable_label(showing_scope, "Scope")
+                .on_hover_text("3")
+                .clicked()
+            {
+                if showing_scope {
+                    editor_state.grid_module_ui = None;
+                } else {
+                    editor_state.grid_module_ui = Some(Box::new(ScopeUi::new()));
+                }
+            }
+
+            if ui
+                .selectable_label(showing_analyzer, "Analyzer")
+                .on_hover_text("4")
+                .clicked()
+            {
+                if showing_analyzer {
+                    editor_state.grid_module_ui = None;
+                } else {
+                    editor_state.grid_module_ui = Some(Box::new(AnalyzerUi::new()));
                 }
             }
 
             show_add_module_menu(ui, &mut editor_state.ui_bridge);
+            show_group_controls(ui, editor_state);
+            show_randomize_menu(ui, editor_state);
+
+            if ui
+                .add_enabled(editor_state.undo_history.can_undo(), Button::new("Undo"))
+                .on_hover_text("Ctrl+Z")
+                .clicked()
+            {
+                editor_state.undo();
+            }
+
+            if ui
+                .add_enabled(editor_state.undo_history.can_redo(), Button::new("Redo"))
+                .on_hover_text("Ctrl+Shift+Z")
+                .clicked()
+            {
+                editor_state.redo();
+            }
+
+            show_compare_controls(ui, editor_state);
+            show_capture_controls(ui, editor_state);
+            show_ui_scale_menu(ui, editor_state);
+
+            if ui
+                .selectable_label(editor_state.show_keyboard, "Keyboard")
+                .on_hover_text("Play notes from the editor")
+                .clicked()
+            {
+                editor_state.show_keyboard = !editor_state.show_keyboard;
+                editor_state.keyboard.release(&mut editor_state.ui_bridge);
+            }
         });
     });
 }
 
+fn handle_shortcuts(ui: &mut Ui, editor_state: &mut EditorState) {
+    if ui.input_mut(|i| i.consume_key(Modifiers::COMMAND | Modifiers::SHIFT, Key::Z)) {
+        editor_state.redo();
+    } else if ui.input_mut(|i| i.consume_key(Modifiers::COMMAND, Key::Z)) {
+        editor_state.undo();
+    }
+
+    // Number keys switch between the top bar panels
+    for (key, detail_key) in [
+        (Key::Num1, DetailViewKey::Params),
+        (Key::Num2, DetailViewKey::Presets),
+        (Key::Num3, DetailViewKey::Scope),
+        (Key::Num4, DetailViewKey::Analyzer),
+    ] {
+        if ui.input_mut(|i| i.consume_key(Modifiers::NONE, key)) {
+            editor_state.grid_module_ui = detail_ui(
+                detail_key,
+                &editor_state.engine_factory,
+                &editor_state.ui_bridge,
+            );
+        }
+    }
+
+    let Some(module_id) = editor_state
+        .grid_module_ui
+        .as_ref()
+        .and_then(|panel| panel.module_id())
+    else {
+        return;
+    };
+
+    if ui.input_mut(|i| {
+        i.consume_key(Modifiers::NONE, Key::Delete)
+            || i.consume_key(Modifiers::NONE, Key::Backspace)
+    }) {
+        editor_state.remove_module_id = Some(module_id);
+    } else if ui.input_mut(|i| i.consume_key(Modifiers::COMMAND, Key::D))
+        && let Some(duplicate_id) = editor_state.ui_bridge.duplicate_module(module_id, false)
+    {
+        editor_state.grid_module_ui = module_ui_for_id(&editor_state.ui_bridge, duplicate_id);
+    }
+}
+
+fn show_remove_module_modal(ui: &mut Ui, editor_state: &mut EditorState) {
+    let Some(module_id) = editor_state.remove_module_id else {
+        return;
+    };
+
+    let mut show_modal = true;
+
+    if utils::module_removal_modal(ui, &mut show_modal) {
+        editor_state.ui_bridge.remove_module(module_id);
+    }
+
+    if !show_modal {
+        editor_state.remove_module_id = None;
+    }
+}
+
 fn show_editor(ui: &mut Ui, editor_state: &mut EditorState) {
     if editor_state
         .engine_factory
         .engine_changed(editor_state.ui_bridge.engine())
     {
-        editor_state.ui_bridge = UiBridge::create(
-            editor_state.engine_factory.get_engine(),
-            editor_state.engine_factory.get_ui_config(),
-        )
-        .unwrap();
+        editor_state.reload_bridge();
 
-        editor_state.grid_module_ui = None;
-        editor_state.grid = grid::Grid::new();
+        // Module ids may point to other modules in the new patch
+        if editor_state
+            .grid_module_ui
+            .as_ref()
+            .is_some_and(|panel| panel.module_id().is_some())
+        {
+            editor_state.grid_module_ui = None;
+        }
+    }
+
+    // Text fields keep their own undo and the keys typed into them
+    if utils::shortcuts_enabled(ui.ctx()) {
+        handle_shortcuts(ui, editor_state);
     }
 
     editor_state.ui_bridge.update();
@@ -203,17 +762,41 @@ fn show_editor(ui: &mut Ui, editor_state: &mut EditorState) {
 
     show_top_bar(ui, editor_state);
 
+    // The audio thread only feeds the scope and the analyzer while they're shown
+    let detail_key = editor_state
+        .grid_module_ui
+        .as_ref()
+        .map(|panel| panel.detail_key());
+
+    if detail_key != Some(DetailViewKey::Scope) {
+        editor_state.ui_bridge.set_scope_active(false);
+    }
+
+    if detail_key != Some(DetailViewKey::Analyzer) {
+        editor_state.ui_bridge.set_analyzer_active(false);
+    }
+
     let grid_selected_id = editor_state
         .grid_module_ui
         .as_ref()
         .and_then(|panel| panel.module_id());
 
+    // Below the detail panel, it's added first
+    if editor_state.show_keyboard {
+        Panel::bottom(Id::new("piano-keyboard"))
+            .resizable(false)
+            .frame(Frame::default().inner_margin(8.0))
+            .show_inside(ui, |ui| {
+                editor_state.keyboard.ui(ui, &mut editor_state.ui_bridge);
+            });
+    }
+
     if let Some(panel) = editor_state.grid_module_ui.as_ref() {
-        let detail_key = DetailViewKey::from_view(panel);
+        let detail_key = panel.detail_key();
 
-        Panel::bottom(Id::new(("grid-module-detail", detail_key)))
+        let response = Panel::bottom(Id::new(("grid-module-detail", detail_key)))
             .resizable(true)
-            .default_size(300.0)
+            .default_size(editor_state.detail_height)
             .min_size(80.0)
             .frame(Frame::default().inner_margin(8.0))
             .show_inside(ui, |ui| {
@@ -224,7 +807,10 @@ fn show_editor(ui: &mut Ui, editor_state: &mut EditorState) {
                             module_ui.ui(&mut editor_state.ui_bridge, ui);
                         }
                     });
-            });
+            })
+            .response;
+
+        editor_state.detail_height = response.rect.height();
     }
 
     CentralPanel::no_frame().show_inside(ui, |ui| {
@@ -232,25 +818,49 @@ fn show_editor(ui: &mut Ui, editor_state: &mut EditorState) {
             .grid
             .ui(ui, &mut editor_state.ui_bridge, grid_selected_id);
     });
+
+    show_remove_module_modal(ui, editor_state);
+
+    // An edit is finished when a drag ends, a typed value is committed or a shortcut is released
+    let key_released = utils::shortcuts_enabled(ui.ctx())
+        && ui.input(|i| {
+            i.events
+                .iter()
+                .any(|event| matches!(event, Event::Key { pressed: false, .. }))
+        });
+
+    if key_released || ui.input(|i| i.pointer.any_released() || i.key_pressed(Key::Enter)) {
+        editor_state.undo_history.record(&editor_state.ui_bridge);
+    }
+
+    editor_state.save_view_state();
 }
 
 pub fn create_editor(
     egui_state: Arc<EguiState>,
     factory: Arc<EngineFactory>,
+    view_state: Arc<Mutex<ViewState>>,
 ) -> Option<Box<dyn Editor>> {
     create_egui_editor(
         Arc::clone(&egui_state),
-        EditorState::new(factory),
+        EditorState::new(factory, view_state),
         Default::default(),
         |egui_ctx, _queue, _editor_state| {
             egui_ctx.global_style_mut(|style| style.debug.warn_if_rect_changes_id = false);
+            // The zoom is only changed from the top bar, where it's saved
+            egui_ctx.options_mut(|options| options.zoom_with_keyboard = false);
         },
-        move |egui_ctx, _setter, _queue, editor_state| {
+        move |egui_ctx, setter, _queue, editor_state| {
+            // Multiplies the host scale factor into the pixels per point
+            egui_ctx.set_zoom_factor(editor_state.ui_scale);
+
             ResizableWindow::new("res-wind")
                 .min_size(Vec2::new(640.0, 480.0))
                 .show(egui_ctx, egui_state.as_ref(), |ui| {
                     show_editor(ui, editor_state);
                 });
+
+            editor_state.apply_host_edits(setter);
         },
     )
 }
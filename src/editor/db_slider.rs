@@ -1,6 +1,9 @@
 use egui::{Color32, PointerButton, Rect, Response, Sense, Ui, Widget, pos2, vec2};
 
-use crate::synth_engine::{Sample, StereoSample};
+use crate::{
+    editor::utils::slider_key_nudge,
+    synth_engine::{Sample, StereoSample},
+};
 
 const BG_COLOR: Color32 = Color32::from_rgb(0, 0, 0);
 const LEVEL_COLOR: Color32 = Color32::from_rgb(0x0b, 0x42, 0x67);
@@ -161,6 +164,10 @@ impl<'a> DbSlider<'a> {
             self.update_normalized_value(&mut response, 0.0.into());
         }
 
+        if let Some(delta) = slider_key_nudge(ui, &response) {
+            self.update_normalized_value(&mut response, normalized_value + delta);
+        }
+
         if ui.is_rect_visible(response.rect) {
             let lr_rect = response.rect.split_top_bottom_at_fraction(0.5);
 
@@ -0,0 +1,229 @@
+use egui::{Color32, CursorIcon, Id, Pos2, Rect, Sense, Stroke, Ui, pos2, vec2};
+
+use crate::synth_engine::{
+    CurveFunction, Exponential, Sample, StereoSample, envelope::EnvelopeConfig,
+};
+
+const PLOT_HEIGHT: f32 = 120.0;
+const MAX_TIME: Sample = 8.0;
+// Short envelopes are drawn within this span instead of stretching over the whole width
+const MIN_VIEW_TIME: Sample = 0.5;
+// Part of the width the sustain takes, it has no length of its own
+const SUSTAIN_SHARE: Sample = 0.2;
+const SEGMENT_POINTS: usize = 32;
+const HANDLE_RADIUS: f32 = 5.0;
+const BG_COLOR: Color32 = Color32::from_rgb(0x1d, 0x21, 0x25);
+const CURVE_COLOR: Color32 = Color32::from_rgb(0xaa, 0x88, 0xff);
+const HANDLE_COLOR: Color32 = Color32::from_rgb(0xe8, 0xe8, 0xe8);
+const ACTIVE_HANDLE_COLOR: Color32 = Color32::from_rgb(0xff, 0xaa, 0x66);
+const LINE_WIDTH: f32 = 1.5;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum EnvelopeHandle {
+    Attack,
+    Hold,
+    Decay, // Also moves the sustain level
+    Release,
+}
+
+// With the index of the segment each handle ends
+const HANDLES: [(EnvelopeHandle, usize); 4] = [
+    (EnvelopeHandle::Attack, 1),
+    (EnvelopeHandle::Hold, 2),
+    (EnvelopeHandle::Decay, 3),
+    (EnvelopeHandle::Release, 5),
+];
+
+struct Segment {
+    time: Sample,
+    curvature: Sample,
+    from: Sample,
+    to: Sample,
+}
+
+struct Layout {
+    rect: Rect,
+    pixels_per_second: f32,
+    sustain_time: Sample,
+}
+
+impl Layout {
+    fn new(rect: Rect, config: &EnvelopeConfig) -> Self {
+        let total_time = [
+            config.delay,
+            config.attack,
+            config.hold,
+            config.decay,
+            config.release,
+        ]
+        .iter()
+        .map(|time| time[0])
+        .sum::<Sample>()
+        .max(MIN_VIEW_TIME);
+        let view_time = total_time / (1.0 - SUSTAIN_SHARE);
+
+        Self {
+            rect,
+            pixels_per_second: rect.width() / view_time,
+            sustain_time: view_time * SUSTAIN_SHARE,
+        }
+    }
+
+    fn level_to_y(&self, level: Sample) -> f32 {
+        self.rect.bottom() - level * self.rect.height()
+    }
+
+    // Delay, attack, hold, decay, sustain and release of the left channel, as the engine runs them
+    fn segments(&self, config: &EnvelopeConfig) -> [Segment; 6] {
+        let sustain = config.sustain[0].clamp(0.0, 1.0);
+        let segment = |time: Sample, curvature: Sample, from: Sample, to: Sample| Segment {
+            time,
+            curvature,
+            from,
+            to,
+        };
+
+        [
+            segment(config.delay[0], 0.0, 0.0, 0.0),
+            segment(config.attack[0], config.attack_curvature, 0.0, 1.0),
+            segment(config.hold[0], 0.0, 1.0, 1.0),
+            segment(config.decay[0], config.decay_curvature, 1.0, sustain),
+            segment(self.sustain_time, 0.0, sustain, sustain),
+            segment(config.release[0], config.release_curvature, sustain, 0.0),
+        ]
+    }
+
+    fn segment_ends(&self, segments: &[Segment; 6]) -> [Pos2; 6] {
+        let mut x = self.rect.left();
+
+        segments.each_ref().map(|segment| {
+            x += segment.time * self.pixels_per_second;
+            pos2(x, self.level_to_y(segment.to))
+        })
+    }
+}
+
+fn drag_time(time: &mut StereoSample, delta: Sample) {
+    *time = (*time + delta).clamp(0.0, MAX_TIME);
+}
+
+fn paint(ui: &Ui, rect: Rect, layout: &Layout, config: &EnvelopeConfig, dragged: Option<usize>) {
+    let painter = ui.painter_at(rect);
+    let segments = layout.segments(config);
+    let ends = layout.segment_ends(&segments);
+    let mut points = Vec::with_capacity(segments.len() * (SEGMENT_POINTS + 1));
+    let mut start_x = layout.rect.left();
+
+    painter.rect_filled(rect, 0.0, BG_COLOR);
+
+    for (segment, end) in segments.iter().zip(ends) {
+        let curve = Exponential::new(segment.curvature);
+
+        for point in 0..=SEGMENT_POINTS {
+            let t = point as Sample / SEGMENT_POINTS as Sample;
+            let level = (segment.to - segment.from).mul_add(curve.calc(t), segment.from);
+
+            points.push(pos2(
+                start_x + t * (end.x - start_x),
+                layout.level_to_y(level),
+            ));
+        }
+
+        start_x = end.x;
+    }
+
+    painter.line(points, Stroke::new(LINE_WIDTH, CURVE_COLOR));
+
+    for (_, segment_idx) in HANDLES {
+        let color = if dragged == Some(segment_idx) {
+            ACTIVE_HANDLE_COLOR
+        } else {
+            HANDLE_COLOR
+        };
+
+        painter.circle_filled(ends[segment_idx], HANDLE_RADIUS, color);
+    }
+}
+
+// Plots the envelope shape with handles at the ends of the attack, hold, decay and release.
+// Dragging a handle shifts the time by the same amount on both channels, returns the dragged one.
+pub fn envelope_plot(ui: &mut Ui, id: Id, config: &mut EnvelopeConfig) -> Option<EnvelopeHandle> {
+    let (rect, _) = ui.allocate_exact_size(vec2(ui.available_width(), PLOT_HEIGHT), Sense::hover());
+    let layout = Layout::new(rect.shrink(HANDLE_RADIUS), config);
+    let ends = layout.segment_ends(&layout.segments(config));
+    let mut dragged = None;
+
+    for (handle, segment_idx) in HANDLES {
+        let response = ui
+            .interact(
+                Rect::from_center_size(ends[segment_idx], vec2(3.0, 3.0) * HANDLE_RADIUS),
+                id.with(segment_idx),
+                Sense::drag(),
+            )
+            .on_hover_cursor(CursorIcon::Grab);
+
+        if !response.dragged() {
+            continue;
+        }
+
+        let delta = response.drag_delta();
+        let time_delta = delta.x / layout.pixels_per_second;
+
+        match handle {
+            EnvelopeHandle::Attack => drag_time(&mut config.attack, time_delta),
+            EnvelopeHandle::Hold => drag_time(&mut config.hold, time_delta),
+            EnvelopeHandle::Decay => {
+                drag_time(&mut config.decay, time_delta);
+                config.sustain = (config.sustain - delta.y / layout.rect.height()).clamp(0.0, 1.0);
+            }
+            EnvelopeHandle::Release => drag_time(&mut config.release, time_delta),
+        }
+
+        dragged = Some((handle, segment_idx));
+    }
+
+    // Drawn from the updated config on the same scale, the dragged handle stays under the pointer
+    if ui.is_rect_visible(rect) {
+        paint(ui, rect, &layout, config, dragged.map(|(_, idx)| idx));
+    }
+
+    dragged.map(|(handle, _)| handle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn segments_fill_the_plot() {
+        let rect = Rect::from_min_size(Pos2::ZERO, vec2(400.0, 100.0));
+        let config = EnvelopeConfig {
+            attack: 0.5.into(),
+            decay: 1.0.into(),
+            sustain: 0.25.into(),
+            release: 2.5.into(),
+            ..EnvelopeConfig::default()
+        };
+        let layout = Layout::new(rect, &config);
+        let ends = layout.segment_ends(&layout.segments(&config));
+
+        // 4 s of segments and 1 s of sustain
+        assert!((ends[1].x - 40.0).abs() < 1e-3);
+        assert!((ends[3] - pos2(120.0, 75.0)).length() < 1e-3);
+        assert!((ends[4].x - 200.0).abs() < 1e-3);
+        assert!((ends[5] - pos2(400.0, 100.0)).length() < 1e-3);
+
+        // Short envelopes keep the scale of the shortest view
+        let short = EnvelopeConfig {
+            attack: 0.0.into(),
+            decay: 0.1.into(),
+            release: 0.1.into(),
+            ..EnvelopeConfig::default()
+        };
+        let layout = Layout::new(rect, &short);
+        let ends = layout.segment_ends(&layout.segments(&short));
+
+        assert!((ends[3].x - 64.0).abs() < 1e-3);
+        assert!(ends[5].x < 400.0);
+    }
+}
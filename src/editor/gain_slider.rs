@@ -1,7 +1,10 @@
 use egui::{Color32, PointerButton, Rect, Response, Sense, Ui, Widget, pos2, vec2};
 use nih_plug::util::MINUS_INFINITY_DB;
 
-use crate::synth_engine::{Sample, StereoSample};
+use crate::{
+    editor::{utils::slider_key_nudge, value_entry::value_entry_menu},
+    synth_engine::{Sample, StereoSample},
+};
 
 const BG_COLOR: Color32 = Color32::from_rgb(0, 0, 0);
 const ATTENUATED_COLOR: Color32 = Color32::from_rgb(0x0b, 0x42, 0x67);
@@ -93,7 +96,7 @@ impl<'a> GainSlider<'a> {
         }
     }
 
-    fn normalized_to_gain(&self, norm: f32) -> f32 {
+    pub fn normalized_to_gain(&self, norm: f32) -> f32 {
         let dbs = if norm > self.mid_point {
             let normalized = (norm - self.mid_point) / (1.0 - self.mid_point);
 
@@ -197,6 +200,36 @@ impl<'a> GainSlider<'a> {
         }
     }
 
+    fn handle_key_nudge(&mut self, ui: &Ui, response: &mut Response) {
+        if let Some(normalized_delta) = slider_key_nudge(ui, response) {
+            self.value
+                .set_left(self.updated_gain(normalized_delta, self.value.left()));
+            self.value
+                .set_right(self.updated_gain(normalized_delta, self.value.right()));
+            response.mark_changed();
+        }
+    }
+
+    fn handle_value_entry(&mut self, response: &mut Response) {
+        let dbs = nih_plug::util::gain_to_db(self.value.left());
+        let current = if dbs <= MINUS_INFINITY_DB {
+            "-inf".to_string()
+        } else {
+            format!("{dbs:.1}")
+        };
+
+        if let Some(dbs) = value_entry_menu(response, &current, "dB") {
+            let gain = if dbs <= MINUS_INFINITY_DB {
+                0.0
+            } else {
+                nih_plug::util::db_to_gain(dbs.min(self.max_dbs))
+            };
+
+            *self.value = StereoSample::splat(gain);
+            response.mark_changed();
+        }
+    }
+
     fn handle_primary_click(&mut self, response: &mut Response) {
         *self.value = StereoSample::splat(1.0);
         response.mark_changed();
@@ -250,6 +283,9 @@ impl<'a> GainSlider<'a> {
             }
         }
 
+        self.handle_key_nudge(ui, &mut response);
+        self.handle_value_entry(&mut response);
+
         if ui.is_rect_visible(response.rect) {
             let lr_rect = response.rect.split_left_right_at_fraction(0.5);
 
@@ -303,6 +339,9 @@ impl<'a> GainSlider<'a> {
             self.handle_secondary_click(&mut response);
         }
 
+        self.handle_key_nudge(ui, &mut response);
+        self.handle_value_entry(&mut response);
+
         let label = if self.value.left() != self.value.right() {
             format!(
                 "L: {}, R: {}",
@@ -1,16 +1,17 @@
 use egui::{
-    Color32, Painter, Pos2, Rect, ScrollArea, Sense, Shape, Ui, Vec2,
+    Align2, Color32, FontId, Id, Painter, Pos2, Rect, ScrollArea, Sense, Shape, Stroke, StrokeKind,
+    Ui, Vec2,
     epaint::{CubicBezierShape, PathStroke},
     pos2,
     scroll_area::{ScrollBarVisibility, ScrollSource},
     vec2,
 };
-use rustc_hash::FxHashMap;
+use rustc_hash::{FxHashMap, FxHashSet};
 
 use crate::{
     editor::grid::grid_widget::GridWidget,
     synth_engine::{
-        ModuleId,
+        ModuleId, OUTPUT_MODULE_ID,
         ui_bridge::{GridVec, UiBridge, routing_state::ModuleIo},
     },
 };
@@ -29,6 +30,8 @@ const WIRE_MOD_T: f32 = 1.0;
 /// keeps it clear of the widgets it attaches to.
 const WIRE_CTRL_MIN: f32 = 8.0;
 const WIRE_END_DOT: f32 = 8.0;
+const C_GROUP: Color32 = Color32::from_rgb(90, 90, 110);
+const GROUP_MARGIN: f32 = 1.0;
 
 /// Compensates for egui-baseview negating horizontal wheel delta on macOS.
 #[cfg(target_os = "macos")]
@@ -83,12 +86,14 @@ struct WidgetsState {
 pub enum GridEvent {
     Moved(ModuleId),
     Selected(ModuleId),
+    GroupToggled(ModuleId), // Shift+click adds or removes a module from the modules to group
 }
 
 struct WidgetCtx<'a> {
     bridge: &'a mut UiBridge,
     state: &'a mut WidgetsState,
     selected_module_id: Option<ModuleId>,
+    group_selection: &'a FxHashSet<ModuleId>,
     events: &'a mut Vec<GridEvent>,
 }
 
@@ -97,6 +102,9 @@ pub struct Grid {
     widgets_state: WidgetsState,
     content_size: egui::Vec2,
     events: Vec<GridEvent>,
+    group_selection: FxHashSet<ModuleId>,
+    scroll_offset: Vec2,
+    restored_scroll_offset: Option<Vec2>,
 }
 
 impl Grid {
@@ -106,9 +114,32 @@ impl Grid {
             widgets_state: WidgetsState::default(),
             content_size: egui::Vec2::ZERO,
             events: Vec::new(),
+            group_selection: FxHashSet::default(),
+            scroll_offset: Vec2::ZERO,
+            restored_scroll_offset: None,
         }
     }
 
+    pub fn scroll_offset(&self) -> Vec2 {
+        self.scroll_offset
+    }
+
+    // Applied on the next frame, the scroll area keeps its own offset after that
+    pub fn restore_scroll_offset(&mut self, offset: Vec2) {
+        self.restored_scroll_offset = Some(offset);
+    }
+
+    pub fn group_selection(&self) -> Vec<ModuleId> {
+        let mut module_ids: Vec<_> = self.group_selection.iter().copied().collect();
+
+        module_ids.sort_unstable();
+        module_ids
+    }
+
+    pub fn clear_group_selection(&mut self) {
+        self.group_selection.clear();
+    }
+
     pub fn update_widgets(&mut self, modules_io: FxHashMap<ModuleId, ModuleIo>) {
         let mut widgets_by_id: FxHashMap<ModuleId, GridWidget> =
             self.widgets.drain(..).map(|w| (w.module_id(), w)).collect();
@@ -123,6 +154,9 @@ impl Grid {
                 None => GridWidget::new(module_io),
             })
             .collect();
+
+        self.group_selection
+            .retain(|id| self.widgets.iter().any(|widget| widget.module_id() == *id));
     }
 
     pub fn events(&self) -> &Vec<GridEvent> {
@@ -131,8 +165,14 @@ impl Grid {
 
     fn process_events(&mut self, bridge: &mut UiBridge) {
         for event in self.events.iter() {
-            if let GridEvent::Moved(module_id) = event {
-                self.resolve_overlaps(*module_id, bridge);
+            match event {
+                GridEvent::Moved(module_id) => self.resolve_overlaps(*module_id, bridge),
+                GridEvent::GroupToggled(module_id) if *module_id != OUTPUT_MODULE_ID => {
+                    if !self.group_selection.remove(module_id) {
+                        self.group_selection.insert(*module_id);
+                    }
+                }
+                _ => (),
             }
         }
         self.events.clear();
@@ -210,7 +250,7 @@ impl Grid {
         // Never smaller than the viewport so the grid fills the panel.
         let grid_area = (self.content_size + 0.5 * viewport_size).max(viewport_size);
 
-        ScrollArea::both()
+        let mut scroll_area = ScrollArea::both()
             .id_salt("module-grid-area")
             .scroll_source(ScrollSource {
                 drag: true,
@@ -219,43 +259,103 @@ impl Grid {
             })
             .wheel_scroll_multiplier(TRACKPAD_SCROLL_MULTIPLIER)
             .scroll_bar_visibility(ScrollBarVisibility::AlwaysHidden)
-            .auto_shrink([true, true])
-            .show(ui, |ui| {
-                self.place_new_modules(ui, bridge);
+            .auto_shrink([true, true]);
 
-                let (response, painter) = ui.allocate_painter(grid_area, Sense::hover());
+        if let Some(offset) = self.restored_scroll_offset.take() {
+            scroll_area = scroll_area.scroll_offset(offset);
+        }
 
-                Self::paint_grid(&painter, painter.clip_rect(), response.rect.min);
+        let output = scroll_area.show(ui, |ui| {
+            self.place_new_modules(ui, bridge);
 
-                // Reserve a paint slot for the wires.
-                let wires = painter.add(Shape::Noop);
+            let (response, painter) = ui.allocate_painter(grid_area, Sense::hover());
 
-                let mut ctx = WidgetCtx {
-                    bridge,
-                    state: &mut self.widgets_state,
-                    events: &mut self.events,
-                    selected_module_id,
-                };
+            Self::paint_grid(&painter, painter.clip_rect(), response.rect.min);
+            self.groups_ui(ui, &painter, bridge, response.rect.min);
 
-                for widget in &mut self.widgets {
-                    widget.ui(ui, &mut ctx);
-                }
+            // Reserve a paint slot for the wires.
+            let wires = painter.add(Shape::Noop);
 
-                painter.set(wires, Shape::Vec(self.build_wire_shapes()));
+            let mut ctx = WidgetCtx {
+                bridge,
+                state: &mut self.widgets_state,
+                events: &mut self.events,
+                selected_module_id,
+                group_selection: &self.group_selection,
+            };
 
-                if let Some(drag) = self.widgets_state.wire_drag.as_mut()
-                    && let Some(dropped_at) = drag.dropped_at
-                    && dropped_at < ui.ctx().cumulative_frame_nr()
-                {
-                    self.widgets_state.wire_drag = None;
-                }
+            for widget in &mut self.widgets {
+                widget.ui(ui, &mut ctx);
+            }
 
-                if let Some(drag) = &self.widgets_state.wire_drag
-                    && let Some(pointer) = ui.ctx().pointer_hover_pos()
-                {
-                    painter.add(self.build_drag_wire_shape(drag, pointer));
+            painter.set(wires, Shape::Vec(self.build_wire_shapes()));
+
+            if let Some(drag) = self.widgets_state.wire_drag.as_mut()
+                && let Some(dropped_at) = drag.dropped_at
+                && dropped_at < ui.ctx().cumulative_frame_nr()
+            {
+                self.widgets_state.wire_drag = None;
+            }
+
+            if let Some(drag) = &self.widgets_state.wire_drag
+                && let Some(pointer) = ui.ctx().pointer_hover_pos()
+            {
+                painter.add(self.build_drag_wire_shape(drag, pointer));
+            }
+        });
+
+        self.scroll_offset = output.state.offset;
+    }
+
+    // Outlines every group around its placed modules, right click on the name ungroups them
+    fn groups_ui(&self, ui: &Ui, painter: &Painter, bridge: &mut UiBridge, origin: Pos2) {
+        for (group_idx, group) in bridge.groups().iter().enumerate() {
+            let Some(rect) = self
+                .widgets
+                .iter()
+                .filter(|widget| group.modules.contains(&widget.module_id()))
+                .filter_map(|widget| {
+                    let pos = bridge.get_module_position(widget.module_id());
+
+                    (pos.x >= 0).then(|| {
+                        Rect::from_min_size(
+                            origin + Vec2::from(pos) + widget.drag_offset(),
+                            widget.grid_size().into(),
+                        )
+                    })
+                })
+                .reduce(|rect, widget_rect| rect.union(widget_rect))
+            else {
+                continue;
+            };
+
+            painter.rect_stroke(
+                rect.expand(GROUP_MARGIN),
+                4.0,
+                Stroke::new(1.0, C_GROUP),
+                StrokeKind::Outside,
+            );
+
+            let label_rect = painter.text(
+                rect.left_top() + vec2(4.0, -2.0),
+                Align2::LEFT_BOTTOM,
+                &group.name,
+                FontId::proportional(12.0),
+                C_GROUP,
+            );
+
+            ui.interact(
+                label_rect,
+                Id::new(("module-group", group_idx)),
+                Sense::click(),
+            )
+            .on_hover_text("Right click to ungroup")
+            .context_menu(|ui| {
+                if ui.button("Ungroup").clicked() {
+                    bridge.ungroup(group_idx);
                 }
             });
+        }
     }
 
     fn calc_content_size(&self, bridge: &UiBridge) -> Vec2 {
@@ -1,6 +1,6 @@
 use egui::{
-    Align, Color32, Id, Label, LayerId, Layout, Order, PointerButton, Pos2, Rect, Response, Sense,
-    Stroke, Ui, UiBuilder, Vec2,
+    Align, Color32, CornerRadius, Id, Label, LayerId, Layout, Order, PointerButton, Pos2, Rect,
+    Response, Sense, Stroke, StrokeKind, Ui, UiBuilder, Vec2,
     emath::{self, GuiRounding},
     lerp, vec2,
 };
@@ -20,7 +20,9 @@ use crate::{
 
 const C_MOD_BG: Color32 = Color32::from_rgb(28, 30, 42);
 const C_MOD_BG_SELECTED: Color32 = Color32::from_rgb(40, 42, 54);
+const C_GROUP_SELECTION: Color32 = Color32::from_rgb(110, 140, 220);
 const CORNER_RADIUS: f32 = 4.0;
+const COLOR_TAG_H: f32 = 3.0;
 const BLOCK_MARGIN: f32 = 3.0;
 
 const IO_STRIPE_W: f32 = 16.0;
@@ -234,6 +236,30 @@ impl GridWidget {
             };
             ui.painter().rect_filled(ui.max_rect(), CORNER_RADIUS, bg);
 
+            if let Some(color) = ctx.bridge.module_color(self.io.id).color() {
+                let rect = ui.max_rect();
+
+                ui.painter().rect_filled(
+                    Rect::from_min_size(rect.min, vec2(rect.width(), COLOR_TAG_H)),
+                    CornerRadius {
+                        nw: CORNER_RADIUS as u8,
+                        ne: CORNER_RADIUS as u8,
+                        sw: 0,
+                        se: 0,
+                    },
+                    color,
+                );
+            }
+
+            if ctx.group_selection.contains(&self.io.id) {
+                ui.painter().rect_stroke(
+                    ui.max_rect(),
+                    CORNER_RADIUS,
+                    Stroke::new(1.0, C_GROUP_SELECTION),
+                    StrokeKind::Inside,
+                );
+            }
+
             ui.allocate_ui_with_layout(
                 vec2(IO_STRIPE_W, full_height),
                 Layout::top_down(Align::Center),
@@ -319,9 +345,14 @@ impl GridWidget {
         let (rect, response) = ui.allocate_exact_size(vec2(width, height), Sense::click_and_drag());
         let color = input.meta.input_type.color();
 
+        let input_id = InputId::new(input.meta.input_type, self.io.id);
+
         if response.double_clicked_by(PointerButton::Primary) {
-            ctx.bridge
-                .remove_input_links(InputId::new(input.meta.input_type, self.io.id));
+            ctx.bridge.remove_input_links(input_id);
+        }
+
+        if !input.sources.is_empty() {
+            response.context_menu(|ui| Self::input_links_menu(ui, ctx, input_id));
         }
 
         let t = ui.ctx().animate_bool_with_time_and_easing(
@@ -352,6 +383,28 @@ impl GridWidget {
             .round_to_pixels(ui.ctx().pixels_per_point())
     }
 
+    fn input_links_menu(ui: &mut Ui, ctx: &mut WidgetCtx, input_id: InputId) {
+        for source in ctx.bridge.get_connected_input_sources(input_id) {
+            if ui.button(format!("Remove {}", source.label)).clicked() {
+                ctx.bridge.remove_link(source.src, input_id);
+            }
+
+            if let Some(modulation) = source.modulation
+                && ui
+                    .button(format!("Remove {} modulation", modulation.label))
+                    .clicked()
+            {
+                ctx.bridge.remove_link_modulation(source.src, &input_id);
+            }
+        }
+
+        ui.separator();
+
+        if ui.button("Remove all").clicked() {
+            ctx.bridge.remove_input_links(input_id);
+        }
+    }
+
     fn draw_output(&self, ui: &mut Ui, ctx: &mut WidgetCtx, height: f32) -> (Pos2, Pos2, Response) {
         let width = ui.available_width();
         let (rect, response) = ui.allocate_exact_size(vec2(width, height), Sense::click_and_drag());
@@ -500,7 +553,11 @@ impl GridWidget {
         }
 
         if response.clicked_by(PointerButton::Primary) {
-            ctx.events.push(GridEvent::Selected(self.io.id));
+            if ui.input(|i| i.modifiers.shift) {
+                ctx.events.push(GridEvent::GroupToggled(self.io.id));
+            } else {
+                ctx.events.push(GridEvent::Selected(self.io.id));
+            }
         }
 
         if self.content.show_label() {
@@ -1,13 +1,13 @@
-use std::collections::HashSet;
+use std::{collections::HashSet, ops::RangeInclusive};
 
-use egui::{ComboBox, Frame, Grid, Margin, Response, Ui, Widget};
+use egui::{ComboBox, DragValue, Frame, Grid, Margin, Response, Ui, Widget};
 
 use crate::{
     editor::{db_slider::DbSlider, stereo_slider::StereoSlider},
     synth_engine::{
-        Input, ModuleId, InputId, Sample, StereoSample,
+        Input, ModuleId, InputId, LinkCurve, LinkMode, LinkPolarity, Sample, StereoSample,
         ui_bridge::{
-            UiBridge,
+            MidiLearnTarget, UiBridge,
             routing_state::{AvailableInputSource, ConnectedInputSource},
         },
     },
@@ -22,6 +22,7 @@ pub struct ModulationInput<'a> {
     input: InputId,
     default: Option<Sample>,
     modulation_default: Option<Sample>,
+    range_max: Option<Sample>,
     before: Option<Box<BeforeCallback>>,
 }
 
@@ -38,6 +39,7 @@ impl<'a> ModulationInput<'a> {
             input: InputId::new(input, module_id),
             default: None,
             modulation_default: None,
+            range_max: None,
             before: None,
         }
     }
@@ -52,11 +54,28 @@ impl<'a> ModulationInput<'a> {
         self
     }
 
+    // Overrides the upper end of both the value and the modulation amount sliders
+    pub fn range_max(mut self, max: Sample) -> Self {
+        self.range_max = Some(max);
+        self
+    }
+
     pub fn before(mut self, func: impl FnMut(&mut Ui, &mut UiBridge) + 'static) -> Self {
         self.before = Some(Box::new(func));
         self
     }
 
+    fn limit_range(slider: StereoSlider<'_>, range_max: Option<Sample>) -> StereoSlider<'_> {
+        match range_max {
+            Some(max) => {
+                let min = *slider.value_range().start();
+
+                slider.range(min..=max.max(min))
+            }
+            None => slider,
+        }
+    }
+
     fn setup_value_slider<'b>(
         slider: StereoSlider<'b>,
         input_type: Input,
@@ -87,6 +106,18 @@ impl<'a> ModulationInput<'a> {
                 .display_scale(1200.0)
                 .default_value(st_to_octave(0.2))
                 .units(" cents"),
+            Input::Drift => slider
+                .range(0.0..=st_to_octave(1.0))
+                .display_scale(1200.0)
+                .default_value(0.0)
+                .precision(1)
+                .units(" cents"),
+            Input::DriftRate => slider
+                .range(0.01..=20.0)
+                .default_value(0.5)
+                .skew(2.0)
+                .precision(2)
+                .units(" Hz"),
             Input::DetunePower => slider.range(0.0..=5.0).default_value(0.0).allow_inverse(),
             Input::PitchShift => slider
                 .range(0.0..=st_to_octave(60.0))
@@ -130,13 +161,121 @@ impl<'a> ModulationInput<'a> {
                 .display_scale(100.0)
                 .precision(2)
                 .units("%"),
-            Input::Delay | Input::Attack | Input::Hold | Input::Decay | Input::Release => slider
+            Input::Delay
+            | Input::Attack
+            | Input::Hold
+            | Input::Decay
+            | Input::Release
+            | Input::FadeIn => slider
                 .range(0.0..=8.0)
                 .display_scale(1000.0)
                 .default_value(0.0)
                 .skew(2.0)
                 .precision(1)
                 .units(" ms"),
+            Input::Size | Input::Damping => slider
+                .default_value(0.5)
+                .display_scale(100.0)
+                .precision(0)
+                .units("%"),
+            Input::PreDelay => slider
+                .range(0.0..=0.2)
+                .display_scale(1000.0)
+                .default_value(0.02)
+                .precision(1)
+                .units(" ms"),
+            Input::DryWet => slider
+                .default_value(0.25)
+                .display_scale(100.0)
+                .precision(0)
+                .units("%"),
+            Input::Mix => slider
+                .default_value(1.0)
+                .display_scale(100.0)
+                .precision(0)
+                .units("%"),
+            Input::Tilt => slider
+                .range(-24.0..=24.0)
+                .default_value(0.0)
+                .precision(1)
+                .units(" dB/oct"),
+            Input::Pivot => slider
+                .range(0.0..=10.0)
+                .display_scale(12.0)
+                .default_value(0.0)
+                .precision(1)
+                .units(" st"),
+            Input::Stretch => slider
+                .range(-0.5..=0.5)
+                .default_value(0.0)
+                .precision(3),
+            Input::HarmonicShift => slider
+                .range(-128.0..=128.0)
+                .default_value(0.0)
+                .precision(2),
+            Input::Threshold => slider
+                .range(-60.0..=0.0)
+                .default_value(-20.0)
+                .precision(1)
+                .units(" dB"),
+            Input::Ratio => slider
+                .range(1.0..=20.0)
+                .default_value(2.0)
+                .precision(1)
+                .units(":1"),
+            Input::Makeup => slider
+                .range(0.0..=24.0)
+                .default_value(0.0)
+                .precision(1)
+                .units(" dB"),
+            Input::DecaySpread => slider
+                .range(0.0..=2.0)
+                .default_value(0.5)
+                .precision(2),
+            Input::Position => slider
+                .default_value(0.0)
+                .display_scale(100.0)
+                .precision(1)
+                .units("%"),
+            Input::FmIndex => slider.range(0.0..=10.0).default_value(0.0).precision(2),
+            Input::Feedback => slider
+                .default_value(0.0)
+                .display_scale(100.0)
+                .precision(1)
+                .units("%"),
+            Input::Pan => slider
+                .range(-1.0..=1.0)
+                .default_value(0.0)
+                .display_scale(100.0)
+                .precision(0)
+                .units("%"),
+            Input::Width => slider
+                .range(0.0..=2.0)
+                .default_value(1.0)
+                .display_scale(100.0)
+                .precision(0)
+                .units("%"),
+            Input::ValueA | Input::ValueB => {
+                slider.range(-1.0..=1.0).default_value(0.0).precision(2)
+            }
+            Input::Partials => slider.range(1.0..=1023.0).default_value(64.0).precision(1),
+            Input::Rolloff => slider
+                .range(0.0..=24.0)
+                .default_value(6.0)
+                .precision(1)
+                .units(" dB/oct"),
+            Input::CombSpacing => slider.range(1.0..=16.0).default_value(1.0).precision(2),
+            Input::EvenOdd => slider.range(-1.0..=1.0).default_value(0.0).precision(2),
+            Input::SplitHarmonic => slider
+                .range(0.0..=1023.0)
+                .default_value(16.0)
+                .skew(2.0)
+                .precision(1),
+            Input::CrossoverWidth => slider
+                .range(0.0..=128.0)
+                .default_value(4.0)
+                .skew(1.8)
+                .precision(1),
             Input::Spectrum | Input::SpectrumTo | Input::SpectrumMix(_) => slider
                 .range(0.0..=1.0)
                 .default_value(1.0)
@@ -144,7 +283,11 @@ impl<'a> ModulationInput<'a> {
                 .precision(2)
                 .allow_inverse()
                 .units("%"),
-            Input::Audio | Input::AudioMix(_) | Input::Level | Input::LevelMix(_) => slider,
+            Input::Audio
+            | Input::AudioMix(_)
+            | Input::PhaseMod
+            | Input::Level
+            | Input::LevelMix(_) => slider,
         };
 
         if let Some(default) = default {
@@ -199,6 +342,20 @@ impl<'a> ModulationInput<'a> {
                 .default_value(st_to_octave(0.2))
                 .allow_inverse()
                 .units(" cents"),
+            Input::Drift => slider
+                .range(0.0..=st_to_octave(1.0))
+                .display_scale(1200.0)
+                .default_value(st_to_octave(0.1))
+                .precision(1)
+                .allow_inverse()
+                .units(" cents"),
+            Input::DriftRate => slider
+                .range(0.0..=20.0)
+                .default_value(1.0)
+                .skew(2.0)
+                .precision(2)
+                .allow_inverse()
+                .units(" Hz"),
             Input::DetunePower => slider.range(0.0..=5.0).default_value(0.0).allow_inverse(),
             Input::PitchShift => slider
                 .range(0.0..=8.0)
@@ -243,7 +400,12 @@ impl<'a> ModulationInput<'a> {
                 .display_scale(100.0)
                 .precision(2)
                 .units("%"),
-            Input::Delay | Input::Attack | Input::Hold | Input::Decay | Input::Release => slider
+            Input::Delay
+            | Input::Attack
+            | Input::Hold
+            | Input::Decay
+            | Input::Release
+            | Input::FadeIn => slider
                 .range(0.0..=8.0)
                 .display_scale(1000.0)
                 .default_value(0.0)
@@ -251,6 +413,133 @@ impl<'a> ModulationInput<'a> {
                 .precision(1)
                 .allow_inverse()
                 .units(" ms"),
+            Input::Size | Input::Damping | Input::DryWet | Input::Mix => slider
+                .default_value(0.0)
+                .display_scale(100.0)
+                .precision(0)
+                .allow_inverse()
+                .units("%"),
+            Input::PreDelay => slider
+                .range(0.0..=0.2)
+                .display_scale(1000.0)
+                .default_value(0.0)
+                .precision(1)
+                .allow_inverse()
+                .units(" ms"),
+            Input::Tilt => slider
+                .range(0.0..=24.0)
+                .default_value(0.0)
+                .precision(1)
+                .allow_inverse()
+                .units(" dB/oct"),
+            Input::Pivot => slider
+                .range(0.0..=10.0)
+                .display_scale(12.0)
+                .default_value(0.0)
+                .precision(1)
+                .allow_inverse()
+                .units(" st"),
+            Input::Stretch => slider
+                .range(0.0..=0.5)
+                .default_value(0.0)
+                .precision(3)
+                .allow_inverse(),
+            Input::HarmonicShift => slider
+                .range(0.0..=128.0)
+                .default_value(0.0)
+                .precision(2)
+                .allow_inverse(),
+            Input::Threshold => slider
+                .range(0.0..=60.0)
+                .default_value(0.0)
+                .precision(1)
+                .allow_inverse()
+                .units(" dB"),
+            Input::Ratio => slider
+                .range(0.0..=19.0)
+                .default_value(0.0)
+                .precision(1)
+                .allow_inverse(),
+            Input::Makeup => slider
+                .range(0.0..=24.0)
+                .default_value(0.0)
+                .precision(1)
+                .allow_inverse()
+                .units(" dB"),
+            Input::DecaySpread => slider
+                .range(0.0..=2.0)
+                .default_value(0.0)
+                .precision(2)
+                .allow_inverse(),
+            Input::Position => slider
+                .default_value(0.0)
+                .display_scale(100.0)
+                .precision(1)
+                .allow_inverse()
+                .units("%"),
+            Input::FmIndex => slider
+                .range(0.0..=10.0)
+                .default_value(0.0)
+                .precision(2)
+                .allow_inverse(),
+            Input::Feedback => slider
+                .default_value(0.0)
+                .display_scale(100.0)
+                .precision(1)
+                .allow_inverse()
+                .units("%"),
+            Input::Pan => slider
+                .range(0.0..=2.0)
+                .default_value(0.0)
+                .display_scale(100.0)
+                .precision(0)
+                .allow_inverse()
+                .units("%"),
+            Input::Width => slider
+                .range(0.0..=2.0)
+                .default_value(0.0)
+                .display_scale(100.0)
+                .precision(0)
+                .allow_inverse()
+                .units("%"),
+            Input::ValueA | Input::ValueB => slider
+                .range(0.0..=2.0)
+                .default_value(1.0)
+                .precision(2)
+                .allow_inverse(),
+            Input::Partials => slider
+                .range(0.0..=1022.0)
+                .default_value(0.0)
+                .precision(1)
+                .allow_inverse(),
+            Input::Rolloff => slider
+                .range(0.0..=24.0)
+                .default_value(0.0)
+                .precision(1)
+                .allow_inverse()
+                .units(" dB/oct"),
+            Input::CombSpacing => slider
+                .range(0.0..=15.0)
+                .default_value(0.0)
+                .precision(2)
+                .allow_inverse(),
+            Input::EvenOdd => slider
+                .range(0.0..=2.0)
+                .default_value(0.0)
+                .precision(2)
+                .allow_inverse(),
+            Input::SplitHarmonic => slider
+                .range(0.0..=1023.0)
+                .default_value(0.0)
+                .skew(2.0)
+                .precision(1)
+                .allow_inverse(),
+            Input::CrossoverWidth => slider
+                .range(0.0..=128.0)
+                .default_value(0.0)
+                .skew(1.8)
+                .precision(1)
+                .allow_inverse(),
             Input::Spectrum | Input::SpectrumTo | Input::SpectrumMix(_) => slider
                 .range(0.0..=1.0)
                 .default_value(1.0)
@@ -258,29 +547,88 @@ impl<'a> ModulationInput<'a> {
                 .precision(0)
                 .allow_inverse()
                 .units("%"),
-            Input::Audio | Input::AudioMix(_) => slider,
+            Input::Audio | Input::AudioMix(_) | Input::PhaseMod => slider,
         };
 
         if let Some(default) = self.modulation_default {
             updated = updated.default_value(default);
         }
 
-        updated
+        Self::limit_range(updated, self.range_max)
     }
 
     fn add_slider(&mut self, ui: &mut Ui) -> Response {
         match self.input.input_type {
             Input::Level | Input::LevelMix(_) => ui.add(DbSlider::new(self.value).width(200.0)),
-            _ => ui.add(
-                Self::setup_value_slider(
+            _ => {
+                let modulated = self.bridge.get_input_modulated_value(self.input);
+                let param = format!("{:?}", self.input.input_type);
+                let slider = Self::setup_value_slider(
                     StereoSlider::new(self.value)
-                        .modulated(self.bridge.get_input_modulated_value(self.input)),
+                        .modulated(modulated)
+                        .channels_link(self.bridge, self.input.module_id, &param),
                     self.input.input_type,
                     self.default,
-                )
-                .length(200.0),
-            ),
+                );
+
+                ui.add(Self::limit_range(slider, self.range_max).length(200.0))
+            }
+        }
+    }
+
+    // Level sliders are in dB, their mapping covers silence to unity gain
+    fn value_range(&self) -> RangeInclusive<Sample> {
+        let mut value = StereoSample::ZERO;
+
+        match self.input.input_type {
+            Input::Level | Input::LevelMix(_) => 0.0..=1.0,
+            input_type => Self::limit_range(
+                Self::setup_value_slider(StereoSlider::new(&mut value), input_type, None),
+                self.range_max,
+            )
+            .value_range(),
+        }
+    }
+
+    fn add_midi_learn(&mut self, ui: &mut Ui, response: &Response) {
+        let learning = self.bridge.midi_learn_target().is_some_and(
+            |target| matches!(target, MidiLearnTarget::Input { input, .. } if input == self.input),
+        );
+        let mapped_cc = self
+            .bridge
+            .midi_mapping(self.input)
+            .map(|mapping| mapping.cc);
+
+        if learning {
+            ui.weak("Move a MIDI control");
+        } else if let Some(cc) = mapped_cc {
+            ui.weak(format!("CC {cc}"));
         }
+
+        response.context_menu(|ui| {
+            if learning {
+                if ui.button("Cancel MIDI Learn").clicked() {
+                    self.bridge.cancel_midi_learn();
+                }
+            } else if ui.button("MIDI Learn").clicked() {
+                let range = self.value_range();
+
+                self.bridge.arm_midi_learn(MidiLearnTarget::Input {
+                    input: self.input,
+                    min: *range.start(),
+                    max: *range.end(),
+                });
+            }
+
+            if mapped_cc.is_some() && ui.button("Remove MIDI Mapping").clicked() {
+                let mut mappings = self.bridge.midi_mappings().to_vec();
+
+                mappings.retain(|m| {
+                    !(m.module_id == self.input.module_id && m.input == self.input.input_type)
+                });
+                self.bridge.set_midi_mappings(mappings);
+            }
+        });
     }
 
     fn add_link(&mut self, src: ModuleId) {
@@ -296,6 +644,113 @@ impl<'a> ModulationInput<'a> {
             .set_link_modulation(src_id, &self.input, modulator_id);
     }
 
+    fn add_curve_select(&mut self, ui: &mut Ui, src: &ConnectedInputSource) {
+        let mut curve = src.curve;
+
+        ui.horizontal(|ui| {
+            ComboBox::from_id_salt(format!(
+                "mod-curve-select-{:?}-{}",
+                self.input.input_type, src.src
+            ))
+            .selected_text(curve.label())
+            .width(60.0)
+            .show_ui(ui, |ui| {
+                for option in [
+                    LinkCurve::Linear,
+                    LinkCurve::Exponential(0.5),
+                    LinkCurve::SCurve(1.0),
+                    LinkCurve::Steps(4),
+                ] {
+                    if ui
+                        .selectable_label(curve.label() == option.label(), option.label())
+                        .clicked()
+                        && curve.label() != option.label()
+                    {
+                        curve = option;
+                    }
+                }
+            })
+            .response
+            .on_hover_text("Response curve");
+
+            match &mut curve {
+                LinkCurve::Linear => {}
+                LinkCurve::Exponential(curvature) => {
+                    ui.add(DragValue::new(curvature).range(-1.0..=1.0).speed(0.01));
+                }
+                LinkCurve::SCurve(steepness) => {
+                    ui.add(DragValue::new(steepness).range(0.0..=1.0).speed(0.01));
+                }
+                LinkCurve::Steps(steps) => {
+                    ui.add(DragValue::new(steps).range(1..=LinkCurve::MAX_STEPS));
+                }
+            }
+        });
+
+        if curve != src.curve {
+            self.bridge.set_link_curve(src.src, &self.input, curve);
+        }
+    }
+
+    fn add_polarity_select(&mut self, ui: &mut Ui, src: &ConnectedInputSource) {
+        let mut polarity = src.polarity;
+        let mut offset = src.offset;
+        let mut mode = src.mode;
+
+        ui.horizontal(|ui| {
+            ComboBox::from_id_salt(format!(
+                "mod-polarity-select-{:?}-{}",
+                self.input.input_type, src.src
+            ))
+            .selected_text(polarity.label())
+            .width(70.0)
+            .show_ui(ui, |ui| {
+                for option in LinkPolarity::ALL {
+                    ui.selectable_value(&mut polarity, option, option.label());
+                }
+            })
+            .response
+            .on_hover_text("Source polarity");
+
+            ui.add(
+                DragValue::new(&mut offset)
+                    .range(-1.0..=1.0)
+                    .speed(0.01)
+                    .prefix("+"),
+            )
+            .on_hover_text("Source offset");
+
+            if self.input.input_type.supports_link_mode() {
+                ComboBox::from_id_salt(format!(
+                    "mod-mode-select-{:?}-{}",
+                    self.input.input_type, src.src
+                ))
+                .selected_text(mode.label())
+                .width(70.0)
+                .show_ui(ui, |ui| {
+                    for option in LinkMode::ALL {
+                        ui.selectable_value(&mut mode, option, option.label());
+                    }
+                })
+                .response
+                .on_hover_text("Add to the value or scale it");
+            }
+        });
+
+        if polarity != src.polarity {
+            self.bridge
+                .set_link_polarity(src.src, &self.input, polarity);
+        }
+
+        if offset != src.offset {
+            self.bridge.set_link_offset(src.src, &self.input, offset);
+        }
+
+        if mode != src.mode {
+            self.bridge.set_link_mode(src.src, &self.input, mode);
+        }
+    }
+
     fn add_link_select(
         &mut self,
         ui: &mut Ui,
@@ -312,6 +767,12 @@ impl<'a> ModulationInput<'a> {
             return;
         }
 
+        let hover_text = if self.bridge.is_audio_rate_input(self.input) {
+            "Add Modulation Source, followed per sample"
+        } else {
+            "Add Modulation Source"
+        };
+
         ComboBox::from_id_salt(format!("mod-src-select-{:?}", self.input.input_type))
             .selected_text("➕")
             .width(0.0)
@@ -323,7 +784,7 @@ impl<'a> ModulationInput<'a> {
                 }
             })
             .response
-            .on_hover_text("Add Modulation Source");
+            .on_hover_text(hover_text);
     }
 
     fn add_connected_links(
@@ -352,15 +813,27 @@ impl<'a> ModulationInput<'a> {
 
                     let mut amount = src.amount;
 
-                    let slider_response = ui.add(
-                        self.setup_modulation_slider(StereoSlider::new(&mut amount))
-                            .length(200.0),
-                    );
+                    // Multiplying links scale the value, the amount is a share of it
+                    let slider = match src.mode {
+                        LinkMode::Add => {
+                            self.setup_modulation_slider(StereoSlider::new(&mut amount))
+                        }
+                        LinkMode::Multiply => StereoSlider::new(&mut amount)
+                            .default_value(0.0)
+                            .display_scale(100.0)
+                            .precision(0)
+                            .allow_inverse()
+                            .units("%"),
+                    };
+                    let slider_response = ui.add(slider.length(200.0));
 
                     if slider_response.changed() {
                         self.bridge.set_link_amount(src.src, self.input, amount);
                     }
 
+                    self.add_curve_select(ui, src);
+                    self.add_polarity_select(ui, src);
+
                     if src.modulation.is_none() {
                         ComboBox::from_id_salt(format!(
                             "mod-mod-select-{:?}-{}",
@@ -406,6 +879,10 @@ impl Widget for ModulationInput<'_> {
 
                     let result_response = self.add_slider(ui);
 
+                    if self.input.input_type.has_value() {
+                        self.add_midi_learn(ui, &result_response);
+                    }
+
                     self.add_link_select(ui, &connected, &available);
                     result_response
                 })
@@ -1,6 +1,18 @@
-use egui::{self, Button, Id, Modal, Response, Sides, TextEdit, Ui, Widget};
+use egui::{
+    self, Button, Color32, DragValue, Id, Modal, Response, RichText, Sides, TextEdit, Ui, Widget,
+};
 
-use crate::synth_engine::{ModuleId, ui_bridge::UiBridge};
+use crate::synth_engine::{
+    MAX_OUTPUT_TRIM, ModuleId,
+    ui_bridge::{ModuleColor, UiBridge},
+};
+
+fn color_swatch(color: ModuleColor, text: &str) -> RichText {
+    match color.color() {
+        Some(color32) => RichText::new(format!("●{text}")).color(color32),
+        None => RichText::new(format!("○{text}")).color(Color32::GRAY),
+    }
+}
 
 pub struct ModuleLabel<'a> {
     state: &'a mut Option<String>,
@@ -28,13 +40,90 @@ impl Widget for ModuleLabel<'_> {
 
         let result = ui
             .horizontal(|ui| {
+                let current_color = self.synth_bridge.module_color(self.module_id);
+
+                ui.menu_button(color_swatch(current_color, ""), |ui| {
+                    for color in ModuleColor::ALL {
+                        let text = color_swatch(color, &format!(" {}", color.label()));
+
+                        if ui.selectable_label(color == current_color, text).clicked() {
+                            self.synth_bridge.set_module_color(self.module_id, color);
+                        }
+                    }
+                })
+                .response
+                .on_hover_text("Color tag");
+
                 ui.heading(&label);
                 if ui.button("✏").clicked() {
                     *self.state = Some(label.to_string());
                 }
+
+                ui.menu_button("⧉", |ui| {
+                    if ui.button("Duplicate").clicked() {
+                        self.synth_bridge.duplicate_module(self.module_id, false);
+                    }
+
+                    if ui.button("Duplicate with Input Links").clicked() {
+                        self.synth_bridge.duplicate_module(self.module_id, true);
+                    }
+                })
+                .response
+                .on_hover_text("Duplicate");
+
+                let mut bypass = self.synth_bridge.is_bypassed(self.module_id);
+
+                if ui
+                    .toggle_value(&mut bypass, "Bypass")
+                    .on_hover_text("Audio and spectrum pass through, modulation outputs zero")
+                    .changed()
+                {
+                    self.synth_bridge.set_module_bypass(self.module_id, bypass);
+                }
+
+                if self.synth_bridge.can_solo(self.module_id) {
+                    let mut solo = self.synth_bridge.solo() == Some(self.module_id);
+
+                    if ui
+                        .toggle_value(&mut solo, "Solo")
+                        .on_hover_text(
+                            "Play this module's output directly, skipping the rest of the chain",
+                        )
+                        .changed()
+                    {
+                        self.synth_bridge.set_solo(solo.then_some(self.module_id));
+                    }
+                }
             })
             .response;
 
+        if self.synth_bridge.can_trim_output(self.module_id) {
+            let mut trim = self.synth_bridge.output_trim(self.module_id);
+
+            ui.horizontal(|ui| {
+                let gain_changed = ui
+                    .add(
+                        DragValue::new(&mut trim.gain)
+                            .range(-MAX_OUTPUT_TRIM..=MAX_OUTPUT_TRIM)
+                            .speed(0.1)
+                            .fixed_decimals(1)
+                            .prefix("Trim ")
+                            .suffix(" dB"),
+                    )
+                    .on_hover_text("Output gain of the module")
+                    .changed();
+
+                let invert_changed = ui
+                    .toggle_value(&mut trim.invert, "Ø")
+                    .on_hover_text("Invert the output polarity")
+                    .changed();
+
+                if gain_changed || invert_changed {
+                    self.synth_bridge.set_output_trim(trim);
+                }
+            });
+        }
+
         if let Some(label) = self.state {
             let trimmed = label.trim().to_string();
 
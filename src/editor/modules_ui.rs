@@ -1,29 +1,69 @@
 mod amplifier_ui;
+mod analyzer_ui;
+mod chorus_ui;
+mod delay_ui;
+mod envelope_follower_ui;
 mod envelope_ui;
 mod expressions_ui;
 mod external_param_ui;
+mod filter_ui;
+mod harmonic_comb_ui;
 mod harmonic_editor_ui;
+mod harmonic_envelopes_ui;
+mod harmonic_shift_ui;
+mod harmonic_stretch_ui;
 mod lfo_ui;
+mod math_ui;
+mod midi_control_ui;
 mod mixer_ui;
 mod oscillator_ui;
 mod output_ui;
+mod panner_ui;
 mod params_ui;
+mod preset_browser_ui;
+mod reverb_ui;
+mod ring_mod_ui;
+mod sample_resynth_ui;
+mod scope_ui;
+mod spectral_analyzer_ui;
 mod spectral_blend_ui;
+mod spectral_dynamics_ui;
 mod spectral_filter_ui;
 mod spectral_mixer_ui;
+mod spectral_tilt_ui;
 mod wave_shaper_ui;
 
 pub use amplifier_ui::AmplifierUI;
+pub use analyzer_ui::AnalyzerUi;
+pub use chorus_ui::ChorusUi;
+pub use delay_ui::DelayUi;
+pub use envelope_follower_ui::EnvelopeFollowerUi;
 pub use envelope_ui::EnvelopeUI;
 pub use expressions_ui::ExpressionsUi;
 pub use external_param_ui::ExternalParamUI;
+pub use filter_ui::FilterUi;
+pub use harmonic_comb_ui::HarmonicCombUi;
 pub use harmonic_editor_ui::HarmonicEditorUI;
+pub use harmonic_envelopes_ui::HarmonicEnvelopesUi;
+pub use harmonic_shift_ui::HarmonicShiftUi;
+pub use harmonic_stretch_ui::HarmonicStretchUi;
 pub use lfo_ui::LfoUi;
+pub use math_ui::MathUi;
+pub use midi_control_ui::MidiControlUi;
 pub use mixer_ui::MixerUi;
 pub use oscillator_ui::OscillatorUI;
 pub use output_ui::OutputUi;
+pub use panner_ui::PannerUi;
 pub use params_ui::ParamsUi;
+pub use preset_browser_ui::PresetBrowserUi;
+pub use reverb_ui::ReverbUi;
+pub use ring_mod_ui::RingModUi;
+pub use sample_resynth_ui::SampleResynthUi;
+pub use scope_ui::ScopeUi;
+pub use spectral_analyzer_ui::SpectralAnalyzerUi;
 pub use spectral_blend_ui::SpectralBlendUi;
+pub use spectral_dynamics_ui::SpectralDynamicsUi;
 pub use spectral_filter_ui::SpectralFilterUI;
 pub use spectral_mixer_ui::SpectralMixerUi;
+pub use spectral_tilt_ui::SpectralTiltUi;
 pub use wave_shaper_ui::WaveShaperUi;
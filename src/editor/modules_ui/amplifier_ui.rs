@@ -1,4 +1,4 @@
-use egui::{Grid, Ui};
+use egui::{Checkbox, Grid, Ui};
 
 use crate::{
     editor::{
@@ -55,6 +55,41 @@ impl AmplifierUI {
                     amp_bridge.set_param(Input::Gain, config.gain);
                 }
                 ui.end_row();
+
+                ui.label("Saturation");
+                if ui
+                    .add(Checkbox::new(&mut config.saturation, "Soft clip"))
+                    .changed()
+                {
+                    amp_bridge.set_saturation(config.saturation);
+                }
+                ui.end_row();
+
+                ui.label("Drive");
+                if ui
+                    .add_enabled(
+                        config.saturation,
+                        ModulationInput::new(&mut config.drive, bridge, Input::Drive, module_id),
+                    )
+                    .changed()
+                {
+                    amp_bridge.set_param(Input::Drive, config.drive);
+                }
+                ui.end_row();
+
+                ui.label("Pan");
+                if ui
+                    .add(ModulationInput::new(
+                        &mut config.pan,
+                        bridge,
+                        Input::Pan,
+                        module_id,
+                    ))
+                    .changed()
+                {
+                    amp_bridge.set_param(Input::Pan, config.pan);
+                }
+                ui.end_row();
             });
 
         ui.add_space(40.0);
@@ -0,0 +1,200 @@
+use egui::{
+    Align2, Button, Checkbox, Color32, FontId, Painter, Pos2, Rect, Sense, Slider, Stroke, Ui,
+    pos2, vec2,
+};
+
+use crate::{
+    editor::{DetailViewKey, ModuleUi},
+    synth_engine::{
+        ModuleId, Sample,
+        analyzer::{ANALYZER_BINS, ANALYZER_FFT_SIZE, ANALYZER_MIN_DB},
+        ui_bridge::UiBridge,
+    },
+};
+
+const MIN_FREQ: Sample = 20.0;
+const MAX_FREQ: Sample = 20_000.0;
+const MIN_DB: Sample = -96.0;
+const MAX_DB: Sample = 6.0;
+const ANALYZER_HEIGHT: f32 = 180.0;
+const BG_COLOR: Color32 = Color32::from_rgb(0x1d, 0x21, 0x25);
+const SPECTRUM_COLOR: Color32 = Color32::from_rgb(0xaa, 0x88, 0xff);
+const PEAK_COLOR: Color32 = Color32::from_rgb(0xff, 0xaa, 0x66);
+const LINE_WIDTH: f32 = 1.5;
+const GRID_FREQS: &[Sample] = &[50.0, 100.0, 200.0, 500.0, 1000.0, 2000.0, 5000.0, 10_000.0];
+const GRID_DBS: &[Sample] = &[0.0, -24.0, -48.0, -72.0];
+
+fn grid_color() -> Color32 {
+    Color32::from_rgba_unmultiplied(255, 255, 255, 24)
+}
+
+pub struct AnalyzerUi {
+    latest: Vec<Sample>,
+    averaged: Vec<Sample>,
+    peaks: Vec<Sample>,
+    averaging: f32,
+    peak_hold: bool,
+}
+
+impl AnalyzerUi {
+    pub fn new() -> Self {
+        Self {
+            latest: Vec::new(),
+            averaged: vec![ANALYZER_MIN_DB; ANALYZER_BINS],
+            peaks: vec![ANALYZER_MIN_DB; ANALYZER_BINS],
+            averaging: 0.7,
+            peak_hold: false,
+        }
+    }
+
+    fn update(&mut self) {
+        for ((averaged, peak), &latest) in self
+            .averaged
+            .iter_mut()
+            .zip(self.peaks.iter_mut())
+            .zip(&self.latest)
+        {
+            *averaged = self.averaging * *averaged + (1.0 - self.averaging) * latest;
+            *peak = if self.peak_hold {
+                peak.max(*averaged)
+            } else {
+                *averaged
+            };
+        }
+    }
+
+    fn freq_to_x(rect: Rect, freq: Sample) -> f32 {
+        rect.left() + rect.width() * (freq / MIN_FREQ).ln() / (MAX_FREQ / MIN_FREQ).ln()
+    }
+
+    fn db_to_y(rect: Rect, db: Sample) -> f32 {
+        rect.top() + rect.height() * (MAX_DB - db.clamp(MIN_DB, MAX_DB)) / (MAX_DB - MIN_DB)
+    }
+
+    fn paint_grid(painter: &Painter, rect: Rect) {
+        let stroke = Stroke::new(1.0, grid_color());
+        let font = FontId::proportional(10.0);
+
+        for &freq in GRID_FREQS {
+            let x = Self::freq_to_x(rect, freq);
+            let label = if freq >= 1000.0 {
+                format!("{}k", freq / 1000.0)
+            } else {
+                format!("{freq}")
+            };
+
+            painter.vline(x, rect.y_range(), stroke);
+            painter.text(
+                pos2(x + 2.0, rect.bottom() - 2.0),
+                Align2::LEFT_BOTTOM,
+                label,
+                font.clone(),
+                Color32::GRAY,
+            );
+        }
+
+        for &db in GRID_DBS {
+            let y = Self::db_to_y(rect, db);
+
+            painter.hline(rect.x_range(), y, stroke);
+            painter.text(
+                pos2(rect.left() + 2.0, y - 2.0),
+                Align2::LEFT_BOTTOM,
+                format!("{db} dB"),
+                font.clone(),
+                Color32::GRAY,
+            );
+        }
+    }
+
+    // Log frequency axis, a column takes the loudest of the bins it covers
+    fn paint_spectrum(
+        painter: &Painter,
+        rect: Rect,
+        spectrum: &[Sample],
+        sample_rate: Sample,
+        color: Color32,
+    ) {
+        let bin_width = sample_rate / ANALYZER_FFT_SIZE as Sample;
+        let columns = rect.width().ceil().max(2.0) as usize;
+        let column_freq = |column: usize| {
+            MIN_FREQ * (MAX_FREQ / MIN_FREQ).powf(column as Sample / columns as Sample)
+        };
+
+        let points: Vec<Pos2> = (0..columns)
+            .filter_map(|column| {
+                let from = column_freq(column) / bin_width;
+                let to = column_freq(column + 1) / bin_width;
+                let last = spectrum.len().checked_sub(1)?;
+
+                let db = if to - from < 1.0 {
+                    let idx = (from.floor() as usize).min(last);
+                    let next = (idx + 1).min(last);
+                    let frac = from.fract();
+
+                    spectrum[idx] + (spectrum[next] - spectrum[idx]) * frac
+                } else {
+                    spectrum[(from as usize).min(last)..=(to as usize).min(last)]
+                        .iter()
+                        .copied()
+                        .fold(ANALYZER_MIN_DB, Sample::max)
+                };
+
+                Some(pos2(rect.left() + column as f32, Self::db_to_y(rect, db)))
+            })
+            .collect();
+
+        painter.line(points, Stroke::new(LINE_WIDTH, color));
+    }
+
+    fn show_controls(&mut self, ui: &mut Ui) {
+        ui.horizontal(|ui| {
+            ui.add(Slider::new(&mut self.averaging, 0.0..=0.95).text("Averaging"));
+            ui.add(Checkbox::new(&mut self.peak_hold, "Peak Hold"));
+
+            if ui
+                .add_enabled(self.peak_hold, Button::new("Reset Peaks"))
+                .clicked()
+            {
+                self.peaks.fill(ANALYZER_MIN_DB);
+            }
+        });
+    }
+}
+
+impl ModuleUi for AnalyzerUi {
+    fn module_id(&self) -> Option<ModuleId> {
+        None
+    }
+
+    fn detail_key(&self) -> DetailViewKey {
+        DetailViewKey::Analyzer
+    }
+
+    fn ui(&mut self, bridge: &mut UiBridge, ui: &mut Ui) {
+        ui.heading("Spectrum Analyzer");
+        ui.add_space(12.0);
+
+        self.show_controls(ui);
+        ui.add_space(8.0);
+
+        bridge.set_analyzer_active(true);
+        bridge.read_spectrum(&mut self.latest);
+        self.update();
+        ui.ctx().request_repaint();
+
+        let sample_rate = bridge.analyzer_sample_rate().unwrap_or(44100.0);
+        let (rect, _) =
+            ui.allocate_exact_size(vec2(ui.available_width(), ANALYZER_HEIGHT), Sense::hover());
+        let painter = ui.painter_at(rect);
+
+        painter.rect_filled(rect, 0.0, BG_COLOR);
+        Self::paint_grid(&painter, rect);
+
+        if self.peak_hold {
+            Self::paint_spectrum(&painter, rect, &self.peaks, sample_rate, PEAK_COLOR);
+        }
+
+        Self::paint_spectrum(&painter, rect, &self.averaged, sample_rate, SPECTRUM_COLOR);
+    }
+}
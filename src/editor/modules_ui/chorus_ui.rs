@@ -0,0 +1,144 @@
+use egui::{Grid, Slider, Ui};
+
+use crate::{
+    editor::{
+        ModuleUi, direct_input::DirectInput, module_label::ModuleLabel,
+        stereo_slider::StereoSlider, utils::confirm_module_removal,
+    },
+    synth_engine::{
+        Input, ModuleId,
+        chorus::{ChorusUiBridge, MAX_CHORUS_VOICES},
+        ui_bridge::{ModuleBridge, UiBridge},
+    },
+};
+
+pub struct ChorusUi {
+    module_id: ModuleId,
+    remove_confirmation: bool,
+    label_state: Option<String>,
+}
+
+impl ChorusUi {
+    pub fn new(module_id: ModuleId) -> Self {
+        Self {
+            module_id,
+            remove_confirmation: false,
+            label_state: None,
+        }
+    }
+
+    fn paint_ui(&mut self, bridge: &mut UiBridge, chorus_bridge: &mut ChorusUiBridge, ui: &mut Ui) {
+        let module_id = self.module_id;
+        let mut config = chorus_bridge.config().clone();
+
+        ui.add(ModuleLabel::new(&mut self.label_state, bridge, module_id));
+
+        ui.add_space(20.0);
+
+        Grid::new("chorus_grid")
+            .num_columns(2)
+            .spacing([40.0, 24.0])
+            .striped(true)
+            .show(ui, |ui| {
+                ui.label("Input");
+                ui.add(DirectInput::new(bridge, Input::Audio, module_id));
+                ui.end_row();
+
+                ui.label("Voices");
+                if ui
+                    .add(Slider::new(&mut config.voices, 1..=MAX_CHORUS_VOICES))
+                    .changed()
+                {
+                    chorus_bridge.set_voices(config.voices);
+                }
+                ui.end_row();
+
+                ui.label("Rate");
+                if ui
+                    .add(
+                        Slider::new(&mut config.rate, 0.01..=10.0)
+                            .logarithmic(true)
+                            .suffix(" Hz"),
+                    )
+                    .changed()
+                {
+                    chorus_bridge.set_rate(config.rate);
+                }
+                ui.end_row();
+
+                ui.label("Delay");
+                if ui
+                    .add(
+                        StereoSlider::new(&mut config.delay)
+                            .channels_link(bridge, module_id, "Delay")
+                            .range(0.001..=0.03)
+                            .display_scale(1000.0)
+                            .default_value(0.012)
+                            .units(" ms"),
+                    )
+                    .changed()
+                {
+                    chorus_bridge.set_delay(config.delay);
+                }
+                ui.end_row();
+
+                ui.label("Depth");
+                if ui
+                    .add(
+                        StereoSlider::new(&mut config.depth)
+                            .channels_link(bridge, module_id, "Depth")
+                            .range(0.0..=0.01)
+                            .display_scale(1000.0)
+                            .default_value(0.003)
+                            .units(" ms"),
+                    )
+                    .changed()
+                {
+                    chorus_bridge.set_depth(config.depth);
+                }
+                ui.end_row();
+
+                ui.label("Stereo spread");
+                if ui.add(Slider::new(&mut config.spread, 0.0..=1.0)).changed() {
+                    chorus_bridge.set_spread(config.spread);
+                }
+                ui.end_row();
+
+                ui.label("Mix");
+                if ui
+                    .add(
+                        StereoSlider::new(&mut config.mix)
+                            .channels_link(bridge, module_id, "Mix")
+                            .display_scale(100.0)
+                            .default_value(0.5)
+                            .precision(0)
+                            .units("%"),
+                    )
+                    .changed()
+                {
+                    chorus_bridge.set_mix(config.mix);
+                }
+                ui.end_row();
+            });
+
+        ui.add_space(40.0);
+
+        if confirm_module_removal(ui, &mut self.remove_confirmation) {
+            bridge.remove_module(module_id);
+        }
+    }
+}
+
+impl ModuleUi for ChorusUi {
+    fn module_id(&self) -> Option<ModuleId> {
+        Some(self.module_id)
+    }
+
+    fn ui(&mut self, bridge: &mut UiBridge, ui: &mut Ui) {
+        bridge.with_module_bridge(self.module_id, |bridge, module_bridge| {
+            if let ModuleBridge::Chorus(chorus_bridge) = module_bridge {
+                self.paint_ui(bridge, chorus_bridge, ui);
+            }
+        });
+    }
+}
@@ -0,0 +1,219 @@
+use egui::{Checkbox, ComboBox, Grid, Slider, Ui};
+
+use crate::{
+    editor::{
+        ModuleUi, direct_input::DirectInput, module_label::ModuleLabel,
+        stereo_slider::StereoSlider, utils::confirm_module_removal,
+    },
+    synth_engine::{
+        Input, ModuleId, NUM_CHANNELS, NoteDivision,
+        delay::{DelayUiBridge, MAX_DELAY_TIME},
+        ui_bridge::{ModuleBridge, UiBridge},
+    },
+};
+
+impl NoteDivision {
+    fn label(&self) -> &'static str {
+        match self {
+            Self::Sixteenth => "1/16",
+            Self::EighthTriplet => "1/8 T",
+            Self::Eighth => "1/8",
+            Self::DottedEighth => "1/8 D",
+            Self::QuarterTriplet => "1/4 T",
+            Self::Quarter => "1/4",
+            Self::DottedQuarter => "1/4 D",
+            Self::Half => "1/2",
+            Self::Whole => "1/1",
+        }
+    }
+}
+
+pub struct DelayUi {
+    module_id: ModuleId,
+    remove_confirmation: bool,
+    label_state: Option<String>,
+}
+
+impl DelayUi {
+    pub fn new(module_id: ModuleId) -> Self {
+        Self {
+            module_id,
+            remove_confirmation: false,
+            label_state: None,
+        }
+    }
+
+    fn paint_ui(&mut self, bridge: &mut UiBridge, delay_bridge: &mut DelayUiBridge, ui: &mut Ui) {
+        let module_id = self.module_id;
+        let mut config = delay_bridge.config().clone();
+
+        ui.add(ModuleLabel::new(&mut self.label_state, bridge, module_id));
+
+        ui.add_space(20.0);
+
+        Grid::new("delay_grid")
+            .num_columns(2)
+            .spacing([40.0, 24.0])
+            .striped(true)
+            .show(ui, |ui| {
+                ui.label("Input");
+                ui.add(DirectInput::new(bridge, Input::Audio, module_id));
+                ui.end_row();
+
+                ui.label("Tempo sync");
+                if ui.add(Checkbox::without_text(&mut config.sync)).changed() {
+                    delay_bridge.set_sync(config.sync);
+                }
+                ui.end_row();
+
+                ui.label("Time");
+                if config.sync {
+                    ui.horizontal(|ui| {
+                        for channel_idx in 0..NUM_CHANNELS {
+                            Self::division_select(
+                                ui,
+                                delay_bridge,
+                                &mut config.divisions[channel_idx],
+                                channel_idx,
+                            );
+                        }
+                    });
+                } else if ui
+                    .add(
+                        StereoSlider::new(&mut config.time)
+                            .channels_link(bridge, module_id, "Time")
+                            .range(0.001..=MAX_DELAY_TIME)
+                            .display_scale(1000.0)
+                            .default_value(0.375)
+                            .precision(0)
+                            .skew(2.0)
+                            .units(" ms"),
+                    )
+                    .changed()
+                {
+                    delay_bridge.set_time(config.time);
+                }
+                ui.end_row();
+
+                ui.label("Feedback");
+                if ui
+                    .add(
+                        StereoSlider::new(&mut config.feedback)
+                            .channels_link(bridge, module_id, "Feedback")
+                            .range(0.0..=0.95)
+                            .display_scale(100.0)
+                            .default_value(0.4)
+                            .precision(0)
+                            .units("%"),
+                    )
+                    .changed()
+                {
+                    delay_bridge.set_feedback(config.feedback);
+                }
+                ui.end_row();
+
+                ui.label("Ping-pong");
+                if ui
+                    .add(Checkbox::without_text(&mut config.ping_pong))
+                    .changed()
+                {
+                    delay_bridge.set_ping_pong(config.ping_pong);
+                }
+                ui.end_row();
+
+                ui.label("Low cut");
+                if ui
+                    .add(
+                        Slider::new(&mut config.low_cut, 20.0..=2000.0)
+                            .logarithmic(true)
+                            .suffix(" Hz"),
+                    )
+                    .changed()
+                {
+                    delay_bridge.set_low_cut(config.low_cut);
+                }
+                ui.end_row();
+
+                ui.label("High cut");
+                if ui
+                    .add(
+                        Slider::new(&mut config.high_cut, 1000.0..=20000.0)
+                            .logarithmic(true)
+                            .suffix(" Hz"),
+                    )
+                    .changed()
+                {
+                    delay_bridge.set_high_cut(config.high_cut);
+                }
+                ui.end_row();
+
+                ui.label("Mix");
+                if ui
+                    .add(
+                        StereoSlider::new(&mut config.mix)
+                            .channels_link(bridge, module_id, "Mix")
+                            .display_scale(100.0)
+                            .default_value(0.3)
+                            .precision(0)
+                            .units("%"),
+                    )
+                    .changed()
+                {
+                    delay_bridge.set_mix(config.mix);
+                }
+                ui.end_row();
+            });
+
+        ui.add_space(40.0);
+
+        if confirm_module_removal(ui, &mut self.remove_confirmation) {
+            bridge.remove_module(module_id);
+        }
+    }
+
+    fn division_select(
+        ui: &mut Ui,
+        delay_bridge: &mut DelayUiBridge,
+        division: &mut NoteDivision,
+        channel_idx: usize,
+    ) {
+        const DIVISION_OPTIONS: &[NoteDivision] = &[
+            NoteDivision::Sixteenth,
+            NoteDivision::EighthTriplet,
+            NoteDivision::Eighth,
+            NoteDivision::DottedEighth,
+            NoteDivision::QuarterTriplet,
+            NoteDivision::Quarter,
+            NoteDivision::DottedQuarter,
+            NoteDivision::Half,
+            NoteDivision::Whole,
+        ];
+
+        ComboBox::from_id_salt(format!("delay-division-{channel_idx}"))
+            .selected_text(division.label())
+            .show_ui(ui, |ui| {
+                for option in DIVISION_OPTIONS {
+                    if ui
+                        .selectable_value(division, *option, option.label())
+                        .clicked()
+                    {
+                        delay_bridge.set_division(channel_idx, *option);
+                    }
+                }
+            });
+    }
+}
+
+impl ModuleUi for DelayUi {
+    fn module_id(&self) -> Option<ModuleId> {
+        Some(self.module_id)
+    }
+
+    fn ui(&mut self, bridge: &mut UiBridge, ui: &mut Ui) {
+        bridge.with_module_bridge(self.module_id, |bridge, module_bridge| {
+            if let ModuleBridge::Delay(delay_bridge) = module_bridge {
+                self.paint_ui(bridge, delay_bridge, ui);
+            }
+        });
+    }
+}
@@ -0,0 +1,131 @@
+use egui::{Grid, Ui};
+
+use crate::{
+    editor::{
+        ModuleUi, module_label::ModuleLabel, stereo_slider::StereoSlider,
+        utils::confirm_module_removal,
+    },
+    synth_engine::{
+        ModuleId, StereoSample,
+        envelope_follower::{EnvelopeFollowerUiBridge, MAX_FOLLOWER_GAIN},
+        ui_bridge::{ModuleBridge, UiBridge},
+    },
+};
+
+pub struct EnvelopeFollowerUi {
+    module_id: ModuleId,
+    remove_confirmation: bool,
+    label_state: Option<String>,
+}
+
+impl EnvelopeFollowerUi {
+    pub fn new(module_id: ModuleId) -> Self {
+        Self {
+            module_id,
+            remove_confirmation: false,
+            label_state: None,
+        }
+    }
+
+    fn paint_ui(
+        &mut self,
+        bridge: &mut UiBridge,
+        follower_bridge: &mut EnvelopeFollowerUiBridge,
+        ui: &mut Ui,
+    ) {
+        let module_id = self.module_id;
+        let config = follower_bridge.config().clone();
+
+        ui.add(ModuleLabel::new(&mut self.label_state, bridge, module_id));
+
+        ui.add_space(20.0);
+
+        ui.label("Follows the level of the plugin sidechain input.");
+
+        ui.add_space(20.0);
+
+        Grid::new("envelope-follower-grid")
+            .num_columns(2)
+            .spacing([40.0, 24.0])
+            .striped(true)
+            .show(ui, |ui| {
+                let mut attack = StereoSample::splat(config.attack);
+
+                ui.label("Attack");
+                if ui
+                    .add(
+                        StereoSlider::new(&mut attack)
+                            .channels_link(bridge, module_id, "Attack")
+                            .range(0.0..=0.5)
+                            .display_scale(1000.0)
+                            .default_value(0.01)
+                            .skew(1.6)
+                            .precision(1)
+                            .units(" ms"),
+                    )
+                    .changed()
+                {
+                    follower_bridge.set_attack(attack.left());
+                }
+                ui.end_row();
+
+                let mut release = StereoSample::splat(config.release);
+
+                ui.label("Release");
+                if ui
+                    .add(
+                        StereoSlider::new(&mut release)
+                            .channels_link(bridge, module_id, "Release")
+                            .range(0.0..=2.0)
+                            .display_scale(1000.0)
+                            .default_value(0.15)
+                            .skew(1.6)
+                            .precision(1)
+                            .units(" ms"),
+                    )
+                    .changed()
+                {
+                    follower_bridge.set_release(release.left());
+                }
+                ui.end_row();
+
+                let mut gain = StereoSample::splat(config.gain);
+
+                ui.label("Gain");
+                if ui
+                    .add(
+                        StereoSlider::new(&mut gain)
+                            .channels_link(bridge, module_id, "Gain")
+                            .range(-MAX_FOLLOWER_GAIN..=MAX_FOLLOWER_GAIN)
+                            .default_value(0.0)
+                            .precision(1)
+                            .units(" dB"),
+                    )
+                    .changed()
+                {
+                    follower_bridge.set_gain(gain.left());
+                }
+                ui.end_row();
+            });
+
+        ui.add_space(40.0);
+
+        if confirm_module_removal(ui, &mut self.remove_confirmation) {
+            bridge.remove_module(module_id);
+        }
+    }
+}
+
+impl ModuleUi for EnvelopeFollowerUi {
+    fn module_id(&self) -> Option<ModuleId> {
+        Some(self.module_id)
+    }
+
+    fn ui(&mut self, bridge: &mut UiBridge, ui: &mut Ui) {
+        bridge.with_module_bridge(self.module_id, |bridge, module_bridge| {
+            if let ModuleBridge::EnvelopeFollower(follower_bridge) = module_bridge {
+                self.paint_ui(bridge, follower_bridge, ui);
+            }
+        });
+    }
+}
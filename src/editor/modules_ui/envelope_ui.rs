@@ -1,18 +1,32 @@
-use egui::{Checkbox, Grid, Slider, Ui};
+use egui::{Checkbox, ComboBox, Grid, Id, Slider, Ui};
 
 use crate::{
     editor::{
-        ModuleUi, modulation_input::ModulationInput, module_label::ModuleLabel,
-        stereo_slider::StereoSlider, utils::confirm_module_removal,
+        ModuleUi,
+        envelope_plot::{EnvelopeHandle, envelope_plot},
+        modulation_input::ModulationInput,
+        module_label::ModuleLabel,
+        stereo_slider::StereoSlider,
+        utils::confirm_module_removal,
     },
     synth_engine::{
-        Input, ModuleId,
-        envelope::EnvelopeUiBridge,
+        EnvelopeLoop, Input, ModuleId,
+        envelope::{EnvelopeUiBridge, MAX_ENVELOPE_LOOPS},
         ui_bridge::{ModuleBridge, UiBridge},
     },
     utils::from_ms,
 };
 
+impl EnvelopeLoop {
+    fn label(&self) -> &'static str {
+        match self {
+            Self::Off => "Off",
+            Self::AttackDecay => "Attack-Decay",
+            Self::AttackHoldDecay => "Attack-Hold-Decay",
+        }
+    }
+}
+
 pub struct EnvelopeUI {
     module_id: ModuleId,
     remove_confirmation: bool,
@@ -36,6 +50,7 @@ impl EnvelopeUI {
     ) {
         let module_id = self.module_id;
         let mut config = env_bridge.config().clone();
+        let mut loop_fade_ms = config.loop_fade * 1000.0;
 
         ui.add(ModuleLabel::new(
             &mut self.label_state,
@@ -45,6 +60,19 @@ impl EnvelopeUI {
 
         ui.add_space(20.0);
 
+        match envelope_plot(ui, Id::new(("env-plot", module_id)), &mut config) {
+            Some(EnvelopeHandle::Attack) => env_bridge.set_param(Input::Attack, config.attack),
+            Some(EnvelopeHandle::Hold) => env_bridge.set_param(Input::Hold, config.hold),
+            Some(EnvelopeHandle::Decay) => {
+                env_bridge.set_param(Input::Decay, config.decay);
+                env_bridge.set_param(Input::Sustain, config.sustain);
+            }
+            Some(EnvelopeHandle::Release) => env_bridge.set_param(Input::Release, config.release),
+            None => (),
+        }
+
+        ui.add_space(20.0);
+
         Grid::new("env_grid")
             .num_columns(2)
             .spacing([40.0, 24.0])
@@ -123,6 +151,61 @@ impl EnvelopeUI {
                 }
                 ui.end_row();
 
+                ui.label("Loop");
+                ComboBox::from_id_salt("env-loop-mode")
+                    .selected_text(config.loop_mode.label())
+                    .show_ui(ui, |ui| {
+                        const LOOP_OPTIONS: &[EnvelopeLoop] = &[
+                            EnvelopeLoop::Off,
+                            EnvelopeLoop::AttackDecay,
+                            EnvelopeLoop::AttackHoldDecay,
+                        ];
+
+                        for loop_mode in LOOP_OPTIONS {
+                            if ui
+                                .selectable_value(
+                                    &mut config.loop_mode,
+                                    *loop_mode,
+                                    loop_mode.label(),
+                                )
+                                .clicked()
+                            {
+                                env_bridge.set_loop_mode(*loop_mode);
+                            }
+                        }
+                    })
+                    .response
+                    .on_hover_text("Repeats while the note is held, the release ends the loop");
+                ui.end_row();
+
+                let looping = config.loop_mode != EnvelopeLoop::Off;
+
+                ui.label("Loop Count");
+                if ui
+                    .add_enabled(
+                        looping,
+                        Slider::new(&mut config.loop_count, 0..=MAX_ENVELOPE_LOOPS),
+                    )
+                    .on_hover_text("0 keeps looping until the release")
+                    .changed()
+                {
+                    env_bridge.set_loop_count(config.loop_count);
+                }
+                ui.end_row();
+
+                ui.label("Loop Fade");
+                if ui
+                    .add_enabled(
+                        looping,
+                        Slider::new(&mut loop_fade_ms, 0.0..=50.0).suffix(" ms"),
+                    )
+                    .on_hover_text("Ramp from the end of the decay back to the attack start")
+                    .changed()
+                {
+                    env_bridge.set_loop_fade(from_ms(loop_fade_ms));
+                }
+                ui.end_row();
+
                 ui.label("Sustain");
                 if ui
                     .add(ModulationInput::new(
@@ -163,10 +246,62 @@ impl EnvelopeUI {
                 }
                 ui.end_row();
 
+                ui.label("Release Velocity");
+                if ui
+                    .add(Slider::new(&mut config.release_velocity, 0.0..=1.0))
+                    .on_hover_text("Hard note-offs shorten the release, soft ones stretch it")
+                    .changed()
+                {
+                    env_bridge.set_release_velocity(config.release_velocity);
+                }
+                ui.end_row();
+
+                ui.label("Velocity to Time");
+                if ui
+                    .add(Slider::new(&mut config.velocity_time, -1.0..=1.0))
+                    .on_hover_text(
+                        "Scales attack, decay and release by the note-on velocity, \
+                        positive makes hard hits faster",
+                    )
+                    .changed()
+                {
+                    env_bridge.set_velocity_time(config.velocity_time);
+                }
+                ui.end_row();
+
+                ui.label("Output Min");
+                if ui
+                    .add(Slider::new(&mut config.output_min, -1.0..=1.0))
+                    .changed()
+                {
+                    env_bridge.set_output_min(config.output_min);
+                }
+                ui.end_row();
+
+                ui.label("Output Max");
+                if ui
+                    .add(Slider::new(&mut config.output_max, -1.0..=1.0))
+                    .changed()
+                {
+                    env_bridge.set_output_max(config.output_max);
+                }
+                ui.end_row();
+
+                ui.label("Invert");
+                if ui
+                    .add(Checkbox::without_text(&mut config.invert))
+                    .on_hover_text("Falls from max to min instead of rising")
+                    .changed()
+                {
+                    env_bridge.set_invert(config.invert);
+                }
+                ui.end_row();
+
                 ui.label("Smooth");
                 if ui
                     .add(
                         StereoSlider::new(&mut config.smooth)
+                            .channels_link(bridge, module_id, "Smooth")
                             .range(0.0..=0.1)
                             .display_scale(1000.0)
                             .default_value(0.0)
@@ -1,4 +1,4 @@
-use egui::{Checkbox, ComboBox, Grid, Ui};
+use egui::{Checkbox, ComboBox, Grid, Slider, Ui};
 
 use crate::{
     editor::{
@@ -20,6 +20,8 @@ impl Expression {
             Self::Pitch => "Pitch",
             Self::Timbre => "Timbre",
             Self::Pressure => "Pressure",
+            Self::Vibrato => "Vibrato",
+            Self::Swell => "Swell",
         }
     }
 }
@@ -74,6 +76,8 @@ impl ExpressionsUi {
                             Expression::Pitch,
                             Expression::Timbre,
                             Expression::Pressure,
+                            Expression::Vibrato,
+                            Expression::Swell,
                         ];
 
                         for expression in TYPE_OPTIONS {
@@ -92,6 +96,15 @@ impl ExpressionsUi {
                 ui.end_row();
 
                 if matches!(config.expression, Expression::Velocity) {
+                    ui.label("Velocity Curve");
+                    if ui
+                        .add(Slider::new(&mut config.velocity_curve, -1.0..=1.0))
+                        .changed()
+                    {
+                        expr_bridge.set_velocity_curve(config.velocity_curve);
+                    }
+                    ui.end_row();
+
                     ui.label("Use Release velocity");
                     if ui
                         .add(Checkbox::without_text(&mut config.use_release_velocity))
@@ -106,6 +119,7 @@ impl ExpressionsUi {
                 if ui
                     .add(
                         StereoSlider::new(&mut smooth)
+                            .channels_link(bridge, module_id, "Smooth")
                             .range(0.0..=0.05)
                             .display_scale(1000.0)
                             .default_value(0.0)
@@ -1,4 +1,4 @@
-use egui::{Checkbox, ComboBox, Grid, Ui};
+use egui::{Checkbox, ComboBox, DragValue, Grid, Slider, TextEdit, Ui};
 
 use crate::{
     editor::{
@@ -8,7 +8,7 @@ use crate::{
     synth_engine::{
         ModuleId, StereoSample,
         external_param::{ExternalParamUiBridge, NUM_FLOAT_PARAMS},
-        ui_bridge::{ModuleBridge, UiBridge},
+        ui_bridge::{MidiLearnTarget, ModuleBridge, UiBridge},
     },
 };
 
@@ -49,30 +49,75 @@ impl ExternalParamUI {
             .spacing([40.0, 24.0])
             .striped(true)
             .show(ui, |ui| {
+                let learning =
+                    bridge.midi_learn_target() == Some(MidiLearnTarget::ExternalParam(module_id));
+                let selected_text = match config.midi_cc {
+                    Some(cc) => format!("CC {cc}"),
+                    None => bridge.float_param_label(config.selected_param_index),
+                };
+
                 ui.label("Input");
-                ComboBox::from_id_salt("ext-param-select")
-                    .selected_text(format!("Param #{}", config.selected_param_index + 1))
-                    .show_ui(ui, |ui| {
-                        for i in 0..NUM_FLOAT_PARAMS {
-                            if ui
-                                .selectable_label(
-                                    i == config.selected_param_index,
-                                    format!("Param #{}", i + 1),
-                                )
-                                .clicked()
-                            {
-                                param_bridge.select_param(i);
+                ui.horizontal(|ui| {
+                    let response = ComboBox::from_id_salt("ext-param-select")
+                        .selected_text(selected_text)
+                        .show_ui(ui, |ui| {
+                            for i in 0..NUM_FLOAT_PARAMS {
+                                if ui
+                                    .selectable_label(
+                                        config.midi_cc.is_none()
+                                            && i == config.selected_param_index,
+                                        bridge.float_param_label(i),
+                                    )
+                                    .clicked()
+                                {
+                                    param_bridge.select_param(i);
+                                    param_bridge.set_midi_cc(None);
+                                }
+                            }
+                        })
+                        .response;
+
+                    if learning {
+                        ui.weak("Move a MIDI control");
+                    }
+
+                    response.context_menu(|ui| {
+                        if learning {
+                            if ui.button("Cancel MIDI Learn").clicked() {
+                                bridge.cancel_midi_learn();
                             }
+                        } else if ui.button("MIDI Learn").clicked() {
+                            bridge.arm_midi_learn(MidiLearnTarget::ExternalParam(module_id));
                         }
                     });
+                });
                 ui.end_row();
 
+                if config.midi_cc.is_none() {
+                    let idx = config.selected_param_index;
+                    let mut name = bridge.float_param_name(idx).to_string();
+
+                    ui.label("Name");
+                    if ui
+                        .add(
+                            TextEdit::singleline(&mut name)
+                                .hint_text(format!("Param #{}", idx + 1)),
+                        )
+                        .on_hover_text("Shown by the host next to the parameter value")
+                        .changed()
+                    {
+                        bridge.set_float_param_name(idx, name);
+                    }
+                    ui.end_row();
+                }
+
                 let mut smooth = StereoSample::splat(config.smooth);
 
                 ui.label("Smooth");
                 if ui
                     .add(
                         StereoSlider::new(&mut smooth)
+                            .channels_link(bridge, module_id, "Smooth")
                             .range(0.0..=0.05)
                             .display_scale(1000.0)
                             .default_value(0.0)
@@ -94,6 +139,40 @@ impl ExternalParamUI {
                     param_bridge.set_sample_and_hold(config.sample_and_hold);
                 }
                 ui.end_row();
+
+                ui.label("Output Min");
+                if ui
+                    .add(DragValue::new(&mut config.output_min).speed(0.01))
+                    .changed()
+                {
+                    param_bridge.set_output_min(config.output_min);
+                }
+                ui.end_row();
+
+                ui.label("Output Max");
+                if ui
+                    .add(DragValue::new(&mut config.output_max).speed(0.01))
+                    .changed()
+                {
+                    param_bridge.set_output_max(config.output_max);
+                }
+                ui.end_row();
+
+                ui.label("Curve");
+                if ui.add(Slider::new(&mut config.curve, -1.0..=1.0)).changed() {
+                    param_bridge.set_curve(config.curve);
+                }
+                ui.end_row();
+
+                ui.label("Bipolar");
+                if ui
+                    .add(Checkbox::without_text(&mut config.bipolar))
+                    .on_hover_text("The curve bends away from the middle of the range")
+                    .changed()
+                {
+                    param_bridge.set_bipolar(config.bipolar);
+                }
+                ui.end_row();
             });
 
         ui.add_space(40.0);
@@ -0,0 +1,156 @@
+use egui::{ComboBox, Grid, Slider, Ui};
+
+use crate::{
+    editor::{
+        ModuleUi, direct_input::DirectInput, modulation_input::ModulationInput,
+        module_label::ModuleLabel, utils::confirm_module_removal,
+    },
+    synth_engine::{
+        FilterType, Input, ModuleId,
+        filter::FilterUiBridge,
+        ui_bridge::{ModuleBridge, UiBridge},
+    },
+};
+
+impl FilterType {
+    fn label(&self) -> &'static str {
+        match self {
+            Self::LowPass => "Lowpass",
+            Self::HighPass => "Highpass",
+            Self::BandPass => "Bandpass",
+            Self::Notch => "Notch",
+        }
+    }
+}
+
+pub struct FilterUi {
+    module_id: ModuleId,
+    remove_confirmation: bool,
+    label_state: Option<String>,
+}
+
+impl FilterUi {
+    pub fn new(module_id: ModuleId) -> Self {
+        Self {
+            module_id,
+            remove_confirmation: false,
+            label_state: None,
+        }
+    }
+
+    fn paint_ui(&mut self, bridge: &mut UiBridge, filter_bridge: &mut FilterUiBridge, ui: &mut Ui) {
+        let module_id = self.module_id;
+        let mut config = filter_bridge.config().clone();
+
+        ui.add(ModuleLabel::new(&mut self.label_state, bridge, module_id));
+
+        ui.add_space(20.0);
+
+        Grid::new("filter_grid")
+            .num_columns(2)
+            .spacing([40.0, 24.0])
+            .striped(true)
+            .show(ui, |ui| {
+                ui.label("Input");
+                ui.add(DirectInput::new(bridge, Input::Audio, module_id));
+                ui.end_row();
+
+                ui.label("Type");
+                ComboBox::from_id_salt("filter-type")
+                    .selected_text(config.filter_type.label())
+                    .show_ui(ui, |ui| {
+                        const TYPE_OPTIONS: &[FilterType] = &[
+                            FilterType::LowPass,
+                            FilterType::HighPass,
+                            FilterType::BandPass,
+                            FilterType::Notch,
+                        ];
+
+                        for filter_type in TYPE_OPTIONS {
+                            if ui
+                                .selectable_value(
+                                    &mut config.filter_type,
+                                    *filter_type,
+                                    filter_type.label(),
+                                )
+                                .clicked()
+                            {
+                                filter_bridge.set_filter_type(*filter_type);
+                            }
+                        }
+                    });
+                ui.end_row();
+
+                ui.label("Cutoff");
+                if ui
+                    .add(ModulationInput::new(
+                        &mut config.cutoff,
+                        bridge,
+                        Input::Cutoff,
+                        module_id,
+                    ))
+                    .changed()
+                {
+                    filter_bridge.set_param(Input::Cutoff, config.cutoff);
+                }
+                ui.end_row();
+
+                ui.label("Keytracking");
+                if ui
+                    .add(Slider::new(&mut config.keytracking, 0.0..=1.0).fixed_decimals(2))
+                    .changed()
+                {
+                    filter_bridge.set_keytracking(config.keytracking);
+                }
+                ui.end_row();
+
+                ui.label("Resonance");
+                if ui
+                    .add(ModulationInput::new(
+                        &mut config.q,
+                        bridge,
+                        Input::Q,
+                        module_id,
+                    ))
+                    .changed()
+                {
+                    filter_bridge.set_param(Input::Q, config.q);
+                }
+                ui.end_row();
+
+                ui.label("Drive");
+                if ui
+                    .add(ModulationInput::new(
+                        &mut config.drive,
+                        bridge,
+                        Input::Drive,
+                        module_id,
+                    ))
+                    .changed()
+                {
+                    filter_bridge.set_param(Input::Drive, config.drive);
+                }
+                ui.end_row();
+            });
+
+        ui.add_space(40.0);
+
+        if confirm_module_removal(ui, &mut self.remove_confirmation) {
+            bridge.remove_module(module_id);
+        }
+    }
+}
+
+impl ModuleUi for FilterUi {
+    fn module_id(&self) -> Option<ModuleId> {
+        Some(self.module_id)
+    }
+
+    fn ui(&mut self, bridge: &mut UiBridge, ui: &mut Ui) {
+        bridge.with_module_bridge(self.module_id, |bridge, module_bridge| {
+            if let ModuleBridge::Filter(filter_bridge) = module_bridge {
+                self.paint_ui(bridge, filter_bridge, ui);
+            }
+        });
+    }
+}
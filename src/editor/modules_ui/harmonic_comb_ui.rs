@@ -0,0 +1,125 @@
+use egui::{Grid, Ui};
+
+use crate::{
+    editor::{
+        ModuleUi, modulation_input::ModulationInput, module_label::ModuleLabel,
+        utils::confirm_module_removal,
+    },
+    synth_engine::{
+        Input, ModuleId,
+        harmonic_comb::HarmonicCombUiBridge,
+        ui_bridge::{ModuleBridge, UiBridge},
+    },
+};
+
+pub struct HarmonicCombUi {
+    module_id: ModuleId,
+    remove_confirmation: bool,
+    label_state: Option<String>,
+}
+
+impl HarmonicCombUi {
+    pub fn new(module_id: ModuleId) -> Self {
+        Self {
+            module_id,
+            remove_confirmation: false,
+            label_state: None,
+        }
+    }
+
+    fn paint_ui(
+        &mut self,
+        bridge: &mut UiBridge,
+        comb_bridge: &mut HarmonicCombUiBridge,
+        ui: &mut Ui,
+    ) {
+        let module_id = self.module_id;
+        let mut config = comb_bridge.config().clone();
+
+        ui.add(ModuleLabel::new(&mut self.label_state, bridge, module_id));
+
+        ui.add_space(20.0);
+
+        Grid::new("harmonic_comb_grid")
+            .num_columns(2)
+            .spacing([40.0, 24.0])
+            .striped(true)
+            .show(ui, |ui| {
+                ui.label("Partials");
+                if ui
+                    .add(ModulationInput::new(
+                        &mut config.partials,
+                        bridge,
+                        Input::Partials,
+                        module_id,
+                    ))
+                    .changed()
+                {
+                    comb_bridge.set_param(Input::Partials, config.partials);
+                }
+                ui.end_row();
+
+                ui.label("Rolloff");
+                if ui
+                    .add(ModulationInput::new(
+                        &mut config.rolloff,
+                        bridge,
+                        Input::Rolloff,
+                        module_id,
+                    ))
+                    .changed()
+                {
+                    comb_bridge.set_param(Input::Rolloff, config.rolloff);
+                }
+                ui.end_row();
+
+                ui.label("Comb Spacing");
+                if ui
+                    .add(ModulationInput::new(
+                        &mut config.comb_spacing,
+                        bridge,
+                        Input::CombSpacing,
+                        module_id,
+                    ))
+                    .changed()
+                {
+                    comb_bridge.set_param(Input::CombSpacing, config.comb_spacing);
+                }
+                ui.end_row();
+
+                ui.label("Even/Odd");
+                if ui
+                    .add(ModulationInput::new(
+                        &mut config.even_odd,
+                        bridge,
+                        Input::EvenOdd,
+                        module_id,
+                    ))
+                    .changed()
+                {
+                    comb_bridge.set_param(Input::EvenOdd, config.even_odd);
+                }
+                ui.end_row();
+            });
+
+        ui.add_space(40.0);
+
+        if confirm_module_removal(ui, &mut self.remove_confirmation) {
+            bridge.remove_module(module_id);
+        }
+    }
+}
+
+impl ModuleUi for HarmonicCombUi {
+    fn module_id(&self) -> Option<ModuleId> {
+        Some(self.module_id)
+    }
+
+    fn ui(&mut self, bridge: &mut UiBridge, ui: &mut Ui) {
+        bridge.with_module_bridge(self.module_id, |bridge, module_bridge| {
+            if let ModuleBridge::HarmonicComb(comb_bridge) = module_bridge {
+                self.paint_ui(bridge, comb_bridge, ui);
+            }
+        });
+    }
+}
@@ -1,17 +1,20 @@
 use egui::{
-    Checkbox, ComboBox, DragValue, Frame, Grid, Id, Margin, Modal, Panel, ScrollArea, Sides, Ui,
-    Vec2, style::ScrollStyle,
+    Button, Checkbox, Color32, ComboBox, DragValue, Frame, Grid, Id, Margin, Modal, Panel, Pos2,
+    Rect, RichText, ScrollArea, Sense, Sides, Stroke, Ui, Vec2, style::ScrollStyle,
 };
 use nih_plug::util::db_to_gain;
+use rfd::FileDialog;
 
 use crate::{
     editor::{
-        ModuleUi, gain_slider::GainSlider, module_label::ModuleLabel, stereo_slider::StereoSlider,
-        utils::confirm_module_removal,
+        ModuleUi, gain_slider::GainSlider, modulation_input::ModulationInput,
+        module_label::ModuleLabel, stereo_slider::StereoSlider, utils::confirm_module_removal,
     },
     synth_engine::{
-        ModuleId, SPECTRAL_BUFFER_SIZE, StereoSample,
-        harmonic_editor::{FilterParams, FilterType, HarmonicEditorUiBridge, SetAction, SetParams},
+        Input, ModuleId, SPECTRAL_BUFFER_SIZE, Sample, StereoSample,
+        harmonic_editor::{
+            FilterParams, FilterType, HarmonicEditorUiBridge, SetAction, SetParams, SnapshotSlot,
+        },
         ui_bridge::{ModuleBridge, UiBridge},
     },
     utils::NthElement,
@@ -40,7 +43,19 @@ impl FilterType {
     }
 }
 
+impl SnapshotSlot {
+    fn label(&self) -> &'static str {
+        match self {
+            Self::A => "A",
+            Self::B => "B",
+        }
+    }
+}
+
+static SNAPSHOT_SLOTS: &[SnapshotSlot] = &[SnapshotSlot::A, SnapshotSlot::B];
+
 struct SelectAndSetState {
+    max_harmonic: usize,
     from: usize,
     to: usize,
     n_th_element: bool,
@@ -51,11 +66,12 @@ struct SelectAndSetState {
     volume: StereoSample,
 }
 
-impl Default for SelectAndSetState {
-    fn default() -> Self {
+impl SelectAndSetState {
+    fn new(max_harmonic: usize) -> Self {
         Self {
+            max_harmonic,
             from: 1,
-            to: NUM_EDITABLE_HARMONICS,
+            to: max_harmonic,
             n_th_element: false,
             n_th_mul: 2,
             n_th_add: 1,
@@ -86,22 +102,149 @@ impl Default for ApplyFilterState {
     }
 }
 
+#[derive(Clone, Copy)]
+struct DrawPoint {
+    harmonic: usize,
+    level: Sample, // Normalized slider position
+}
+
+#[derive(Clone, Copy)]
+struct DrawState {
+    start: DrawPoint,
+    last: DrawPoint,
+    ramp: bool,
+}
+
 pub struct HarmonicEditorUI {
     module_id: ModuleId,
+    harmonics: usize,
     remove_confirmation: bool,
     label_state: Option<String>,
     select_and_set_state: Option<Box<SelectAndSetState>>,
     apply_filter_state: Option<Box<ApplyFilterState>>,
+    draw_mode: bool,
+    draw_state: Option<DrawState>,
+    show_phases: bool,
+    import_error: Option<String>,
 }
 
 impl HarmonicEditorUI {
     pub fn new(module_id: ModuleId) -> Self {
         Self {
             module_id,
+            harmonics: NUM_EDITABLE_HARMONICS,
             remove_confirmation: false,
             label_state: None,
             select_and_set_state: None,
             apply_filter_state: None,
+            draw_mode: false,
+            draw_state: None,
+            show_phases: false,
+            import_error: None,
+        }
+    }
+
+    fn draw_point(slider_rects: &[Rect], pos: Pos2) -> DrawPoint {
+        let idx = slider_rects
+            .partition_point(|rect| rect.right() < pos.x)
+            .min(slider_rects.len() - 1);
+        let rect = slider_rects[idx];
+
+        DrawPoint {
+            harmonic: idx + 1,
+            level: (1.0 - (pos.y - rect.top()) / rect.height()).clamp(0.0, 1.0),
+        }
+    }
+
+    fn draw_line(bridge: &mut HarmonicEditorUiBridge, from: DrawPoint, to: DrawPoint) {
+        let (from, to) = if from.harmonic <= to.harmonic {
+            (from, to)
+        } else {
+            (to, from)
+        };
+
+        let mut probe = StereoSample::ZERO;
+        let slider = GainSlider::new(&mut probe);
+        let steps = (to.harmonic - from.harmonic).max(1) as Sample;
+
+        let gains: Vec<StereoSample> = (from.harmonic..=to.harmonic)
+            .map(|harmonic| {
+                let t = (harmonic - from.harmonic) as Sample / steps;
+                let level = (to.level - from.level).mul_add(t, from.level);
+
+                StereoSample::splat(slider.normalized_to_gain(level))
+            })
+            .collect();
+
+        bridge.set_harmonics_range(from.harmonic, &gains);
+    }
+
+    fn handle_drawing(
+        &mut self,
+        bridge: &mut HarmonicEditorUiBridge,
+        ui: &mut Ui,
+        slider_rects: &[Rect],
+    ) {
+        let Some(strip_rect) = slider_rects
+            .iter()
+            .copied()
+            .reduce(|acc, rect| acc.union(rect))
+        else {
+            return;
+        };
+
+        let response = ui.interact(strip_rect, ui.id().with("draw-harmonics"), Sense::drag());
+
+        if !(response.drag_started() || response.dragged() || response.drag_stopped()) {
+            self.draw_state = None;
+            return;
+        }
+
+        let Some(pos) = response
+            .interact_pointer_pos()
+            .or_else(|| ui.input(|state| state.pointer.latest_pos()))
+        else {
+            return;
+        };
+
+        let point = Self::draw_point(slider_rects, pos);
+
+        if response.drag_started() {
+            let ramp = ui.input(|state| state.modifiers.shift);
+
+            self.draw_state = Some(DrawState {
+                start: point,
+                last: point,
+                ramp,
+            });
+
+            if !ramp {
+                Self::draw_line(bridge, point, point);
+            }
+        }
+
+        let Some(state) = self.draw_state.as_mut() else {
+            return;
+        };
+
+        if response.drag_stopped() {
+            if state.ramp {
+                Self::draw_line(bridge, state.start, point);
+            }
+
+            self.draw_state = None;
+        } else if state.ramp {
+            let start_rect = slider_rects[state.start.harmonic - 1];
+            let start_pos = Pos2::new(
+                start_rect.center().x,
+                start_rect.bottom() - state.start.level * start_rect.height(),
+            );
+
+            ui.painter()
+                .line_segment([start_pos, pos], Stroke::new(1.5, Color32::WHITE));
+        } else if response.dragged() {
+            Self::draw_line(bridge, state.last, point);
+            state.last = point;
         }
     }
 
@@ -144,9 +287,9 @@ impl HarmonicEditorUI {
                 .show(ui, |ui| {
                     ui.label("Harmonics");
                     ui.horizontal(|ui| {
-                        ui.add(DragValue::new(&mut state.from).range(1..=NUM_EDITABLE_HARMONICS));
+                        ui.add(DragValue::new(&mut state.from).range(1..=state.max_harmonic));
                         ui.label(" — ");
-                        ui.add(DragValue::new(&mut state.to).range(1..=NUM_EDITABLE_HARMONICS));
+                        ui.add(DragValue::new(&mut state.to).range(1..=state.max_harmonic));
                     });
                     ui.end_row();
 
@@ -334,6 +477,56 @@ impl HarmonicEditorUI {
         !modal.should_close()
     }
 
+    fn show_levels_strip(&mut self, bridge: &mut HarmonicEditorUiBridge, ui: &mut Ui) {
+        let mut harmonics = bridge.harmonics();
+        let height = ui.available_height();
+        let mut slider_rects = Vec::with_capacity(harmonics.len());
+
+        for (idx, harmonic) in harmonics
+            .iter_mut()
+            .enumerate()
+            .skip(1)
+            .take(self.harmonics)
+        {
+            let response = ui.add(
+                GainSlider::new(harmonic)
+                    .label(&format!("{}", idx))
+                    .height(height),
+            );
+
+            if response.changed() && !self.draw_mode {
+                bridge.set_harmonic(idx, *harmonic);
+            }
+
+            slider_rects.push(response.rect);
+        }
+
+        if self.draw_mode {
+            self.handle_drawing(bridge, ui, &slider_rects);
+        }
+    }
+
+    fn show_phases_strip(bridge: &mut HarmonicEditorUiBridge, ui: &mut Ui, harmonics: usize) {
+        let mut phases = bridge.phases();
+        let height = ui.available_height();
+
+        for (idx, phase) in phases.iter_mut().enumerate().skip(1).take(harmonics) {
+            if ui
+                .add(
+                    StereoSlider::new(phase)
+                        .vertical()
+                        .thickness(12.0)
+                        .length(height)
+                        .precision(2)
+                        .default_value(0.0),
+                )
+                .changed()
+            {
+                bridge.set_phase(idx, *phase);
+            }
+        }
+    }
+
     fn paint_ui(
         &mut self,
         bridge: &mut UiBridge,
@@ -343,6 +536,9 @@ impl HarmonicEditorUI {
         let module_id = self.module_id;
         ui.style_mut().spacing.scroll = ScrollStyle::solid();
 
+        // Only the harmonics the oscillators play are shown
+        self.harmonics = bridge.engine_params().spectral_size.harmonics();
+
         Panel::top("harmonics-list")
             .resizable(true)
             .size_range(150.0..=400.0)
@@ -356,24 +552,14 @@ impl HarmonicEditorUI {
             .show_inside(ui, |ui| {
                 ScrollArea::horizontal().show(ui, |ui| {
                     ui.horizontal_top(|ui| {
-                        let mut harmonics = editor_bridge.harmonics();
-                        let height = ui.available_height();
-
                         ui.style_mut().spacing.item_spacing = Vec2::splat(2.0);
                         ui.style_mut().interaction.tooltip_delay = 0.1;
                         ui.style_mut().interaction.show_tooltips_only_when_still = false;
 
-                        for (idx, harmonic) in harmonics.iter_mut().enumerate().skip(1) {
-                            if ui
-                                .add(
-                                    GainSlider::new(harmonic)
-                                        .label(&format!("{}", idx))
-                                        .height(height),
-                                )
-                                .changed()
-                            {
-                                editor_bridge.set_harmonic(idx, *harmonic);
-                            }
+                        if self.show_phases {
+                            Self::show_phases_strip(editor_bridge, ui, self.harmonics);
+                        } else {
+                            self.show_levels_strip(editor_bridge, ui);
                         }
                     });
                 });
@@ -427,12 +613,85 @@ impl HarmonicEditorUI {
 
         ui.horizontal(|ui| {
             if ui.button("Select and Set").clicked() {
-                self.select_and_set_state = Some(Box::new(SelectAndSetState::default()));
+                self.select_and_set_state = Some(Box::new(SelectAndSetState::new(self.harmonics)));
             }
 
             if ui.button("Apply Filter").clicked() {
                 self.apply_filter_state = Some(Box::new(ApplyFilterState::default()));
             }
+
+            ui.toggle_value(&mut self.draw_mode, "Draw").on_hover_text(
+                "Drag across the harmonics to draw levels, hold Shift to draw a linear ramp",
+            );
+
+            if ui
+                .button("Import Waveform")
+                .on_hover_text("Load a single cycle WAV file, longer files are scanned for a cycle")
+                .clicked()
+                && let Some(path) = FileDialog::new().add_filter("WAV", &["wav"]).pick_file()
+            {
+                self.import_error = editor_bridge.import_waveform(&path).err();
+            }
+
+            if let Some(error) = &self.import_error {
+                ui.label(RichText::new(error).color(Color32::RED));
+            }
+        });
+
+        ui.horizontal(|ui| {
+            ui.toggle_value(&mut self.show_phases, "Phases");
+
+            if ui.button("Randomize Phases").clicked() {
+                editor_bridge.randomize_phases();
+            }
+
+            if ui.button("Zero Phases").clicked() {
+                editor_bridge.zero_phases();
+            }
+        });
+
+        ui.horizontal(|ui| {
+            for slot in SNAPSHOT_SLOTS {
+                if ui.button(format!("Store {}", slot.label())).clicked() {
+                    editor_bridge.store_snapshot(*slot);
+                }
+
+                if ui
+                    .add_enabled(
+                        editor_bridge.has_snapshot(*slot),
+                        Button::new(format!("Recall {}", slot.label())),
+                    )
+                    .clicked()
+                {
+                    editor_bridge.recall_snapshot(*slot);
+                }
+            }
+        });
+
+        ui.horizontal(|ui| {
+            let mut morph = editor_bridge.config().morph;
+            let mut blend = editor_bridge.config().blend;
+
+            if ui
+                .toggle_value(&mut morph, "Morph")
+                .on_hover_text("Output a blend of snapshots A and B instead of the edited spectrum")
+                .changed()
+            {
+                editor_bridge.set_morph(morph);
+            }
+
+            ui.label("Blend");
+            if ui
+                .add(ModulationInput::new(
+                    &mut blend,
+                    bridge,
+                    Input::Blend,
+                    module_id,
+                ))
+                .changed()
+            {
+                editor_bridge.set_param(Input::Blend, blend);
+            }
         });
 
         if let Some(mut state) = self.select_and_set_state.take()
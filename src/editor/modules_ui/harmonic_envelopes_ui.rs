@@ -0,0 +1,133 @@
+use egui::{Grid, Slider, Ui};
+
+use crate::{
+    editor::{
+        ModuleUi, direct_input::DirectInput, modulation_input::ModulationInput,
+        module_label::ModuleLabel, utils::confirm_module_removal,
+    },
+    synth_engine::{
+        Input, ModuleId,
+        harmonic_envelopes::{HarmonicEnvelopesUiBridge, MAX_BANDS},
+        ui_bridge::{ModuleBridge, UiBridge},
+    },
+};
+
+pub struct HarmonicEnvelopesUi {
+    module_id: ModuleId,
+    remove_confirmation: bool,
+    label_state: Option<String>,
+}
+
+impl HarmonicEnvelopesUi {
+    pub fn new(module_id: ModuleId) -> Self {
+        Self {
+            module_id,
+            remove_confirmation: false,
+            label_state: None,
+        }
+    }
+
+    fn paint_ui(
+        &mut self,
+        bridge: &mut UiBridge,
+        envelopes_bridge: &mut HarmonicEnvelopesUiBridge,
+        ui: &mut Ui,
+    ) {
+        let module_id = self.module_id;
+        let mut config = envelopes_bridge.config().clone();
+
+        ui.add(ModuleLabel::new(&mut self.label_state, bridge, module_id));
+
+        ui.add_space(20.0);
+
+        Grid::new("harmonic_envelopes_grid")
+            .num_columns(2)
+            .spacing([40.0, 24.0])
+            .striped(true)
+            .show(ui, |ui| {
+                ui.label("Input");
+                ui.add(DirectInput::new(bridge, Input::Spectrum, module_id));
+                ui.end_row();
+
+                ui.label("Bands");
+                if ui
+                    .add(Slider::new(&mut config.bands, 1..=MAX_BANDS))
+                    .changed()
+                {
+                    envelopes_bridge.set_bands(config.bands);
+                }
+                ui.end_row();
+
+                ui.label("Attack");
+                if ui
+                    .add(ModulationInput::new(
+                        &mut config.attack,
+                        bridge,
+                        Input::Attack,
+                        module_id,
+                    ))
+                    .changed()
+                {
+                    envelopes_bridge.set_param(Input::Attack, config.attack);
+                }
+                ui.end_row();
+
+                ui.label("Decay");
+                if ui
+                    .add(ModulationInput::new(
+                        &mut config.decay,
+                        bridge,
+                        Input::Decay,
+                        module_id,
+                    ))
+                    .changed()
+                {
+                    envelopes_bridge.set_param(Input::Decay, config.decay);
+                }
+                ui.end_row();
+
+                ui.label("Decay Spread");
+                if ui
+                    .add(ModulationInput::new(
+                        &mut config.decay_spread,
+                        bridge,
+                        Input::DecaySpread,
+                        module_id,
+                    ))
+                    .changed()
+                {
+                    envelopes_bridge.set_param(Input::DecaySpread, config.decay_spread);
+                }
+                ui.end_row();
+
+                ui.label("Keytracking");
+                if ui
+                    .add(Slider::new(&mut config.keytracking, 0.0..=1.0).fixed_decimals(2))
+                    .changed()
+                {
+                    envelopes_bridge.set_keytracking(config.keytracking);
+                }
+                ui.end_row();
+            });
+
+        ui.add_space(40.0);
+
+        if confirm_module_removal(ui, &mut self.remove_confirmation) {
+            bridge.remove_module(module_id);
+        }
+    }
+}
+
+impl ModuleUi for HarmonicEnvelopesUi {
+    fn module_id(&self) -> Option<ModuleId> {
+        Some(self.module_id)
+    }
+
+    fn ui(&mut self, bridge: &mut UiBridge, ui: &mut Ui) {
+        bridge.with_module_bridge(self.module_id, |bridge, module_bridge| {
+            if let ModuleBridge::HarmonicEnvelopes(envelopes_bridge) = module_bridge {
+                self.paint_ui(bridge, envelopes_bridge, ui);
+            }
+        });
+    }
+}
@@ -0,0 +1,114 @@
+use egui::{ComboBox, Grid, Ui};
+
+use crate::{
+    editor::{
+        ModuleUi, direct_input::DirectInput, modulation_input::ModulationInput,
+        module_label::ModuleLabel, utils::confirm_module_removal,
+    },
+    synth_engine::{
+        HarmonicShiftMode, Input, ModuleId,
+        harmonic_shift::HarmonicShiftUiBridge,
+        ui_bridge::{ModuleBridge, UiBridge},
+    },
+};
+
+impl HarmonicShiftMode {
+    fn label(&self) -> &'static str {
+        match self {
+            Self::Shift => "Shift",
+            Self::SwapOddEven => "Swap Odd/Even",
+        }
+    }
+}
+
+pub struct HarmonicShiftUi {
+    module_id: ModuleId,
+    remove_confirmation: bool,
+    label_state: Option<String>,
+}
+
+impl HarmonicShiftUi {
+    pub fn new(module_id: ModuleId) -> Self {
+        Self {
+            module_id,
+            remove_confirmation: false,
+            label_state: None,
+        }
+    }
+
+    fn paint_ui(
+        &mut self,
+        bridge: &mut UiBridge,
+        shift_bridge: &mut HarmonicShiftUiBridge,
+        ui: &mut Ui,
+    ) {
+        let module_id = self.module_id;
+        let mut config = shift_bridge.config().clone();
+
+        ui.add(ModuleLabel::new(&mut self.label_state, bridge, module_id));
+
+        ui.add_space(20.0);
+
+        Grid::new("harmonic_shift_grid")
+            .num_columns(2)
+            .spacing([40.0, 24.0])
+            .striped(true)
+            .show(ui, |ui| {
+                ui.label("Input");
+                ui.add(DirectInput::new(bridge, Input::Spectrum, module_id));
+                ui.end_row();
+
+                ui.label("Mode");
+                ComboBox::from_id_salt("harmonic-shift-mode")
+                    .selected_text(config.mode.label())
+                    .show_ui(ui, |ui| {
+                        const MODE_OPTIONS: &[HarmonicShiftMode] =
+                            &[HarmonicShiftMode::Shift, HarmonicShiftMode::SwapOddEven];
+
+                        for mode in MODE_OPTIONS {
+                            if ui
+                                .selectable_value(&mut config.mode, *mode, mode.label())
+                                .clicked()
+                            {
+                                shift_bridge.set_mode(*mode);
+                            }
+                        }
+                    });
+                ui.end_row();
+
+                ui.label("Shift");
+                if ui
+                    .add(ModulationInput::new(
+                        &mut config.shift,
+                        bridge,
+                        Input::HarmonicShift,
+                        module_id,
+                    ))
+                    .changed()
+                {
+                    shift_bridge.set_param(Input::HarmonicShift, config.shift);
+                }
+                ui.end_row();
+            });
+
+        ui.add_space(40.0);
+
+        if confirm_module_removal(ui, &mut self.remove_confirmation) {
+            bridge.remove_module(module_id);
+        }
+    }
+}
+
+impl ModuleUi for HarmonicShiftUi {
+    fn module_id(&self) -> Option<ModuleId> {
+        Some(self.module_id)
+    }
+
+    fn ui(&mut self, bridge: &mut UiBridge, ui: &mut Ui) {
+        bridge.with_module_bridge(self.module_id, |bridge, module_bridge| {
+            if let ModuleBridge::HarmonicShift(shift_bridge) = module_bridge {
+                self.paint_ui(bridge, shift_bridge, ui);
+            }
+        });
+    }
+}
@@ -0,0 +1,87 @@
+use egui::{Grid, Ui};
+
+use crate::{
+    editor::{
+        ModuleUi, direct_input::DirectInput, modulation_input::ModulationInput,
+        module_label::ModuleLabel, utils::confirm_module_removal,
+    },
+    synth_engine::{
+        Input, ModuleId,
+        harmonic_stretch::HarmonicStretchUiBridge,
+        ui_bridge::{ModuleBridge, UiBridge},
+    },
+};
+
+pub struct HarmonicStretchUi {
+    module_id: ModuleId,
+    remove_confirmation: bool,
+    label_state: Option<String>,
+}
+
+impl HarmonicStretchUi {
+    pub fn new(module_id: ModuleId) -> Self {
+        Self {
+            module_id,
+            remove_confirmation: false,
+            label_state: None,
+        }
+    }
+
+    fn paint_ui(
+        &mut self,
+        bridge: &mut UiBridge,
+        stretch_bridge: &mut HarmonicStretchUiBridge,
+        ui: &mut Ui,
+    ) {
+        let module_id = self.module_id;
+        let mut config = stretch_bridge.config().clone();
+
+        ui.add(ModuleLabel::new(&mut self.label_state, bridge, module_id));
+
+        ui.add_space(20.0);
+
+        Grid::new("harmonic_stretch_grid")
+            .num_columns(2)
+            .spacing([40.0, 24.0])
+            .striped(true)
+            .show(ui, |ui| {
+                ui.label("Input");
+                ui.add(DirectInput::new(bridge, Input::Spectrum, module_id));
+                ui.end_row();
+
+                ui.label("Stretch");
+                if ui
+                    .add(ModulationInput::new(
+                        &mut config.stretch,
+                        bridge,
+                        Input::Stretch,
+                        module_id,
+                    ))
+                    .changed()
+                {
+                    stretch_bridge.set_param(Input::Stretch, config.stretch);
+                }
+                ui.end_row();
+            });
+
+        ui.add_space(40.0);
+
+        if confirm_module_removal(ui, &mut self.remove_confirmation) {
+            bridge.remove_module(module_id);
+        }
+    }
+}
+
+impl ModuleUi for HarmonicStretchUi {
+    fn module_id(&self) -> Option<ModuleId> {
+        Some(self.module_id)
+    }
+
+    fn ui(&mut self, bridge: &mut UiBridge, ui: &mut Ui) {
+        bridge.with_module_bridge(self.module_id, |bridge, module_bridge| {
+            if let ModuleBridge::HarmonicStretch(stretch_bridge) = module_bridge {
+                self.paint_ui(bridge, stretch_bridge, ui);
+            }
+        });
+    }
+}
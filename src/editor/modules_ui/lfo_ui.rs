@@ -109,10 +109,39 @@ impl LfoUi {
                 }
                 ui.end_row();
 
+                ui.label("Delay");
+                if ui
+                    .add(ModulationInput::new(
+                        &mut config.delay,
+                        bridge,
+                        Input::Delay,
+                        module_id,
+                    ))
+                    .changed()
+                {
+                    lfo_bridge.set_param(Input::Delay, config.delay);
+                }
+                ui.end_row();
+
+                ui.label("Fade In");
+                if ui
+                    .add(ModulationInput::new(
+                        &mut config.fade_in,
+                        bridge,
+                        Input::FadeIn,
+                        module_id,
+                    ))
+                    .changed()
+                {
+                    lfo_bridge.set_param(Input::FadeIn, config.fade_in);
+                }
+                ui.end_row();
+
                 ui.label("Smooth");
                 if ui
                     .add(
                         StereoSlider::new(&mut config.smooth_time)
+                            .channels_link(bridge, module_id, "Smooth")
                             .range(0.0..=0.1)
                             .display_scale(1000.0)
                             .default_value(0.0)
@@ -135,9 +164,24 @@ impl LfoUi {
                 }
                 ui.end_row();
 
+                ui.label("Global");
+                if ui
+                    .add(Checkbox::without_text(&mut config.global))
+                    .on_hover_text(
+                        "One free-running phase for all voices, frequency modulation is ignored",
+                    )
+                    .changed()
+                {
+                    lfo_bridge.set_global(config.global);
+                }
+                ui.end_row();
+
                 ui.label("Steal phase");
                 if ui
-                    .add(Checkbox::without_text(&mut config.steal_phase))
+                    .add_enabled(
+                        !config.global,
+                        Checkbox::without_text(&mut config.steal_phase),
+                    )
                     .changed()
                 {
                     lfo_bridge.set_steal_phase(config.steal_phase);
@@ -0,0 +1,235 @@
+use egui::{ComboBox, DragValue, Grid, Slider, Ui};
+
+use crate::{
+    editor::{
+        ModuleUi, modulation_input::ModulationInput, module_label::ModuleLabel,
+        utils::confirm_module_removal,
+    },
+    synth_engine::{
+        Input, MathOperation, ModuleId, QuantizeGrid,
+        math::{MAX_QUANTIZE_STEPS, MathUiBridge},
+        ui_bridge::{ModuleBridge, UiBridge},
+    },
+    utils::from_ms,
+};
+
+impl MathOperation {
+    fn label(&self) -> &'static str {
+        match self {
+            Self::Add => "A + B",
+            Self::Multiply => "A × B",
+            Self::Min => "Min",
+            Self::Max => "Max",
+            Self::Crossfade => "Crossfade",
+            Self::Remap => "Remap A",
+            Self::Slew => "Slew A",
+            Self::Quantize => "Quantize A",
+        }
+    }
+}
+
+impl QuantizeGrid {
+    fn label(&self) -> &'static str {
+        match self {
+            Self::Steps => "Steps",
+            Self::Semitones => "Semitones",
+        }
+    }
+}
+
+static OPERATION_OPTIONS: &[MathOperation] = &[
+    MathOperation::Add,
+    MathOperation::Multiply,
+    MathOperation::Min,
+    MathOperation::Max,
+    MathOperation::Crossfade,
+    MathOperation::Remap,
+    MathOperation::Slew,
+    MathOperation::Quantize,
+];
+
+static GRID_OPTIONS: &[QuantizeGrid] = &[QuantizeGrid::Steps, QuantizeGrid::Semitones];
+
+pub struct MathUi {
+    module_id: ModuleId,
+    remove_confirmation: bool,
+    label_state: Option<String>,
+}
+
+impl MathUi {
+    pub fn new(module_id: ModuleId) -> Self {
+        Self {
+            module_id,
+            remove_confirmation: false,
+            label_state: None,
+        }
+    }
+
+    fn paint_ui(&mut self, bridge: &mut UiBridge, math_bridge: &mut MathUiBridge, ui: &mut Ui) {
+        let module_id = self.module_id;
+        let mut config = math_bridge.config().clone();
+        let mut slew_rise_ms = config.slew_rise * 1000.0;
+        let mut slew_fall_ms = config.slew_fall * 1000.0;
+
+        ui.add(ModuleLabel::new(&mut self.label_state, bridge, module_id));
+
+        ui.add_space(20.0);
+
+        Grid::new("math_grid")
+            .num_columns(2)
+            .spacing([40.0, 24.0])
+            .striped(true)
+            .show(ui, |ui| {
+                ui.label("Operation");
+                ComboBox::from_id_salt("math-operation-select")
+                    .selected_text(config.operation.label())
+                    .show_ui(ui, |ui| {
+                        for operation in OPERATION_OPTIONS {
+                            if ui
+                                .selectable_label(config.operation == *operation, operation.label())
+                                .clicked()
+                            {
+                                math_bridge.set_operation(*operation);
+                            }
+                        }
+                    });
+                ui.end_row();
+
+                ui.label("A");
+                if ui
+                    .add(ModulationInput::new(
+                        &mut config.value_a,
+                        bridge,
+                        Input::ValueA,
+                        module_id,
+                    ))
+                    .changed()
+                {
+                    math_bridge.set_param(Input::ValueA, config.value_a);
+                }
+                ui.end_row();
+
+                match config.operation {
+                    MathOperation::Remap => {
+                        ui.label("Range");
+                        ui.horizontal(|ui| {
+                            let min = ui.add(DragValue::new(&mut config.range_min).speed(0.01));
+                            ui.label("to");
+                            let max = ui.add(DragValue::new(&mut config.range_max).speed(0.01));
+
+                            if min.changed() || max.changed() {
+                                math_bridge.set_range(config.range_min, config.range_max);
+                            }
+                        });
+                        ui.end_row();
+                    }
+                    MathOperation::Slew => {
+                        ui.label("Rise");
+                        let rise = ui
+                            .add(
+                                Slider::new(&mut slew_rise_ms, 0.0..=2000.0)
+                                    .logarithmic(true)
+                                    .suffix(" ms"),
+                            )
+                            .on_hover_text("Time to rise by 1.0");
+                        ui.end_row();
+
+                        ui.label("Fall");
+                        let fall = ui
+                            .add(
+                                Slider::new(&mut slew_fall_ms, 0.0..=2000.0)
+                                    .logarithmic(true)
+                                    .suffix(" ms"),
+                            )
+                            .on_hover_text("Time to fall by 1.0");
+                        ui.end_row();
+
+                        if rise.changed() || fall.changed() {
+                            math_bridge.set_slew(from_ms(slew_rise_ms), from_ms(slew_fall_ms));
+                        }
+                    }
+                    MathOperation::Quantize => {
+                        ui.label("Grid");
+                        ComboBox::from_id_salt("math-quantize-grid-select")
+                            .selected_text(config.quantize_grid.label())
+                            .show_ui(ui, |ui| {
+                                for grid in GRID_OPTIONS {
+                                    if ui
+                                        .selectable_label(
+                                            config.quantize_grid == *grid,
+                                            grid.label(),
+                                        )
+                                        .clicked()
+                                    {
+                                        math_bridge.set_quantize(*grid, config.quantize_steps);
+                                    }
+                                }
+                            });
+                        ui.end_row();
+
+                        ui.label("Steps");
+                        if ui
+                            .add_enabled(
+                                config.quantize_grid == QuantizeGrid::Steps,
+                                Slider::new(&mut config.quantize_steps, 1..=MAX_QUANTIZE_STEPS),
+                            )
+                            .on_hover_text("Steps per 1.0, semitones snap pitch modulation")
+                            .changed()
+                        {
+                            math_bridge.set_quantize(config.quantize_grid, config.quantize_steps);
+                        }
+                        ui.end_row();
+                    }
+                    _ => {
+                        ui.label("B");
+                        if ui
+                            .add(ModulationInput::new(
+                                &mut config.value_b,
+                                bridge,
+                                Input::ValueB,
+                                module_id,
+                            ))
+                            .changed()
+                        {
+                            math_bridge.set_param(Input::ValueB, config.value_b);
+                        }
+                        ui.end_row();
+                    }
+                }
+
+                if config.operation == MathOperation::Crossfade {
+                    ui.label("Mix");
+                    if ui
+                        .add(
+                            ModulationInput::new(&mut config.mix, bridge, Input::Mix, module_id)
+                                .default(0.5),
+                        )
+                        .changed()
+                    {
+                        math_bridge.set_param(Input::Mix, config.mix);
+                    }
+                    ui.end_row();
+                }
+            });
+
+        ui.add_space(40.0);
+
+        if confirm_module_removal(ui, &mut self.remove_confirmation) {
+            bridge.remove_module(module_id);
+        }
+    }
+}
+
+impl ModuleUi for MathUi {
+    fn module_id(&self) -> Option<ModuleId> {
+        Some(self.module_id)
+    }
+
+    fn ui(&mut self, bridge: &mut UiBridge, ui: &mut Ui) {
+        bridge.with_module_bridge(self.module_id, |bridge, module_bridge| {
+            if let ModuleBridge::Math(math_bridge) = module_bridge {
+                self.paint_ui(bridge, math_bridge, ui);
+            }
+        });
+    }
+}
@@ -0,0 +1,129 @@
+use egui::{ComboBox, DragValue, Grid, Ui};
+
+use crate::{
+    editor::{
+        ModuleUi, module_label::ModuleLabel, stereo_slider::StereoSlider,
+        utils::confirm_module_removal,
+    },
+    synth_engine::{
+        MidiSource, ModuleId, StereoSample,
+        midi_control::MidiControlUiBridge,
+        ui_bridge::{ModuleBridge, UiBridge},
+    },
+};
+
+impl MidiSource {
+    fn label(&self) -> &'static str {
+        match self {
+            Self::PitchBend => "Pitch Bend",
+            Self::ModWheel => "Mod Wheel",
+            Self::Cc(_) => "CC",
+        }
+    }
+}
+
+pub struct MidiControlUi {
+    module_id: ModuleId,
+    remove_confirmation: bool,
+    label_state: Option<String>,
+}
+
+impl MidiControlUi {
+    pub fn new(module_id: ModuleId) -> Self {
+        Self {
+            module_id,
+            remove_confirmation: false,
+            label_state: None,
+        }
+    }
+
+    fn paint_ui(
+        &mut self,
+        bridge: &mut UiBridge,
+        control_bridge: &mut MidiControlUiBridge,
+        ui: &mut Ui,
+    ) {
+        let module_id = self.module_id;
+        let config = control_bridge.config().clone();
+
+        ui.add(ModuleLabel::new(&mut self.label_state, bridge, module_id));
+
+        ui.add_space(20.0);
+
+        Grid::new("midi-control-grid")
+            .num_columns(2)
+            .spacing([40.0, 24.0])
+            .striped(true)
+            .show(ui, |ui| {
+                ui.label("Source");
+                ComboBox::from_id_salt("midi-control-source")
+                    .selected_text(config.source.label())
+                    .show_ui(ui, |ui| {
+                        const SOURCE_OPTIONS: &[MidiSource] = &[
+                            MidiSource::PitchBend,
+                            MidiSource::ModWheel,
+                            MidiSource::Cc(2),
+                        ];
+
+                        for source in SOURCE_OPTIONS {
+                            let selected = std::mem::discriminant(&config.source)
+                                == std::mem::discriminant(source);
+
+                            if ui.selectable_label(selected, source.label()).clicked() && !selected
+                            {
+                                control_bridge.set_source(*source);
+                            }
+                        }
+                    });
+                ui.end_row();
+
+                if let MidiSource::Cc(mut cc) = config.source {
+                    ui.label("CC Number");
+                    if ui.add(DragValue::new(&mut cc).range(0..=127)).changed() {
+                        control_bridge.set_source(MidiSource::Cc(cc));
+                    }
+                    ui.end_row();
+                }
+
+                let mut smooth = StereoSample::splat(config.smooth);
+
+                ui.label("Smooth");
+                if ui
+                    .add(
+                        StereoSlider::new(&mut smooth)
+                            .channels_link(bridge, module_id, "Smooth")
+                            .range(0.0..=0.05)
+                            .display_scale(1000.0)
+                            .default_value(0.0)
+                            .skew(1.2)
+                            .precision(1)
+                            .units(" ms"),
+                    )
+                    .changed()
+                {
+                    control_bridge.set_smooth(smooth.left());
+                }
+                ui.end_row();
+            });
+
+        ui.add_space(40.0);
+
+        if confirm_module_removal(ui, &mut self.remove_confirmation) {
+            bridge.remove_module(module_id);
+        }
+    }
+}
+
+impl ModuleUi for MidiControlUi {
+    fn module_id(&self) -> Option<ModuleId> {
+        Some(self.module_id)
+    }
+
+    fn ui(&mut self, bridge: &mut UiBridge, ui: &mut Ui) {
+        bridge.with_module_bridge(self.module_id, |bridge, module_bridge| {
+            if let ModuleBridge::MidiControl(control_bridge) = module_bridge {
+                self.paint_ui(bridge, control_bridge, ui);
+            }
+        });
+    }
+}
@@ -1,18 +1,35 @@
-use egui::{Checkbox, DragValue, Grid, Id, Modal, Sides, Ui};
+use egui::{
+    Align2, Button, Checkbox, Color32, DragValue, FontId, Grid, Id, Modal, Painter, Rect, RichText,
+    Sense, Sides, Slider, Stroke, Ui, pos2, vec2,
+};
+use rfd::FileDialog;
 
 use crate::{
     editor::{
-        ModuleUi, db_slider::DbSlider, direct_input::DirectInput, gain_slider::GainSlider,
-        modulation_input::ModulationInput, module_label::ModuleLabel, stereo_slider::StereoSlider,
+        ModuleUi,
+        db_slider::DbSlider,
+        direct_input::DirectInput,
+        gain_slider::GainSlider,
+        modulation_input::ModulationInput,
+        module_label::ModuleLabel,
+        stereo_slider::StereoSlider,
         utils::confirm_module_removal,
+        waveform::{self, WAVEFORM_LEN},
     },
     synth_engine::{
-        Input, ModuleId, Sample, StereoSample,
-        oscillator::{self, OscillatorConfig, OscillatorUiBridge, PhasesDst},
+        Input, MAX_WAVETABLE_FRAMES, ModuleId, Sample, StereoSample,
+        oscillator::{self, OscillatorConfig, OscillatorUiBridge, PhasesDst, WavePreview},
         ui_bridge::{ModuleBridge, UiBridge},
     },
 };
 
+const PREVIEW_HEIGHT: f32 = 120.0;
+const PREVIEW_MIN_DB: Sample = -96.0;
+const BG_COLOR: Color32 = Color32::from_rgb(0x1d, 0x21, 0x25);
+const PLAYED_COLOR: Color32 = Color32::from_rgb(0xaa, 0x88, 0xff);
+const CUT_COLOR: Color32 = Color32::from_rgb(0x55, 0x55, 0x60);
+const CUTOFF_COLOR: Color32 = Color32::from_rgb(0xff, 0xaa, 0x66);
+
 struct GainShapeState {
     center: StereoSample,
     level: StereoSample, // dB
@@ -33,11 +50,31 @@ struct UnisonState {
     randomize_phase_state: Option<Box<RandomizePhaseState>>,
 }
 
+struct ExportWavetableState {
+    num_frames: usize,
+    duration: Sample,
+    error: String,
+    exporting: bool,
+}
+
+impl Default for ExportWavetableState {
+    fn default() -> Self {
+        Self {
+            num_frames: 64,
+            duration: 2.0,
+            error: String::new(),
+            exporting: false,
+        }
+    }
+}
+
 pub struct OscillatorUI {
     module_id: ModuleId,
     remove_confirmation: bool,
     label_state: Option<String>,
     unison_state: UnisonState,
+    export_wavetable_state: Option<Box<ExportWavetableState>>,
+    show_preview: bool,
 }
 
 impl OscillatorUI {
@@ -52,7 +89,89 @@ impl OscillatorUI {
                 gain_shape_state: None,
                 randomize_phase_state: None,
             },
+            export_wavetable_state: None,
+            show_preview: false,
+        }
+    }
+
+    // Log harmonic axis, dB relative to the loudest harmonic. Harmonics above the cutoff are
+    // dimmed, the voice doesn't play them.
+    fn paint_preview_spectrum(painter: &Painter, rect: Rect, preview: &WavePreview) {
+        let count = preview.magnitudes.len().max(2);
+        let max_db = preview
+            .magnitudes
+            .iter()
+            .copied()
+            .fold(PREVIEW_MIN_DB, Sample::max);
+        let harmonic_x = |harmonic: usize| {
+            rect.left() + rect.width() * (harmonic as Sample).ln() / (count as Sample).ln()
+        };
+
+        painter.rect_filled(rect, 0.0, BG_COLOR);
+
+        for (idx, db) in preview.magnitudes.iter().enumerate() {
+            let harmonic = idx + 1;
+            let level = ((db - max_db - PREVIEW_MIN_DB) / -PREVIEW_MIN_DB).clamp(0.0, 1.0);
+
+            if level <= 0.0 {
+                continue;
+            }
+
+            let color = if harmonic < preview.cutoff {
+                PLAYED_COLOR
+            } else {
+                CUT_COLOR
+            };
+
+            painter.vline(
+                harmonic_x(harmonic),
+                (rect.bottom() - rect.height() * level)..=rect.bottom(),
+                Stroke::new(1.0, color),
+            );
         }
+
+        painter.vline(
+            harmonic_x(preview.cutoff.max(1)),
+            rect.y_range(),
+            Stroke::new(1.0, CUTOFF_COLOR),
+        );
+        painter.text(
+            pos2(rect.right() - 4.0, rect.top() + 2.0),
+            Align2::RIGHT_TOP,
+            format!("{} harmonics", preview.cutoff.saturating_sub(1)),
+            FontId::proportional(10.0),
+            Color32::GRAY,
+        );
+    }
+
+    fn show_preview(ui: &mut Ui, preview: Option<&WavePreview>) {
+        let Some(preview) = preview else {
+            ui.label("Play a note to see the waveform.");
+            return;
+        };
+
+        let (rect, _) =
+            ui.allocate_exact_size(vec2(ui.available_width(), PREVIEW_HEIGHT), Sense::hover());
+
+        Self::paint_preview_spectrum(&ui.painter_at(rect), rect, preview);
+        ui.add_space(8.0);
+
+        // Peak normalized, quiet tables keep their shape visible
+        let peak = preview
+            .wave
+            .iter()
+            .fold(0.0, |peak: Sample, s| peak.max(s.abs()));
+        let scale = if peak > 0.0 { peak.recip() } else { 0.0 };
+        let len = preview.wave.len();
+        let mut wave = [0.0; WAVEFORM_LEN];
+
+        if len > 0 {
+            for (idx, sample) in wave.iter_mut().enumerate() {
+                *sample = preview.wave[idx * len / WAVEFORM_LEN] * scale;
+            }
+        }
+
+        waveform::draw_waveform(ui, &wave, vec2(ui.available_width(), PREVIEW_HEIGHT));
     }
 
     fn show_gain_shape_modal(
@@ -183,6 +302,100 @@ impl OscillatorUI {
         !modal.should_close()
     }
 
+    fn show_export_wavetable_modal(
+        module_id: ModuleId,
+        bridge: &mut UiBridge,
+        ui: &mut Ui,
+        state: &mut ExportWavetableState,
+    ) -> bool {
+        if state.exporting
+            && let Some(result) = bridge.finished_wavetable_export()
+        {
+            state.exporting = false;
+
+            match result {
+                Ok(()) => return false,
+                Err(err) => state.error = err,
+            }
+        }
+
+        let modal = Modal::new(Id::new("export_wavetable_modal")).show(ui.ctx(), |ui| {
+            ui.heading("Export wavetable");
+            ui.add_space(20.0);
+            ui.set_width(440.0);
+
+            Grid::new("export_wavetable-grid")
+                .num_columns(2)
+                .spacing([40.0, 24.0])
+                .striped(true)
+                .show(ui, |ui| {
+                    ui.label("Frames");
+                    ui.add(DragValue::new(&mut state.num_frames).range(1..=MAX_WAVETABLE_FRAMES));
+                    ui.end_row();
+
+                    ui.label("Duration");
+                    ui.add(
+                        DragValue::new(&mut state.duration)
+                            .range(0.0..=30.0)
+                            .speed(0.05)
+                            .suffix(" s"),
+                    );
+                    ui.end_row();
+                });
+
+            if !state.error.is_empty() {
+                ui.label(RichText::new(&state.error).color(Color32::RED));
+            }
+
+            if state.exporting {
+                ui.horizontal(|ui| {
+                    ui.spinner();
+                    ui.label("Exporting...");
+                });
+                ui.ctx().request_repaint();
+            }
+
+            ui.add_space(40.0);
+
+            Sides::new().show(
+                ui,
+                |_ui| {},
+                |ui| {
+                    if ui
+                        .add_enabled(!state.exporting, Button::new("Export"))
+                        .clicked()
+                        && let Some(path) = FileDialog::new()
+                            .add_filter("WAV", &["wav"])
+                            .set_file_name("wavetable.wav")
+                            .save_file()
+                    {
+                        match bridge.export_wavetable(
+                            module_id,
+                            &path,
+                            state.num_frames,
+                            state.duration,
+                        ) {
+                            Ok(()) => {
+                                state.exporting = true;
+                                state.error.clear();
+                            }
+                            Err(err) => state.error = err,
+                        }
+                    }
+
+                    if ui
+                        .add_enabled(!state.exporting, Button::new("Cancel"))
+                        .clicked()
+                    {
+                        ui.close();
+                    }
+                },
+            );
+        });
+
+        !modal.should_close()
+    }
+
     fn show_phases(
         ui: &mut Ui,
         phases: impl Iterator<Item = StereoSample>,
@@ -387,6 +600,24 @@ impl OscillatorUI {
             bridge.set_param(Input::GainsBlend, config.gains_blend);
         }
         ui.end_row();
+
+        ui.label("Unison Blend");
+        if ui
+            .add(Slider::new(&mut config.unison_blend, 0.0..=1.0))
+            .changed()
+        {
+            bridge.set_unison_blend(config.unison_blend);
+        }
+        ui.end_row();
+
+        ui.label("Stereo Spread");
+        if ui
+            .add(Slider::new(&mut config.stereo_spread, 0.0..=1.0))
+            .changed()
+        {
+            bridge.set_stereo_spread(config.stereo_spread);
+        }
+        ui.end_row();
     }
 
     fn paint_ui(
@@ -428,18 +659,61 @@ impl OscillatorUI {
 
                 ui.label("Pitch shift");
                 if ui
-                    .add(ModulationInput::new(
-                        &mut config.pitch_shift,
-                        bridge,
-                        Input::PitchShift,
-                        module_id,
-                    ))
+                    .add(
+                        ModulationInput::new(
+                            &mut config.pitch_shift,
+                            bridge,
+                            Input::PitchShift,
+                            module_id,
+                        )
+                        .range_max(config.pitch_range),
+                    )
                     .changed()
                 {
                     osc_bridge.set_param(Input::PitchShift, config.pitch_shift);
                 }
                 ui.end_row();
 
+                ui.label("Pitch range");
+                let mut pitch_range = config.pitch_range * 12.0;
+                if ui
+                    .add(
+                        Slider::new(&mut pitch_range, 1.0..=oscillator::MAX_PITCH_RANGE * 12.0)
+                            .suffix(" st"),
+                    )
+                    .on_hover_text("Limits the modulated pitch shift both ways")
+                    .changed()
+                {
+                    osc_bridge.set_pitch_range(pitch_range / 12.0);
+                }
+                ui.end_row();
+
+                ui.label("Frequency limits");
+                ui.horizontal(|ui| {
+                    let frequency_range = oscillator::MIN_FREQUENCY..=oscillator::MAX_FREQUENCY;
+                    let min = ui.add(
+                        DragValue::new(&mut config.min_frequency)
+                            .range(frequency_range.clone())
+                            .speed(1.0)
+                            .suffix(" Hz"),
+                    );
+                    ui.label("to");
+                    let max = ui.add(
+                        DragValue::new(&mut config.max_frequency)
+                            .range(frequency_range)
+                            .speed(10.0)
+                            .suffix(" Hz"),
+                    );
+
+                    if min.changed() || max.changed() {
+                        osc_bridge.set_frequency_range(
+                            config.min_frequency,
+                            config.max_frequency.max(config.min_frequency),
+                        );
+                    }
+                });
+                ui.end_row();
+
                 ui.label("Phase shift");
                 if ui
                     .add(ModulationInput::new(
@@ -468,6 +742,38 @@ impl OscillatorUI {
                 }
                 ui.end_row();
 
+                ui.label("Phase mod");
+                ui.add(DirectInput::new(bridge, Input::PhaseMod, module_id));
+                ui.end_row();
+
+                ui.label("FM index");
+                if ui
+                    .add(ModulationInput::new(
+                        &mut config.fm_index,
+                        bridge,
+                        Input::FmIndex,
+                        module_id,
+                    ))
+                    .changed()
+                {
+                    osc_bridge.set_param(Input::FmIndex, config.fm_index);
+                }
+                ui.end_row();
+
+                ui.label("Feedback");
+                if ui
+                    .add(ModulationInput::new(
+                        &mut config.feedback,
+                        bridge,
+                        Input::Feedback,
+                        module_id,
+                    ))
+                    .changed()
+                {
+                    osc_bridge.set_param(Input::Feedback, config.feedback);
+                }
+                ui.end_row();
+
                 ui.label("Detune");
                 if ui
                     .add(ModulationInput::new(
@@ -496,6 +802,35 @@ impl OscillatorUI {
                 }
                 ui.end_row();
 
+                ui.label("Drift");
+                if ui
+                    .add(ModulationInput::new(
+                        &mut config.drift,
+                        bridge,
+                        Input::Drift,
+                        module_id,
+                    ))
+                    .on_hover_text("Slow random pitch wander of each voice")
+                    .changed()
+                {
+                    osc_bridge.set_param(Input::Drift, config.drift);
+                }
+                ui.end_row();
+
+                ui.label("Drift Rate");
+                if ui
+                    .add(ModulationInput::new(
+                        &mut config.drift_rate,
+                        bridge,
+                        Input::DriftRate,
+                        module_id,
+                    ))
+                    .changed()
+                {
+                    osc_bridge.set_param(Input::DriftRate, config.drift_rate);
+                }
+                ui.end_row();
+
                 ui.label("Glide");
                 if ui
                     .add(ModulationInput::new(
@@ -533,6 +868,15 @@ impl OscillatorUI {
                 }
                 ui.end_row();
 
+                ui.label("Random phase");
+                if ui
+                    .add(Checkbox::without_text(&mut config.random_phase))
+                    .changed()
+                {
+                    osc_bridge.set_random_phase(config.random_phase);
+                }
+                ui.end_row();
+
                 ui.label("Unison");
                 if ui
                     .add(DragValue::new(&mut config.unison_voices).range(1..=16))
@@ -568,6 +912,29 @@ impl OscillatorUI {
             self.unison_state.randomize_phase_state.replace(state);
         }
 
+        ui.horizontal(|ui| {
+            if ui.button("Export Wavetable").clicked() {
+                self.export_wavetable_state = Some(Box::default());
+            }
+
+            ui.toggle_value(&mut self.show_preview, "Played Waveform");
+        });
+
+        if self.show_preview {
+            osc_bridge.request_preview();
+            ui.add_space(8.0);
+            Self::show_preview(ui, osc_bridge.preview());
+            ui.ctx().request_repaint();
+        }
+
+        if let Some(mut state) = self.export_wavetable_state.take()
+            && Self::show_export_wavetable_modal(module_id, bridge, ui, &mut state)
+        {
+            self.export_wavetable_state.replace(state);
+        }
+
+        ui.add_space(40.0);
+
         if confirm_module_removal(ui, &mut self.remove_confirmation) {
             bridge.remove_module(module_id);
         }
@@ -0,0 +1,96 @@
+use egui::{Grid, Ui};
+
+use crate::{
+    editor::{
+        ModuleUi, direct_input::DirectInput, modulation_input::ModulationInput,
+        module_label::ModuleLabel, utils::confirm_module_removal,
+    },
+    synth_engine::{
+        Input, ModuleId,
+        panner::PannerUiBridge,
+        ui_bridge::{ModuleBridge, UiBridge},
+    },
+};
+
+pub struct PannerUi {
+    module_id: ModuleId,
+    remove_confirmation: bool,
+    label_state: Option<String>,
+}
+
+impl PannerUi {
+    pub fn new(module_id: ModuleId) -> Self {
+        Self {
+            module_id,
+            remove_confirmation: false,
+            label_state: None,
+        }
+    }
+
+    fn paint_ui(&mut self, bridge: &mut UiBridge, panner_bridge: &mut PannerUiBridge, ui: &mut Ui) {
+        let module_id = self.module_id;
+        let mut config = panner_bridge.config().clone();
+
+        ui.add(ModuleLabel::new(&mut self.label_state, bridge, module_id));
+
+        ui.add_space(20.0);
+
+        Grid::new("panner_grid")
+            .num_columns(2)
+            .spacing([40.0, 24.0])
+            .striped(true)
+            .show(ui, |ui| {
+                ui.label("Input");
+                ui.add(DirectInput::new(bridge, Input::Audio, module_id));
+                ui.end_row();
+
+                ui.label("Pan");
+                if ui
+                    .add(ModulationInput::new(
+                        &mut config.pan,
+                        bridge,
+                        Input::Pan,
+                        module_id,
+                    ))
+                    .changed()
+                {
+                    panner_bridge.set_param(Input::Pan, config.pan);
+                }
+                ui.end_row();
+
+                ui.label("Width");
+                if ui
+                    .add(ModulationInput::new(
+                        &mut config.width,
+                        bridge,
+                        Input::Width,
+                        module_id,
+                    ))
+                    .changed()
+                {
+                    panner_bridge.set_param(Input::Width, config.width);
+                }
+                ui.end_row();
+            });
+
+        ui.add_space(40.0);
+
+        if confirm_module_removal(ui, &mut self.remove_confirmation) {
+            bridge.remove_module(module_id);
+        }
+    }
+}
+
+impl ModuleUi for PannerUi {
+    fn module_id(&self) -> Option<ModuleId> {
+        Some(self.module_id)
+    }
+
+    fn ui(&mut self, bridge: &mut UiBridge, ui: &mut Ui) {
+        bridge.with_module_bridge(self.module_id, |bridge, module_bridge| {
+            if let ModuleBridge::Panner(panner_bridge) = module_bridge {
+                self.paint_ui(bridge, panner_bridge, ui);
+            }
+        });
+    }
+}
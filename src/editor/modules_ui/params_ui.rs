@@ -1,56 +1,372 @@
-use std::sync::Arc;
+use std::path::PathBuf;
 
 use egui::{
-    Button, Checkbox, Color32, ComboBox, Frame, Grid, Id, Label, Modal, RichText, Sense, Sides,
-    Slider, TextEdit, Ui, vec2,
+    Button, Checkbox, Color32, ComboBox, DragValue, Grid, Id, Modal, Rect, RichText, Sense, Sides,
+    Slider, Stroke, StrokeKind, TextEdit, Ui, vec2,
 };
-use egui_extras::{Column, TableBuilder};
+use rfd::FileDialog;
 
+#[cfg(feature = "mts-esp")]
+use crate::synth_engine::MtsEspMode;
 use crate::{
     editor::{ModuleUi, direct_input::DirectInput},
-    engine_factory::EngineFactory,
-    presets::{PresetListItem, Presets},
-    synth_engine::{Input, ModuleId, OUTPUT_MODULE_ID, SynthEngine, ui_bridge::UiBridge},
+    presets::Presets,
+    synth_engine::{
+        AutomationConfig, EDO_PRESETS, Input, MAX_MASTER_TUNE, MAX_TRANSPOSE, ModuleId,
+        NUM_AUTOMATION_PARAMS, OUTPUT_MODULE_ID, OutputMode, Oversampling, PortamentoMode,
+        SpectralSize, StealPriority, SynthEngine, TuningConfig, VoiceState,
+        chord_memory::{MAX_CHORD_INTERVAL, MAX_CHORD_NOTES, MAX_STRUM},
+        limiter::{LimiterMode, MIN_CEILING, MIN_THRESHOLD},
+        oscillator::{TableUpdate, WaveQuality},
+        ui_bridge::{UiBridge, VoiceMeter},
+    },
     utils::from_ms,
 };
 
+impl PortamentoMode {
+    fn label(&self) -> &'static str {
+        match self {
+            Self::Off => "Off",
+            Self::Legato => "Legato",
+            Self::Always => "Always",
+        }
+    }
+}
+
+impl LimiterMode {
+    fn label(&self) -> &'static str {
+        match self {
+            Self::Brickwall => "Brickwall",
+            Self::SoftClip => "Soft Clip",
+        }
+    }
+}
+
+impl OutputMode {
+    fn label(&self) -> &'static str {
+        match self {
+            Self::Stereo => "Stereo",
+            Self::MonoSum => "Mono Sum",
+            Self::Mid => "Mid Only",
+        }
+    }
+}
+
+impl StealPriority {
+    fn label(&self) -> &'static str {
+        match self {
+            Self::Oldest => "Oldest",
+            Self::Quietest => "Quietest",
+            Self::LowestNote => "Lowest Note",
+            Self::HighestNote => "Highest Note",
+            Self::Released => "Nearest to Released",
+        }
+    }
+}
+
+#[cfg(feature = "mts-esp")]
+impl MtsEspMode {
+    fn label(&self) -> &'static str {
+        match self {
+            Self::Off => "Off",
+            Self::NoteOn => "Note On",
+            Self::Continuous => "Continuous",
+        }
+    }
+}
+
+#[cfg(feature = "mts-esp")]
+static MTS_ESP_OPTIONS: &[MtsEspMode] =
+    &[MtsEspMode::Off, MtsEspMode::NoteOn, MtsEspMode::Continuous];
+
+static STEAL_PRIORITY_OPTIONS: &[StealPriority] = &[
+    StealPriority::Oldest,
+    StealPriority::Quietest,
+    StealPriority::LowestNote,
+    StealPriority::HighestNote,
+    StealPriority::Released,
+];
+
+static LIMITER_MODE_OPTIONS: &[LimiterMode] = &[LimiterMode::Brickwall, LimiterMode::SoftClip];
+
+static OUTPUT_MODE_OPTIONS: &[OutputMode] =
+    &[OutputMode::Stereo, OutputMode::MonoSum, OutputMode::Mid];
+
+const METER_W: f32 = 10.0;
+const METER_H: f32 = 32.0;
+const METER_SPACING: f32 = 3.0;
+const METER_MIN_DB: f32 = -60.0;
+const GR_METER_W: f32 = 160.0;
+const GR_METER_H: f32 = 10.0;
+const GR_METER_MAX_DB: f32 = 24.0;
+const GR_COLOR: Color32 = Color32::from_rgb(0xe0, 0x90, 0x40);
+const CORRELATION_METER_W: f32 = 160.0;
+const CORRELATION_METER_H: f32 = 10.0;
+const CORRELATION_COLOR: Color32 = Color32::from_rgb(0x66, 0xcc, 0x66);
+const ANTI_CORRELATION_COLOR: Color32 = Color32::from_rgb(0xe0, 0x50, 0x50);
+const LOAD_METER_W: f32 = 160.0;
+const LOAD_METER_H: f32 = 10.0;
+// Bars are scaled to a quarter of the block budget, a single module rarely takes more
+const LOAD_METER_MAX: f32 = 0.25;
+
+impl VoiceState {
+    fn color(&self) -> Color32 {
+        match self {
+            Self::Idle => Color32::from_gray(60),
+            Self::Playing => Color32::from_rgb(0x66, 0xcc, 0x66),
+            Self::Releasing => Color32::from_rgb(0xe0, 0xb0, 0x40),
+            Self::Killing => Color32::from_rgb(0xe0, 0x50, 0x50),
+        }
+    }
+}
+
+impl Oversampling {
+    fn label(&self) -> &'static str {
+        match self {
+            Self::Off => "Off",
+            Self::X2 => "2x",
+            Self::X4 => "4x",
+        }
+    }
+}
+
+impl WaveQuality {
+    fn label(&self, spectral_size: SpectralSize) -> String {
+        format!("{} samples", self.size(spectral_size))
+    }
+}
+
+static WAVE_QUALITY_OPTIONS: &[WaveQuality] =
+    &[WaveQuality::Low, WaveQuality::Standard, WaveQuality::High];
+
+impl SpectralSize {
+    fn label(&self) -> String {
+        format!("{} bins", self.bins())
+    }
+}
+
+static SPECTRAL_SIZE_OPTIONS: &[SpectralSize] =
+    &[SpectralSize::S256, SpectralSize::S512, SpectralSize::S1024];
+
+impl TableUpdate {
+    fn label(&self) -> &'static str {
+        match self {
+            Self::Economy => "Economy",
+            Self::Standard => "Standard",
+            Self::Precise => "Precise",
+        }
+    }
+
+    fn description(&self) -> &'static str {
+        match self {
+            Self::Economy => {
+                "Changing spectra rebuild the tables every second block, \
+                about half the oscillator FFT load of Standard"
+            }
+            Self::Standard => "Tables are rebuilt once per block when the spectrum changes",
+            Self::Precise => {
+                "The engine renders in half blocks, spectral modules and table \
+                rebuilds run twice as often, up to twice the CPU load of Standard"
+            }
+        }
+    }
+}
+
+static TABLE_UPDATE_OPTIONS: &[TableUpdate] = &[
+    TableUpdate::Economy,
+    TableUpdate::Standard,
+    TableUpdate::Precise,
+];
+
+static OVERSAMPLING_OPTIONS: &[Oversampling] =
+    &[Oversampling::Off, Oversampling::X2, Oversampling::X4];
+
+static PORTAMENTO_OPTIONS: &[PortamentoMode] = &[
+    PortamentoMode::Off,
+    PortamentoMode::Legato,
+    PortamentoMode::Always,
+];
+
 #[derive(Default)]
 pub struct SavePresetState {
     title: String,
-    error: String,
-}
-
-pub struct LoadPresetState {
-    preset_list: Vec<PresetListItem>,
-    selected_index: Option<usize>,
+    category: String,
+    tags: String,
     error: String,
 }
 
 pub struct ParamsUi {
-    engine_factory: Arc<EngineFactory>,
     save_preset_state: Option<Box<SavePresetState>>,
-    load_preset_state: Option<Box<LoadPresetState>>,
+    tuning_error: Option<String>,
 }
 
 impl ParamsUi {
-    pub fn new(engine_factory: Arc<EngineFactory>) -> Self {
+    pub fn new() -> Self {
         Self {
-            engine_factory,
             save_preset_state: None,
-            load_preset_state: None,
+            tuning_error: None,
+        }
+    }
+
+    fn load_scala(&mut self, bridge: &mut UiBridge) {
+        let Some(paths) = FileDialog::new()
+            .add_filter("Scala", &["scl", "kbm"])
+            .pick_files()
+        else {
+            return;
+        };
+
+        let with_extension = |ext: &str| -> Option<&PathBuf> {
+            paths.iter().find(|path| {
+                path.extension()
+                    .is_some_and(|e| e.eq_ignore_ascii_case(ext))
+            })
+        };
+
+        let result = match with_extension("scl") {
+            Some(scl) => TuningConfig::from_scala(scl, with_extension("kbm").map(PathBuf::as_path)),
+            None => Err("Select a .scl file, optionally together with a .kbm file".to_string()),
+        };
+
+        match result {
+            Ok(tuning) => {
+                bridge.set_tuning(Some(tuning));
+                self.tuning_error = None;
+            }
+            Err(err) => self.tuning_error = Some(err),
+        }
+    }
+
+    fn show_tuning(&mut self, bridge: &mut UiBridge, ui: &mut Ui) {
+        let current = bridge.engine_params().tuning.clone();
+        let selected_text = current
+            .as_ref()
+            .map_or("12-TET", |tuning| tuning.name.as_str())
+            .to_string();
+
+        ui.label("Tuning");
+        ui.vertical(|ui| {
+            ui.horizontal(|ui| {
+                ComboBox::from_id_salt("tuning-select")
+                    .selected_text(selected_text)
+                    .show_ui(ui, |ui| {
+                        if ui.selectable_label(current.is_none(), "12-TET").clicked() {
+                            bridge.set_tuning(None);
+                        }
+
+                        for divisions in EDO_PRESETS {
+                            let tuning = TuningConfig::edo(*divisions);
+
+                            if ui
+                                .selectable_label(current.as_ref() == Some(&tuning), &tuning.name)
+                                .clicked()
+                            {
+                                bridge.set_tuning(Some(tuning));
+                            }
+                        }
+                    });
+
+                if ui
+                    .button("Load Scala")
+                    .on_hover_text("Select a .scl scale and an optional .kbm keyboard mapping")
+                    .clicked()
+                {
+                    self.load_scala(bridge);
+                }
+            });
+
+            if let Some(err) = &self.tuning_error {
+                ui.label(RichText::new(err).color(Color32::RED));
+            }
+        });
+        ui.end_row();
+    }
+
+    fn show_chord(bridge: &mut UiBridge, ui: &mut Ui) {
+        let mut chord = bridge.engine_params().chord;
+        let mut strum_ms = chord.strum * 1000.0;
+
+        ui.label("Chord");
+        ui.horizontal(|ui| {
+            if ui.add(Checkbox::without_text(&mut chord.enabled)).changed() {
+                bridge.set_chord(chord);
+            }
+
+            if ui
+                .add_enabled(
+                    chord.enabled,
+                    Slider::new(&mut chord.num_notes, 1..=MAX_CHORD_NOTES).suffix(" notes"),
+                )
+                .changed()
+            {
+                bridge.set_chord(chord);
+            }
+        });
+        ui.end_row();
+
+        ui.label("Chord Intervals");
+        ui.add_enabled_ui(chord.enabled, |ui| {
+            ui.horizontal(|ui| {
+                let mut changed = false;
+
+                for interval in &mut chord.intervals[..chord.num_notes] {
+                    changed |= ui
+                        .add(
+                            DragValue::new(interval)
+                                .range(-MAX_CHORD_INTERVAL..=MAX_CHORD_INTERVAL)
+                                .suffix(" st"),
+                        )
+                        .changed();
+                }
+
+                if changed {
+                    bridge.set_chord(chord);
+                }
+            });
+        });
+        ui.end_row();
+
+        ui.label("Strum");
+        if ui
+            .add_enabled(
+                chord.enabled,
+                Slider::new(&mut strum_ms, 0.0..=MAX_STRUM * 1000.0).suffix(" ms"),
+            )
+            .changed()
+        {
+            chord.strum = from_ms(strum_ms);
+            bridge.set_chord(chord);
         }
+        ui.end_row();
     }
 
-    fn show_save_preset_modal(&mut self, ui: &mut Ui, state: &mut SavePresetState) -> bool {
+    fn show_save_preset_modal(bridge: &UiBridge, ui: &mut Ui, state: &mut SavePresetState) -> bool {
         let modal = Modal::new(Id::new("save_preset_modal")).show(ui.ctx(), |ui| {
-            ui.set_width(220.0);
+            ui.set_width(260.0);
             ui.heading("Save Preset");
             ui.add_space(16.0);
-            ui.horizontal(|ui| {
-                ui.label("Title");
-                ui.add(TextEdit::singleline(&mut state.title))
-                    .request_focus();
-            });
+
+            Grid::new("save_preset_grid")
+                .num_columns(2)
+                .spacing([12.0, 8.0])
+                .show(ui, |ui| {
+                    ui.label("Title");
+                    let title = ui.add(TextEdit::singleline(&mut state.title));
+
+                    // Focus the title only when the dialog opens, so other fields stay editable
+                    if ui.memory(|mem| mem.focused().is_none()) {
+                        title.request_focus();
+                    }
+                    ui.end_row();
+
+                    ui.label("Category");
+                    ui.add(TextEdit::singleline(&mut state.category));
+                    ui.end_row();
+
+                    ui.label("Tags");
+                    ui.add(TextEdit::singleline(&mut state.tags).hint_text("Comma separated"));
+                    ui.end_row();
+                });
 
             if !state.error.is_empty() {
                 ui.label(RichText::new(&state.error).color(Color32::RED));
@@ -66,9 +382,17 @@ impl ParamsUi {
                 |_ui| {},
                 |ui| {
                     if ui.add_enabled(valid, Button::new("Save")).clicked() {
-                        let mut preset = self.engine_factory.get_preset();
+                        let mut preset = bridge.preset();
 
                         preset.info.title = trimmed.to_string();
+                        preset.info.category = state.category.trim().to_string();
+                        preset.info.tags = state
+                            .tags
+                            .split(',')
+                            .map(str::trim)
+                            .filter(|tag| !tag.is_empty())
+                            .map(String::from)
+                            .collect();
 
                         if let Some(presets) = Presets::new() {
                             if presets.write_preset(&preset).is_some() {
@@ -90,75 +414,332 @@ impl ParamsUi {
         !modal.should_close()
     }
 
-    fn show_load_preset_modal(&mut self, ui: &mut Ui, state: &mut LoadPresetState) -> bool {
-        let modal = Modal::new(Id::new("load_preset_modal")).show(ui.ctx(), |ui| {
-            ui.set_width(440.0);
+    fn meter_height(level: f32) -> f32 {
+        let db = 20.0 * level.max(1e-6).log10();
 
-            ui.heading("Load Preset");
-            ui.add_space(16.0);
+        ((db - METER_MIN_DB) / -METER_MIN_DB).clamp(0.0, 1.0) * METER_H
+    }
+
+    // One cell per voice slot: the frame shows the voice state, the fill RMS and the line peak
+    fn show_voice_meters(ui: &mut Ui, meters: &[VoiceMeter]) {
+        let width = meters.len() as f32 * (METER_W + METER_SPACING) - METER_SPACING;
+        let (rect, response) = ui.allocate_exact_size(vec2(width, METER_H), Sense::hover());
+        let painter = ui.painter_at(rect);
+
+        for (idx, meter) in meters.iter().enumerate() {
+            let color = meter.state.color();
+            let cell = Rect::from_min_size(
+                rect.min + vec2(idx as f32 * (METER_W + METER_SPACING), 0.0),
+                vec2(METER_W, METER_H),
+            );
+            let rms_h = Self::meter_height(meter.rms);
+            let peak_y = cell.bottom() - Self::meter_height(meter.peak);
+
+            painter.rect_filled(cell, 2.0, Color32::from_gray(24));
+            painter.rect_filled(
+                Rect::from_min_max(cell.left_bottom() - vec2(0.0, rms_h), cell.right_bottom()),
+                2.0,
+                color.gamma_multiply(0.6),
+            );
+
+            if meter.peak > 0.0 {
+                painter.hline(cell.x_range(), peak_y, Stroke::new(1.0, color));
+            }
+
+            painter.rect_stroke(cell, 2.0, Stroke::new(1.0, color), StrokeKind::Inside);
+        }
+
+        response.on_hover_text("Voice slots: green playing, yellow releasing, red killing");
+    }
+
+    fn show_gain_reduction(ui: &mut Ui, gain_reduction: f32) {
+        ui.horizontal(|ui| {
+            let (rect, _) = ui.allocate_exact_size(vec2(GR_METER_W, GR_METER_H), Sense::hover());
+            let painter = ui.painter_at(rect);
+            let width = (gain_reduction / GR_METER_MAX_DB).clamp(0.0, 1.0) * GR_METER_W;
+
+            painter.rect_filled(rect, 2.0, Color32::from_gray(24));
+            painter.rect_filled(
+                Rect::from_min_size(rect.min, vec2(width, GR_METER_H)),
+                2.0,
+                GR_COLOR,
+            );
+            ui.label(format!("-{gain_reduction:.1} dB"));
+        });
+    }
+
+    // Bar grows from the center, right towards +1 (mono) and left towards -1 (cancels in mono)
+    fn show_correlation(ui: &mut Ui, correlation: f32) {
+        ui.horizontal(|ui| {
+            let (rect, response) = ui.allocate_exact_size(
+                vec2(CORRELATION_METER_W, CORRELATION_METER_H),
+                Sense::hover(),
+            );
+            let painter = ui.painter_at(rect);
+            let center = rect.center().x;
+            let end = center + correlation.clamp(-1.0, 1.0) * 0.5 * CORRELATION_METER_W;
+            let color = if correlation < 0.0 {
+                ANTI_CORRELATION_COLOR
+            } else {
+                CORRELATION_COLOR
+            };
 
-            Frame::new().inner_margin(vec2(8.0, 8.0)).show(ui, |ui| {
-                let table = TableBuilder::new(ui)
-                    .striped(true)
-                    .resizable(false)
-                    .sense(Sense::click())
-                    .min_scrolled_height(0.0)
-                    .max_scroll_height(300.0)
-                    .column(Column::remainder());
-
-                table
-                    .header(20.0, |mut header| {
-                        header.col(|ui| {
-                            ui.strong("Title");
+            painter.rect_filled(rect, 2.0, Color32::from_gray(24));
+            painter.rect_filled(
+                Rect::from_x_y_ranges(center.min(end)..=center.max(end), rect.y_range()),
+                2.0,
+                color,
+            );
+            painter.vline(
+                center,
+                rect.y_range(),
+                Stroke::new(1.0, Color32::from_gray(90)),
+            );
+            ui.label(format!("{correlation:+.2}"));
+
+            response.on_hover_text("Negative values lose level when the output is played in mono");
+        });
+    }
+
+    fn show_automation(bridge: &mut UiBridge, ui: &mut Ui) {
+        let mut modules = bridge.get_modules();
+        let mut automation = bridge.automation().to_vec();
+        let mut changed = false;
+        let mut remove_idx = None;
+
+        modules.retain(|m| !bridge.get_automatable_inputs(m.id).is_empty());
+        modules.sort_by_key(|m| m.id);
+
+        ui.heading("Automation");
+        ui.add_space(12.0);
+
+        Grid::new("automation_grid")
+            .num_columns(6)
+            .spacing([12.0, 8.0])
+            .striped(true)
+            .show(ui, |ui| {
+                for (idx, assignment) in automation.iter_mut().enumerate() {
+                    ComboBox::from_id_salt(("automation-slot", idx))
+                        .selected_text(format!("Automation {}", assignment.slot + 1))
+                        .show_ui(ui, |ui| {
+                            for slot in 0..NUM_AUTOMATION_PARAMS {
+                                changed |= ui
+                                    .selectable_value(
+                                        &mut assignment.slot,
+                                        slot,
+                                        format!("Automation {}", slot + 1),
+                                    )
+                                    .clicked();
+                            }
                         });
-                    })
-                    .body(|mut body| {
-                        for (idx, preset) in state.preset_list.iter().enumerate() {
-                            body.row(18.0, |mut row| {
-                                row.set_selected(state.selected_index == Some(idx));
 
-                                row.col(|ui| {
-                                    ui.add(Label::new(&preset.info.title).selectable(false));
-                                });
+                    let module_label = modules
+                        .iter()
+                        .find(|m| m.id == assignment.module_id)
+                        .map(|m| m.label.as_str())
+                        .unwrap_or_default();
 
-                                if row.response().clicked() {
-                                    state.selected_index = Some(idx);
+                    ComboBox::from_id_salt(("automation-module", idx))
+                        .selected_text(module_label)
+                        .show_ui(ui, |ui| {
+                            for module in &modules {
+                                if ui
+                                    .selectable_label(
+                                        assignment.module_id == module.id,
+                                        &module.label,
+                                    )
+                                    .clicked()
+                                    && let Some(input) =
+                                        bridge.get_automatable_inputs(module.id).first().copied()
+                                {
+                                    assignment.module_id = module.id;
+                                    assignment.input = input;
+                                    changed = true;
                                 }
-                            });
-                        }
-                    });
+                            }
+                        });
+
+                    ComboBox::from_id_salt(("automation-input", idx))
+                        .selected_text(assignment.input.label())
+                        .show_ui(ui, |ui| {
+                            for input in bridge.get_automatable_inputs(assignment.module_id) {
+                                changed |= ui
+                                    .selectable_value(&mut assignment.input, input, input.label())
+                                    .clicked();
+                            }
+                        });
+
+                    changed |= ui
+                        .add(
+                            DragValue::new(&mut assignment.min)
+                                .speed(0.01)
+                                .prefix("Min "),
+                        )
+                        .changed();
+                    changed |= ui
+                        .add(
+                            DragValue::new(&mut assignment.max)
+                                .speed(0.01)
+                                .prefix("Max "),
+                        )
+                        .changed();
 
-                if !state.error.is_empty() {
-                    ui.label(RichText::new(&state.error).color(Color32::RED));
+                    if ui.button("❌").on_hover_text("Remove Automation").clicked() {
+                        remove_idx = Some(idx);
+                    }
+                    ui.end_row();
                 }
             });
 
-            Sides::new().show(
-                ui,
-                |_ui| {},
-                |ui| {
-                    if ui
-                        .add_enabled(state.selected_index.is_some(), Button::new("Load"))
-                        .clicked()
-                    {
-                        if let Some(idx) = state.selected_index
-                            && let Some(preset) = Presets::read_preset(&state.preset_list[idx].path)
-                            && self.engine_factory.load_preset(&preset)
-                        {
-                            ui.close();
-                        } else {
-                            state.error = "Failed to load preset.".into();
-                        }
+        if let Some(idx) = remove_idx {
+            automation.remove(idx);
+            changed = true;
+        }
+
+        if ui.button("Add").clicked()
+            && let Some(module) = modules.first()
+            && let Some(input) = bridge.get_automatable_inputs(module.id).first().copied()
+        {
+            let slot = (0..NUM_AUTOMATION_PARAMS)
+                .find(|slot| automation.iter().all(|a| a.slot != *slot))
+                .unwrap_or_default();
+
+            automation.push(AutomationConfig {
+                slot,
+                module_id: module.id,
+                input,
+                min: 0.0,
+                max: 1.0,
+            });
+            changed = true;
+        }
+
+        if changed {
+            bridge.set_automation(automation);
+        }
+    }
+
+    fn show_midi_mappings(bridge: &mut UiBridge, ui: &mut Ui) {
+        let modules = bridge.get_modules();
+        let mut mappings = bridge.midi_mappings().to_vec();
+        let mut changed = false;
+        let mut remove_idx = None;
+
+        ui.heading("MIDI Mappings");
+        ui.add_space(12.0);
+
+        if mappings.is_empty() {
+            ui.weak("Right click a slider and select MIDI Learn to map a controller onto it");
+            return;
+        }
+
+        Grid::new("midi_mappings_grid")
+            .num_columns(7)
+            .spacing([12.0, 8.0])
+            .striped(true)
+            .show(ui, |ui| {
+                for (idx, mapping) in mappings.iter_mut().enumerate() {
+                    changed |= ui
+                        .add(DragValue::new(&mut mapping.cc).range(0..=127).prefix("CC "))
+                        .changed();
+
+                    ui.label(
+                        modules
+                            .iter()
+                            .find(|m| m.id == mapping.module_id)
+                            .map(|m| m.label.as_str())
+                            .unwrap_or_default(),
+                    );
+                    ui.label(mapping.input.label());
+
+                    changed |= ui
+                        .add(DragValue::new(&mut mapping.min).speed(0.01).prefix("Min "))
+                        .changed();
+                    changed |= ui
+                        .add(DragValue::new(&mut mapping.max).speed(0.01).prefix("Max "))
+                        .changed();
+                    changed |= ui
+                        .add(
+                            DragValue::new(&mut mapping.curve)
+                                .range(-1.0..=1.0)
+                                .speed(0.01)
+                                .prefix("Curve "),
+                        )
+                        .changed();
+
+                    if ui.button("❌").on_hover_text("Remove Mapping").clicked() {
+                        remove_idx = Some(idx);
                     }
+                    ui.end_row();
+                }
+            });
 
-                    if ui.button("Cancel").clicked() {
-                        ui.close();
+        if let Some(idx) = remove_idx {
+            mappings.remove(idx);
+            changed = true;
+        }
+
+        if changed {
+            bridge.set_midi_mappings(mappings);
+        }
+    }
+
+    // Green for cheap modules through yellow to red at the top of the scale
+    fn load_color(load: f32) -> Color32 {
+        let t = (load / LOAD_METER_MAX).clamp(0.0, 1.0);
+        let red = (0x66 as f32 + t.min(0.5) * 2.0 * (0xe0 - 0x66) as f32) as u8;
+        let green = (0xcc as f32 - (t - 0.5).max(0.0) * 2.0 * (0xcc - 0x50) as f32) as u8;
+
+        Color32::from_rgb(red, green, 0x50)
+    }
+
+    fn show_module_load(bridge: &UiBridge, ui: &mut Ui) {
+        let mut modules: Vec<_> = bridge
+            .get_modules()
+            .into_iter()
+            .map(|module| (bridge.module_load(module.id), module))
+            .collect();
+
+        modules.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+        let total: f32 = modules.iter().map(|(load, _)| load).sum();
+
+        ui.heading("Module CPU");
+        ui.add_space(12.0);
+
+        Grid::new("module_load_grid")
+            .num_columns(3)
+            .spacing([12.0, 6.0])
+            .striped(true)
+            .show(ui, |ui| {
+                for (load, module) in &modules {
+                    if module.id == OUTPUT_MODULE_ID {
+                        ui.label("Output");
+                    } else {
+                        ui.label(&module.label);
                     }
-                },
-            );
-        });
 
-        !modal.should_close()
+                    let (rect, _) =
+                        ui.allocate_exact_size(vec2(LOAD_METER_W, LOAD_METER_H), Sense::hover());
+                    let painter = ui.painter_at(rect);
+                    let width = (load / LOAD_METER_MAX).clamp(0.0, 1.0) * LOAD_METER_W;
+
+                    painter.rect_filled(rect, 2.0, Color32::from_gray(24));
+                    painter.rect_filled(
+                        Rect::from_min_size(rect.min, vec2(width, LOAD_METER_H)),
+                        2.0,
+                        Self::load_color(*load),
+                    );
+
+                    ui.label(format!("{:.1}%", load * 100.0));
+                    ui.end_row();
+                }
+
+                ui.label(RichText::new("Total").strong());
+                ui.label("");
+                ui.label(RichText::new(format!("{:.1}%", total * 100.0)).strong());
+                ui.end_row();
+            });
     }
 }
 
@@ -179,13 +760,26 @@ impl ModuleUi for ParamsUi {
                 let controls = bridge.engine_params();
                 let block_sizes = [8, 16, 32, 64, 128];
                 let voices_status = *bridge.voices_status();
+                let mut master_tune = bridge.master_tune();
+                let mut transpose = bridge.transpose();
 
                 let mut kill_time_ms = controls.voice_kill_time * 1000.0;
                 let mut voices = controls.num_voices;
                 let mut legato = controls.legato;
+                let portamento = controls.portamento;
+                let steal_priority = controls.steal_priority;
+                let mut mpe = controls.mpe;
+                let mut pitch_bend_range = controls.pitch_bend_range;
                 let mut block_size = controls.block_size;
-                let mut oversampling = controls.oversampling;
+                let oversampling = controls.oversampling;
+                let wave_quality = controls.wave_quality;
+                let spectral_size = controls.spectral_size;
+                let table_update = controls.table_update;
                 let mut stereo_spectrum = controls.stereo_spectrum;
+                let mut dc_blocker = controls.dc_blocker;
+                let output_mode = controls.output_mode;
+                let mut limiter = controls.limiter;
+                let mut release_ms = limiter.release * 1000.0;
 
                 ui.label("Voices");
                 if ui
@@ -202,6 +796,105 @@ impl ModuleUi for ParamsUi {
                 }
                 ui.end_row();
 
+                ui.label("Portamento");
+                ComboBox::from_id_salt("portamento-select")
+                    .selected_text(portamento.label())
+                    .show_ui(ui, |ui| {
+                        for mode in PORTAMENTO_OPTIONS {
+                            if ui
+                                .selectable_label(portamento == *mode, mode.label())
+                                .clicked()
+                            {
+                                bridge.set_portamento(*mode);
+                            }
+                        }
+                    });
+                ui.end_row();
+
+                ui.label("Voice Stealing");
+                ComboBox::from_id_salt("steal-priority-select")
+                    .selected_text(steal_priority.label())
+                    .show_ui(ui, |ui| {
+                        for priority in STEAL_PRIORITY_OPTIONS {
+                            if ui
+                                .selectable_label(steal_priority == *priority, priority.label())
+                                .clicked()
+                            {
+                                bridge.set_steal_priority(*priority);
+                            }
+                        }
+                    });
+                ui.end_row();
+
+                Self::show_chord(bridge, ui);
+                self.show_tuning(bridge, ui);
+
+                #[cfg(feature = "mts-esp")]
+                {
+                    let mts_esp = bridge.engine_params().mts_esp;
+
+                    ui.label("MTS-ESP");
+                    ComboBox::from_id_salt("mts-esp-select")
+                        .selected_text(mts_esp.label())
+                        .show_ui(ui, |ui| {
+                            for mode in MTS_ESP_OPTIONS {
+                                if ui
+                                    .selectable_label(mts_esp == *mode, mode.label())
+                                    .clicked()
+                                {
+                                    bridge.set_mts_esp(*mode);
+                                }
+                            }
+                        })
+                        .response
+                        .on_hover_text(
+                            "Follow the tuning of an MTS-ESP master when one is running, \
+                             Continuous also retunes held notes",
+                        );
+                    ui.end_row();
+                }
+
+                ui.label("MPE");
+                if ui.add(Checkbox::without_text(&mut mpe)).changed() {
+                    bridge.set_mpe(mpe);
+                }
+                ui.end_row();
+
+                ui.label("Pitch Bend Range");
+                if ui
+                    .add(
+                        Slider::new(&mut pitch_bend_range, 0.0..=48.0)
+                            .step_by(1.0)
+                            .suffix(" st"),
+                    )
+                    .changed()
+                {
+                    bridge.set_pitch_bend_range(pitch_bend_range);
+                }
+                ui.end_row();
+
+                ui.label("Master Tune");
+                if ui
+                    .add(
+                        Slider::new(&mut master_tune, -MAX_MASTER_TUNE..=MAX_MASTER_TUNE)
+                            .step_by(0.1)
+                            .suffix(" cents"),
+                    )
+                    .changed()
+                {
+                    bridge.set_master_tune(master_tune);
+                }
+                ui.end_row();
+
+                ui.label("Transpose");
+                if ui
+                    .add(Slider::new(&mut transpose, -MAX_TRANSPOSE..=MAX_TRANSPOSE).suffix(" st"))
+                    .changed()
+                {
+                    bridge.set_transpose(transpose);
+                }
+                ui.end_row();
+
                 ui.label("Voice kill time");
                 if ui
                     .add(Slider::new(&mut kill_time_ms, 4.0..=100.0))
@@ -221,6 +914,10 @@ impl ModuleUi for ParamsUi {
                 ));
                 ui.end_row();
 
+                ui.label("Voice activity");
+                Self::show_voice_meters(ui, bridge.voice_meters());
+                ui.end_row();
+
                 ui.label("Block Size");
                 ComboBox::from_id_salt("buff-size-select")
                     .selected_text(format!("{} samples", block_size))
@@ -236,10 +933,70 @@ impl ModuleUi for ParamsUi {
                     });
                 ui.end_row();
 
-                ui.label("Oversampling x2");
-                if ui.add(Checkbox::without_text(&mut oversampling)).changed() {
-                    bridge.set_oversampling(oversampling);
-                }
+                ui.label("Oversampling");
+                ComboBox::from_id_salt("oversampling-select")
+                    .selected_text(oversampling.label())
+                    .show_ui(ui, |ui| {
+                        for option in OVERSAMPLING_OPTIONS {
+                            if ui
+                                .selectable_label(oversampling == *option, option.label())
+                                .clicked()
+                            {
+                                bridge.set_oversampling(*option);
+                            }
+                        }
+                    });
+                ui.end_row();
+
+                ui.label("Waveform Quality");
+                ComboBox::from_id_salt("wave-quality-select")
+                    .selected_text(wave_quality.label(spectral_size))
+                    .show_ui(ui, |ui| {
+                        for option in WAVE_QUALITY_OPTIONS {
+                            if ui
+                                .selectable_label(
+                                    wave_quality == *option,
+                                    option.label(spectral_size),
+                                )
+                                .clicked()
+                            {
+                                bridge.set_wave_quality(*option);
+                            }
+                        }
+                    });
+                ui.end_row();
+
+                ui.label("Spectral Size");
+                ComboBox::from_id_salt("spectral-size-select")
+                    .selected_text(spectral_size.label())
+                    .show_ui(ui, |ui| {
+                        for option in SPECTRAL_SIZE_OPTIONS {
+                            if ui
+                                .selectable_label(spectral_size == *option, option.label())
+                                .clicked()
+                            {
+                                bridge.set_spectral_size(*option);
+                            }
+                        }
+                    });
+                ui.end_row();
+
+                ui.label("Table Updates");
+                ComboBox::from_id_salt("table-update-select")
+                    .selected_text(table_update.label())
+                    .show_ui(ui, |ui| {
+                        for option in TABLE_UPDATE_OPTIONS {
+                            if ui
+                                .selectable_label(table_update == *option, option.label())
+                                .on_hover_text(option.description())
+                                .clicked()
+                            {
+                                bridge.set_table_update(*option);
+                            }
+                        }
+                    })
+                    .response
+                    .on_hover_text(table_update.description());
                 ui.end_row();
 
                 ui.label("Stereo Spectrum");
@@ -251,39 +1008,126 @@ impl ModuleUi for ParamsUi {
                 }
                 ui.end_row();
 
-                ui.label("Output");
-                ui.add(DirectInput::new(bridge, Input::Audio, OUTPUT_MODULE_ID));
+                ui.label("DC Blocker");
+                if ui.add(Checkbox::without_text(&mut dc_blocker)).changed() {
+                    bridge.set_dc_blocker(dc_blocker);
+                }
                 ui.end_row();
 
-                ui.label("Presets");
-                ui.horizontal(|ui| {
-                    if ui.button("Save").clicked() {
-                        self.save_preset_state = Some(Box::new(SavePresetState::default()));
-                    }
+                ui.label("Output Mode");
+                ComboBox::from_id_salt("output-mode-select")
+                    .selected_text(output_mode.label())
+                    .show_ui(ui, |ui| {
+                        for option in OUTPUT_MODE_OPTIONS {
+                            if ui
+                                .selectable_label(output_mode == *option, option.label())
+                                .clicked()
+                            {
+                                bridge.set_output_mode(*option);
+                            }
+                        }
+                    });
+                ui.end_row();
 
-                    if ui.button("Load").clicked()
-                        && let Some(presets) = Presets::new()
+                ui.label("Limiter");
+                ui.horizontal(|ui| {
+                    if ui
+                        .add(Checkbox::without_text(&mut limiter.enabled))
+                        .changed()
                     {
-                        self.load_preset_state = Some(Box::new(LoadPresetState {
-                            preset_list: presets.read_presets_list(),
-                            selected_index: None,
-                            error: String::new(),
-                        }));
+                        bridge.set_limiter(limiter);
                     }
+
+                    ui.add_enabled_ui(limiter.enabled, |ui| {
+                        ComboBox::from_id_salt("limiter-mode-select")
+                            .selected_text(limiter.mode.label())
+                            .show_ui(ui, |ui| {
+                                for mode in LIMITER_MODE_OPTIONS {
+                                    if ui
+                                        .selectable_value(&mut limiter.mode, *mode, mode.label())
+                                        .clicked()
+                                    {
+                                        bridge.set_limiter(limiter);
+                                    }
+                                }
+                            });
+                    });
                 });
                 ui.end_row();
+
+                ui.label("Limiter Threshold");
+                if ui
+                    .add_enabled(
+                        limiter.enabled,
+                        Slider::new(&mut limiter.threshold, MIN_THRESHOLD..=limiter.ceiling)
+                            .suffix(" dB"),
+                    )
+                    .changed()
+                {
+                    bridge.set_limiter(limiter);
+                }
+                ui.end_row();
+
+                ui.label("Limiter Ceiling");
+                if ui
+                    .add_enabled(
+                        limiter.enabled,
+                        Slider::new(&mut limiter.ceiling, MIN_CEILING..=0.0).suffix(" dB"),
+                    )
+                    .changed()
+                {
+                    limiter.threshold = limiter.threshold.min(limiter.ceiling);
+                    bridge.set_limiter(limiter);
+                }
+                ui.end_row();
+
+                ui.label("Limiter Release");
+                if ui
+                    .add_enabled(
+                        limiter.enabled && limiter.mode == LimiterMode::Brickwall,
+                        Slider::new(&mut release_ms, 1.0..=1000.0)
+                            .logarithmic(true)
+                            .suffix(" ms"),
+                    )
+                    .changed()
+                {
+                    limiter.release = from_ms(release_ms);
+                    bridge.set_limiter(limiter);
+                }
+                ui.end_row();
+
+                ui.label("Gain Reduction");
+                Self::show_gain_reduction(ui, bridge.gain_reduction());
+                ui.end_row();
+
+                ui.label("Correlation");
+                Self::show_correlation(ui, bridge.correlation());
+                ui.end_row();
+
+                ui.label("Output");
+                ui.add(DirectInput::new(bridge, Input::Audio, OUTPUT_MODULE_ID));
+                ui.end_row();
+
+                ui.label("Presets");
+                if ui.button("Save").clicked() {
+                    self.save_preset_state = Some(Box::new(SavePresetState::default()));
+                }
+                ui.end_row();
             });
 
+        ui.add_space(24.0);
+        Self::show_automation(bridge, ui);
+
+        ui.add_space(24.0);
+        Self::show_midi_mappings(bridge, ui);
+
+        ui.add_space(24.0);
+        Self::show_module_load(bridge, ui);
+
         if let Some(mut state) = self.save_preset_state.take()
-            && self.show_save_preset_modal(ui, &mut state)
+            && Self::show_save_preset_modal(bridge, ui, &mut state)
         {
             self.save_preset_state.replace(state);
         }
-
-        if let Some(mut state) = self.load_preset_state.take()
-            && self.show_load_preset_modal(ui, &mut state)
-        {
-            self.load_preset_state.replace(state);
-        }
     }
 }
@@ -0,0 +1,310 @@
+use std::sync::Arc;
+
+use egui::{Button, Color32, ComboBox, Label, RichText, Sense, TextEdit, Ui};
+use egui_extras::{Column, TableBuilder};
+
+use crate::{
+    editor::{DetailViewKey, ModuleUi},
+    engine_factory::EngineFactory,
+    presets::{PresetListItem, Presets},
+    synth_engine::{ModuleId, ui_bridge::UiBridge},
+};
+
+#[derive(Clone, Copy, PartialEq)]
+enum SortOrder {
+    Title,
+    Category,
+}
+
+impl SortOrder {
+    fn label(&self) -> &'static str {
+        match self {
+            Self::Title => "Title",
+            Self::Category => "Category",
+        }
+    }
+}
+
+static SORT_OPTIONS: &[SortOrder] = &[SortOrder::Title, SortOrder::Category];
+
+pub struct PresetBrowserUi {
+    engine_factory: Arc<EngineFactory>,
+    presets: Vec<PresetListItem>,
+    search: String,
+    category: Option<String>,
+    sort_order: SortOrder,
+    selected_index: Option<usize>,
+    rename_title: Option<String>,
+    delete_confirmation: bool,
+    error: String,
+}
+
+impl PresetBrowserUi {
+    pub fn new(engine_factory: Arc<EngineFactory>) -> Self {
+        let mut browser = Self {
+            engine_factory,
+            presets: Vec::new(),
+            search: String::new(),
+            category: None,
+            sort_order: SortOrder::Title,
+            selected_index: None,
+            rename_title: None,
+            delete_confirmation: false,
+            error: String::new(),
+        };
+
+        browser.refresh();
+        browser
+    }
+
+    fn refresh(&mut self) {
+        self.presets = Presets::new()
+            .map(|presets| presets.read_presets_list())
+            .unwrap_or_default();
+        self.selected_index = None;
+        self.rename_title = None;
+        self.delete_confirmation = false;
+        self.sort();
+    }
+
+    fn sort(&mut self) {
+        match self.sort_order {
+            SortOrder::Title => self
+                .presets
+                .sort_by_key(|item| item.info.title.to_lowercase()),
+            SortOrder::Category => self.presets.sort_by_key(|item| {
+                (
+                    item.info.category.to_lowercase(),
+                    item.info.title.to_lowercase(),
+                )
+            }),
+        }
+
+        self.selected_index = None;
+    }
+
+    fn categories(&self) -> Vec<String> {
+        let mut categories: Vec<String> = self
+            .presets
+            .iter()
+            .map(|item| item.info.category.clone())
+            .filter(|category| !category.is_empty())
+            .collect();
+
+        categories.sort_by_key(|category| category.to_lowercase());
+        categories.dedup();
+        categories
+    }
+
+    fn is_visible(&self, item: &PresetListItem) -> bool {
+        self.category
+            .as_ref()
+            .is_none_or(|category| item.info.category == *category)
+            && item.matches(&self.search)
+    }
+
+    fn load_selected(&mut self) {
+        if let Some(item) = self.selected_index.and_then(|idx| self.presets.get(idx))
+            && let Some(preset) = Presets::read_preset(&item.source)
+            && self.engine_factory.load_preset(&preset)
+        {
+            self.error.clear();
+        } else {
+            self.error = "Failed to load preset.".into();
+        }
+    }
+
+    fn show_filters(&mut self, ui: &mut Ui) {
+        let categories = self.categories();
+
+        ui.horizontal(|ui| {
+            ui.add(
+                TextEdit::singleline(&mut self.search)
+                    .hint_text("Search")
+                    .desired_width(200.0),
+            );
+
+            ComboBox::from_id_salt("preset-category")
+                .selected_text(self.category.as_deref().unwrap_or("All categories"))
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut self.category, None, "All categories");
+
+                    for category in categories {
+                        let label = category.clone();
+
+                        ui.selectable_value(&mut self.category, Some(category), label);
+                    }
+                });
+
+            let sort_order = self.sort_order;
+
+            ComboBox::from_id_salt("preset-sort")
+                .selected_text(format!("Sort by {}", sort_order.label()))
+                .show_ui(ui, |ui| {
+                    for option in SORT_OPTIONS {
+                        ui.selectable_value(&mut self.sort_order, *option, option.label());
+                    }
+                });
+
+            if self.sort_order != sort_order {
+                self.sort();
+            }
+
+            if ui.button("Refresh").clicked() {
+                self.refresh();
+            }
+        });
+    }
+
+    fn show_list(&mut self, ui: &mut Ui) {
+        let mut load = false;
+
+        TableBuilder::new(ui)
+            .striped(true)
+            .resizable(false)
+            .sense(Sense::click())
+            .min_scrolled_height(0.0)
+            .max_scroll_height(300.0)
+            .column(Column::initial(200.0))
+            .column(Column::initial(120.0))
+            .column(Column::remainder())
+            .header(20.0, |mut header| {
+                header.col(|ui| {
+                    ui.strong("Title");
+                });
+                header.col(|ui| {
+                    ui.strong("Category");
+                });
+                header.col(|ui| {
+                    ui.strong("Tags");
+                });
+            })
+            .body(|mut body| {
+                for (idx, item) in self.presets.iter().enumerate() {
+                    if !self.is_visible(item) {
+                        continue;
+                    }
+
+                    body.row(18.0, |mut row| {
+                        row.set_selected(self.selected_index == Some(idx));
+
+                        row.col(|ui| {
+                            let title = if item.is_factory() {
+                                RichText::new(&item.info.title).italics()
+                            } else {
+                                RichText::new(&item.info.title)
+                            };
+
+                            ui.add(Label::new(title).selectable(false));
+                        });
+                        row.col(|ui| {
+                            ui.add(Label::new(&item.info.category).selectable(false));
+                        });
+                        row.col(|ui| {
+                            ui.add(Label::new(item.info.tags.join(", ")).selectable(false));
+                        });
+
+                        let response = row.response();
+
+                        if response.clicked() {
+                            self.selected_index = Some(idx);
+                            self.rename_title = None;
+                            self.delete_confirmation = false;
+                        }
+
+                        if response.double_clicked() {
+                            self.selected_index = Some(idx);
+                            load = true;
+                        }
+                    });
+                }
+            });
+
+        if load {
+            self.load_selected();
+        }
+    }
+
+    fn show_actions(&mut self, ui: &mut Ui) {
+        let selected = self.selected_index.and_then(|idx| self.presets.get(idx));
+        let editable = selected.is_some_and(|item| !item.is_factory());
+
+        ui.horizontal(|ui| {
+            if ui
+                .add_enabled(selected.is_some(), Button::new("Load"))
+                .clicked()
+            {
+                self.load_selected();
+            }
+
+            if let Some(title) = &mut self.rename_title {
+                ui.add(TextEdit::singleline(title).desired_width(160.0));
+
+                let trimmed = title.trim().to_string();
+
+                if ui
+                    .add_enabled(!trimmed.is_empty(), Button::new("Save"))
+                    .clicked()
+                {
+                    if let Some(item) = selected
+                        && let Some(presets) = Presets::new()
+                        && presets.rename_preset(&item.source, &trimmed).is_some()
+                    {
+                        self.refresh();
+                    } else {
+                        self.error = "Failed to rename preset.".into();
+                    }
+                } else if ui.button("Cancel").clicked() {
+                    self.rename_title = None;
+                }
+            } else if ui.add_enabled(editable, Button::new("Rename")).clicked()
+                && let Some(item) = selected
+            {
+                self.rename_title = Some(item.info.title.clone());
+            }
+
+            if self.delete_confirmation {
+                ui.label("Delete the preset?");
+
+                if ui.button("Confirm").clicked() {
+                    if let Some(item) = selected
+                        && Presets::delete_preset(&item.source).is_some()
+                    {
+                        self.refresh();
+                    } else {
+                        self.error = "Failed to delete preset.".into();
+                    }
+                } else if ui.button("Cancel").clicked() {
+                    self.delete_confirmation = false;
+                }
+            } else if ui.add_enabled(editable, Button::new("Delete")).clicked() {
+                self.delete_confirmation = true;
+            }
+        });
+
+        if !self.error.is_empty() {
+            ui.label(RichText::new(&self.error).color(Color32::RED));
+        }
+    }
+}
+
+impl ModuleUi for PresetBrowserUi {
+    fn module_id(&self) -> Option<ModuleId> {
+        None
+    }
+
+    fn detail_key(&self) -> DetailViewKey {
+        DetailViewKey::Presets
+    }
+
+    fn ui(&mut self, _bridge: &mut UiBridge, ui: &mut Ui) {
+        ui.heading("Presets");
+        ui.add_space(12.0);
+
+        self.show_filters(ui);
+        ui.add_space(8.0);
+        self.show_list(ui);
+        ui.add_space(8.0);
+        self.show_actions(ui);
+    }
+}
@@ -0,0 +1,124 @@
+use egui::{Grid, Ui};
+
+use crate::{
+    editor::{
+        ModuleUi, direct_input::DirectInput, modulation_input::ModulationInput,
+        module_label::ModuleLabel, utils::confirm_module_removal,
+    },
+    synth_engine::{
+        Input, ModuleId,
+        reverb::ReverbUiBridge,
+        ui_bridge::{ModuleBridge, UiBridge},
+    },
+};
+
+pub struct ReverbUi {
+    module_id: ModuleId,
+    remove_confirmation: bool,
+    label_state: Option<String>,
+}
+
+impl ReverbUi {
+    pub fn new(module_id: ModuleId) -> Self {
+        Self {
+            module_id,
+            remove_confirmation: false,
+            label_state: None,
+        }
+    }
+
+    fn paint_ui(&mut self, bridge: &mut UiBridge, reverb_bridge: &mut ReverbUiBridge, ui: &mut Ui) {
+        let module_id = self.module_id;
+        let mut config = reverb_bridge.config().clone();
+
+        ui.add(ModuleLabel::new(&mut self.label_state, bridge, module_id));
+
+        ui.add_space(20.0);
+
+        Grid::new("reverb_grid")
+            .num_columns(2)
+            .spacing([40.0, 24.0])
+            .striped(true)
+            .show(ui, |ui| {
+                ui.label("Input");
+                ui.add(DirectInput::new(bridge, Input::Audio, module_id));
+                ui.end_row();
+
+                ui.label("Size");
+                if ui
+                    .add(ModulationInput::new(
+                        &mut config.size,
+                        bridge,
+                        Input::Size,
+                        module_id,
+                    ))
+                    .changed()
+                {
+                    reverb_bridge.set_param(Input::Size, config.size);
+                }
+                ui.end_row();
+
+                ui.label("Damping");
+                if ui
+                    .add(ModulationInput::new(
+                        &mut config.damping,
+                        bridge,
+                        Input::Damping,
+                        module_id,
+                    ))
+                    .changed()
+                {
+                    reverb_bridge.set_param(Input::Damping, config.damping);
+                }
+                ui.end_row();
+
+                ui.label("Pre-delay");
+                if ui
+                    .add(ModulationInput::new(
+                        &mut config.pre_delay,
+                        bridge,
+                        Input::PreDelay,
+                        module_id,
+                    ))
+                    .changed()
+                {
+                    reverb_bridge.set_param(Input::PreDelay, config.pre_delay);
+                }
+                ui.end_row();
+
+                ui.label("Dry/Wet");
+                if ui
+                    .add(ModulationInput::new(
+                        &mut config.dry_wet,
+                        bridge,
+                        Input::DryWet,
+                        module_id,
+                    ))
+                    .changed()
+                {
+                    reverb_bridge.set_param(Input::DryWet, config.dry_wet);
+                }
+                ui.end_row();
+            });
+
+        ui.add_space(40.0);
+
+        if confirm_module_removal(ui, &mut self.remove_confirmation) {
+            bridge.remove_module(module_id);
+        }
+    }
+}
+
+impl ModuleUi for ReverbUi {
+    fn module_id(&self) -> Option<ModuleId> {
+        Some(self.module_id)
+    }
+
+    fn ui(&mut self, bridge: &mut UiBridge, ui: &mut Ui) {
+        bridge.with_module_bridge(self.module_id, |bridge, module_bridge| {
+            if let ModuleBridge::Reverb(reverb_bridge) = module_bridge {
+                self.paint_ui(bridge, reverb_bridge, ui);
+            }
+        });
+    }
+}
@@ -0,0 +1,86 @@
+use egui::{Grid, Ui};
+
+use crate::{
+    editor::{
+        ModuleUi, direct_input::DirectInput, modulation_input::ModulationInput,
+        module_label::ModuleLabel, utils::confirm_module_removal,
+    },
+    synth_engine::{
+        Input, ModuleId,
+        ring_mod::{CARRIER_INPUT, MODULATOR_INPUT, RingModUiBridge},
+        ui_bridge::{ModuleBridge, UiBridge},
+    },
+};
+
+pub struct RingModUi {
+    module_id: ModuleId,
+    remove_confirmation: bool,
+    label_state: Option<String>,
+}
+
+impl RingModUi {
+    pub fn new(module_id: ModuleId) -> Self {
+        Self {
+            module_id,
+            remove_confirmation: false,
+            label_state: None,
+        }
+    }
+
+    fn paint_ui(&mut self, bridge: &mut UiBridge, ring_bridge: &mut RingModUiBridge, ui: &mut Ui) {
+        let module_id = self.module_id;
+        let mut config = ring_bridge.config().clone();
+
+        ui.add(ModuleLabel::new(&mut self.label_state, bridge, module_id));
+
+        ui.add_space(20.0);
+
+        Grid::new("ring_mod_grid")
+            .num_columns(2)
+            .spacing([40.0, 24.0])
+            .striped(true)
+            .show(ui, |ui| {
+                ui.label("Audio 1");
+                ui.add(DirectInput::new(bridge, CARRIER_INPUT, module_id));
+                ui.end_row();
+
+                ui.label("Audio 2");
+                ui.add(DirectInput::new(bridge, MODULATOR_INPUT, module_id));
+                ui.end_row();
+
+                ui.label("Mix");
+                if ui
+                    .add(ModulationInput::new(
+                        &mut config.mix,
+                        bridge,
+                        Input::Mix,
+                        module_id,
+                    ))
+                    .changed()
+                {
+                    ring_bridge.set_param(Input::Mix, config.mix);
+                }
+                ui.end_row();
+            });
+
+        ui.add_space(40.0);
+
+        if confirm_module_removal(ui, &mut self.remove_confirmation) {
+            bridge.remove_module(module_id);
+        }
+    }
+}
+
+impl ModuleUi for RingModUi {
+    fn module_id(&self) -> Option<ModuleId> {
+        Some(self.module_id)
+    }
+
+    fn ui(&mut self, bridge: &mut UiBridge, ui: &mut Ui) {
+        bridge.with_module_bridge(self.module_id, |bridge, module_bridge| {
+            if let ModuleBridge::RingMod(ring_bridge) = module_bridge {
+                self.paint_ui(bridge, ring_bridge, ui);
+            }
+        });
+    }
+}
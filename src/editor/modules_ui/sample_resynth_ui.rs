@@ -0,0 +1,121 @@
+use egui::{Color32, Grid, RichText, Ui};
+use rfd::FileDialog;
+
+use crate::{
+    editor::{
+        ModuleUi, modulation_input::ModulationInput, module_label::ModuleLabel,
+        utils::confirm_module_removal,
+    },
+    synth_engine::{
+        Input, ModuleId,
+        sample_resynth::SampleResynthUiBridge,
+        ui_bridge::{ModuleBridge, UiBridge},
+    },
+};
+
+pub struct SampleResynthUi {
+    module_id: ModuleId,
+    remove_confirmation: bool,
+    label_state: Option<String>,
+    load_error: Option<String>,
+}
+
+impl SampleResynthUi {
+    pub fn new(module_id: ModuleId) -> Self {
+        Self {
+            module_id,
+            remove_confirmation: false,
+            label_state: None,
+            load_error: None,
+        }
+    }
+
+    fn paint_ui(
+        &mut self,
+        bridge: &mut UiBridge,
+        resynth_bridge: &mut SampleResynthUiBridge,
+        ui: &mut Ui,
+    ) {
+        let module_id = self.module_id;
+        let mut config = resynth_bridge.config().clone();
+
+        ui.add(ModuleLabel::new(&mut self.label_state, bridge, module_id));
+
+        ui.add_space(20.0);
+
+        Grid::new("sample_resynth_grid")
+            .num_columns(2)
+            .spacing([40.0, 24.0])
+            .striped(true)
+            .show(ui, |ui| {
+                ui.label("Sample");
+                ui.horizontal(|ui| {
+                    if ui
+                        .button("Load Sample")
+                        .on_hover_text("Analyze a pitched WAV file into harmonic frames")
+                        .clicked()
+                        && let Some(path) =
+                            FileDialog::new().add_filter("WAV", &["wav"]).pick_file()
+                    {
+                        self.load_error = resynth_bridge.load_sample(&path).err();
+                    }
+
+                    let file_name = config
+                        .path
+                        .as_ref()
+                        .and_then(|path| path.file_name())
+                        .map(|name| name.to_string_lossy().into_owned());
+
+                    match (file_name, resynth_bridge.info()) {
+                        (Some(name), Some(info)) => ui.label(format!(
+                            "{name}: {:.1} Hz, {} frames, {:.2} s",
+                            info.fundamental, info.num_frames, info.duration
+                        )),
+                        (Some(name), None) => ui.label(
+                            RichText::new(format!("{name} isn't loaded")).color(Color32::RED),
+                        ),
+                        _ => ui.label("No sample"),
+                    };
+                });
+                ui.end_row();
+
+                ui.label("Position");
+                if ui
+                    .add(ModulationInput::new(
+                        &mut config.position,
+                        bridge,
+                        Input::Position,
+                        module_id,
+                    ))
+                    .changed()
+                {
+                    resynth_bridge.set_param(Input::Position, config.position);
+                }
+                ui.end_row();
+            });
+
+        if let Some(error) = &self.load_error {
+            ui.label(RichText::new(error).color(Color32::RED));
+        }
+
+        ui.add_space(40.0);
+
+        if confirm_module_removal(ui, &mut self.remove_confirmation) {
+            bridge.remove_module(module_id);
+        }
+    }
+}
+
+impl ModuleUi for SampleResynthUi {
+    fn module_id(&self) -> Option<ModuleId> {
+        Some(self.module_id)
+    }
+
+    fn ui(&mut self, bridge: &mut UiBridge, ui: &mut Ui) {
+        bridge.with_module_bridge(self.module_id, |bridge, module_bridge| {
+            if let ModuleBridge::SampleResynth(resynth_bridge) = module_bridge {
+                self.paint_ui(bridge, resynth_bridge, ui);
+            }
+        });
+    }
+}
@@ -0,0 +1,233 @@
+use std::collections::VecDeque;
+
+use egui::{Checkbox, Color32, ComboBox, Painter, Pos2, Rect, Sense, Slider, Stroke, Ui, vec2};
+
+use crate::{
+    editor::{DetailViewKey, ModuleUi},
+    synth_engine::{ModuleId, Sample, scope::ScopeFrame, ui_bridge::UiBridge},
+};
+
+const MIN_TIME_SCALE: f32 = 1.0;
+const MAX_TIME_SCALE: f32 = 200.0;
+const SCOPE_HEIGHT: f32 = 160.0;
+const BG_COLOR: Color32 = Color32::from_rgb(0x1d, 0x21, 0x25);
+const LEFT_COLOR: Color32 = Color32::from_rgb(0xaa, 0x88, 0xff);
+const RIGHT_COLOR: Color32 = Color32::from_rgb(0x66, 0xcc, 0xaa);
+const LINE_WIDTH: f32 = 1.5;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Trigger {
+    Off,
+    Rising,
+    Falling,
+}
+
+impl Trigger {
+    fn label(&self) -> &'static str {
+        match self {
+            Self::Off => "Free Running",
+            Self::Rising => "Rising Edge",
+            Self::Falling => "Falling Edge",
+        }
+    }
+}
+
+static TRIGGER_OPTIONS: &[Trigger] = &[Trigger::Off, Trigger::Rising, Trigger::Falling];
+
+pub struct ScopeUi {
+    history: VecDeque<ScopeFrame>,
+    trigger: Trigger,
+    trigger_level: f32,
+    time_scale: f32,
+    freeze: bool,
+}
+
+impl ScopeUi {
+    pub fn new() -> Self {
+        Self {
+            history: VecDeque::new(),
+            trigger: Trigger::Rising,
+            trigger_level: 0.0,
+            time_scale: 20.0,
+            freeze: false,
+        }
+    }
+
+    fn is_triggered(trigger: Trigger, level: Sample, prev: Sample, next: Sample) -> bool {
+        match trigger {
+            Trigger::Off => true,
+            Trigger::Rising => prev < level && next >= level,
+            Trigger::Falling => prev > level && next <= level,
+        }
+    }
+
+    // Start of the latest `window` frames beginning at a trigger crossing of the left channel.
+    // Falls back to the latest frames when there's no crossing.
+    fn find_window_start(
+        history: &VecDeque<ScopeFrame>,
+        window: usize,
+        trigger: Trigger,
+        level: Sample,
+    ) -> usize {
+        let latest = history.len().saturating_sub(window);
+
+        if trigger == Trigger::Off {
+            return latest;
+        }
+
+        (1..=latest)
+            .rev()
+            .find(|&idx| Self::is_triggered(trigger, level, history[idx - 1][0], history[idx][0]))
+            .unwrap_or(latest)
+    }
+
+    fn paint_channel(painter: &Painter, rect: Rect, samples: &[Sample], color: Color32) {
+        if samples.len() < 2 {
+            return;
+        }
+
+        let columns = (rect.width().ceil() as usize).clamp(2, samples.len());
+        let sample_to_y =
+            |sample: Sample| rect.center().y - sample.clamp(-1.0, 1.0) * rect.height() * 0.5;
+        let mut points = Vec::with_capacity(2 * columns);
+
+        // Each column spans several samples on long time scales, its min/max keeps peaks visible
+        for column in 0..columns {
+            let from = column * samples.len() / columns;
+            let to = ((column + 1) * samples.len() / columns).max(from + 1);
+            let x = rect.left() + rect.width() * column as f32 / (columns - 1) as f32;
+            let (min, max) = samples[from..to]
+                .iter()
+                .fold((Sample::MAX, Sample::MIN), |(min, max), &sample| {
+                    (min.min(sample), max.max(sample))
+                });
+
+            points.push(Pos2::new(x, sample_to_y(max)));
+
+            if min < max {
+                points.push(Pos2::new(x, sample_to_y(min)));
+            }
+        }
+
+        painter.line(points, Stroke::new(LINE_WIDTH, color));
+    }
+
+    fn show_controls(&mut self, ui: &mut Ui) {
+        ui.horizontal(|ui| {
+            ComboBox::from_id_salt("scope-trigger")
+                .selected_text(self.trigger.label())
+                .show_ui(ui, |ui| {
+                    for option in TRIGGER_OPTIONS {
+                        ui.selectable_value(&mut self.trigger, *option, option.label());
+                    }
+                });
+
+            ui.add_enabled(
+                self.trigger != Trigger::Off,
+                Slider::new(&mut self.trigger_level, -1.0..=1.0).text("Level"),
+            );
+
+            ui.add(
+                Slider::new(&mut self.time_scale, MIN_TIME_SCALE..=MAX_TIME_SCALE)
+                    .logarithmic(true)
+                    .suffix(" ms")
+                    .text("Time"),
+            );
+
+            ui.add(Checkbox::new(&mut self.freeze, "Freeze"));
+        });
+    }
+}
+
+impl ModuleUi for ScopeUi {
+    fn module_id(&self) -> Option<ModuleId> {
+        None
+    }
+
+    fn detail_key(&self) -> DetailViewKey {
+        DetailViewKey::Scope
+    }
+
+    fn ui(&mut self, bridge: &mut UiBridge, ui: &mut Ui) {
+        ui.heading("Oscilloscope");
+        ui.add_space(12.0);
+
+        self.show_controls(ui);
+        ui.add_space(8.0);
+
+        let sample_rate = bridge.scope_sample_rate().unwrap_or(44100.0);
+        // Twice the longest window, so a trigger point is found for any time scale
+        let capacity = (2.0 * MAX_TIME_SCALE * 0.001 * sample_rate) as usize;
+
+        // Stopping the tap while frozen, the stale audio is dropped when it's resumed
+        bridge.set_scope_active(!self.freeze);
+
+        if !self.freeze {
+            bridge.read_scope(&mut self.history, capacity);
+            ui.ctx().request_repaint();
+        }
+
+        let window = ((self.time_scale * 0.001 * sample_rate) as usize).max(2);
+        let start =
+            Self::find_window_start(&self.history, window, self.trigger, self.trigger_level);
+        let end = (start + window).min(self.history.len());
+
+        let (rect, _) =
+            ui.allocate_exact_size(vec2(ui.available_width(), SCOPE_HEIGHT), Sense::hover());
+        let painter = ui.painter_at(rect);
+
+        painter.rect_filled(rect, 0.0, BG_COLOR);
+        painter.hline(
+            rect.x_range(),
+            rect.center().y,
+            Stroke::new(1.0, Color32::from_rgba_unmultiplied(255, 255, 255, 32)),
+        );
+
+        for (channel, color) in [(1, RIGHT_COLOR), (0, LEFT_COLOR)] {
+            let samples: Vec<Sample> = self
+                .history
+                .range(start..end)
+                .map(|frame| frame[channel])
+                .collect();
+
+            Self::paint_channel(&painter, rect, &samples, color);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn history(samples: &[Sample]) -> VecDeque<ScopeFrame> {
+        samples.iter().map(|&s| [s, s]).collect()
+    }
+
+    #[test]
+    fn window_starts_at_latest_crossing() {
+        let history = history(&[-1.0, 1.0, -1.0, 1.0, -1.0, -1.0, -1.0]);
+
+        assert_eq!(
+            ScopeUi::find_window_start(&history, 4, Trigger::Rising, 0.0),
+            3
+        );
+        assert_eq!(
+            ScopeUi::find_window_start(&history, 4, Trigger::Falling, 0.0),
+            2
+        );
+        assert_eq!(
+            ScopeUi::find_window_start(&history, 4, Trigger::Off, 0.0),
+            3
+        );
+    }
+
+    #[test]
+    fn window_without_crossing_shows_latest_frames() {
+        let history = history(&[0.5; 10]);
+
+        assert_eq!(
+            ScopeUi::find_window_start(&history, 4, Trigger::Rising, 0.0),
+            6
+        );
+    }
+}
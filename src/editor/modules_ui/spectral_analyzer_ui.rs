@@ -0,0 +1,110 @@
+use egui::{Grid, Ui};
+
+use crate::{
+    editor::{
+        ModuleUi, module_label::ModuleLabel, stereo_slider::StereoSlider,
+        utils::confirm_module_removal,
+    },
+    synth_engine::{
+        ModuleId, StereoSample,
+        spectral_analyzer::{MAX_ANALYZER_GAIN, MAX_SMOOTHING, SpectralAnalyzerUiBridge},
+        ui_bridge::{ModuleBridge, UiBridge},
+    },
+};
+
+pub struct SpectralAnalyzerUi {
+    module_id: ModuleId,
+    remove_confirmation: bool,
+    label_state: Option<String>,
+}
+
+impl SpectralAnalyzerUi {
+    pub fn new(module_id: ModuleId) -> Self {
+        Self {
+            module_id,
+            remove_confirmation: false,
+            label_state: None,
+        }
+    }
+
+    fn paint_ui(
+        &mut self,
+        bridge: &mut UiBridge,
+        analyzer_bridge: &mut SpectralAnalyzerUiBridge,
+        ui: &mut Ui,
+    ) {
+        let module_id = self.module_id;
+        let config = analyzer_bridge.config().clone();
+
+        ui.add(ModuleLabel::new(&mut self.label_state, bridge, module_id));
+
+        ui.add_space(20.0);
+
+        ui.label("Follows the spectrum of the plugin sidechain input.");
+        ui.label("Multiply a spectrum with it in a Spectral Mixer for a vocoder.");
+
+        ui.add_space(20.0);
+
+        Grid::new("spectral-analyzer-grid")
+            .num_columns(2)
+            .spacing([40.0, 24.0])
+            .striped(true)
+            .show(ui, |ui| {
+                let mut gain = StereoSample::splat(config.gain);
+
+                ui.label("Gain");
+                if ui
+                    .add(
+                        StereoSlider::new(&mut gain)
+                            .channels_link(bridge, module_id, "Gain")
+                            .range(-MAX_ANALYZER_GAIN..=MAX_ANALYZER_GAIN)
+                            .default_value(0.0)
+                            .precision(1)
+                            .units(" dB"),
+                    )
+                    .changed()
+                {
+                    analyzer_bridge.set_gain(gain.left());
+                }
+                ui.end_row();
+
+                let mut smoothing = StereoSample::splat(config.smoothing);
+
+                ui.label("Smoothing");
+                if ui
+                    .add(
+                        StereoSlider::new(&mut smoothing)
+                            .channels_link(bridge, module_id, "Smoothing")
+                            .range(0.0..=MAX_SMOOTHING)
+                            .default_value(1.0 / 3.0)
+                            .precision(2)
+                            .units(" oct"),
+                    )
+                    .changed()
+                {
+                    analyzer_bridge.set_smoothing(smoothing.left());
+                }
+                ui.end_row();
+            });
+
+        ui.add_space(40.0);
+
+        if confirm_module_removal(ui, &mut self.remove_confirmation) {
+            bridge.remove_module(module_id);
+        }
+    }
+}
+
+impl ModuleUi for SpectralAnalyzerUi {
+    fn module_id(&self) -> Option<ModuleId> {
+        Some(self.module_id)
+    }
+
+    fn ui(&mut self, bridge: &mut UiBridge, ui: &mut Ui) {
+        bridge.with_module_bridge(self.module_id, |bridge, module_bridge| {
+            if let ModuleBridge::SpectralAnalyzer(analyzer_bridge) = module_bridge {
+                self.paint_ui(bridge, analyzer_bridge, ui);
+            }
+        });
+    }
+}
@@ -1,4 +1,4 @@
-use egui::{Grid, Ui};
+use egui::{Checkbox, ComboBox, Grid, Ui};
 
 use crate::{
     editor::{
@@ -6,11 +6,23 @@ use crate::{
         module_label::ModuleLabel, utils::confirm_module_removal,
     },
     synth_engine::{
-        Input, ModuleId, spectral_blend::SpectralBlendUiBridge,
+        BlendMode, Input, ModuleId,
+        spectral_blend::SpectralBlendUiBridge,
         ui_bridge::{ModuleBridge, UiBridge},
     },
 };
 
+impl BlendMode {
+    fn label(&self) -> &'static str {
+        match self {
+            Self::Linear => "Linear",
+            Self::LogMagnitude => "Log Magnitude",
+        }
+    }
+}
+
+static MODE_OPTIONS: &[BlendMode] = &[BlendMode::Linear, BlendMode::LogMagnitude];
+
 pub struct SpectralBlendUi {
     module_id: ModuleId,
     remove_confirmation: bool,
@@ -56,6 +68,21 @@ impl SpectralBlendUi {
                 ui.add(DirectInput::new(bridge, Input::SpectrumTo, module_id));
                 ui.end_row();
 
+                ui.label("Mode");
+                ComboBox::from_id_salt("blend-mode-select")
+                    .selected_text(config.mode.label())
+                    .show_ui(ui, |ui| {
+                        for mode in MODE_OPTIONS {
+                            if ui
+                                .selectable_label(config.mode == *mode, mode.label())
+                                .clicked()
+                            {
+                                blend_bridge.set_mode(*mode);
+                            }
+                        }
+                    });
+                ui.end_row();
+
                 ui.label("Blend");
                 if ui
                     .add(ModulationInput::new(
@@ -69,6 +96,42 @@ impl SpectralBlendUi {
                     blend_bridge.set_param(Input::Blend, config.blend);
                 }
                 ui.end_row();
+
+                ui.label("Split");
+                if ui.add(Checkbox::without_text(&mut config.split)).changed() {
+                    blend_bridge.set_split(config.split);
+                }
+                ui.end_row();
+
+                if config.split {
+                    ui.label("Split Harmonic");
+                    if ui
+                        .add(ModulationInput::new(
+                            &mut config.split_harmonic,
+                            bridge,
+                            Input::SplitHarmonic,
+                            module_id,
+                        ))
+                        .changed()
+                    {
+                        blend_bridge.set_param(Input::SplitHarmonic, config.split_harmonic);
+                    }
+                    ui.end_row();
+
+                    ui.label("Crossover");
+                    if ui
+                        .add(ModulationInput::new(
+                            &mut config.crossover_width,
+                            bridge,
+                            Input::CrossoverWidth,
+                            module_id,
+                        ))
+                        .changed()
+                    {
+                        blend_bridge.set_param(Input::CrossoverWidth, config.crossover_width);
+                    }
+                    ui.end_row();
+                }
             });
 
         ui.add_space(40.0);
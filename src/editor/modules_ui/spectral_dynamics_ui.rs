@@ -0,0 +1,115 @@
+use egui::{Grid, Ui};
+
+use crate::{
+    editor::{
+        ModuleUi, direct_input::DirectInput, modulation_input::ModulationInput,
+        module_label::ModuleLabel, utils::confirm_module_removal,
+    },
+    synth_engine::{
+        Input, ModuleId,
+        spectral_dynamics::SpectralDynamicsUiBridge,
+        ui_bridge::{ModuleBridge, UiBridge},
+    },
+};
+
+pub struct SpectralDynamicsUi {
+    module_id: ModuleId,
+    remove_confirmation: bool,
+    label_state: Option<String>,
+}
+
+impl SpectralDynamicsUi {
+    pub fn new(module_id: ModuleId) -> Self {
+        Self {
+            module_id,
+            remove_confirmation: false,
+            label_state: None,
+        }
+    }
+
+    fn paint_ui(
+        &mut self,
+        bridge: &mut UiBridge,
+        dynamics_bridge: &mut SpectralDynamicsUiBridge,
+        ui: &mut Ui,
+    ) {
+        let module_id = self.module_id;
+        let mut config = dynamics_bridge.config().clone();
+
+        ui.add(ModuleLabel::new(&mut self.label_state, bridge, module_id));
+
+        ui.add_space(20.0);
+
+        Grid::new("spectral_dynamics_grid")
+            .num_columns(2)
+            .spacing([40.0, 24.0])
+            .striped(true)
+            .show(ui, |ui| {
+                ui.label("Input");
+                ui.add(DirectInput::new(bridge, Input::Spectrum, module_id));
+                ui.end_row();
+
+                ui.label("Threshold");
+                if ui
+                    .add(ModulationInput::new(
+                        &mut config.threshold,
+                        bridge,
+                        Input::Threshold,
+                        module_id,
+                    ))
+                    .changed()
+                {
+                    dynamics_bridge.set_param(Input::Threshold, config.threshold);
+                }
+                ui.end_row();
+
+                ui.label("Ratio");
+                if ui
+                    .add(ModulationInput::new(
+                        &mut config.ratio,
+                        bridge,
+                        Input::Ratio,
+                        module_id,
+                    ))
+                    .changed()
+                {
+                    dynamics_bridge.set_param(Input::Ratio, config.ratio);
+                }
+                ui.end_row();
+
+                ui.label("Makeup");
+                if ui
+                    .add(ModulationInput::new(
+                        &mut config.makeup,
+                        bridge,
+                        Input::Makeup,
+                        module_id,
+                    ))
+                    .changed()
+                {
+                    dynamics_bridge.set_param(Input::Makeup, config.makeup);
+                }
+                ui.end_row();
+            });
+
+        ui.add_space(40.0);
+
+        if confirm_module_removal(ui, &mut self.remove_confirmation) {
+            bridge.remove_module(module_id);
+        }
+    }
+}
+
+impl ModuleUi for SpectralDynamicsUi {
+    fn module_id(&self) -> Option<ModuleId> {
+        Some(self.module_id)
+    }
+
+    fn ui(&mut self, bridge: &mut UiBridge, ui: &mut Ui) {
+        bridge.with_module_bridge(self.module_id, |bridge, module_bridge| {
+            if let ModuleBridge::SpectralDynamics(dynamics_bridge) = module_bridge {
+                self.paint_ui(bridge, dynamics_bridge, ui);
+            }
+        });
+    }
+}
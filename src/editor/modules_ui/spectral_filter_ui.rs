@@ -1,4 +1,4 @@
-use egui::{Checkbox, ComboBox, Grid, Ui};
+use egui::{Checkbox, ComboBox, Grid, Slider, Ui};
 
 use crate::{
     editor::{
@@ -6,7 +6,8 @@ use crate::{
         module_label::ModuleLabel, utils::confirm_module_removal,
     },
     synth_engine::{
-        Input, ModuleId, SpectralFilterType, spectral_filter::SpectralFilterUiBridge,
+        Input, ModuleId, SpectralFilterType,
+        spectral_filter::{MAX_TRACKING, SpectralFilterUiBridge},
         ui_bridge::{ModuleBridge, UiBridge},
     },
 };
@@ -150,6 +151,33 @@ impl SpectralFilterUI {
                     filter_bridge.set_linear_phase(config.linear_phase);
                 }
                 ui.end_row();
+
+                ui.label("Key tracking");
+                if ui
+                    .add(
+                        Slider::new(&mut config.key_tracking, 0.0..=MAX_TRACKING).suffix(" dB/oct"),
+                    )
+                    .on_hover_text(
+                        "Rolls off the harmonics of notes above middle C, per octave of the note",
+                    )
+                    .changed()
+                {
+                    filter_bridge.set_key_tracking(config.key_tracking);
+                }
+                ui.end_row();
+
+                ui.label("Velocity tracking");
+                if ui
+                    .add(
+                        Slider::new(&mut config.velocity_tracking, 0.0..=MAX_TRACKING)
+                            .suffix(" dB/oct"),
+                    )
+                    .on_hover_text("Rolls off the harmonics of soft notes, none at full velocity")
+                    .changed()
+                {
+                    filter_bridge.set_velocity_tracking(config.velocity_tracking);
+                }
+                ui.end_row();
             });
 
         ui.add_space(40.0);
@@ -0,0 +1,101 @@
+use egui::{Grid, Ui};
+
+use crate::{
+    editor::{
+        ModuleUi, direct_input::DirectInput, modulation_input::ModulationInput,
+        module_label::ModuleLabel, utils::confirm_module_removal,
+    },
+    synth_engine::{
+        Input, ModuleId,
+        spectral_tilt::SpectralTiltUiBridge,
+        ui_bridge::{ModuleBridge, UiBridge},
+    },
+};
+
+pub struct SpectralTiltUi {
+    module_id: ModuleId,
+    remove_confirmation: bool,
+    label_state: Option<String>,
+}
+
+impl SpectralTiltUi {
+    pub fn new(module_id: ModuleId) -> Self {
+        Self {
+            module_id,
+            remove_confirmation: false,
+            label_state: None,
+        }
+    }
+
+    fn paint_ui(
+        &mut self,
+        bridge: &mut UiBridge,
+        tilt_bridge: &mut SpectralTiltUiBridge,
+        ui: &mut Ui,
+    ) {
+        let module_id = self.module_id;
+        let mut config = tilt_bridge.config().clone();
+
+        ui.add(ModuleLabel::new(&mut self.label_state, bridge, module_id));
+
+        ui.add_space(20.0);
+
+        Grid::new("spectral_tilt_grid")
+            .num_columns(2)
+            .spacing([40.0, 24.0])
+            .striped(true)
+            .show(ui, |ui| {
+                ui.label("Input");
+                ui.add(DirectInput::new(bridge, Input::Spectrum, module_id));
+                ui.end_row();
+
+                ui.label("Tilt");
+                if ui
+                    .add(ModulationInput::new(
+                        &mut config.tilt,
+                        bridge,
+                        Input::Tilt,
+                        module_id,
+                    ))
+                    .changed()
+                {
+                    tilt_bridge.set_param(Input::Tilt, config.tilt);
+                }
+                ui.end_row();
+
+                ui.label("Pivot");
+                if ui
+                    .add(ModulationInput::new(
+                        &mut config.pivot,
+                        bridge,
+                        Input::Pivot,
+                        module_id,
+                    ))
+                    .changed()
+                {
+                    tilt_bridge.set_param(Input::Pivot, config.pivot);
+                }
+                ui.end_row();
+            });
+
+        ui.add_space(40.0);
+
+        if confirm_module_removal(ui, &mut self.remove_confirmation) {
+            bridge.remove_module(module_id);
+        }
+    }
+}
+
+impl ModuleUi for SpectralTiltUi {
+    fn module_id(&self) -> Option<ModuleId> {
+        Some(self.module_id)
+    }
+
+    fn ui(&mut self, bridge: &mut UiBridge, ui: &mut Ui) {
+        bridge.with_module_bridge(self.module_id, |bridge, module_bridge| {
+            if let ModuleBridge::SpectralTilt(tilt_bridge) = module_bridge {
+                self.paint_ui(bridge, tilt_bridge, ui);
+            }
+        });
+    }
+}
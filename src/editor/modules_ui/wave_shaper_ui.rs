@@ -1,4 +1,4 @@
-use egui::{ComboBox, Grid, Ui};
+use egui::{Checkbox, ComboBox, Grid, Ui};
 
 use crate::{
     editor::{
@@ -15,7 +15,9 @@ impl ShaperType {
     fn label(&self) -> &'static str {
         match self {
             Self::HardClip => "Hard Clip",
-            Self::Sigmoid => "Sigmoid",
+            Self::Sigmoid => "Tanh",
+            Self::Fold => "Fold",
+            Self::Asymmetric => "Asymmetric",
         }
     }
 }
@@ -65,8 +67,12 @@ impl WaveShaperUi {
                 ComboBox::from_id_salt("waveshaper-type")
                     .selected_text(config.shaper_type.label())
                     .show_ui(ui, |ui| {
-                        const TYPE_OPTIONS: &[ShaperType] =
-                            &[ShaperType::HardClip, ShaperType::Sigmoid];
+                        const TYPE_OPTIONS: &[ShaperType] = &[
+                            ShaperType::HardClip,
+                            ShaperType::Sigmoid,
+                            ShaperType::Fold,
+                            ShaperType::Asymmetric,
+                        ];
 
                         for shaper_type in TYPE_OPTIONS {
                             if ui
@@ -110,6 +116,26 @@ impl WaveShaperUi {
                     shaper_bridge.set_param(Input::ClippingLevel, config.clipping_level);
                 }
                 ui.end_row();
+
+                ui.label("Output level");
+                if ui
+                    .add(ModulationInput::new(
+                        &mut config.level,
+                        bridge,
+                        Input::Level,
+                        module_id,
+                    ))
+                    .changed()
+                {
+                    shaper_bridge.set_param(Input::Level, config.level);
+                }
+                ui.end_row();
+
+                ui.label("Oversampling");
+                if ui.add(Checkbox::new(&mut config.oversampling, "2x")).changed() {
+                    shaper_bridge.set_oversampling(config.oversampling);
+                }
+                ui.end_row();
             });
 
         ui.add_space(40.0);
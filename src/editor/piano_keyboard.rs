@@ -0,0 +1,192 @@
+use egui::{Button, Color32, Pos2, Rect, Sense, Stroke, StrokeKind, Ui, pos2, vec2};
+
+use crate::synth_engine::{Sample, ui_bridge::UiBridge};
+
+const OCTAVES: u8 = 4;
+const KEYBOARD_HEIGHT: f32 = 64.0;
+const BLACK_KEY_WIDTH: f32 = 0.6; // Of a white key
+const BLACK_KEY_HEIGHT: f32 = 0.6;
+// The highest C the keyboard can start from with all of its keys in the MIDI range
+const MAX_LOWEST_NOTE: u8 = (127 - 12 * OCTAVES) / 12 * 12;
+const WHITE_KEY_COLOR: Color32 = Color32::from_rgb(0xe8, 0xe8, 0xe8);
+const BLACK_KEY_COLOR: Color32 = Color32::from_rgb(0x20, 0x20, 0x20);
+const HELD_KEY_COLOR: Color32 = Color32::from_rgb(0x0b, 0x42, 0x67);
+const KEY_BORDER_COLOR: Color32 = Color32::from_rgb(0x60, 0x60, 0x60);
+
+// Semitones of the white keys in an octave
+const WHITE_KEYS: [u8; 7] = [0, 2, 4, 5, 7, 9, 11];
+// Semitones of the black keys with the white key they sit after
+const BLACK_KEYS: [(u8, usize); 5] = [(1, 0), (3, 1), (6, 3), (8, 4), (10, 5)];
+
+pub struct PianoKeyboard {
+    lowest_note: u8,
+    held_note: Option<u8>,
+}
+
+impl PianoKeyboard {
+    pub fn new() -> Self {
+        Self {
+            lowest_note: 48,
+            held_note: None,
+        }
+    }
+
+    // C4 is the middle C, MIDI note 60
+    fn note_name(note: u8) -> String {
+        format!("C{}", i32::from(note / 12) - 1)
+    }
+
+    fn white_keys_count() -> usize {
+        WHITE_KEYS.len() * usize::from(OCTAVES) + 1
+    }
+
+    fn white_key_rect(rect: Rect, key_width: f32, idx: usize) -> Rect {
+        Rect::from_min_size(
+            pos2(rect.left() + idx as f32 * key_width, rect.top()),
+            vec2(key_width, rect.height()),
+        )
+    }
+
+    fn black_key_rect(rect: Rect, key_width: f32, white_idx: usize) -> Rect {
+        let width = key_width * BLACK_KEY_WIDTH;
+
+        Rect::from_min_size(
+            pos2(
+                rect.left() + (white_idx + 1) as f32 * key_width - 0.5 * width,
+                rect.top(),
+            ),
+            vec2(width, rect.height() * BLACK_KEY_HEIGHT),
+        )
+    }
+
+    fn white_keys(&self, rect: Rect, key_width: f32) -> impl Iterator<Item = (u8, Rect)> {
+        let lowest_note = self.lowest_note;
+
+        (0..Self::white_keys_count()).map(move |idx| {
+            let note = lowest_note
+                + 12 * (idx / WHITE_KEYS.len()) as u8
+                + WHITE_KEYS[idx % WHITE_KEYS.len()];
+
+            (note, Self::white_key_rect(rect, key_width, idx))
+        })
+    }
+
+    fn black_keys(&self, rect: Rect, key_width: f32) -> impl Iterator<Item = (u8, Rect)> {
+        let lowest_note = self.lowest_note;
+
+        (0..OCTAVES).flat_map(move |octave| {
+            BLACK_KEYS.iter().map(move |(semitone, white_idx)| {
+                (
+                    lowest_note + 12 * octave + semitone,
+                    Self::black_key_rect(
+                        rect,
+                        key_width,
+                        usize::from(octave) * WHITE_KEYS.len() + white_idx,
+                    ),
+                )
+            })
+        })
+    }
+
+    // Black keys are on top, so they're checked first. The velocity grows towards the front of a key.
+    fn note_at(&self, rect: Rect, key_width: f32, pos: Pos2) -> Option<(u8, Sample)> {
+        self.black_keys(rect, key_width)
+            .chain(self.white_keys(rect, key_width))
+            .find(|(_, key_rect)| key_rect.contains(pos))
+            .map(|(note, key_rect)| {
+                let velocity = (pos.y - key_rect.top()) / key_rect.height();
+
+                (note, velocity.clamp(0.05, 1.0))
+            })
+    }
+
+    // Kept held while the queue is full, the next frame tries again
+    pub fn release(&mut self, bridge: &mut UiBridge) {
+        if let Some(note) = self.held_note
+            && bridge.note_off(note)
+        {
+            self.held_note = None;
+        }
+    }
+
+    fn octave_controls(&mut self, ui: &mut Ui, bridge: &mut UiBridge) {
+        ui.vertical(|ui| {
+            if ui
+                .add_enabled(self.lowest_note < MAX_LOWEST_NOTE, Button::new("▲"))
+                .on_hover_text("Octave up")
+                .clicked()
+            {
+                self.release(bridge);
+                self.lowest_note = (self.lowest_note + 12).min(MAX_LOWEST_NOTE);
+            }
+
+            ui.label(Self::note_name(self.lowest_note));
+
+            if ui
+                .add_enabled(self.lowest_note > 0, Button::new("▼"))
+                .on_hover_text("Octave down")
+                .clicked()
+            {
+                self.release(bridge);
+                self.lowest_note = self.lowest_note.saturating_sub(12);
+            }
+        });
+    }
+
+    pub fn ui(&mut self, ui: &mut Ui, bridge: &mut UiBridge) {
+        ui.horizontal(|ui| {
+            self.octave_controls(ui, bridge);
+
+            let (rect, response) = ui.allocate_exact_size(
+                vec2(ui.available_width(), KEYBOARD_HEIGHT),
+                Sense::click_and_drag(),
+            );
+            let key_width = rect.width() / Self::white_keys_count() as f32;
+
+            // Dragging across the keys plays them one after another
+            let pressed = response
+                .interact_pointer_pos()
+                .filter(|_| response.is_pointer_button_down_on())
+                .and_then(|pos| self.note_at(rect, key_width, pos));
+
+            match pressed {
+                Some((note, _)) if self.held_note == Some(note) => (),
+                Some((note, velocity)) => {
+                    self.release(bridge);
+
+                    if self.held_note.is_none() && bridge.note_on(note, velocity) {
+                        self.held_note = Some(note);
+                    }
+                }
+                None => self.release(bridge),
+            }
+
+            if !ui.is_rect_visible(rect) {
+                return;
+            }
+
+            let painter = ui.painter();
+            let key_color = |note: u8, color: Color32| {
+                if self.held_note == Some(note) {
+                    HELD_KEY_COLOR
+                } else {
+                    color
+                }
+            };
+
+            for (note, key_rect) in self.white_keys(rect, key_width) {
+                painter.rect_filled(key_rect, 0.0, key_color(note, WHITE_KEY_COLOR));
+                painter.rect_stroke(
+                    key_rect,
+                    0.0,
+                    Stroke::new(1.0, KEY_BORDER_COLOR),
+                    StrokeKind::Inside,
+                );
+            }
+
+            for (note, key_rect) in self.black_keys(rect, key_width) {
+                painter.rect_filled(key_rect, 0.0, key_color(note, BLACK_KEY_COLOR));
+            }
+        });
+    }
+}
@@ -1,6 +1,6 @@
 use egui::{Color32, ecolor::Hsva};
 
-use crate::synth_engine::{DataType, Input};
+use crate::synth_engine::{DataType, Input, ui_bridge::ModuleColor};
 
 const IO_COLOR_S: f32 = 0.8;
 const IO_COLOR_V: f32 = 0.5;
@@ -49,6 +49,36 @@ impl Input {
             Self::Decay => "Decay".to_string(),
             Self::Sustain => "Sustain".to_string(),
             Self::Release => "Release".to_string(),
+            Self::Size => "Size".to_string(),
+            Self::Damping => "Damping".to_string(),
+            Self::PreDelay => "Pre-delay".to_string(),
+            Self::DryWet => "Dry/Wet".to_string(),
+            Self::Mix => "Mix".to_string(),
+            Self::Tilt => "Tilt".to_string(),
+            Self::Pivot => "Pivot".to_string(),
+            Self::Stretch => "Stretch".to_string(),
+            Self::HarmonicShift => "Shift".to_string(),
+            Self::Threshold => "Threshold".to_string(),
+            Self::Ratio => "Ratio".to_string(),
+            Self::Makeup => "Makeup".to_string(),
+            Self::DecaySpread => "Decay Spread".to_string(),
+            Self::Position => "Position".to_string(),
+            Self::PhaseMod => "Phase Mod".to_string(),
+            Self::FmIndex => "FM Index".to_string(),
+            Self::Feedback => "Feedback".to_string(),
+            Self::Pan => "Pan".to_string(),
+            Self::Width => "Width".to_string(),
+            Self::ValueA => "Value A".to_string(),
+            Self::ValueB => "Value B".to_string(),
+            Self::Partials => "Partials".to_string(),
+            Self::Rolloff => "Rolloff".to_string(),
+            Self::CombSpacing => "Comb Spacing".to_string(),
+            Self::EvenOdd => "Even/Odd".to_string(),
+            Self::SplitHarmonic => "Split".to_string(),
+            Self::CrossoverWidth => "Crossover".to_string(),
+            Self::FadeIn => "Fade In".to_string(),
+            Self::Drift => "Drift".to_string(),
+            Self::DriftRate => "Drift Rate".to_string(),
         }
     }
 
@@ -86,6 +116,36 @@ impl Input {
             Self::Decay => 0.48,
             Self::Sustain => 0.43,
             Self::Release => 0.38,
+            Self::Size => 0.92,
+            Self::Damping => 0.95,
+            Self::PreDelay => 0.22,
+            Self::DryWet => 0.25,
+            Self::Mix => 0.27,
+            Self::Tilt => 0.62,
+            Self::Pivot => 0.60,
+            Self::Stretch => 0.81,
+            Self::HarmonicShift => 0.65,
+            Self::Threshold => 0.08,
+            Self::Ratio => 0.12,
+            Self::Makeup => 0.16,
+            Self::DecaySpread => 0.50,
+            Self::Position => 0.56,
+            Self::PhaseMod => 0.03,
+            Self::FmIndex => 0.77,
+            Self::Feedback => 0.75,
+            Self::Pan => 0.89,
+            Self::Width => 0.98,
+            Self::ValueA => 0.24,
+            Self::ValueB => 0.29,
+            Self::Partials => 0.84,
+            Self::Rolloff => 0.63,
+            Self::CombSpacing => 0.80,
+            Self::EvenOdd => 0.88,
+            Self::SplitHarmonic => 0.40,
+            Self::CrossoverWidth => 0.37,
+            Self::FadeIn => 0.55,
+            Self::Drift => 0.71,
+            Self::DriftRate => 0.69,
         }
     }
 
@@ -94,12 +154,37 @@ impl Input {
     }
 }
 
+impl ModuleColor {
+    pub fn color(&self) -> Option<Color32> {
+        let h = match self {
+            Self::None => return None,
+            Self::Red => 0.0,
+            Self::Orange => 0.08,
+            Self::Yellow => 0.15,
+            Self::Green => 0.33,
+            Self::Teal => 0.47,
+            Self::Blue => 0.6,
+            Self::Purple => 0.75,
+            Self::Pink => 0.9,
+        };
+
+        // Brighter than the IO colors, tags are small and should stand out of the module block
+        Some(Color32::from(Hsva {
+            h,
+            s: 0.7,
+            v: 0.9,
+            a: 1.0,
+        }))
+    }
+}
+
 impl DataType {
     pub fn hue(&self) -> f32 {
         match self {
             Self::Audio => 0.0,
             Self::Control => 0.36,
             Self::Spectral => 0.84,
+            Self::Bus => 0.58,
         }
     }
 
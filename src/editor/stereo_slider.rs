@@ -2,13 +2,26 @@ use std::ops::RangeInclusive;
 
 use egui::{Color32, PointerButton, Pos2, Rect, Response, Sense, Ui, Vec2, Widget, vec2};
 
-use crate::synth_engine::{Sample, StereoSample, ui_bridge::ModulatedValue};
+use crate::{
+    editor::{utils::slider_key_nudge, value_entry::value_entry_menu},
+    synth_engine::{
+        ModuleId, Sample, StereoSample,
+        ui_bridge::{ModulatedValue, UiBridge},
+    },
+};
 
 const BG_COLOR: Color32 = Color32::from_rgb(0, 0, 0);
 const LEVEL_COLOR: Color32 = Color32::from_rgb(0x0b, 0x42, 0x67);
 const NEGATIVE_LEVEL_COLOR: Color32 = Color32::from_rgb(0x72, 0x72, 0x12);
 const MODULATED_COLOR: Color32 = Color32::from_rgb(0x9a, 0x6a, 0x12);
 
+// Where the link toggle of a parameter is kept, with the module in the UI config
+struct ChannelsLink<'a> {
+    bridge: &'a mut UiBridge,
+    module_id: ModuleId,
+    param: &'a str,
+}
+
 pub struct StereoSlider<'a> {
     units: Option<&'a str>,
     value: &'a mut StereoSample,
@@ -23,6 +36,7 @@ pub struct StereoSlider<'a> {
     vertical: bool,
     color: Color32,
     allow_inverse: bool,
+    channels_link: Option<ChannelsLink<'a>>,
 }
 
 impl<'a> StereoSlider<'a> {
@@ -41,6 +55,7 @@ impl<'a> StereoSlider<'a> {
             modulated: None,
             range: 0.0..=1.0,
             allow_inverse: false,
+            channels_link: None,
         }
     }
 
@@ -49,6 +64,10 @@ impl<'a> StereoSlider<'a> {
         self
     }
 
+    pub fn value_range(&self) -> RangeInclusive<Sample> {
+        self.range.clone()
+    }
+
     pub fn units(mut self, units: &'a str) -> Self {
         self.units = Some(units);
         self
@@ -105,6 +124,27 @@ impl<'a> StereoSlider<'a> {
         self
     }
 
+    // Adds a toggle after the value. Unlinked, a drag edits the channel under the pointer.
+    pub fn channels_link(
+        mut self,
+        bridge: &'a mut UiBridge,
+        module_id: ModuleId,
+        param: &'a str,
+    ) -> Self {
+        self.channels_link = Some(ChannelsLink {
+            bridge,
+            module_id,
+            param,
+        });
+        self
+    }
+
+    fn channels_linked(&self) -> bool {
+        self.channels_link
+            .as_ref()
+            .is_none_or(|link| link.bridge.channels_linked(link.module_id, link.param))
+    }
+
     fn normalized_minimum(&self) -> Sample {
         if self.allow_inverse { -1.0 } else { 0.0 }
     }
@@ -235,9 +275,17 @@ impl<'a> StereoSlider<'a> {
     fn add_contents(&mut self, ui: &mut Ui) -> Response {
         let mut response = ui.allocate_response(self.response_size(), Sense::click_and_drag());
         let normalized_value = self.normalized_value();
+        let linked = self.channels_linked();
+
+        // Secondary button always edits a single channel
+        let single_channel = |response: &Response| {
+            response.dragged_by(PointerButton::Secondary)
+                || (!linked && response.dragged_by(PointerButton::Primary))
+        };
 
         if let Some(pos) = response.interact_pointer_pos()
-            && response.drag_started_by(PointerButton::Secondary)
+            && response.drag_started()
+            && single_channel(&response)
         {
             ui.memory_mut(|mem| {
                 mem.data
@@ -252,9 +300,7 @@ impl<'a> StereoSlider<'a> {
                 normalized_delta *= 0.01;
             }
 
-            if response.dragged_by(PointerButton::Primary) {
-                self.update_normalized_value(&mut response, normalized_value + normalized_delta);
-            } else if response.dragged_by(PointerButton::Secondary) {
+            if single_channel(&response) {
                 let is_right_channel =
                     ui.memory(|mem| mem.data.get_temp(response.id).unwrap_or(false));
 
@@ -264,6 +310,8 @@ impl<'a> StereoSlider<'a> {
                     StereoSample::new(normalized_delta, 0.0)
                 };
                 self.update_normalized_value(&mut response, normalized_value + delta);
+            } else if response.dragged_by(PointerButton::Primary) {
+                self.update_normalized_value(&mut response, normalized_value + normalized_delta);
             }
         } else if response.double_clicked_by(PointerButton::Primary)
             && let Some(default) = self.default
@@ -272,6 +320,20 @@ impl<'a> StereoSlider<'a> {
             response.mark_changed();
         }
 
+        if let Some(delta) = slider_key_nudge(ui, &response) {
+            self.update_normalized_value(&mut response, normalized_value + delta);
+        }
+
+        if let Some(value) = value_entry_menu(
+            &response,
+            &self.format_value(self.value.left()),
+            self.units.unwrap_or_default(),
+        ) {
+            let value = StereoSample::splat(value / self.display_scale_factor);
+
+            self.update_normalized_value(&mut response, self.normalized_value_from(&value));
+        }
+
         if ui.is_rect_visible(response.rect) {
             ui.painter().rect_filled(response.rect, 0.0, BG_COLOR);
             self.paint_bars(ui, &response, normalized_value);
@@ -294,6 +356,21 @@ impl<'a> StereoSlider<'a> {
 
         if !self.vertical {
             ui.label(&label);
+
+            if let Some(link) = &mut self.channels_link {
+                let mut linked = linked;
+
+                if ui
+                    .toggle_value(&mut linked, "🔗")
+                    .on_hover_text(
+                        "Link channels, unlinked a drag edits the channel under the pointer",
+                    )
+                    .changed()
+                {
+                    link.bridge
+                        .set_channels_linked(link.module_id, link.param, linked);
+                }
+            }
         }
         response = response.on_hover_text_at_pointer(label);
 
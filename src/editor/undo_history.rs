@@ -0,0 +1,117 @@
+use std::{
+    collections::VecDeque,
+    mem, ptr,
+    sync::{Arc, Weak},
+};
+
+use parking_lot::Mutex;
+
+use crate::{
+    engine_factory::EngineFactory,
+    preset::Preset,
+    synth_engine::{SynthEngine, ui_bridge::UiBridge},
+};
+
+const MAX_UNDO_STEPS: usize = 64;
+
+// Patch states are kept serialized, it's compact and makes comparing them cheap
+pub struct UndoHistory {
+    current: String,
+    undo: VecDeque<String>,
+    redo: Vec<String>,
+    // Bridge and edit count the last record saw, the patch is only serialized once they move.
+    // The weak handle keeps a reloaded bridge from matching the dropped one.
+    checked: Option<(Weak<Mutex<SynthEngine>>, u32)>,
+}
+
+impl UndoHistory {
+    pub fn new(preset: &Preset) -> Self {
+        Self {
+            current: serde_json::to_string(preset).unwrap_or_default(),
+            undo: VecDeque::new(),
+            redo: Vec::new(),
+            checked: None,
+        }
+    }
+
+    // Made from the editor's mirror of the patch, the engine isn't locked
+    fn snapshot(bridge: &UiBridge) -> Option<String> {
+        serde_json::to_string(&bridge.preset()).ok()
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    // Adds an undo step if the patch changed since the last recorded state
+    pub fn record(&mut self, bridge: &UiBridge) {
+        let edits = bridge.edits();
+
+        if self
+            .checked
+            .as_ref()
+            .is_some_and(|(engine, checked_edits)| {
+                *checked_edits == edits && ptr::eq(engine.as_ptr(), Arc::as_ptr(bridge.engine()))
+            })
+        {
+            return;
+        }
+
+        self.checked = Some((Arc::downgrade(bridge.engine()), edits));
+
+        // Events that didn't change the patch, a note from the keyboard for one, count as well
+        let Some(state) = Self::snapshot(bridge) else {
+            return;
+        };
+
+        if state == self.current {
+            return;
+        }
+
+        if self.undo.len() == MAX_UNDO_STEPS {
+            self.undo.pop_front();
+        }
+
+        self.undo.push_back(mem::replace(&mut self.current, state));
+        self.redo.clear();
+    }
+
+    pub fn undo(&mut self, factory: &EngineFactory, bridge: &UiBridge) -> bool {
+        // Edits made since the last record shouldn't be lost on redo
+        self.record(bridge);
+
+        let Some(state) = self.undo.pop_back() else {
+            return false;
+        };
+
+        if !Self::restore(factory, &state) {
+            self.undo.push_back(state);
+            return false;
+        }
+
+        self.redo.push(mem::replace(&mut self.current, state));
+        true
+    }
+
+    pub fn redo(&mut self, factory: &EngineFactory) -> bool {
+        let Some(state) = self.redo.pop() else {
+            return false;
+        };
+
+        if !Self::restore(factory, &state) {
+            self.redo.push(state);
+            return false;
+        }
+
+        self.undo.push_back(mem::replace(&mut self.current, state));
+        true
+    }
+
+    fn restore(factory: &EngineFactory, state: &str) -> bool {
+        serde_json::from_str::<Preset>(state).is_ok_and(|preset| factory.load_preset(&preset))
+    }
+}
@@ -1,4 +1,4 @@
-use egui::{Id, Modal, Sides, Ui};
+use egui::{Context, EventFilter, Id, Key, Modal, Response, Sides, StrokeKind, Ui};
 
 #[macro_export]
 macro_rules! show_modal {
@@ -11,13 +11,19 @@ macro_rules! show_modal {
     };
 }
 
-pub fn confirm_module_removal(ui: &mut Ui, show_modal: &mut bool) -> bool {
-    let mut remove = false;
+const FOCUSED_SLIDER_KEY: &str = "focused-slider";
 
+pub fn confirm_module_removal(ui: &mut Ui, show_modal: &mut bool) -> bool {
     if ui.button("Remove Module").clicked() {
         *show_modal = true;
     }
 
+    module_removal_modal(ui, show_modal)
+}
+
+pub fn module_removal_modal(ui: &mut Ui, show_modal: &mut bool) -> bool {
+    let mut remove = false;
+
     if *show_modal {
         let modal = Modal::new(Id::new("remove-mod-modal")).show(ui.ctx(), |ui| {
             ui.set_width(220.0);
@@ -47,3 +53,55 @@ pub fn confirm_module_removal(ui: &mut Ui, show_modal: &mut bool) -> bool {
 
     remove
 }
+
+// Sliders take the focus when clicked, the arrow keys then step the value in normalized units
+// instead of moving the focus. Shift takes finer steps like it does for dragging.
+pub fn slider_key_nudge(ui: &Ui, response: &Response) -> Option<f32> {
+    if response.clicked() || response.drag_started() {
+        response.request_focus();
+    }
+
+    if !response.has_focus() {
+        return None;
+    }
+
+    ui.memory_mut(|mem| {
+        mem.set_focus_lock_filter(
+            response.id,
+            EventFilter {
+                horizontal_arrows: true,
+                vertical_arrows: true,
+                ..Default::default()
+            },
+        );
+        mem.data
+            .insert_temp(Id::new(FOCUSED_SLIDER_KEY), response.id);
+    });
+
+    if ui.is_rect_visible(response.rect) {
+        ui.painter().rect_stroke(
+            response.rect,
+            0.0,
+            ui.visuals().selection.stroke,
+            StrokeKind::Outside,
+        );
+    }
+
+    let (steps, shift) = ui.input(|i| {
+        let up = i.num_presses(Key::ArrowUp) + i.num_presses(Key::ArrowRight);
+        let down = i.num_presses(Key::ArrowDown) + i.num_presses(Key::ArrowLeft);
+
+        (up as f32 - down as f32, i.modifiers.shift)
+    });
+
+    (steps != 0.0).then(|| steps * if shift { 0.001 } else { 0.01 })
+}
+
+// Editor shortcuts are off while a text field or another widget takes the keyboard,
+// a focused slider only uses the arrows
+pub fn shortcuts_enabled(ctx: &Context) -> bool {
+    ctx.memory(|mem| match mem.focused() {
+        Some(id) => mem.data.get_temp::<Id>(Id::new(FOCUSED_SLIDER_KEY)) == Some(id),
+        None => true,
+    })
+}
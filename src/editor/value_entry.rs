@@ -0,0 +1,113 @@
+use egui::{Color32, Key, Response, RichText, TextEdit};
+
+use crate::synth_engine::Sample;
+
+// Other units accepted for the ones sliders show, with the factor to the shown unit
+const UNIT_CONVERSIONS: &[(&str, &str, Sample)] = &[
+    ("ms", "s", 1000.0),
+    ("hz", "khz", 1000.0),
+    ("st", "oct", 12.0),
+    ("st", "cents", 0.01),
+    ("st", "ct", 0.01),
+    ("cents", "st", 100.0),
+    ("cents", "ct", 1.0),
+];
+
+fn normalize_units(units: &str) -> String {
+    units
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+// A number optionally followed by units, either the ones the slider shows or a convertible one.
+// The result is in the shown units.
+pub fn parse_value(text: &str, units: &str) -> Option<Sample> {
+    let text = text.trim();
+    let (value, suffix) = (1..=text.len())
+        .rev()
+        .filter(|len| text.is_char_boundary(*len))
+        .find_map(|len| {
+            let value: Sample = text[..len].trim().parse().ok()?;
+
+            Some((value, &text[len..]))
+        })?;
+
+    if value.is_nan() {
+        return None;
+    }
+
+    let suffix = normalize_units(suffix);
+    let units = normalize_units(units);
+
+    if suffix.is_empty() || suffix == units {
+        return Some(value);
+    }
+
+    UNIT_CONVERSIONS
+        .iter()
+        .find(|(to, from, _)| *to == units && *from == suffix)
+        .map(|(_, _, factor)| value * factor)
+}
+
+// Right click menu with a field to type an exact value, returns it once Enter is pressed
+pub fn value_entry_menu(response: &Response, current: &str, units: &str) -> Option<Sample> {
+    let id = response.id.with("value-entry");
+    let mut entered = None;
+
+    let menu = response.context_menu(|ui| {
+        let mut text = ui.data_mut(|data| {
+            data.get_temp_mut_or_insert_with(id, || current.to_string())
+                .clone()
+        });
+
+        ui.horizontal(|ui| {
+            ui.add(TextEdit::singleline(&mut text).desired_width(80.0))
+                .request_focus();
+            ui.label(units.trim());
+        });
+
+        let value = parse_value(&text, units);
+
+        if value.is_none() {
+            ui.label(RichText::new("Invalid value").color(Color32::RED));
+        }
+
+        if ui.input(|i| i.key_pressed(Key::Enter))
+            && let Some(value) = value
+        {
+            entered = Some(value);
+            ui.close();
+        }
+
+        ui.data_mut(|data| data.insert_temp(id, text));
+    });
+
+    // The field starts from the current value every time the menu opens
+    if menu.is_none() || entered.is_some() {
+        response.ctx.data_mut(|data| data.remove::<String>(id));
+    }
+
+    entered
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_values_with_units() {
+        assert_eq!(parse_value("12.5", " ms"), Some(12.5));
+        assert_eq!(parse_value("12.5 ms", " ms"), Some(12.5));
+        assert_eq!(parse_value("0.25s", " ms"), Some(250.0));
+        assert_eq!(parse_value("-3 dB", " dB"), Some(-3.0));
+        assert_eq!(parse_value("-inf dB", "dB"), Some(Sample::NEG_INFINITY));
+        assert_eq!(parse_value("1.5 kHz", " Hz"), Some(1500.0));
+        assert_eq!(parse_value("1 oct", " st"), Some(12.0));
+        assert_eq!(parse_value("7 st", " cents"), Some(700.0));
+        assert_eq!(parse_value("5 Hz", " ms"), None);
+        assert_eq!(parse_value("dB", " dB"), None);
+        assert_eq!(parse_value("", "%"), None);
+    }
+}
@@ -0,0 +1,14 @@
+use serde::{Deserialize, Serialize};
+
+use crate::editor::DetailViewKey;
+
+// Saved with the plugin state, the editor reopens with the same panel and grid position
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct ViewState {
+    pub detail: Option<DetailViewKey>,
+    pub detail_height: Option<f32>,
+    pub grid_offset: [f32; 2],
+    pub ui_scale: Option<f32>,
+    #[serde(default)]
+    pub keyboard: bool,
+}
@@ -1,4 +1,7 @@
-use std::sync::{Arc, atomic::Ordering};
+use std::sync::{
+    Arc,
+    atomic::{AtomicBool, Ordering},
+};
 
 use arc_swap::ArcSwap;
 use atomic_float::AtomicF32;
@@ -7,20 +10,55 @@ use parking_lot::Mutex;
 
 use crate::{
     preset::{Preset, PresetInfo},
+    presets::Presets,
     synth_engine::{
-        EngineConfig, ExternalParamsBlock, Sample, SynthEngine, ui_bridge::ui_config::UiConfig,
+        EngineConfig, ExternalParamsBlock, Randomizer, Sample, SynthEngine,
+        ui_bridge::{UiBridge, ui_config::UiConfig},
     },
 };
 
 pub type EngineHandle = Arc<Mutex<SynthEngine>>;
 pub type UiConfigHandle = Arc<Mutex<UiConfig>>;
 
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum CompareSlot {
+    A,
+    B,
+}
+
+// Engine for the inactive compare slot, built ahead so switching is just a pointer swap
+struct StandbyEngine {
+    engine: SynthEngine,
+    ui_config: UiConfig,
+    sample_rate: Sample,
+}
+
+impl StandbyEngine {
+    fn preset(&self) -> Preset {
+        Preset {
+            info: PresetInfo::default(),
+            engine: self.engine.get_config(),
+            ui: self.ui_config.clone(),
+        }
+    }
+}
+
+struct CompareState {
+    active: CompareSlot,
+    standby: Option<StandbyEngine>,
+}
+
 pub struct EngineFactory {
     external_params: Arc<ExternalParamsBlock>,
     output_level_param: Arc<FloatParam>,
     host_sample_rate: AtomicF32,
+    // Off while the host renders offline, the tables are built inline then
+    background_tables: Arc<AtomicBool>,
     engine: ArcSwap<Mutex<SynthEngine>>,
     ui_config: ArcSwap<Mutex<UiConfig>>,
+    // Made along with the engine and waiting for the editor to take it
+    ui_bridge: Mutex<Option<UiBridge>>,
+    compare: Mutex<CompareState>,
 }
 
 impl EngineFactory {
@@ -28,20 +66,36 @@ impl EngineFactory {
         output_level_param: Arc<FloatParam>,
         external_params: Arc<ExternalParamsBlock>,
     ) -> Self {
+        let mut engine = SynthEngine::try_new(
+            &EngineConfig::default(),
+            output_level_param.clone(),
+            external_params.clone(),
+            44100.0,
+        )
+        .unwrap();
+        let background_tables = Arc::new(AtomicBool::new(true));
+
+        engine.set_background_tables(true);
+
+        let engine = Arc::new(Mutex::new(engine));
+        let ui_config = Arc::new(Mutex::new(UiConfig::default()));
+
         Self {
-            external_params: external_params.clone(),
-            output_level_param: output_level_param.clone(),
+            external_params,
+            output_level_param,
             host_sample_rate: AtomicF32::new(44100.0),
-            engine: ArcSwap::from_pointee(Mutex::new(
-                SynthEngine::try_new(
-                    &EngineConfig::default(),
-                    output_level_param.clone(),
-                    external_params.clone(),
-                    44100.0,
-                )
-                .unwrap(),
+            ui_bridge: Mutex::new(UiBridge::create(
+                engine.clone(),
+                ui_config.clone(),
+                background_tables.clone(),
             )),
-            ui_config: ArcSwap::from_pointee(Mutex::new(UiConfig::default())),
+            background_tables,
+            engine: ArcSwap::new(engine),
+            ui_config: ArcSwap::new(ui_config),
+            compare: Mutex::new(CompareState {
+                active: CompareSlot::A,
+                standby: None,
+            }),
         }
     }
 
@@ -49,8 +103,9 @@ impl EngineFactory {
         self.engine.load_full()
     }
 
-    pub fn get_ui_config(&self) -> UiConfigHandle {
-        self.ui_config.load_full()
+    /// The bridge of the latest engine, once. Taking it doesn't lock the engine.
+    pub fn take_ui_bridge(&self) -> Option<UiBridge> {
+        self.ui_bridge.lock().take()
     }
 
     pub fn engine_changed(&self, cached: &EngineHandle) -> bool {
@@ -61,6 +116,26 @@ impl EngineFactory {
         self.host_sample_rate.store(sample_rate, Ordering::Release);
     }
 
+    // Called from the plugin initialize, the audio thread isn't running the engines then
+    pub fn set_background_tables(&self, enabled: bool) {
+        self.background_tables.store(enabled, Ordering::Relaxed);
+        self.engine.load().lock().set_background_tables(enabled);
+
+        if let Some(standby) = &mut self.compare.lock().standby {
+            standby.engine.set_background_tables(enabled);
+        }
+    }
+
+    // Called from the plugin initialize, tops up the engines the audio thread ran before
+    pub fn preallocate(&self) {
+        self.engine.load().lock().preallocate();
+
+        if let Some(standby) = &mut self.compare.lock().standby {
+            standby.engine.preallocate();
+        }
+    }
+
+    // For saving the host state, the editor makes its presets from the bridge instead
     pub fn get_preset(&self) -> Preset {
         Preset {
             info: PresetInfo::default(),
@@ -69,19 +144,133 @@ impl EngineFactory {
         }
     }
 
-    pub fn load_preset(&self, preset: &Preset) -> bool {
-        let Some(new_engine) = SynthEngine::try_new(
+    fn build_standby(&self, preset: &Preset) -> Option<StandbyEngine> {
+        let sample_rate = self.host_sample_rate.load(Ordering::Acquire);
+        let mut engine = SynthEngine::try_new(
             &preset.engine,
             self.output_level_param.clone(),
             self.external_params.clone(),
-            self.host_sample_rate.load(Ordering::Acquire),
-        ) else {
+            sample_rate,
+        )?;
+
+        engine.set_background_tables(self.background_tables.load(Ordering::Relaxed));
+
+        Some(StandbyEngine {
+            engine,
+            ui_config: preset.ui.clone(),
+            sample_rate,
+        })
+    }
+
+    // The bridge is made before the audio thread can pick the engine up
+    fn activate(&self, standby: StandbyEngine) {
+        let engine = Arc::new(Mutex::new(standby.engine));
+        let ui_config = Arc::new(Mutex::new(standby.ui_config));
+        let bridge = UiBridge::create(
+            engine.clone(),
+            ui_config.clone(),
+            self.background_tables.clone(),
+        );
+
+        // A bridge the editor didn't take is dropped here, off the audio thread
+        *self.ui_bridge.lock() = bridge;
+        self.ui_config.store(ui_config);
+        self.engine.store(engine);
+    }
+
+    pub fn load_preset(&self, preset: &Preset) -> bool {
+        let Some(standby) = self.build_standby(preset) else {
             return false;
         };
 
-        self.ui_config
-            .store(Arc::new(Mutex::new(preset.ui.clone())));
-        self.engine.store(Arc::new(Mutex::new(new_engine)));
+        self.activate(standby);
+        true
+    }
+
+    /// Loads the preset at `program` in the sorted presets list, runs off the audio thread.
+    pub fn load_program(&self, program: usize) -> bool {
+        let Some(preset) = Presets::new()
+            .and_then(|presets| presets.read_presets_list().into_iter().nth(program))
+            .and_then(|item| Presets::read_preset(&item.source))
+        else {
+            return false;
+        };
+
+        self.load_preset(&preset)
+    }
+
+    /// Rebuilds `preset` with unlocked parameters moved towards random values by `amount`.
+    pub fn randomize(&self, mut preset: Preset, amount: Sample, seed: u64) -> bool {
+        let modules = &preset.ui.modules;
+
+        Randomizer::new(seed, amount).randomize_patch(&mut preset.engine, |id, param| {
+            modules.get(&id).is_some_and(|module| {
+                module.randomize_locked || module.locked_params.contains(&param)
+            })
+        });
+
+        self.load_preset(&preset)
+    }
+
+    pub fn compare_slot(&self) -> CompareSlot {
+        self.compare.lock().active
+    }
+
+    /// Makes the other slot active, `current` is the patch of the active one. The first switch
+    /// starts B as a copy of A. The audio thread picks the new engine up at the next block.
+    pub fn switch_compare_slot(&self, current: &Preset) -> bool {
+        let mut compare = self.compare.lock();
+
+        // Voices of the outgoing engine are dropped with it, the standby one starts silent
+        let Some(outgoing) = self.build_standby(current) else {
+            return false;
+        };
+
+        let sample_rate = self.host_sample_rate.load(Ordering::Acquire);
+        let incoming = match compare.standby.take() {
+            Some(standby) if standby.sample_rate == sample_rate => Some(standby),
+            Some(standby) => self.build_standby(&standby.preset()),
+            None => self.build_standby(current),
+        };
+
+        let Some(incoming) = incoming else {
+            return false;
+        };
+
+        self.activate(incoming);
+        compare.standby = Some(outgoing);
+        compare.active = match compare.active {
+            CompareSlot::A => CompareSlot::B,
+            CompareSlot::B => CompareSlot::A,
+        };
+        true
+    }
+
+    /// Replaces the B slot with the A patch, `current` is the patch of the active slot.
+    pub fn copy_a_to_b(&self, current: &Preset) -> bool {
+        let mut compare = self.compare.lock();
+
+        match compare.active {
+            CompareSlot::A => {
+                let Some(standby) = self.build_standby(current) else {
+                    return false;
+                };
+
+                compare.standby = Some(standby);
+            }
+            CompareSlot::B => {
+                let Some(a) = compare.standby.as_ref() else {
+                    return false;
+                };
+
+                let Some(b) = self.build_standby(&a.preset()) else {
+                    return false;
+                };
+
+                self.activate(b);
+            }
+        }
+
         true
     }
 }
@@ -0,0 +1,171 @@
+use crate::{
+    engine_factory::EngineHandle,
+    synth_engine::{NUM_CHANNELS, Sample},
+};
+
+const FADE_TIME: Sample = 0.01;
+
+// Crossfade from the engine replaced by a preset load or a compare slot switch. The voices of the
+// previous patch keep playing and fade out while the new engine fades in, instead of being cut off.
+pub struct EngineFade {
+    outgoing: Option<EngineHandle>,
+    length: usize,
+    position: usize,
+    buffers: [Vec<Sample>; NUM_CHANNELS],
+}
+
+impl EngineFade {
+    pub fn new() -> Self {
+        Self {
+            outgoing: None,
+            length: 1,
+            position: 0,
+            buffers: Default::default(),
+        }
+    }
+
+    pub fn initialize(&mut self, sample_rate: Sample, max_buffer_size: usize) {
+        self.outgoing = None;
+        self.length = ((FADE_TIME * sample_rate) as usize).max(1);
+        self.buffers = [vec![0.0; max_buffer_size], vec![0.0; max_buffer_size]];
+    }
+
+    // A switch during a fade cuts the engine still fading out, it's returned to be freed by the
+    // caller
+    pub fn start(&mut self, outgoing: EngineHandle) -> Option<EngineHandle> {
+        self.position = 0;
+        self.outgoing.replace(outgoing)
+    }
+
+    fn render_outgoing(&mut self, samples: usize) -> bool {
+        let Some(mut synth) = self.outgoing.as_ref().and_then(|engine| engine.try_lock()) else {
+            return false;
+        };
+
+        let [left, right] = &mut self.buffers;
+        let mut sample_from = 0;
+
+        while sample_from < samples {
+            let block = synth.block_size().min(samples - sample_from);
+            let range = sample_from..sample_from + block;
+
+            synth.process(
+                block,
+                false,
+                [&mut left[range.clone()], &mut right[range]].into_iter(),
+            );
+            sample_from += block;
+        }
+
+        true
+    }
+
+    // Mixes the previous engine into the output of the new one. Returns the previous engine once
+    // faded out, the caller frees it off the audio thread.
+    pub fn process(&mut self, outputs: &mut [&mut [f32]]) -> Option<EngineHandle> {
+        if self.outgoing.is_none() {
+            return None;
+        }
+
+        let samples = outputs
+            .iter()
+            .map(|output| output.len())
+            .min()
+            .unwrap_or(0)
+            .min(self.length - self.position)
+            .min(self.buffers[0].len());
+
+        // Held by someone else, the new engine just starts on its own
+        if !self.render_outgoing(samples) {
+            return self.outgoing.take();
+        }
+
+        for (output, faded) in outputs.iter_mut().zip(&self.buffers) {
+            for (idx, (out, faded)) in output.iter_mut().zip(faded).take(samples).enumerate() {
+                let gain = (self.position + idx) as Sample / self.length as Sample;
+
+                *out = *out * gain + *faded * (1.0 - gain);
+            }
+        }
+
+        self.position += samples;
+
+        if self.position < self.length {
+            return None;
+        }
+
+        self.outgoing.take()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use parking_lot::Mutex;
+
+    use super::*;
+    use crate::{Additizer, default_scheme::build_default_preset};
+
+    const SAMPLE_RATE: Sample = 48_000.0;
+
+    #[test]
+    fn fades_previous_engine_out() {
+        let preset = build_default_preset();
+        let mut outgoing = Additizer::offline_engine(&preset.engine, SAMPLE_RATE).unwrap();
+        let mut warmup = [vec![0.0; 64], vec![0.0; 64]];
+
+        outgoing.handle_note_on(0, 60, 1.0);
+
+        for _ in 0..16 {
+            let [left, right] = &mut warmup;
+
+            outgoing.process(64, false, [&mut left[..], &mut right[..]].into_iter());
+        }
+
+        let mut fade = EngineFade::new();
+        let mut left = vec![0.0; 256];
+        let mut right = vec![0.0; 256];
+        let mut faded = Vec::new();
+        let mut finished = None;
+
+        fade.initialize(SAMPLE_RATE, 256);
+        fade.start(Arc::new(Mutex::new(outgoing)));
+
+        // The new engine is silent, only the previous one is heard
+        while finished.is_none() {
+            left.fill(0.0);
+            right.fill(0.0);
+            finished = fade.process(&mut [&mut left[..], &mut right[..]]);
+            faded.extend_from_slice(&left);
+        }
+
+        let peak = |samples: &[Sample]| samples.iter().fold(0.0, |p: Sample, s| p.max(s.abs()));
+        let length = (FADE_TIME * SAMPLE_RATE) as usize;
+
+        assert!(peak(&faded[..64]) > 1e-3);
+        assert!(peak(&faded[length - 16..length]) < 0.05 * peak(&faded[..64]));
+        assert!(peak(&faded[length..]) == 0.0);
+        assert!(fade.process(&mut [&mut left[..], &mut right[..]]).is_none());
+    }
+
+    #[test]
+    fn restart_hands_back_the_interrupted_engine() {
+        let preset = build_default_preset();
+        let first = Arc::new(Mutex::new(
+            Additizer::offline_engine(&preset.engine, SAMPLE_RATE).unwrap(),
+        ));
+        let second = Arc::new(Mutex::new(
+            Additizer::offline_engine(&preset.engine, SAMPLE_RATE).unwrap(),
+        ));
+        let mut fade = EngineFade::new();
+
+        fade.initialize(SAMPLE_RATE, 256);
+
+        assert!(fade.start(first.clone()).is_none());
+
+        let interrupted = fade.start(second).expect("engine still fading out");
+
+        assert!(Arc::ptr_eq(&interrupted, &first));
+    }
+}
@@ -6,45 +6,61 @@ use smallvec::SmallVec;
 mod default_scheme;
 mod editor;
 mod engine_factory;
+mod engine_fade;
 mod params;
-mod preset;
+pub mod preset;
 mod presets;
+mod soft_bypass;
 pub mod synth_engine;
 mod utils;
 
 use crate::editor::create_editor;
 use crate::engine_factory::{EngineFactory, EngineHandle};
+use crate::engine_fade::EngineFade;
 use crate::params::AdditizerParams;
-use crate::synth_engine::{Expression, ExternalParamsBlock, SynthEngine};
+use crate::soft_bypass::SoftBypass;
+use crate::synth_engine::{EngineConfig, Expression, ExternalParamsBlock, Sample, SynthEngine};
 pub use egui;
 use nih_plug::prelude::*;
-use std::sync::Arc;
+use std::{array, sync::Arc};
+
+// Reports allocations inside `assert_no_alloc`, the audio thread runs in it. Only in the tests and
+// with the `alloc-checks` feature, a host loading the plugin keeps its own allocator.
+#[cfg(any(test, feature = "alloc-checks"))]
+#[global_allocator]
+static ALLOCATOR: assert_no_alloc::AllocDisabler = assert_no_alloc::AllocDisabler;
+
+pub enum Task {
+    // Preset loading reads files and builds the engine, the audio thread only requests it
+    LoadProgram(usize),
+    // An engine the audio thread is done with, its last handle is dropped here
+    DropEngine(EngineHandle),
+}
 
 pub struct Additizer {
     params: Arc<AdditizerParams>,
     engine: Option<EngineHandle>,
+    engine_fade: EngineFade,
+    soft_bypass: SoftBypass,
     factory: Arc<EngineFactory>,
+    latency: u32,
+    program: i32,
 }
 
 impl Default for Additizer {
     fn default() -> Self {
         let params = Arc::new(AdditizerParams::default());
-
-        let external_params = Arc::new(ExternalParamsBlock {
-            float_params: [
-                params.float_param_1.clone(),
-                params.float_param_2.clone(),
-                params.float_param_3.clone(),
-                params.float_param_4.clone(),
-            ],
-        });
-
+        let external_params = Self::external_params(&params);
         let factory = Arc::new(EngineFactory::new(params.volume.clone(), external_params));
 
         Self {
             params,
             engine: None,
+            engine_fade: EngineFade::new(),
+            soft_bypass: SoftBypass::new(),
             factory,
+            latency: 0,
+            program: 0,
         }
     }
 }
@@ -108,6 +124,52 @@ impl<'a, C: ProcessContext<Additizer>> EventReorderer<'a, C> {
 }
 
 impl Additizer {
+    fn external_params(params: &AdditizerParams) -> Arc<ExternalParamsBlock> {
+        Arc::new(ExternalParamsBlock {
+            float_params: [
+                params.float_param_1.clone(),
+                params.float_param_2.clone(),
+                params.float_param_3.clone(),
+                params.float_param_4.clone(),
+                params.float_param_5.clone(),
+                params.float_param_6.clone(),
+                params.float_param_7.clone(),
+                params.float_param_8.clone(),
+                params.float_param_9.clone(),
+                params.float_param_10.clone(),
+                params.float_param_11.clone(),
+                params.float_param_12.clone(),
+                params.float_param_13.clone(),
+                params.float_param_14.clone(),
+                params.float_param_15.clone(),
+                params.float_param_16.clone(),
+            ],
+            float_param_names: params.float_param_names.clone(),
+            automation_params: array::from_fn(|idx| params.automation[idx].value.clone()),
+            master_tune: params.master_tune.clone(),
+            transpose: params.transpose.clone(),
+        })
+    }
+
+    // Engine outside of a host, the host parameters stay at their defaults
+    pub fn offline_engine(config: &EngineConfig, sample_rate: Sample) -> Option<SynthEngine> {
+        let params = AdditizerParams::default();
+
+        SynthEngine::try_new(
+            config,
+            params.volume.clone(),
+            Self::external_params(&params),
+            sample_rate,
+        )
+    }
+
+    fn program_change(event: &NoteEvent<()>) -> Option<u8> {
+        match event {
+            NoteEvent::MidiProgramChange { program, .. } => Some(*program),
+            _ => None,
+        }
+    }
+
     fn process_event(synth: &mut SynthEngine, event: NoteEvent<()>) {
         // nih_log!("Event: {:?}", event);
 
@@ -152,6 +214,22 @@ impl Additizer {
             } => {
                 synth.handle_note_expression(channel, note, Expression::Pitch, tuning);
             }
+            NoteEvent::PolyVibrato {
+                channel,
+                note,
+                vibrato,
+                ..
+            } => {
+                synth.handle_note_expression(channel, note, Expression::Vibrato, vibrato);
+            }
+            NoteEvent::PolyExpression {
+                channel,
+                note,
+                expression,
+                ..
+            } => {
+                synth.handle_note_expression(channel, note, Expression::Swell, expression);
+            }
             NoteEvent::PolyBrightness {
                 channel,
                 note,
@@ -168,6 +246,17 @@ impl Additizer {
             } => {
                 synth.handle_note_expression(channel, note, Expression::Pressure, pressure);
             }
+            NoteEvent::MidiPitchBend { channel, value, .. } => {
+                synth.handle_pitch_bend(channel, value);
+            }
+            NoteEvent::MidiCC { cc, value, .. } => {
+                synth.handle_midi_cc(cc, value);
+            }
+            NoteEvent::MidiChannelPressure {
+                channel, pressure, ..
+            } => {
+                synth.handle_channel_expression(channel, Expression::Pressure, pressure);
+            }
             _ => (),
         }
     }
@@ -184,6 +273,12 @@ impl Plugin for Additizer {
     const AUDIO_IO_LAYOUTS: &'static [AudioIOLayout] = &[AudioIOLayout {
         main_input_channels: None,
         main_output_channels: NonZeroU32::new(2),
+        // Followed by the envelope follower modules
+        aux_input_ports: &[new_nonzero_u32(2)],
+        names: PortNames {
+            aux_inputs: &["Sidechain"],
+            ..PortNames::const_default()
+        },
         ..AudioIOLayout::const_default()
     }];
 
@@ -191,24 +286,53 @@ impl Plugin for Additizer {
     const SAMPLE_ACCURATE_AUTOMATION: bool = true;
 
     type SysExMessage = ();
-    type BackgroundTask = ();
+    type BackgroundTask = Task;
 
     fn params(&self) -> Arc<dyn Params> {
         self.params.clone()
     }
 
+    fn task_executor(&mut self) -> TaskExecutor<Self> {
+        let factory = self.factory.clone();
+
+        Box::new(move |task| match task {
+            Task::LoadProgram(program) => {
+                factory.load_program(program);
+            }
+            Task::DropEngine(engine) => drop(engine),
+        })
+    }
+
     fn editor(&mut self, _async_executor: AsyncExecutor<Self>) -> Option<Box<dyn Editor>> {
-        create_editor(Arc::clone(&self.params.editor_state), self.factory.clone())
+        create_editor(
+            Arc::clone(&self.params.editor_state),
+            self.factory.clone(),
+            self.params.view_state.clone(),
+        )
     }
 
     fn initialize(
         &mut self,
         _audio_io_layout: &AudioIOLayout,
         buffer_config: &BufferConfig,
-        _context: &mut impl InitContext<Self>,
+        context: &mut impl InitContext<Self>,
     ) -> bool {
         self.factory.set_host_sample_rate(buffer_config.sample_rate);
+        // Offline renders can't wait for the table builder, their output would depend on timing
+        self.factory
+            .set_background_tables(buffer_config.process_mode != ProcessMode::Offline);
         self.params.config.set_factory(self.factory.clone());
+        // Everything the audio thread fills is sized here, for the largest host buffer
+        self.engine_fade.initialize(
+            buffer_config.sample_rate,
+            buffer_config.max_buffer_size as usize,
+        );
+        self.factory.preallocate();
+        self.soft_bypass.initialize(buffer_config.sample_rate);
+        self.latency = self.factory.get_engine().lock().latency_samples();
+        context.set_latency_samples(self.latency);
+        // The restored state already holds the patch of the restored program
+        self.program = self.params.program.value();
 
         true
     }
@@ -218,20 +342,30 @@ impl Plugin for Additizer {
     fn process(
         &mut self,
         buffer: &mut Buffer,
-        _aux: &mut AuxiliaryBuffers,
+        aux: &mut AuxiliaryBuffers,
         context: &mut impl ProcessContext<Self>,
     ) -> ProcessStatus {
-        struct BlocksHandler<'a, 'b> {
+        struct BlocksHandler<'a, 'b, 'c> {
             buffer: &'a mut Buffer<'b>,
+            sidechain: Option<&'a Buffer<'c>>,
             synth: &'a mut SynthEngine,
             desired_block_size: usize,
             iteration: usize,
             update_ui: bool,
         }
 
-        impl<'a, 'b> BlocksHandler<'a, 'b> {
+        impl<'a, 'b, 'c> BlocksHandler<'a, 'b, 'c> {
             #[inline]
             fn process_single_block(&mut self, sample_from: usize, samples: usize) {
+                if let Some(sidechain) = self.sidechain {
+                    self.synth.set_sidechain(
+                        sidechain
+                            .as_slice_immutable()
+                            .iter()
+                            .map(|buff| &buff[sample_from..sample_from + samples]),
+                    );
+                }
+
                 self.synth.process(
                     samples,
                     self.update_ui && self.iteration & 1 == 0,
@@ -265,22 +399,70 @@ impl Plugin for Additizer {
             }
         }
 
+        let program = self.params.program.value();
+
+        if program != self.program {
+            self.program = program;
+            context.execute_background(Task::LoadProgram(program as usize));
+        }
+
+        let bypassed = self.params.bypass.value();
+
+        // Fully bypassed the voices are already cleared, notes arriving meanwhile are dropped
+        if self.soft_bypass.is_silent(bypassed) {
+            for channel in buffer.as_slice() {
+                channel.fill(0.0);
+            }
+
+            while context.next_event().is_some() {}
+
+            return ProcessStatus::Normal;
+        }
+
         if self
             .engine
             .as_ref()
             .is_none_or(|engine| self.factory.engine_changed(engine))
         {
-            self.engine = Some(self.factory.get_engine());
+            if let Some(previous) = self.engine.replace(self.factory.get_engine())
+                && let Some(interrupted) = self.engine_fade.start(previous)
+            {
+                context.execute_background(Task::DropEngine(interrupted));
+            }
+        }
+
+        let engine = self.engine.as_deref().unwrap();
+
+        // Edits from the editor arrive through the event queues, routing changes come prepared
+        // and are swapped in between blocks. Saving the host state is the only other place
+        // locking a running engine, the block is silent rather than waiting for it.
+        let Some(mut synth) = engine.try_lock() else {
+            for channel in buffer.as_slice() {
+                channel.fill(0.0);
+            }
+
+            while context.next_event().is_some() {}
+
+            return ProcessStatus::Normal;
+        };
+
+        if let Some(tempo) = context.transport().tempo {
+            synth.set_tempo(tempo as f32);
+        }
+
+        if !self.params.editor_state.is_open() {
+            synth.release_keyboard_note();
         }
 
-        let mut synth = self.engine.as_deref().unwrap().lock();
+        let mut program_change = None;
 
-        assert_no_alloc::assert_no_alloc(|| {
+        let faded_out = assert_no_alloc::assert_no_alloc(|| {
             let total_samples = buffer.samples();
             let desired_block_size = synth.block_size();
 
             let mut blocks_handler = BlocksHandler {
                 buffer,
+                sidechain: aux.inputs.first(),
                 synth: &mut synth,
                 desired_block_size,
                 iteration: 0,
@@ -299,12 +481,40 @@ impl Plugin for Additizer {
                     sample_from = blocks_handler.process(sample_from, sample_to);
                 }
 
+                program_change = Self::program_change(&event).or(program_change);
                 Self::process_event(blocks_handler.synth, event);
             }
 
             blocks_handler.process_all(sample_from, total_samples);
+
+            let faded_out = self.engine_fade.process(blocks_handler.buffer.as_slice());
+
+            if self
+                .soft_bypass
+                .process(bypassed, blocks_handler.buffer.as_slice())
+            {
+                blocks_handler.synth.clear_voices();
+            }
+
+            faded_out
         });
 
+        if let Some(engine) = faded_out {
+            context.execute_background(Task::DropEngine(engine));
+        }
+
+        if let Some(program) = program_change {
+            context.execute_background(Task::LoadProgram(program as usize));
+        }
+
+        // Oversampling changes apply while processing, the host is told about the new latency after
+        let latency = synth.latency_samples();
+
+        if latency != self.latency {
+            self.latency = latency;
+            context.set_latency_samples(latency);
+        }
+
         ProcessStatus::KeepAlive
     }
 }
@@ -321,14 +531,16 @@ impl ClapPlugin for Additizer {
     ];
 }
 
-// impl Vst3Plugin for Additizer {
-//     const VST3_CLASS_ID: [u8; 16] = *b"Additizer1111337";
-//     const VST3_SUBCATEGORIES: &'static [Vst3SubCategory] = &[
-//         Vst3SubCategory::Instrument,
-//         Vst3SubCategory::Synth,
-//         Vst3SubCategory::Stereo,
-//     ];
-// }
+#[cfg(feature = "vst3")]
+impl Vst3Plugin for Additizer {
+    const VST3_CLASS_ID: [u8; 16] = *b"AlekseiklAdditzr";
+    const VST3_SUBCATEGORIES: &'static [Vst3SubCategory] = &[
+        Vst3SubCategory::Instrument,
+        Vst3SubCategory::Synth,
+        Vst3SubCategory::Stereo,
+    ];
+}
 
 nih_export_clap!(Additizer);
-// nih_export_vst3!(Additizer);
+#[cfg(feature = "vst3")]
+nih_export_vst3!(Additizer);
@@ -2,9 +2,21 @@ use nih_plug::params::persist::PersistentField;
 use nih_plug::prelude::*;
 use nih_plug_egui::EguiState;
 use parking_lot::Mutex;
-use std::sync::Arc;
+use std::{array, sync::Arc};
 
-use crate::{default_scheme::build_default_preset, engine_factory::EngineFactory, preset::Preset};
+use crate::{
+    default_scheme::build_default_preset,
+    editor::ViewState,
+    engine_factory::EngineFactory,
+    preset::Preset,
+    synth_engine::{
+        MAX_MASTER_TUNE, MAX_TRANSPOSE,
+        external_param::{FloatParamNames, NUM_AUTOMATION_PARAMS},
+    },
+};
+
+// Same range as MIDI program change messages
+const MAX_PROGRAM: i32 = 127;
 
 #[derive(Params)]
 pub struct AdditizerParams {
@@ -14,9 +26,24 @@ pub struct AdditizerParams {
     #[persist = "plugin-preset"]
     pub config: PresetWrapper,
 
+    #[persist = "editor-view"]
+    pub view_state: Arc<Mutex<ViewState>>,
+
     #[id = "volume"]
     pub volume: Arc<FloatParam>,
 
+    #[id = "bypass"]
+    pub bypass: Arc<BoolParam>,
+
+    #[id = "master-tune"]
+    pub master_tune: Arc<FloatParam>,
+
+    #[id = "transpose"]
+    pub transpose: Arc<IntParam>,
+
+    #[id = "program"]
+    pub program: Arc<IntParam>,
+
     #[id = "float-param-1"]
     pub float_param_1: Arc<FloatParam>,
 
@@ -28,13 +55,101 @@ pub struct AdditizerParams {
 
     #[id = "float-param-4"]
     pub float_param_4: Arc<FloatParam>,
+
+    #[id = "float-param-5"]
+    pub float_param_5: Arc<FloatParam>,
+
+    #[id = "float-param-6"]
+    pub float_param_6: Arc<FloatParam>,
+
+    #[id = "float-param-7"]
+    pub float_param_7: Arc<FloatParam>,
+
+    #[id = "float-param-8"]
+    pub float_param_8: Arc<FloatParam>,
+
+    #[id = "float-param-9"]
+    pub float_param_9: Arc<FloatParam>,
+
+    #[id = "float-param-10"]
+    pub float_param_10: Arc<FloatParam>,
+
+    #[id = "float-param-11"]
+    pub float_param_11: Arc<FloatParam>,
+
+    #[id = "float-param-12"]
+    pub float_param_12: Arc<FloatParam>,
+
+    #[id = "float-param-13"]
+    pub float_param_13: Arc<FloatParam>,
+
+    #[id = "float-param-14"]
+    pub float_param_14: Arc<FloatParam>,
+
+    #[id = "float-param-15"]
+    pub float_param_15: Arc<FloatParam>,
+
+    #[id = "float-param-16"]
+    pub float_param_16: Arc<FloatParam>,
+
+    pub float_param_names: Arc<FloatParamNames>,
+
+    #[nested(array, group = "Automation")]
+    pub automation: [AutomationParams; NUM_AUTOMATION_PARAMS],
+}
+
+// Generic host parameters, the engine maps them onto module inputs
+#[derive(Params)]
+pub struct AutomationParams {
+    #[id = "automation"]
+    pub value: Arc<FloatParam>,
+}
+
+impl AutomationParams {
+    fn new(idx: usize) -> Self {
+        Self {
+            value: Arc::new(FloatParam::new(
+                format!("Automation {}", idx + 1),
+                0.0,
+                FloatRange::Linear { min: 0.0, max: 1.0 },
+            )),
+        }
+    }
+}
+
+// Float param slot, the value text starts with the user name of the slot
+fn float_param(idx: usize, names: &Arc<FloatParamNames>) -> Arc<FloatParam> {
+    let names = names.clone();
+
+    Arc::new(
+        FloatParam::new(
+            format!("Float Param {}", idx + 1),
+            0.0,
+            FloatRange::Linear { min: 0.0, max: 1.0 },
+        )
+        .with_value_to_string(Arc::new(move |value| {
+            let name = names[idx].lock();
+
+            if name.is_empty() {
+                format!("{value:.2}")
+            } else {
+                format!("{name}: {value:.2}")
+            }
+        }))
+        .with_string_to_value(Arc::new(|string| {
+            string.rsplit(':').next()?.trim().parse().ok()
+        })),
+    )
 }
 
 impl Default for AdditizerParams {
     fn default() -> Self {
+        let float_param_names: Arc<FloatParamNames> = Arc::default();
+
         Self {
             editor_state: EguiState::from_size(900, 600),
             config: PresetWrapper::new(),
+            view_state: Arc::new(Mutex::new(ViewState::default())),
             volume: Arc::new(
                 FloatParam::new(
                     "Volume",
@@ -50,26 +165,56 @@ impl Default for AdditizerParams {
                 .with_step_size(0.01)
                 .with_unit(" dB"),
             ),
-            float_param_1: Arc::new(FloatParam::new(
-                "Float Param 1",
-                0.0,
-                FloatRange::Linear { min: 0.0, max: 1.0 },
-            )),
-            float_param_2: Arc::new(FloatParam::new(
-                "Float Param 2",
-                0.0,
-                FloatRange::Linear { min: 0.0, max: 1.0 },
-            )),
-            float_param_3: Arc::new(FloatParam::new(
-                "Float Param 3",
-                0.0,
-                FloatRange::Linear { min: 0.0, max: 1.0 },
-            )),
-            float_param_4: Arc::new(FloatParam::new(
-                "Float Param 4",
-                0.0,
-                FloatRange::Linear { min: 0.0, max: 1.0 },
+            bypass: Arc::new(BoolParam::new("Bypass", false).make_bypass()),
+            master_tune: Arc::new(
+                FloatParam::new(
+                    "Master Tune",
+                    0.0,
+                    FloatRange::Linear {
+                        min: -MAX_MASTER_TUNE,
+                        max: MAX_MASTER_TUNE,
+                    },
+                )
+                .with_step_size(0.1)
+                .with_unit(" cents"),
+            ),
+            transpose: Arc::new(
+                IntParam::new(
+                    "Transpose",
+                    0,
+                    IntRange::Linear {
+                        min: -MAX_TRANSPOSE,
+                        max: MAX_TRANSPOSE,
+                    },
+                )
+                .with_unit(" st"),
+            ),
+            program: Arc::new(IntParam::new(
+                "Program",
+                0,
+                IntRange::Linear {
+                    min: 0,
+                    max: MAX_PROGRAM,
+                },
             )),
+            float_param_1: float_param(0, &float_param_names),
+            float_param_2: float_param(1, &float_param_names),
+            float_param_3: float_param(2, &float_param_names),
+            float_param_4: float_param(3, &float_param_names),
+            float_param_5: float_param(4, &float_param_names),
+            float_param_6: float_param(5, &float_param_names),
+            float_param_7: float_param(6, &float_param_names),
+            float_param_8: float_param(7, &float_param_names),
+            float_param_9: float_param(8, &float_param_names),
+            float_param_10: float_param(9, &float_param_names),
+            float_param_11: float_param(10, &float_param_names),
+            float_param_12: float_param(11, &float_param_names),
+            float_param_13: float_param(12, &float_param_names),
+            float_param_14: float_param(13, &float_param_names),
+            float_param_15: float_param(14, &float_param_names),
+            float_param_16: float_param(15, &float_param_names),
+            float_param_names,
+            automation: array::from_fn(AutomationParams::new),
         }
     }
 }
@@ -1,10 +1,14 @@
 use serde::{Deserialize, Serialize};
 
-use crate::synth_engine::{EngineConfig, ui_bridge::ui_config::UiConfig};
+use crate::synth_engine::{EngineConfig, SubPatchConfig, ui_bridge::ui_config::UiConfig};
 
 #[derive(Default, Clone, Serialize, Deserialize)]
 pub struct PresetInfo {
     pub title: String,
+    #[serde(default)]
+    pub category: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
 }
 
 #[derive(Default, Clone, Serialize, Deserialize)]
@@ -14,3 +18,11 @@ pub struct Preset {
     pub engine: EngineConfig,
     pub ui: UiConfig,
 }
+
+// Modules saved from a group, the ui config keeps their labels and positions
+#[derive(Default, Clone, Serialize, Deserialize)]
+pub struct SubPatch {
+    pub name: String,
+    pub engine: SubPatchConfig,
+    pub ui: UiConfig,
+}
@@ -5,18 +5,49 @@ use std::{
 };
 
 use directories::ProjectDirs;
-use serde::{Deserialize, Serialize};
 
-use crate::preset::{Preset, PresetInfo};
+use crate::preset::{Preset, PresetInfo, SubPatch};
 
 const PRESET_EXT: &str = "adp";
+const SUB_PATCH_EXT: &str = "ads";
+const SUB_PATCHES_DIR: &str = "Sub-patches";
+
+// Shipped with the plugin, they can't be renamed or deleted
+static FACTORY_PRESETS: &[&[u8]] = &[
+    include_bytes!("../presets/Init.adp"),
+    include_bytes!("../presets/Saw Pluck Bass.adp"),
+    include_bytes!("../presets/Hollow Square Pad.adp"),
+];
+
+#[derive(Clone, PartialEq)]
+pub enum PresetSource {
+    Factory(usize),
+    User(PathBuf),
+}
 
-#[derive(Serialize, Deserialize)]
 pub struct PresetListItem {
-    #[serde(flatten)]
     pub info: PresetInfo,
-    #[serde(skip)]
-    pub path: String,
+    pub source: PresetSource,
+}
+
+impl PresetListItem {
+    pub fn is_factory(&self) -> bool {
+        matches!(self.source, PresetSource::Factory(_))
+    }
+
+    // Case insensitive match against the title, category and tags
+    pub fn matches(&self, query: &str) -> bool {
+        let query = query.trim().to_lowercase();
+
+        query.is_empty()
+            || self.info.title.to_lowercase().contains(&query)
+            || self.info.category.to_lowercase().contains(&query)
+            || self
+                .info
+                .tags
+                .iter()
+                .any(|tag| tag.to_lowercase().contains(&query))
+    }
 }
 
 pub struct Presets {
@@ -28,57 +59,187 @@ impl Presets {
         ProjectDirs::from("com", "Additizer", "Additizer").map(|dirs| Self { dirs })
     }
 
-    fn read_preset_list_item(path: &Path) -> Option<PresetListItem> {
+    fn read_preset_info(path: &Path) -> Option<PresetInfo> {
         let file = File::open(path).ok()?;
         let reader = BufReader::new(file);
-        let mut item: PresetListItem = serde_json::from_reader(reader).ok()?;
 
-        item.path = path.to_str()?.to_string();
-        Some(item)
+        serde_json::from_reader(reader).ok()
+    }
+
+    fn factory_presets_list() -> impl Iterator<Item = PresetListItem> {
+        FACTORY_PRESETS
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, bytes)| {
+                Some(PresetListItem {
+                    info: serde_json::from_slice(bytes).ok()?,
+                    source: PresetSource::Factory(idx),
+                })
+            })
     }
 
     pub fn read_presets_list(&self) -> Vec<PresetListItem> {
-        let presets_dir = self.dirs.data_dir();
+        let mut list: Vec<PresetListItem> = Self::factory_presets_list().collect();
+
+        if let Ok(entries) = self.dirs.data_dir().read_dir() {
+            list.extend(
+                entries
+                    .filter_map(|entry| entry.ok())
+                    .map(|entry| entry.path())
+                    .filter(|path| path.extension().is_some_and(|ext| ext == PRESET_EXT))
+                    .filter_map(|path| {
+                        Some(PresetListItem {
+                            info: Self::read_preset_info(&path)?,
+                            source: PresetSource::User(path),
+                        })
+                    }),
+            );
+        }
+
+        list.sort_by_key(|item| item.info.title.to_lowercase());
+
+        list
+    }
 
-        let Ok(entries) = presets_dir.read_dir() else {
-            return Vec::new();
+    pub fn read_preset(source: &PresetSource) -> Option<Preset> {
+        match source {
+            PresetSource::Factory(idx) => serde_json::from_slice(FACTORY_PRESETS.get(*idx)?).ok(),
+            PresetSource::User(path) => {
+                let file = File::open(path).ok()?;
+                let reader = BufReader::new(file);
+
+                serde_json::from_reader(reader).ok()
+            }
+        }
+    }
+
+    fn preset_path(&self, title: &str) -> PathBuf {
+        let mut path = PathBuf::from(self.dirs.data_dir());
+
+        path.push(title);
+        path.set_extension(PRESET_EXT);
+        path
+    }
+
+    pub fn write_preset(&self, preset: &Preset) -> Option<()> {
+        fs::create_dir_all(self.dirs.data_dir()).ok()?;
+
+        let file = File::create(self.preset_path(&preset.info.title)).ok()?;
+        let writer = BufWriter::new(file);
+
+        serde_json::to_writer(writer, preset).ok()?;
+        Some(())
+    }
+
+    pub fn rename_preset(&self, source: &PresetSource, title: &str) -> Option<()> {
+        let PresetSource::User(path) = source else {
+            return None;
         };
 
-        let mut list: Vec<PresetListItem> = entries
-            .filter_map(|entry| entry.ok())
-            .filter(|entry| {
-                entry
-                    .path()
-                    .extension()
-                    .map(|ext| ext == PRESET_EXT)
-                    .unwrap_or(false)
-            })
-            .filter_map(|entry| Self::read_preset_list_item(entry.path().as_path()))
-            .collect();
+        let mut preset = Self::read_preset(source)?;
 
-        list.sort_by_key(|item| item.info.title.to_lowercase());
+        preset.info.title = title.to_string();
+        self.write_preset(&preset)?;
+
+        if self.preset_path(title) != *path {
+            fs::remove_file(path).ok()?;
+        }
+
+        Some(())
+    }
+
+    pub fn delete_preset(source: &PresetSource) -> Option<()> {
+        let PresetSource::User(path) = source else {
+            return None;
+        };
+
+        fs::remove_file(path).ok()
+    }
+
+    fn sub_patches_dir(&self) -> PathBuf {
+        self.dirs.data_dir().join(SUB_PATCHES_DIR)
+    }
+
+    // Names and paths of the saved sub-patches, sorted by name
+    pub fn read_sub_patches_list(&self) -> Vec<(String, PathBuf)> {
+        let mut list: Vec<_> = self
+            .sub_patches_dir()
+            .read_dir()
+            .map(|entries| {
+                entries
+                    .filter_map(|entry| entry.ok())
+                    .map(|entry| entry.path())
+                    .filter(|path| path.extension().is_some_and(|ext| ext == SUB_PATCH_EXT))
+                    .filter_map(|path| {
+                        Some((path.file_stem()?.to_string_lossy().into_owned(), path))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
 
+        list.sort_by_key(|(name, _)| name.to_lowercase());
         list
     }
 
-    pub fn read_preset(path: &str) -> Option<Preset> {
+    pub fn read_sub_patch(path: &Path) -> Option<SubPatch> {
         let file = File::open(path).ok()?;
         let reader = BufReader::new(file);
 
-        serde_json::from_reader(reader).ok()?
+        serde_json::from_reader(reader).ok()
     }
 
-    pub fn write_preset(&self, preset: &Preset) -> Option<()> {
-        let mut path = PathBuf::from(self.dirs.data_dir());
+    pub fn write_sub_patch(&self, sub_patch: &SubPatch) -> Option<()> {
+        let dir = self.sub_patches_dir();
 
-        fs::create_dir_all(path.as_path()).ok()?;
-        path.push(&preset.info.title);
-        path.set_extension(PRESET_EXT);
+        fs::create_dir_all(&dir).ok()?;
+
+        let mut path = dir.join(&sub_patch.name);
+
+        path.set_extension(SUB_PATCH_EXT);
 
         let file = File::create(path).ok()?;
         let writer = BufWriter::new(file);
 
-        serde_json::to_writer(writer, preset).ok()?;
+        serde_json::to_writer(writer, sub_patch).ok()?;
         Some(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn factory_presets_are_readable() {
+        let list: Vec<_> = Presets::factory_presets_list().collect();
+
+        assert_eq!(list.len(), FACTORY_PRESETS.len());
+
+        for item in &list {
+            assert!(!item.info.title.is_empty());
+            assert!(!item.info.category.is_empty());
+
+            let preset = Presets::read_preset(&item.source).expect("factory preset");
+
+            assert!(!preset.engine.modules.is_empty());
+        }
+    }
+
+    #[test]
+    fn search_matches_title_category_and_tags() {
+        let item = PresetListItem {
+            info: PresetInfo {
+                title: "Glass Bell".into(),
+                category: "Keys".into(),
+                tags: vec!["Bright".into(), "FM".into()],
+            },
+            source: PresetSource::Factory(0),
+        };
+
+        assert!(item.matches(""));
+        assert!(item.matches("glass"));
+        assert!(item.matches("KEYS"));
+        assert!(item.matches("bri"));
+        assert!(!item.matches("pad"));
+    }
+}
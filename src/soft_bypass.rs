@@ -0,0 +1,99 @@
+use crate::synth_engine::Sample;
+
+const FADE_TIME: Sample = 0.005;
+
+// Host bypass. The output fades out instead of stopping abruptly and fades back in after, fully
+// bypassed the engine isn't processed at all.
+pub struct SoftBypass {
+    length: usize,
+    // Samples into the fade, `length` is the full output
+    position: usize,
+}
+
+impl SoftBypass {
+    pub fn new() -> Self {
+        Self {
+            length: 1,
+            position: 1,
+        }
+    }
+
+    pub fn initialize(&mut self, sample_rate: Sample) {
+        self.length = ((FADE_TIME * sample_rate) as usize).max(1);
+        self.position = self.length;
+    }
+
+    pub fn is_silent(&self, bypassed: bool) -> bool {
+        bypassed && self.position == 0
+    }
+
+    fn position_after(&self, bypassed: bool, samples: usize) -> usize {
+        if bypassed {
+            self.position.saturating_sub(samples)
+        } else {
+            (self.position + samples).min(self.length)
+        }
+    }
+
+    // Applies the fade to the output. Returns true once faded out, the voices can be cleared then.
+    pub fn process(&mut self, bypassed: bool, outputs: &mut [&mut [f32]]) -> bool {
+        let target = if bypassed { 0 } else { self.length };
+
+        if self.position == target {
+            return false;
+        }
+
+        let samples = outputs.iter().map(|output| output.len()).min().unwrap_or(0);
+
+        for output in outputs.iter_mut() {
+            for (idx, out) in output.iter_mut().take(samples).enumerate() {
+                *out *= self.position_after(bypassed, idx + 1) as Sample / self.length as Sample;
+            }
+        }
+
+        self.position = self.position_after(bypassed, samples);
+        self.is_silent(bypassed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_RATE: Sample = 48_000.0;
+
+    #[test]
+    fn fades_out_and_back_in() {
+        let mut bypass = SoftBypass::new();
+        let mut left = vec![1.0; 128];
+        let mut right = vec![1.0; 128];
+        let length = (FADE_TIME * SAMPLE_RATE) as usize;
+
+        bypass.initialize(SAMPLE_RATE);
+
+        assert!(!bypass.process(false, &mut [&mut left[..], &mut right[..]]));
+        assert!(left.iter().all(|s| *s == 1.0));
+
+        let mut faded = Vec::new();
+        let mut silent = false;
+
+        while !silent {
+            left.fill(1.0);
+            right.fill(1.0);
+            silent = bypass.process(true, &mut [&mut left[..], &mut right[..]]);
+            faded.extend_from_slice(&left);
+        }
+
+        assert!(bypass.is_silent(true));
+        assert!(faded.windows(2).all(|pair| pair[1] <= pair[0]));
+        assert!(faded[length - 1] == 0.0 && faded[length - 2] > 0.0);
+        assert!(!bypass.is_silent(false));
+
+        left.fill(1.0);
+        right.fill(1.0);
+        bypass.process(false, &mut [&mut left[..], &mut right[..]]);
+
+        assert!(left[0] > 0.0 && left[0] < 0.1);
+        assert!(left[length - 1] == 1.0);
+    }
+}
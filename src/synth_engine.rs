@@ -1,77 +1,153 @@
 use core::f32;
-use std::{
-    collections::{HashMap, HashSet},
-    sync::Arc,
-};
+use std::{path::Path, sync::Arc, time::Instant};
 
 use nih_plug::params::FloatParam;
 use rustc_hash::FxHashMap;
-use std::assert_matches;
-use topo_sort::{SortResults, TopoSort};
 
 use crate::synth_engine::{
+    analyzer::Analyzer,
+    automation::Automation,
+    capture::{Capture, create_capture_pair},
+    chord_memory::{ChordConfig, ChordMemory, ChordNotes, DueNotes},
+    limiter::LimiterConfig,
+    midi_mapping::MidiMapping,
+    midi_state::{MidiState, NUM_MIDI_CCS},
     module_handle::ModuleHandle,
     modules::Output,
+    output_trims::OutputTrims,
+    oversampling::MAX_OVERSAMPLED_BLOCK,
+    profiler::ModuleProfiler,
     routing::{
-        InputSlot, InputSlots, InputSource, MIN_MODULE_ID, ModuleLink, OutputsArena,
-        ProcessContext, ProcessParams, SpectralInputSlot, data_types_compatible,
+        InputMeta, InputSource, MAX_VOICES, MIN_MODULE_ID, ModuleLink, OutputsArena,
+        ProcessContext, ProcessParams, data_types_compatible,
     },
+    scope::{Scope, create_scope_pair},
+    staged_routing::{ModuleOutputs, RoutingTables, StagedRouting},
     synth_module::SynthModule,
     voices_handler::{
-        DecayingVoices, MAX_AVAILABLE_VOICES, PlayingVoices, VoiceEvents, VoicesHandler,
+        DecayingVoices, MAX_AVAILABLE_VOICES, MPE_MASTER_CHANNEL, MPE_PITCH_BEND_RANGE,
+        PlayingVoices, VoiceEvents, VoiceState, VoicesHandler,
     },
+    wavetable::WavetableWriter,
 };
 
 pub use buffer::{Buffer, HARMONIC_SERIES_BUFFER, SPECTRAL_BUFFER_SIZE, SpectralBuffer};
-pub use config::{EngineConfig, EngineParams, LinkConfig, ModuleConfig};
+pub use config::{
+    AutomationConfig, EngineConfig, EngineParams, ExposedInputConfig, LinkConfig,
+    MidiMappingConfig, ModuleConfig, OutputTrimConfig, SubPatchConfig,
+};
+pub use curves::{CurveFunction, Exponential};
 pub use module_handle::ModuleType;
 pub use modules::{
-    Amplifier, Envelope, Expressions, ExternalParam, ExternalParamsBlock, Lfo, LfoShape, Mixer,
-    Oscillator, ShaperType, SpectralBlend, SpectralFilter, SpectralFilterType, SpectralMixer,
-    WaveShaper,
+    Amplifier, BlendMode, Chorus, Delay, Envelope, EnvelopeFollower, EnvelopeLoop, Expressions,
+    ExternalParam, ExternalParamsBlock, Filter, FilterType, HarmonicComb, HarmonicEnvelopes,
+    HarmonicShift, HarmonicShiftMode, HarmonicStretch, Lfo, LfoShape, Math, MathOperation,
+    MidiControl, MidiSource, Mixer, NoteDivision, Oscillator, Panner, QuantizeGrid, Reverb,
+    RingMod, SampleResynth, ShaperType, SpectralAnalyzer, SpectralBlend, SpectralDynamics,
+    SpectralFilter, SpectralFilterType, SpectralMixer, SpectralTilt, WaveShaper,
     amplifier::{self},
+    chorus::{self},
+    delay::{self},
     envelope::{self},
+    envelope_follower::{self},
     expressions::{self},
-    external_param::{self},
+    external_param::{self, NUM_AUTOMATION_PARAMS},
+    filter::{self},
+    harmonic_comb::{self},
     harmonic_editor::{self, HarmonicEditor},
+    harmonic_envelopes::{self},
+    harmonic_shift::{self},
+    harmonic_stretch::{self},
     lfo::{self},
+    math::{self},
+    midi_control::{self},
     mixer::{self},
-    oscillator::{self},
+    oscillator::{self, TableUpdate, WaveQuality},
+    panner::{self},
+    reverb::{self},
+    ring_mod::{self},
+    sample_resynth::{self},
+    spectral_analyzer::{self},
     spectral_blend::{self},
+    spectral_dynamics::{self},
     spectral_filter::{self},
     spectral_mixer::{self},
+    spectral_tilt::{self},
     wave_shaper::{self},
 };
+pub use output_mode::OutputMode;
+pub use output_trims::MAX_OUTPUT_TRIM;
+pub use oversampling::Oversampling;
+pub use randomizer::{RandomParam, Randomizer};
 pub use routing::{
-    DataType, Expression, Input, InputId, MixType, ModuleId, NUM_CHANNELS, OUTPUT_MODULE_ID,
-    VoiceEvent, VolumeType,
+    DataType, Expression, Input, InputId, LinkCurve, LinkMode, LinkPolarity, MixType, ModuleId,
+    NUM_CHANNELS, OUTPUT_MODULE_ID, VoiceEvent, VolumeType,
 };
 pub use smooth::SmoothedSampleParams;
+pub use spectral_size::SpectralSize;
 pub use stereo_sample::StereoSample;
 pub use synth_module::ModuleUiBridge;
+pub use tuning::{EDO_PRESETS, TuningConfig};
 pub use types::Sample;
+pub use voices_handler::{
+    MAX_MASTER_TUNE, MAX_TRANSPOSE, MtsEspMode, PortamentoMode, StealPriority, VoiceState,
+};
+pub use wavetable::MAX_WAVETABLE_FRAMES;
 
+pub mod analyzer;
+mod automation;
 mod buffer;
 mod config;
 #[macro_use]
 mod synth_module;
 mod biquad_filter;
+pub mod capture;
+pub mod chord_memory;
 mod curves;
+mod dc_blocker;
+mod declicker;
+mod delay_line;
+mod edit_sender;
+mod half_band;
 mod iir_decimator;
+pub mod limiter;
+mod midi_mapping;
+mod midi_state;
 mod module_handle;
 mod modules;
+#[cfg(feature = "mts-esp")]
+mod mts_esp;
+mod output_mode;
+mod output_trims;
+mod oversampling;
 mod phase;
+mod profiler;
+mod randomizer;
 mod routing;
+mod sample_analysis;
+pub mod scope;
 mod smooth;
+mod spectral_size;
+mod staged_routing;
 mod stereo_sample;
+mod triple_buffer;
+mod tuning;
 mod types;
 pub mod ui_bridge;
 mod voices_handler;
+mod wavetable;
 
 #[cfg(test)]
 mod tests;
 
 pub const MAX_BLOCK_SIZE: usize = 128;
+const MIN_PRECISE_SPLIT: usize = 16; // Shorter blocks aren't split in half for precise updates
+// Modules of a patch, the editor doesn't add more. Room for them is reserved up front.
+pub const MAX_MODULES: usize = 256;
+pub const DEFAULT_TEMPO: Sample = 120.0;
+const WAVETABLE_NOTE: u8 = 60;
+// The editor keyboard has no release velocity, MIDI uses the middle value for that
+const KEYBOARD_RELEASE_VELOCITY: Sample = 0.5;
 
 type ModulesMap = FxHashMap<ModuleId, ModuleHandle>;
 type RoutingMap = FxHashMap<InputId, Vec<InputSource>>;
@@ -80,23 +156,51 @@ pub struct SynthEngine {
     next_id: ModuleId,
     host_sample_rate: f32,
     block_size: usize,
-    oversampling: bool,
+    oversampling: Oversampling,
+    wave_quality: WaveQuality,
+    spectral_size: SpectralSize,
+    table_update: TableUpdate,
+    background_tables: bool, // Off for offline renders and the copies made for exports
     spectrum_channels: usize,
+    tempo: Sample,
+    sidechain: [[Sample; MAX_BLOCK_SIZE]; NUM_CHANNELS],
+    sidechain_len: usize,
+    keyboard_note: Option<u8>, // Held on the editor keyboard
     modules: ModulesMap,
     input_sources: RoutingMap,
     execution_order: Vec<ModuleId>,
+    bus_chain: Vec<ModuleId>,
+    solo: Option<ModuleId>,
     voices_handler: VoicesHandler,
+    midi_state: MidiState,
+    chord_memory: ChordMemory,
     external_params: Option<Arc<ExternalParamsBlock>>,
+    automation: Automation,
+    midi_mapping: MidiMapping,
+    output_trims: OutputTrims,
     audio_end: ui_bridge::AudioEnd,
     ui_end: Option<ui_bridge::UiEnd>,
+    capture: Option<Capture>,
+    scope: Option<Scope>,
+    analyzer: Option<Analyzer>,
     outputs_arena: OutputsArena,
+    profiler: ModuleProfiler,
 }
 
 macro_rules! add_module_method {
-    ($func_name:ident, $module_type:ident $(, $arg:ident )*) => {
+    ($func_name:ident, $module_type:ident) => {
         pub fn $func_name(&mut self) -> ModuleId {
             let id = self.alloc_module_id();
-            let mut module = ModuleHandle::$module_type(Box::new($module_type::new(id $(, self.$arg() )*)));
+            let mut module = Self::new_module(
+                ModuleType::$module_type,
+                id,
+                self.wave_quality,
+                self.spectral_size,
+                self.table_update,
+                self.background_tables,
+                &self.get_external_params(),
+            )
+            .expect("should be a processing module");
 
             self.outputs_arena.allocate_slot(&mut module);
             self.modules.insert(id, module);
@@ -115,72 +219,83 @@ impl SynthEngine {
         host_sample_rate: Sample,
     ) -> Option<Self> {
         let (audio_end, ui_end) = ui_bridge::create_link_pair();
+        let (capture_tap, capture) = create_capture_pair(host_sample_rate);
+        let (scope_tap, scope) = create_scope_pair(host_sample_rate);
+        let (analyzer_tap, analyzer_scope) = create_scope_pair(host_sample_rate);
 
         let mut engine = Self {
             next_id: 1,
             host_sample_rate,
             block_size: Self::clamp_block_size(cfg.engine.block_size),
             oversampling: cfg.engine.oversampling,
+            wave_quality: cfg.engine.wave_quality,
+            spectral_size: cfg.engine.spectral_size,
+            table_update: cfg.engine.table_update,
+            background_tables: false,
             spectrum_channels: Self::stereo_spectrum_channels(cfg.engine.stereo_spectrum),
-            modules: ModulesMap::default(),
+            tempo: DEFAULT_TEMPO,
+            sidechain: [[0.0; MAX_BLOCK_SIZE]; NUM_CHANNELS],
+            sidechain_len: 0,
+            keyboard_note: None,
+            // Room for in-place rehashing too, staged modules are inserted on the audio thread
+            modules: ModulesMap::with_capacity_and_hasher(2 * MAX_MODULES, Default::default()),
             input_sources: RoutingMap::default(),
             execution_order: Vec::new(),
+            bus_chain: Vec::with_capacity(MAX_MODULES),
+            solo: None,
             voices_handler: VoicesHandler::new(
                 Self::clamp_num_voices(cfg.engine.num_voices),
                 cfg.engine.legato,
             ),
+            midi_state: MidiState::new(cfg.engine.pitch_bend_range),
+            chord_memory: ChordMemory::new(cfg.engine.chord),
             external_params: Some(external_params.clone()),
+            automation: Automation::new(external_params.clone()),
+            midi_mapping: MidiMapping::default(),
+            output_trims: OutputTrims::new(),
             audio_end,
             ui_end: Some(ui_end),
-            outputs_arena: OutputsArena::new(),
+            capture: Some(capture),
+            scope: Some(scope),
+            analyzer: Some(Analyzer::new(analyzer_scope)),
+            outputs_arena: OutputsArena::new(MAX_MODULES),
+            profiler: ModuleProfiler::new(&[]),
         };
 
+        engine.voices_handler.set_portamento(cfg.engine.portamento);
+        engine
+            .voices_handler
+            .set_steal_priority(cfg.engine.steal_priority);
+        engine.voices_handler.set_tuning(cfg.engine.tuning.clone());
+        engine.voices_handler.set_mts_esp(cfg.engine.mts_esp);
+        engine.voices_handler.set_mpe(cfg.engine.mpe);
+
         engine.modules.insert(
             OUTPUT_MODULE_ID,
             ModuleHandle::Output(Box::new(Output::new(
                 cfg.engine.output_gain,
                 cfg.engine.voice_kill_time,
                 output_level_param,
+                capture_tap,
+                scope_tap,
+                analyzer_tap,
+                cfg.engine.dc_blocker,
+                cfg.engine.output_mode,
+                cfg.engine.limiter,
             ))),
         );
 
         let mut max_module_id = MIN_MODULE_ID;
 
         for module_cfg in cfg.modules.iter() {
-            let mut module = match module_cfg {
-                ModuleConfig::Oscillator(cfg) => {
-                    ModuleHandle::Oscillator(Box::new(Oscillator::from_config(cfg)))
-                }
-                ModuleConfig::Envelope(cfg) => {
-                    ModuleHandle::Envelope(Box::new(Envelope::from_config(cfg)))
-                }
-                ModuleConfig::Lfo(cfg) => ModuleHandle::Lfo(Box::new(Lfo::from_config(cfg))),
-                ModuleConfig::Amplifier(cfg) => {
-                    ModuleHandle::Amplifier(Box::new(Amplifier::from_config(cfg)))
-                }
-                ModuleConfig::Mixer(cfg) => ModuleHandle::Mixer(Box::new(Mixer::from_config(cfg))),
-                ModuleConfig::WaveShaper(cfg) => {
-                    ModuleHandle::WaveShaper(Box::new(WaveShaper::from_config(cfg)))
-                }
-                ModuleConfig::SpectralFilter(cfg) => {
-                    ModuleHandle::SpectralFilter(Box::new(SpectralFilter::from_config(cfg)))
-                }
-                ModuleConfig::SpectralBlend(cfg) => {
-                    ModuleHandle::SpectralBlend(Box::new(SpectralBlend::from_config(cfg)))
-                }
-                ModuleConfig::SpectralMixer(cfg) => {
-                    ModuleHandle::SpectralMixer(Box::new(SpectralMixer::from_config(cfg)))
-                }
-                ModuleConfig::HarmonicEditor(cfg) => {
-                    ModuleHandle::HarmonicEditor(Box::new(HarmonicEditor::from_config(cfg)))
-                }
-                ModuleConfig::Expressions(cfg) => {
-                    ModuleHandle::Expressions(Box::new(Expressions::from_config(cfg)))
-                }
-                ModuleConfig::ExternalParam(cfg) => ModuleHandle::ExternalParam(Box::new(
-                    ExternalParam::from_config(cfg, external_params.clone()),
-                )),
-            };
+            let mut module = Self::module_from_config(
+                module_cfg,
+                engine.wave_quality,
+                engine.spectral_size,
+                engine.table_update,
+                engine.background_tables,
+                &external_params,
+            );
 
             let module_id = module.id();
 
@@ -198,13 +313,35 @@ impl SynthEngine {
 
         engine.next_id = max_module_id + 1;
 
-        if !engine.set_config_links(&cfg.links) {
+        if !engine.set_config_links(&cfg.links)
+            || engine.set_automation(&cfg.automation).is_err()
+            || engine.set_midi_mappings(&cfg.midi_mappings).is_err()
+        {
             return None;
         }
 
+        for trim in &cfg.output_trims {
+            engine.set_output_trim(trim.clone());
+        }
+
+        external_params.set_float_param_names(&cfg.float_param_names);
+        engine.preallocate();
+
         Some(engine)
     }
 
+    // Sizes what the audio thread fills up to the engine limits, the voice buffers of the
+    // modules are fixed at MAX_VOICES by BUFFER_SIZE already. Allocates, it's done before the
+    // audio thread runs the engine, modules added live from the editor then only fill the room.
+    pub fn preallocate(&mut self) {
+        self.modules
+            .reserve((2 * MAX_MODULES).saturating_sub(self.modules.len()));
+        self.bus_chain
+            .reserve(MAX_MODULES.saturating_sub(self.bus_chain.len()));
+        self.outputs_arena.preallocate();
+        self.voices_handler.preallocate();
+    }
+
     pub fn get_config(&self) -> EngineConfig {
         let mut module_ids: Vec<_> = self.modules.keys().copied().collect();
 
@@ -212,82 +349,315 @@ impl SynthEngine {
 
         let modules = module_ids
             .iter()
-            .filter_map(|&id| {
-                let module = self.modules.get(&id)?;
-                match module {
-                    ModuleHandle::Output(_) => None,
-                    ModuleHandle::Oscillator(m) => {
-                        Some(ModuleConfig::Oscillator(Box::new(m.get_config())))
-                    }
-                    ModuleHandle::Envelope(m) => {
-                        Some(ModuleConfig::Envelope(Box::new(m.get_config())))
-                    }
-                    ModuleHandle::Lfo(m) => Some(ModuleConfig::Lfo(Box::new(m.get_config()))),
-                    ModuleHandle::Amplifier(m) => {
-                        Some(ModuleConfig::Amplifier(Box::new(m.get_config())))
-                    }
-                    ModuleHandle::Mixer(m) => Some(ModuleConfig::Mixer(Box::new(m.get_config()))),
-                    ModuleHandle::WaveShaper(m) => {
-                        Some(ModuleConfig::WaveShaper(Box::new(m.get_config())))
-                    }
-                    ModuleHandle::SpectralFilter(m) => {
-                        Some(ModuleConfig::SpectralFilter(Box::new(m.get_config())))
-                    }
-                    ModuleHandle::SpectralBlend(m) => {
-                        Some(ModuleConfig::SpectralBlend(Box::new(m.get_config())))
-                    }
-                    ModuleHandle::SpectralMixer(m) => {
-                        Some(ModuleConfig::SpectralMixer(Box::new(m.get_config())))
-                    }
-                    ModuleHandle::HarmonicEditor(m) => {
-                        Some(ModuleConfig::HarmonicEditor(Box::new(m.get_config())))
-                    }
-                    ModuleHandle::Expressions(m) => {
-                        Some(ModuleConfig::Expressions(Box::new(m.get_config())))
-                    }
-                    ModuleHandle::ExternalParam(m) => {
-                        Some(ModuleConfig::ExternalParam(Box::new(m.get_config())))
-                    }
-                }
-            })
+            .filter_map(|id| Self::module_config(self.modules.get(id)?))
             .collect();
 
         EngineConfig {
             engine: self.get_engine_params(),
             modules,
-            links: self
-                .get_links()
-                .into_iter()
-                .map(|link| LinkConfig {
-                    src_id: link.src,
-                    dst_id: link.dst.module_id,
-                    dst_input: link.dst.input_type,
-                    amount: link.amount,
-                    modulator_id: link.modulation,
-                })
-                .collect(),
+            links: Self::link_configs(&self.input_sources),
+            automation: self.automation.get_config(),
+            midi_mappings: self.midi_mapping.get_config(),
+            output_trims: self.output_trims.get_config(),
+            float_param_names: self
+                .external_params
+                .as_ref()
+                .map(|params| params.float_param_names())
+                .unwrap_or_default(),
         }
     }
 
-    fn sample_rate(&self) -> Sample {
-        if self.oversampling {
-            2.0 * self.host_sample_rate
-        } else {
-            self.host_sample_rate
+    // Sorted, so the same routing always saves the same way
+    fn link_configs(input_sources: &RoutingMap) -> Vec<LinkConfig> {
+        let mut links: Vec<_> = Self::links(input_sources)
+            .into_iter()
+            .map(|link| LinkConfig {
+                src_id: link.src,
+                dst_id: link.dst.module_id,
+                dst_input: link.dst.input_type,
+                amount: link.amount,
+                modulator_id: link.modulation,
+                curve: link.curve,
+                polarity: link.polarity,
+                offset: link.offset,
+                mode: link.mode,
+            })
+            .collect();
+
+        links.sort_by_key(|link| (link.dst_id, link.dst_input, link.src_id));
+        links
+    }
+
+    fn module_from_config(
+        cfg: &ModuleConfig,
+        wave_quality: WaveQuality,
+        spectral_size: SpectralSize,
+        table_update: TableUpdate,
+        background_tables: bool,
+        external_params: &Arc<ExternalParamsBlock>,
+    ) -> ModuleHandle {
+        match cfg {
+            ModuleConfig::Oscillator(config) => {
+                let mut oscillator =
+                    Oscillator::from_config(config, wave_quality, spectral_size, table_update);
+
+                oscillator.set_background_tables(background_tables);
+                ModuleHandle::Oscillator(Box::new(oscillator))
+            }
+            ModuleConfig::Envelope(config) => {
+                ModuleHandle::Envelope(Box::new(Envelope::from_config(config)))
+            }
+            ModuleConfig::Lfo(config) => ModuleHandle::Lfo(Box::new(Lfo::from_config(config))),
+            ModuleConfig::Amplifier(config) => {
+                ModuleHandle::Amplifier(Box::new(Amplifier::from_config(config)))
+            }
+            ModuleConfig::Mixer(config) => {
+                ModuleHandle::Mixer(Box::new(Mixer::from_config(config)))
+            }
+            ModuleConfig::WaveShaper(config) => {
+                ModuleHandle::WaveShaper(Box::new(WaveShaper::from_config(config)))
+            }
+            ModuleConfig::SpectralFilter(config) => {
+                ModuleHandle::SpectralFilter(Box::new(SpectralFilter::from_config(config)))
+            }
+            ModuleConfig::SpectralBlend(config) => {
+                ModuleHandle::SpectralBlend(Box::new(SpectralBlend::from_config(config)))
+            }
+            ModuleConfig::SpectralMixer(config) => {
+                ModuleHandle::SpectralMixer(Box::new(SpectralMixer::from_config(config)))
+            }
+            ModuleConfig::HarmonicEditor(config) => {
+                ModuleHandle::HarmonicEditor(Box::new(HarmonicEditor::from_config(config)))
+            }
+            ModuleConfig::Expressions(config) => {
+                ModuleHandle::Expressions(Box::new(Expressions::from_config(config)))
+            }
+            ModuleConfig::ExternalParam(config) => ModuleHandle::ExternalParam(Box::new(
+                ExternalParam::from_config(config, external_params.clone()),
+            )),
+            ModuleConfig::MidiControl(config) => {
+                ModuleHandle::MidiControl(Box::new(MidiControl::from_config(config)))
+            }
+            ModuleConfig::Chorus(config) => {
+                ModuleHandle::Chorus(Box::new(Chorus::from_config(config)))
+            }
+            ModuleConfig::Delay(config) => {
+                ModuleHandle::Delay(Box::new(Delay::from_config(config)))
+            }
+            ModuleConfig::Reverb(config) => {
+                ModuleHandle::Reverb(Box::new(Reverb::from_config(config)))
+            }
+            ModuleConfig::RingMod(config) => {
+                ModuleHandle::RingMod(Box::new(RingMod::from_config(config)))
+            }
+            ModuleConfig::Panner(config) => {
+                ModuleHandle::Panner(Box::new(Panner::from_config(config)))
+            }
+            ModuleConfig::Filter(config) => {
+                ModuleHandle::Filter(Box::new(Filter::from_config(config)))
+            }
+            ModuleConfig::Math(config) => ModuleHandle::Math(Box::new(Math::from_config(config))),
+            ModuleConfig::HarmonicComb(config) => {
+                ModuleHandle::HarmonicComb(Box::new(HarmonicComb::from_config(config)))
+            }
+            ModuleConfig::SpectralTilt(config) => {
+                ModuleHandle::SpectralTilt(Box::new(SpectralTilt::from_config(config)))
+            }
+            ModuleConfig::HarmonicStretch(config) => {
+                ModuleHandle::HarmonicStretch(Box::new(HarmonicStretch::from_config(config)))
+            }
+            ModuleConfig::HarmonicShift(config) => {
+                ModuleHandle::HarmonicShift(Box::new(HarmonicShift::from_config(config)))
+            }
+            ModuleConfig::SpectralDynamics(config) => {
+                ModuleHandle::SpectralDynamics(Box::new(SpectralDynamics::from_config(config)))
+            }
+            ModuleConfig::HarmonicEnvelopes(config) => {
+                ModuleHandle::HarmonicEnvelopes(Box::new(HarmonicEnvelopes::from_config(config)))
+            }
+            ModuleConfig::SampleResynth(config) => {
+                ModuleHandle::SampleResynth(Box::new(SampleResynth::from_config(config)))
+            }
+            ModuleConfig::EnvelopeFollower(config) => {
+                ModuleHandle::EnvelopeFollower(Box::new(EnvelopeFollower::from_config(config)))
+            }
+            ModuleConfig::SpectralAnalyzer(config) => {
+                ModuleHandle::SpectralAnalyzer(Box::new(SpectralAnalyzer::from_config(config)))
+            }
         }
     }
 
+    // A module with the default settings, None for the Output module every engine has
+    fn new_module(
+        module_type: ModuleType,
+        id: ModuleId,
+        wave_quality: WaveQuality,
+        spectral_size: SpectralSize,
+        table_update: TableUpdate,
+        background_tables: bool,
+        external_params: &Arc<ExternalParamsBlock>,
+    ) -> Option<ModuleHandle> {
+        let module = match module_type {
+            ModuleType::Output => return None,
+            ModuleType::Oscillator => {
+                let mut oscillator = Oscillator::new(id, wave_quality, spectral_size, table_update);
+
+                oscillator.set_background_tables(background_tables);
+                ModuleHandle::Oscillator(Box::new(oscillator))
+            }
+            ModuleType::Envelope => ModuleHandle::Envelope(Box::new(Envelope::new(id))),
+            ModuleType::Lfo => ModuleHandle::Lfo(Box::new(Lfo::new(id))),
+            ModuleType::Amplifier => ModuleHandle::Amplifier(Box::new(Amplifier::new(id))),
+            ModuleType::Mixer => ModuleHandle::Mixer(Box::new(Mixer::new(id))),
+            ModuleType::WaveShaper => ModuleHandle::WaveShaper(Box::new(WaveShaper::new(id))),
+            ModuleType::SpectralFilter => {
+                ModuleHandle::SpectralFilter(Box::new(SpectralFilter::new(id)))
+            }
+            ModuleType::SpectralBlend => {
+                ModuleHandle::SpectralBlend(Box::new(SpectralBlend::new(id)))
+            }
+            ModuleType::SpectralMixer => {
+                ModuleHandle::SpectralMixer(Box::new(SpectralMixer::new(id)))
+            }
+            ModuleType::HarmonicEditor => {
+                ModuleHandle::HarmonicEditor(Box::new(HarmonicEditor::new(id)))
+            }
+            ModuleType::Expressions => ModuleHandle::Expressions(Box::new(Expressions::new(id))),
+            ModuleType::ExternalParam => ModuleHandle::ExternalParam(Box::new(ExternalParam::new(
+                id,
+                external_params.clone(),
+            ))),
+            ModuleType::MidiControl => ModuleHandle::MidiControl(Box::new(MidiControl::new(id))),
+            ModuleType::Chorus => ModuleHandle::Chorus(Box::new(Chorus::new(id))),
+            ModuleType::Delay => ModuleHandle::Delay(Box::new(Delay::new(id))),
+            ModuleType::Reverb => ModuleHandle::Reverb(Box::new(Reverb::new(id))),
+            ModuleType::RingMod => ModuleHandle::RingMod(Box::new(RingMod::new(id))),
+            ModuleType::Panner => ModuleHandle::Panner(Box::new(Panner::new(id))),
+            ModuleType::Filter => ModuleHandle::Filter(Box::new(Filter::new(id))),
+            ModuleType::Math => ModuleHandle::Math(Box::new(Math::new(id))),
+            ModuleType::HarmonicComb => ModuleHandle::HarmonicComb(Box::new(HarmonicComb::new(id))),
+            ModuleType::SpectralTilt => ModuleHandle::SpectralTilt(Box::new(SpectralTilt::new(id))),
+            ModuleType::HarmonicStretch => {
+                ModuleHandle::HarmonicStretch(Box::new(HarmonicStretch::new(id)))
+            }
+            ModuleType::HarmonicShift => {
+                ModuleHandle::HarmonicShift(Box::new(HarmonicShift::new(id)))
+            }
+            ModuleType::SpectralDynamics => {
+                ModuleHandle::SpectralDynamics(Box::new(SpectralDynamics::new(id)))
+            }
+            ModuleType::HarmonicEnvelopes => {
+                ModuleHandle::HarmonicEnvelopes(Box::new(HarmonicEnvelopes::new(id)))
+            }
+            ModuleType::SampleResynth => {
+                ModuleHandle::SampleResynth(Box::new(SampleResynth::new(id)))
+            }
+            ModuleType::EnvelopeFollower => {
+                ModuleHandle::EnvelopeFollower(Box::new(EnvelopeFollower::new(id)))
+            }
+            ModuleType::SpectralAnalyzer => {
+                ModuleHandle::SpectralAnalyzer(Box::new(SpectralAnalyzer::new(id)))
+            }
+        };
+
+        Some(module)
+    }
+
+    fn module_config(module: &ModuleHandle) -> Option<ModuleConfig> {
+        match module {
+            ModuleHandle::Output(_) => None,
+            ModuleHandle::Oscillator(m) => Some(ModuleConfig::Oscillator(Box::new(m.get_config()))),
+            ModuleHandle::Envelope(m) => Some(ModuleConfig::Envelope(Box::new(m.get_config()))),
+            ModuleHandle::Lfo(m) => Some(ModuleConfig::Lfo(Box::new(m.get_config()))),
+            ModuleHandle::Amplifier(m) => Some(ModuleConfig::Amplifier(Box::new(m.get_config()))),
+            ModuleHandle::Mixer(m) => Some(ModuleConfig::Mixer(Box::new(m.get_config()))),
+            ModuleHandle::WaveShaper(m) => Some(ModuleConfig::WaveShaper(Box::new(m.get_config()))),
+            ModuleHandle::SpectralFilter(m) => {
+                Some(ModuleConfig::SpectralFilter(Box::new(m.get_config())))
+            }
+            ModuleHandle::SpectralBlend(m) => {
+                Some(ModuleConfig::SpectralBlend(Box::new(m.get_config())))
+            }
+            ModuleHandle::SpectralMixer(m) => {
+                Some(ModuleConfig::SpectralMixer(Box::new(m.get_config())))
+            }
+            ModuleHandle::HarmonicEditor(m) => {
+                Some(ModuleConfig::HarmonicEditor(Box::new(m.get_config())))
+            }
+            ModuleHandle::Expressions(m) => {
+                Some(ModuleConfig::Expressions(Box::new(m.get_config())))
+            }
+            ModuleHandle::ExternalParam(m) => {
+                Some(ModuleConfig::ExternalParam(Box::new(m.get_config())))
+            }
+            ModuleHandle::MidiControl(m) => {
+                Some(ModuleConfig::MidiControl(Box::new(m.get_config())))
+            }
+            ModuleHandle::Chorus(m) => Some(ModuleConfig::Chorus(Box::new(m.get_config()))),
+            ModuleHandle::Delay(m) => Some(ModuleConfig::Delay(Box::new(m.get_config()))),
+            ModuleHandle::Reverb(m) => Some(ModuleConfig::Reverb(Box::new(m.get_config()))),
+            ModuleHandle::RingMod(m) => Some(ModuleConfig::RingMod(Box::new(m.get_config()))),
+            ModuleHandle::Panner(m) => Some(ModuleConfig::Panner(Box::new(m.get_config()))),
+            ModuleHandle::Filter(m) => Some(ModuleConfig::Filter(Box::new(m.get_config()))),
+            ModuleHandle::Math(m) => Some(ModuleConfig::Math(Box::new(m.get_config()))),
+            ModuleHandle::HarmonicComb(m) => {
+                Some(ModuleConfig::HarmonicComb(Box::new(m.get_config())))
+            }
+            ModuleHandle::SpectralTilt(m) => {
+                Some(ModuleConfig::SpectralTilt(Box::new(m.get_config())))
+            }
+            ModuleHandle::HarmonicStretch(m) => {
+                Some(ModuleConfig::HarmonicStretch(Box::new(m.get_config())))
+            }
+            ModuleHandle::HarmonicShift(m) => {
+                Some(ModuleConfig::HarmonicShift(Box::new(m.get_config())))
+            }
+            ModuleHandle::SpectralDynamics(m) => {
+                Some(ModuleConfig::SpectralDynamics(Box::new(m.get_config())))
+            }
+            ModuleHandle::HarmonicEnvelopes(m) => {
+                Some(ModuleConfig::HarmonicEnvelopes(Box::new(m.get_config())))
+            }
+            ModuleHandle::SampleResynth(m) => {
+                Some(ModuleConfig::SampleResynth(Box::new(m.get_config())))
+            }
+            ModuleHandle::EnvelopeFollower(m) => {
+                Some(ModuleConfig::EnvelopeFollower(Box::new(m.get_config())))
+            }
+            ModuleHandle::SpectralAnalyzer(m) => {
+                Some(ModuleConfig::SpectralAnalyzer(Box::new(m.get_config())))
+            }
+        }
+    }
+
+    fn sample_rate(&self) -> Sample {
+        self.oversampling.factor() as Sample * self.host_sample_rate
+    }
+
     fn get_engine_params(&self) -> EngineParams {
         let voices = self.voices_handler.get_ui_state();
 
         EngineParams {
             num_voices: voices.num_voices,
             legato: voices.legato,
+            portamento: voices.portamento,
+            steal_priority: voices.steal_priority,
+            tuning: self.voices_handler.tuning().cloned(),
+            mts_esp: voices.mts_esp,
+            mpe: voices.mpe,
+            pitch_bend_range: self.midi_state.pitch_bend_range(),
             block_size: self.block_size,
             oversampling: self.oversampling,
+            wave_quality: self.wave_quality,
+            spectral_size: self.spectral_size,
+            table_update: self.table_update,
             stereo_spectrum: self.spectrum_channels == NUM_CHANNELS,
             voice_kill_time: self.get_voice_kill_time(),
             output_gain: self.get_output_gain(),
+            dc_blocker: self.get_dc_blocker(),
+            output_mode: self.get_output_mode(),
+            limiter: self.get_limiter(),
+            chord: self.chord_memory.config(),
         }
     }
 
@@ -295,7 +665,14 @@ impl SynthEngine {
         ui_bridge::RoutingState::new(
             self.modules
                 .values()
-                .map(|m| (m.id(), ui_bridge::routing_state::Module::new(m)))
+                .map(|m| {
+                    let output_trim = self.output_trims.get(m.id()).cloned();
+
+                    (
+                        m.id(),
+                        ui_bridge::routing_state::Module::new(m, output_trim),
+                    )
+                })
                 .collect(),
             self.input_sources.clone(),
         )
@@ -310,6 +687,30 @@ impl SynthEngine {
         self.voices_handler.set_legato(legato);
     }
 
+    pub fn set_portamento(&mut self, portamento: PortamentoMode) {
+        self.voices_handler.set_portamento(portamento);
+    }
+
+    pub fn set_steal_priority(&mut self, steal_priority: StealPriority) {
+        self.voices_handler.set_steal_priority(steal_priority);
+    }
+
+    pub fn set_tuning(&mut self, tuning: Option<TuningConfig>) -> Option<TuningConfig> {
+        self.voices_handler.set_tuning(tuning)
+    }
+
+    pub fn set_mts_esp(&mut self, mts_esp: MtsEspMode) {
+        self.voices_handler.set_mts_esp(mts_esp);
+    }
+
+    pub fn set_mpe(&mut self, mpe: bool) {
+        self.voices_handler.set_mpe(mpe);
+    }
+
+    pub fn set_pitch_bend_range(&mut self, pitch_bend_range: Sample) {
+        self.midi_state.set_pitch_bend_range(pitch_bend_range);
+    }
+
     pub fn block_size(&self) -> usize {
         self.block_size
     }
@@ -324,14 +725,98 @@ impl SynthEngine {
         }
     }
 
-    pub fn set_oversampling(&mut self, oversampling: bool) {
+    pub fn set_oversampling(&mut self, oversampling: Oversampling) {
         self.oversampling = oversampling;
     }
 
+    // Latency reported to the host in host samples, stages delaying the output add theirs here
+    pub fn latency_samples(&self) -> u32 {
+        self.oversampling.latency()
+    }
+
+    pub fn wave_quality(&self) -> WaveQuality {
+        self.wave_quality
+    }
+
+    pub fn set_wave_quality(&mut self, wave_quality: WaveQuality) {
+        self.wave_quality = wave_quality;
+
+        for module in self.modules.values_mut() {
+            if let ModuleHandle::Oscillator(oscillator) = module {
+                oscillator.set_wave_quality(wave_quality);
+            }
+        }
+    }
+
+    pub fn spectral_size(&self) -> SpectralSize {
+        self.spectral_size
+    }
+
+    pub fn set_spectral_size(&mut self, spectral_size: SpectralSize) {
+        self.spectral_size = spectral_size;
+
+        // The other spectral modules see the new size through the process params
+        for module in self.modules.values_mut() {
+            match module {
+                ModuleHandle::Oscillator(oscillator) => oscillator.set_spectral_size(spectral_size),
+                ModuleHandle::HarmonicEditor(editor) => editor.set_needs_update(),
+                _ => (),
+            }
+        }
+    }
+
+    pub fn table_update(&self) -> TableUpdate {
+        self.table_update
+    }
+
+    pub fn set_table_update(&mut self, table_update: TableUpdate) {
+        self.table_update = table_update;
+
+        for module in self.modules.values_mut() {
+            if let ModuleHandle::Oscillator(oscillator) = module {
+                oscillator.set_table_update(table_update);
+            }
+        }
+    }
+
+    pub fn background_tables(&self) -> bool {
+        self.background_tables
+    }
+
+    // Held notes get their changed tables from the builder thread, a block or more later.
+    // Allocates, it's set before the audio thread gets the engine or while it's stopped.
+    pub fn set_background_tables(&mut self, enabled: bool) {
+        self.background_tables = enabled;
+
+        for module in self.modules.values_mut() {
+            if let ModuleHandle::Oscillator(oscillator) = module {
+                oscillator.set_background_tables(enabled);
+            }
+        }
+    }
+
     pub fn set_stereo_spectrum(&mut self, stereo_spectrum: bool) {
         self.spectrum_channels = Self::stereo_spectrum_channels(stereo_spectrum);
     }
 
+    // Host tempo in BPM, used by tempo synced modules
+    pub fn set_tempo(&mut self, tempo: Sample) {
+        self.tempo = tempo.clamp(20.0, 999.0);
+    }
+
+    // Host sidechain input of the next `process` call, up to a block of samples. Without it the
+    // modules reading the sidechain get silence.
+    pub fn set_sidechain<'a>(&mut self, channels: impl Iterator<Item = &'a [f32]>) {
+        let mut len = MAX_BLOCK_SIZE;
+
+        for (dst, samples) in self.sidechain.iter_mut().zip(channels) {
+            len = len.min(samples.len());
+            dst[..len].copy_from_slice(&samples[..len]);
+        }
+
+        self.sidechain_len = len;
+    }
+
     pub fn get_output_gain(&self) -> StereoSample {
         match self.modules.get(&OUTPUT_MODULE_ID) {
             Some(ModuleHandle::Output(output)) => output.get_gain(),
@@ -352,6 +837,45 @@ impl SynthEngine {
         }
     }
 
+    pub fn get_dc_blocker(&self) -> bool {
+        match self.modules.get(&OUTPUT_MODULE_ID) {
+            Some(ModuleHandle::Output(output)) => output.get_dc_blocker(),
+            _ => false,
+        }
+    }
+
+    pub fn set_dc_blocker(&mut self, enabled: bool) {
+        if let Some(ModuleHandle::Output(output)) = self.modules.get_mut(&OUTPUT_MODULE_ID) {
+            output.set_dc_blocker(enabled);
+        }
+    }
+
+    pub fn get_output_mode(&self) -> OutputMode {
+        match self.modules.get(&OUTPUT_MODULE_ID) {
+            Some(ModuleHandle::Output(output)) => output.get_output_mode(),
+            _ => OutputMode::Stereo,
+        }
+    }
+
+    pub fn set_output_mode(&mut self, output_mode: OutputMode) {
+        if let Some(ModuleHandle::Output(output)) = self.modules.get_mut(&OUTPUT_MODULE_ID) {
+            output.set_output_mode(output_mode);
+        }
+    }
+
+    pub fn get_limiter(&self) -> LimiterConfig {
+        match self.modules.get(&OUTPUT_MODULE_ID) {
+            Some(ModuleHandle::Output(output)) => output.get_limiter(),
+            _ => LimiterConfig::default(),
+        }
+    }
+
+    pub fn set_limiter(&mut self, limiter: LimiterConfig) {
+        if let Some(ModuleHandle::Output(output)) = self.modules.get_mut(&OUTPUT_MODULE_ID) {
+            output.set_limiter(limiter);
+        }
+    }
+
     fn clamp_num_voices(num_voices: usize) -> usize {
         num_voices.clamp(1, Self::AVAILABLE_VOICES)
     }
@@ -371,12 +895,230 @@ impl SynthEngine {
     add_module_method!(add_spectral_mixer, SpectralMixer);
     add_module_method!(add_harmonic_editor, HarmonicEditor);
     add_module_method!(add_expressions, Expressions);
-    add_module_method!(add_external_param, ExternalParam, get_external_params);
+    add_module_method!(add_external_param, ExternalParam);
+    add_module_method!(add_midi_control, MidiControl);
+    add_module_method!(add_chorus, Chorus);
+    add_module_method!(add_delay, Delay);
+    add_module_method!(add_reverb, Reverb);
+    add_module_method!(add_ring_mod, RingMod);
+    add_module_method!(add_panner, Panner);
+    add_module_method!(add_filter, Filter);
+    add_module_method!(add_math, Math);
+    add_module_method!(add_harmonic_comb, HarmonicComb);
+    add_module_method!(add_spectral_tilt, SpectralTilt);
+    add_module_method!(add_harmonic_stretch, HarmonicStretch);
+    add_module_method!(add_harmonic_shift, HarmonicShift);
+    add_module_method!(add_spectral_dynamics, SpectralDynamics);
+    add_module_method!(add_harmonic_envelopes, HarmonicEnvelopes);
+    add_module_method!(add_sample_resynth, SampleResynth);
+    add_module_method!(add_envelope_follower, EnvelopeFollower);
+    add_module_method!(add_spectral_analyzer, SpectralAnalyzer);
 
     fn get_external_params(&self) -> Arc<ExternalParamsBlock> {
         Arc::clone(self.external_params.as_ref().unwrap())
     }
 
+    pub fn float_param_names(&self) -> Vec<String> {
+        self.get_external_params().float_param_names()
+    }
+
+    pub fn set_float_param_name(&mut self, idx: usize, name: &str) {
+        self.get_external_params().set_float_param_name(idx, name);
+    }
+
+    // Copies the module config into a new module, `with_links` also copies the links into its inputs
+    pub fn duplicate_module(&mut self, id: ModuleId, with_links: bool) -> Result<ModuleId, String> {
+        let mut config = self
+            .modules
+            .get(&id)
+            .and_then(Self::module_config)
+            .ok_or_else(|| "Invalid module.".to_string())?;
+
+        let new_id = self.alloc_module_id();
+
+        config.set_id(new_id);
+
+        let mut module = Self::module_from_config(
+            &config,
+            self.wave_quality,
+            self.spectral_size,
+            self.table_update,
+            self.background_tables,
+            &self.get_external_params(),
+        );
+
+        self.outputs_arena.allocate_slot(&mut module);
+        self.modules.insert(new_id, module);
+
+        if with_links {
+            let mut links = self.get_links();
+            let input_links: Vec<_> = links
+                .iter()
+                .filter(|link| link.dst.module_id == id)
+                .map(|link| ModuleLink {
+                    dst: InputId::new(link.dst.input_type, new_id),
+                    ..*link
+                })
+                .collect();
+
+            links.extend(input_links);
+            self.setup_routing(&links)?;
+        }
+
+        if let Some(trim) = self.output_trims.get(id).cloned() {
+            self.output_trims.set(OutputTrimConfig {
+                module_id: new_id,
+                ..trim
+            });
+        }
+
+        Ok(new_id)
+    }
+
+    // The links between the modules are kept, the inputs linked from other modules are exposed
+    pub fn extract_sub_patch(&self, module_ids: &[ModuleId]) -> Result<SubPatchConfig, String> {
+        Self::sub_patch_config(module_ids, &self.input_sources, |id| {
+            self.modules.get(&id).and_then(Self::module_config)
+        })
+    }
+
+    // The editor extracts sub-patches too, from the module configs its bridges keep
+    fn sub_patch_config(
+        module_ids: &[ModuleId],
+        input_sources: &RoutingMap,
+        module_config: impl Fn(ModuleId) -> Option<ModuleConfig>,
+    ) -> Result<SubPatchConfig, String> {
+        if module_ids.is_empty() {
+            return Err("No modules selected.".to_string());
+        }
+
+        let modules = module_ids
+            .iter()
+            .map(|id| module_config(*id).ok_or_else(|| "Invalid module.".to_string()))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut links = Vec::new();
+        let mut exposed_inputs = Vec::new();
+
+        for link in Self::links(input_sources)
+            .into_iter()
+            .filter(|link| module_ids.contains(&link.dst.module_id))
+        {
+            if module_ids.contains(&link.src)
+                && link.modulation.is_none_or(|id| module_ids.contains(&id))
+            {
+                links.push(LinkConfig {
+                    src_id: link.src,
+                    dst_id: link.dst.module_id,
+                    dst_input: link.dst.input_type,
+                    amount: link.amount,
+                    modulator_id: link.modulation,
+                    curve: link.curve,
+                    polarity: link.polarity,
+                    offset: link.offset,
+                    mode: link.mode,
+                });
+            } else {
+                let input = ExposedInputConfig {
+                    module_id: link.dst.module_id,
+                    input: link.dst.input_type,
+                };
+
+                if !exposed_inputs.contains(&input) {
+                    exposed_inputs.push(input);
+                }
+            }
+        }
+
+        Ok(SubPatchConfig {
+            modules,
+            links,
+            exposed_inputs,
+        })
+    }
+
+    // Adds the sub-patch modules under new ids, returns the new id for each sub-patch module id
+    pub fn insert_sub_patch(
+        &mut self,
+        sub_patch: &SubPatchConfig,
+    ) -> Result<FxHashMap<ModuleId, ModuleId>, String> {
+        let new_ids = Self::sub_patch_ids(sub_patch, self.next_id)?;
+        let links = Self::sub_patch_links(sub_patch, &new_ids)?;
+
+        self.next_id += new_ids.len() as ModuleId;
+
+        let external_params = self.get_external_params();
+
+        for config in &sub_patch.modules {
+            let mut config = config.clone();
+
+            config.set_id(new_ids[&config.id()]);
+
+            let mut module = Self::module_from_config(
+                &config,
+                self.wave_quality,
+                self.spectral_size,
+                self.table_update,
+                self.background_tables,
+                &external_params,
+            );
+
+            self.outputs_arena.allocate_slot(&mut module);
+            self.modules.insert(config.id(), module);
+        }
+
+        if !self.set_config_links(&links) {
+            for id in new_ids.values() {
+                self.remove_module(*id);
+            }
+
+            return Err("Invalid sub-patch links.".to_string());
+        }
+
+        Ok(new_ids)
+    }
+
+    // New ids from `first_id` up for the sub-patch modules, keyed by their ids in the sub-patch
+    fn sub_patch_ids(
+        sub_patch: &SubPatchConfig,
+        first_id: ModuleId,
+    ) -> Result<FxHashMap<ModuleId, ModuleId>, String> {
+        let mut new_ids = FxHashMap::default();
+
+        for (idx, config) in sub_patch.modules.iter().enumerate() {
+            if new_ids
+                .insert(config.id(), first_id + idx as ModuleId)
+                .is_some()
+            {
+                return Err("Duplicate sub-patch module.".to_string());
+            }
+        }
+
+        Ok(new_ids)
+    }
+
+    fn sub_patch_links(
+        sub_patch: &SubPatchConfig,
+        new_ids: &FxHashMap<ModuleId, ModuleId>,
+    ) -> Result<Vec<LinkConfig>, String> {
+        sub_patch
+            .links
+            .iter()
+            .map(|link| {
+                Some(LinkConfig {
+                    src_id: *new_ids.get(&link.src_id)?,
+                    dst_id: *new_ids.get(&link.dst_id)?,
+                    modulator_id: match link.modulator_id {
+                        Some(id) => Some(*new_ids.get(&id)?),
+                        None => None,
+                    },
+                    ..link.clone()
+                })
+            })
+            .collect::<Option<Vec<_>>>()
+            .ok_or_else(|| "Invalid sub-patch link.".to_string())
+    }
+
     pub fn remove_module(&mut self, id: ModuleId) {
         let Some(module) = self.modules.get(&id) else {
             return;
@@ -384,6 +1126,9 @@ impl SynthEngine {
 
         self.outputs_arena.free_slot(module);
         self.modules.remove(&id);
+        self.automation.remove_module(id);
+        self.midi_mapping.remove_module(id);
+        self.output_trims.remove_module(id);
 
         let new_links: Vec<_> = self
             .get_links()
@@ -391,35 +1136,152 @@ impl SynthEngine {
             .filter(|link| !(link.src == id || link.dst.module_id == id))
             .collect();
 
-        self.setup_routing(&new_links).unwrap();
+        self.setup_routing(&new_links).unwrap();
+    }
+
+    pub fn get_automation(&self) -> Vec<AutomationConfig> {
+        self.automation.get_config()
+    }
+
+    pub fn set_automation(&mut self, automation: &[AutomationConfig]) -> Result<(), String> {
+        for assignment in automation {
+            self.can_be_automated(assignment)?;
+        }
+
+        self.automation.set_config(automation);
+        Ok(())
+    }
+
+    fn can_be_automated(&self, assignment: &AutomationConfig) -> Result<(), String> {
+        Self::check_automation(assignment, self.module_inputs(assignment.module_id))
+    }
+
+    fn check_automation(
+        assignment: &AutomationConfig,
+        inputs: Option<&[InputMeta]>,
+    ) -> Result<(), String> {
+        if assignment.slot >= NUM_AUTOMATION_PARAMS {
+            return Err("Invalid automation slot.".to_string());
+        }
+
+        Self::check_value_input(inputs, assignment.input)
+    }
+
+    pub fn get_solo(&self) -> Option<ModuleId> {
+        self.solo
+    }
+
+    // Sends the audio output of the module straight to the output, skipping the bus modules
+    pub fn set_solo(&mut self, module_id: Option<ModuleId>) {
+        self.solo = module_id.filter(|id| {
+            self.modules
+                .get(id)
+                .is_some_and(|module| module.output_type() == DataType::Audio)
+        });
+        self.setup_bus_chain();
+        self.declick_output();
+    }
+
+    pub fn set_module_bypass(&mut self, module_id: ModuleId, bypass: bool) {
+        if let Some(module) = self.modules.get_mut(&module_id) {
+            module.set_bypass(bypass);
+            self.declick_output();
+        }
+    }
+
+    // Audio modules only, modulation is scaled by the link amounts and the Output module has volume
+    pub fn can_trim_output(&self, module_id: ModuleId) -> bool {
+        module_id != OUTPUT_MODULE_ID
+            && self
+                .modules
+                .get(&module_id)
+                .is_some_and(|module| module.output_type() == DataType::Audio)
+    }
+
+    pub fn get_output_trim(&self, module_id: ModuleId) -> Option<OutputTrimConfig> {
+        self.output_trims.get(module_id).cloned()
+    }
+
+    pub fn set_output_trim(&mut self, trim: OutputTrimConfig) {
+        if self.can_trim_output(trim.module_id) {
+            self.output_trims.set(trim);
+        }
+    }
+
+    pub fn get_midi_mappings(&self) -> Vec<MidiMappingConfig> {
+        self.midi_mapping.get_config()
+    }
+
+    pub fn set_midi_mappings(&mut self, mappings: &[MidiMappingConfig]) -> Result<(), String> {
+        for mapping in mappings {
+            Self::check_midi_mapping(mapping, self.module_inputs(mapping.module_id))?;
+        }
+
+        self.midi_mapping.set_config(mappings);
+        Ok(())
+    }
+
+    fn check_midi_mapping(
+        mapping: &MidiMappingConfig,
+        inputs: Option<&[InputMeta]>,
+    ) -> Result<(), String> {
+        if mapping.cc as usize >= NUM_MIDI_CCS {
+            return Err("Invalid MIDI CC.".to_string());
+        }
+
+        Self::check_value_input(inputs, mapping.input)
+    }
+
+    fn module_inputs(&self, module_id: ModuleId) -> Option<&'static [InputMeta]> {
+        self.modules.get(&module_id).map(|module| module.inputs())
+    }
+
+    fn check_value_input(inputs: Option<&[InputMeta]>, input_type: Input) -> Result<(), String> {
+        let Some(inputs) = inputs else {
+            return Err("Invalid node.".to_string());
+        };
+
+        let has_input =
+            input_type.has_value() && inputs.iter().any(|input| input.input_type == input_type);
+
+        if !has_input {
+            return Err("Input can't be automated.".to_string());
+        }
+
+        Ok(())
     }
 
     fn set_config_links(&mut self, links: &[LinkConfig]) -> bool {
         let mut new_links = self.get_links();
 
-        for link in links.iter() {
-            let src = link.src_id;
-            let dst = InputId::new(link.dst_input, link.dst_id);
-
-            if self.can_be_linked(&src, &dst).is_err() {
+        for link in links.iter().map(Self::config_link) {
+            if self.can_be_linked(&link.src, &link.dst).is_err() {
                 return false;
             }
 
-            if self.already_linked(&src, &dst) {
+            if self.already_linked(&link.src, &link.dst) {
                 continue;
             }
 
-            new_links.push(ModuleLink {
-                src,
-                dst,
-                amount: link.amount,
-                modulation: link.modulator_id,
-            });
+            new_links.push(link);
         }
 
         self.setup_routing(&new_links).is_ok()
     }
 
+    fn config_link(link: &LinkConfig) -> ModuleLink {
+        ModuleLink {
+            src: link.src_id,
+            dst: InputId::new(link.dst_input, link.dst_id),
+            amount: link.amount,
+            curve: link.curve,
+            polarity: link.polarity,
+            offset: link.offset,
+            mode: link.mode,
+            modulation: link.modulator_id,
+        }
+    }
+
     pub fn set_direct_link(&mut self, src: ModuleId, dst: InputId) -> Result<(), String> {
         self.can_be_linked(&src, &dst)?;
 
@@ -462,14 +1324,7 @@ impl SynthEngine {
     }
 
     pub fn update_link_amount(&mut self, src: &ModuleId, dst: &InputId, amount: StereoSample) {
-        if let Some(inputs) = self.input_sources.get_mut(dst)
-            && let Some(input) = inputs.iter_mut().find(|input| input.module_id == *src)
-            && let Some(src_slot) = self.modules.get(src).map(|m| m.output_slot())
-            && let Some(dst_module) = self.modules.get_mut(&dst.module_id)
-        {
-            input.amount = amount;
-            dst_module.update_input_amount(dst.input_type, src_slot, amount);
-        }
+        self.update_link_source(*src, dst, |source| source.amount = amount);
     }
 
     pub fn set_link_modulation(
@@ -492,6 +1347,44 @@ impl SynthEngine {
         }
     }
 
+    // Changes the link in place, edits from the UI don't rebuild the slots on the audio thread
+    fn update_link_source(
+        &mut self,
+        src_id: ModuleId,
+        dst_input: &InputId,
+        update: impl FnOnce(&mut InputSource),
+    ) {
+        if let Some(sources) = self.input_sources.get_mut(dst_input)
+            && let Some(source) = sources.iter_mut().find(|src| src.module_id == src_id)
+            && let Some(src_slot) = self.modules.get(&src_id).map(|m| m.output_slot())
+            && let Some(dst_module) = self.modules.get_mut(&dst_input.module_id)
+        {
+            update(source);
+            dst_module.update_input_source(dst_input.input_type, src_slot, source);
+        }
+    }
+
+    pub fn set_link_curve(&mut self, src_id: ModuleId, dst_input: &InputId, curve: LinkCurve) {
+        self.update_link_source(src_id, dst_input, |source| source.curve = curve);
+    }
+
+    pub fn set_link_polarity(
+        &mut self,
+        src_id: ModuleId,
+        dst_input: &InputId,
+        polarity: LinkPolarity,
+    ) {
+        self.update_link_source(src_id, dst_input, |source| source.polarity = polarity);
+    }
+
+    pub fn set_link_mode(&mut self, src_id: ModuleId, dst_input: &InputId, mode: LinkMode) {
+        self.update_link_source(src_id, dst_input, |source| source.mode = mode);
+    }
+
+    pub fn set_link_offset(&mut self, src_id: ModuleId, dst_input: &InputId, offset: Sample) {
+        self.update_link_source(src_id, dst_input, |source| source.offset = offset);
+    }
+
     pub fn remove_link_modulation(&mut self, src_id: ModuleId, dst_input: &InputId) {
         if let Some(sources) = self.input_sources.get_mut(dst_input)
             && let Some(source) = sources.iter_mut().find(|src| src.module_id == src_id)
@@ -539,7 +1432,25 @@ impl SynthEngine {
         }
     }
 
+    // The editor can't release its keyboard note once the window is closed
+    pub fn release_keyboard_note(&mut self) {
+        if let Some(note) = self.keyboard_note.take() {
+            self.handle_note_off(0, note, KEYBOARD_RELEASE_VELOCITY);
+        }
+    }
+
     pub fn handle_note_on(&mut self, channel: u8, note: u8, velocity: f32) {
+        let mut notes = ChordNotes::new();
+
+        self.chord_memory
+            .note_on(channel, note, velocity, &mut notes);
+
+        for note in notes {
+            self.start_note(channel, note, velocity);
+        }
+    }
+
+    fn start_note(&mut self, channel: u8, note: u8, velocity: f32) {
         let mut voice_events = VoiceEvents::new();
 
         self.voices_handler
@@ -549,12 +1460,18 @@ impl SynthEngine {
     }
 
     pub fn handle_note_off(&mut self, channel: u8, note: u8, velocity: f32) {
-        let mut voice_events = VoiceEvents::new();
+        let mut notes = ChordNotes::new();
 
-        self.voices_handler
-            .handle_note_off(channel, note, velocity, &mut voice_events);
+        self.chord_memory.note_off(channel, note, &mut notes);
 
-        self.process_voice_events(voice_events.events());
+        for note in notes {
+            let mut voice_events = VoiceEvents::new();
+
+            self.voices_handler
+                .handle_note_off(channel, note, velocity, &mut voice_events);
+
+            self.process_voice_events(voice_events.events());
+        }
     }
 
     pub fn handle_note_expression(
@@ -572,20 +1489,116 @@ impl SynthEngine {
         self.process_voice_events(voice_events.events());
     }
 
+    pub fn handle_channel_expression(
+        &mut self,
+        channel: u8,
+        expression: Expression,
+        value: Sample,
+    ) {
+        let mut voice_events = VoiceEvents::new();
+
+        self.voices_handler.handle_channel_expression(
+            channel,
+            expression,
+            value,
+            &mut voice_events,
+        );
+
+        self.process_voice_events(voice_events.events());
+    }
+
+    pub fn handle_pitch_bend(&mut self, channel: u8, value: Sample) {
+        if self.voices_handler.mpe() && channel != MPE_MASTER_CHANNEL {
+            self.handle_channel_expression(
+                channel,
+                Expression::Pitch,
+                (value - 0.5) * 2.0 * MPE_PITCH_BEND_RANGE,
+            );
+        } else {
+            self.midi_state.set_pitch_bend(value);
+        }
+    }
+
+    pub fn handle_midi_cc(&mut self, cc: u8, value: Sample) {
+        self.midi_state.set_cc(cc, value);
+        self.midi_mapping.handle_cc(cc, value, &mut self.modules);
+
+        if self.midi_mapping.take_learning() {
+            self.audio_end.update_midi_learned(cc);
+        }
+    }
+
+    pub fn set_midi_learn(&mut self, learning: bool) {
+        self.midi_mapping.set_learning(learning);
+    }
+
     pub fn handle_choke(&mut self, channel: u8, note: u8) {
-        self.voices_handler.handle_choke(channel, note);
+        let mut notes = ChordNotes::new();
+
+        self.chord_memory.note_off(channel, note, &mut notes);
+
+        for note in notes {
+            self.voices_handler.handle_choke(channel, note);
+        }
+    }
+
+    // Stops all notes at once, including the strummed ones still waiting to start
+    pub fn clear_voices(&mut self) {
+        self.chord_memory.clear();
+        self.voices_handler.clear();
+    }
+
+    pub fn set_chord(&mut self, chord: ChordConfig) {
+        self.chord_memory.set_config(chord);
     }
 
     fn handle_ui_events(&mut self) {
-        use ui_bridge::UiEvent;
+        use ui_bridge::{Released, UiEvent};
+
+        self.audio_end.flush_released();
 
         while let Some(event) = self.audio_end.pop_event() {
             match event {
                 UiEvent::LinkAmount { src, dst, amount } => {
                     self.update_link_amount(&src, &dst, amount);
                 }
+                UiEvent::LinkCurve { src, dst, curve } => self.set_link_curve(src, &dst, curve),
+                UiEvent::LinkPolarity { src, dst, polarity } => {
+                    self.set_link_polarity(src, &dst, polarity);
+                }
+                UiEvent::LinkMode { src, dst, mode } => self.set_link_mode(src, &dst, mode),
+                UiEvent::LinkOffset { src, dst, offset } => self.set_link_offset(src, &dst, offset),
+                UiEvent::ModuleBypass { module_id, bypass } => {
+                    self.set_module_bypass(module_id, bypass);
+                }
+                UiEvent::OutputTrim(trim) => self.set_output_trim(trim),
+                UiEvent::Solo(module_id) => self.set_solo(module_id),
+                UiEvent::Tuning(mut tuning) => {
+                    *tuning = self.set_tuning(tuning.take());
+                    self.audio_end.release(Released::Tuning(tuning));
+                }
+                UiEvent::Routing(staged) => self.apply_staged_routing(staged),
+                UiEvent::Automation(mut assignments) => {
+                    self.automation.swap_assignments(&mut assignments);
+                    self.audio_end.release(Released::Automation(assignments));
+                }
+                UiEvent::MidiMappings(mut mappings) => {
+                    self.midi_mapping.swap_config(&mut mappings);
+                    self.audio_end.release(Released::MidiMappings(mappings));
+                }
+                UiEvent::MtsEsp(mts_esp, mut connection) => {
+                    self.voices_handler.swap_mts_esp(mts_esp, &mut connection);
+                    self.audio_end.release(Released::MtsEsp(connection));
+                }
+                UiEvent::WaveQuality(wave_quality) => self.set_wave_quality(wave_quality),
                 UiEvent::Voices(voices) => self.set_num_voices(voices),
                 UiEvent::Legato(legato) => self.set_legato(legato),
+                UiEvent::Portamento(portamento) => self.set_portamento(portamento),
+                UiEvent::StealPriority(steal_priority) => self.set_steal_priority(steal_priority),
+                UiEvent::Mpe(mpe) => self.set_mpe(mpe),
+                UiEvent::PitchBendRange(pitch_bend_range) => {
+                    self.set_pitch_bend_range(pitch_bend_range);
+                }
                 UiEvent::BlockSize(block_size) => self.set_block_size(block_size),
                 UiEvent::VoiceKillTime(voice_kill_time) => {
                     self.set_voice_kill_time(voice_kill_time);
@@ -594,7 +1607,26 @@ impl SynthEngine {
                 UiEvent::StereoSpectrum(stereo_spectrum) => {
                     self.set_stereo_spectrum(stereo_spectrum);
                 }
+                UiEvent::SpectralSize(spectral_size) => self.set_spectral_size(spectral_size),
+                UiEvent::TableUpdate(table_update) => self.set_table_update(table_update),
                 UiEvent::OutputGain(output_gain) => self.set_output_gain(output_gain),
+                UiEvent::DcBlocker(dc_blocker) => self.set_dc_blocker(dc_blocker),
+                UiEvent::OutputMode(output_mode) => self.set_output_mode(output_mode),
+                UiEvent::Limiter(limiter) => self.set_limiter(limiter),
+                UiEvent::Chord(chord) => self.set_chord(chord),
+                UiEvent::MidiLearn(learning) => self.set_midi_learn(learning),
+                // Notes from the editor keyboard play on the first channel
+                UiEvent::NoteOn { note, velocity } => {
+                    self.keyboard_note = Some(note);
+                    self.handle_note_on(0, note, velocity);
+                }
+                // Already released when the editor closed
+                UiEvent::NoteOff { note, velocity } => {
+                    if self.keyboard_note == Some(note) {
+                        self.keyboard_note = None;
+                        self.handle_note_off(0, note, velocity);
+                    }
+                }
             }
         }
 
@@ -607,10 +1639,51 @@ impl SynthEngine {
         &mut self,
         samples: usize,
         update_ui: bool,
-        outputs: impl Iterator<Item = &'a mut [f32]>,
+        mut outputs: impl Iterator<Item = &'a mut [f32]>,
     ) {
+        let (left, right) = (outputs.next().unwrap(), outputs.next().unwrap());
+
         self.handle_ui_events();
 
+        // Oversampled samples of a block have to fit the module buffers
+        let chunk_size = MAX_OVERSAMPLED_BLOCK / self.oversampling.factor();
+
+        // Precise table updates render in halves, spectra and tables follow twice as often
+        let chunk_size =
+            if self.table_update == TableUpdate::Precise && samples >= MIN_PRECISE_SPLIT {
+                chunk_size.min(samples).div_ceil(2)
+            } else {
+                chunk_size
+            };
+
+        for (idx, (left, right)) in left[..samples]
+            .chunks_mut(chunk_size)
+            .zip(right[..samples].chunks_mut(chunk_size))
+            .enumerate()
+        {
+            self.process_block(
+                left.len(),
+                update_ui && idx == 0,
+                idx * chunk_size,
+                left,
+                right,
+            );
+        }
+
+        self.sidechain_len = 0;
+    }
+
+    fn process_block(
+        &mut self,
+        samples: usize,
+        update_ui: bool,
+        sidechain_from: usize,
+        left: &mut [f32],
+        right: &mut [f32],
+    ) {
+        let sidechain_range = sidechain_from.min(self.sidechain_len)
+            ..(sidechain_from + samples).min(self.sidechain_len);
+
         {
             let mut decaying_voices = DecayingVoices::new();
 
@@ -625,6 +1698,32 @@ impl SynthEngine {
             self.voices_handler.update_decaying_voices(&decaying_voices);
         }
 
+        {
+            let mut due_notes = DueNotes::new();
+
+            self.chord_memory
+                .take_due(samples as Sample / self.host_sample_rate, &mut due_notes);
+
+            for due in due_notes {
+                self.start_note(due.channel, due.note, due.velocity);
+            }
+        }
+
+        if let Some(params) = &self.external_params {
+            self.voices_handler
+                .set_host_tuning(params.master_tune.value(), params.transpose.value());
+        }
+
+        {
+            let mut voice_events = VoiceEvents::new();
+
+            self.voices_handler.retune(&mut voice_events);
+
+            if !voice_events.events().is_empty() {
+                self.process_voice_events(voice_events.events());
+            }
+        }
+
         if update_ui {
             self.audio_end
                 .update_voices_status(&self.voices_handler.get_ui_state());
@@ -633,12 +1732,11 @@ impl SynthEngine {
         let mut playing_voices = PlayingVoices::new();
 
         self.voices_handler.get_playing_voices(&mut playing_voices);
+        self.automation
+            .apply(samples, self.host_sample_rate, &mut self.modules);
 
-        let samples = if self.oversampling {
-            2 * samples
-        } else {
-            samples
-        };
+        let block_duration = samples as Sample / self.host_sample_rate;
+        let samples = self.oversampling.factor() * samples;
         let sample_rate = self.sample_rate();
 
         let mut ctx = ProcessContext {
@@ -651,18 +1749,101 @@ impl SynthEngine {
                 smooth_params: SmoothedSampleParams::new(sample_rate),
                 needs_update_ui: update_ui,
                 spectrum_channels: self.spectrum_channels,
+                spectral_bins: self.spectral_size.bins(),
+                tempo: self.tempo,
+                host_sample_rate: self.host_sample_rate,
+                sidechain: self
+                    .sidechain
+                    .each_ref()
+                    .map(|channel| &channel[sidechain_range.clone()]),
                 active_voices: &playing_voices,
+                midi_state: &self.midi_state,
             },
         };
 
         for module_id in &self.execution_order {
-            if let Some(module) = self.modules.get_mut(module_id) {
+            let Some(module) = self.modules.get(module_id) else {
+                continue;
+            };
+            let started = update_ui.then(Instant::now);
+
+            if module.bypass() {
+                let input_slot =
+                    Self::bypass_input_slot(&self.modules, &self.input_sources, module);
+
+                ctx.bypass(module.output_type(), module.output_slot(), input_slot);
+            } else if let Some(module) = self.modules.get_mut(module_id) {
+                // A module added live from the editor has to come with its output in place
+                debug_assert!(
+                    ctx.outputs_arena.has_output(module),
+                    "module {module_id} has no output slot"
+                );
+                #[cfg(debug_assertions)]
+                let allocations = assert_no_alloc::violation_count();
+
                 module.process(&mut ctx);
+
+                if let Some((from, to)) = self.output_trims.next_gains(*module_id) {
+                    module.trim_output(&mut ctx, from, to);
+                }
+
+                // Counted inside `assert_no_alloc` only, the audio thread always runs in it
+                #[cfg(debug_assertions)]
+                debug_assert_eq!(
+                    assert_no_alloc::violation_count(),
+                    allocations,
+                    "module {module_id} allocated in process"
+                );
+            }
+
+            if let Some(started) = started {
+                self.profiler.add(*module_id, started);
+            }
+        }
+
+        for module_id in &self.bus_chain {
+            if let [Some(ModuleHandle::Output(output)), Some(module)] = self
+                .modules
+                .get_disjoint_mut([&OUTPUT_MODULE_ID, module_id])
+                && !module.bypass()
+            {
+                let started = update_ui.then(Instant::now);
+
+                module.process_bus(output.bus_mut(), &ctx.params);
+
+                if let Some(started) = started {
+                    self.profiler.add(*module_id, started);
+                }
             }
         }
 
         if let Some(ModuleHandle::Output(output)) = self.modules.get_mut(&OUTPUT_MODULE_ID) {
-            output.read_output(self.oversampling, outputs);
+            let started = update_ui.then(Instant::now);
+
+            output.read_output(&ctx.params, self.oversampling, [left, right].into_iter());
+            self.voices_handler
+                .update_voice_levels(output.voice_levels().iter().map(|level| level.rms));
+
+            if let Some(started) = started {
+                self.profiler.add(OUTPUT_MODULE_ID, started);
+            }
+
+            if update_ui {
+                let mut states = [VoiceState::Idle; MAX_VOICES];
+
+                self.voices_handler.get_voice_states(&mut states);
+                self.audio_end
+                    .update_voice_meters(&states, output.voice_levels());
+                self.audio_end
+                    .update_gain_reduction(output.gain_reduction());
+                self.audio_end.update_correlation(output.correlation());
+            }
+        }
+
+        if update_ui {
+            self.profiler.flush(block_duration, |module_id, load| {
+                self.audio_end.update_module_load(module_id, load);
+            });
         }
     }
 
@@ -673,38 +1854,38 @@ impl SynthEngine {
         module_id
     }
 
-    fn can_be_linked_with_output(&self, src: &ModuleId, dst: &InputId) -> Result<(), String> {
-        let Some(src_module) = self.modules.get(src) else {
-            return Err("Invalid node.".to_string());
-        };
-
-        let is_compatible = dst.input_type == Input::Audio
-            && data_types_compatible(src_module.output_type(), DataType::Audio);
-
-        if !is_compatible {
-            return Err("Data types mismatch.".to_string());
-        }
-
-        Ok(())
-    }
-
     fn can_be_linked(&self, src: &ModuleId, dst: &InputId) -> Result<(), String> {
-        if dst.module_id == OUTPUT_MODULE_ID {
-            return self.can_be_linked_with_output(src, dst);
-        }
+        Self::check_link(
+            self.modules.get(src).map(|module| module.output_type()),
+            dst,
+            self.modules
+                .get(&dst.module_id)
+                .map(|module| module.inputs()),
+        )
+    }
 
-        let (Some(src_module), Some(dst_module)) =
-            (self.modules.get(src), self.modules.get(&dst.module_id))
-        else {
+    // The editor makes the same check against its copy of the modules before staging a link
+    fn check_link(
+        src_type: Option<DataType>,
+        dst: &InputId,
+        dst_inputs: Option<&[InputMeta]>,
+    ) -> Result<(), String> {
+        let Some(src_type) = src_type else {
             return Err("Invalid node.".to_string());
         };
 
-        let src_data_types = src_module.output_type();
+        let is_compatible = if dst.module_id == OUTPUT_MODULE_ID {
+            dst.input_type == Input::Audio && data_types_compatible(src_type, DataType::Bus)
+        } else {
+            let Some(dst_inputs) = dst_inputs else {
+                return Err("Invalid node.".to_string());
+            };
 
-        let is_compatible = dst_module.inputs().iter().any(|input_info| {
-            input_info.input_type == dst.input_type
-                && data_types_compatible(src_data_types, input_info.data_type)
-        });
+            dst_inputs.iter().any(|input_info| {
+                input_info.input_type == dst.input_type
+                    && data_types_compatible(src_type, input_info.data_type)
+            })
+        };
 
         if !is_compatible {
             return Err("Data types mismatch.".to_string());
@@ -713,6 +1894,34 @@ impl SynthEngine {
         Ok(())
     }
 
+    // The first source of the first audio or spectrum input matching the module output
+    fn bypass_input_slot(
+        modules: &ModulesMap,
+        input_sources: &RoutingMap,
+        module: &ModuleHandle,
+    ) -> Option<usize> {
+        let output_type = module.output_type();
+
+        module
+            .inputs()
+            .iter()
+            .filter(|input| {
+                input.data_type == output_type
+                    && matches!(
+                        input.input_type,
+                        Input::Audio | Input::AudioMix(_) | Input::Spectrum | Input::SpectrumMix(_)
+                    )
+            })
+            .find_map(|input| {
+                input_sources
+                    .get(&InputId::new(input.input_type, module.id()))?
+                    .first()
+            })
+            .and_then(|src| modules.get(&src.module_id))
+            .filter(|src| src.output_type() == output_type)
+            .map(|src| src.output_slot())
+    }
+
     fn already_linked(&self, src: &ModuleId, dst: &InputId) -> bool {
         if let Some(inputs) = self.input_sources.get(dst) {
             inputs.iter().any(|input| input.module_id == *src)
@@ -722,180 +1931,236 @@ impl SynthEngine {
     }
 
     fn get_links(&self) -> Vec<ModuleLink> {
-        self.input_sources
+        Self::links(&self.input_sources)
+    }
+
+    fn links(input_sources: &RoutingMap) -> Vec<ModuleLink> {
+        input_sources
             .iter()
             .flat_map(|(dst, sources)| {
                 sources.iter().map(|src| ModuleLink {
                     dst: *dst,
                     src: src.module_id,
                     amount: src.amount,
+                    curve: src.curve,
+                    polarity: src.polarity,
+                    offset: src.offset,
+                    mode: src.mode,
                     modulation: src.modulation,
                 })
             })
             .collect()
     }
 
-    pub fn get_module(&self, id: ModuleId) -> Option<&ModuleHandle> {
-        self.modules.get(&id)
+    /// Fresh engine built from the current config, safe to render without touching the live one.
+    pub fn offline_copy(&self) -> Option<Self> {
+        Self::try_new(
+            &self.get_config(),
+            self.output_level_param()?,
+            self.external_params.clone()?,
+            self.host_sample_rate,
+        )
     }
 
-    pub fn get_module_mut(&mut self, id: ModuleId) -> Option<&mut ModuleHandle> {
-        self.modules.get_mut(&id)
+    fn output_level_param(&self) -> Option<Arc<FloatParam>> {
+        match self.modules.get(&OUTPUT_MODULE_ID) {
+            Some(ModuleHandle::Output(output)) => Some(output.level_param()),
+            _ => None,
+        }
     }
 
-    fn calc_execution_order(links: &[ModuleLink]) -> Result<Vec<ModuleId>, String> {
-        let mut dependents: HashMap<ModuleId, HashSet<ModuleId>> = HashMap::new();
-
-        for link in links {
-            let src_node = link.src;
-            let dst_node = link.dst.module_id;
+    fn spectrum_source_slot(&self, oscillator_id: ModuleId) -> Option<usize> {
+        self.input_sources
+            .get(&InputId::new(Input::Spectrum, oscillator_id))
+            .and_then(|sources| sources.first())
+            .and_then(|src| self.modules.get(&src.module_id))
+            .map(|module| module.output_slot())
+    }
 
-            dependents.entry(dst_node).or_default().insert(src_node);
-            dependents.entry(src_node).or_default();
+    /// Holds a note and writes the spectrum feeding the oscillator as wavetable frames spread
+    /// evenly over `duration` seconds. Consumes the engine, use it on an offline copy.
+    pub fn export_wavetable(
+        mut self,
+        oscillator_id: ModuleId,
+        path: &Path,
+        num_frames: usize,
+        duration: Sample,
+    ) -> Result<(), String> {
+        if !matches!(
+            self.modules.get(&oscillator_id),
+            Some(ModuleHandle::Oscillator(_))
+        ) {
+            return Err("Invalid oscillator.".to_string());
+        }
 
-            if let Some(modulation) = link.modulation {
-                dependents.entry(dst_node).or_default().insert(modulation);
-                dependents.entry(modulation).or_default();
+        let src_slot = self
+            .spectrum_source_slot(oscillator_id)
+            .ok_or_else(|| "Oscillator has no spectrum input.".to_string())?;
+
+        let num_frames = num_frames.clamp(1, MAX_WAVETABLE_FRAMES);
+        let frame_step = duration.max(0.0) * self.host_sample_rate / num_frames as Sample;
+        let block_size = self.block_size;
+        let mut left = vec![0.0; block_size];
+        let mut right = vec![0.0; block_size];
+        let mut writer = WavetableWriter::new(num_frames);
+        let mut elapsed = 0;
+
+        self.handle_note_on(0, WAVETABLE_NOTE, 1.0);
+
+        for frame_idx in 0..num_frames {
+            let frame_time = (frame_idx as Sample * frame_step) as usize;
+
+            loop {
+                self.process(
+                    block_size,
+                    false,
+                    [&mut left[..], &mut right[..]].into_iter(),
+                );
+                elapsed += block_size;
+
+                if elapsed > frame_time {
+                    break;
+                }
             }
-        }
 
-        let topo_sort = TopoSort::from_map(dependents);
+            let mut playing_voices = PlayingVoices::new();
 
-        match topo_sort.into_vec_nodes() {
-            SortResults::Full(nodes) => {
-                let mut order: Vec<_> = nodes
-                    .into_iter()
-                    .filter(|id| *id != OUTPUT_MODULE_ID)
-                    .collect();
+            self.voices_handler.get_playing_voices(&mut playing_voices);
 
-                order.push(OUTPUT_MODULE_ID);
-                Ok(order)
-            }
-            SortResults::Partial(_) => Err("Cycles detected!".to_string()),
+            let Some(voice_idx) = playing_voices.first() else {
+                return Err("No voice is playing.".to_string());
+            };
+
+            writer.add_frame(self.outputs_arena.latest_spectral(src_slot, 0, *voice_idx));
         }
+
+        writer.write(path)
     }
 
-    fn setup_slots(&mut self) {
-        struct ModuleSlots {
-            data_type: DataType,
-            output_slot: usize,
-            inputs: Vec<InputSlots>,
-            spectral_inputs: Vec<SpectralInputSlot>,
-        }
+    pub fn get_module(&self, id: ModuleId) -> Option<&ModuleHandle> {
+        self.modules.get(&id)
+    }
 
-        let mut modules_slots: FxHashMap<_, _> = self
-            .modules
-            .iter()
-            .map(|(&mod_id, m)| {
-                (
-                    mod_id,
-                    ModuleSlots {
-                        data_type: m.output_type(),
-                        output_slot: m.output_slot(),
-                        inputs: Default::default(),
-                        spectral_inputs: Default::default(),
-                    },
-                )
-            })
-            .collect();
+    pub fn get_module_mut(&mut self, id: ModuleId) -> Option<&mut ModuleHandle> {
+        self.modules.get_mut(&id)
+    }
 
-        for (input, sources) in self.input_sources.iter() {
-            if sources.len() == 1
-                && modules_slots
-                    .get(&sources[0].module_id)
-                    .expect("should be in place")
-                    .data_type
-                    == DataType::Spectral
-            {
-                let src_output_slot = modules_slots
-                    .get(&sources[0].module_id)
-                    .expect("should be in place")
-                    .output_slot;
-
-                let dst_module = modules_slots
-                    .get_mut(&input.module_id)
-                    .expect("should be in place");
-
-                dst_module.spectral_inputs.push(SpectralInputSlot {
-                    input_type: input.input_type,
-                    slot: src_output_slot,
-                });
+    #[cfg(test)]
+    fn calc_execution_order(links: &[ModuleLink]) -> Result<Vec<ModuleId>, String> {
+        RoutingTables::execution_order(&Self::routing_map(links))
+    }
 
-                continue;
-            }
+    fn routing_map(links: &[ModuleLink]) -> RoutingMap {
+        let mut input_sources = RoutingMap::default();
 
-            let mut input_slots = InputSlots {
-                input_type: input.input_type,
-                slots: Vec::new(),
-            };
+        for link in links {
+            input_sources
+                .entry(link.dst)
+                .or_default()
+                .push(link.source());
+        }
 
-            for src in sources {
-                let mut input_src = InputSlot {
-                    src_slot: 0,
-                    modulation_slot: None,
-                    amount: src.amount,
-                };
+        input_sources
+    }
 
-                let src_module = modules_slots
-                    .get(&src.module_id)
-                    .expect("should be in place");
+    fn module_outputs(&self) -> ModuleOutputs {
+        self.modules
+            .values()
+            .map(|m| (m.id(), (m.output_type(), m.output_slot())))
+            .collect()
+    }
 
-                assert_matches!(src_module.data_type, DataType::Audio | DataType::Control);
+    fn setup_routing(&mut self, links: &[ModuleLink]) -> Result<(), String> {
+        let mut tables = RoutingTables::build(Self::routing_map(links), &self.module_outputs())?;
 
-                input_src.src_slot = src_module.output_slot;
+        self.swap_routing(&mut tables);
+        Ok(())
+    }
 
-                if let Some(modulation_src) = src.modulation {
-                    let modulation_module = modules_slots
-                        .get(&modulation_src)
-                        .expect("should be in place");
+    // Doesn't allocate, the replaced routing is left in `tables`
+    fn swap_routing(&mut self, tables: &mut RoutingTables) {
+        std::mem::swap(&mut self.input_sources, &mut tables.input_sources);
+        std::mem::swap(&mut self.execution_order, &mut tables.execution_order);
+        std::mem::swap(&mut self.profiler, &mut tables.profiler);
 
-                    assert_matches!(
-                        modulation_module.data_type,
-                        DataType::Audio | DataType::Control
-                    );
+        for slots in &tables.module_slots {
+            if let Some(module) = self.modules.get_mut(&slots.module_id) {
+                module.set_input_slots(&slots.inputs, &slots.spectral_inputs);
+            }
+        }
 
-                    input_src.modulation_slot = Some(modulation_module.output_slot);
-                }
+        self.setup_bus_chain();
+        self.declick_output();
+    }
 
-                input_slots.slots.push(input_src);
+    // A routing change staged by the editor, applied between blocks without allocating. The
+    // removed modules and the replaced tables go back to the editor to be freed there.
+    fn apply_staged_routing(&mut self, mut staged: Box<StagedRouting>) {
+        for &module_id in &staged.removed {
+            if let Some(module) = self.modules.remove(&module_id) {
+                let output = self.outputs_arena.take_output(&module);
+
+                staged.released.push((module, output));
+                self.automation.remove_module(module_id);
+                self.midi_mapping.remove_module(module_id);
+                self.output_trims.remove_module(module_id);
             }
+        }
 
-            let dst_module = modules_slots
-                .get_mut(&input.module_id)
-                .expect("should be in place");
+        for (module, output) in staged.added.drain(..) {
+            let module_id = module.id();
 
-            dst_module.inputs.push(input_slots);
+            self.outputs_arena.insert_output(&module, output);
+            self.modules.insert(module_id, module);
+            self.next_id = self.next_id.max(module_id + 1);
         }
 
-        for (module_id, mod_slots) in modules_slots.iter() {
-            let module = self
-                .modules
-                .get_mut(module_id)
-                .expect("module should be in place");
+        self.swap_routing(&mut staged.tables);
+        self.audio_end.release(ui_bridge::Released::Routing(staged));
+    }
 
-            module.set_input_slots(&mod_slots.inputs, &mod_slots.spectral_inputs);
+    fn declick_output(&mut self) {
+        if let Some(ModuleHandle::Output(output)) = self.modules.get_mut(&OUTPUT_MODULE_ID) {
+            output.declick();
         }
     }
 
-    fn setup_routing(&mut self, links: &[ModuleLink]) -> Result<(), String> {
-        let execution_order = Self::calc_execution_order(links)?;
-        let mut input_sources: FxHashMap<InputId, Vec<InputSource>> = FxHashMap::default();
+    // Walks from the output through bus modules up to the module providing the voices
+    // Reuses the chain, a solo change on the audio thread doesn't allocate
+    fn setup_bus_chain(&mut self) {
+        let mut voices_slot = None;
+        let mut input = InputId::new(Input::Audio, OUTPUT_MODULE_ID);
+
+        self.bus_chain.clear();
+
+        // A soloed module takes the place of the voices source and the bus modules
+        let solo_slot = self
+            .solo
+            .and_then(|id| self.modules.get(&id))
+            .map(|module| module.output_slot());
+
+        while solo_slot.is_none()
+            && let Some(src_id) = self
+                .input_sources
+                .get(&input)
+                .and_then(|sources| sources.first())
+                .map(|src| src.module_id)
+            && let Some(src_module) = self.modules.get(&src_id)
+        {
+            if src_module.output_type() != DataType::Bus {
+                voices_slot = Some(src_module.output_slot());
+                break;
+            }
 
-        for link in links {
-            input_sources
-                .entry(link.dst)
-                .or_default()
-                .push(InputSource {
-                    module_id: link.src,
-                    amount: link.amount,
-                    modulation: link.modulation,
-                });
+            self.bus_chain.push(src_id);
+            input = InputId::new(Input::Audio, src_id);
         }
 
-        self.input_sources = input_sources;
-        self.execution_order = execution_order;
-        self.setup_slots();
-        Ok(())
+        self.bus_chain.reverse();
+
+        if let Some(ModuleHandle::Output(output)) = self.modules.get_mut(&OUTPUT_MODULE_ID) {
+            output.set_voices_input(solo_slot.or(voices_slot));
+        }
     }
 
     fn stereo_spectrum_channels(stereo_spectrum: bool) -> usize {
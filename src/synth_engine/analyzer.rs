@@ -0,0 +1,127 @@
+use std::{
+    collections::VecDeque,
+    f32::consts::PI,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use parking_lot::Mutex;
+use realfft::RealFftPlanner;
+
+use crate::synth_engine::{Sample, scope::Scope};
+
+pub const ANALYZER_FFT_SIZE: usize = 4096;
+pub const ANALYZER_BINS: usize = ANALYZER_FFT_SIZE / 2 + 1;
+pub const ANALYZER_MIN_DB: Sample = -120.0;
+
+const HOP_SIZE: usize = ANALYZER_FFT_SIZE / 4;
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+struct Worker {
+    stop: Arc<AtomicBool>,
+    thread: JoinHandle<Scope>,
+}
+
+// Runs the FFT of the output on its own thread, the editor only copies the latest magnitudes
+pub struct Analyzer {
+    scope: Option<Scope>,
+    sample_rate: Sample,
+    spectrum: Arc<Mutex<Vec<Sample>>>,
+    worker: Option<Worker>,
+}
+
+impl Analyzer {
+    pub fn new(scope: Scope) -> Self {
+        Self {
+            sample_rate: scope.sample_rate(),
+            scope: Some(scope),
+            spectrum: Arc::new(Mutex::new(vec![ANALYZER_MIN_DB; ANALYZER_BINS])),
+            worker: None,
+        }
+    }
+
+    pub fn sample_rate(&self) -> Sample {
+        self.sample_rate
+    }
+
+    pub fn set_active(&mut self, active: bool) {
+        if active {
+            let Some(mut scope) = self.scope.take() else {
+                return;
+            };
+
+            scope.set_active(true);
+
+            let stop = Arc::new(AtomicBool::new(false));
+            let thread_stop = stop.clone();
+            let spectrum = self.spectrum.clone();
+            let thread = thread::spawn(move || {
+                Self::run(&mut scope, &spectrum, &thread_stop);
+                scope
+            });
+
+            self.worker = Some(Worker { stop, thread });
+        } else if let Some(worker) = self.worker.take() {
+            worker.stop.store(true, Ordering::Release);
+
+            if let Ok(mut scope) = worker.thread.join() {
+                scope.set_active(false);
+                self.scope = Some(scope);
+            }
+
+            self.spectrum.lock().fill(ANALYZER_MIN_DB);
+        }
+    }
+
+    /// Magnitudes in dB for `ANALYZER_BINS` linearly spaced bins up to Nyquist.
+    pub fn read_spectrum(&self, spectrum: &mut Vec<Sample>) {
+        spectrum.clone_from(&self.spectrum.lock());
+    }
+
+    fn run(scope: &mut Scope, spectrum: &Mutex<Vec<Sample>>, stop: &AtomicBool) {
+        let fft = RealFftPlanner::<Sample>::new().plan_fft_forward(ANALYZER_FFT_SIZE);
+        let mut input = fft.make_input_vec();
+        let mut output = fft.make_output_vec();
+        let mut magnitudes = vec![ANALYZER_MIN_DB; ANALYZER_BINS];
+        let mut history = VecDeque::with_capacity(ANALYZER_FFT_SIZE);
+        let mut pending = 0;
+
+        let window: Vec<Sample> = (0..ANALYZER_FFT_SIZE)
+            .map(|idx| 0.5 - 0.5 * (2.0 * PI * idx as Sample / ANALYZER_FFT_SIZE as Sample).cos())
+            .collect();
+        // Full scale sine reads as 0 dB
+        let norm = 2.0 / window.iter().sum::<Sample>();
+
+        while !stop.load(Ordering::Acquire) {
+            pending += scope.read(&mut history, ANALYZER_FFT_SIZE);
+
+            if history.len() == ANALYZER_FFT_SIZE && pending >= HOP_SIZE {
+                pending = 0;
+
+                for ((dst, frame), window) in input.iter_mut().zip(&history).zip(&window) {
+                    *dst = 0.5 * (frame[0] + frame[1]) * window;
+                }
+
+                if fft.process(&mut input, &mut output).is_ok() {
+                    for (magnitude, bin) in magnitudes.iter_mut().zip(&output) {
+                        *magnitude = (20.0 * (bin.norm() * norm).log10()).max(ANALYZER_MIN_DB);
+                    }
+
+                    spectrum.lock().copy_from_slice(&magnitudes);
+                }
+            }
+
+            thread::sleep(POLL_INTERVAL);
+        }
+    }
+}
+
+impl Drop for Analyzer {
+    fn drop(&mut self) {
+        self.set_active(false);
+    }
+}
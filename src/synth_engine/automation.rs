@@ -0,0 +1,116 @@
+use std::sync::Arc;
+
+use crate::{
+    synth_engine::{
+        ExternalParamsBlock, ModulesMap, Sample, StereoSample, config::AutomationConfig,
+        external_param::NUM_AUTOMATION_PARAMS, routing::ModuleId, smooth::InfiniteSmoothed,
+        synth_module::SynthModule,
+    },
+    utils::from_ms,
+};
+
+const SMOOTH_TIME: Sample = from_ms(20.0);
+
+pub struct Assignment {
+    config: AutomationConfig,
+    applied: Option<Sample>,
+}
+
+// Drives module inputs from the host automation pool, values are smoothed per block
+pub struct Automation {
+    params: Arc<ExternalParamsBlock>,
+    assignments: Vec<Assignment>,
+    used_slots: [bool; NUM_AUTOMATION_PARAMS],
+    slots: [InfiniteSmoothed; NUM_AUTOMATION_PARAMS],
+}
+
+impl Automation {
+    pub fn new(params: Arc<ExternalParamsBlock>) -> Self {
+        Self {
+            params,
+            assignments: Vec::new(),
+            used_slots: [false; NUM_AUTOMATION_PARAMS],
+            slots: [InfiniteSmoothed::default(); NUM_AUTOMATION_PARAMS],
+        }
+    }
+
+    pub fn get_config(&self) -> Vec<AutomationConfig> {
+        self.assignments.iter().map(|a| a.config.clone()).collect()
+    }
+
+    pub fn set_config(&mut self, assignments: &[AutomationConfig]) {
+        self.assignments = Self::assignments(assignments);
+        self.update_used_slots();
+    }
+
+    /// Allocates, the editor prepares the assignments before they're swapped in.
+    pub fn assignments(configs: &[AutomationConfig]) -> Vec<Assignment> {
+        configs
+            .iter()
+            .map(|config| Assignment {
+                config: config.clone(),
+                applied: None,
+            })
+            .collect()
+    }
+
+    // Leaves the replaced assignments in `assignments`
+    pub fn swap_assignments(&mut self, assignments: &mut Vec<Assignment>) {
+        std::mem::swap(&mut self.assignments, assignments);
+        self.update_used_slots();
+    }
+
+    pub fn remove_module(&mut self, module_id: ModuleId) {
+        self.assignments.retain(|a| a.config.module_id != module_id);
+        self.update_used_slots();
+    }
+
+    // Doesn't allocate, modules are removed on the audio thread
+    fn update_used_slots(&mut self) {
+        let mut used_slots = [false; NUM_AUTOMATION_PARAMS];
+
+        for assignment in &self.assignments {
+            used_slots[assignment.config.slot] = true;
+        }
+
+        // Starts from the current host value instead of gliding in
+        for (slot, used) in used_slots.iter().enumerate() {
+            if *used && !self.used_slots[slot] {
+                self.slots[slot] =
+                    InfiniteSmoothed::new(self.params.automation_params[slot].value());
+            }
+        }
+
+        self.used_slots = used_slots;
+    }
+
+    pub fn apply(&mut self, samples: usize, sample_rate: Sample, modules: &mut ModulesMap) {
+        if self.assignments.is_empty() || samples == 0 {
+            return;
+        }
+
+        let smooth_mult =
+            InfiniteSmoothed::smooth_mult(sample_rate / samples as Sample, SMOOTH_TIME);
+
+        let mut values = [0.0; NUM_AUTOMATION_PARAMS];
+
+        for slot in (0..NUM_AUTOMATION_PARAMS).filter(|slot| self.used_slots[*slot]) {
+            let smoothed = &mut self.slots[slot];
+
+            smoothed.set(self.params.automation_params[slot].value());
+            values[slot] = smoothed.iter(smooth_mult).next().unwrap_or_default();
+        }
+
+        for assignment in &mut self.assignments {
+            let config = &assignment.config;
+            let value = config.min + values[config.slot] * (config.max - config.min);
+
+            if assignment.applied != Some(value)
+                && let Some(module) = modules.get_mut(&config.module_id)
+            {
+                module.set_input_param(config.input, StereoSample::splat(value));
+                assignment.applied = Some(value);
+            }
+        }
+    }
+}
@@ -0,0 +1,161 @@
+use std::{
+    fs::File,
+    io::BufWriter,
+    path::Path,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use hound::{SampleFormat, WavSpec, WavWriter};
+
+use crate::synth_engine::{Sample, routing::NUM_CHANNELS};
+
+// About 2.7 seconds at 48 kHz, the writer thread drains it every few milliseconds
+const CAPTURE_BUFFER_SIZE: usize = 1 << 17;
+const DRAIN_INTERVAL: Duration = Duration::from_millis(10);
+
+type Frame = [Sample; NUM_CHANNELS];
+type Writer = WavWriter<BufWriter<File>>;
+
+pub struct CaptureTap {
+    producer: rtrb::Producer<Frame>,
+    active: Arc<AtomicBool>,
+}
+
+impl CaptureTap {
+    pub fn write(&mut self, left: &[Sample], right: &[Sample]) {
+        if !self.active.load(Ordering::Acquire) {
+            return;
+        }
+
+        for (left, right) in left.iter().zip(right) {
+            // Frames are dropped if the writer thread falls behind
+            if self.producer.push([*left, *right]).is_err() {
+                break;
+            }
+        }
+    }
+}
+
+struct Recording {
+    stop: Arc<AtomicBool>,
+    thread: JoinHandle<(rtrb::Consumer<Frame>, hound::Result<()>)>,
+}
+
+pub struct Capture {
+    consumer: Option<rtrb::Consumer<Frame>>,
+    active: Arc<AtomicBool>,
+    sample_rate: u32,
+    recording: Option<Recording>,
+}
+
+impl Capture {
+    pub fn is_recording(&self) -> bool {
+        self.recording.is_some()
+    }
+
+    pub fn start(&mut self, path: &Path) -> Result<(), String> {
+        let Some(mut consumer) = self.consumer.take() else {
+            return Err("Capture is already running.".to_string());
+        };
+
+        let spec = WavSpec {
+            channels: NUM_CHANNELS as u16,
+            sample_rate: self.sample_rate,
+            bits_per_sample: 32,
+            sample_format: SampleFormat::Float,
+        };
+
+        let writer = match WavWriter::create(path, spec) {
+            Ok(writer) => writer,
+            Err(err) => {
+                self.consumer = Some(consumer);
+                return Err(err.to_string());
+            }
+        };
+
+        // Leftovers pushed after the previous capture was stopped
+        while consumer.pop().is_ok() {}
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread_stop = stop.clone();
+        let thread = thread::spawn(move || {
+            let result = Self::drain(&mut consumer, writer, &thread_stop);
+
+            (consumer, result)
+        });
+
+        self.active.store(true, Ordering::Release);
+        self.recording = Some(Recording { stop, thread });
+        Ok(())
+    }
+
+    pub fn stop(&mut self) -> Result<(), String> {
+        let Some(recording) = self.recording.take() else {
+            return Ok(());
+        };
+
+        self.active.store(false, Ordering::Release);
+        recording.stop.store(true, Ordering::Release);
+
+        let (consumer, result) = recording
+            .thread
+            .join()
+            .map_err(|_| "Capture thread panicked.".to_string())?;
+
+        self.consumer = Some(consumer);
+        result.map_err(|err| err.to_string())
+    }
+
+    fn drain(
+        consumer: &mut rtrb::Consumer<Frame>,
+        mut writer: Writer,
+        stop: &AtomicBool,
+    ) -> hound::Result<()> {
+        loop {
+            let stopping = stop.load(Ordering::Acquire);
+
+            while let Ok(frame) = consumer.pop() {
+                for sample in frame {
+                    writer.write_sample(sample)?;
+                }
+            }
+
+            if stopping {
+                break;
+            }
+
+            thread::sleep(DRAIN_INTERVAL);
+        }
+
+        writer.finalize()
+    }
+}
+
+impl Drop for Capture {
+    fn drop(&mut self) {
+        let _ = self.stop();
+    }
+}
+
+pub fn create_capture_pair(sample_rate: Sample) -> (CaptureTap, Capture) {
+    let (producer, consumer) = rtrb::RingBuffer::<Frame>::new(CAPTURE_BUFFER_SIZE);
+    let active = Arc::new(AtomicBool::new(false));
+
+    (
+        CaptureTap {
+            producer,
+            active: active.clone(),
+        },
+        Capture {
+            consumer: Some(consumer),
+            active,
+            sample_rate: sample_rate as u32,
+            recording: None,
+        },
+    )
+}
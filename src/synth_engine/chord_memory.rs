@@ -0,0 +1,204 @@
+use serde::{Deserialize, Serialize};
+use smallvec::SmallVec;
+
+use crate::synth_engine::Sample;
+
+pub const MAX_CHORD_NOTES: usize = 8;
+pub const MAX_CHORD_INTERVAL: i8 = 36;
+pub const MAX_STRUM: Sample = 0.5;
+const MAX_PENDING_NOTES: usize = 64;
+
+pub type ChordNotes = SmallVec<[u8; MAX_CHORD_NOTES]>;
+pub type DueNotes = SmallVec<[DueNote; MAX_PENDING_NOTES]>;
+
+// A played note triggers every interval of the chord, each one strum later than the previous
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ChordConfig {
+    pub enabled: bool,
+    pub num_notes: usize,
+    pub intervals: [i8; MAX_CHORD_NOTES], // Semitones from the played note
+    pub strum: Sample,                    // Seconds between consecutive notes
+}
+
+impl Default for ChordConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            num_notes: 3,
+            intervals: [0, 4, 7, 12, 16, 19, 24, 28],
+            strum: 0.0,
+        }
+    }
+}
+
+impl ChordConfig {
+    fn clamped(self) -> Self {
+        Self {
+            num_notes: self.num_notes.clamp(1, MAX_CHORD_NOTES),
+            intervals: self
+                .intervals
+                .map(|interval| interval.clamp(-MAX_CHORD_INTERVAL, MAX_CHORD_INTERVAL)),
+            strum: self.strum.clamp(0.0, MAX_STRUM),
+            ..self
+        }
+    }
+
+    fn chord_notes(&self, note: u8) -> impl Iterator<Item = (usize, u8)> {
+        self.intervals[..self.num_notes]
+            .iter()
+            .enumerate()
+            .filter_map(move |(idx, interval)| {
+                u8::try_from(note as i16 + *interval as i16)
+                    .ok()
+                    .filter(|note| *note <= 127)
+                    .map(|note| (idx, note))
+            })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DueNote {
+    pub channel: u8,
+    pub note: u8,
+    pub velocity: Sample,
+}
+
+#[derive(Clone, Copy)]
+struct PendingNote {
+    root: u8,
+    due: DueNote,
+    delay: Sample,
+}
+
+pub struct ChordMemory {
+    config: ChordConfig,
+    pending: SmallVec<[PendingNote; MAX_PENDING_NOTES]>,
+}
+
+impl ChordMemory {
+    pub fn new(config: ChordConfig) -> Self {
+        Self {
+            config: config.clamped(),
+            pending: SmallVec::new(),
+        }
+    }
+
+    pub fn config(&self) -> ChordConfig {
+        self.config
+    }
+
+    pub fn set_config(&mut self, config: ChordConfig) {
+        self.config = config.clamped();
+
+        if !self.config.enabled {
+            self.pending.clear();
+        }
+    }
+
+    // Notes to start now, strummed ones are kept until `take_due` reaches them
+    pub fn note_on(&mut self, channel: u8, note: u8, velocity: Sample, notes: &mut ChordNotes) {
+        if !self.config.enabled {
+            notes.push(note);
+            return;
+        }
+
+        for (idx, chord_note) in self.config.chord_notes(note) {
+            let delay = idx as Sample * self.config.strum;
+
+            // A full queue plays the rest of the chord at once rather than allocating
+            if delay <= 0.0 || self.pending.len() == self.pending.inline_size() {
+                notes.push(chord_note);
+            } else {
+                self.pending.push(PendingNote {
+                    root: note,
+                    due: DueNote {
+                        channel,
+                        note: chord_note,
+                        velocity,
+                    },
+                    delay,
+                });
+            }
+        }
+    }
+
+    // Notes to release, the strummed notes of the chord that didn't start yet are dropped
+    pub fn note_off(&mut self, channel: u8, note: u8, notes: &mut ChordNotes) {
+        if !self.config.enabled {
+            notes.push(note);
+            return;
+        }
+
+        self.pending
+            .retain(|pending| !(pending.root == note && pending.due.channel == channel));
+        notes.extend(self.config.chord_notes(note).map(|(_, note)| note));
+    }
+
+    pub fn clear(&mut self) {
+        self.pending.clear();
+    }
+
+    // Moves the notes starting within the next `duration` seconds to `due`
+    pub fn take_due(&mut self, duration: Sample, due: &mut DueNotes) {
+        self.pending.retain(|pending| {
+            pending.delay -= duration;
+
+            if pending.delay < 0.0 {
+                due.push(pending.due);
+                false
+            } else {
+                true
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strummed(strum: Sample) -> ChordMemory {
+        ChordMemory::new(ChordConfig {
+            enabled: true,
+            num_notes: 4,
+            strum,
+            ..ChordConfig::default()
+        })
+    }
+
+    #[test]
+    fn strummed_notes_start_after_their_delay() {
+        let mut chord = strummed(0.01);
+        let mut notes = ChordNotes::new();
+        let mut due = DueNotes::new();
+
+        chord.note_on(0, 60, 1.0, &mut notes);
+        assert_eq!(notes.as_slice(), &[60]);
+
+        chord.take_due(0.005, &mut due);
+        assert!(due.is_empty());
+
+        chord.take_due(0.006, &mut due);
+        assert_eq!(due.iter().map(|n| n.note).collect::<Vec<_>>(), [64]);
+
+        chord.take_due(0.01, &mut due);
+        assert_eq!(due.iter().map(|n| n.note).collect::<Vec<_>>(), [64, 67]);
+    }
+
+    #[test]
+    fn release_drops_pending_notes_and_skips_out_of_range() {
+        let mut chord = strummed(0.1);
+        let mut notes = ChordNotes::new();
+        let mut due = DueNotes::new();
+
+        chord.note_on(0, 120, 1.0, &mut notes);
+        notes.clear();
+        chord.note_off(0, 120, &mut notes);
+
+        // 120 + 12 is above the MIDI range
+        assert_eq!(notes.as_slice(), &[120, 124, 127]);
+
+        chord.take_due(1.0, &mut due);
+        assert!(due.is_empty());
+    }
+}
@@ -2,25 +2,87 @@ use serde::{Deserialize, Serialize};
 
 use crate::{
     synth_engine::{
-        Input, MAX_BLOCK_SIZE, Sample, StereoSample, amplifier::AmplifierConfig,
-        envelope::EnvelopeConfig, expressions::ExpressionsConfig,
-        external_param::ExternalParamConfig, harmonic_editor::HarmonicEditorConfig, lfo::LfoConfig,
-        mixer::MixerConfig, oscillator::OscillatorConfig, routing::ModuleId,
-        spectral_blend::SpectralBlendConfig, spectral_filter::SpectralFilterConfig,
-        spectral_mixer::SpectralMixerConfig, wave_shaper::WaveShaperConfig,
+        Input, MAX_BLOCK_SIZE, Sample, SpectralSize, StereoSample,
+        amplifier::AmplifierConfig,
+        chord_memory::ChordConfig,
+        chorus::ChorusConfig,
+        delay::DelayConfig,
+        envelope::EnvelopeConfig,
+        envelope_follower::EnvelopeFollowerConfig,
+        expressions::ExpressionsConfig,
+        external_param::ExternalParamConfig,
+        filter::FilterConfig,
+        harmonic_comb::HarmonicCombConfig,
+        harmonic_editor::HarmonicEditorConfig,
+        harmonic_envelopes::HarmonicEnvelopesConfig,
+        harmonic_shift::HarmonicShiftConfig,
+        harmonic_stretch::HarmonicStretchConfig,
+        lfo::LfoConfig,
+        limiter::LimiterConfig,
+        math::MathConfig,
+        midi_control::MidiControlConfig,
+        midi_state::DEFAULT_PITCH_BEND_RANGE,
+        mixer::MixerConfig,
+        oscillator::{OscillatorConfig, TableUpdate, WaveQuality},
+        output_mode::OutputMode,
+        oversampling::Oversampling,
+        panner::PannerConfig,
+        reverb::ReverbConfig,
+        ring_mod::RingModConfig,
+        routing::{LinkCurve, LinkMode, LinkPolarity, ModuleId},
+        sample_resynth::SampleResynthConfig,
+        spectral_analyzer::SpectralAnalyzerConfig,
+        spectral_blend::SpectralBlendConfig,
+        spectral_dynamics::SpectralDynamicsConfig,
+        spectral_filter::SpectralFilterConfig,
+        spectral_mixer::SpectralMixerConfig,
+        spectral_tilt::SpectralTiltConfig,
+        tuning::TuningConfig,
+        voices_handler::{MtsEspMode, PortamentoMode, StealPriority},
+        wave_shaper::WaveShaperConfig,
     },
     utils::from_ms,
 };
 
+fn default_pitch_bend_range() -> Sample {
+    DEFAULT_PITCH_BEND_RANGE
+}
+
 #[derive(Clone, Serialize, Deserialize)]
 pub struct EngineParams {
     pub num_voices: usize,
     pub legato: bool,
+    #[serde(default)]
+    pub portamento: PortamentoMode,
+    #[serde(default)]
+    pub steal_priority: StealPriority,
+    #[serde(default)]
+    pub tuning: Option<TuningConfig>,
+    #[serde(default)]
+    pub mts_esp: MtsEspMode,
+    #[serde(default)]
+    pub mpe: bool,
+    #[serde(default = "default_pitch_bend_range")]
+    pub pitch_bend_range: Sample,
     pub block_size: usize,
-    pub oversampling: bool,
+    pub oversampling: Oversampling,
+    #[serde(default)]
+    pub wave_quality: WaveQuality,
+    #[serde(default)]
+    pub spectral_size: SpectralSize,
+    #[serde(default)]
+    pub table_update: TableUpdate,
     pub stereo_spectrum: bool,
     pub voice_kill_time: Sample,
     pub output_gain: StereoSample,
+    #[serde(default)]
+    pub dc_blocker: bool,
+    #[serde(default)]
+    pub output_mode: OutputMode,
+    #[serde(default)]
+    pub limiter: LimiterConfig,
+    #[serde(default)]
+    pub chord: ChordConfig,
 }
 
 impl Default for EngineParams {
@@ -28,11 +90,24 @@ impl Default for EngineParams {
         Self {
             num_voices: 1,
             legato: false,
+            portamento: PortamentoMode::default(),
+            steal_priority: StealPriority::default(),
+            tuning: None,
+            mts_esp: MtsEspMode::Off,
+            mpe: false,
+            pitch_bend_range: DEFAULT_PITCH_BEND_RANGE,
             block_size: MAX_BLOCK_SIZE,
-            oversampling: false,
+            oversampling: Oversampling::Off,
+            wave_quality: WaveQuality::default(),
+            spectral_size: SpectralSize::default(),
+            table_update: TableUpdate::default(),
             stereo_spectrum: true,
             voice_kill_time: from_ms(30.0),
             output_gain: 1.0.into(),
+            dc_blocker: false,
+            output_mode: OutputMode::Stereo,
+            limiter: LimiterConfig::default(),
+            chord: ChordConfig::default(),
         }
     }
 }
@@ -44,6 +119,44 @@ pub struct LinkConfig {
     pub dst_input: Input,
     pub amount: StereoSample,
     pub modulator_id: Option<ModuleId>,
+    #[serde(default)]
+    pub curve: LinkCurve,
+    #[serde(default)]
+    pub polarity: LinkPolarity,
+    #[serde(default)]
+    pub offset: Sample,
+    #[serde(default)]
+    pub mode: LinkMode,
+}
+
+// Maps an automation pool slot onto a module input, the 0..1 host value is scaled to min..max
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct AutomationConfig {
+    pub slot: usize,
+    pub module_id: ModuleId,
+    pub input: Input,
+    pub min: Sample,
+    pub max: Sample,
+}
+
+// Maps a MIDI CC onto a module input, the 0..1 value is bent by the curve and scaled to min..max
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct MidiMappingConfig {
+    pub cc: u8,
+    pub module_id: ModuleId,
+    pub input: Input,
+    pub min: Sample,
+    pub max: Sample,
+    pub curve: Sample,
+}
+
+// Gain in dB and polarity of a module output, applied after the module is processed
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct OutputTrimConfig {
+    pub module_id: ModuleId,
+    pub gain: Sample,
+    #[serde(default)]
+    pub invert: bool,
 }
 
 #[derive(Clone, Serialize, Deserialize)]
@@ -57,9 +170,145 @@ pub enum ModuleConfig {
     SpectralFilter(Box<SpectralFilterConfig>),
     SpectralBlend(Box<SpectralBlendConfig>),
     SpectralMixer(Box<SpectralMixerConfig>),
+    SpectralTilt(Box<SpectralTiltConfig>),
+    HarmonicStretch(Box<HarmonicStretchConfig>),
+    HarmonicShift(Box<HarmonicShiftConfig>),
+    SpectralDynamics(Box<SpectralDynamicsConfig>),
+    HarmonicEnvelopes(Box<HarmonicEnvelopesConfig>),
+    SampleResynth(Box<SampleResynthConfig>),
     HarmonicEditor(Box<HarmonicEditorConfig>),
     Expressions(Box<ExpressionsConfig>),
     ExternalParam(Box<ExternalParamConfig>),
+    MidiControl(Box<MidiControlConfig>),
+    Chorus(Box<ChorusConfig>),
+    Delay(Box<DelayConfig>),
+    Reverb(Box<ReverbConfig>),
+    RingMod(Box<RingModConfig>),
+    Panner(Box<PannerConfig>),
+    Filter(Box<FilterConfig>),
+    Math(Box<MathConfig>),
+    HarmonicComb(Box<HarmonicCombConfig>),
+    EnvelopeFollower(Box<EnvelopeFollowerConfig>),
+    SpectralAnalyzer(Box<SpectralAnalyzerConfig>),
+}
+
+impl ModuleConfig {
+    pub fn id(&self) -> ModuleId {
+        match self {
+            Self::Oscillator(config) => config.id,
+            Self::Envelope(config) => config.id,
+            Self::Lfo(config) => config.id,
+            Self::Amplifier(config) => config.id,
+            Self::Mixer(config) => config.id,
+            Self::WaveShaper(config) => config.id,
+            Self::SpectralFilter(config) => config.id,
+            Self::SpectralBlend(config) => config.id,
+            Self::SpectralMixer(config) => config.id,
+            Self::SpectralTilt(config) => config.id,
+            Self::HarmonicStretch(config) => config.id,
+            Self::HarmonicShift(config) => config.id,
+            Self::SpectralDynamics(config) => config.id,
+            Self::HarmonicEnvelopes(config) => config.id,
+            Self::SampleResynth(config) => config.id,
+            Self::HarmonicEditor(config) => config.id,
+            Self::Expressions(config) => config.id,
+            Self::ExternalParam(config) => config.id,
+            Self::MidiControl(config) => config.id,
+            Self::Chorus(config) => config.id,
+            Self::Delay(config) => config.id,
+            Self::Reverb(config) => config.id,
+            Self::RingMod(config) => config.id,
+            Self::Panner(config) => config.id,
+            Self::Filter(config) => config.id,
+            Self::Math(config) => config.id,
+            Self::HarmonicComb(config) => config.id,
+            Self::EnvelopeFollower(config) => config.id,
+            Self::SpectralAnalyzer(config) => config.id,
+        }
+    }
+
+    pub fn set_id(&mut self, id: ModuleId) {
+        match self {
+            Self::Oscillator(config) => config.id = id,
+            Self::Envelope(config) => config.id = id,
+            Self::Lfo(config) => config.id = id,
+            Self::Amplifier(config) => config.id = id,
+            Self::Mixer(config) => config.id = id,
+            Self::WaveShaper(config) => config.id = id,
+            Self::SpectralFilter(config) => config.id = id,
+            Self::SpectralBlend(config) => config.id = id,
+            Self::SpectralMixer(config) => config.id = id,
+            Self::SpectralTilt(config) => config.id = id,
+            Self::HarmonicStretch(config) => config.id = id,
+            Self::HarmonicShift(config) => config.id = id,
+            Self::SpectralDynamics(config) => config.id = id,
+            Self::HarmonicEnvelopes(config) => config.id = id,
+            Self::SampleResynth(config) => config.id = id,
+            Self::HarmonicEditor(config) => config.id = id,
+            Self::Expressions(config) => config.id = id,
+            Self::ExternalParam(config) => config.id = id,
+            Self::MidiControl(config) => config.id = id,
+            Self::Chorus(config) => config.id = id,
+            Self::Delay(config) => config.id = id,
+            Self::Reverb(config) => config.id = id,
+            Self::RingMod(config) => config.id = id,
+            Self::Panner(config) => config.id = id,
+            Self::Filter(config) => config.id = id,
+            Self::Math(config) => config.id = id,
+            Self::HarmonicComb(config) => config.id = id,
+            Self::EnvelopeFollower(config) => config.id = id,
+            Self::SpectralAnalyzer(config) => config.id = id,
+        }
+    }
+
+    pub fn set_bypass(&mut self, bypass: bool) {
+        match self {
+            Self::Oscillator(config) => config.bypass = bypass,
+            Self::Envelope(config) => config.bypass = bypass,
+            Self::Lfo(config) => config.bypass = bypass,
+            Self::Amplifier(config) => config.bypass = bypass,
+            Self::Mixer(config) => config.bypass = bypass,
+            Self::WaveShaper(config) => config.bypass = bypass,
+            Self::SpectralFilter(config) => config.bypass = bypass,
+            Self::SpectralBlend(config) => config.bypass = bypass,
+            Self::SpectralMixer(config) => config.bypass = bypass,
+            Self::SpectralTilt(config) => config.bypass = bypass,
+            Self::HarmonicStretch(config) => config.bypass = bypass,
+            Self::HarmonicShift(config) => config.bypass = bypass,
+            Self::SpectralDynamics(config) => config.bypass = bypass,
+            Self::HarmonicEnvelopes(config) => config.bypass = bypass,
+            Self::SampleResynth(config) => config.bypass = bypass,
+            Self::HarmonicEditor(config) => config.bypass = bypass,
+            Self::Expressions(config) => config.bypass = bypass,
+            Self::ExternalParam(config) => config.bypass = bypass,
+            Self::MidiControl(config) => config.bypass = bypass,
+            Self::Chorus(config) => config.bypass = bypass,
+            Self::Delay(config) => config.bypass = bypass,
+            Self::Reverb(config) => config.bypass = bypass,
+            Self::RingMod(config) => config.bypass = bypass,
+            Self::Panner(config) => config.bypass = bypass,
+            Self::Filter(config) => config.bypass = bypass,
+            Self::Math(config) => config.bypass = bypass,
+            Self::HarmonicComb(config) => config.bypass = bypass,
+            Self::EnvelopeFollower(config) => config.bypass = bypass,
+            Self::SpectralAnalyzer(config) => config.bypass = bypass,
+        }
+    }
+}
+
+// An input of a sub-patch module that was linked from outside of the sub-patch
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ExposedInputConfig {
+    pub module_id: ModuleId,
+    pub input: Input,
+}
+
+// A group of modules with the links between them, inserted into a patch under new ids
+#[derive(Default, Clone, Serialize, Deserialize)]
+pub struct SubPatchConfig {
+    pub modules: Vec<ModuleConfig>,
+    pub links: Vec<LinkConfig>,
+    pub exposed_inputs: Vec<ExposedInputConfig>,
 }
 
 #[derive(Default, Clone, Serialize, Deserialize)]
@@ -67,4 +316,12 @@ pub struct EngineConfig {
     pub engine: EngineParams,
     pub modules: Vec<ModuleConfig>,
     pub links: Vec<LinkConfig>,
+    #[serde(default)]
+    pub automation: Vec<AutomationConfig>,
+    #[serde(default)]
+    pub midi_mappings: Vec<MidiMappingConfig>,
+    #[serde(default)]
+    pub output_trims: Vec<OutputTrimConfig>,
+    #[serde(default)]
+    pub float_param_names: Vec<String>,
 }
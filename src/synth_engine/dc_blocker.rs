@@ -0,0 +1,57 @@
+use std::f32::consts::TAU;
+
+use crate::synth_engine::{Sample, routing::NUM_CHANNELS};
+
+const CUTOFF: Sample = 5.0;
+
+// One-pole high-pass per channel: y[n] = x[n] - x[n - 1] + r * y[n - 1]
+pub struct DcBlocker {
+    enabled: bool,
+    prev_input: [Sample; NUM_CHANNELS],
+    prev_output: [Sample; NUM_CHANNELS],
+}
+
+impl DcBlocker {
+    pub fn new(enabled: bool) -> Self {
+        Self {
+            enabled,
+            prev_input: [0.0; NUM_CHANNELS],
+            prev_output: [0.0; NUM_CHANNELS],
+        }
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub fn set_enabled(&mut self, enabled: bool) {
+        if enabled && !self.enabled {
+            self.prev_input = [0.0; NUM_CHANNELS];
+            self.prev_output = [0.0; NUM_CHANNELS];
+        }
+
+        self.enabled = enabled;
+    }
+
+    pub fn process(&mut self, sample_rate: Sample, channels: [&mut [Sample]; NUM_CHANNELS]) {
+        if !self.enabled {
+            return;
+        }
+
+        let r = (-TAU * CUTOFF / sample_rate).exp();
+
+        for ((samples, prev_input), prev_output) in channels
+            .into_iter()
+            .zip(self.prev_input.iter_mut())
+            .zip(self.prev_output.iter_mut())
+        {
+            for sample in samples.iter_mut() {
+                let output = *sample - *prev_input + r * *prev_output;
+
+                *prev_input = *sample;
+                *prev_output = output;
+                *sample = output;
+            }
+        }
+    }
+}
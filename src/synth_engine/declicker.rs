@@ -0,0 +1,64 @@
+use crate::synth_engine::{Sample, routing::NUM_CHANNELS};
+
+const DECAY_TIME: Sample = 0.003;
+const SETTLED: Sample = 1e-6;
+
+// Hides the step in the output after the module graph changes under playing notes. The jump from
+// the sample extrapolated from the last two before the change to the first one after it is added
+// back as an offset that decays exponentially.
+pub struct Declicker {
+    armed: bool,
+    last: [[Sample; 2]; NUM_CHANNELS],
+    offset: [Sample; NUM_CHANNELS],
+}
+
+impl Declicker {
+    pub fn new() -> Self {
+        Self {
+            armed: false,
+            last: [[0.0; 2]; NUM_CHANNELS],
+            offset: [0.0; NUM_CHANNELS],
+        }
+    }
+
+    pub fn arm(&mut self) {
+        self.armed = true;
+    }
+
+    pub fn process(&mut self, sample_rate: Sample, channels: [&mut [Sample]; NUM_CHANNELS]) {
+        let armed = std::mem::take(&mut self.armed);
+        let decay = (-1.0 / (DECAY_TIME * sample_rate)).exp();
+
+        for ((samples, last), offset) in channels
+            .into_iter()
+            .zip(self.last.iter_mut())
+            .zip(self.offset.iter_mut())
+        {
+            let Some(first) = samples.first().copied() else {
+                continue;
+            };
+
+            if armed {
+                let [prev, last] = *last;
+
+                *offset = 2.0 * last - prev - first;
+            }
+
+            if *offset != 0.0 {
+                for sample in samples.iter_mut() {
+                    *sample += *offset;
+                    *offset *= decay;
+                }
+
+                if offset.abs() < SETTLED {
+                    *offset = 0.0;
+                }
+            }
+
+            *last = match &*samples {
+                [.., prev, last] => [*prev, *last],
+                _ => [last[1], samples[0]],
+            };
+        }
+    }
+}
@@ -0,0 +1,43 @@
+use crate::synth_engine::Sample;
+
+// 192 kHz host rate with 4x oversampling, bus effects size their lines for it upfront
+pub const MAX_BUS_SAMPLE_RATE: Sample = 768_000.0;
+
+pub struct DelayLine {
+    buffer: Vec<Sample>,
+    mask: usize,
+    write_pos: usize,
+}
+
+impl DelayLine {
+    pub fn new(max_delay_samples: usize) -> Self {
+        let size = (max_delay_samples + 2).next_power_of_two();
+
+        Self {
+            buffer: vec![0.0; size],
+            mask: size - 1,
+            write_pos: 0,
+        }
+    }
+
+    pub fn max_delay(&self) -> Sample {
+        (self.buffer.len() - 2) as Sample
+    }
+
+    pub fn push(&mut self, sample: Sample) {
+        self.write_pos = (self.write_pos + 1) & self.mask;
+        self.buffer[self.write_pos] = sample;
+    }
+
+    // Delay in samples from the last pushed one, linearly interpolated
+    pub fn read(&self, delay: Sample) -> Sample {
+        let delay = delay.clamp(0.0, self.max_delay());
+        let whole = delay as usize;
+        let fraction = delay - whole as Sample;
+        let pos = self.write_pos.wrapping_sub(whole) & self.mask;
+        let current = self.buffer[pos];
+        let prev = self.buffer[pos.wrapping_sub(1) & self.mask];
+
+        current + (prev - current) * fraction
+    }
+}
@@ -0,0 +1,28 @@
+use rtrb::{Producer, PushError};
+
+// Editor end of an event queue to the audio thread. It counts the events it sent, the undo history
+// compares the counts to notice edits instead of serializing the patch.
+pub struct EditSender<T> {
+    tx: Producer<T>,
+    sent: u32,
+}
+
+impl<T> EditSender<T> {
+    pub fn new(tx: Producer<T>) -> Self {
+        Self { tx, sent: 0 }
+    }
+
+    pub fn push(&mut self, value: T) -> Result<(), PushError<T>> {
+        self.tx.push(value)?;
+        self.sent = self.sent.wrapping_add(1);
+        Ok(())
+    }
+
+    pub fn slots(&self) -> usize {
+        self.tx.slots()
+    }
+
+    pub fn sent(&self) -> u32 {
+        self.sent
+    }
+}
@@ -0,0 +1,70 @@
+use itertools::izip;
+
+use crate::synth_engine::Sample;
+
+pub const NUM_TAPS: usize = 6;
+
+// Polyphase allpass paths of the half-band filter, also used by the output decimator
+pub const PATH_A: [Sample; NUM_TAPS] = [
+    0.093_022_42,
+    0.312_318_06,
+    0.548_379_06,
+    0.737_198_53,
+    0.872_235,
+    0.975_497_8,
+];
+pub const PATH_B: [Sample; NUM_TAPS] = [
+    0.024_388_384,
+    0.194_029_99,
+    0.433_855_68,
+    0.650_124_97,
+    0.810_418_67,
+    0.925_979_7,
+];
+
+#[derive(Default, Clone, Copy)]
+struct AllpassChain {
+    in_memory: [Sample; NUM_TAPS],
+    out_memory: [Sample; NUM_TAPS],
+}
+
+impl AllpassChain {
+    fn process(&mut self, taps: &[Sample; NUM_TAPS], input: Sample) -> Sample {
+        let mut result = input;
+
+        for (in_mem, out_mem, tap) in izip!(&mut self.in_memory, &mut self.out_memory, taps) {
+            let new_result = tap.mul_add(result - *out_mem, *in_mem);
+
+            *in_mem = result;
+            *out_mem = new_result;
+            result = new_result;
+        }
+
+        result
+    }
+}
+
+// Mono 2x oversampler
+#[derive(Default, Clone, Copy)]
+pub struct HalfBand {
+    up_a: AllpassChain,
+    up_b: AllpassChain,
+    down_a: AllpassChain,
+    down_b: AllpassChain,
+}
+
+impl HalfBand {
+    pub fn upsample(&mut self, input: &[Sample], output: &mut [Sample]) {
+        for (input, pair) in input.iter().zip(output.chunks_exact_mut(2)) {
+            pair[0] = self.up_b.process(&PATH_B, *input);
+            pair[1] = self.up_a.process(&PATH_A, *input);
+        }
+    }
+
+    pub fn downsample(&mut self, input: &[Sample], output: &mut [Sample]) {
+        for (out, pair) in output.iter_mut().zip(input.chunks_exact(2)) {
+            *out = 0.5
+                * (self.down_a.process(&PATH_A, pair[0]) + self.down_b.process(&PATH_B, pair[1]));
+        }
+    }
+}
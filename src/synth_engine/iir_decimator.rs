@@ -1,23 +1,18 @@
 use itertools::izip;
 use wide::f32x4;
 
-use crate::synth_engine::Sample;
+use crate::synth_engine::{
+    Sample,
+    half_band::{NUM_TAPS, PATH_A, PATH_B},
+};
 
-const fn tap(x1: f32, x2: f32) -> f32x4 {
-    f32x4::new([x1, x2, x1, x2])
+const fn tap(idx: usize) -> f32x4 {
+    f32x4::new([PATH_A[idx], PATH_B[idx], PATH_A[idx], PATH_B[idx]])
 }
 
-const NUM_TAPS: usize = 6;
 const CHANNELS: usize = 2;
 
-static TAPS: [f32x4; NUM_TAPS] = [
-    tap(0.093_022_42, 0.024_388_384),
-    tap(0.312_318_06, 0.194_029_99),
-    tap(0.548_379_06, 0.433_855_68),
-    tap(0.737_198_53, 0.650_124_97),
-    tap(0.872_235, 0.810_418_67),
-    tap(0.975_497_8, 0.925_979_7),
-];
+static TAPS: [f32x4; NUM_TAPS] = [tap(0), tap(1), tap(2), tap(3), tap(4), tap(5)];
 
 pub struct IirDecimator {
     in_memory: [f32x4; NUM_TAPS],
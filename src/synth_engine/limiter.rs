@@ -0,0 +1,134 @@
+use nih_plug::util::{db_to_gain, gain_to_db};
+use serde::{Deserialize, Serialize};
+
+use crate::{synth_engine::Sample, utils::from_ms};
+
+pub const MIN_THRESHOLD: Sample = -24.0;
+pub const MIN_CEILING: Sample = -12.0;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LimiterMode {
+    #[default]
+    Brickwall,
+    SoftClip,
+}
+
+// Threshold and ceiling are in dB. The signal is driven by `ceiling - threshold` into the stage,
+// so lowering the threshold makes the patch louder while the peaks stay under the ceiling.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct LimiterConfig {
+    pub enabled: bool,
+    pub mode: LimiterMode,
+    pub threshold: Sample,
+    pub ceiling: Sample,
+    pub release: Sample,
+}
+
+impl Default for LimiterConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            mode: LimiterMode::default(),
+            threshold: 0.0,
+            ceiling: -0.3,
+            release: from_ms(100.0),
+        }
+    }
+}
+
+impl LimiterConfig {
+    fn clamped(self) -> Self {
+        let ceiling = self.ceiling.clamp(MIN_CEILING, 0.0);
+
+        Self {
+            ceiling,
+            threshold: self.threshold.clamp(MIN_THRESHOLD, ceiling),
+            release: self.release.clamp(from_ms(1.0), from_ms(1000.0)),
+            ..self
+        }
+    }
+}
+
+// Final stage on the host rate output, attack is instant so the ceiling is never exceeded
+pub struct Limiter {
+    config: LimiterConfig,
+    envelope: Sample,
+    gain_reduction: Sample,
+}
+
+impl Limiter {
+    pub fn new(config: LimiterConfig) -> Self {
+        Self {
+            config: config.clamped(),
+            envelope: 1.0,
+            gain_reduction: 0.0,
+        }
+    }
+
+    pub fn config(&self) -> LimiterConfig {
+        self.config
+    }
+
+    pub fn set_config(&mut self, config: LimiterConfig) {
+        self.config = config.clamped();
+
+        if !self.config.enabled {
+            self.envelope = 1.0;
+            self.gain_reduction = 0.0;
+        }
+    }
+
+    /// Largest gain reduction of the last processed block in dB, zero or positive.
+    pub fn gain_reduction(&self) -> Sample {
+        self.gain_reduction
+    }
+
+    pub fn process(&mut self, sample_rate: Sample, left: &mut [Sample], right: &mut [Sample]) {
+        self.gain_reduction = 0.0;
+
+        if !self.config.enabled {
+            return;
+        }
+
+        let drive = db_to_gain(self.config.ceiling - self.config.threshold);
+        let ceiling = db_to_gain(self.config.ceiling);
+        let mut min_gain: Sample = 1.0;
+
+        match self.config.mode {
+            LimiterMode::Brickwall => {
+                let release = (-(self.config.release * sample_rate).recip()).exp();
+
+                for (left, right) in left.iter_mut().zip(right.iter_mut()) {
+                    let peak = drive * left.abs().max(right.abs());
+                    let target = if peak > ceiling { ceiling / peak } else { 1.0 };
+
+                    self.envelope = if target < self.envelope {
+                        target
+                    } else {
+                        target + (self.envelope - target) * release
+                    };
+
+                    let gain = drive * self.envelope;
+
+                    *left = (*left * gain).clamp(-ceiling, ceiling);
+                    *right = (*right * gain).clamp(-ceiling, ceiling);
+                    min_gain = min_gain.min(self.envelope);
+                }
+            }
+            LimiterMode::SoftClip => {
+                for (left, right) in left.iter_mut().zip(right.iter_mut()) {
+                    let peak = drive * left.abs().max(right.abs());
+
+                    *left = ceiling * (drive * *left / ceiling).tanh();
+                    *right = ceiling * (drive * *right / ceiling).tanh();
+
+                    if peak > Sample::EPSILON {
+                        min_gain = min_gain.min(left.abs().max(right.abs()) / peak);
+                    }
+                }
+            }
+        }
+
+        self.gain_reduction = -gain_to_db(min_gain).min(0.0);
+    }
+}
@@ -0,0 +1,53 @@
+use crate::synth_engine::{
+    ModulesMap, Sample, StereoSample,
+    config::MidiMappingConfig,
+    curves::{CurveFunction, Exponential},
+    routing::ModuleId,
+    synth_module::SynthModule,
+};
+
+// Drives module inputs from MIDI CCs, the mappings are applied when a CC arrives
+#[derive(Default)]
+pub struct MidiMapping {
+    mappings: Vec<MidiMappingConfig>,
+    learning: bool,
+}
+
+impl MidiMapping {
+    pub fn get_config(&self) -> Vec<MidiMappingConfig> {
+        self.mappings.clone()
+    }
+
+    pub fn set_config(&mut self, mappings: &[MidiMappingConfig]) {
+        self.mappings = mappings.to_vec();
+    }
+
+    // Leaves the replaced mappings in `mappings`
+    pub fn swap_config(&mut self, mappings: &mut Vec<MidiMappingConfig>) {
+        std::mem::swap(&mut self.mappings, mappings);
+    }
+
+    pub fn remove_module(&mut self, module_id: ModuleId) {
+        self.mappings.retain(|m| m.module_id != module_id);
+    }
+
+    pub fn set_learning(&mut self, learning: bool) {
+        self.learning = learning;
+    }
+
+    // The first CC after learning was armed is the one to map, learning stops there
+    pub fn take_learning(&mut self) -> bool {
+        std::mem::take(&mut self.learning)
+    }
+
+    pub fn handle_cc(&self, cc: u8, value: Sample, modules: &mut ModulesMap) {
+        for mapping in self.mappings.iter().filter(|m| m.cc == cc) {
+            if let Some(module) = modules.get_mut(&mapping.module_id) {
+                let shaped = Exponential::new(mapping.curve).calc(value.clamp(0.0, 1.0));
+                let value = mapping.min + shaped * (mapping.max - mapping.min);
+
+                module.set_input_param(mapping.input, StereoSample::splat(value));
+            }
+        }
+    }
+}
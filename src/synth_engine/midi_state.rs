@@ -0,0 +1,50 @@
+use crate::{synth_engine::Sample, utils::st_to_octave};
+
+pub const NUM_MIDI_CCS: usize = 128;
+pub const MOD_WHEEL_CC: u8 = 1;
+pub const DEFAULT_PITCH_BEND_RANGE: Sample = 2.0;
+pub const MAX_PITCH_BEND_RANGE: Sample = 48.0;
+
+pub struct MidiState {
+    pitch_bend: Sample,
+    pitch_bend_range: Sample,
+    ccs: [Sample; NUM_MIDI_CCS],
+}
+
+impl MidiState {
+    pub fn new(pitch_bend_range: Sample) -> Self {
+        Self {
+            pitch_bend: 0.0,
+            pitch_bend_range: pitch_bend_range.clamp(0.0, MAX_PITCH_BEND_RANGE),
+            ccs: [0.0; NUM_MIDI_CCS],
+        }
+    }
+
+    pub fn pitch_bend_range(&self) -> Sample {
+        self.pitch_bend_range
+    }
+
+    pub fn set_pitch_bend_range(&mut self, pitch_bend_range: Sample) {
+        self.pitch_bend_range = pitch_bend_range.clamp(0.0, MAX_PITCH_BEND_RANGE);
+    }
+
+    // Takes the normalized 0..1 value with 0.5 at the center
+    pub fn set_pitch_bend(&mut self, value: Sample) {
+        self.pitch_bend = value.mul_add(2.0, -1.0).clamp(-1.0, 1.0);
+    }
+
+    // Bend in octaves
+    pub fn pitch_bend(&self) -> Sample {
+        st_to_octave(self.pitch_bend * self.pitch_bend_range)
+    }
+
+    pub fn set_cc(&mut self, cc: u8, value: Sample) {
+        if let Some(cc_value) = self.ccs.get_mut(cc as usize) {
+            *cc_value = value.clamp(0.0, 1.0);
+        }
+    }
+
+    pub fn cc(&self, cc: u8) -> Sample {
+        self.ccs.get(cc as usize).copied().unwrap_or(0.0)
+    }
+}
@@ -1,6 +1,9 @@
 use crate::synth_engine::{
-    Amplifier, Envelope, Expressions, ExternalParam, HarmonicEditor, Input, Lfo, Mixer, ModuleId,
-    Oscillator, SpectralBlend, SpectralFilter, SpectralMixer, StereoSample, VoiceEvent, WaveShaper,
+    Amplifier, Chorus, Delay, Envelope, EnvelopeFollower, Expressions, ExternalParam, Filter,
+    HarmonicComb, HarmonicEditor, HarmonicEnvelopes, HarmonicShift, HarmonicStretch, Input, Lfo,
+    Math, MidiControl, Mixer, ModuleId, Oscillator, Panner, Reverb, RingMod, SampleResynth,
+    SpectralAnalyzer, SpectralBlend, SpectralDynamics, SpectralFilter, SpectralMixer, SpectralTilt,
+    StereoSample, VoiceEvent, WaveShaper,
     modules::Output,
     routing::{DataType, InputMeta, InputSlots, ProcessContext, SpectralInputSlot},
     synth_module::SynthModule,
@@ -18,11 +21,28 @@ pub enum ModuleType {
     SpectralFilter,
     SpectralBlend,
     SpectralMixer,
+    SpectralTilt,
+    HarmonicStretch,
+    HarmonicShift,
+    SpectralDynamics,
+    HarmonicEnvelopes,
+    SampleResynth,
     HarmonicEditor,
     ExternalParam,
     Lfo,
     WaveShaper,
     Expressions,
+    MidiControl,
+    Chorus,
+    Delay,
+    Reverb,
+    RingMod,
+    Panner,
+    Filter,
+    Math,
+    HarmonicComb,
+    EnvelopeFollower,
+    SpectralAnalyzer,
 }
 
 impl ModuleType {
@@ -36,11 +56,28 @@ impl ModuleType {
             Self::SpectralFilter => "Spectral Filter",
             Self::SpectralBlend => "Spectral Blend",
             Self::SpectralMixer => "Spectral Mixer",
+            Self::SpectralTilt => "Spectral Tilt",
+            Self::HarmonicStretch => "Harmonic Stretch",
+            Self::HarmonicShift => "Harmonic Shift",
+            Self::SpectralDynamics => "Spectral Dynamics",
+            Self::HarmonicEnvelopes => "Harmonic Envelopes",
+            Self::SampleResynth => "Sample Resynth",
             Self::HarmonicEditor => "Harmonic Editor",
             Self::ExternalParam => "External Parameter",
             Self::Lfo => "LFO",
             Self::WaveShaper => "Waveshaper",
             Self::Expressions => "Expressions",
+            Self::MidiControl => "MIDI Control",
+            Self::Chorus => "Chorus",
+            Self::Delay => "Delay",
+            Self::Reverb => "Reverb",
+            Self::RingMod => "Ring Modulator",
+            Self::Panner => "Panner",
+            Self::Filter => "Filter",
+            Self::Math => "Math",
+            Self::HarmonicComb => "Harmonic Comb",
+            Self::EnvelopeFollower => "Envelope Follower",
+            Self::SpectralAnalyzer => "Spectral Analyzer",
         }
     }
 }
@@ -56,9 +93,26 @@ pub enum ModuleHandle {
     SpectralFilter(Box<SpectralFilter>),
     SpectralBlend(Box<SpectralBlend>),
     SpectralMixer(Box<SpectralMixer>),
+    SpectralTilt(Box<SpectralTilt>),
+    HarmonicStretch(Box<HarmonicStretch>),
+    HarmonicShift(Box<HarmonicShift>),
+    SpectralDynamics(Box<SpectralDynamics>),
+    HarmonicEnvelopes(Box<HarmonicEnvelopes>),
+    SampleResynth(Box<SampleResynth>),
     HarmonicEditor(Box<HarmonicEditor>),
     Expressions(Box<Expressions>),
     ExternalParam(Box<ExternalParam>),
+    MidiControl(Box<MidiControl>),
+    Chorus(Box<Chorus>),
+    Delay(Box<Delay>),
+    Reverb(Box<Reverb>),
+    RingMod(Box<RingMod>),
+    Panner(Box<Panner>),
+    Filter(Box<Filter>),
+    Math(Box<Math>),
+    HarmonicComb(Box<HarmonicComb>),
+    EnvelopeFollower(Box<EnvelopeFollower>),
+    SpectralAnalyzer(Box<SpectralAnalyzer>),
     Output(Box<Output>),
 }
 
@@ -75,9 +129,26 @@ impl ModuleHandle {
             Self::SpectralFilter(_) => ModuleType::SpectralFilter,
             Self::SpectralBlend(_) => ModuleType::SpectralBlend,
             Self::SpectralMixer(_) => ModuleType::SpectralMixer,
+            Self::SpectralTilt(_) => ModuleType::SpectralTilt,
+            Self::HarmonicStretch(_) => ModuleType::HarmonicStretch,
+            Self::HarmonicShift(_) => ModuleType::HarmonicShift,
+            Self::SpectralDynamics(_) => ModuleType::SpectralDynamics,
+            Self::HarmonicEnvelopes(_) => ModuleType::HarmonicEnvelopes,
+            Self::SampleResynth(_) => ModuleType::SampleResynth,
             Self::HarmonicEditor(_) => ModuleType::HarmonicEditor,
             Self::Expressions(_) => ModuleType::Expressions,
             Self::ExternalParam(_) => ModuleType::ExternalParam,
+            Self::MidiControl(_) => ModuleType::MidiControl,
+            Self::Chorus(_) => ModuleType::Chorus,
+            Self::Delay(_) => ModuleType::Delay,
+            Self::Reverb(_) => ModuleType::Reverb,
+            Self::RingMod(_) => ModuleType::RingMod,
+            Self::Panner(_) => ModuleType::Panner,
+            Self::Filter(_) => ModuleType::Filter,
+            Self::Math(_) => ModuleType::Math,
+            Self::HarmonicComb(_) => ModuleType::HarmonicComb,
+            Self::EnvelopeFollower(_) => ModuleType::EnvelopeFollower,
+            Self::SpectralAnalyzer(_) => ModuleType::SpectralAnalyzer,
         }
     }
 }
@@ -1,26 +1,60 @@
 pub mod amplifier;
+pub mod chorus;
+pub mod delay;
 pub mod envelope;
+pub mod envelope_follower;
 pub mod expressions;
 pub mod external_param;
+pub mod filter;
 pub mod harmonic_editor;
+pub mod harmonic_comb;
+pub mod harmonic_envelopes;
+pub mod harmonic_shift;
+pub mod harmonic_stretch;
 pub mod lfo;
+pub mod math;
+pub mod midi_control;
 pub mod mixer;
 pub mod oscillator;
 mod output;
+pub mod panner;
+pub mod reverb;
+pub mod ring_mod;
+pub mod sample_resynth;
+pub mod spectral_analyzer;
 pub mod spectral_blend;
+pub mod spectral_dynamics;
 pub mod spectral_filter;
 pub mod spectral_mixer;
+pub mod spectral_tilt;
 pub mod wave_shaper;
 
 pub use amplifier::Amplifier;
-pub use envelope::Envelope;
+pub use chorus::Chorus;
+pub use delay::{Delay, NoteDivision};
+pub use envelope::{Envelope, EnvelopeLoop};
+pub use envelope_follower::EnvelopeFollower;
 pub use expressions::Expressions;
 pub use external_param::{ExternalParam, ExternalParamsBlock};
+pub use filter::{Filter, FilterType};
+pub use harmonic_comb::HarmonicComb;
+pub use harmonic_envelopes::HarmonicEnvelopes;
+pub use harmonic_shift::{HarmonicShift, HarmonicShiftMode};
+pub use harmonic_stretch::HarmonicStretch;
 pub use lfo::{Lfo, LfoShape};
+pub use math::{Math, MathOperation, QuantizeGrid};
+pub use midi_control::{MidiControl, MidiSource};
 pub use mixer::Mixer;
-pub use output::Output;
-pub use spectral_blend::SpectralBlend;
+pub use output::{Output, VoiceLevel};
+pub use panner::Panner;
+pub use reverb::Reverb;
+pub use ring_mod::RingMod;
+pub use sample_resynth::SampleResynth;
+pub use spectral_analyzer::SpectralAnalyzer;
+pub use spectral_blend::{BlendMode, SpectralBlend};
+pub use spectral_dynamics::SpectralDynamics;
 pub use spectral_filter::{SpectralFilter, SpectralFilterType};
 pub use spectral_mixer::SpectralMixer;
+pub use spectral_tilt::SpectralTilt;
 pub use oscillator::Oscillator;
 pub use wave_shaper::{ShaperType, WaveShaper};
@@ -1,6 +1,7 @@
 use std::array;
 
 use itertools::izip;
+use nih_plug::util::db_to_gain_fast;
 
 mod config;
 mod link;
@@ -11,24 +12,31 @@ use link::{AudioEnd, UiEnd, UiEvent, create_link_pair};
 pub use ui_bridge::AmplifierUiBridge;
 
 use crate::synth_engine::{
-    StereoSample,
+    RandomParam, Randomizer, Sample, StereoSample,
     buffer::{Buffer, VoicesLayout, zero_buffer},
+    modules::Panner,
     routing::{
-        AudioRouterType, DataType, Input, InputMeta, InputSlots, ModuleId, NUM_CHANNELS,
-        ProcessContext, SamplesOutput, SpectralInputSlot, VoiceRouter,
+        AudioRouterType, DataType, Input, InputMeta, InputSlots, InputSource, ModuleId,
+        NUM_CHANNELS, ProcessContext, SamplesOutput, SpectralInputSlot, VoiceRouter,
     },
     smooth::SmoothedSample,
     synth_module::SynthModule,
 };
 
+pub const MAX_AMP_DRIVE: Sample = 24.0; // dB
+
 struct ChannelParams {
     gain: SmoothedSample,
+    drive: SmoothedSample,
+    pan: SmoothedSample,
 }
 
 impl ChannelParams {
     fn from_config(c: &AmplifierConfig, channel_idx: usize) -> Self {
         Self {
             gain: c.gain[channel_idx].into(),
+            drive: c.drive[channel_idx].into(),
+            pan: c.pan[channel_idx].into(),
         }
     }
 }
@@ -36,6 +44,8 @@ impl ChannelParams {
 pub struct Inputs {
     audio: Option<usize>,
     gain: InputSlots,
+    drive: InputSlots,
+    pan: InputSlots,
 }
 
 impl Default for Inputs {
@@ -43,6 +53,8 @@ impl Default for Inputs {
         Self {
             audio: None,
             gain: InputSlots::empty(Input::Gain),
+            drive: InputSlots::empty(Input::Drive),
+            pan: InputSlots::empty(Input::Pan),
         }
     }
 }
@@ -55,6 +67,8 @@ impl Inputs {
             match input.input_type {
                 Input::Audio => result.audio = input.slots.first().map(|s| s.src_slot),
                 Input::Gain => result.gain = input.clone(),
+                Input::Drive => result.drive = input.clone(),
+                Input::Pan => result.pan = input.clone(),
                 _ => (),
             }
         }
@@ -62,9 +76,12 @@ impl Inputs {
         result
     }
 
-    fn update_amount(&mut self, input_type: Input, src_slot: usize, amount: StereoSample) {
-        if input_type == Input::Gain {
-            self.gain.update_amount(src_slot, amount);
+    fn update_source(&mut self, input_type: Input, src_slot: usize, source: &InputSource) {
+        match input_type {
+            Input::Gain => self.gain.update_source(src_slot, source),
+            Input::Drive => self.drive.update_source(src_slot, source),
+            Input::Pan => self.pan.update_source(src_slot, source),
+            _ => (),
         }
     }
 }
@@ -73,10 +90,15 @@ type Router<'v, 'f, 'c> = VoiceRouter<'v, 'f, 'c, AudioRouterType>;
 
 struct Buffers {
     gain_mod_input: Buffer,
+    drive_mod_input: Buffer,
+    pan_mod_input: Buffer,
 }
 
+// Gain, optional tanh saturation and a balance pan, the usual end of a voice chain in one module
 pub struct Amplifier {
     id: ModuleId,
+    bypass: bool,
+    saturation: bool,
     channel_params: [ChannelParams; NUM_CHANNELS],
     buffers: Buffers,
     audio_end: AudioEnd,
@@ -98,11 +120,15 @@ impl Amplifier {
 
         Self {
             id: config.id,
+            bypass: config.bypass,
+            saturation: config.saturation,
             channel_params: array::from_fn(|channel_idx| {
                 ChannelParams::from_config(config, channel_idx)
             }),
             buffers: Buffers {
                 gain_mod_input: zero_buffer(),
+                drive_mod_input: zero_buffer(),
+                pan_mod_input: zero_buffer(),
             },
             audio_end,
             ui_end: Some(ui_end),
@@ -114,11 +140,31 @@ impl Amplifier {
     pub fn get_config(&self) -> AmplifierConfig {
         AmplifierConfig {
             id: self.id,
+            bypass: self.bypass,
             gain: get_smoothed_param!(self, gain),
+            saturation: self.saturation,
+            drive: get_smoothed_param!(self, drive),
+            pan: get_smoothed_param!(self, pan),
+        }
+    }
+
+    pub fn randomize(
+        config: &mut AmplifierConfig,
+        randomizer: &mut Randomizer,
+        unlocked: impl Fn(RandomParam) -> bool,
+    ) {
+        if unlocked(RandomParam::Drive) {
+            config.drive = randomizer.stereo(config.drive, 0.0, MAX_AMP_DRIVE);
         }
     }
 
     set_smoothed_param!(set_gain, gain);
+    set_smoothed_param!(set_drive, drive, drive.clamp(-MAX_AMP_DRIVE, MAX_AMP_DRIVE));
+    set_smoothed_param!(set_pan, pan, pan.clamp(-1.0, 1.0));
+
+    pub fn set_saturation(&mut self, saturation: bool) {
+        self.saturation = saturation;
+    }
 
     fn process_voice(
         &mut self,
@@ -137,10 +183,39 @@ impl Amplifier {
             &mut self.buffers.gain_mod_input,
         );
 
-        let input = router.buff(inputs.audio);
+        router.buff_param(
+            &inputs.pan,
+            &mut channel.pan,
+            &mut self.buffers.pan_mod_input,
+        );
 
-        for (out, input, modulation) in izip!(output, input, &self.buffers.gain_mod_input) {
-            *out = input * modulation;
+        if self.saturation {
+            router.buff_param(
+                &inputs.drive,
+                &mut channel.drive,
+                &mut self.buffers.drive_mod_input,
+            );
+        }
+
+        let input = router.buff(inputs.audio);
+        let buffers = &self.buffers;
+        // The left channel follows the mirrored pan
+        let sign = if channel_idx == 0 { -1.0 } else { 1.0 };
+
+        for (out, input, gain, drive, pan) in izip!(
+            output,
+            input,
+            &buffers.gain_mod_input,
+            &buffers.drive_mod_input,
+            &buffers.pan_mod_input
+        ) {
+            let amplified = if self.saturation {
+                (input * gain * db_to_gain_fast(drive.min(MAX_AMP_DRIVE))).tanh()
+            } else {
+                input * gain
+            };
+
+            *out = amplified * Panner::pan_gain(sign * pan);
         }
     }
 }
@@ -150,10 +225,20 @@ impl SynthModule for Amplifier {
         self.id
     }
 
+    fn bypass(&self) -> bool {
+        self.bypass
+    }
+
+    fn set_bypass(&mut self, bypass: bool) {
+        self.bypass = bypass;
+    }
+
     fn inputs(&self) -> &'static [InputMeta] {
         static INPUTS: &[InputMeta] = &[
             InputMeta::audio(Input::Audio),
             InputMeta::control(Input::Gain),
+            InputMeta::control(Input::Drive),
+            InputMeta::control(Input::Pan),
         ];
 
         INPUTS
@@ -175,18 +260,24 @@ impl SynthModule for Amplifier {
         self.inputs = Inputs::from_slots(inputs, spectral_inputs);
     }
 
-    fn update_input_amount(&mut self, input_type: Input, src_slot: usize, amount: StereoSample) {
-        self.inputs.update_amount(input_type, src_slot, amount);
+    fn update_input_source(&mut self, input_type: Input, src_slot: usize, source: &InputSource) {
+        self.inputs.update_source(input_type, src_slot, source);
+    }
+
+    fn set_input_param(&mut self, input: Input, value: StereoSample) {
+        match input {
+            Input::Gain => self.set_gain(value),
+            Input::Drive => self.set_drive(value),
+            Input::Pan => self.set_pan(value),
+            _ => (),
+        }
     }
 
     fn process_ui_events(&mut self) {
         while let Some(event) = self.audio_end.pop_event() {
             match event {
-                UiEvent::InputParam { input, value } => {
-                    if input == Input::Gain {
-                        self.set_gain(value)
-                    }
-                }
+                UiEvent::InputParam { input, value } => self.set_input_param(input, value),
+                UiEvent::Saturation(saturation) => self.set_saturation(saturation),
             }
         }
     }
@@ -5,14 +5,26 @@ use crate::synth_engine::{ModuleId, StereoSample};
 #[derive(Clone, Serialize, Deserialize)]
 pub struct AmplifierConfig {
     pub id: ModuleId,
+    #[serde(default)]
+    pub bypass: bool,
     pub gain: StereoSample,
+    #[serde(default)]
+    pub saturation: bool,
+    #[serde(default)]
+    pub drive: StereoSample, // dB
+    #[serde(default)]
+    pub pan: StereoSample,
 }
 
 impl Default for AmplifierConfig {
     fn default() -> Self {
         Self {
             id: -1,
+            bypass: false,
             gain: 0.0.into(),
+            saturation: false,
+            drive: 0.0.into(),
+            pan: 0.0.into(),
         }
     }
 }
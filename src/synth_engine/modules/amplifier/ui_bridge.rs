@@ -21,12 +21,29 @@ impl AmplifierUiBridge {
     }
 
     pub fn set_param(&mut self, input: Input, value: StereoSample) {
-        if self.ui_end.set_param(input, value) && input == Input::Gain {
-            self.config.gain = value;
+        if !self.ui_end.set_param(input, value) {
+            return;
+        }
+
+        match input {
+            Input::Gain => self.config.gain = value,
+            Input::Drive => self.config.drive = value,
+            Input::Pan => self.config.pan = value,
+            _ => (),
+        }
+    }
+
+    pub fn set_saturation(&mut self, saturation: bool) {
+        if self.ui_end.set_saturation(saturation) {
+            self.config.saturation = saturation;
         }
     }
 }
 
 impl ModuleUiBridge for AmplifierUiBridge {
     fn update(&mut self) {}
+
+    fn edits(&self) -> u32 {
+        self.ui_end.edits()
+    }
 }
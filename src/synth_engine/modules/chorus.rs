@@ -0,0 +1,245 @@
+use std::{array, f32::consts::TAU};
+
+use itertools::izip;
+
+mod config;
+mod link;
+mod ui_bridge;
+
+pub use config::ChorusConfig;
+use link::{AudioEnd, UiEnd, UiEvent, create_link_pair};
+pub use ui_bridge::ChorusUiBridge;
+
+use crate::{
+    synth_engine::{
+        RandomParam, Randomizer, Sample,
+        buffer::{Buffer, zero_buffer},
+        delay_line::{DelayLine, MAX_BUS_SAMPLE_RATE},
+        routing::{
+            DataType, Input, InputMeta, InputSlots, InputSource, ModuleId, NUM_CHANNELS,
+            ProcessContext, ProcessParams, SpectralInputSlot,
+        },
+        smooth::SmoothedSample,
+        synth_module::SynthModule,
+    },
+    utils::from_ms,
+};
+
+pub const MAX_CHORUS_VOICES: usize = 4;
+const MAX_DELAY: Sample = from_ms(30.0);
+const MAX_DEPTH: Sample = from_ms(10.0);
+
+struct Params {
+    voices: usize,
+    rate: Sample,
+    spread: Sample,
+}
+
+impl Params {
+    fn from_config(c: &ChorusConfig) -> Self {
+        Self {
+            voices: Chorus::clamp_voices(c.voices),
+            rate: Chorus::clamp_rate(c.rate),
+            spread: c.spread.clamp(0.0, 1.0),
+        }
+    }
+}
+
+struct ChannelParams {
+    delay: Sample,
+    depth: Sample,
+    mix: SmoothedSample,
+}
+
+impl ChannelParams {
+    fn from_config(c: &ChorusConfig, channel_idx: usize) -> Self {
+        Self {
+            delay: Chorus::clamp_delay(c.delay[channel_idx]),
+            depth: Chorus::clamp_depth(c.depth[channel_idx]),
+            mix: c.mix[channel_idx].clamp(0.0, 1.0).into(),
+        }
+    }
+}
+
+pub struct Chorus {
+    id: ModuleId,
+    bypass: bool,
+    params: Params,
+    channel_params: [ChannelParams; NUM_CHANNELS],
+    lines: [DelayLine; NUM_CHANNELS],
+    mix_buffer: Buffer,
+    phase: Sample,
+    audio_end: AudioEnd,
+    ui_end: Option<UiEnd>,
+}
+
+impl Chorus {
+    pub fn new(id: ModuleId) -> Self {
+        Self::from_config(&ChorusConfig {
+            id,
+            ..ChorusConfig::default()
+        })
+    }
+
+    pub fn from_config(config: &ChorusConfig) -> Self {
+        let (audio_end, ui_end) = create_link_pair();
+        let max_delay_samples = ((MAX_DELAY + MAX_DEPTH) * MAX_BUS_SAMPLE_RATE).ceil() as usize;
+
+        Self {
+            id: config.id,
+            bypass: config.bypass,
+            params: Params::from_config(config),
+            channel_params: array::from_fn(|channel_idx| {
+                ChannelParams::from_config(config, channel_idx)
+            }),
+            lines: array::from_fn(|_| DelayLine::new(max_delay_samples)),
+            mix_buffer: zero_buffer(),
+            phase: 0.0,
+            audio_end,
+            ui_end: Some(ui_end),
+        }
+    }
+
+    pub fn get_config(&self) -> ChorusConfig {
+        ChorusConfig {
+            id: self.id,
+            bypass: self.bypass,
+            voices: self.params.voices,
+            rate: self.params.rate,
+            spread: self.params.spread,
+            delay: get_stereo_param!(self, delay),
+            depth: get_stereo_param!(self, depth),
+            mix: get_smoothed_param!(self, mix),
+        }
+    }
+
+    pub fn randomize(
+        config: &mut ChorusConfig,
+        randomizer: &mut Randomizer,
+        unlocked: impl Fn(RandomParam) -> bool,
+    ) {
+        if unlocked(RandomParam::Rate) {
+            config.rate = randomizer.log(config.rate.into(), 0.05, 5.0).left();
+        }
+        if unlocked(RandomParam::Depth) {
+            config.depth = randomizer.stereo(config.depth, 0.0, MAX_DEPTH);
+        }
+        if unlocked(RandomParam::Mix) {
+            config.mix = randomizer.stereo(config.mix, 0.0, 1.0);
+        }
+    }
+
+    fn clamp_voices(voices: usize) -> usize {
+        voices.clamp(1, MAX_CHORUS_VOICES)
+    }
+
+    fn clamp_rate(rate: Sample) -> Sample {
+        rate.clamp(0.01, 10.0)
+    }
+
+    fn clamp_delay(delay: Sample) -> Sample {
+        delay.clamp(from_ms(1.0), MAX_DELAY)
+    }
+
+    fn clamp_depth(depth: Sample) -> Sample {
+        depth.clamp(0.0, MAX_DEPTH)
+    }
+
+    set_mono_param!(set_voices, voices, usize, Self::clamp_voices(voices));
+    set_mono_param!(set_rate, rate, Sample, Self::clamp_rate(rate));
+    set_mono_param!(set_spread, spread, Sample, spread.clamp(0.0, 1.0));
+
+    set_stereo_param!(set_delay, delay, Self::clamp_delay(*delay));
+    set_stereo_param!(set_depth, depth, Self::clamp_depth(*depth));
+    set_smoothed_param!(set_mix, mix, mix.clamp(0.0, 1.0));
+}
+
+impl SynthModule for Chorus {
+    fn id(&self) -> ModuleId {
+        self.id
+    }
+
+    fn bypass(&self) -> bool {
+        self.bypass
+    }
+
+    fn set_bypass(&mut self, bypass: bool) {
+        self.bypass = bypass;
+    }
+
+    fn inputs(&self) -> &'static [InputMeta] {
+        static INPUTS: &[InputMeta] = &[InputMeta::bus(Input::Audio)];
+
+        INPUTS
+    }
+
+    fn output_type(&self) -> DataType {
+        DataType::Bus
+    }
+
+    fn output_slot(&self) -> usize {
+        usize::MAX
+    }
+
+    fn set_output_slot(&mut self, _slot: usize) {}
+
+    fn set_input_slots(&mut self, _inputs: &[InputSlots], _spectral_inputs: &[SpectralInputSlot]) {}
+
+    fn update_input_source(&mut self, _input_type: Input, _src_slot: usize, _source: &InputSource) {
+    }
+
+    fn process_ui_events(&mut self) {
+        while let Some(event) = self.audio_end.pop_event() {
+            match event {
+                UiEvent::Voices(voices) => self.set_voices(voices),
+                UiEvent::Rate(rate) => self.set_rate(rate),
+                UiEvent::Spread(spread) => self.set_spread(spread),
+                UiEvent::Delay(delay) => self.set_delay(delay),
+                UiEvent::Depth(depth) => self.set_depth(depth),
+                UiEvent::Mix(mix) => self.set_mix(mix),
+            }
+        }
+    }
+
+    fn process(&mut self, _ctx: &mut ProcessContext) {}
+
+    fn process_bus(&mut self, bus: &mut [Buffer; NUM_CHANNELS], params: &ProcessParams) {
+        let samples = params.samples;
+        let sample_rate = params.sample_rate;
+        let voices = self.params.voices;
+        let phase_step = self.params.rate / sample_rate;
+        let voice_phase_step = (voices as Sample).recip();
+        let wet_gain = (voices as Sample).sqrt().recip();
+
+        for (channel_idx, (buffer, line, channel)) in
+            izip!(bus.iter_mut(), &mut self.lines, &mut self.channel_params).enumerate()
+        {
+            // Spread moves the right channel modulation up to half a cycle away from the left one
+            let channel_phase = 0.5 * self.params.spread * channel_idx as Sample;
+            let delay = channel.delay * sample_rate;
+            let depth = channel.depth * sample_rate;
+            let mut phase = self.phase + channel_phase;
+
+            channel
+                .mix
+                .smoothed_buff(&mut self.mix_buffer[..samples], &params.smooth_params);
+
+            for (out, mix) in izip!(buffer.iter_mut(), &self.mix_buffer).take(samples) {
+                line.push(*out);
+
+                let wet: Sample = (0..voices)
+                    .map(|voice_idx| {
+                        let lfo = ((phase + voice_idx as Sample * voice_phase_step) * TAU).sin();
+
+                        line.read(delay + 0.5 * depth * (1.0 + lfo))
+                    })
+                    .sum();
+
+                *out += (wet * wet_gain - *out) * mix;
+                phase += phase_step;
+            }
+        }
+
+        self.phase = (self.phase + phase_step * samples as Sample).fract();
+    }
+}
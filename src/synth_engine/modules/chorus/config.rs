@@ -0,0 +1,34 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    synth_engine::{ModuleId, Sample, StereoSample},
+    utils::from_ms,
+};
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ChorusConfig {
+    pub id: ModuleId,
+    #[serde(default)]
+    pub bypass: bool,
+    pub voices: usize,
+    pub rate: Sample,   // Hz
+    pub spread: Sample, // 0.0 - 1.0
+    pub delay: StereoSample,
+    pub depth: StereoSample,
+    pub mix: StereoSample,
+}
+
+impl Default for ChorusConfig {
+    fn default() -> Self {
+        Self {
+            id: -1,
+            bypass: false,
+            voices: 3,
+            rate: 0.8,
+            spread: 0.5,
+            delay: from_ms(12.0).into(),
+            depth: from_ms(3.0).into(),
+            mix: 0.5.into(),
+        }
+    }
+}
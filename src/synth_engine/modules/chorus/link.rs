@@ -0,0 +1,70 @@
+use crate::synth_engine::{Sample, StereoSample, edit_sender::EditSender};
+
+pub enum UiEvent {
+    Voices(usize),
+    Rate(Sample),
+    Spread(Sample),
+    Delay(StereoSample),
+    Depth(StereoSample),
+    Mix(StereoSample),
+}
+
+pub struct UiEnd {
+    tx: EditSender<UiEvent>,
+}
+
+impl UiEnd {
+    pub fn new(tx: rtrb::Producer<UiEvent>) -> Self {
+        Self {
+            tx: EditSender::new(tx),
+        }
+    }
+
+    pub fn edits(&self) -> u32 {
+        self.tx.sent()
+    }
+
+    pub fn set_voices(&mut self, voices: usize) -> bool {
+        self.tx.push(UiEvent::Voices(voices)).is_ok()
+    }
+
+    pub fn set_rate(&mut self, rate: Sample) -> bool {
+        self.tx.push(UiEvent::Rate(rate)).is_ok()
+    }
+
+    pub fn set_spread(&mut self, spread: Sample) -> bool {
+        self.tx.push(UiEvent::Spread(spread)).is_ok()
+    }
+
+    pub fn set_delay(&mut self, delay: StereoSample) -> bool {
+        self.tx.push(UiEvent::Delay(delay)).is_ok()
+    }
+
+    pub fn set_depth(&mut self, depth: StereoSample) -> bool {
+        self.tx.push(UiEvent::Depth(depth)).is_ok()
+    }
+
+    pub fn set_mix(&mut self, mix: StereoSample) -> bool {
+        self.tx.push(UiEvent::Mix(mix)).is_ok()
+    }
+}
+
+pub struct AudioEnd {
+    rx: rtrb::Consumer<UiEvent>,
+}
+
+impl AudioEnd {
+    pub fn new(rx: rtrb::Consumer<UiEvent>) -> Self {
+        Self { rx }
+    }
+
+    pub fn pop_event(&mut self) -> Option<UiEvent> {
+        self.rx.pop().ok()
+    }
+}
+
+pub fn create_link_pair() -> (AudioEnd, UiEnd) {
+    let (to_audio_tx, from_ui_rx) = rtrb::RingBuffer::<UiEvent>::new(128);
+
+    (AudioEnd::new(from_ui_rx), UiEnd::new(to_audio_tx))
+}
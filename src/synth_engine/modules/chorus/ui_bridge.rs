@@ -0,0 +1,66 @@
+use crate::synth_engine::{Sample, StereoSample, synth_module::ModuleUiBridge};
+
+use super::link::UiEnd;
+use super::{Chorus, ChorusConfig};
+
+pub struct ChorusUiBridge {
+    ui_end: UiEnd,
+    config: ChorusConfig,
+}
+
+impl ChorusUiBridge {
+    pub fn try_new(chorus: &mut Chorus) -> Option<Self> {
+        Some(Self {
+            ui_end: chorus.ui_end.take()?,
+            config: chorus.get_config(),
+        })
+    }
+
+    pub fn config(&self) -> &ChorusConfig {
+        &self.config
+    }
+
+    pub fn set_voices(&mut self, voices: usize) {
+        if self.ui_end.set_voices(voices) {
+            self.config.voices = voices;
+        }
+    }
+
+    pub fn set_rate(&mut self, rate: Sample) {
+        if self.ui_end.set_rate(rate) {
+            self.config.rate = rate;
+        }
+    }
+
+    pub fn set_spread(&mut self, spread: Sample) {
+        if self.ui_end.set_spread(spread) {
+            self.config.spread = spread;
+        }
+    }
+
+    pub fn set_delay(&mut self, delay: StereoSample) {
+        if self.ui_end.set_delay(delay) {
+            self.config.delay = delay;
+        }
+    }
+
+    pub fn set_depth(&mut self, depth: StereoSample) {
+        if self.ui_end.set_depth(depth) {
+            self.config.depth = depth;
+        }
+    }
+
+    pub fn set_mix(&mut self, mix: StereoSample) {
+        if self.ui_end.set_mix(mix) {
+            self.config.mix = mix;
+        }
+    }
+}
+
+impl ModuleUiBridge for ChorusUiBridge {
+    fn update(&mut self) {}
+
+    fn edits(&self) -> u32 {
+        self.ui_end.edits()
+    }
+}
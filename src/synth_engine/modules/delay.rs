@@ -0,0 +1,334 @@
+use std::{array, f32::consts::TAU};
+
+mod config;
+mod link;
+mod ui_bridge;
+
+pub use config::{DelayConfig, NoteDivision};
+use link::{AudioEnd, UiEnd, UiEvent, create_link_pair};
+pub use ui_bridge::DelayUiBridge;
+
+use crate::{
+    synth_engine::{
+        DEFAULT_TEMPO, RandomParam, Randomizer, Sample,
+        buffer::{Buffer, zero_buffer},
+        delay_line::{DelayLine, MAX_BUS_SAMPLE_RATE},
+        routing::{
+            DataType, Input, InputMeta, InputSlots, InputSource, ModuleId, NUM_CHANNELS,
+            ProcessContext, ProcessParams, SpectralInputSlot,
+        },
+        smooth::SmoothedSample,
+        synth_module::SynthModule,
+    },
+    utils::from_ms,
+};
+
+pub const MAX_DELAY_TIME: Sample = 2.0;
+const MIN_DELAY_TIME: Sample = from_ms(1.0);
+const MAX_FEEDBACK: Sample = 0.95;
+
+struct Params {
+    sync: bool,
+    divisions: [NoteDivision; NUM_CHANNELS],
+    ping_pong: bool,
+    low_cut: Sample,
+    high_cut: Sample,
+}
+
+impl Params {
+    fn from_config(c: &DelayConfig) -> Self {
+        Self {
+            sync: c.sync,
+            divisions: c.divisions,
+            ping_pong: c.ping_pong,
+            low_cut: Delay::clamp_low_cut(c.low_cut),
+            high_cut: Delay::clamp_high_cut(c.high_cut),
+        }
+    }
+}
+
+struct ChannelParams {
+    time: Sample,
+    feedback: Sample,
+    mix: SmoothedSample,
+}
+
+impl ChannelParams {
+    fn from_config(c: &DelayConfig, channel_idx: usize) -> Self {
+        Self {
+            time: Delay::clamp_time(c.time[channel_idx]),
+            feedback: Delay::clamp_feedback(c.feedback[channel_idx]),
+            mix: c.mix[channel_idx].clamp(0.0, 1.0).into(),
+        }
+    }
+}
+
+// Band limits the repeats, both cuts are one-pole
+#[derive(Default)]
+struct Damping {
+    high_cut_state: Sample,
+    low_cut_state: Sample,
+}
+
+impl Damping {
+    fn mult(freq: Sample, sample_rate: Sample) -> Sample {
+        1.0 - (-TAU * freq / sample_rate).exp()
+    }
+
+    fn process(&mut self, input: Sample, low_cut_mult: Sample, high_cut_mult: Sample) -> Sample {
+        self.high_cut_state += (input - self.high_cut_state) * high_cut_mult;
+        self.low_cut_state += (self.high_cut_state - self.low_cut_state) * low_cut_mult;
+        self.high_cut_state - self.low_cut_state
+    }
+}
+
+struct Channel {
+    line: DelayLine,
+    time: SmoothedSample,
+    damping: Damping,
+    time_buffer: Buffer,
+    mix_buffer: Buffer,
+}
+
+pub struct Delay {
+    id: ModuleId,
+    bypass: bool,
+    params: Params,
+    channel_params: [ChannelParams; NUM_CHANNELS],
+    channels: [Channel; NUM_CHANNELS],
+    audio_end: AudioEnd,
+    ui_end: Option<UiEnd>,
+}
+
+impl Delay {
+    pub fn new(id: ModuleId) -> Self {
+        Self::from_config(&DelayConfig {
+            id,
+            ..DelayConfig::default()
+        })
+    }
+
+    pub fn from_config(config: &DelayConfig) -> Self {
+        let (audio_end, ui_end) = create_link_pair();
+        let max_delay_samples = (MAX_DELAY_TIME * MAX_BUS_SAMPLE_RATE).ceil() as usize;
+        let mut delay = Self {
+            id: config.id,
+            bypass: config.bypass,
+            params: Params::from_config(config),
+            channel_params: array::from_fn(|channel_idx| {
+                ChannelParams::from_config(config, channel_idx)
+            }),
+            channels: array::from_fn(|_| Channel {
+                line: DelayLine::new(max_delay_samples),
+                time: SmoothedSample::default(),
+                damping: Damping::default(),
+                time_buffer: zero_buffer(),
+                mix_buffer: zero_buffer(),
+            }),
+            audio_end,
+            ui_end: Some(ui_end),
+        };
+
+        // Avoids gliding from zero on the first block, the host tempo is not known yet
+        for channel_idx in 0..NUM_CHANNELS {
+            delay.channels[channel_idx].time =
+                SmoothedSample::new(delay.target_time(channel_idx, DEFAULT_TEMPO));
+        }
+
+        delay
+    }
+
+    pub fn get_config(&self) -> DelayConfig {
+        DelayConfig {
+            id: self.id,
+            bypass: self.bypass,
+            sync: self.params.sync,
+            divisions: self.params.divisions,
+            time: get_stereo_param!(self, time),
+            feedback: get_stereo_param!(self, feedback),
+            ping_pong: self.params.ping_pong,
+            low_cut: self.params.low_cut,
+            high_cut: self.params.high_cut,
+            mix: get_smoothed_param!(self, mix),
+        }
+    }
+
+    pub fn randomize(
+        config: &mut DelayConfig,
+        randomizer: &mut Randomizer,
+        unlocked: impl Fn(RandomParam) -> bool,
+    ) {
+        if unlocked(RandomParam::Time) {
+            config.time = randomizer.log(config.time, MIN_DELAY_TIME, MAX_DELAY_TIME);
+        }
+        if unlocked(RandomParam::Feedback) {
+            config.feedback = randomizer.stereo(config.feedback, 0.0, MAX_FEEDBACK);
+        }
+        if unlocked(RandomParam::Mix) {
+            config.mix = randomizer.stereo(config.mix, 0.0, 1.0);
+        }
+    }
+
+    fn clamp_time(time: Sample) -> Sample {
+        time.clamp(MIN_DELAY_TIME, MAX_DELAY_TIME)
+    }
+
+    fn clamp_feedback(feedback: Sample) -> Sample {
+        feedback.clamp(0.0, MAX_FEEDBACK)
+    }
+
+    fn clamp_low_cut(low_cut: Sample) -> Sample {
+        low_cut.clamp(20.0, 2000.0)
+    }
+
+    fn clamp_high_cut(high_cut: Sample) -> Sample {
+        high_cut.clamp(1000.0, 20000.0)
+    }
+
+    pub fn set_division(&mut self, channel_idx: usize, division: NoteDivision) {
+        if let Some(channel_division) = self.params.divisions.get_mut(channel_idx) {
+            *channel_division = division;
+        }
+    }
+
+    set_mono_param!(set_sync, sync, bool);
+    set_mono_param!(set_ping_pong, ping_pong, bool);
+    set_mono_param!(set_low_cut, low_cut, Sample, Self::clamp_low_cut(low_cut));
+    set_mono_param!(
+        set_high_cut,
+        high_cut,
+        Sample,
+        Self::clamp_high_cut(high_cut)
+    );
+
+    set_stereo_param!(set_time, time, Self::clamp_time(*time));
+    set_stereo_param!(set_feedback, feedback, Self::clamp_feedback(*feedback));
+    set_smoothed_param!(set_mix, mix, mix.clamp(0.0, 1.0));
+
+    fn target_time(&self, channel_idx: usize, tempo: Sample) -> Sample {
+        if self.params.sync {
+            Self::clamp_time(self.params.divisions[channel_idx].beats() * 60.0 / tempo)
+        } else {
+            self.channel_params[channel_idx].time
+        }
+    }
+}
+
+impl SynthModule for Delay {
+    fn id(&self) -> ModuleId {
+        self.id
+    }
+
+    fn bypass(&self) -> bool {
+        self.bypass
+    }
+
+    fn set_bypass(&mut self, bypass: bool) {
+        self.bypass = bypass;
+    }
+
+    fn inputs(&self) -> &'static [InputMeta] {
+        static INPUTS: &[InputMeta] = &[InputMeta::bus(Input::Audio)];
+
+        INPUTS
+    }
+
+    fn output_type(&self) -> DataType {
+        DataType::Bus
+    }
+
+    fn output_slot(&self) -> usize {
+        usize::MAX
+    }
+
+    fn set_output_slot(&mut self, _slot: usize) {}
+
+    fn set_input_slots(&mut self, _inputs: &[InputSlots], _spectral_inputs: &[SpectralInputSlot]) {}
+
+    fn update_input_source(&mut self, _input_type: Input, _src_slot: usize, _source: &InputSource) {
+    }
+
+    fn process_ui_events(&mut self) {
+        while let Some(event) = self.audio_end.pop_event() {
+            match event {
+                UiEvent::Sync(sync) => self.set_sync(sync),
+                UiEvent::Division {
+                    channel_idx,
+                    division,
+                } => self.set_division(channel_idx, division),
+                UiEvent::Time(time) => self.set_time(time),
+                UiEvent::Feedback(feedback) => self.set_feedback(feedback),
+                UiEvent::PingPong(ping_pong) => self.set_ping_pong(ping_pong),
+                UiEvent::LowCut(low_cut) => self.set_low_cut(low_cut),
+                UiEvent::HighCut(high_cut) => self.set_high_cut(high_cut),
+                UiEvent::Mix(mix) => self.set_mix(mix),
+            }
+        }
+    }
+
+    fn process(&mut self, _ctx: &mut ProcessContext) {}
+
+    fn process_bus(&mut self, bus: &mut [Buffer; NUM_CHANNELS], params: &ProcessParams) {
+        let samples = params.samples;
+        let sample_rate = params.sample_rate;
+        let low_cut_mult = Damping::mult(self.params.low_cut, sample_rate);
+        let high_cut_mult = Damping::mult(self.params.high_cut, sample_rate);
+
+        for channel_idx in 0..NUM_CHANNELS {
+            let target_time = self.target_time(channel_idx, params.tempo);
+            let channel = &mut self.channels[channel_idx];
+
+            if channel.time.get() != target_time {
+                channel.time.set(target_time);
+            }
+
+            channel
+                .time
+                .smoothed_buff(&mut channel.time_buffer[..samples], &params.smooth_params);
+            self.channel_params[channel_idx]
+                .mix
+                .smoothed_buff(&mut channel.mix_buffer[..samples], &params.smooth_params);
+        }
+
+        let left_feedback = self.channel_params[0].feedback;
+        let right_feedback = self.channel_params[1].feedback;
+        let [left, right] = &mut self.channels;
+        let [left_bus, right_bus] = bus;
+
+        for (sample_idx, (left_out, right_out)) in left_bus
+            .iter_mut()
+            .zip(right_bus.iter_mut())
+            .take(samples)
+            .enumerate()
+        {
+            // Reading before the push, so one sample less
+            let delayed_left = left
+                .line
+                .read(left.time_buffer[sample_idx] * sample_rate - 1.0);
+            let delayed_right = right
+                .line
+                .read(right.time_buffer[sample_idx] * sample_rate - 1.0);
+
+            let (left_in, right_in) = if self.params.ping_pong {
+                (
+                    0.5 * (*left_out + *right_out) + left_feedback * delayed_right,
+                    right_feedback * delayed_left,
+                )
+            } else {
+                (
+                    *left_out + left_feedback * delayed_left,
+                    *right_out + right_feedback * delayed_right,
+                )
+            };
+
+            left.line
+                .push(left.damping.process(left_in, low_cut_mult, high_cut_mult));
+            right
+                .line
+                .push(right.damping.process(right_in, low_cut_mult, high_cut_mult));
+
+            *left_out += (delayed_left - *left_out) * left.mix_buffer[sample_idx];
+            *right_out += (delayed_right - *right_out) * right.mix_buffer[sample_idx];
+        }
+    }
+}
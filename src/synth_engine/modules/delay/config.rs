@@ -0,0 +1,68 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    synth_engine::{ModuleId, NUM_CHANNELS, Sample, StereoSample},
+    utils::from_ms,
+};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum NoteDivision {
+    Sixteenth,
+    EighthTriplet,
+    Eighth,
+    DottedEighth,
+    QuarterTriplet,
+    #[default]
+    Quarter,
+    DottedQuarter,
+    Half,
+    Whole,
+}
+
+impl NoteDivision {
+    pub fn beats(self) -> Sample {
+        match self {
+            Self::Sixteenth => 0.25,
+            Self::EighthTriplet => 1.0 / 3.0,
+            Self::Eighth => 0.5,
+            Self::DottedEighth => 0.75,
+            Self::QuarterTriplet => 2.0 / 3.0,
+            Self::Quarter => 1.0,
+            Self::DottedQuarter => 1.5,
+            Self::Half => 2.0,
+            Self::Whole => 4.0,
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct DelayConfig {
+    pub id: ModuleId,
+    #[serde(default)]
+    pub bypass: bool,
+    pub sync: bool,
+    pub divisions: [NoteDivision; NUM_CHANNELS],
+    pub time: StereoSample,
+    pub feedback: StereoSample,
+    pub ping_pong: bool,
+    pub low_cut: Sample,  // Hz
+    pub high_cut: Sample, // Hz
+    pub mix: StereoSample,
+}
+
+impl Default for DelayConfig {
+    fn default() -> Self {
+        Self {
+            id: -1,
+            bypass: false,
+            sync: false,
+            divisions: [NoteDivision::DottedEighth, NoteDivision::Quarter],
+            time: from_ms(375.0).into(),
+            feedback: 0.4.into(),
+            ping_pong: false,
+            low_cut: 80.0,
+            high_cut: 8000.0,
+            mix: 0.3.into(),
+        }
+    }
+}
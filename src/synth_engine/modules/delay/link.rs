@@ -0,0 +1,90 @@
+use crate::synth_engine::{Sample, StereoSample, edit_sender::EditSender};
+
+use super::config::NoteDivision;
+
+pub enum UiEvent {
+    Sync(bool),
+    Division {
+        channel_idx: usize,
+        division: NoteDivision,
+    },
+    Time(StereoSample),
+    Feedback(StereoSample),
+    PingPong(bool),
+    LowCut(Sample),
+    HighCut(Sample),
+    Mix(StereoSample),
+}
+
+pub struct UiEnd {
+    tx: EditSender<UiEvent>,
+}
+
+impl UiEnd {
+    pub fn new(tx: rtrb::Producer<UiEvent>) -> Self {
+        Self {
+            tx: EditSender::new(tx),
+        }
+    }
+
+    pub fn edits(&self) -> u32 {
+        self.tx.sent()
+    }
+
+    pub fn set_sync(&mut self, sync: bool) -> bool {
+        self.tx.push(UiEvent::Sync(sync)).is_ok()
+    }
+
+    pub fn set_division(&mut self, channel_idx: usize, division: NoteDivision) -> bool {
+        self.tx
+            .push(UiEvent::Division {
+                channel_idx,
+                division,
+            })
+            .is_ok()
+    }
+
+    pub fn set_time(&mut self, time: StereoSample) -> bool {
+        self.tx.push(UiEvent::Time(time)).is_ok()
+    }
+
+    pub fn set_feedback(&mut self, feedback: StereoSample) -> bool {
+        self.tx.push(UiEvent::Feedback(feedback)).is_ok()
+    }
+
+    pub fn set_ping_pong(&mut self, ping_pong: bool) -> bool {
+        self.tx.push(UiEvent::PingPong(ping_pong)).is_ok()
+    }
+
+    pub fn set_low_cut(&mut self, low_cut: Sample) -> bool {
+        self.tx.push(UiEvent::LowCut(low_cut)).is_ok()
+    }
+
+    pub fn set_high_cut(&mut self, high_cut: Sample) -> bool {
+        self.tx.push(UiEvent::HighCut(high_cut)).is_ok()
+    }
+
+    pub fn set_mix(&mut self, mix: StereoSample) -> bool {
+        self.tx.push(UiEvent::Mix(mix)).is_ok()
+    }
+}
+
+pub struct AudioEnd {
+    rx: rtrb::Consumer<UiEvent>,
+}
+
+impl AudioEnd {
+    pub fn new(rx: rtrb::Consumer<UiEvent>) -> Self {
+        Self { rx }
+    }
+
+    pub fn pop_event(&mut self) -> Option<UiEvent> {
+        self.rx.pop().ok()
+    }
+}
+
+pub fn create_link_pair() -> (AudioEnd, UiEnd) {
+    let (to_audio_tx, from_ui_rx) = rtrb::RingBuffer::<UiEvent>::new(128);
+
+    (AudioEnd::new(from_ui_rx), UiEnd::new(to_audio_tx))
+}
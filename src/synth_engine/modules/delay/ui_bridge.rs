@@ -0,0 +1,78 @@
+use crate::synth_engine::{Sample, StereoSample, synth_module::ModuleUiBridge};
+
+use super::link::UiEnd;
+use super::{Delay, DelayConfig, NoteDivision};
+
+pub struct DelayUiBridge {
+    ui_end: UiEnd,
+    config: DelayConfig,
+}
+
+impl DelayUiBridge {
+    pub fn try_new(delay: &mut Delay) -> Option<Self> {
+        Some(Self {
+            ui_end: delay.ui_end.take()?,
+            config: delay.get_config(),
+        })
+    }
+
+    pub fn config(&self) -> &DelayConfig {
+        &self.config
+    }
+
+    pub fn set_sync(&mut self, sync: bool) {
+        if self.ui_end.set_sync(sync) {
+            self.config.sync = sync;
+        }
+    }
+
+    pub fn set_division(&mut self, channel_idx: usize, division: NoteDivision) {
+        if self.ui_end.set_division(channel_idx, division) {
+            self.config.divisions[channel_idx] = division;
+        }
+    }
+
+    pub fn set_time(&mut self, time: StereoSample) {
+        if self.ui_end.set_time(time) {
+            self.config.time = time;
+        }
+    }
+
+    pub fn set_feedback(&mut self, feedback: StereoSample) {
+        if self.ui_end.set_feedback(feedback) {
+            self.config.feedback = feedback;
+        }
+    }
+
+    pub fn set_ping_pong(&mut self, ping_pong: bool) {
+        if self.ui_end.set_ping_pong(ping_pong) {
+            self.config.ping_pong = ping_pong;
+        }
+    }
+
+    pub fn set_low_cut(&mut self, low_cut: Sample) {
+        if self.ui_end.set_low_cut(low_cut) {
+            self.config.low_cut = low_cut;
+        }
+    }
+
+    pub fn set_high_cut(&mut self, high_cut: Sample) {
+        if self.ui_end.set_high_cut(high_cut) {
+            self.config.high_cut = high_cut;
+        }
+    }
+
+    pub fn set_mix(&mut self, mix: StereoSample) {
+        if self.ui_end.set_mix(mix) {
+            self.config.mix = mix;
+        }
+    }
+}
+
+impl ModuleUiBridge for DelayUiBridge {
+    fn update(&mut self) {}
+
+    fn edits(&self) -> u32 {
+        self.ui_end.edits()
+    }
+}
@@ -4,18 +4,19 @@ mod config;
 mod link;
 mod ui_bridge;
 
-pub use config::EnvelopeConfig;
+pub use config::{EnvelopeConfig, EnvelopeLoop};
 use link::{AudioEnd, UiEnd, UiEvent, create_link_pair};
 pub use ui_bridge::EnvelopeUiBridge;
 
 use crate::{
     synth_engine::{
-        StereoSample,
-        buffer::{VoicesLayout, new_voices_layout},
+        RandomParam, Randomizer, StereoSample,
+        buffer::{Buffer, VoicesLayout, new_voices_layout, zero_buffer},
         curves::{CurveFunction, Exponential},
         routing::{
-            ControlRouterType, DataType, Input, InputMeta, InputSlots, ModuleId, NUM_CHANNELS,
-            ProcessContext, SamplesOutput, SpectralInputSlot, VoiceEvent, VoiceRouter,
+            ControlRouterType, DataType, Input, InputMeta, InputSlots, InputSource, ModuleId,
+            NUM_CHANNELS, ProcessContext, SamplesOutput, SpectralInputSlot, VoiceEvent,
+            VoiceRouter,
         },
         smooth::Smoother,
         synth_module::SynthModule,
@@ -26,12 +27,25 @@ use crate::{
 };
 
 const MIN_TIME_THRESHOLD: Sample = from_ms(0.5);
+// Octaves the release time is stretched or shortened by at full release velocity amount
+const RELEASE_VELOCITY_RANGE: Sample = 1.0;
+// Octaves the attack, decay and release are scaled by at full note-on velocity amount
+const VELOCITY_TIME_RANGE: Sample = 1.0;
+pub const MAX_ENVELOPE_LOOPS: usize = 64;
 
 struct Params {
     keep_voice_alive: bool,
     attack_curvature: Sample,
     decay_curvature: Sample,
     release_curvature: Sample,
+    release_velocity: Sample,
+    velocity_time: Sample,
+    loop_mode: EnvelopeLoop,
+    loop_count: usize,
+    loop_fade: Sample,
+    output_min: Sample,
+    output_max: Sample,
+    invert: bool,
 }
 
 impl Params {
@@ -41,8 +55,23 @@ impl Params {
             attack_curvature: c.attack_curvature,
             decay_curvature: c.decay_curvature,
             release_curvature: c.release_curvature,
+            release_velocity: c.release_velocity,
+            velocity_time: c.velocity_time,
+            loop_mode: c.loop_mode,
+            loop_count: c.loop_count.min(MAX_ENVELOPE_LOOPS),
+            loop_fade: c.loop_fade,
+            output_min: c.output_min,
+            output_max: c.output_max,
+            invert: c.invert,
         }
     }
+
+    // Maps the 0..1 envelope level into the output range, optionally falling instead of rising
+    fn output_value(&self, level: Sample) -> Sample {
+        let level = if self.invert { 1.0 - level } else { level };
+
+        (self.output_max - self.output_min).mul_add(level, self.output_min)
+    }
 }
 
 struct ChannelParams {
@@ -137,6 +166,7 @@ enum Stage {
     Attack(CurveIter),
     Hold(CurveIter),
     Decay(CurveIter),
+    LoopFade(CurveIter),
     Sustain,
     Release(CurveIter),
     Flush(CurveIter),
@@ -147,6 +177,9 @@ struct VoiceState {
     stage: Stage,
     triggered: bool,
     released: bool,
+    release_velocity: Sample,
+    velocity: Sample,
+    loops: usize,
     next_frame_value: Sample,
     smoother: Smoother,
 }
@@ -157,6 +190,9 @@ impl Default for VoiceState {
             stage: Stage::Done,
             triggered: false,
             released: false,
+            release_velocity: 0.5,
+            velocity: 0.5,
+            loops: 0,
             next_frame_value: 0.0,
             smoother: Smoother::default(),
         }
@@ -204,14 +240,14 @@ impl Inputs {
         result
     }
 
-    fn update_amount(&mut self, input_type: Input, src_slot: usize, amount: StereoSample) {
+    fn update_source(&mut self, input_type: Input, src_slot: usize, source: &InputSource) {
         match input_type {
-            Input::Delay => self.delay.update_amount(src_slot, amount),
-            Input::Attack => self.attack.update_amount(src_slot, amount),
-            Input::Hold => self.hold.update_amount(src_slot, amount),
-            Input::Decay => self.decay.update_amount(src_slot, amount),
-            Input::Sustain => self.sustain.update_amount(src_slot, amount),
-            Input::Release => self.release.update_amount(src_slot, amount),
+            Input::Delay => self.delay.update_source(src_slot, source),
+            Input::Attack => self.attack.update_source(src_slot, source),
+            Input::Hold => self.hold.update_source(src_slot, source),
+            Input::Decay => self.decay.update_source(src_slot, source),
+            Input::Sustain => self.sustain.update_source(src_slot, source),
+            Input::Release => self.release.update_source(src_slot, source),
             _ => (),
         }
     }
@@ -219,10 +255,16 @@ impl Inputs {
 
 type Router<'v, 'f, 'c> = VoiceRouter<'v, 'f, 'c, ControlRouterType>;
 
+struct Buffers {
+    sustain: Buffer,
+}
+
 pub struct Envelope {
     id: ModuleId,
+    bypass: bool,
     params: Params,
     channel_params: [ChannelParams; NUM_CHANNELS],
+    buffers: Buffers,
     audio_end: AudioEnd,
     ui_end: Option<UiEnd>,
     inputs: Inputs,
@@ -243,10 +285,14 @@ impl Envelope {
 
         Self {
             id: config.id,
+            bypass: config.bypass,
             params: Params::from_config(config),
             channel_params: array::from_fn(|channel_idx| {
                 ChannelParams::from_config(config, channel_idx)
             }),
+            buffers: Buffers {
+                sustain: zero_buffer(),
+            },
             audio_end,
             ui_end: Some(ui_end),
             inputs: Inputs::default(),
@@ -258,6 +304,7 @@ impl Envelope {
     pub fn get_config(&self) -> EnvelopeConfig {
         EnvelopeConfig {
             id: self.id,
+            bypass: self.bypass,
             keep_voice_alive: self.params.keep_voice_alive,
             delay: get_stereo_param!(self, delay),
             attack: get_stereo_param!(self, attack),
@@ -268,14 +315,74 @@ impl Envelope {
             sustain: get_stereo_param!(self, sustain),
             release: get_stereo_param!(self, release),
             release_curvature: self.params.release_curvature,
+            release_velocity: self.params.release_velocity,
+            velocity_time: self.params.velocity_time,
+            loop_mode: self.params.loop_mode,
+            loop_count: self.params.loop_count,
+            loop_fade: self.params.loop_fade,
+            output_min: self.params.output_min,
+            output_max: self.params.output_max,
+            invert: self.params.invert,
             smooth: get_stereo_param!(self, smooth),
         }
     }
 
+    pub fn randomize(
+        config: &mut EnvelopeConfig,
+        randomizer: &mut Randomizer,
+        unlocked: impl Fn(RandomParam) -> bool,
+    ) {
+        if unlocked(RandomParam::Attack) {
+            config.attack = randomizer.time(config.attack);
+        }
+        if unlocked(RandomParam::Decay) {
+            config.decay = randomizer.time(config.decay);
+        }
+        if unlocked(RandomParam::Sustain) {
+            config.sustain = randomizer.stereo(config.sustain, 0.0, 1.0);
+        }
+        if unlocked(RandomParam::Release) {
+            config.release = randomizer.time(config.release);
+        }
+    }
+
     set_mono_param!(set_keep_voice_alive, keep_voice_alive, bool);
     set_mono_param!(set_attack_curvature, attack_curvature, Sample);
     set_mono_param!(set_decay_curvature, decay_curvature, Sample);
     set_mono_param!(set_release_curvature, release_curvature, Sample);
+    set_mono_param!(
+        set_release_velocity,
+        release_velocity,
+        Sample,
+        release_velocity.clamp(0.0, 1.0)
+    );
+    set_mono_param!(
+        set_velocity_time,
+        velocity_time,
+        Sample,
+        velocity_time.clamp(-1.0, 1.0)
+    );
+    set_mono_param!(set_loop_mode, loop_mode, EnvelopeLoop);
+    set_mono_param!(
+        set_loop_count,
+        loop_count,
+        usize,
+        loop_count.min(MAX_ENVELOPE_LOOPS)
+    );
+    set_mono_param!(set_loop_fade, loop_fade, Sample, loop_fade.max(0.0));
+    set_mono_param!(
+        set_output_min,
+        output_min,
+        Sample,
+        output_min.clamp(-1.0, 1.0)
+    );
+    set_mono_param!(
+        set_output_max,
+        output_max,
+        Sample,
+        output_max.clamp(-1.0, 1.0)
+    );
+    set_mono_param!(set_invert, invert, bool);
 
     set_stereo_param!(set_delay, delay);
     set_stereo_param!(set_attack, attack);
@@ -296,13 +403,23 @@ impl Envelope {
         let params = &self.params;
         let channel = &mut self.channel_params[channel_idx];
         let voice = &mut self.voices[channel_idx][voice_idx];
+        let sustain_buff = &mut self.buffers.sustain;
         let samples = router.samples();
         let t_step = router.sample_rate().recip();
         let voice_output = &mut output_slot[channel_idx][voice_idx];
+        // Hard releases shorten the release, soft ones stretch it, 0.5 leaves it as set
+        let release_scale = (params.release_velocity
+            * RELEASE_VELOCITY_RANGE
+            * (1.0 - 2.0 * voice.release_velocity))
+            .exp2();
+        // A positive amount makes harder hits faster, a negative one slower
+        let time_scale =
+            (params.velocity_time * VELOCITY_TIME_RANGE * (1.0 - 2.0 * voice.velocity)).exp2();
 
         if voice.triggered {
+            voice.loops = 0;
             voice.next_frame_value = 0.0;
-            voice.smoother.reset(0.0);
+            voice.smoother.reset(params.output_value(0.0));
             voice.stage = Stage::Delay(CurveIter::flat(0.0));
         }
 
@@ -318,6 +435,8 @@ impl Envelope {
         let mut control_output = voice_output.control_output(samples, voice.triggered);
         let output = control_output.output();
         let mut sample_from = 0;
+        // A loop shorter than a sample settles on sustain instead of spinning
+        let mut looped_at = None;
 
         loop {
             voice.stage = match &mut voice.stage {
@@ -337,10 +456,20 @@ impl Envelope {
                 Stage::Attack(curve) => {
                     match curve.next_block(
                         t_step,
-                        router.scalar_param(&inputs.attack, channel.attack, false),
+                        router.scalar_param(&inputs.attack, channel.attack, false) * time_scale,
                         &mut sample_from,
                         output,
                     ) {
+                        // The attack-decay loop only holds on the first pass
+                        CurveBlockResult::Done
+                            if voice.loops > 0 && params.loop_mode == EnvelopeLoop::AttackDecay =>
+                        {
+                            Stage::Decay(CurveIter::new(
+                                params.decay_curvature,
+                                1.0,
+                                channel.sustain,
+                            ))
+                        }
                         CurveBlockResult::Done => Stage::Hold(CurveIter::flat(1.0)),
                         CurveBlockResult::HasMore => break,
                     }
@@ -363,26 +492,55 @@ impl Envelope {
                 Stage::Decay(curve) => {
                     match curve.next_block(
                         t_step,
-                        router.scalar_param(&inputs.decay, channel.decay, false),
+                        router.scalar_param(&inputs.decay, channel.decay, false) * time_scale,
                         &mut sample_from,
                         output,
                     ) {
+                        CurveBlockResult::Done
+                            if params.loop_mode != EnvelopeLoop::Off
+                                && (params.loop_count == 0 || voice.loops < params.loop_count)
+                                && looped_at != Some(sample_from) =>
+                        {
+                            voice.loops += 1;
+                            looped_at = Some(sample_from);
+                            Stage::LoopFade(CurveIter::new(0.0, channel.sustain, 0.0))
+                        }
                         CurveBlockResult::Done => Stage::Sustain,
                         CurveBlockResult::HasMore => break,
                     }
                 }
+                Stage::LoopFade(curve) => {
+                    match curve.next_block(t_step, params.loop_fade, &mut sample_from, output) {
+                        CurveBlockResult::Done => {
+                            Stage::Attack(CurveIter::new(params.attack_curvature, 0.0, 1.0))
+                        }
+                        CurveBlockResult::HasMore => break,
+                    }
+                }
                 Stage::Sustain => {
-                    output[sample_from..].fill(
-                        router
-                            .scalar_param(&inputs.sustain, channel.sustain, false)
-                            .clamp(0.0, 1.0),
+                    let skip = usize::from(!voice.triggered);
+
+                    router.scalar_buff_param(
+                        &inputs.sustain,
+                        channel.sustain,
+                        sustain_buff,
+                        voice.triggered,
                     );
+
+                    for (out, sustain) in output[sample_from..]
+                        .iter_mut()
+                        .zip(&sustain_buff[skip + sample_from..])
+                    {
+                        *out = sustain.clamp(0.0, 1.0);
+                    }
                     break;
                 }
                 Stage::Release(curve) => {
                     match curve.next_block(
                         t_step,
-                        router.scalar_param(&inputs.release, channel.release, false),
+                        router.scalar_param(&inputs.release, channel.release, false)
+                            * release_scale
+                            * time_scale,
                         &mut sample_from,
                         output,
                     ) {
@@ -403,9 +561,14 @@ impl Envelope {
             };
         }
 
+        // The stages continue from the level, the output range only applies to what's sent out
         voice.next_frame_value = control_output.next_frame_value();
         voice.triggered = false;
 
+        for out in control_output.output() {
+            *out = params.output_value(*out);
+        }
+
         drop(control_output);
 
         voice.smoother.apply_if_needed(
@@ -422,13 +585,21 @@ impl SynthModule for Envelope {
         self.id
     }
 
+    fn bypass(&self) -> bool {
+        self.bypass
+    }
+
+    fn set_bypass(&mut self, bypass: bool) {
+        self.bypass = bypass;
+    }
+
     fn inputs(&self) -> &'static [InputMeta] {
         static INPUTS: &[InputMeta] = &[
             InputMeta::control(Input::Delay),
             InputMeta::control(Input::Attack),
             InputMeta::control(Input::Hold),
             InputMeta::control(Input::Decay),
-            InputMeta::control(Input::Sustain),
+            InputMeta::control_audio_rate(Input::Sustain),
             InputMeta::control(Input::Release),
         ];
 
@@ -451,20 +622,29 @@ impl SynthModule for Envelope {
         self.inputs = Inputs::from_slots(inputs, spectral_inputs);
     }
 
-    fn update_input_amount(&mut self, input_type: Input, src_slot: usize, amount: StereoSample) {
-        self.inputs.update_amount(input_type, src_slot, amount);
+    fn update_input_source(&mut self, input_type: Input, src_slot: usize, source: &InputSource) {
+        self.inputs.update_source(input_type, src_slot, source);
     }
 
     fn process_events(&mut self, events: &[VoiceEvent]) {
         for channel in self.voices.iter_mut() {
             for event in events {
                 match event {
-                    VoiceEvent::Trigger { voice_idx, .. } => {
+                    VoiceEvent::Trigger {
+                        voice_idx,
+                        velocity,
+                        ..
+                    } => {
                         channel[*voice_idx].triggered = true;
                         channel[*voice_idx].released = false;
+                        channel[*voice_idx].velocity = *velocity;
                     }
-                    VoiceEvent::Release { voice_idx, .. } => {
+                    VoiceEvent::Release {
+                        voice_idx,
+                        velocity,
+                    } => {
                         channel[*voice_idx].released = true;
+                        channel[*voice_idx].release_velocity = *velocity;
                     }
                     _ => (),
                 }
@@ -486,23 +666,35 @@ impl SynthModule for Envelope {
         }
     }
 
+    fn set_input_param(&mut self, input: Input, value: StereoSample) {
+        match input {
+            Input::Delay => self.set_delay(value),
+            Input::Attack => self.set_attack(value),
+            Input::Hold => self.set_hold(value),
+            Input::Decay => self.set_decay(value),
+            Input::Sustain => self.set_sustain(value),
+            Input::Release => self.set_release(value),
+            _ => (),
+        }
+    }
+
     fn process_ui_events(&mut self) {
         while let Some(event) = self.audio_end.pop_event() {
             match event {
-                UiEvent::InputParam { input, value } => match input {
-                    Input::Delay => self.set_delay(value),
-                    Input::Attack => self.set_attack(value),
-                    Input::Hold => self.set_hold(value),
-                    Input::Decay => self.set_decay(value),
-                    Input::Sustain => self.set_sustain(value),
-                    Input::Release => self.set_release(value),
-                    _ => (),
-                },
+                UiEvent::InputParam { input, value } => self.set_input_param(input, value),
                 UiEvent::Smooth(value) => self.set_smooth(value),
                 UiEvent::AttackCurvature(value) => self.set_attack_curvature(value),
                 UiEvent::DecayCurvature(value) => self.set_decay_curvature(value),
                 UiEvent::ReleaseCurvature(value) => self.set_release_curvature(value),
+                UiEvent::ReleaseVelocity(value) => self.set_release_velocity(value),
+                UiEvent::VelocityTime(value) => self.set_velocity_time(value),
                 UiEvent::KeepVoiceAlive(value) => self.set_keep_voice_alive(value),
+                UiEvent::LoopMode(value) => self.set_loop_mode(value),
+                UiEvent::LoopCount(value) => self.set_loop_count(value),
+                UiEvent::LoopFade(value) => self.set_loop_fade(value),
+                UiEvent::OutputMin(value) => self.set_output_min(value),
+                UiEvent::OutputMax(value) => self.set_output_max(value),
+                UiEvent::Invert(value) => self.set_invert(value),
             }
         }
     }
@@ -5,9 +5,23 @@ use crate::{
     utils::from_ms,
 };
 
+#[derive(Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum EnvelopeLoop {
+    #[default]
+    Off,
+    AttackDecay,
+    AttackHoldDecay,
+}
+
+fn default_output_max() -> Sample {
+    1.0
+}
+
 #[derive(Clone, Serialize, Deserialize)]
 pub struct EnvelopeConfig {
     pub id: ModuleId,
+    #[serde(default)]
+    pub bypass: bool,
     pub keep_voice_alive: bool,
     pub delay: StereoSample,
     pub attack: StereoSample,
@@ -18,6 +32,22 @@ pub struct EnvelopeConfig {
     pub sustain: StereoSample,
     pub release: StereoSample,
     pub release_curvature: Sample,
+    #[serde(default)]
+    pub release_velocity: Sample,
+    #[serde(default)]
+    pub velocity_time: Sample,
+    #[serde(default)]
+    pub loop_mode: EnvelopeLoop,
+    #[serde(default)]
+    pub loop_count: usize,
+    #[serde(default)]
+    pub loop_fade: Sample,
+    #[serde(default)]
+    pub output_min: Sample,
+    #[serde(default = "default_output_max")]
+    pub output_max: Sample,
+    #[serde(default)]
+    pub invert: bool,
     pub smooth: StereoSample,
 }
 
@@ -25,6 +55,7 @@ impl Default for EnvelopeConfig {
     fn default() -> Self {
         Self {
             id: -1,
+            bypass: false,
             keep_voice_alive: false,
             delay: 0.0.into(),
             attack: 0.0.into(),
@@ -35,6 +66,14 @@ impl Default for EnvelopeConfig {
             sustain: 1.0.into(),
             release: from_ms(300.0).into(),
             release_curvature: 0.2,
+            release_velocity: 0.0,
+            velocity_time: 0.0,
+            loop_mode: EnvelopeLoop::Off,
+            loop_count: 0,
+            loop_fade: from_ms(5.0),
+            output_min: 0.0,
+            output_max: 1.0,
+            invert: false,
             smooth: 0.0.into(),
         }
     }
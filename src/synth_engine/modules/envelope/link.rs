@@ -1,4 +1,6 @@
-use crate::synth_engine::{Input, Sample, StereoSample};
+use crate::synth_engine::{Input, Sample, StereoSample, edit_sender::EditSender};
+
+use super::config::EnvelopeLoop;
 
 pub enum UiEvent {
     InputParam { input: Input, value: StereoSample },
@@ -6,16 +8,30 @@ pub enum UiEvent {
     AttackCurvature(Sample),
     DecayCurvature(Sample),
     ReleaseCurvature(Sample),
+    ReleaseVelocity(Sample),
+    VelocityTime(Sample),
     KeepVoiceAlive(bool),
+    LoopMode(EnvelopeLoop),
+    LoopCount(usize),
+    LoopFade(Sample),
+    OutputMin(Sample),
+    OutputMax(Sample),
+    Invert(bool),
 }
 
 pub struct UiEnd {
-    tx: rtrb::Producer<UiEvent>,
+    tx: EditSender<UiEvent>,
 }
 
 impl UiEnd {
     pub fn new(tx: rtrb::Producer<UiEvent>) -> Self {
-        Self { tx }
+        Self {
+            tx: EditSender::new(tx),
+        }
+    }
+
+    pub fn edits(&self) -> u32 {
+        self.tx.sent()
     }
 
     pub fn set_param(&mut self, input: Input, value: StereoSample) -> bool {
@@ -38,9 +54,41 @@ impl UiEnd {
         self.tx.push(UiEvent::ReleaseCurvature(value)).is_ok()
     }
 
+    pub fn set_release_velocity(&mut self, value: Sample) -> bool {
+        self.tx.push(UiEvent::ReleaseVelocity(value)).is_ok()
+    }
+
+    pub fn set_velocity_time(&mut self, value: Sample) -> bool {
+        self.tx.push(UiEvent::VelocityTime(value)).is_ok()
+    }
+
     pub fn set_keep_voice_alive(&mut self, value: bool) -> bool {
         self.tx.push(UiEvent::KeepVoiceAlive(value)).is_ok()
     }
+
+    pub fn set_loop_mode(&mut self, value: EnvelopeLoop) -> bool {
+        self.tx.push(UiEvent::LoopMode(value)).is_ok()
+    }
+
+    pub fn set_loop_count(&mut self, value: usize) -> bool {
+        self.tx.push(UiEvent::LoopCount(value)).is_ok()
+    }
+
+    pub fn set_loop_fade(&mut self, value: Sample) -> bool {
+        self.tx.push(UiEvent::LoopFade(value)).is_ok()
+    }
+
+    pub fn set_output_min(&mut self, value: Sample) -> bool {
+        self.tx.push(UiEvent::OutputMin(value)).is_ok()
+    }
+
+    pub fn set_output_max(&mut self, value: Sample) -> bool {
+        self.tx.push(UiEvent::OutputMax(value)).is_ok()
+    }
+
+    pub fn set_invert(&mut self, value: bool) -> bool {
+        self.tx.push(UiEvent::Invert(value)).is_ok()
+    }
 }
 
 pub struct AudioEnd {
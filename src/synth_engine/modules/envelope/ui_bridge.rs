@@ -1,7 +1,7 @@
 use crate::synth_engine::{Input, Sample, StereoSample, synth_module::ModuleUiBridge};
 
 use super::link::UiEnd;
-use super::{Envelope, EnvelopeConfig};
+use super::{Envelope, EnvelopeConfig, EnvelopeLoop};
 
 pub struct EnvelopeUiBridge {
     ui_end: UiEnd,
@@ -60,13 +60,65 @@ impl EnvelopeUiBridge {
         }
     }
 
+    pub fn set_release_velocity(&mut self, value: Sample) {
+        if self.ui_end.set_release_velocity(value) {
+            self.config.release_velocity = value;
+        }
+    }
+
+    pub fn set_velocity_time(&mut self, value: Sample) {
+        if self.ui_end.set_velocity_time(value) {
+            self.config.velocity_time = value;
+        }
+    }
+
     pub fn set_keep_voice_alive(&mut self, value: bool) {
         if self.ui_end.set_keep_voice_alive(value) {
             self.config.keep_voice_alive = value;
         }
     }
+
+    pub fn set_loop_mode(&mut self, value: EnvelopeLoop) {
+        if self.ui_end.set_loop_mode(value) {
+            self.config.loop_mode = value;
+        }
+    }
+
+    pub fn set_loop_count(&mut self, value: usize) {
+        if self.ui_end.set_loop_count(value) {
+            self.config.loop_count = value;
+        }
+    }
+
+    pub fn set_loop_fade(&mut self, value: Sample) {
+        if self.ui_end.set_loop_fade(value) {
+            self.config.loop_fade = value;
+        }
+    }
+
+    pub fn set_output_min(&mut self, value: Sample) {
+        if self.ui_end.set_output_min(value) {
+            self.config.output_min = value;
+        }
+    }
+
+    pub fn set_output_max(&mut self, value: Sample) {
+        if self.ui_end.set_output_max(value) {
+            self.config.output_max = value;
+        }
+    }
+
+    pub fn set_invert(&mut self, value: bool) {
+        if self.ui_end.set_invert(value) {
+            self.config.invert = value;
+        }
+    }
 }
 
 impl ModuleUiBridge for EnvelopeUiBridge {
     fn update(&mut self) {}
+
+    fn edits(&self) -> u32 {
+        self.ui_end.edits()
+    }
 }
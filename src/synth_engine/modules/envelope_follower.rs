@@ -0,0 +1,249 @@
+use std::array;
+
+use nih_plug::util::db_to_gain_fast;
+
+mod config;
+mod link;
+mod ui_bridge;
+
+pub use config::EnvelopeFollowerConfig;
+use link::{AudioEnd, UiEnd, UiEvent, create_link_pair};
+pub use ui_bridge::EnvelopeFollowerUiBridge;
+
+use crate::synth_engine::{
+    ModuleId, RandomParam, Randomizer, Sample,
+    buffer::{Buffer, VoicesLayout, new_voices_layout, zero_buffer},
+    routing::{
+        ControlRouterType, DataType, Input, InputMeta, InputSlots, InputSource, NUM_CHANNELS,
+        ProcessContext, SamplesOutput, SpectralInputSlot, VoiceEvent, VoiceRouter,
+    },
+    synth_module::SynthModule,
+};
+
+pub const MAX_FOLLOWER_GAIN: Sample = 24.0; // dB
+
+struct Params {
+    attack: Sample,
+    release: Sample,
+    gain: Sample,
+}
+
+impl Params {
+    fn from_config(c: &EnvelopeFollowerConfig) -> Self {
+        Self {
+            attack: c.attack.max(0.0),
+            release: c.release.max(0.0),
+            gain: c.gain.clamp(-MAX_FOLLOWER_GAIN, MAX_FOLLOWER_GAIN),
+        }
+    }
+}
+
+#[derive(Default)]
+struct VoiceState {
+    triggered: bool,
+}
+
+type Router<'v, 'f, 'c> = VoiceRouter<'v, 'f, 'c, ControlRouterType>;
+
+// Follows the level of the host sidechain input. The input is shared, so every voice gets the same
+// value and the follower keeps running while no notes are playing.
+pub struct EnvelopeFollower {
+    id: ModuleId,
+    bypass: bool,
+    params: Params,
+    level: [Sample; NUM_CHANNELS],
+    levels: [Buffer; NUM_CHANNELS],
+    audio_end: AudioEnd,
+    ui_end: Option<UiEnd>,
+    output_slot: usize,
+    voices: VoicesLayout<VoiceState>,
+}
+
+impl EnvelopeFollower {
+    pub fn new(id: ModuleId) -> Self {
+        Self::from_config(&EnvelopeFollowerConfig {
+            id,
+            ..EnvelopeFollowerConfig::default()
+        })
+    }
+
+    pub fn from_config(config: &EnvelopeFollowerConfig) -> Self {
+        let (audio_end, ui_end) = create_link_pair();
+
+        Self {
+            id: config.id,
+            bypass: config.bypass,
+            params: Params::from_config(config),
+            level: [0.0; NUM_CHANNELS],
+            levels: array::from_fn(|_| zero_buffer()),
+            audio_end,
+            ui_end: Some(ui_end),
+            output_slot: usize::MAX,
+            voices: new_voices_layout(),
+        }
+    }
+
+    pub fn get_config(&self) -> EnvelopeFollowerConfig {
+        EnvelopeFollowerConfig {
+            id: self.id,
+            bypass: self.bypass,
+            attack: self.params.attack,
+            release: self.params.release,
+            gain: self.params.gain,
+        }
+    }
+
+    pub fn randomize(
+        config: &mut EnvelopeFollowerConfig,
+        randomizer: &mut Randomizer,
+        unlocked: impl Fn(RandomParam) -> bool,
+    ) {
+        if unlocked(RandomParam::Attack) {
+            config.attack = randomizer.time(config.attack.into()).left();
+        }
+        if unlocked(RandomParam::Release) {
+            config.release = randomizer.time(config.release.into()).left();
+        }
+    }
+
+    set_mono_param!(set_attack, attack, Sample, attack.max(0.0));
+    set_mono_param!(set_release, release, Sample, release.max(0.0));
+    set_mono_param!(
+        set_gain,
+        gain,
+        Sample,
+        gain.clamp(-MAX_FOLLOWER_GAIN, MAX_FOLLOWER_GAIN)
+    );
+
+    fn coefficient(time: Sample, sample_rate: Sample) -> Sample {
+        if time > 0.0 {
+            (-1.0 / (time * sample_rate)).exp()
+        } else {
+            0.0
+        }
+    }
+
+    // The first value is where the previous block ended, the rest approach the sidechain peak
+    fn follow(
+        &mut self,
+        channel_idx: usize,
+        samples: usize,
+        sample_rate: Sample,
+        sidechain: &[Sample],
+    ) {
+        let peak = sidechain
+            .iter()
+            .fold(0.0, |peak: Sample, s| peak.max(s.abs()));
+        let target = peak * db_to_gain_fast(self.params.gain);
+        let attack = Self::coefficient(self.params.attack, sample_rate);
+        let release = Self::coefficient(self.params.release, sample_rate);
+        let level = &mut self.level[channel_idx];
+        let levels = &mut self.levels[channel_idx];
+
+        levels[0] = *level;
+
+        for out in &mut levels[1..=samples] {
+            let coefficient = if target > *level { attack } else { release };
+
+            *level = (*level - target).mul_add(coefficient, target);
+            *out = *level;
+        }
+    }
+
+    fn process_voice(
+        &mut self,
+        output_slot: &mut VoicesLayout<SamplesOutput>,
+        router: Router<'_, '_, '_>,
+    ) {
+        let channel_idx = router.channel_idx();
+        let voice_idx = router.voice_idx();
+        let voice = &mut self.voices[channel_idx][voice_idx];
+        let samples = router.samples();
+        let skip = usize::from(!voice.triggered);
+
+        output_slot[channel_idx][voice_idx]
+            .control_output(samples, voice.triggered)
+            .output()
+            .copy_from_slice(&self.levels[channel_idx][skip..=samples]);
+
+        voice.triggered = false;
+    }
+}
+
+impl SynthModule for EnvelopeFollower {
+    fn id(&self) -> ModuleId {
+        self.id
+    }
+
+    fn bypass(&self) -> bool {
+        self.bypass
+    }
+
+    fn set_bypass(&mut self, bypass: bool) {
+        self.bypass = bypass;
+    }
+
+    fn inputs(&self) -> &'static [InputMeta] {
+        &[]
+    }
+
+    fn output_type(&self) -> DataType {
+        DataType::Control
+    }
+
+    fn output_slot(&self) -> usize {
+        self.output_slot
+    }
+
+    fn set_output_slot(&mut self, slot: usize) {
+        self.output_slot = slot;
+    }
+
+    fn set_input_slots(&mut self, _inputs: &[InputSlots], _spectral_inputs: &[SpectralInputSlot]) {}
+
+    fn update_input_source(&mut self, _input_type: Input, _src_slot: usize, _source: &InputSource) {
+    }
+
+    fn process_events(&mut self, events: &[VoiceEvent]) {
+        for channel in self.voices.iter_mut() {
+            for event in events {
+                if let VoiceEvent::Trigger { voice_idx, .. } = event {
+                    channel[*voice_idx].triggered = true;
+                }
+            }
+        }
+    }
+
+    fn process_ui_events(&mut self) {
+        while let Some(event) = self.audio_end.pop_event() {
+            match event {
+                UiEvent::Attack(value) => self.set_attack(value),
+                UiEvent::Release(value) => self.set_release(value),
+                UiEvent::Gain(value) => self.set_gain(value),
+            }
+        }
+    }
+
+    fn process(&mut self, ctx: &mut ProcessContext) {
+        ctx.for_control(self.id, self.output_slot, |router, output| {
+            let num_active_voices = router.params().active_voices.len();
+
+            for channel_idx in 0..NUM_CHANNELS {
+                let params = router.params();
+
+                self.follow(
+                    channel_idx,
+                    params.samples,
+                    params.sample_rate,
+                    params.sidechain[channel_idx],
+                );
+
+                for seq_idx in 0..num_active_voices {
+                    let voice_idx = router.params().active_voices[seq_idx];
+
+                    self.process_voice(output, router.for_voice(channel_idx, voice_idx, seq_idx));
+                }
+            }
+        });
+    }
+}
@@ -0,0 +1,28 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    synth_engine::{ModuleId, Sample},
+    utils::from_ms,
+};
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct EnvelopeFollowerConfig {
+    pub id: ModuleId,
+    #[serde(default)]
+    pub bypass: bool,
+    pub attack: Sample,
+    pub release: Sample,
+    pub gain: Sample, // dB
+}
+
+impl Default for EnvelopeFollowerConfig {
+    fn default() -> Self {
+        Self {
+            id: -1,
+            bypass: false,
+            attack: from_ms(10.0),
+            release: from_ms(150.0),
+            gain: 0.0,
+        }
+    }
+}
@@ -0,0 +1,48 @@
+use crate::synth_engine::{Sample, synth_module::ModuleUiBridge};
+
+use super::link::UiEnd;
+use super::{EnvelopeFollower, EnvelopeFollowerConfig};
+
+pub struct EnvelopeFollowerUiBridge {
+    ui_end: UiEnd,
+    config: EnvelopeFollowerConfig,
+}
+
+impl EnvelopeFollowerUiBridge {
+    pub fn try_new(follower: &mut EnvelopeFollower) -> Option<Self> {
+        Some(Self {
+            ui_end: follower.ui_end.take()?,
+            config: follower.get_config(),
+        })
+    }
+
+    pub fn config(&self) -> &EnvelopeFollowerConfig {
+        &self.config
+    }
+
+    pub fn set_attack(&mut self, value: Sample) {
+        if self.ui_end.set_attack(value) {
+            self.config.attack = value;
+        }
+    }
+
+    pub fn set_release(&mut self, value: Sample) {
+        if self.ui_end.set_release(value) {
+            self.config.release = value;
+        }
+    }
+
+    pub fn set_gain(&mut self, value: Sample) {
+        if self.ui_end.set_gain(value) {
+            self.config.gain = value;
+        }
+    }
+}
+
+impl ModuleUiBridge for EnvelopeFollowerUiBridge {
+    fn update(&mut self) {}
+
+    fn edits(&self) -> u32 {
+        self.ui_end.edits()
+    }
+}
@@ -8,11 +8,12 @@ pub use ui_bridge::ExpressionsUiBridge;
 
 use crate::{
     synth_engine::{
-        Expression, Input, ModuleId, Sample, StereoSample,
+        Expression, Input, ModuleId, RandomParam, Randomizer, Sample,
         buffer::{VoicesLayout, new_voices_layout},
+        curves::{CurveFunction, Exponential},
         routing::{
-            ControlRouterType, DataType, InputMeta, InputSlots, NUM_CHANNELS, ProcessContext,
-            SamplesOutput, SpectralInputSlot, VoiceEvent, VoiceRouter,
+            ControlRouterType, DataType, InputMeta, InputSlots, InputSource, NUM_CHANNELS,
+            ProcessContext, SamplesOutput, SpectralInputSlot, VoiceEvent, VoiceRouter,
         },
         smooth::Smoother,
         synth_module::SynthModule,
@@ -23,6 +24,7 @@ use crate::{
 struct Params {
     expression: Expression,
     use_release_velocity: bool,
+    velocity_curve: Sample,
     smooth: Sample,
 }
 
@@ -31,6 +33,7 @@ impl Params {
         Self {
             expression: c.expression,
             use_release_velocity: c.use_release_velocity,
+            velocity_curve: c.velocity_curve,
             smooth: c.smooth,
         }
     }
@@ -56,6 +59,7 @@ type Router<'v, 'f, 'c> = VoiceRouter<'v, 'f, 'c, ControlRouterType>;
 
 pub struct Expressions {
     id: ModuleId,
+    bypass: bool,
     params: Params,
     audio_end: AudioEnd,
     ui_end: Option<UiEnd>,
@@ -76,6 +80,7 @@ impl Expressions {
 
         Self {
             id: config.id,
+            bypass: config.bypass,
             params: Params::from_config(config),
             audio_end,
             ui_end: Some(ui_end),
@@ -87,16 +92,36 @@ impl Expressions {
     pub fn get_config(&self) -> ExpressionsConfig {
         ExpressionsConfig {
             id: self.id,
+            bypass: self.bypass,
             expression: self.params.expression,
             use_release_velocity: self.params.use_release_velocity,
+            velocity_curve: self.params.velocity_curve,
             smooth: self.params.smooth,
         }
     }
 
+    // Controls follow the player, nothing to randomize
+    pub fn randomize(
+        _config: &mut ExpressionsConfig,
+        _randomizer: &mut Randomizer,
+        _unlocked: impl Fn(RandomParam) -> bool,
+    ) {
+    }
+
     set_mono_param!(set_expression, expression, Expression);
     set_mono_param!(set_use_release_velocity, use_release_velocity, bool);
+    set_mono_param!(
+        set_velocity_curve,
+        velocity_curve,
+        Sample,
+        velocity_curve.clamp(-1.0, 1.0)
+    );
     set_mono_param!(set_smooth, smooth, Sample);
 
+    fn shape_velocity(params: &Params, velocity: Sample) -> Sample {
+        Exponential::new(params.velocity_curve).calc(velocity.clamp(0.0, 1.0))
+    }
+
     fn transform_value(expression: Expression, channel_idx: usize, value: Sample) -> Sample {
         match expression {
             Expression::Pitch => st_to_octave(value),
@@ -127,6 +152,8 @@ impl Expressions {
         velocity: Sample,
     ) {
         if matches!(params.expression, Expression::Velocity) {
+            let velocity = Self::shape_velocity(params, velocity);
+
             voice.value = velocity;
             voice.smoother.reset(velocity);
             voice.triggered = false;
@@ -147,7 +174,7 @@ impl Expressions {
         velocity: Sample,
     ) {
         if matches!(params.expression, Expression::Velocity) {
-            voice.value = velocity;
+            voice.value = Self::shape_velocity(params, velocity);
         } else {
             let default_value = Self::default_value(params.expression);
             let value = Self::transform_value(params.expression, channel_idx, default_value);
@@ -158,7 +185,7 @@ impl Expressions {
 
     fn handle_release(voice: &mut VoiceState, params: &Params, velocity: Sample) {
         if matches!(params.expression, Expression::Velocity) && params.use_release_velocity {
-            voice.value = velocity;
+            voice.value = Self::shape_velocity(params, velocity);
         }
     }
 
@@ -213,6 +240,14 @@ impl SynthModule for Expressions {
         self.id
     }
 
+    fn bypass(&self) -> bool {
+        self.bypass
+    }
+
+    fn set_bypass(&mut self, bypass: bool) {
+        self.bypass = bypass;
+    }
+
     fn inputs(&self) -> &'static [InputMeta] {
         &[]
     }
@@ -231,7 +266,7 @@ impl SynthModule for Expressions {
 
     fn set_input_slots(&mut self, _inputs: &[InputSlots], _spectral_inputs: &[SpectralInputSlot]) {}
 
-    fn update_input_amount(&mut self, _input_type: Input, _src_slot: usize, _amount: StereoSample) {
+    fn update_input_source(&mut self, _input_type: Input, _src_slot: usize, _source: &InputSource) {
     }
 
     fn process_events(&mut self, events: &[VoiceEvent]) {
@@ -291,6 +326,7 @@ impl SynthModule for Expressions {
             match event {
                 UiEvent::Expression(expression) => self.set_expression(expression),
                 UiEvent::UseReleaseVelocity(value) => self.set_use_release_velocity(value),
+                UiEvent::VelocityCurve(value) => self.set_velocity_curve(value),
                 UiEvent::Smooth(value) => self.set_smooth(value),
             }
         }
@@ -8,8 +8,12 @@ use crate::{
 #[derive(Clone, Serialize, Deserialize)]
 pub struct ExpressionsConfig {
     pub id: ModuleId,
+    #[serde(default)]
+    pub bypass: bool,
     pub expression: Expression,
     pub use_release_velocity: bool,
+    #[serde(default)]
+    pub velocity_curve: Sample,
     pub smooth: Sample,
 }
 
@@ -17,8 +21,10 @@ impl Default for ExpressionsConfig {
     fn default() -> Self {
         Self {
             id: -1,
+            bypass: false,
             expression: Expression::Velocity,
             use_release_velocity: false,
+            velocity_curve: 0.0,
             smooth: from_ms(4.0),
         }
     }
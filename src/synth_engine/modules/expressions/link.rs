@@ -1,18 +1,25 @@
-use crate::synth_engine::{Expression, Sample};
+use crate::synth_engine::{Expression, Sample, edit_sender::EditSender};
 
 pub enum UiEvent {
     Expression(Expression),
     UseReleaseVelocity(bool),
+    VelocityCurve(Sample),
     Smooth(Sample),
 }
 
 pub struct UiEnd {
-    tx: rtrb::Producer<UiEvent>,
+    tx: EditSender<UiEvent>,
 }
 
 impl UiEnd {
     pub fn new(tx: rtrb::Producer<UiEvent>) -> Self {
-        Self { tx }
+        Self {
+            tx: EditSender::new(tx),
+        }
+    }
+
+    pub fn edits(&self) -> u32 {
+        self.tx.sent()
     }
 
     pub fn set_expression(&mut self, expression: Expression) -> bool {
@@ -23,6 +30,10 @@ impl UiEnd {
         self.tx.push(UiEvent::UseReleaseVelocity(value)).is_ok()
     }
 
+    pub fn set_velocity_curve(&mut self, value: Sample) -> bool {
+        self.tx.push(UiEvent::VelocityCurve(value)).is_ok()
+    }
+
     pub fn set_smooth(&mut self, value: Sample) -> bool {
         self.tx.push(UiEvent::Smooth(value)).is_ok()
     }
@@ -32,6 +32,12 @@ impl ExpressionsUiBridge {
         }
     }
 
+    pub fn set_velocity_curve(&mut self, value: Sample) {
+        if self.ui_end.set_velocity_curve(value) {
+            self.config.velocity_curve = value.clamp(-1.0, 1.0);
+        }
+    }
+
     pub fn set_smooth(&mut self, value: Sample) {
         if self.ui_end.set_smooth(value) {
             self.config.smooth = value;
@@ -41,4 +47,8 @@ impl ExpressionsUiBridge {
 
 impl ModuleUiBridge for ExpressionsUiBridge {
     fn update(&mut self) {}
+
+    fn edits(&self) -> u32 {
+        self.ui_end.edits()
+    }
 }
@@ -1,6 +1,7 @@
 use std::sync::Arc;
 
-use nih_plug::params::FloatParam;
+use nih_plug::params::{FloatParam, IntParam};
+use parking_lot::Mutex;
 
 mod config;
 mod link;
@@ -11,26 +12,63 @@ use link::{AudioEnd, UiEnd, UiEvent, create_link_pair};
 pub use ui_bridge::ExternalParamUiBridge;
 
 use crate::synth_engine::{
-    ModuleId, Sample, StereoSample,
+    ModuleId, RandomParam, Randomizer, Sample,
     buffer::{VoicesLayout, new_voices_layout},
+    curves::{CurveFunction, Exponential},
     routing::{
-        ControlRouterType, DataType, Input, InputMeta, InputSlots, NUM_CHANNELS, ProcessContext,
-        SamplesOutput, SpectralInputSlot, VoiceEvent, VoiceRouter,
+        ControlRouterType, DataType, Input, InputMeta, InputSlots, InputSource, NUM_CHANNELS,
+        ProcessContext, SamplesOutput, SpectralInputSlot, VoiceEvent, VoiceRouter,
     },
     smooth::Smoother,
     synth_module::SynthModule,
 };
 
-pub const NUM_FLOAT_PARAMS: usize = 4;
+pub const NUM_FLOAT_PARAMS: usize = 16;
+pub const NUM_AUTOMATION_PARAMS: usize = 64;
+
+// User names of the float params. Host parameter names are fixed, the host shows the name as part
+// of the value text instead.
+pub type FloatParamNames = [Mutex<String>; NUM_FLOAT_PARAMS];
 
 pub struct ExternalParamsBlock {
     pub float_params: [Arc<FloatParam>; NUM_FLOAT_PARAMS],
+    pub float_param_names: Arc<FloatParamNames>,
+    pub automation_params: [Arc<FloatParam>; NUM_AUTOMATION_PARAMS],
+    // Added to the master tune (cents) and transpose (semitones) of the patch
+    pub master_tune: Arc<FloatParam>,
+    pub transpose: Arc<IntParam>,
+}
+
+impl ExternalParamsBlock {
+    pub fn float_param_names(&self) -> Vec<String> {
+        self.float_param_names
+            .iter()
+            .map(|name| name.lock().clone())
+            .collect()
+    }
+
+    pub fn set_float_param_names(&self, names: &[String]) {
+        for (idx, name) in self.float_param_names.iter().enumerate() {
+            *name.lock() = names.get(idx).cloned().unwrap_or_default();
+        }
+    }
+
+    pub fn set_float_param_name(&self, idx: usize, name: &str) {
+        if let Some(slot) = self.float_param_names.get(idx) {
+            *slot.lock() = name.to_string();
+        }
+    }
 }
 
 struct Params {
     selected_param_index: usize,
     smooth: Sample,
     sample_and_hold: bool,
+    midi_cc: Option<u8>,
+    output_min: Sample,
+    output_max: Sample,
+    curve: Sample,
+    bipolar: bool,
 }
 
 impl Params {
@@ -39,6 +77,28 @@ impl Params {
             selected_param_index: c.selected_param_index.min(NUM_FLOAT_PARAMS - 1),
             smooth: c.smooth,
             sample_and_hold: c.sample_and_hold,
+            midi_cc: c.midi_cc,
+            output_min: c.output_min,
+            output_max: c.output_max,
+            curve: c.curve,
+            bipolar: c.bipolar,
+        }
+    }
+
+    // Maps the 0..1 host value onto the output range. Bipolar bends both halves away from the
+    // center, so the middle of the knob stays in the middle of the range.
+    fn map(&self, value: Sample) -> Sample {
+        let curve = Exponential::new(self.curve);
+        let value = value.clamp(0.0, 1.0);
+        let range = self.output_max - self.output_min;
+
+        if self.bipolar {
+            let centered = 2.0 * value - 1.0;
+            let shaped = curve.calc(centered.abs()).copysign(centered);
+
+            (0.5 * range).mul_add(shaped + 1.0, self.output_min)
+        } else {
+            range.mul_add(curve.calc(value), self.output_min)
         }
     }
 }
@@ -63,8 +123,10 @@ type Router<'v, 'f, 'c> = VoiceRouter<'v, 'f, 'c, ControlRouterType>;
 
 pub struct ExternalParam {
     id: ModuleId,
+    bypass: bool,
     params_block: Arc<ExternalParamsBlock>,
     params: Params,
+    cc_value: Sample,
     audio_end: AudioEnd,
     ui_end: Option<UiEnd>,
     output_slot: usize,
@@ -90,8 +152,10 @@ impl ExternalParam {
 
         Self {
             id: config.id,
+            bypass: config.bypass,
             params_block,
             params: Params::from_config(config),
+            cc_value: 0.0,
             audio_end,
             ui_end: Some(ui_end),
             output_slot: usize::MAX,
@@ -102,12 +166,26 @@ impl ExternalParam {
     pub fn get_config(&self) -> ExternalParamConfig {
         ExternalParamConfig {
             id: self.id,
+            bypass: self.bypass,
             selected_param_index: self.params.selected_param_index,
             smooth: self.params.smooth,
             sample_and_hold: self.params.sample_and_hold,
+            midi_cc: self.params.midi_cc,
+            output_min: self.params.output_min,
+            output_max: self.params.output_max,
+            curve: self.params.curve,
+            bipolar: self.params.bipolar,
         }
     }
 
+    // Controls follow the host, nothing to randomize
+    pub fn randomize(
+        _config: &mut ExternalParamConfig,
+        _randomizer: &mut Randomizer,
+        _unlocked: impl Fn(RandomParam) -> bool,
+    ) {
+    }
+
     set_mono_param!(
         select_param,
         selected_param_index,
@@ -116,6 +194,20 @@ impl ExternalParam {
     );
     set_mono_param!(set_smooth, smooth, Sample);
     set_mono_param!(set_sample_and_hold, sample_and_hold, bool);
+    set_mono_param!(set_midi_cc, midi_cc, Option<u8>);
+    set_mono_param!(set_output_min, output_min, Sample);
+    set_mono_param!(set_output_max, output_max, Sample);
+    set_mono_param!(set_curve, curve, Sample);
+    set_mono_param!(set_bipolar, bipolar, bool);
+
+    fn param_value(&self) -> Sample {
+        let value = match self.params.midi_cc {
+            Some(_) => self.cc_value,
+            None => self.params_block.float_params[self.params.selected_param_index].value(),
+        };
+
+        self.params.map(value)
+    }
 
     fn process_voice(
         &mut self,
@@ -124,6 +216,7 @@ impl ExternalParam {
     ) {
         let channel_idx = router.channel_idx();
         let voice_idx = router.voice_idx();
+        let current_value = self.param_value();
         let voice = &mut self.voices[channel_idx][voice_idx];
         let samples = router.samples();
         let voice_output = &mut output_slot[channel_idx][voice_idx];
@@ -131,7 +224,7 @@ impl ExternalParam {
         let param_value = if self.params.sample_and_hold {
             voice.value_at_trigger
         } else {
-            self.params_block.float_params[self.params.selected_param_index].value()
+            current_value
         };
 
         let mut control_output = voice_output.control_output(samples, voice.triggered);
@@ -154,6 +247,14 @@ impl SynthModule for ExternalParam {
         self.id
     }
 
+    fn bypass(&self) -> bool {
+        self.bypass
+    }
+
+    fn set_bypass(&mut self, bypass: bool) {
+        self.bypass = bypass;
+    }
+
     fn inputs(&self) -> &'static [InputMeta] {
         &[]
     }
@@ -172,15 +273,15 @@ impl SynthModule for ExternalParam {
 
     fn set_input_slots(&mut self, _inputs: &[InputSlots], _spectral_inputs: &[SpectralInputSlot]) {}
 
-    fn update_input_amount(&mut self, _input_type: Input, _src_slot: usize, _amount: StereoSample) {
+    fn update_input_source(&mut self, _input_type: Input, _src_slot: usize, _source: &InputSource) {
     }
 
     fn process_events(&mut self, events: &[VoiceEvent]) {
+        let param_value = self.param_value();
+
         for channel in self.voices.iter_mut() {
             for event in events {
                 if let VoiceEvent::Trigger { voice_idx, .. } = event {
-                    let param_value =
-                        self.params_block.float_params[self.params.selected_param_index].value();
                     let voice = &mut channel[*voice_idx];
 
                     voice.triggered = true;
@@ -197,6 +298,11 @@ impl SynthModule for ExternalParam {
                 UiEvent::SelectedParamIndex(index) => self.select_param(index),
                 UiEvent::Smooth(value) => self.set_smooth(value),
                 UiEvent::SampleAndHold(value) => self.set_sample_and_hold(value),
+                UiEvent::MidiCc(cc) => self.set_midi_cc(cc),
+                UiEvent::OutputMin(value) => self.set_output_min(value),
+                UiEvent::OutputMax(value) => self.set_output_max(value),
+                UiEvent::Curve(value) => self.set_curve(value),
+                UiEvent::Bipolar(value) => self.set_bipolar(value),
             }
         }
     }
@@ -205,6 +311,10 @@ impl SynthModule for ExternalParam {
         ctx.for_control(self.id, self.output_slot, |router, output| {
             let num_active_voices = router.params().active_voices.len();
 
+            if let Some(cc) = self.params.midi_cc {
+                self.cc_value = router.params().midi_state.cc(cc);
+            }
+
             for channel_idx in 0..NUM_CHANNELS {
                 for seq_idx in 0..num_active_voices {
                     let voice_idx = router.params().active_voices[seq_idx];
@@ -215,3 +325,39 @@ impl SynthModule for ExternalParam {
         });
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params(curve: Sample, bipolar: bool) -> Params {
+        Params::from_config(&ExternalParamConfig {
+            output_min: -12.0,
+            output_max: 24.0,
+            curve,
+            bipolar,
+            ..ExternalParamConfig::default()
+        })
+    }
+
+    #[test]
+    fn maps_host_value_onto_output_range() {
+        let linear = params(0.0, false);
+
+        assert_eq!(linear.map(0.0), -12.0);
+        assert_eq!(linear.map(0.5), 6.0);
+        assert_eq!(linear.map(1.0), 24.0);
+        assert!(params(0.5, false).map(0.5) > 6.0);
+    }
+
+    #[test]
+    fn bipolar_curve_keeps_the_center() {
+        let bipolar = params(0.5, true);
+
+        assert_eq!(bipolar.map(0.0), -12.0);
+        assert_eq!(bipolar.map(0.5), 6.0);
+        assert_eq!(bipolar.map(1.0), 24.0);
+        assert!((bipolar.map(0.75) - 6.0 + bipolar.map(0.25) - 6.0).abs() < 1e-4);
+        assert!(bipolar.map(0.75) > params(0.0, true).map(0.75));
+    }
+}
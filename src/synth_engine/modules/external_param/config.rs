@@ -5,21 +5,43 @@ use crate::{
     utils::from_ms,
 };
 
+fn default_output_max() -> Sample {
+    1.0
+}
+
 #[derive(Clone, Serialize, Deserialize)]
 pub struct ExternalParamConfig {
     pub id: ModuleId,
+    #[serde(default)]
+    pub bypass: bool,
     pub selected_param_index: usize,
     pub smooth: Sample,
     pub sample_and_hold: bool,
+    #[serde(default)]
+    pub midi_cc: Option<u8>, // Follows the CC instead of the host parameter
+    #[serde(default)]
+    pub output_min: Sample,
+    #[serde(default = "default_output_max")]
+    pub output_max: Sample,
+    #[serde(default)]
+    pub curve: Sample, // Curvature -1..1
+    #[serde(default)]
+    pub bipolar: bool, // The middle of the host range maps to the middle of the output range
 }
 
 impl Default for ExternalParamConfig {
     fn default() -> Self {
         Self {
             id: -1,
+            bypass: false,
             selected_param_index: 0,
             smooth: from_ms(2.0),
             sample_and_hold: false,
+            midi_cc: None,
+            output_min: 0.0,
+            output_max: 1.0,
+            curve: 0.0,
+            bipolar: false,
         }
     }
 }
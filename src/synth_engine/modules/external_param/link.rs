@@ -1,18 +1,29 @@
-use crate::synth_engine::Sample;
+use crate::synth_engine::{Sample, edit_sender::EditSender};
 
 pub enum UiEvent {
     SelectedParamIndex(usize),
     Smooth(Sample),
     SampleAndHold(bool),
+    MidiCc(Option<u8>),
+    OutputMin(Sample),
+    OutputMax(Sample),
+    Curve(Sample),
+    Bipolar(bool),
 }
 
 pub struct UiEnd {
-    tx: rtrb::Producer<UiEvent>,
+    tx: EditSender<UiEvent>,
 }
 
 impl UiEnd {
     pub fn new(tx: rtrb::Producer<UiEvent>) -> Self {
-        Self { tx }
+        Self {
+            tx: EditSender::new(tx),
+        }
+    }
+
+    pub fn edits(&self) -> u32 {
+        self.tx.sent()
     }
 
     pub fn select_param(&mut self, index: usize) -> bool {
@@ -26,6 +37,26 @@ impl UiEnd {
     pub fn set_sample_and_hold(&mut self, value: bool) -> bool {
         self.tx.push(UiEvent::SampleAndHold(value)).is_ok()
     }
+
+    pub fn set_midi_cc(&mut self, cc: Option<u8>) -> bool {
+        self.tx.push(UiEvent::MidiCc(cc)).is_ok()
+    }
+
+    pub fn set_output_min(&mut self, value: Sample) -> bool {
+        self.tx.push(UiEvent::OutputMin(value)).is_ok()
+    }
+
+    pub fn set_output_max(&mut self, value: Sample) -> bool {
+        self.tx.push(UiEvent::OutputMax(value)).is_ok()
+    }
+
+    pub fn set_curve(&mut self, value: Sample) -> bool {
+        self.tx.push(UiEvent::Curve(value)).is_ok()
+    }
+
+    pub fn set_bipolar(&mut self, value: bool) -> bool {
+        self.tx.push(UiEvent::Bipolar(value)).is_ok()
+    }
 }
 
 pub struct AudioEnd {
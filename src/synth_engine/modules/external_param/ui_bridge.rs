@@ -37,8 +37,42 @@ impl ExternalParamUiBridge {
             self.config.sample_and_hold = value;
         }
     }
+
+    pub fn set_midi_cc(&mut self, cc: Option<u8>) {
+        if self.ui_end.set_midi_cc(cc) {
+            self.config.midi_cc = cc;
+        }
+    }
+
+    pub fn set_output_min(&mut self, value: Sample) {
+        if self.ui_end.set_output_min(value) {
+            self.config.output_min = value;
+        }
+    }
+
+    pub fn set_output_max(&mut self, value: Sample) {
+        if self.ui_end.set_output_max(value) {
+            self.config.output_max = value;
+        }
+    }
+
+    pub fn set_curve(&mut self, value: Sample) {
+        if self.ui_end.set_curve(value) {
+            self.config.curve = value;
+        }
+    }
+
+    pub fn set_bipolar(&mut self, value: bool) {
+        if self.ui_end.set_bipolar(value) {
+            self.config.bipolar = value;
+        }
+    }
 }
 
 impl ModuleUiBridge for ExternalParamUiBridge {
     fn update(&mut self) {}
+
+    fn edits(&self) -> u32 {
+        self.ui_end.edits()
+    }
 }
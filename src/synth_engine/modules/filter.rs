@@ -0,0 +1,368 @@
+use std::{array, f32::consts::PI};
+
+use itertools::izip;
+use nih_plug::util::db_to_gain_fast;
+
+mod config;
+mod link;
+mod ui_bridge;
+
+pub use config::{FilterConfig, FilterType};
+use link::{AudioEnd, UiEnd, UiEvent, create_link_pair};
+pub use ui_bridge::FilterUiBridge;
+
+use crate::{
+    synth_engine::{
+        RandomParam, Randomizer, Sample, StereoSample,
+        buffer::{Buffer, VoicesLayout, new_voices_layout, zero_buffer},
+        routing::{
+            AudioRouterType, DataType, Input, InputMeta, InputSlots, InputSource, ModuleId,
+            NUM_CHANNELS, ProcessContext, SamplesOutput, SpectralInputSlot, VoiceEvent,
+            VoiceRouter,
+        },
+        smooth::SmoothedSample,
+        synth_module::SynthModule,
+    },
+    utils::pitch_to_freq,
+};
+
+const MIN_CUTOFF_FREQ: Sample = 20.0;
+const MAX_DRIVE: Sample = 24.0; // dB
+
+struct Params {
+    filter_type: FilterType,
+    keytracking: Sample,
+}
+
+impl Params {
+    fn from_config(c: &FilterConfig) -> Self {
+        Self {
+            filter_type: c.filter_type,
+            keytracking: c.keytracking.clamp(0.0, 1.0),
+        }
+    }
+}
+
+struct ChannelParams {
+    cutoff: SmoothedSample,
+    q: SmoothedSample,
+    drive: SmoothedSample,
+}
+
+impl ChannelParams {
+    fn from_config(c: &FilterConfig, channel_idx: usize) -> Self {
+        Self {
+            cutoff: c.cutoff[channel_idx].into(),
+            q: c.q[channel_idx].into(),
+            drive: c.drive[channel_idx].into(),
+        }
+    }
+}
+
+// Integrator states of the topology preserving transform SVF
+#[derive(Default)]
+struct VoiceState {
+    pitch: Sample,
+    ic1eq: Sample,
+    ic2eq: Sample,
+}
+
+pub struct Inputs {
+    audio: Option<usize>,
+    cutoff: InputSlots,
+    q: InputSlots,
+    drive: InputSlots,
+}
+
+impl Default for Inputs {
+    fn default() -> Self {
+        Self {
+            audio: None,
+            cutoff: InputSlots::empty(Input::Cutoff),
+            q: InputSlots::empty(Input::Q),
+            drive: InputSlots::empty(Input::Drive),
+        }
+    }
+}
+
+impl Inputs {
+    fn from_slots(inputs: &[InputSlots], _spectral_inputs: &[SpectralInputSlot]) -> Self {
+        let mut result = Self::default();
+
+        for input in inputs {
+            match input.input_type {
+                Input::Audio => result.audio = input.slots.first().map(|s| s.src_slot),
+                Input::Cutoff => result.cutoff = input.clone(),
+                Input::Q => result.q = input.clone(),
+                Input::Drive => result.drive = input.clone(),
+                _ => (),
+            }
+        }
+
+        result
+    }
+
+    fn update_source(&mut self, input_type: Input, src_slot: usize, source: &InputSource) {
+        match input_type {
+            Input::Cutoff => self.cutoff.update_source(src_slot, source),
+            Input::Q => self.q.update_source(src_slot, source),
+            Input::Drive => self.drive.update_source(src_slot, source),
+            _ => (),
+        }
+    }
+}
+
+type Router<'v, 'f, 'c> = VoiceRouter<'v, 'f, 'c, AudioRouterType>;
+
+struct Buffers {
+    cutoff_mod_input: Buffer,
+    q_mod_input: Buffer,
+    drive_mod_input: Buffer,
+}
+
+pub struct Filter {
+    id: ModuleId,
+    bypass: bool,
+    params: Params,
+    channel_params: [ChannelParams; NUM_CHANNELS],
+    buffers: Buffers,
+    audio_end: AudioEnd,
+    ui_end: Option<UiEnd>,
+    inputs: Inputs,
+    output_slot: usize,
+    voices: VoicesLayout<VoiceState>,
+}
+
+impl Filter {
+    pub fn new(id: ModuleId) -> Self {
+        Self::from_config(&FilterConfig {
+            id,
+            ..FilterConfig::default()
+        })
+    }
+
+    pub fn from_config(config: &FilterConfig) -> Self {
+        let (audio_end, ui_end) = create_link_pair();
+
+        Self {
+            id: config.id,
+            bypass: config.bypass,
+            params: Params::from_config(config),
+            channel_params: array::from_fn(|channel_idx| {
+                ChannelParams::from_config(config, channel_idx)
+            }),
+            buffers: Buffers {
+                cutoff_mod_input: zero_buffer(),
+                q_mod_input: zero_buffer(),
+                drive_mod_input: zero_buffer(),
+            },
+            audio_end,
+            ui_end: Some(ui_end),
+            inputs: Inputs::default(),
+            output_slot: usize::MAX,
+            voices: new_voices_layout(),
+        }
+    }
+
+    pub fn get_config(&self) -> FilterConfig {
+        FilterConfig {
+            id: self.id,
+            bypass: self.bypass,
+            filter_type: self.params.filter_type,
+            keytracking: self.params.keytracking,
+            cutoff: get_smoothed_param!(self, cutoff),
+            q: get_smoothed_param!(self, q),
+            drive: get_smoothed_param!(self, drive),
+        }
+    }
+
+    pub fn randomize(
+        config: &mut FilterConfig,
+        randomizer: &mut Randomizer,
+        unlocked: impl Fn(RandomParam) -> bool,
+    ) {
+        if unlocked(RandomParam::Cutoff) {
+            config.cutoff = randomizer.stereo(config.cutoff, 0.0, 8.0);
+        }
+        if unlocked(RandomParam::Resonance) {
+            config.q = randomizer.log(config.q, 0.5, 5.0);
+        }
+    }
+
+    set_mono_param!(set_filter_type, filter_type, FilterType);
+    set_mono_param!(
+        set_keytracking,
+        keytracking,
+        Sample,
+        keytracking.clamp(0.0, 1.0)
+    );
+
+    set_smoothed_param!(set_cutoff, cutoff);
+    set_smoothed_param!(set_q, q);
+    set_smoothed_param!(set_drive, drive);
+
+    fn process_voice(
+        &mut self,
+        output: &mut VoicesLayout<SamplesOutput>,
+        mut router: Router<'_, '_, '_>,
+    ) {
+        let channel_idx = router.channel_idx();
+        let voice_idx = router.voice_idx();
+        let sample_rate = router.sample_rate();
+        let max_cutoff_freq = 0.49 * sample_rate;
+        let filter_type = self.params.filter_type;
+        let inputs = &self.inputs;
+        let buffers = &mut self.buffers;
+        let channel = &mut self.channel_params[channel_idx];
+        let voice = &mut self.voices[channel_idx][voice_idx];
+        let output = output[channel_idx][voice_idx].output(router.samples());
+
+        router.buff_param(
+            &inputs.cutoff,
+            &mut channel.cutoff,
+            &mut buffers.cutoff_mod_input,
+        );
+        router.buff_param(&inputs.q, &mut channel.q, &mut buffers.q_mod_input);
+        router.buff_param(
+            &inputs.drive,
+            &mut channel.drive,
+            &mut buffers.drive_mod_input,
+        );
+
+        // Cutoff is in octaves, relative to the played note with full keytracking and to A4 without
+        let pitch_offset = self.params.keytracking * voice.pitch;
+
+        for (out, input, cutoff, q, drive) in izip!(
+            output,
+            router.buff(inputs.audio),
+            &buffers.cutoff_mod_input,
+            &buffers.q_mod_input,
+            &buffers.drive_mod_input
+        ) {
+            let freq = pitch_to_freq(pitch_offset + cutoff.clamp(-4.0, 10.0))
+                .clamp(MIN_CUTOFF_FREQ, max_cutoff_freq);
+            let g = (PI * freq / sample_rate).tan();
+            let k = q.clamp(0.1, 10.0).recip();
+            let a1 = (1.0 + g * (g + k)).recip();
+            let a2 = g * a1;
+            let a3 = g * a2;
+
+            let v0 = (input * db_to_gain_fast(drive.min(MAX_DRIVE))).tanh();
+            let v3 = v0 - voice.ic2eq;
+            let v1 = a1 * voice.ic1eq + a2 * v3;
+            let v2 = voice.ic2eq + a2 * voice.ic1eq + a3 * v3;
+
+            voice.ic1eq = 2.0 * v1 - voice.ic1eq;
+            voice.ic2eq = 2.0 * v2 - voice.ic2eq;
+
+            *out = match filter_type {
+                FilterType::LowPass => v2,
+                FilterType::HighPass => v0 - k * v1 - v2,
+                FilterType::BandPass => v1,
+                FilterType::Notch => v0 - k * v1,
+            };
+        }
+    }
+}
+
+impl SynthModule for Filter {
+    fn id(&self) -> ModuleId {
+        self.id
+    }
+
+    fn bypass(&self) -> bool {
+        self.bypass
+    }
+
+    fn set_bypass(&mut self, bypass: bool) {
+        self.bypass = bypass;
+    }
+
+    fn inputs(&self) -> &'static [InputMeta] {
+        static INPUTS: &[InputMeta] = &[
+            InputMeta::audio(Input::Audio),
+            InputMeta::control(Input::Cutoff),
+            InputMeta::control(Input::Q),
+            InputMeta::control(Input::Drive),
+        ];
+
+        INPUTS
+    }
+
+    fn output_type(&self) -> DataType {
+        DataType::Audio
+    }
+
+    fn output_slot(&self) -> usize {
+        self.output_slot
+    }
+
+    fn set_output_slot(&mut self, slot: usize) {
+        self.output_slot = slot;
+    }
+
+    fn set_input_slots(&mut self, inputs: &[InputSlots], spectral_inputs: &[SpectralInputSlot]) {
+        self.inputs = Inputs::from_slots(inputs, spectral_inputs);
+    }
+
+    fn process_events(&mut self, events: &[VoiceEvent]) {
+        for channel in self.voices.iter_mut() {
+            for event in events {
+                match event {
+                    VoiceEvent::Trigger {
+                        voice_idx, pitch, ..
+                    } => {
+                        channel[*voice_idx] = VoiceState {
+                            pitch: *pitch,
+                            ..VoiceState::default()
+                        };
+                    }
+                    VoiceEvent::Update {
+                        voice_idx, pitch, ..
+                    }
+                    | VoiceEvent::Retune { voice_idx, pitch } => {
+                        channel[*voice_idx].pitch = *pitch;
+                    }
+                    _ => (),
+                }
+            }
+        }
+    }
+
+    fn update_input_source(&mut self, input_type: Input, src_slot: usize, source: &InputSource) {
+        self.inputs.update_source(input_type, src_slot, source);
+    }
+
+    fn set_input_param(&mut self, input: Input, value: StereoSample) {
+        match input {
+            Input::Cutoff => self.set_cutoff(value),
+            Input::Q => self.set_q(value),
+            Input::Drive => self.set_drive(value),
+            _ => (),
+        }
+    }
+
+    fn process_ui_events(&mut self) {
+        while let Some(event) = self.audio_end.pop_event() {
+            match event {
+                UiEvent::InputParam { input, value } => self.set_input_param(input, value),
+                UiEvent::FilterType(filter_type) => self.set_filter_type(filter_type),
+                UiEvent::Keytracking(keytracking) => self.set_keytracking(keytracking),
+            }
+        }
+    }
+
+    fn process(&mut self, ctx: &mut ProcessContext) {
+        ctx.for_audio(self.id, self.output_slot, |router, output| {
+            let num_active_voices = router.params().active_voices.len();
+
+            for channel_idx in 0..NUM_CHANNELS {
+                for seq_idx in 0..num_active_voices {
+                    let voice_idx = router.params().active_voices[seq_idx];
+
+                    self.process_voice(output, router.for_voice(channel_idx, voice_idx, seq_idx));
+                }
+            }
+        });
+    }
+}
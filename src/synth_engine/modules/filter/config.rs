@@ -0,0 +1,38 @@
+use serde::{Deserialize, Serialize};
+
+use crate::synth_engine::{ModuleId, Sample, StereoSample};
+
+#[derive(Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum FilterType {
+    #[default]
+    LowPass,
+    HighPass,
+    BandPass,
+    Notch,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct FilterConfig {
+    pub id: ModuleId,
+    #[serde(default)]
+    pub bypass: bool,
+    pub filter_type: FilterType,
+    pub keytracking: Sample,
+    pub cutoff: StereoSample,
+    pub q: StereoSample,
+    pub drive: StereoSample,
+}
+
+impl Default for FilterConfig {
+    fn default() -> Self {
+        Self {
+            id: -1,
+            bypass: false,
+            filter_type: FilterType::default(),
+            keytracking: 1.0,
+            cutoff: 4.0.into(),
+            q: 0.707.into(),
+            drive: 0.0.into(),
+        }
+    }
+}
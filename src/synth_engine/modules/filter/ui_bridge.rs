@@ -0,0 +1,55 @@
+use crate::synth_engine::{Input, Sample, StereoSample, synth_module::ModuleUiBridge};
+
+use super::link::UiEnd;
+use super::{Filter, FilterConfig, FilterType};
+
+pub struct FilterUiBridge {
+    ui_end: UiEnd,
+    config: FilterConfig,
+}
+
+impl FilterUiBridge {
+    pub fn try_new(filter: &mut Filter) -> Option<Self> {
+        Some(Self {
+            ui_end: filter.ui_end.take()?,
+            config: filter.get_config(),
+        })
+    }
+
+    pub fn config(&self) -> &FilterConfig {
+        &self.config
+    }
+
+    pub fn set_param(&mut self, input: Input, value: StereoSample) {
+        if !self.ui_end.set_param(input, value) {
+            return;
+        }
+
+        match input {
+            Input::Cutoff => self.config.cutoff = value,
+            Input::Q => self.config.q = value,
+            Input::Drive => self.config.drive = value,
+            _ => (),
+        }
+    }
+
+    pub fn set_filter_type(&mut self, filter_type: FilterType) {
+        if self.ui_end.set_filter_type(filter_type) {
+            self.config.filter_type = filter_type;
+        }
+    }
+
+    pub fn set_keytracking(&mut self, keytracking: Sample) {
+        if self.ui_end.set_keytracking(keytracking) {
+            self.config.keytracking = keytracking;
+        }
+    }
+}
+
+impl ModuleUiBridge for FilterUiBridge {
+    fn update(&mut self) {}
+
+    fn edits(&self) -> u32 {
+        self.ui_end.edits()
+    }
+}
@@ -0,0 +1,392 @@
+use std::{
+    array,
+    f32::consts::{LOG10_2, PI},
+};
+
+mod config;
+mod link;
+mod ui_bridge;
+
+pub use config::HarmonicCombConfig;
+use link::{AudioEnd, UiEnd, UiEvent, create_link_pair};
+pub use ui_bridge::HarmonicCombUiBridge;
+
+use crate::synth_engine::{
+    RandomParam, Randomizer, StereoSample,
+    buffer::{
+        HARMONIC_SERIES_BUFFER, SPECTRAL_BUFFER_SIZE, SpectralBuffer, VoicesLayout,
+        new_voices_layout,
+    },
+    routing::{
+        DataType, Input, InputMeta, InputSlots, InputSource, ModuleId, NUM_CHANNELS,
+        ProcessContext, SpectralInputSlot, SpectralOutput, SpectralRouterType, VoiceEvent,
+        VoiceRouter,
+    },
+    synth_module::SynthModule,
+    types::{ComplexSample, Sample},
+};
+
+pub const MAX_PARTIALS: Sample = (SPECTRAL_BUFFER_SIZE - 1) as Sample;
+pub const MAX_ROLLOFF: Sample = 24.0; // dB/octave
+pub const MAX_COMB_SPACING: Sample = 16.0; // Harmonics between the comb peaks
+
+// Rolloff of the harmonic series itself, the generator is relative to it
+const SERIES_ROLLOFF: Sample = 20.0 * LOG10_2;
+
+struct ChannelParams {
+    partials: Sample,
+    rolloff: Sample,
+    comb_spacing: Sample,
+    even_odd: Sample,
+}
+
+impl ChannelParams {
+    fn from_config(c: &HarmonicCombConfig, channel_idx: usize) -> Self {
+        Self {
+            partials: c.partials[channel_idx],
+            rolloff: c.rolloff[channel_idx],
+            comb_spacing: c.comb_spacing[channel_idx],
+            even_odd: c.even_odd[channel_idx],
+        }
+    }
+}
+
+// Parameters the latest output was generated with
+#[derive(Clone, Copy, PartialEq)]
+struct CombShape {
+    bins: usize,
+    partials: Sample,
+    rolloff: Sample,
+    comb_spacing: Sample,
+    even_odd: Sample,
+}
+
+#[derive(Default)]
+struct VoiceState {
+    triggered: bool,
+    applied: Option<CombShape>,
+}
+
+pub struct Inputs {
+    partials: InputSlots,
+    rolloff: InputSlots,
+    comb_spacing: InputSlots,
+    even_odd: InputSlots,
+}
+
+impl Default for Inputs {
+    fn default() -> Self {
+        Self {
+            partials: InputSlots::empty(Input::Partials),
+            rolloff: InputSlots::empty(Input::Rolloff),
+            comb_spacing: InputSlots::empty(Input::CombSpacing),
+            even_odd: InputSlots::empty(Input::EvenOdd),
+        }
+    }
+}
+
+impl Inputs {
+    fn from_slots(inputs: &[InputSlots], _spectral_inputs: &[SpectralInputSlot]) -> Self {
+        let mut result = Self::default();
+
+        for input in inputs {
+            match input.input_type {
+                Input::Partials => result.partials = input.clone(),
+                Input::Rolloff => result.rolloff = input.clone(),
+                Input::CombSpacing => result.comb_spacing = input.clone(),
+                Input::EvenOdd => result.even_odd = input.clone(),
+                _ => (),
+            }
+        }
+
+        result
+    }
+
+    fn update_source(&mut self, input_type: Input, src_slot: usize, source: &InputSource) {
+        match input_type {
+            Input::Partials => self.partials.update_source(src_slot, source),
+            Input::Rolloff => self.rolloff.update_source(src_slot, source),
+            Input::CombSpacing => self.comb_spacing.update_source(src_slot, source),
+            Input::EvenOdd => self.even_odd.update_source(src_slot, source),
+            _ => (),
+        }
+    }
+}
+
+type Router<'v, 'f, 'c> = VoiceRouter<'v, 'f, 'c, SpectralRouterType>;
+
+// Procedural spectrum source, the spectrum is rebuilt only when a parameter moves
+pub struct HarmonicComb {
+    id: ModuleId,
+    bypass: bool,
+    channel_params: [ChannelParams; NUM_CHANNELS],
+    audio_end: AudioEnd,
+    ui_end: Option<UiEnd>,
+    inputs: Inputs,
+    output_slot: usize,
+    voices: VoicesLayout<VoiceState>,
+}
+
+impl HarmonicComb {
+    pub fn new(id: ModuleId) -> Self {
+        Self::from_config(&HarmonicCombConfig {
+            id,
+            ..HarmonicCombConfig::default()
+        })
+    }
+
+    pub fn from_config(config: &HarmonicCombConfig) -> Self {
+        let (audio_end, ui_end) = create_link_pair();
+
+        Self {
+            id: config.id,
+            bypass: config.bypass,
+            channel_params: array::from_fn(|channel_idx| {
+                ChannelParams::from_config(config, channel_idx)
+            }),
+            audio_end,
+            ui_end: Some(ui_end),
+            inputs: Inputs::default(),
+            output_slot: usize::MAX,
+            voices: new_voices_layout(),
+        }
+    }
+
+    pub fn get_config(&self) -> HarmonicCombConfig {
+        HarmonicCombConfig {
+            id: self.id,
+            bypass: self.bypass,
+            partials: get_stereo_param!(self, partials),
+            rolloff: get_stereo_param!(self, rolloff),
+            comb_spacing: get_stereo_param!(self, comb_spacing),
+            even_odd: get_stereo_param!(self, even_odd),
+        }
+    }
+
+    pub fn randomize(
+        config: &mut HarmonicCombConfig,
+        randomizer: &mut Randomizer,
+        unlocked: impl Fn(RandomParam) -> bool,
+    ) {
+        if unlocked(RandomParam::Rolloff) {
+            config.rolloff = randomizer.stereo(config.rolloff, 0.0, MAX_ROLLOFF);
+        }
+        if unlocked(RandomParam::Spacing) {
+            config.comb_spacing = randomizer.stereo(config.comb_spacing, 1.0, MAX_COMB_SPACING);
+        }
+        if unlocked(RandomParam::EvenOdd) {
+            config.even_odd = randomizer.stereo(config.even_odd, -1.0, 1.0);
+        }
+    }
+
+    set_stereo_param!(set_partials, partials, (*partials).clamp(1.0, MAX_PARTIALS));
+    set_stereo_param!(set_rolloff, rolloff, (*rolloff).clamp(0.0, MAX_ROLLOFF));
+    set_stereo_param!(
+        set_comb_spacing,
+        comb_spacing,
+        (*comb_spacing).clamp(1.0, MAX_COMB_SPACING)
+    );
+    set_stereo_param!(set_even_odd, even_odd, (*even_odd).clamp(-1.0, 1.0));
+
+    /// Fractional `partials` fade the last partial in, `comb_spacing` of 1 keeps every harmonic
+    /// and 2 keeps the odd ones, negative `even_odd` mutes the even harmonics and positive the odd ones.
+    fn generate(output: &mut SpectralBuffer, shape: &CombShape) {
+        let exponent = 1.0 - shape.rolloff / SERIES_ROLLOFF;
+        let odd_gain = (1.0 - shape.even_odd).min(1.0);
+        let even_gain = (1.0 + shape.even_odd).min(1.0);
+        let last = shape.partials.ceil() as usize;
+        let fade = 1.0 - (shape.partials.ceil() - shape.partials);
+        let output = &mut output[..shape.bins];
+
+        output.fill(ComplexSample::ZERO);
+
+        for (harmonic, (out, series)) in output
+            .iter_mut()
+            .zip(&HARMONIC_SERIES_BUFFER)
+            .enumerate()
+            .take(last + 1)
+            .skip(1)
+        {
+            let position = (harmonic - 1) as Sample / shape.comb_spacing;
+            let comb = (PI * position).cos().powi(2);
+            let parity = if harmonic % 2 == 0 {
+                even_gain
+            } else {
+                odd_gain
+            };
+            let fade = if harmonic == last { fade } else { 1.0 };
+
+            *out = *series * ((harmonic as Sample).powf(exponent) * comb * parity * fade);
+        }
+    }
+
+    fn process_voice(
+        &mut self,
+        output: &mut VoicesLayout<SpectralOutput>,
+        mut router: Router<'_, '_, '_>,
+    ) {
+        let channel_idx = router.channel_idx();
+        let voice_idx = router.voice_idx();
+        let inputs = &self.inputs;
+        let channel = &self.channel_params[channel_idx];
+        let voice = &mut self.voices[channel_idx][voice_idx];
+
+        let bins = router.params().spectral_bins;
+        let shape = CombShape {
+            bins,
+            partials: router
+                .scalar_param(&inputs.partials, channel.partials, voice.triggered)
+                .clamp(1.0, (bins - 1) as Sample),
+            rolloff: router
+                .scalar_param(&inputs.rolloff, channel.rolloff, voice.triggered)
+                .clamp(0.0, MAX_ROLLOFF),
+            comb_spacing: router
+                .scalar_param(&inputs.comb_spacing, channel.comb_spacing, voice.triggered)
+                .clamp(1.0, MAX_COMB_SPACING),
+            even_odd: router
+                .scalar_param(&inputs.even_odd, channel.even_odd, voice.triggered)
+                .clamp(-1.0, 1.0),
+        };
+
+        if !voice.triggered && voice.applied == Some(shape) {
+            return;
+        }
+
+        voice.applied = Some(shape);
+        Self::generate(output[channel_idx][voice_idx].advance(), &shape);
+
+        if voice.triggered {
+            voice.triggered = false;
+            voice.applied = None;
+
+            self.process_voice(output, router);
+        }
+    }
+}
+
+impl SynthModule for HarmonicComb {
+    fn id(&self) -> ModuleId {
+        self.id
+    }
+
+    fn bypass(&self) -> bool {
+        self.bypass
+    }
+
+    fn set_bypass(&mut self, bypass: bool) {
+        self.bypass = bypass;
+    }
+
+    fn inputs(&self) -> &'static [InputMeta] {
+        static INPUTS: &[InputMeta] = &[
+            InputMeta::control(Input::Partials),
+            InputMeta::control(Input::Rolloff),
+            InputMeta::control(Input::CombSpacing),
+            InputMeta::control(Input::EvenOdd),
+        ];
+
+        INPUTS
+    }
+
+    fn output_type(&self) -> DataType {
+        DataType::Spectral
+    }
+
+    fn output_slot(&self) -> usize {
+        self.output_slot
+    }
+
+    fn set_output_slot(&mut self, slot: usize) {
+        self.output_slot = slot;
+    }
+
+    fn set_input_slots(&mut self, inputs: &[InputSlots], spectral_inputs: &[SpectralInputSlot]) {
+        self.inputs = Inputs::from_slots(inputs, spectral_inputs);
+
+        for voice in self.voices.iter_mut().flatten() {
+            voice.applied = None;
+        }
+    }
+
+    fn process_events(&mut self, events: &[VoiceEvent]) {
+        for channel in self.voices.iter_mut() {
+            for event in events {
+                if let VoiceEvent::Trigger { voice_idx, .. } = event {
+                    channel[*voice_idx].triggered = true;
+                }
+            }
+        }
+    }
+
+    fn update_input_source(&mut self, input_type: Input, src_slot: usize, source: &InputSource) {
+        self.inputs.update_source(input_type, src_slot, source);
+    }
+
+    fn set_input_param(&mut self, input: Input, value: StereoSample) {
+        match input {
+            Input::Partials => self.set_partials(value),
+            Input::Rolloff => self.set_rolloff(value),
+            Input::CombSpacing => self.set_comb_spacing(value),
+            Input::EvenOdd => self.set_even_odd(value),
+            _ => (),
+        }
+    }
+
+    fn process_ui_events(&mut self) {
+        while let Some(event) = self.audio_end.pop_event() {
+            match event {
+                UiEvent::InputParam { input, value } => self.set_input_param(input, value),
+            }
+        }
+    }
+
+    fn process(&mut self, ctx: &mut ProcessContext) {
+        ctx.for_spectral(self.id, self.output_slot, |router, output| {
+            let num_active_voices = router.params().active_voices.len();
+            let spectrum_channels = router.params().spectrum_channels;
+
+            for channel_idx in 0..spectrum_channels {
+                for seq_idx in 0..num_active_voices {
+                    let voice_idx = router.params().active_voices[seq_idx];
+
+                    self.process_voice(output, router.for_voice(channel_idx, voice_idx, seq_idx));
+                }
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::synth_engine::buffer::zero_spectral_buffer;
+
+    fn shape(partials: Sample, comb_spacing: Sample, even_odd: Sample) -> CombShape {
+        CombShape {
+            bins: SPECTRAL_BUFFER_SIZE,
+            partials,
+            rolloff: SERIES_ROLLOFF,
+            comb_spacing,
+            even_odd,
+        }
+    }
+
+    #[test]
+    fn comb_keeps_selected_partials_of_the_series() {
+        let mut output = zero_spectral_buffer();
+
+        HarmonicComb::generate(&mut output, &shape(8.5, 1.0, 0.0));
+        assert!((output[3] - HARMONIC_SERIES_BUFFER[3]).norm() < 1e-6);
+        assert!((output[9] - HARMONIC_SERIES_BUFFER[9] * 0.5).norm() < 1e-6);
+        assert_eq!(output[10], ComplexSample::ZERO);
+
+        // Spacing of 2 leaves the odd harmonics, like muting the even ones
+        HarmonicComb::generate(&mut output, &shape(8.0, 2.0, 0.0));
+        assert!(output[2].norm() < 1e-6);
+        assert!((output[5] - HARMONIC_SERIES_BUFFER[5]).norm() < 1e-6);
+
+        HarmonicComb::generate(&mut output, &shape(8.0, 1.0, 1.0));
+        assert_eq!(output[1], ComplexSample::ZERO);
+        assert!((output[4] - HARMONIC_SERIES_BUFFER[4]).norm() < 1e-6);
+    }
+}
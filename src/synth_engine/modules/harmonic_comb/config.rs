@@ -0,0 +1,27 @@
+use serde::{Deserialize, Serialize};
+
+use crate::synth_engine::{ModuleId, StereoSample};
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct HarmonicCombConfig {
+    pub id: ModuleId,
+    #[serde(default)]
+    pub bypass: bool,
+    pub partials: StereoSample,
+    pub rolloff: StereoSample,
+    pub comb_spacing: StereoSample,
+    pub even_odd: StereoSample,
+}
+
+impl Default for HarmonicCombConfig {
+    fn default() -> Self {
+        Self {
+            id: -1,
+            bypass: false,
+            partials: 64.0.into(),
+            rolloff: 6.0.into(),
+            comb_spacing: 1.0.into(),
+            even_odd: 0.0.into(),
+        }
+    }
+}
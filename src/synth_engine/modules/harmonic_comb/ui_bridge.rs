@@ -0,0 +1,44 @@
+use crate::synth_engine::{Input, StereoSample, synth_module::ModuleUiBridge};
+
+use super::link::UiEnd;
+use super::{HarmonicComb, HarmonicCombConfig};
+
+pub struct HarmonicCombUiBridge {
+    ui_end: UiEnd,
+    config: HarmonicCombConfig,
+}
+
+impl HarmonicCombUiBridge {
+    pub fn try_new(comb: &mut HarmonicComb) -> Option<Self> {
+        Some(Self {
+            ui_end: comb.ui_end.take()?,
+            config: comb.get_config(),
+        })
+    }
+
+    pub fn config(&self) -> &HarmonicCombConfig {
+        &self.config
+    }
+
+    pub fn set_param(&mut self, input: Input, value: StereoSample) {
+        if !self.ui_end.set_param(input, value) {
+            return;
+        }
+
+        match input {
+            Input::Partials => self.config.partials = value,
+            Input::Rolloff => self.config.rolloff = value,
+            Input::CombSpacing => self.config.comb_spacing = value,
+            Input::EvenOdd => self.config.even_odd = value,
+            _ => (),
+        }
+    }
+}
+
+impl ModuleUiBridge for HarmonicCombUiBridge {
+    fn update(&mut self) {}
+
+    fn edits(&self) -> u32 {
+        self.ui_end.edits()
+    }
+}
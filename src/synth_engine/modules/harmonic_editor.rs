@@ -1,16 +1,20 @@
-use std::f32;
+use std::{array, f32};
+
+use itertools::izip;
+use rand::RngExt;
+use rand_pcg::Pcg32;
 
 use crate::{
     synth_engine::{
-        Sample, StereoSample, VoiceEvent,
+        RandomParam, Randomizer, Sample, StereoSample, VoiceEvent,
         biquad_filter::BiquadFilter,
         buffer::{
             HARMONIC_SERIES_BUFFER, SPECTRAL_BUFFER_SIZE, SpectralBuffer, VoicesLayout,
             new_voices_layout,
         },
         routing::{
-            DataType, Input, InputMeta, InputSlots, ModuleId, NUM_CHANNELS, ProcessContext,
-            SpectralInputSlot,
+            DataType, Input, InputMeta, InputSlots, InputSource, ModuleId, NUM_CHANNELS,
+            ProcessContext, SpectralInputSlot, SpectralOutput, SpectralRouterType, VoiceRouter,
         },
         synth_module::SynthModule,
         types::ComplexSample,
@@ -21,10 +25,13 @@ use crate::{
 mod config;
 mod link;
 mod ui_bridge;
+mod waveform_import;
 
-pub use config::{ComplexCfg, HarmonicEditorConfig};
-use link::{AudioEnd, UiEnd, UiEvent, create_link_pair};
+pub use config::{ComplexCfg, HarmonicEditorConfig, NUM_SNAPSHOTS, SnapshotConfig};
+pub use link::HARMONICS_CHUNK_SIZE;
+use link::{AudioEnd, HarmonicsState, UiEnd, UiEvent, create_link_pair};
 pub use ui_bridge::HarmonicEditorUiBridge;
+pub use waveform_import::{ImportedSpectrum, read_waveform_spectrum};
 
 #[derive(Clone, Copy, PartialEq)]
 pub enum SetAction {
@@ -40,6 +47,18 @@ pub struct SetParams {
     pub gain: StereoSample,
 }
 
+#[derive(Clone, Copy, PartialEq)]
+pub enum SnapshotSlot {
+    A,
+    B,
+}
+
+impl SnapshotSlot {
+    pub fn idx(self) -> usize {
+        self as usize
+    }
+}
+
 #[derive(Clone, Copy, PartialEq)]
 pub enum FilterType {
     LowPass,
@@ -82,6 +101,7 @@ impl BiquadFilter {
 struct Voice {
     triggered: bool,
     needs_update: bool,
+    morph_blend: Sample,
 }
 
 impl Default for Voice {
@@ -89,15 +109,97 @@ impl Default for Voice {
         Self {
             triggered: false,
             needs_update: true,
+            morph_blend: 0.0,
+        }
+    }
+}
+
+type PhasesBuffer = [Sample; SPECTRAL_BUFFER_SIZE];
+
+struct Snapshot {
+    stored: bool,
+    harmonics: [SpectralBuffer; NUM_CHANNELS],
+    phases: [PhasesBuffer; NUM_CHANNELS],
+}
+
+impl Snapshot {
+    fn from_config(config: Option<&SnapshotConfig>) -> Self {
+        let mut snapshot = Self {
+            stored: config.is_some(),
+            harmonics: [HARMONIC_SERIES_BUFFER; NUM_CHANNELS],
+            phases: [[0.0; SPECTRAL_BUFFER_SIZE]; NUM_CHANNELS],
+        };
+
+        if let Some(config) = config {
+            HarmonicEditor::read_spectrum(&config.spectrum, &mut snapshot.harmonics);
+            HarmonicEditor::read_phases(&config.phases, &mut snapshot.phases);
+        }
+
+        snapshot
+    }
+
+    fn get_config(&self) -> Option<SnapshotConfig> {
+        self.stored.then(|| SnapshotConfig {
+            spectrum: HarmonicEditor::spectrum_config(&self.harmonics),
+            phases: self.phases.map(|channel| channel.to_vec()),
+        })
+    }
+}
+
+struct Params {
+    morph: bool,
+}
+
+struct ChannelParams {
+    blend: Sample,
+}
+
+struct Inputs {
+    blend: InputSlots,
+}
+
+impl Default for Inputs {
+    fn default() -> Self {
+        Self {
+            blend: InputSlots::empty(Input::Blend),
+        }
+    }
+}
+
+impl Inputs {
+    fn from_slots(inputs: &[InputSlots]) -> Self {
+        let mut result = Self::default();
+
+        for input in inputs {
+            if input.input_type == Input::Blend {
+                result.blend = input.clone();
+            }
+        }
+
+        result
+    }
+
+    fn update_source(&mut self, input_type: Input, src_slot: usize, source: &InputSource) {
+        if input_type == Input::Blend {
+            self.blend.update_source(src_slot, source);
         }
     }
 }
 
+type Router<'v, 'f, 'c> = VoiceRouter<'v, 'f, 'c, SpectralRouterType>;
+
 pub struct HarmonicEditor {
     id: ModuleId,
+    bypass: bool,
+    params: Params,
+    channel_params: [ChannelParams; NUM_CHANNELS],
     harmonics: [SpectralBuffer; NUM_CHANNELS],
+    phases: [PhasesBuffer; NUM_CHANNELS],
+    snapshots: Box<[Snapshot; NUM_SNAPSHOTS]>,
+    random: Pcg32,
     audio_end: AudioEnd,
     ui_end: Option<UiEnd>,
+    inputs: Inputs,
     output_slot: usize,
     voices: VoicesLayout<Voice>,
 }
@@ -111,22 +213,32 @@ impl HarmonicEditor {
     }
 
     pub fn from_config(config: &config::HarmonicEditorConfig) -> Self {
-        let (audio_end, ui_end) = create_link_pair();
         let mut harmonics = [HARMONIC_SERIES_BUFFER; NUM_CHANNELS];
+        let mut phases = [[0.0; SPECTRAL_BUFFER_SIZE]; NUM_CHANNELS];
 
-        for (channel, cfg_channel) in harmonics.iter_mut().zip(&config.spectrum) {
-            if cfg_channel.len() == SPECTRAL_BUFFER_SIZE {
-                for (out, cfg) in channel.iter_mut().zip(cfg_channel.iter()) {
-                    *out = cfg.complex();
-                }
-            }
-        }
+        Self::read_spectrum(&config.spectrum, &mut harmonics);
+        Self::read_phases(&config.phases, &mut phases);
+
+        let (audio_end, ui_end) = create_link_pair(HarmonicsState { harmonics, phases });
 
         Self {
             id: config.id,
+            bypass: config.bypass,
+            params: Params {
+                morph: config.morph,
+            },
+            channel_params: array::from_fn(|channel_idx| ChannelParams {
+                blend: config.blend[channel_idx],
+            }),
             harmonics,
+            phases,
+            snapshots: Box::new(array::from_fn(|idx| {
+                Snapshot::from_config(config.snapshots[idx].as_ref())
+            })),
+            random: Pcg32::new(420, 1337),
             audio_end,
             ui_end: Some(ui_end),
+            inputs: Inputs::default(),
             output_slot: usize::MAX,
             voices: new_voices_layout(),
         }
@@ -135,15 +247,152 @@ impl HarmonicEditor {
     pub fn get_config(&self) -> HarmonicEditorConfig {
         HarmonicEditorConfig {
             id: self.id,
-            spectrum: self.harmonics.map(|channel| {
-                channel
-                    .iter()
-                    .map(|complex| ComplexCfg::from_complex(*complex))
-                    .collect()
-            }),
+            bypass: self.bypass,
+            spectrum: Self::spectrum_config(&self.harmonics),
+            phases: self.phases.map(|channel| channel.to_vec()),
+            snapshots: array::from_fn(|idx| self.snapshots[idx].get_config()),
+            morph: self.params.morph,
+            blend: get_stereo_param!(self, blend),
+        }
+    }
+
+    pub fn randomize(
+        config: &mut HarmonicEditorConfig,
+        randomizer: &mut Randomizer,
+        unlocked: impl Fn(RandomParam) -> bool,
+    ) {
+        if unlocked(RandomParam::Harmonics) {
+            randomizer.harmonics(config);
+        }
+    }
+
+    fn read_spectrum(
+        config: &[Vec<ComplexCfg>; NUM_CHANNELS],
+        harmonics: &mut [SpectralBuffer; NUM_CHANNELS],
+    ) {
+        for (channel, cfg_channel) in harmonics.iter_mut().zip(config) {
+            if cfg_channel.len() == SPECTRAL_BUFFER_SIZE {
+                for (out, cfg) in channel.iter_mut().zip(cfg_channel.iter()) {
+                    *out = cfg.complex();
+                }
+            }
+        }
+    }
+
+    fn read_phases(
+        config: &[Vec<Sample>; NUM_CHANNELS],
+        phases: &mut [PhasesBuffer; NUM_CHANNELS],
+    ) {
+        for (channel, cfg_channel) in phases.iter_mut().zip(config) {
+            if cfg_channel.len() == SPECTRAL_BUFFER_SIZE {
+                channel.copy_from_slice(cfg_channel);
+            }
+        }
+    }
+
+    fn spectrum_config(
+        harmonics: &[SpectralBuffer; NUM_CHANNELS],
+    ) -> [Vec<ComplexCfg>; NUM_CHANNELS] {
+        harmonics.each_ref().map(|channel| {
+            channel
+                .iter()
+                .map(|complex| ComplexCfg::from_complex(*complex))
+                .collect()
+        })
+    }
+
+    pub fn set_morph(&mut self, morph: bool) {
+        self.params.morph = morph;
+        self.set_needs_update();
+    }
+
+    set_stereo_param!(set_blend, blend, blend.clamp(0.0, 1.0));
+
+    pub fn store_snapshot(&mut self, slot: SnapshotSlot) {
+        let snapshot = &mut self.snapshots[slot.idx()];
+
+        snapshot.harmonics = self.harmonics;
+        snapshot.phases = self.phases;
+        snapshot.stored = true;
+        self.set_needs_update();
+    }
+
+    pub fn recall_snapshot(&mut self, slot: SnapshotSlot) {
+        let snapshot = &self.snapshots[slot.idx()];
+
+        if snapshot.stored {
+            self.harmonics = snapshot.harmonics;
+            self.phases = snapshot.phases;
+            self.set_needs_update();
+        }
+    }
+
+    fn morphing(&self) -> bool {
+        self.params.morph && self.snapshots.iter().all(|snapshot| snapshot.stored)
+    }
+
+    fn process_morph_voice(
+        &mut self,
+        output: &mut VoicesLayout<SpectralOutput>,
+        mut router: Router<'_, '_, '_>,
+    ) {
+        let channel_idx = router.channel_idx();
+        let voice_idx = router.voice_idx();
+        let voice = &mut self.voices[channel_idx][voice_idx];
+
+        let blend = router
+            .scalar_param(
+                &self.inputs.blend,
+                self.channel_params[channel_idx].blend,
+                voice.triggered,
+            )
+            .clamp(0.0, 1.0);
+
+        // Same snapshots and blend, downstream modules keep reading the latest spectrum
+        if !voice.triggered && !voice.needs_update && voice.morph_blend == blend {
+            return;
+        }
+
+        voice.needs_update = false;
+        voice.morph_blend = blend;
+
+        let bins = router.params().spectral_bins;
+        let voice_output = &mut output[channel_idx][voice_idx].advance()[..bins];
+
+        let [from, to] = &*self.snapshots;
+
+        for (out, from, to) in izip!(
+            voice_output,
+            &from.harmonics[channel_idx],
+            &to.harmonics[channel_idx]
+        ) {
+            *out = from + (to - from) * blend;
+        }
+
+        if voice.triggered {
+            voice.triggered = false;
+            voice.needs_update = true;
+
+            self.process_morph_voice(output, router);
         }
     }
 
+    pub fn harmonic_value(idx: usize, gain: Sample, phase: Sample) -> ComplexSample {
+        HARMONIC_SERIES_BUFFER[idx] * ComplexSample::from_polar(gain, f32::consts::TAU * phase)
+    }
+
+    pub fn phases_from_config(config: &HarmonicEditorConfig) -> Vec<StereoSample> {
+        let mut phases = vec![StereoSample::ZERO; SPECTRAL_BUFFER_SIZE];
+
+        for (channel_idx, channel) in config.phases.iter().enumerate() {
+            for (phase, cfg_phase) in phases.iter_mut().zip(channel.iter()) {
+                phase[channel_idx] = *cfg_phase;
+            }
+        }
+
+        phases
+    }
+
     pub fn harmonics_from_config(config: &HarmonicEditorConfig) -> Vec<StereoSample> {
         let mut magnitudes = vec![StereoSample::ZERO; SPECTRAL_BUFFER_SIZE];
 
@@ -173,8 +422,68 @@ impl HarmonicEditor {
     pub fn set_harmonic(&mut self, harmonic_number: usize, gain: StereoSample) {
         let idx = harmonic_number.clamp(1, SPECTRAL_BUFFER_SIZE - 1);
 
-        for (spectrum, gain) in self.harmonics.iter_mut().zip(gain.iter()) {
-            spectrum[idx] = HARMONIC_SERIES_BUFFER[idx] * gain;
+        for (spectrum, phases, gain) in izip!(&mut self.harmonics, &self.phases, gain.iter()) {
+            spectrum[idx] = Self::harmonic_value(idx, *gain, phases[idx]);
+        }
+
+        self.set_needs_update();
+    }
+
+    pub fn set_phase(&mut self, harmonic_number: usize, phase: StereoSample) {
+        self.set_phases_range(harmonic_number, &[phase]);
+    }
+
+    pub fn set_phases_range(&mut self, from: usize, phases: &[StereoSample]) {
+        let idx_from = from.clamp(1, SPECTRAL_BUFFER_SIZE - 1);
+        let range = idx_from..(idx_from + phases.len()).min(SPECTRAL_BUFFER_SIZE);
+
+        for (channel_idx, (spectrum, channel_phases)) in
+            self.harmonics.iter_mut().zip(&mut self.phases).enumerate()
+        {
+            for (idx, phase) in range.clone().zip(phases) {
+                let gain = spectrum[idx].norm() / HARMONIC_SERIES_BUFFER[idx].norm();
+
+                channel_phases[idx] = phase[channel_idx].rem_euclid(1.0);
+                spectrum[idx] = Self::harmonic_value(idx, gain, channel_phases[idx]);
+            }
+        }
+
+        self.set_needs_update();
+    }
+
+    fn apply_phases(&mut self, phase_fn: impl Fn(&mut Pcg32) -> Sample) {
+        for idx in 1..SPECTRAL_BUFFER_SIZE {
+            let phase = phase_fn(&mut self.random);
+
+            for (spectrum, phases) in self.harmonics.iter_mut().zip(self.phases.iter_mut()) {
+                let gain = spectrum[idx].norm() / HARMONIC_SERIES_BUFFER[idx].norm();
+
+                phases[idx] = phase;
+                spectrum[idx] = Self::harmonic_value(idx, gain, phase);
+            }
+        }
+
+        self.set_needs_update();
+    }
+
+    pub fn randomize_phases(&mut self) {
+        self.apply_phases(|random| random.random::<Sample>());
+    }
+
+    pub fn zero_phases(&mut self) {
+        self.apply_phases(|_| 0.0);
+    }
+
+    pub fn set_harmonics_range(&mut self, from: usize, gains: &[StereoSample]) {
+        let idx_from = from.clamp(1, SPECTRAL_BUFFER_SIZE - 1);
+        let range = idx_from..(idx_from + gains.len()).min(SPECTRAL_BUFFER_SIZE);
+
+        for (channel_idx, (spectrum, phases)) in
+            self.harmonics.iter_mut().zip(&self.phases).enumerate()
+        {
+            for (idx, gain) in range.clone().zip(gains) {
+                spectrum[idx] = Self::harmonic_value(idx, gain[channel_idx], phases[idx]);
+            }
         }
 
         self.set_needs_update();
@@ -184,10 +493,11 @@ impl HarmonicEditor {
         let idx_from = params.from.clamp(1, SPECTRAL_BUFFER_SIZE - 1);
         let range = idx_from..(params.to + 1).clamp(idx_from, SPECTRAL_BUFFER_SIZE);
 
-        for (spectrum, gain) in self.harmonics.iter_mut().zip(params.gain.iter()) {
-            for (idx, (harmonic, initial_harmonic)) in spectrum[range.clone()]
+        for (spectrum, phases, gain) in izip!(&mut self.harmonics, &self.phases, params.gain.iter())
+        {
+            for (idx, (harmonic, phase)) in spectrum[range.clone()]
                 .iter_mut()
-                .zip(HARMONIC_SERIES_BUFFER[range.clone()].iter())
+                .zip(phases[range.clone()].iter())
                 .enumerate()
             {
                 let matches = params
@@ -200,7 +510,9 @@ impl HarmonicEditor {
                 }
 
                 match params.action {
-                    SetAction::Set => *harmonic = *initial_harmonic * gain,
+                    SetAction::Set => {
+                        *harmonic = Self::harmonic_value(idx_from + idx, *gain, *phase);
+                    }
                     SetAction::Multiple => *harmonic *= gain,
                 }
             }
@@ -234,8 +546,18 @@ impl SynthModule for HarmonicEditor {
         self.id
     }
 
+    fn bypass(&self) -> bool {
+        self.bypass
+    }
+
+    fn set_bypass(&mut self, bypass: bool) {
+        self.bypass = bypass;
+    }
+
     fn inputs(&self) -> &'static [InputMeta] {
-        &[]
+        static INPUTS: &[InputMeta] = &[InputMeta::control(Input::Blend)];
+
+        INPUTS
     }
 
     fn output_type(&self) -> DataType {
@@ -250,9 +572,12 @@ impl SynthModule for HarmonicEditor {
         self.output_slot = slot;
     }
 
-    fn set_input_slots(&mut self, _inputs: &[InputSlots], _spectral_inputs: &[SpectralInputSlot]) {}
+    fn set_input_slots(&mut self, inputs: &[InputSlots], _spectral_inputs: &[SpectralInputSlot]) {
+        self.inputs = Inputs::from_slots(inputs);
+    }
 
-    fn update_input_amount(&mut self, _input_type: Input, _src_slot: usize, _amount: StereoSample) {
+    fn update_input_source(&mut self, input_type: Input, src_slot: usize, source: &InputSource) {
+        self.inputs.update_source(input_type, src_slot, source);
     }
 
     fn process_events(&mut self, events: &[VoiceEvent]) {
@@ -265,6 +590,12 @@ impl SynthModule for HarmonicEditor {
         }
     }
 
+    fn set_input_param(&mut self, input: Input, value: StereoSample) {
+        if input == Input::Blend {
+            self.set_blend(value);
+        }
+    }
+
     fn process_ui_events(&mut self) {
         let mut refresh = false;
 
@@ -274,6 +605,24 @@ impl SynthModule for HarmonicEditor {
                     harmonic_number,
                     gain,
                 } => self.set_harmonic(harmonic_number, gain),
+                UiEvent::SetHarmonicsRange { from, gains } => {
+                    self.set_harmonics_range(from, &gains);
+                }
+                UiEvent::SetPhase {
+                    harmonic_number,
+                    phase,
+                } => self.set_phase(harmonic_number, phase),
+                UiEvent::SetPhasesRange { from, phases } => {
+                    self.set_phases_range(from, &phases);
+                }
+                UiEvent::RandomizePhases => {
+                    self.randomize_phases();
+                    refresh = true;
+                }
+                UiEvent::ZeroPhases => {
+                    self.zero_phases();
+                    refresh = true;
+                }
                 UiEvent::SetSelected(params) => {
                     self.set_selected(&params);
                     refresh = true;
@@ -282,11 +631,18 @@ impl SynthModule for HarmonicEditor {
                     self.apply_filter(&params);
                     refresh = true;
                 }
+                UiEvent::StoreSnapshot(slot) => self.store_snapshot(slot),
+                UiEvent::RecallSnapshot(slot) => {
+                    self.recall_snapshot(slot);
+                    refresh = true;
+                }
+                UiEvent::Morph(morph) => self.set_morph(morph),
+                UiEvent::InputParam { input, value } => self.set_input_param(input, value),
             }
         }
 
         if refresh {
-            self.audio_end.push_refresh_state();
+            self.audio_end.publish_state(&self.harmonics, &self.phases);
         }
     }
 
@@ -294,21 +650,33 @@ impl SynthModule for HarmonicEditor {
         ctx.for_spectral(self.id, self.output_slot, |router, output| {
             let num_active_voices = router.params().active_voices.len();
             let spectrum_channels = router.params().spectrum_channels;
+            let bins = router.params().spectral_bins;
+            let morphing = self.morphing();
 
             for channel_idx in 0..spectrum_channels {
                 for seq_idx in 0..num_active_voices {
                     let voice_idx = router.params().active_voices[seq_idx];
+
+                    if morphing {
+                        self.process_morph_voice(
+                            output,
+                            router.for_voice(channel_idx, voice_idx, seq_idx),
+                        );
+                        continue;
+                    }
+
                     let voice = &mut self.voices[channel_idx][voice_idx];
 
                     if voice.needs_update {
                         let voice_output = &mut output[channel_idx][voice_idx];
+                        let harmonics = &self.harmonics[channel_idx][..bins];
 
                         if voice.triggered {
-                            *voice_output.advance() = self.harmonics[channel_idx];
+                            voice_output.advance()[..bins].copy_from_slice(harmonics);
                             voice.triggered = false;
                         }
 
-                        *voice_output.advance() = self.harmonics[channel_idx];
+                        voice_output.advance()[..bins].copy_from_slice(harmonics);
                         voice.needs_update = false;
                     }
                 }
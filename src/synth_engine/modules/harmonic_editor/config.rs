@@ -1,7 +1,7 @@
 use serde::{Deserialize, Serialize};
 
 use crate::synth_engine::{
-    ModuleId, Sample,
+    ModuleId, Sample, StereoSample,
     buffer::{HARMONIC_SERIES_BUFFER, SPECTRAL_BUFFER_SIZE},
     routing::NUM_CHANNELS,
     types::ComplexSample,
@@ -26,17 +26,44 @@ impl ComplexCfg {
     }
 }
 
+pub const NUM_SNAPSHOTS: usize = 2;
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SnapshotConfig {
+    pub spectrum: [Vec<ComplexCfg>; NUM_CHANNELS],
+    pub phases: [Vec<Sample>; NUM_CHANNELS],
+}
+
+fn default_blend() -> StereoSample {
+    StereoSample::ZERO
+}
+
 #[derive(Clone, Serialize, Deserialize)]
 pub struct HarmonicEditorConfig {
     pub id: ModuleId,
+    #[serde(default)]
+    pub bypass: bool,
     pub spectrum: [Vec<ComplexCfg>; NUM_CHANNELS],
+    #[serde(default)]
+    pub phases: [Vec<Sample>; NUM_CHANNELS], // Normalized, relative to the harmonic series
+    #[serde(default)]
+    pub snapshots: [Option<SnapshotConfig>; NUM_SNAPSHOTS],
+    #[serde(default)]
+    pub morph: bool,
+    #[serde(default = "default_blend")]
+    pub blend: StereoSample,
 }
 
 impl Default for HarmonicEditorConfig {
     fn default() -> Self {
         let mut cfg = Self {
             id: -1,
+            bypass: false,
             spectrum: Default::default(),
+            phases: Default::default(),
+            snapshots: Default::default(),
+            morph: false,
+            blend: default_blend(),
         };
 
         let harmonic_series = &HARMONIC_SERIES_BUFFER;
@@ -50,6 +77,10 @@ impl Default for HarmonicEditorConfig {
             );
         }
 
+        for channel in &mut cfg.phases {
+            channel.resize(SPECTRAL_BUFFER_SIZE, 0.0);
+        }
+
         cfg
     }
 }
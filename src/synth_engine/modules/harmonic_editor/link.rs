@@ -1,28 +1,68 @@
-use crate::synth_engine::StereoSample;
+use smallvec::SmallVec;
 
-use super::{FilterParams, SetParams};
+use crate::synth_engine::{
+    Input, NUM_CHANNELS, SpectralBuffer, StereoSample,
+    edit_sender::EditSender,
+    triple_buffer::{TripleReader, TripleWriter, create_triple_buffer},
+};
+
+use super::{FilterParams, PhasesBuffer, SetParams, SnapshotSlot};
+
+// Ranges are sent in fixed chunks so the audio thread never frees heap memory
+pub const HARMONICS_CHUNK_SIZE: usize = 32;
 
 pub enum UiEvent {
     SetHarmonic {
         harmonic_number: usize,
         gain: StereoSample,
     },
+    SetHarmonicsRange {
+        from: usize,
+        gains: SmallVec<[StereoSample; HARMONICS_CHUNK_SIZE]>,
+    },
+    SetPhase {
+        harmonic_number: usize,
+        phase: StereoSample,
+    },
+    SetPhasesRange {
+        from: usize,
+        phases: SmallVec<[StereoSample; HARMONICS_CHUNK_SIZE]>,
+    },
+    RandomizePhases,
+    ZeroPhases,
     SetSelected(SetParams),
     ApplyFilter(FilterParams),
+    StoreSnapshot(SnapshotSlot),
+    RecallSnapshot(SnapshotSlot),
+    Morph(bool),
+    InputParam {
+        input: Input,
+        value: StereoSample,
+    },
 }
 
-pub enum UiUpdate {
-    RefreshState,
+// Harmonics after the edits the audio thread computes itself, read by the editor without locking
+#[derive(Clone)]
+pub struct HarmonicsState {
+    pub harmonics: [SpectralBuffer; NUM_CHANNELS],
+    pub phases: [PhasesBuffer; NUM_CHANNELS],
 }
 
 pub struct UiEnd {
-    rx: rtrb::Consumer<UiUpdate>,
-    tx: rtrb::Producer<UiEvent>,
+    tx: EditSender<UiEvent>,
+    state: TripleReader<HarmonicsState>,
 }
 
 impl UiEnd {
-    pub fn new(rx: rtrb::Consumer<UiUpdate>, tx: rtrb::Producer<UiEvent>) -> Self {
-        Self { rx, tx }
+    pub fn new(tx: rtrb::Producer<UiEvent>, state: TripleReader<HarmonicsState>) -> Self {
+        Self {
+            tx: EditSender::new(tx),
+            state,
+        }
+    }
+
+    pub fn edits(&self) -> u32 {
+        self.tx.sent()
     }
 
     pub fn set_harmonic(&mut self, harmonic_number: usize, gain: StereoSample) -> bool {
@@ -34,6 +74,45 @@ impl UiEnd {
             .is_ok()
     }
 
+    pub fn set_harmonics_range(&mut self, from: usize, gains: &[StereoSample]) -> bool {
+        debug_assert!(gains.len() <= HARMONICS_CHUNK_SIZE);
+
+        self.tx
+            .push(UiEvent::SetHarmonicsRange {
+                from,
+                gains: SmallVec::from_slice(gains),
+            })
+            .is_ok()
+    }
+
+    pub fn set_phase(&mut self, harmonic_number: usize, phase: StereoSample) -> bool {
+        self.tx
+            .push(UiEvent::SetPhase {
+                harmonic_number,
+                phase,
+            })
+            .is_ok()
+    }
+
+    pub fn set_phases_range(&mut self, from: usize, phases: &[StereoSample]) -> bool {
+        debug_assert!(phases.len() <= HARMONICS_CHUNK_SIZE);
+
+        self.tx
+            .push(UiEvent::SetPhasesRange {
+                from,
+                phases: SmallVec::from_slice(phases),
+            })
+            .is_ok()
+    }
+
+    pub fn randomize_phases(&mut self) -> bool {
+        self.tx.push(UiEvent::RandomizePhases).is_ok()
+    }
+
+    pub fn zero_phases(&mut self) -> bool {
+        self.tx.push(UiEvent::ZeroPhases).is_ok()
+    }
+
     pub fn set_selected(&mut self, params: SetParams) -> bool {
         self.tx.push(UiEvent::SetSelected(params)).is_ok()
     }
@@ -42,36 +121,65 @@ impl UiEnd {
         self.tx.push(UiEvent::ApplyFilter(params)).is_ok()
     }
 
-    pub fn pop_update(&mut self) -> Option<UiUpdate> {
-        self.rx.pop().ok()
+    pub fn store_snapshot(&mut self, slot: SnapshotSlot) -> bool {
+        self.tx.push(UiEvent::StoreSnapshot(slot)).is_ok()
+    }
+
+    pub fn recall_snapshot(&mut self, slot: SnapshotSlot) -> bool {
+        self.tx.push(UiEvent::RecallSnapshot(slot)).is_ok()
+    }
+
+    pub fn set_morph(&mut self, morph: bool) -> bool {
+        self.tx.push(UiEvent::Morph(morph)).is_ok()
+    }
+
+    pub fn set_param(&mut self, input: Input, value: StereoSample) -> bool {
+        self.tx.push(UiEvent::InputParam { input, value }).is_ok()
+    }
+
+    pub fn free_slots(&self) -> usize {
+        self.tx.slots()
+    }
+
+    // The latest harmonics state when one was published since the last call
+    pub fn updated_state(&mut self) -> Option<&HarmonicsState> {
+        self.state.update().then(|| self.state.output())
     }
 }
 
 pub struct AudioEnd {
     rx: rtrb::Consumer<UiEvent>,
-    tx: rtrb::Producer<UiUpdate>,
+    state: TripleWriter<HarmonicsState>,
 }
 
 impl AudioEnd {
-    pub fn new(rx: rtrb::Consumer<UiEvent>, tx: rtrb::Producer<UiUpdate>) -> Self {
-        Self { rx, tx }
+    pub fn new(rx: rtrb::Consumer<UiEvent>, state: TripleWriter<HarmonicsState>) -> Self {
+        Self { rx, state }
     }
 
     pub fn pop_event(&mut self) -> Option<UiEvent> {
         self.rx.pop().ok()
     }
 
-    pub fn push_refresh_state(&mut self) -> bool {
-        self.tx.push(UiUpdate::RefreshState).is_ok()
+    pub fn publish_state(
+        &mut self,
+        harmonics: &[SpectralBuffer; NUM_CHANNELS],
+        phases: &[PhasesBuffer; NUM_CHANNELS],
+    ) {
+        let state = self.state.input();
+
+        state.harmonics = *harmonics;
+        state.phases = *phases;
+        self.state.publish();
     }
 }
 
-pub fn create_link_pair() -> (AudioEnd, UiEnd) {
+pub fn create_link_pair(state: HarmonicsState) -> (AudioEnd, UiEnd) {
     let (to_audio_tx, from_ui_rx) = rtrb::RingBuffer::<UiEvent>::new(128);
-    let (to_ui_tx, from_audio_rx) = rtrb::RingBuffer::<UiUpdate>::new(128);
+    let (state_tx, state_rx) = create_triple_buffer(state);
 
     (
-        AudioEnd::new(from_ui_rx, to_ui_tx),
-        UiEnd::new(from_audio_rx, to_audio_tx),
+        AudioEnd::new(from_ui_rx, state_tx),
+        UiEnd::new(to_audio_tx, state_rx),
     )
 }
@@ -1,65 +1,179 @@
-use std::sync::Arc;
-
-use parking_lot::Mutex;
+use std::path::Path;
 
 use crate::synth_engine::{
-    ModuleHandle, ModuleId, SPECTRAL_BUFFER_SIZE, StereoSample, SynthEngine,
-    buffer::HARMONIC_SERIES_BUFFER, synth_module::ModuleUiBridge,
+    HARMONIC_SERIES_BUFFER, Input, SPECTRAL_BUFFER_SIZE, Sample, StereoSample,
+    synth_module::ModuleUiBridge,
 };
 
-use super::link::{UiEnd, UiUpdate};
-use super::{FilterParams, HarmonicEditor, HarmonicEditorConfig, SetParams};
+use super::link::{HARMONICS_CHUNK_SIZE, UiEnd};
+use super::{
+    ComplexCfg, FilterParams, HarmonicEditor, HarmonicEditorConfig, SetParams, SnapshotConfig,
+    SnapshotSlot, read_waveform_spectrum,
+};
 
 pub struct HarmonicEditorUiBridge {
-    synth: Arc<Mutex<SynthEngine>>,
-    module_id: ModuleId,
     ui_end: UiEnd,
     config: HarmonicEditorConfig,
+    received_states: u32,
 }
 
 impl HarmonicEditorUiBridge {
-    pub fn try_new(
-        module_id: ModuleId,
-        synth: Arc<Mutex<SynthEngine>>,
-        editor: &mut HarmonicEditor,
-    ) -> Option<Self> {
+    pub fn try_new(editor: &mut HarmonicEditor) -> Option<Self> {
         Some(Self {
-            synth,
-            module_id,
             ui_end: editor.ui_end.take()?,
             config: editor.get_config(),
+            received_states: 0,
         })
     }
 
-    pub fn sync(&mut self) {
-        let synth_lock = self.synth.lock();
-
-        if let Some(ModuleHandle::HarmonicEditor(editor)) = synth_lock.get_module(self.module_id) {
-            self.config = editor.get_config();
-        }
+    pub fn config(&self) -> &HarmonicEditorConfig {
+        &self.config
     }
 
-    // pub fn config(&self) -> &Config {
-    //     &self.config
-    // }
-
     pub fn harmonics(&self) -> Vec<StereoSample> {
         HarmonicEditor::harmonics_from_config(&self.config)
     }
 
+    pub fn phases(&self) -> Vec<StereoSample> {
+        HarmonicEditor::phases_from_config(&self.config)
+    }
+
+    fn update_harmonic(&mut self, channel_idx: usize, idx: usize, gain: Sample) {
+        let phase = self.config.phases[channel_idx]
+            .get(idx)
+            .copied()
+            .unwrap_or(0.0);
+
+        if let Some(harmonic) = self.config.spectrum[channel_idx].get_mut(idx) {
+            *harmonic = ComplexCfg::from_complex(HarmonicEditor::harmonic_value(idx, gain, phase));
+        }
+    }
+
     pub fn set_harmonic(&mut self, harmonic_number: usize, gain: StereoSample) {
         if self.ui_end.set_harmonic(harmonic_number, gain) {
             let idx = harmonic_number.clamp(1, SPECTRAL_BUFFER_SIZE - 1);
 
-            for (channel, gain) in self.config.spectrum.iter_mut().zip(gain.iter()) {
-                if idx < channel.len() {
-                    channel[idx] =
-                        super::config::ComplexCfg::from_complex(HARMONIC_SERIES_BUFFER[idx] * gain);
+            for (channel_idx, gain) in gain.iter().enumerate() {
+                self.update_harmonic(channel_idx, idx, *gain);
+            }
+        }
+    }
+
+    fn update_phase(&mut self, channel_idx: usize, idx: usize, phase: Sample) {
+        let phase = phase.rem_euclid(1.0);
+
+        if let Some(cfg_phase) = self.config.phases[channel_idx].get_mut(idx) {
+            *cfg_phase = phase;
+        }
+
+        if let Some(harmonic) = self.config.spectrum[channel_idx].get_mut(idx) {
+            let gain = harmonic.complex().norm() / HARMONIC_SERIES_BUFFER[idx].norm();
+
+            *harmonic = ComplexCfg::from_complex(HarmonicEditor::harmonic_value(idx, gain, phase));
+        }
+    }
+
+    pub fn set_phase(&mut self, harmonic_number: usize, phase: StereoSample) {
+        if self.ui_end.set_phase(harmonic_number, phase) {
+            let idx = harmonic_number.clamp(1, SPECTRAL_BUFFER_SIZE - 1);
+
+            for (channel_idx, phase) in phase.iter().enumerate() {
+                self.update_phase(channel_idx, idx, *phase);
+            }
+        }
+    }
+
+    pub fn set_phases_range(&mut self, from: usize, phases: &[StereoSample]) {
+        let from = from.clamp(1, SPECTRAL_BUFFER_SIZE - 1);
+
+        for (chunk_idx, chunk) in phases.chunks(HARMONICS_CHUNK_SIZE).enumerate() {
+            let chunk_from = from + chunk_idx * HARMONICS_CHUNK_SIZE;
+
+            if !self.ui_end.set_phases_range(chunk_from, chunk) {
+                break;
+            }
+
+            for (idx, phase) in (chunk_from..SPECTRAL_BUFFER_SIZE).zip(chunk) {
+                for (channel_idx, phase) in phase.iter().enumerate() {
+                    self.update_phase(channel_idx, idx, *phase);
                 }
             }
         }
     }
 
+    pub fn import_waveform(&mut self, path: &Path) -> Result<(), String> {
+        let spectrum =
+            read_waveform_spectrum(path).ok_or_else(|| "Failed to import waveform.".to_string())?;
+
+        // Half an import would leave the spectrum mixed with the previous one
+        let chunks = spectrum.phases[1..].len().div_ceil(HARMONICS_CHUNK_SIZE)
+            + spectrum.gains[1..].len().div_ceil(HARMONICS_CHUNK_SIZE);
+
+        if self.ui_end.free_slots() < chunks {
+            return Err("Too many pending edits, try again.".to_string());
+        }
+
+        // Phases go first, levels are applied on top of the stored phases
+        self.set_phases_range(1, &spectrum.phases[1..]);
+        self.set_harmonics_range(1, &spectrum.gains[1..]);
+        Ok(())
+    }
+
+    pub fn randomize_phases(&mut self) {
+        self.ui_end.randomize_phases();
+    }
+
+    pub fn zero_phases(&mut self) {
+        self.ui_end.zero_phases();
+    }
+
+    pub fn set_harmonics_range(&mut self, from: usize, gains: &[StereoSample]) {
+        let from = from.clamp(1, SPECTRAL_BUFFER_SIZE - 1);
+
+        for (chunk_idx, chunk) in gains.chunks(HARMONICS_CHUNK_SIZE).enumerate() {
+            let chunk_from = from + chunk_idx * HARMONICS_CHUNK_SIZE;
+
+            if !self.ui_end.set_harmonics_range(chunk_from, chunk) {
+                break;
+            }
+
+            for (idx, gain) in (chunk_from..SPECTRAL_BUFFER_SIZE).zip(chunk) {
+                for (channel_idx, gain) in gain.iter().enumerate() {
+                    self.update_harmonic(channel_idx, idx, *gain);
+                }
+            }
+        }
+    }
+
+    pub fn has_snapshot(&self, slot: SnapshotSlot) -> bool {
+        self.config.snapshots[slot.idx()].is_some()
+    }
+
+    pub fn store_snapshot(&mut self, slot: SnapshotSlot) {
+        if self.ui_end.store_snapshot(slot) {
+            self.config.snapshots[slot.idx()] = Some(SnapshotConfig {
+                spectrum: self.config.spectrum.clone(),
+                phases: self.config.phases.clone(),
+            });
+        }
+    }
+
+    pub fn recall_snapshot(&mut self, slot: SnapshotSlot) {
+        self.ui_end.recall_snapshot(slot);
+    }
+
+    pub fn set_morph(&mut self, morph: bool) {
+        if self.ui_end.set_morph(morph) {
+            self.config.morph = morph;
+        }
+    }
+
+    pub fn set_param(&mut self, input: Input, value: StereoSample) {
+        if self.ui_end.set_param(input, value) && input == Input::Blend {
+            self.config.blend = value;
+        }
+    }
+
     pub fn set_selected(&mut self, params: SetParams) {
         self.ui_end.set_selected(params);
     }
@@ -71,13 +185,15 @@ impl HarmonicEditorUiBridge {
 
 impl ModuleUiBridge for HarmonicEditorUiBridge {
     fn update(&mut self) {
-        while let Some(update) = self.ui_end.pop_update() {
-            match update {
-                UiUpdate::RefreshState => {
-                    self.sync();
-                }
-            }
+        // Random phases, filters and recalled snapshots are computed on the audio thread
+        if let Some(state) = self.ui_end.updated_state() {
+            self.config.spectrum = HarmonicEditor::spectrum_config(&state.harmonics);
+            self.config.phases = state.phases.map(|channel| channel.to_vec());
+            self.received_states = self.received_states.wrapping_add(1);
         }
     }
-}
 
+    fn edits(&self) -> u32 {
+        self.ui_end.edits().wrapping_add(self.received_states)
+    }
+}
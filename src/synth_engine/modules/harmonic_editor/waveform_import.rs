@@ -0,0 +1,85 @@
+use std::{f32, ops::Range, path::Path};
+
+use itertools::izip;
+use realfft::RealFftPlanner;
+
+use crate::synth_engine::{
+    Sample, StereoSample,
+    buffer::{HARMONIC_SERIES_BUFFER, SPECTRAL_BUFFER_SIZE},
+    routing::NUM_CHANNELS,
+    sample_analysis::{DETECTION_WINDOW, detect_period, read_channels},
+    wavetable::WAVETABLE_FRAME_SIZE,
+};
+
+// Shorter files are taken as a single cycle as is
+const MAX_SINGLE_CYCLE: usize = 4096;
+const MIN_GAIN: Sample = 1e-6;
+
+pub struct ImportedSpectrum {
+    pub gains: Vec<StereoSample>,
+    pub phases: Vec<StereoSample>, // Normalized, relative to the harmonic series
+}
+
+fn detect_cycle(samples: &[Sample]) -> Option<Range<usize>> {
+    if samples.len() <= MAX_SINGLE_CYCLE {
+        return Some(0..samples.len());
+    }
+
+    // Wavetables, only the first frame is imported
+    if samples.len().is_multiple_of(WAVETABLE_FRAME_SIZE) {
+        return Some(0..WAVETABLE_FRAME_SIZE);
+    }
+
+    let window = &samples[..samples.len().min(DETECTION_WINDOW)];
+    let period = detect_period(window)?;
+
+    let start = window
+        .windows(2)
+        .take(window.len() - period)
+        .position(|pair| pair[0] <= 0.0 && pair[1] > 0.0)
+        .unwrap_or(0);
+
+    Some(start..start + period)
+}
+
+fn analyze_cycle(channels: &[Vec<Sample>; NUM_CHANNELS], cycle: Range<usize>) -> ImportedSpectrum {
+    let fft = RealFftPlanner::<Sample>::new().plan_fft_forward(cycle.len());
+    let scale = (cycle.len() as Sample).recip();
+    let mut gains = vec![StereoSample::ZERO; SPECTRAL_BUFFER_SIZE];
+    let mut phases = vec![StereoSample::ZERO; SPECTRAL_BUFFER_SIZE];
+
+    for (channel_idx, channel) in channels.iter().enumerate() {
+        let mut input = channel[cycle.clone()].to_vec();
+        let mut output = fft.make_output_vec();
+
+        fft.process(&mut input, &mut output).unwrap();
+
+        // Bins at and above Nyquist would be doubled by the inverse transform
+        let num_bins = cycle.len().div_ceil(2);
+
+        for (idx, (gain, phase, bin)) in izip!(&mut gains, &mut phases, &output[..num_bins])
+            .enumerate()
+            .skip(1)
+        {
+            let relative = *bin * scale / HARMONIC_SERIES_BUFFER[idx];
+
+            if relative.norm() > MIN_GAIN {
+                gain[channel_idx] = relative.norm();
+                phase[channel_idx] = (relative.arg() / f32::consts::TAU).rem_euclid(1.0);
+            }
+        }
+    }
+
+    ImportedSpectrum { gains, phases }
+}
+
+pub fn read_waveform_spectrum(path: &Path) -> Option<ImportedSpectrum> {
+    let wav = read_channels(path)?;
+    let cycle = detect_cycle(&wav.mono())?;
+
+    if cycle.len() < 2 {
+        return None;
+    }
+
+    Some(analyze_cycle(&wav.channels, cycle))
+}
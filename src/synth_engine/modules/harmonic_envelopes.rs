@@ -0,0 +1,408 @@
+use std::array;
+
+use itertools::izip;
+use nih_plug::util::db_to_gain_fast;
+
+mod config;
+mod link;
+mod ui_bridge;
+
+pub use config::HarmonicEnvelopesConfig;
+use link::{AudioEnd, UiEnd, UiEvent, create_link_pair};
+pub use ui_bridge::HarmonicEnvelopesUiBridge;
+
+use crate::synth_engine::{
+    RandomParam, Randomizer, StereoSample,
+    buffer::{SPECTRAL_BUFFER_SIZE, SPECTRUM_BITS, VoicesLayout, new_voices_layout},
+    routing::{
+        DataType, Input, InputMeta, InputSlots, InputSource, ModuleId, NUM_CHANNELS,
+        ProcessContext, SpectralInputSlot, SpectralOutput, SpectralRouterType, VoiceEvent,
+        VoiceRouter,
+    },
+    synth_module::SynthModule,
+    types::Sample,
+};
+
+pub const MAX_BANDS: usize = SPECTRUM_BITS;
+const MIN_DECAY: Sample = 0.001;
+const MAX_DECAY_SPREAD: Sample = 2.0;
+// The decay time is how long a band takes to fall by 60 dB
+const DECAY_DB: Sample = -60.0;
+const MIN_GAIN_DB: Sample = -120.0;
+
+struct Params {
+    bands: usize,
+    keytracking: Sample,
+}
+
+impl Params {
+    fn from_config(c: &HarmonicEnvelopesConfig) -> Self {
+        Self {
+            bands: c.bands.clamp(1, MAX_BANDS),
+            keytracking: c.keytracking.clamp(0.0, 1.0),
+        }
+    }
+}
+
+struct ChannelParams {
+    attack: Sample,
+    decay: Sample,
+    decay_spread: Sample,
+}
+
+impl ChannelParams {
+    fn from_config(c: &HarmonicEnvelopesConfig, channel_idx: usize) -> Self {
+        Self {
+            attack: c.attack[channel_idx],
+            decay: c.decay[channel_idx],
+            decay_spread: c.decay_spread[channel_idx],
+        }
+    }
+}
+
+#[derive(Default)]
+struct VoiceState {
+    triggered: bool,
+    pitch: Sample,
+    time: Sample,
+}
+
+pub struct Inputs {
+    spectrum: Option<usize>,
+    attack: InputSlots,
+    decay: InputSlots,
+    decay_spread: InputSlots,
+}
+
+impl Default for Inputs {
+    fn default() -> Self {
+        Self {
+            spectrum: None,
+            attack: InputSlots::empty(Input::Attack),
+            decay: InputSlots::empty(Input::Decay),
+            decay_spread: InputSlots::empty(Input::DecaySpread),
+        }
+    }
+}
+
+impl Inputs {
+    fn from_slots(inputs: &[InputSlots], spectral_inputs: &[SpectralInputSlot]) -> Self {
+        let mut result = Self::default();
+
+        for input in inputs {
+            match input.input_type {
+                Input::Attack => result.attack = input.clone(),
+                Input::Decay => result.decay = input.clone(),
+                Input::DecaySpread => result.decay_spread = input.clone(),
+                _ => (),
+            }
+        }
+
+        for input in spectral_inputs {
+            if matches!(input.input_type, Input::Spectrum) {
+                result.spectrum = Some(input.slot);
+            }
+        }
+
+        result
+    }
+
+    fn update_source(&mut self, input_type: Input, src_slot: usize, source: &InputSource) {
+        match input_type {
+            Input::Attack => self.attack.update_source(src_slot, source),
+            Input::Decay => self.decay.update_source(src_slot, source),
+            Input::DecaySpread => self.decay_spread.update_source(src_slot, source),
+            _ => (),
+        }
+    }
+}
+
+type Router<'v, 'f, 'c> = VoiceRouter<'v, 'f, 'c, SpectralRouterType>;
+
+// Splits the spectrum into octave-spaced bands, each one gets its own attack/decay envelope.
+// The decay shortens by the spread for every octave up, keytracking shortens it for higher notes.
+pub struct HarmonicEnvelopes {
+    id: ModuleId,
+    bypass: bool,
+    params: Params,
+    channel_params: [ChannelParams; NUM_CHANNELS],
+    audio_end: AudioEnd,
+    ui_end: Option<UiEnd>,
+    inputs: Inputs,
+    output_slot: usize,
+    harmonic_bands: [u8; SPECTRAL_BUFFER_SIZE],
+    voices: VoicesLayout<VoiceState>,
+}
+
+impl HarmonicEnvelopes {
+    pub fn new(id: ModuleId) -> Self {
+        Self::from_config(&HarmonicEnvelopesConfig {
+            id,
+            ..HarmonicEnvelopesConfig::default()
+        })
+    }
+
+    pub fn from_config(config: &HarmonicEnvelopesConfig) -> Self {
+        let (audio_end, ui_end) = create_link_pair();
+        let params = Params::from_config(config);
+
+        Self {
+            id: config.id,
+            bypass: config.bypass,
+            harmonic_bands: Self::harmonic_bands(params.bands),
+            params,
+            channel_params: array::from_fn(|channel_idx| {
+                ChannelParams::from_config(config, channel_idx)
+            }),
+            audio_end,
+            ui_end: Some(ui_end),
+            inputs: Inputs::default(),
+            output_slot: usize::MAX,
+            voices: new_voices_layout(),
+        }
+    }
+
+    pub fn get_config(&self) -> HarmonicEnvelopesConfig {
+        HarmonicEnvelopesConfig {
+            id: self.id,
+            bypass: self.bypass,
+            bands: self.params.bands,
+            keytracking: self.params.keytracking,
+            attack: get_stereo_param!(self, attack),
+            decay: get_stereo_param!(self, decay),
+            decay_spread: get_stereo_param!(self, decay_spread),
+        }
+    }
+
+    pub fn randomize(
+        config: &mut HarmonicEnvelopesConfig,
+        randomizer: &mut Randomizer,
+        unlocked: impl Fn(RandomParam) -> bool,
+    ) {
+        if unlocked(RandomParam::Attack) {
+            config.attack = randomizer.time(config.attack);
+        }
+        if unlocked(RandomParam::Decay) {
+            config.decay = randomizer.time(config.decay);
+        }
+        if unlocked(RandomParam::Spread) {
+            config.decay_spread = randomizer.stereo(config.decay_spread, 0.0, MAX_DECAY_SPREAD);
+        }
+    }
+
+    pub fn set_bands(&mut self, bands: usize) {
+        self.params.bands = bands.clamp(1, MAX_BANDS);
+        self.harmonic_bands = Self::harmonic_bands(self.params.bands);
+    }
+
+    set_mono_param!(
+        set_keytracking,
+        keytracking,
+        Sample,
+        keytracking.clamp(0.0, 1.0)
+    );
+
+    set_stereo_param!(set_attack, attack, attack.max(0.0));
+    set_stereo_param!(set_decay, decay, decay.max(MIN_DECAY));
+    set_stereo_param!(
+        set_decay_spread,
+        decay_spread,
+        decay_spread.clamp(0.0, MAX_DECAY_SPREAD)
+    );
+
+    // Lowest octave of the band, relative to the fundamental
+    fn band_octave(band: usize, bands: usize) -> Sample {
+        (band * SPECTRUM_BITS) as Sample / bands as Sample
+    }
+
+    fn harmonic_bands(bands: usize) -> [u8; SPECTRAL_BUFFER_SIZE] {
+        array::from_fn(|harmonic| {
+            let octave = (harmonic.max(1) as Sample).log2();
+
+            ((octave * bands as Sample / SPECTRUM_BITS as Sample) as usize).min(bands - 1) as u8
+        })
+    }
+
+    fn envelope_level(time: Sample, attack: Sample, decay: Sample) -> Sample {
+        if time < attack {
+            time / attack
+        } else {
+            db_to_gain_fast((DECAY_DB * (time - attack) / decay).max(MIN_GAIN_DB))
+        }
+    }
+
+    fn process_voice(
+        &mut self,
+        output: &mut VoicesLayout<SpectralOutput>,
+        mut router: Router<'_, '_, '_>,
+    ) {
+        let channel_idx = router.channel_idx();
+        let voice_idx = router.voice_idx();
+        let inputs = &self.inputs;
+        let bands = self.params.bands;
+        let channel = &self.channel_params[channel_idx];
+        let voice = &mut self.voices[channel_idx][voice_idx];
+
+        let attack = router
+            .scalar_param(&inputs.attack, channel.attack, voice.triggered)
+            .max(0.0);
+        let decay = router
+            .scalar_param(&inputs.decay, channel.decay, voice.triggered)
+            .max(MIN_DECAY)
+            * (-self.params.keytracking * voice.pitch).exp2();
+        let decay_spread = router
+            .scalar_param(&inputs.decay_spread, channel.decay_spread, voice.triggered)
+            .clamp(0.0, MAX_DECAY_SPREAD);
+
+        let mut band_levels = [0.0; MAX_BANDS];
+
+        for (band, level) in band_levels.iter_mut().take(bands).enumerate() {
+            let band_decay = decay * (-decay_spread * Self::band_octave(band, bands)).exp2();
+
+            *level = Self::envelope_level(voice.time, attack, band_decay);
+        }
+
+        // Bands stay on the same harmonics whatever the spectral size, a smaller one only drops the top
+        let bins = router.params().spectral_bins;
+        let voice_output = &mut output[channel_idx][voice_idx].advance()[..bins];
+        let input = router.spectral(inputs.spectrum, voice.triggered);
+
+        for (out, input, band) in izip!(voice_output.iter_mut(), input, &self.harmonic_bands) {
+            *out = input * band_levels[*band as usize];
+        }
+
+        if voice.triggered {
+            voice.triggered = false;
+
+            self.process_voice(output, router);
+        } else {
+            voice.time += router.samples() as Sample / router.sample_rate();
+        }
+    }
+}
+
+impl SynthModule for HarmonicEnvelopes {
+    fn id(&self) -> ModuleId {
+        self.id
+    }
+
+    fn bypass(&self) -> bool {
+        self.bypass
+    }
+
+    fn set_bypass(&mut self, bypass: bool) {
+        self.bypass = bypass;
+    }
+
+    fn inputs(&self) -> &'static [InputMeta] {
+        static INPUTS: &[InputMeta] = &[
+            InputMeta::spectral(Input::Spectrum),
+            InputMeta::control(Input::Attack),
+            InputMeta::control(Input::Decay),
+            InputMeta::control(Input::DecaySpread),
+        ];
+
+        INPUTS
+    }
+
+    fn output_type(&self) -> DataType {
+        DataType::Spectral
+    }
+
+    fn output_slot(&self) -> usize {
+        self.output_slot
+    }
+
+    fn set_output_slot(&mut self, slot: usize) {
+        self.output_slot = slot;
+    }
+
+    fn set_input_slots(&mut self, inputs: &[InputSlots], spectral_inputs: &[SpectralInputSlot]) {
+        self.inputs = Inputs::from_slots(inputs, spectral_inputs);
+    }
+
+    fn process_events(&mut self, events: &[VoiceEvent]) {
+        for channel in self.voices.iter_mut() {
+            for event in events {
+                match event {
+                    VoiceEvent::Trigger {
+                        voice_idx, pitch, ..
+                    } => {
+                        channel[*voice_idx] = VoiceState {
+                            triggered: true,
+                            pitch: *pitch,
+                            time: 0.0,
+                        };
+                    }
+                    VoiceEvent::Update {
+                        voice_idx, pitch, ..
+                    }
+                    | VoiceEvent::Retune { voice_idx, pitch } => {
+                        channel[*voice_idx].pitch = *pitch;
+                    }
+                    _ => (),
+                }
+            }
+        }
+    }
+
+    fn update_input_source(&mut self, input_type: Input, src_slot: usize, source: &InputSource) {
+        self.inputs.update_source(input_type, src_slot, source);
+    }
+
+    fn set_input_param(&mut self, input: Input, value: StereoSample) {
+        match input {
+            Input::Attack => self.set_attack(value),
+            Input::Decay => self.set_decay(value),
+            Input::DecaySpread => self.set_decay_spread(value),
+            _ => (),
+        }
+    }
+
+    fn process_ui_events(&mut self) {
+        while let Some(event) = self.audio_end.pop_event() {
+            match event {
+                UiEvent::InputParam { input, value } => self.set_input_param(input, value),
+                UiEvent::Bands(bands) => self.set_bands(bands),
+                UiEvent::Keytracking(keytracking) => self.set_keytracking(keytracking),
+            }
+        }
+    }
+
+    fn process(&mut self, ctx: &mut ProcessContext) {
+        ctx.for_spectral(self.id, self.output_slot, |router, output| {
+            let num_active_voices = router.params().active_voices.len();
+            let spectrum_channels = router.params().spectrum_channels;
+
+            for channel_idx in 0..spectrum_channels {
+                for seq_idx in 0..num_active_voices {
+                    let voice_idx = router.params().active_voices[seq_idx];
+
+                    self.process_voice(output, router.for_voice(channel_idx, voice_idx, seq_idx));
+                }
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bands_split_harmonics_by_octave() {
+        let bands = HarmonicEnvelopes::harmonic_bands(5);
+
+        assert_eq!(bands[1], 0);
+        assert_eq!(bands[3], 0);
+        assert_eq!(bands[4], 1);
+        assert_eq!(bands[SPECTRAL_BUFFER_SIZE - 1], 4);
+    }
+
+    #[test]
+    fn envelope_attacks_then_decays_by_60_db() {
+        assert!((HarmonicEnvelopes::envelope_level(0.05, 0.1, 1.0) - 0.5).abs() < 1e-6);
+        assert!((HarmonicEnvelopes::envelope_level(0.1, 0.1, 1.0) - 1.0).abs() < 1e-4);
+        assert!((HarmonicEnvelopes::envelope_level(1.1, 0.1, 1.0) - 0.001).abs() < 1e-4);
+    }
+}
@@ -0,0 +1,29 @@
+use serde::{Deserialize, Serialize};
+
+use crate::synth_engine::{ModuleId, Sample, StereoSample};
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct HarmonicEnvelopesConfig {
+    pub id: ModuleId,
+    #[serde(default)]
+    pub bypass: bool,
+    pub bands: usize,
+    pub keytracking: Sample,
+    pub attack: StereoSample,
+    pub decay: StereoSample,
+    pub decay_spread: StereoSample,
+}
+
+impl Default for HarmonicEnvelopesConfig {
+    fn default() -> Self {
+        Self {
+            id: -1,
+            bypass: false,
+            bands: 5,
+            keytracking: 0.0,
+            attack: 0.0.into(),
+            decay: 2.0.into(),
+            decay_spread: 0.5.into(),
+        }
+    }
+}
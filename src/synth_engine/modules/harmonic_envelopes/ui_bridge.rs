@@ -0,0 +1,55 @@
+use crate::synth_engine::{Input, Sample, StereoSample, synth_module::ModuleUiBridge};
+
+use super::link::UiEnd;
+use super::{HarmonicEnvelopes, HarmonicEnvelopesConfig};
+
+pub struct HarmonicEnvelopesUiBridge {
+    ui_end: UiEnd,
+    config: HarmonicEnvelopesConfig,
+}
+
+impl HarmonicEnvelopesUiBridge {
+    pub fn try_new(envelopes: &mut HarmonicEnvelopes) -> Option<Self> {
+        Some(Self {
+            ui_end: envelopes.ui_end.take()?,
+            config: envelopes.get_config(),
+        })
+    }
+
+    pub fn config(&self) -> &HarmonicEnvelopesConfig {
+        &self.config
+    }
+
+    pub fn set_param(&mut self, input: Input, value: StereoSample) {
+        if !self.ui_end.set_param(input, value) {
+            return;
+        }
+
+        match input {
+            Input::Attack => self.config.attack = value,
+            Input::Decay => self.config.decay = value,
+            Input::DecaySpread => self.config.decay_spread = value,
+            _ => (),
+        }
+    }
+
+    pub fn set_bands(&mut self, bands: usize) {
+        if self.ui_end.set_bands(bands) {
+            self.config.bands = bands;
+        }
+    }
+
+    pub fn set_keytracking(&mut self, keytracking: Sample) {
+        if self.ui_end.set_keytracking(keytracking) {
+            self.config.keytracking = keytracking;
+        }
+    }
+}
+
+impl ModuleUiBridge for HarmonicEnvelopesUiBridge {
+    fn update(&mut self) {}
+
+    fn edits(&self) -> u32 {
+        self.ui_end.edits()
+    }
+}
@@ -0,0 +1,365 @@
+use std::array;
+
+mod config;
+mod link;
+mod ui_bridge;
+
+pub use config::{HarmonicShiftConfig, HarmonicShiftMode};
+use link::{AudioEnd, UiEnd, UiEvent, create_link_pair};
+pub use ui_bridge::HarmonicShiftUiBridge;
+
+use crate::synth_engine::{
+    RandomParam, Randomizer, StereoSample,
+    buffer::{VoicesLayout, new_voices_layout},
+    routing::{
+        DataType, Input, InputMeta, InputSlots, InputSource, ModuleId, NUM_CHANNELS,
+        ProcessContext, SpectralInputSlot, SpectralOutput, SpectralRouterType, VoiceEvent,
+        VoiceRouter,
+    },
+    synth_module::SynthModule,
+    types::{ComplexSample, Sample},
+};
+
+pub const MAX_SHIFT: Sample = 128.0; // Harmonics
+const RANDOM_SHIFT: Sample = 16.0; // Harmonics, further shifts leave little of the spectrum
+
+#[derive(Clone, Copy, PartialEq)]
+struct Params {
+    mode: HarmonicShiftMode,
+}
+
+struct ChannelParams {
+    shift: Sample,
+}
+
+impl ChannelParams {
+    fn from_config(c: &HarmonicShiftConfig, channel_idx: usize) -> Self {
+        Self {
+            shift: c.shift[channel_idx],
+        }
+    }
+}
+
+// Everything the latest output was computed from
+#[derive(Clone, Copy, PartialEq)]
+struct AppliedState {
+    spectrum_revision: u32,
+    bins: usize,
+    params: Params,
+    shift: Sample,
+}
+
+#[derive(Default)]
+struct VoiceState {
+    triggered: bool,
+    applied: Option<AppliedState>,
+}
+
+pub struct Inputs {
+    spectrum: Option<usize>,
+    shift: InputSlots,
+}
+
+impl Default for Inputs {
+    fn default() -> Self {
+        Self {
+            spectrum: None,
+            shift: InputSlots::empty(Input::HarmonicShift),
+        }
+    }
+}
+
+impl Inputs {
+    fn from_slots(inputs: &[InputSlots], spectral_inputs: &[SpectralInputSlot]) -> Self {
+        let mut result = Self::default();
+
+        for input in inputs {
+            if input.input_type == Input::HarmonicShift {
+                result.shift = input.clone();
+            }
+        }
+
+        for input in spectral_inputs {
+            if matches!(input.input_type, Input::Spectrum) {
+                result.spectrum = Some(input.slot);
+            }
+        }
+
+        result
+    }
+
+    fn update_source(&mut self, input_type: Input, src_slot: usize, source: &InputSource) {
+        if input_type == Input::HarmonicShift {
+            self.shift.update_source(src_slot, source);
+        }
+    }
+}
+
+type Router<'v, 'f, 'c> = VoiceRouter<'v, 'f, 'c, SpectralRouterType>;
+
+// Moves every harmonic up or down by a number of bins, optionally swapping odd and even harmonics first.
+// A fractional shift splits each harmonic across the two nearest bins.
+pub struct HarmonicShift {
+    id: ModuleId,
+    bypass: bool,
+    params: Params,
+    channel_params: [ChannelParams; NUM_CHANNELS],
+    audio_end: AudioEnd,
+    ui_end: Option<UiEnd>,
+    inputs: Inputs,
+    output_slot: usize,
+    voices: VoicesLayout<VoiceState>,
+}
+
+impl HarmonicShift {
+    pub fn new(id: ModuleId) -> Self {
+        Self::from_config(&HarmonicShiftConfig {
+            id,
+            ..HarmonicShiftConfig::default()
+        })
+    }
+
+    pub fn from_config(config: &HarmonicShiftConfig) -> Self {
+        let (audio_end, ui_end) = create_link_pair();
+
+        Self {
+            id: config.id,
+            bypass: config.bypass,
+            params: Params { mode: config.mode },
+            channel_params: array::from_fn(|channel_idx| {
+                ChannelParams::from_config(config, channel_idx)
+            }),
+            audio_end,
+            ui_end: Some(ui_end),
+            inputs: Inputs::default(),
+            output_slot: usize::MAX,
+            voices: new_voices_layout(),
+        }
+    }
+
+    pub fn get_config(&self) -> HarmonicShiftConfig {
+        HarmonicShiftConfig {
+            id: self.id,
+            bypass: self.bypass,
+            mode: self.params.mode,
+            shift: get_stereo_param!(self, shift),
+        }
+    }
+
+    pub fn randomize(
+        config: &mut HarmonicShiftConfig,
+        randomizer: &mut Randomizer,
+        unlocked: impl Fn(RandomParam) -> bool,
+    ) {
+        if unlocked(RandomParam::Shift) {
+            config.shift = randomizer.stereo(config.shift, -RANDOM_SHIFT, RANDOM_SHIFT);
+        }
+    }
+
+    set_mono_param!(set_mode, mode, HarmonicShiftMode);
+
+    set_stereo_param!(set_shift, shift, (*shift).clamp(-MAX_SHIFT, MAX_SHIFT));
+
+    // Where harmonic n lands before the shift
+    fn remap(mode: HarmonicShiftMode, harmonic: usize) -> usize {
+        match mode {
+            HarmonicShiftMode::Shift => harmonic,
+            HarmonicShiftMode::SwapOddEven if harmonic % 2 == 1 => harmonic + 1,
+            HarmonicShiftMode::SwapOddEven => harmonic - 1,
+        }
+    }
+
+    fn apply_shift(
+        output: &mut [ComplexSample],
+        input: &[ComplexSample],
+        mode: HarmonicShiftMode,
+        shift: Sample,
+    ) {
+        output.fill(ComplexSample::ZERO);
+        output[0] = input[0];
+
+        let whole = shift.floor();
+        let frac = shift - whole;
+        let whole = whole as isize;
+
+        for (harmonic, bin) in input.iter().enumerate().skip(1) {
+            let lower = Self::remap(mode, harmonic) as isize + whole;
+
+            // Harmonics moved below the fundamental or past the top one are dropped
+            for (target, gain) in [(lower, 1.0 - frac), (lower + 1, frac)] {
+                if target >= 1
+                    && gain > 0.0
+                    && let Some(out) = output.get_mut(target as usize)
+                {
+                    *out += bin * gain;
+                }
+            }
+        }
+    }
+
+    fn process_voice(
+        &mut self,
+        output: &mut VoicesLayout<SpectralOutput>,
+        mut router: Router<'_, '_, '_>,
+    ) {
+        let channel_idx = router.channel_idx();
+        let voice_idx = router.voice_idx();
+        let inputs = &self.inputs;
+        let channel = &self.channel_params[channel_idx];
+        let voice = &mut self.voices[channel_idx][voice_idx];
+
+        let shift = router
+            .scalar_param(&inputs.shift, channel.shift, voice.triggered)
+            .clamp(-MAX_SHIFT, MAX_SHIFT);
+
+        let bins = router.params().spectral_bins;
+        let applied = Some(AppliedState {
+            spectrum_revision: router.spectral_revision(inputs.spectrum),
+            bins,
+            params: self.params,
+            shift,
+        });
+
+        // Unchanged input and parameters, downstream modules keep reading the latest spectrum
+        if !voice.triggered && voice.applied == applied {
+            return;
+        }
+
+        voice.applied = applied;
+
+        let voice_output = &mut output[channel_idx][voice_idx].advance()[..bins];
+        let input = &router.spectral(inputs.spectrum, voice.triggered)[..bins];
+
+        Self::apply_shift(voice_output, input, self.params.mode, shift);
+
+        if voice.triggered {
+            voice.triggered = false;
+            voice.applied = None;
+
+            self.process_voice(output, router);
+        }
+    }
+}
+
+impl SynthModule for HarmonicShift {
+    fn id(&self) -> ModuleId {
+        self.id
+    }
+
+    fn bypass(&self) -> bool {
+        self.bypass
+    }
+
+    fn set_bypass(&mut self, bypass: bool) {
+        self.bypass = bypass;
+    }
+
+    fn inputs(&self) -> &'static [InputMeta] {
+        static INPUTS: &[InputMeta] = &[
+            InputMeta::spectral(Input::Spectrum),
+            InputMeta::control(Input::HarmonicShift),
+        ];
+
+        INPUTS
+    }
+
+    fn output_type(&self) -> DataType {
+        DataType::Spectral
+    }
+
+    fn output_slot(&self) -> usize {
+        self.output_slot
+    }
+
+    fn set_output_slot(&mut self, slot: usize) {
+        self.output_slot = slot;
+    }
+
+    fn set_input_slots(&mut self, inputs: &[InputSlots], spectral_inputs: &[SpectralInputSlot]) {
+        self.inputs = Inputs::from_slots(inputs, spectral_inputs);
+
+        for voice in self.voices.iter_mut().flatten() {
+            voice.applied = None;
+        }
+    }
+
+    fn process_events(&mut self, events: &[VoiceEvent]) {
+        for channel in self.voices.iter_mut() {
+            for event in events {
+                if let VoiceEvent::Trigger { voice_idx, .. } = event {
+                    channel[*voice_idx].triggered = true;
+                }
+            }
+        }
+    }
+
+    fn update_input_source(&mut self, input_type: Input, src_slot: usize, source: &InputSource) {
+        self.inputs.update_source(input_type, src_slot, source);
+    }
+
+    fn set_input_param(&mut self, input: Input, value: StereoSample) {
+        if input == Input::HarmonicShift {
+            self.set_shift(value);
+        }
+    }
+
+    fn process_ui_events(&mut self) {
+        while let Some(event) = self.audio_end.pop_event() {
+            match event {
+                UiEvent::InputParam { input, value } => self.set_input_param(input, value),
+                UiEvent::Mode(mode) => self.set_mode(mode),
+            }
+        }
+    }
+
+    fn process(&mut self, ctx: &mut ProcessContext) {
+        ctx.for_spectral(self.id, self.output_slot, |router, output| {
+            let num_active_voices = router.params().active_voices.len();
+            let spectrum_channels = router.params().spectrum_channels;
+
+            for channel_idx in 0..spectrum_channels {
+                for seq_idx in 0..num_active_voices {
+                    let voice_idx = router.params().active_voices[seq_idx];
+
+                    self.process_voice(output, router.for_voice(channel_idx, voice_idx, seq_idx));
+                }
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::synth_engine::buffer::zero_spectral_buffer;
+
+    #[test]
+    fn shift_moves_harmonics_by_bins() {
+        let mut input = zero_spectral_buffer();
+        let mut output = zero_spectral_buffer();
+
+        input[2] = ComplexSample::new(1.0, 0.0);
+        input[5] = ComplexSample::new(0.5, 0.0);
+        HarmonicShift::apply_shift(&mut output, &input, HarmonicShiftMode::Shift, -2.5);
+
+        // The 2nd harmonic falls below the fundamental, the 5th is split between the 2nd and 3rd bins
+        assert_eq!(output[1], ComplexSample::ZERO);
+        assert!((output[2].re - 0.25).abs() < 1e-6);
+        assert!((output[3].re - 0.25).abs() < 1e-6);
+    }
+
+    #[test]
+    fn swap_exchanges_odd_and_even_harmonics() {
+        let mut input = zero_spectral_buffer();
+        let mut output = zero_spectral_buffer();
+
+        input[1] = ComplexSample::new(1.0, 0.0);
+        input[4] = ComplexSample::new(0.5, 0.0);
+        HarmonicShift::apply_shift(&mut output, &input, HarmonicShiftMode::SwapOddEven, 0.0);
+
+        assert_eq!(output[2], input[1]);
+        assert_eq!(output[3], input[4]);
+        assert_eq!(output[1], ComplexSample::ZERO);
+        assert_eq!(output[4], ComplexSample::ZERO);
+    }
+}
@@ -0,0 +1,30 @@
+use serde::{Deserialize, Serialize};
+
+use crate::synth_engine::{ModuleId, StereoSample};
+
+#[derive(Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum HarmonicShiftMode {
+    #[default]
+    Shift,
+    SwapOddEven,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct HarmonicShiftConfig {
+    pub id: ModuleId,
+    #[serde(default)]
+    pub bypass: bool,
+    pub mode: HarmonicShiftMode,
+    pub shift: StereoSample,
+}
+
+impl Default for HarmonicShiftConfig {
+    fn default() -> Self {
+        Self {
+            id: -1,
+            bypass: false,
+            mode: HarmonicShiftMode::default(),
+            shift: 0.0.into(),
+        }
+    }
+}
@@ -0,0 +1,46 @@
+use crate::synth_engine::{Input, StereoSample, synth_module::ModuleUiBridge};
+
+use super::link::UiEnd;
+use super::{HarmonicShift, HarmonicShiftConfig, HarmonicShiftMode};
+
+pub struct HarmonicShiftUiBridge {
+    ui_end: UiEnd,
+    config: HarmonicShiftConfig,
+}
+
+impl HarmonicShiftUiBridge {
+    pub fn try_new(shift: &mut HarmonicShift) -> Option<Self> {
+        Some(Self {
+            ui_end: shift.ui_end.take()?,
+            config: shift.get_config(),
+        })
+    }
+
+    pub fn config(&self) -> &HarmonicShiftConfig {
+        &self.config
+    }
+
+    pub fn set_mode(&mut self, mode: HarmonicShiftMode) {
+        if self.ui_end.set_mode(mode) {
+            self.config.mode = mode;
+        }
+    }
+
+    pub fn set_param(&mut self, input: Input, value: StereoSample) {
+        if !self.ui_end.set_param(input, value) {
+            return;
+        }
+
+        if input == Input::HarmonicShift {
+            self.config.shift = value;
+        }
+    }
+}
+
+impl ModuleUiBridge for HarmonicShiftUiBridge {
+    fn update(&mut self) {}
+
+    fn edits(&self) -> u32 {
+        self.ui_end.edits()
+    }
+}
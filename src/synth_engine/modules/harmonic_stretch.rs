@@ -0,0 +1,349 @@
+use std::array;
+
+mod config;
+mod link;
+mod ui_bridge;
+
+pub use config::HarmonicStretchConfig;
+use link::{AudioEnd, UiEnd, UiEvent, create_link_pair};
+pub use ui_bridge::HarmonicStretchUiBridge;
+
+use crate::synth_engine::{
+    RandomParam, Randomizer, StereoSample,
+    buffer::{VoicesLayout, new_voices_layout},
+    routing::{
+        DataType, Input, InputMeta, InputSlots, InputSource, ModuleId, NUM_CHANNELS,
+        ProcessContext, SpectralInputSlot, SpectralOutput, SpectralRouterType, VoiceEvent,
+        VoiceRouter,
+    },
+    synth_module::SynthModule,
+    types::{ComplexSample, Sample},
+};
+
+pub const MAX_STRETCH: Sample = 0.5;
+
+struct ChannelParams {
+    stretch: Sample,
+}
+
+impl ChannelParams {
+    fn from_config(c: &HarmonicStretchConfig, channel_idx: usize) -> Self {
+        Self {
+            stretch: c.stretch[channel_idx],
+        }
+    }
+}
+
+// Everything the latest output was computed from
+#[derive(Clone, Copy, PartialEq)]
+struct AppliedState {
+    spectrum_revision: u32,
+    bins: usize,
+    stretch: Sample,
+}
+
+#[derive(Default)]
+struct VoiceState {
+    triggered: bool,
+    applied: Option<AppliedState>,
+}
+
+pub struct Inputs {
+    spectrum: Option<usize>,
+    stretch: InputSlots,
+}
+
+impl Default for Inputs {
+    fn default() -> Self {
+        Self {
+            spectrum: None,
+            stretch: InputSlots::empty(Input::Stretch),
+        }
+    }
+}
+
+impl Inputs {
+    fn from_slots(inputs: &[InputSlots], spectral_inputs: &[SpectralInputSlot]) -> Self {
+        let mut result = Self::default();
+
+        for input in inputs {
+            if input.input_type == Input::Stretch {
+                result.stretch = input.clone();
+            }
+        }
+
+        for input in spectral_inputs {
+            if matches!(input.input_type, Input::Spectrum) {
+                result.spectrum = Some(input.slot);
+            }
+        }
+
+        result
+    }
+
+    fn update_source(&mut self, input_type: Input, src_slot: usize, source: &InputSource) {
+        if input_type == Input::Stretch {
+            self.stretch.update_source(src_slot, source);
+        }
+    }
+}
+
+type Router<'v, 'f, 'c> = VoiceRouter<'v, 'f, 'c, SpectralRouterType>;
+
+// Moves harmonic n to n^(1 + stretch) like the inharmonicity of a stiff string.
+// The oscillator only plays whole harmonics, so a partial landing between two bins is split across them.
+pub struct HarmonicStretch {
+    id: ModuleId,
+    bypass: bool,
+    channel_params: [ChannelParams; NUM_CHANNELS],
+    audio_end: AudioEnd,
+    ui_end: Option<UiEnd>,
+    inputs: Inputs,
+    output_slot: usize,
+    voices: VoicesLayout<VoiceState>,
+}
+
+impl HarmonicStretch {
+    pub fn new(id: ModuleId) -> Self {
+        Self::from_config(&HarmonicStretchConfig {
+            id,
+            ..HarmonicStretchConfig::default()
+        })
+    }
+
+    pub fn from_config(config: &HarmonicStretchConfig) -> Self {
+        let (audio_end, ui_end) = create_link_pair();
+
+        Self {
+            id: config.id,
+            bypass: config.bypass,
+            channel_params: array::from_fn(|channel_idx| {
+                ChannelParams::from_config(config, channel_idx)
+            }),
+            audio_end,
+            ui_end: Some(ui_end),
+            inputs: Inputs::default(),
+            output_slot: usize::MAX,
+            voices: new_voices_layout(),
+        }
+    }
+
+    pub fn get_config(&self) -> HarmonicStretchConfig {
+        HarmonicStretchConfig {
+            id: self.id,
+            bypass: self.bypass,
+            stretch: get_stereo_param!(self, stretch),
+        }
+    }
+
+    pub fn randomize(
+        config: &mut HarmonicStretchConfig,
+        randomizer: &mut Randomizer,
+        unlocked: impl Fn(RandomParam) -> bool,
+    ) {
+        if unlocked(RandomParam::Stretch) {
+            config.stretch = randomizer.stereo(config.stretch, -MAX_STRETCH, MAX_STRETCH);
+        }
+    }
+
+    set_stereo_param!(
+        set_stretch,
+        stretch,
+        (*stretch).clamp(-MAX_STRETCH, MAX_STRETCH)
+    );
+
+    fn apply_stretch(output: &mut [ComplexSample], input: &[ComplexSample], stretch: Sample) {
+        if stretch == 0.0 {
+            output.copy_from_slice(input);
+            return;
+        }
+
+        let exponent = 1.0 + stretch;
+
+        output.fill(ComplexSample::ZERO);
+        output[0] = input[0];
+
+        for (harmonic, bin) in input.iter().enumerate().skip(1) {
+            let position = (harmonic as Sample).powf(exponent);
+            let lower = position as usize;
+
+            // Partials above the top harmonic would alias, the ones below the fundamental are dropped too
+            if lower == 0 || lower >= output.len() {
+                continue;
+            }
+
+            let frac = position - lower as Sample;
+
+            output[lower] += bin * (1.0 - frac);
+
+            if let Some(upper) = output.get_mut(lower + 1) {
+                *upper += bin * frac;
+            }
+        }
+    }
+
+    fn process_voice(
+        &mut self,
+        output: &mut VoicesLayout<SpectralOutput>,
+        mut router: Router<'_, '_, '_>,
+    ) {
+        let channel_idx = router.channel_idx();
+        let voice_idx = router.voice_idx();
+        let inputs = &self.inputs;
+        let channel = &self.channel_params[channel_idx];
+        let voice = &mut self.voices[channel_idx][voice_idx];
+
+        let stretch = router
+            .scalar_param(&inputs.stretch, channel.stretch, voice.triggered)
+            .clamp(-MAX_STRETCH, MAX_STRETCH);
+
+        let bins = router.params().spectral_bins;
+        let applied = Some(AppliedState {
+            spectrum_revision: router.spectral_revision(inputs.spectrum),
+            bins,
+            stretch,
+        });
+
+        // Unchanged input and parameters, downstream modules keep reading the latest spectrum
+        if !voice.triggered && voice.applied == applied {
+            return;
+        }
+
+        voice.applied = applied;
+
+        let voice_output = &mut output[channel_idx][voice_idx].advance()[..bins];
+        let input = &router.spectral(inputs.spectrum, voice.triggered)[..bins];
+
+        Self::apply_stretch(voice_output, input, stretch);
+
+        if voice.triggered {
+            voice.triggered = false;
+            voice.applied = None;
+
+            self.process_voice(output, router);
+        }
+    }
+}
+
+impl SynthModule for HarmonicStretch {
+    fn id(&self) -> ModuleId {
+        self.id
+    }
+
+    fn bypass(&self) -> bool {
+        self.bypass
+    }
+
+    fn set_bypass(&mut self, bypass: bool) {
+        self.bypass = bypass;
+    }
+
+    fn inputs(&self) -> &'static [InputMeta] {
+        static INPUTS: &[InputMeta] = &[
+            InputMeta::spectral(Input::Spectrum),
+            InputMeta::control(Input::Stretch),
+        ];
+
+        INPUTS
+    }
+
+    fn output_type(&self) -> DataType {
+        DataType::Spectral
+    }
+
+    fn output_slot(&self) -> usize {
+        self.output_slot
+    }
+
+    fn set_output_slot(&mut self, slot: usize) {
+        self.output_slot = slot;
+    }
+
+    fn set_input_slots(&mut self, inputs: &[InputSlots], spectral_inputs: &[SpectralInputSlot]) {
+        self.inputs = Inputs::from_slots(inputs, spectral_inputs);
+
+        for voice in self.voices.iter_mut().flatten() {
+            voice.applied = None;
+        }
+    }
+
+    fn process_events(&mut self, events: &[VoiceEvent]) {
+        for channel in self.voices.iter_mut() {
+            for event in events {
+                if let VoiceEvent::Trigger { voice_idx, .. } = event {
+                    channel[*voice_idx].triggered = true;
+                }
+            }
+        }
+    }
+
+    fn update_input_source(&mut self, input_type: Input, src_slot: usize, source: &InputSource) {
+        self.inputs.update_source(input_type, src_slot, source);
+    }
+
+    fn set_input_param(&mut self, input: Input, value: StereoSample) {
+        if input == Input::Stretch {
+            self.set_stretch(value);
+        }
+    }
+
+    fn process_ui_events(&mut self) {
+        while let Some(event) = self.audio_end.pop_event() {
+            match event {
+                UiEvent::InputParam { input, value } => self.set_input_param(input, value),
+            }
+        }
+    }
+
+    fn process(&mut self, ctx: &mut ProcessContext) {
+        ctx.for_spectral(self.id, self.output_slot, |router, output| {
+            let num_active_voices = router.params().active_voices.len();
+            let spectrum_channels = router.params().spectrum_channels;
+
+            for channel_idx in 0..spectrum_channels {
+                for seq_idx in 0..num_active_voices {
+                    let voice_idx = router.params().active_voices[seq_idx];
+
+                    self.process_voice(output, router.for_voice(channel_idx, voice_idx, seq_idx));
+                }
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::synth_engine::{SpectralSize, buffer::zero_spectral_buffer};
+
+    #[test]
+    fn stretch_moves_harmonics_between_bins() {
+        let mut input = zero_spectral_buffer();
+        let mut output = zero_spectral_buffer();
+
+        input[4] = ComplexSample::new(1.0, 0.0);
+        HarmonicStretch::apply_stretch(&mut output, &input, 0.25);
+
+        // 4^1.25 = 5.66: most of the level goes to the 6th bin, the rest to the 5th
+        let position = (4.0 as Sample).powf(1.25);
+        assert!((output[6].re - position.fract()).abs() < 1e-5);
+        assert!((output[5].re - (1.0 - position.fract())).abs() < 1e-5);
+        assert_eq!(output[4], ComplexSample::ZERO);
+    }
+
+    #[test]
+    fn stretch_keeps_to_the_spectral_size() {
+        let mut input = zero_spectral_buffer();
+        let mut output = zero_spectral_buffer();
+        let bins = SpectralSize::S256.bins();
+
+        input[100] = ComplexSample::new(1.0, 0.0);
+        input[bins] = ComplexSample::new(1.0, 0.0);
+        output[bins + 1] = ComplexSample::new(0.5, 0.0);
+        HarmonicStretch::apply_stretch(&mut output[..bins], &input[..bins], 0.25);
+
+        // 100^1.25 lands past the 256th bin and is dropped, the bins above are never touched
+        assert!(output[..bins].iter().all(|bin| *bin == ComplexSample::ZERO));
+        assert_eq!(output[bins + 1], ComplexSample::new(0.5, 0.0));
+    }
+}
@@ -0,0 +1,21 @@
+use serde::{Deserialize, Serialize};
+
+use crate::synth_engine::{ModuleId, StereoSample};
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct HarmonicStretchConfig {
+    pub id: ModuleId,
+    #[serde(default)]
+    pub bypass: bool,
+    pub stretch: StereoSample,
+}
+
+impl Default for HarmonicStretchConfig {
+    fn default() -> Self {
+        Self {
+            id: -1,
+            bypass: false,
+            stretch: 0.0.into(),
+        }
+    }
+}
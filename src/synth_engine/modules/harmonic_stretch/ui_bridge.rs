@@ -0,0 +1,40 @@
+use crate::synth_engine::{Input, StereoSample, synth_module::ModuleUiBridge};
+
+use super::link::UiEnd;
+use super::{HarmonicStretch, HarmonicStretchConfig};
+
+pub struct HarmonicStretchUiBridge {
+    ui_end: UiEnd,
+    config: HarmonicStretchConfig,
+}
+
+impl HarmonicStretchUiBridge {
+    pub fn try_new(stretch: &mut HarmonicStretch) -> Option<Self> {
+        Some(Self {
+            ui_end: stretch.ui_end.take()?,
+            config: stretch.get_config(),
+        })
+    }
+
+    pub fn config(&self) -> &HarmonicStretchConfig {
+        &self.config
+    }
+
+    pub fn set_param(&mut self, input: Input, value: StereoSample) {
+        if !self.ui_end.set_param(input, value) {
+            return;
+        }
+
+        if input == Input::Stretch {
+            self.config.stretch = value;
+        }
+    }
+}
+
+impl ModuleUiBridge for HarmonicStretchUiBridge {
+    fn update(&mut self) {}
+
+    fn edits(&self) -> u32 {
+        self.ui_end.edits()
+    }
+}
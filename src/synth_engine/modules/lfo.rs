@@ -11,12 +11,12 @@ use link::{AudioEnd, UiEnd, UiEvent, create_link_pair};
 pub use ui_bridge::LfoUiBridge;
 
 use crate::synth_engine::{
-    Input, ModuleId, Sample, StereoSample,
+    Input, ModuleId, RandomParam, Randomizer, Sample, StereoSample,
     buffer::{Buffer, VoicesLayout, new_voices_layout, zero_buffer},
     phase::Phase,
     routing::{
-        ControlRouterType, DataType, InputMeta, InputSlots, NUM_CHANNELS, ProcessContext,
-        SamplesOutput, SpectralInputSlot, VoiceEvent, VoiceRouter,
+        ControlRouterType, DataType, InputMeta, InputSlots, InputSource, NUM_CHANNELS,
+        ProcessContext, SamplesOutput, SpectralInputSlot, VoiceEvent, VoiceRouter,
     },
     smooth::{SmoothedSample, Smoother},
     synth_module::SynthModule,
@@ -27,6 +27,8 @@ struct ChannelParams {
     phase_shift: SmoothedSample,
     skew: SmoothedSample,
     smooth_time: Sample,
+    delay: Sample,
+    fade_in: Sample,
 }
 
 impl ChannelParams {
@@ -36,6 +38,8 @@ impl ChannelParams {
             phase_shift: c.phase_shift[channel_idx].into(),
             skew: c.skew[channel_idx].into(),
             smooth_time: c.smooth_time[channel_idx],
+            delay: c.delay[channel_idx],
+            fade_in: c.fade_in[channel_idx],
         }
     }
 }
@@ -44,6 +48,7 @@ struct Params {
     shape: LfoShape,
     bipolar: bool,
     steal_phase: bool,
+    global: bool,
 }
 
 impl Params {
@@ -52,6 +57,7 @@ impl Params {
             shape: c.shape,
             bipolar: c.bipolar,
             steal_phase: c.steal_phase,
+            global: c.global,
         }
     }
 }
@@ -59,6 +65,7 @@ impl Params {
 struct VoiceState {
     phase: Phase,
     triggered: bool,
+    elapsed: Sample, // Since the note start, for the onset delay and fade in
     smoother: Smoother,
 }
 
@@ -67,6 +74,7 @@ impl Default for VoiceState {
         Self {
             phase: Phase::ZERO,
             triggered: false,
+            elapsed: 0.0,
             smoother: Smoother::default(),
         }
     }
@@ -76,6 +84,8 @@ pub struct Inputs {
     frequency: InputSlots,
     phase_shift: InputSlots,
     skew: InputSlots,
+    delay: InputSlots,
+    fade_in: InputSlots,
 }
 
 impl Default for Inputs {
@@ -84,6 +94,8 @@ impl Default for Inputs {
             frequency: InputSlots::empty(Input::LowFrequency),
             phase_shift: InputSlots::empty(Input::PhaseShift),
             skew: InputSlots::empty(Input::Skew),
+            delay: InputSlots::empty(Input::Delay),
+            fade_in: InputSlots::empty(Input::FadeIn),
         }
     }
 }
@@ -97,6 +109,8 @@ impl Inputs {
                 Input::LowFrequency => result.frequency = input.clone(),
                 Input::PhaseShift => result.phase_shift = input.clone(),
                 Input::Skew => result.skew = input.clone(),
+                Input::Delay => result.delay = input.clone(),
+                Input::FadeIn => result.fade_in = input.clone(),
                 _ => (),
             }
         }
@@ -104,11 +118,13 @@ impl Inputs {
         result
     }
 
-    fn update_amount(&mut self, input_type: Input, src_slot: usize, amount: StereoSample) {
+    fn update_source(&mut self, input_type: Input, src_slot: usize, source: &InputSource) {
         match input_type {
-            Input::LowFrequency => self.frequency.update_amount(src_slot, amount),
-            Input::PhaseShift => self.phase_shift.update_amount(src_slot, amount),
-            Input::Skew => self.skew.update_amount(src_slot, amount),
+            Input::LowFrequency => self.frequency.update_source(src_slot, source),
+            Input::PhaseShift => self.phase_shift.update_source(src_slot, source),
+            Input::Skew => self.skew.update_source(src_slot, source),
+            Input::Delay => self.delay.update_source(src_slot, source),
+            Input::FadeIn => self.fade_in.update_source(src_slot, source),
             _ => (),
         }
     }
@@ -124,6 +140,7 @@ struct Buffers {
 
 pub struct Lfo {
     id: ModuleId,
+    bypass: bool,
     params: Params,
     channel_params: [ChannelParams; NUM_CHANNELS],
     buffers: Buffers,
@@ -132,6 +149,7 @@ pub struct Lfo {
     inputs: Inputs,
     output_slot: usize,
     voices: VoicesLayout<VoiceState>,
+    global_phase: [Phase; NUM_CHANNELS],
 }
 
 impl Lfo {
@@ -147,6 +165,7 @@ impl Lfo {
 
         Self {
             id: config.id,
+            bypass: config.bypass,
             params: Params::from_config(config),
             channel_params: array::from_fn(|channel_idx| {
                 ChannelParams::from_config(config, channel_idx)
@@ -161,30 +180,51 @@ impl Lfo {
             inputs: Inputs::default(),
             output_slot: usize::MAX,
             voices: new_voices_layout(),
+            global_phase: [Phase::ZERO; NUM_CHANNELS],
         }
     }
 
     pub fn get_config(&self) -> LfoConfig {
         LfoConfig {
             id: self.id,
+            bypass: self.bypass,
             shape: self.params.shape,
             bipolar: self.params.bipolar,
             steal_phase: self.params.steal_phase,
+            global: self.params.global,
             frequency: get_smoothed_param!(self, frequency),
             phase_shift: get_smoothed_param!(self, phase_shift),
             skew: get_smoothed_param!(self, skew),
             smooth_time: get_stereo_param!(self, smooth_time),
+            delay: get_stereo_param!(self, delay),
+            fade_in: get_stereo_param!(self, fade_in),
+        }
+    }
+
+    pub fn randomize(
+        config: &mut LfoConfig,
+        randomizer: &mut Randomizer,
+        unlocked: impl Fn(RandomParam) -> bool,
+    ) {
+        if unlocked(RandomParam::Rate) {
+            config.frequency = randomizer.log(config.frequency, 0.05, 10.0);
+        }
+        if unlocked(RandomParam::Skew) {
+            config.skew = randomizer.stereo(config.skew, 0.0, 1.0);
         }
     }
 
     set_mono_param!(set_shape, shape, LfoShape);
     set_mono_param!(set_bipolar, bipolar, bool);
     set_mono_param!(set_steal_phase, steal_phase, bool);
+    set_mono_param!(set_global, global, bool);
 
     set_smoothed_param!(set_frequency, frequency);
     set_smoothed_param!(set_phase_shift, phase_shift, phase_shift.clamp(-1.0, 1.0));
     set_smoothed_param!(set_skew, skew, skew.clamp(0.0, 1.0));
     set_stereo_param!(set_smooth_time, smooth_time, smooth_time.max(0.0));
+    set_stereo_param!(set_delay, delay, delay.max(0.0));
+    set_stereo_param!(set_fade_in, fade_in, fade_in.max(0.0));
 
     fn triangle(x: Sample) -> Sample {
         2.0 * x.min(1.0 - x)
@@ -217,6 +257,17 @@ impl Lfo {
                 * (0.5 + (arg - skew) * 0.5 / (1.0 - skew).max(Sample::EPSILON))
     }
 
+    #[inline]
+    fn onset_gain(elapsed: Sample, delay: Sample, fade_in: Sample) -> Sample {
+        if elapsed < delay {
+            0.0
+        } else if elapsed >= delay + fade_in {
+            1.0
+        } else {
+            (elapsed - delay) / fade_in
+        }
+    }
+
     #[inline]
     fn apply_bipolar(value: Sample, bipolar: bool) -> Sample {
         Sample::from(bipolar) * value.mul_add(2.0, -1.0) + Sample::from(!bipolar) * value
@@ -237,12 +288,18 @@ impl Lfo {
         let sample_rate = router.sample_rate();
         let voice_output = &mut output_slot[channel_idx][voice_idx];
 
-        router.buff_param(
-            &inputs.frequency,
-            &mut channel.frequency,
-            &mut self.buffers.frequency,
-            voice.triggered,
-        );
+        // The shared phase runs at the set rate, frequency modulation only applies per voice
+        if params.global {
+            self.buffers.frequency.fill(channel.frequency.get());
+        } else {
+            router.buff_param(
+                &inputs.frequency,
+                &mut channel.frequency,
+                &mut self.buffers.frequency,
+                voice.triggered,
+            );
+        }
+
         router.buff_param(
             &inputs.phase_shift,
             &mut channel.phase_shift,
@@ -256,9 +313,26 @@ impl Lfo {
             voice.triggered,
         );
 
+        let delay = router
+            .scalar_param(&inputs.delay, channel.delay, voice.triggered)
+            .max(0.0);
+        let fade_in = router
+            .scalar_param(&inputs.fade_in, channel.fade_in, voice.triggered)
+            .max(0.0);
+        let t_step = sample_rate.recip();
+
+        if voice.triggered {
+            voice.elapsed = 0.0;
+        }
+
         let mut control_output = voice_output.control_output(samples, voice.triggered);
         let shape_func = Self::shape_function(params.shape);
         let freq_phase_mult = Phase::freq_phase_mult(sample_rate);
+        let mut phase = if params.global {
+            self.global_phase[channel_idx]
+        } else {
+            voice.phase
+        };
 
         voice.smoother.update(sample_rate, channel.smooth_time);
 
@@ -268,19 +342,20 @@ impl Lfo {
             &self.buffers.phase_shift,
             &self.buffers.skew,
         ) {
-            let arg = voice
-                .phase
+            let arg = phase
                 .add_normalized(phase_shift.clamp(-1.0, 1.0))
                 .normalized();
 
             *out = Self::apply_bipolar(
                 shape_func(Self::skew_arg(arg, skew.clamp(0.0, 1.0))),
                 params.bipolar,
-            );
+            ) * Self::onset_gain(voice.elapsed, delay, fade_in);
 
-            voice.phase += *frequency * freq_phase_mult;
+            phase += *frequency * freq_phase_mult;
+            voice.elapsed += t_step;
         }
 
+        voice.phase = phase;
         drop(control_output);
 
         if voice.triggered {
@@ -302,11 +377,21 @@ impl SynthModule for Lfo {
         self.id
     }
 
+    fn bypass(&self) -> bool {
+        self.bypass
+    }
+
+    fn set_bypass(&mut self, bypass: bool) {
+        self.bypass = bypass;
+    }
+
     fn inputs(&self) -> &'static [InputMeta] {
         static INPUTS: &[InputMeta] = &[
             InputMeta::control(Input::LowFrequency),
             InputMeta::control(Input::PhaseShift),
             InputMeta::control(Input::Skew),
+            InputMeta::control(Input::Delay),
+            InputMeta::control(Input::FadeIn),
         ];
 
         INPUTS
@@ -328,8 +413,8 @@ impl SynthModule for Lfo {
         self.inputs = Inputs::from_slots(inputs, spectral_inputs);
     }
 
-    fn update_input_amount(&mut self, input_type: Input, src_slot: usize, amount: StereoSample) {
-        self.inputs.update_amount(input_type, src_slot, amount);
+    fn update_input_source(&mut self, input_type: Input, src_slot: usize, source: &InputSource) {
+        self.inputs.update_source(input_type, src_slot, source);
     }
 
     fn process_events(&mut self, events: &[VoiceEvent]) {
@@ -358,18 +443,25 @@ impl SynthModule for Lfo {
         }
     }
 
+    fn set_input_param(&mut self, input: Input, value: StereoSample) {
+        match input {
+            Input::LowFrequency => self.set_frequency(value),
+            Input::PhaseShift => self.set_phase_shift(value),
+            Input::Skew => self.set_skew(value),
+            Input::Delay => self.set_delay(value),
+            Input::FadeIn => self.set_fade_in(value),
+            _ => (),
+        }
+    }
+
     fn process_ui_events(&mut self) {
         while let Some(event) = self.audio_end.pop_event() {
             match event {
-                UiEvent::InputParam { input, value } => match input {
-                    Input::LowFrequency => self.set_frequency(value),
-                    Input::PhaseShift => self.set_phase_shift(value),
-                    Input::Skew => self.set_skew(value),
-                    _ => (),
-                },
+                UiEvent::InputParam { input, value } => self.set_input_param(input, value),
                 UiEvent::Shape(shape) => self.set_shape(shape),
                 UiEvent::Bipolar(value) => self.set_bipolar(value),
                 UiEvent::StealPhase(value) => self.set_steal_phase(value),
+                UiEvent::Global(value) => self.set_global(value),
                 UiEvent::SmoothTime(value) => self.set_smooth_time(value),
             }
         }
@@ -386,6 +478,17 @@ impl SynthModule for Lfo {
                     self.process_voice(output, router.for_voice(channel_idx, voice_idx, seq_idx));
                 }
             }
+
+            // Keeps running without voices, notes pick it up wherever it is
+            if self.params.global {
+                let params = router.params();
+                let block_mult =
+                    Phase::freq_phase_mult(params.sample_rate) * params.samples as Sample;
+
+                for (phase, channel) in self.global_phase.iter_mut().zip(&self.channel_params) {
+                    *phase += channel.frequency.get() * block_mult;
+                }
+            }
         });
     }
 }
@@ -13,26 +13,38 @@ pub enum LfoShape {
 #[derive(Clone, Serialize, Deserialize)]
 pub struct LfoConfig {
     pub id: ModuleId,
+    #[serde(default)]
+    pub bypass: bool,
     pub shape: LfoShape,
     pub bipolar: bool,
     pub steal_phase: bool,
+    #[serde(default)]
+    pub global: bool,
     pub frequency: StereoSample,
     pub phase_shift: StereoSample,
     pub skew: StereoSample,
     pub smooth_time: StereoSample,
+    #[serde(default)]
+    pub delay: StereoSample,
+    #[serde(default)]
+    pub fade_in: StereoSample,
 }
 
 impl Default for LfoConfig {
     fn default() -> Self {
         Self {
             id: -1,
+            bypass: false,
             shape: LfoShape::default(),
             bipolar: false,
             steal_phase: false,
+            global: false,
             frequency: 1.0.into(),
             phase_shift: 0.0.into(),
             skew: 0.5.into(),
             smooth_time: 0.0.into(),
+            delay: 0.0.into(),
+            fade_in: 0.0.into(),
         }
     }
 }
@@ -1,4 +1,4 @@
-use crate::synth_engine::{Input, StereoSample};
+use crate::synth_engine::{Input, StereoSample, edit_sender::EditSender};
 
 use super::config::LfoShape;
 
@@ -7,16 +7,23 @@ pub enum UiEvent {
     Shape(LfoShape),
     Bipolar(bool),
     StealPhase(bool),
+    Global(bool),
     SmoothTime(StereoSample),
 }
 
 pub struct UiEnd {
-    tx: rtrb::Producer<UiEvent>,
+    tx: EditSender<UiEvent>,
 }
 
 impl UiEnd {
     pub fn new(tx: rtrb::Producer<UiEvent>) -> Self {
-        Self { tx }
+        Self {
+            tx: EditSender::new(tx),
+        }
+    }
+
+    pub fn edits(&self) -> u32 {
+        self.tx.sent()
     }
 
     pub fn set_param(&mut self, input: Input, value: StereoSample) -> bool {
@@ -35,6 +42,10 @@ impl UiEnd {
         self.tx.push(UiEvent::StealPhase(value)).is_ok()
     }
 
+    pub fn set_global(&mut self, value: bool) -> bool {
+        self.tx.push(UiEvent::Global(value)).is_ok()
+    }
+
     pub fn set_smooth_time(&mut self, value: StereoSample) -> bool {
         self.tx.push(UiEvent::SmoothTime(value)).is_ok()
     }
@@ -29,6 +29,8 @@ impl LfoUiBridge {
             Input::LowFrequency => self.config.frequency = value,
             Input::PhaseShift => self.config.phase_shift = value,
             Input::Skew => self.config.skew = value,
+            Input::Delay => self.config.delay = value,
+            Input::FadeIn => self.config.fade_in = value,
             _ => (),
         }
     }
@@ -51,6 +53,12 @@ impl LfoUiBridge {
         }
     }
 
+    pub fn set_global(&mut self, value: bool) {
+        if self.ui_end.set_global(value) {
+            self.config.global = value;
+        }
+    }
+
     pub fn set_smooth_time(&mut self, value: StereoSample) {
         if self.ui_end.set_smooth_time(value) {
             self.config.smooth_time = value;
@@ -60,4 +68,8 @@ impl LfoUiBridge {
 
 impl ModuleUiBridge for LfoUiBridge {
     fn update(&mut self) {}
+
+    fn edits(&self) -> u32 {
+        self.ui_end.edits()
+    }
 }
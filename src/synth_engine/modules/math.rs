@@ -0,0 +1,403 @@
+use std::array;
+
+use itertools::izip;
+
+mod config;
+mod link;
+mod ui_bridge;
+
+pub use config::{MathConfig, MathOperation, QuantizeGrid};
+use link::{AudioEnd, UiEnd, UiEvent, create_link_pair};
+pub use ui_bridge::MathUiBridge;
+
+use crate::synth_engine::{
+    Input, ModuleId, RandomParam, Randomizer, Sample, StereoSample,
+    buffer::{Buffer, VoicesLayout, new_voices_layout, zero_buffer},
+    routing::{
+        ControlRouterType, DataType, InputMeta, InputSlots, InputSource, NUM_CHANNELS,
+        ProcessContext, SamplesOutput, SpectralInputSlot, VoiceEvent, VoiceRouter,
+    },
+    smooth::SmoothedSample,
+    synth_module::SynthModule,
+};
+
+pub const MAX_QUANTIZE_STEPS: usize = 48;
+const SEMITONE_STEPS: Sample = 12.0;
+
+struct ChannelParams {
+    value_a: SmoothedSample,
+    value_b: SmoothedSample,
+    mix: SmoothedSample,
+}
+
+impl ChannelParams {
+    fn from_config(c: &MathConfig, channel_idx: usize) -> Self {
+        Self {
+            value_a: c.value_a[channel_idx].into(),
+            value_b: c.value_b[channel_idx].into(),
+            mix: c.mix[channel_idx].into(),
+        }
+    }
+}
+
+struct Params {
+    operation: MathOperation,
+    range_min: Sample,
+    range_max: Sample,
+    slew_rise: Sample,
+    slew_fall: Sample,
+    quantize_grid: QuantizeGrid,
+    quantize_steps: usize,
+}
+
+impl Params {
+    fn from_config(c: &MathConfig) -> Self {
+        Self {
+            operation: c.operation,
+            range_min: c.range_min,
+            range_max: c.range_max,
+            slew_rise: c.slew_rise.max(0.0),
+            slew_fall: c.slew_fall.max(0.0),
+            quantize_grid: c.quantize_grid,
+            quantize_steps: c.quantize_steps.clamp(1, MAX_QUANTIZE_STEPS),
+        }
+    }
+}
+
+#[derive(Default)]
+struct VoiceState {
+    triggered: bool,
+    slewed: Sample,
+}
+
+pub struct Inputs {
+    value_a: InputSlots,
+    value_b: InputSlots,
+    mix: InputSlots,
+}
+
+impl Default for Inputs {
+    fn default() -> Self {
+        Self {
+            value_a: InputSlots::empty(Input::ValueA),
+            value_b: InputSlots::empty(Input::ValueB),
+            mix: InputSlots::empty(Input::Mix),
+        }
+    }
+}
+
+impl Inputs {
+    fn from_slots(inputs: &[InputSlots], _spectral_inputs: &[SpectralInputSlot]) -> Self {
+        let mut result = Self::default();
+
+        for input in inputs {
+            match input.input_type {
+                Input::ValueA => result.value_a = input.clone(),
+                Input::ValueB => result.value_b = input.clone(),
+                Input::Mix => result.mix = input.clone(),
+                _ => (),
+            }
+        }
+
+        result
+    }
+
+    fn update_source(&mut self, input_type: Input, src_slot: usize, source: &InputSource) {
+        match input_type {
+            Input::ValueA => self.value_a.update_source(src_slot, source),
+            Input::ValueB => self.value_b.update_source(src_slot, source),
+            Input::Mix => self.mix.update_source(src_slot, source),
+            _ => (),
+        }
+    }
+}
+
+type Router<'v, 'f, 'c> = VoiceRouter<'v, 'f, 'c, ControlRouterType>;
+
+struct Buffers {
+    value_a: Buffer,
+    value_b: Buffer,
+    mix: Buffer,
+}
+
+// Combines two control signals, for anything beyond the plain sum the router does for linked sources
+pub struct Math {
+    id: ModuleId,
+    bypass: bool,
+    params: Params,
+    channel_params: [ChannelParams; NUM_CHANNELS],
+    buffers: Buffers,
+    audio_end: AudioEnd,
+    ui_end: Option<UiEnd>,
+    inputs: Inputs,
+    output_slot: usize,
+    voices: VoicesLayout<VoiceState>,
+}
+
+impl Math {
+    pub fn new(id: ModuleId) -> Self {
+        Self::from_config(&MathConfig {
+            id,
+            ..MathConfig::default()
+        })
+    }
+
+    pub fn from_config(config: &MathConfig) -> Self {
+        let (audio_end, ui_end) = create_link_pair();
+
+        Self {
+            id: config.id,
+            bypass: config.bypass,
+            params: Params::from_config(config),
+            channel_params: array::from_fn(|channel_idx| {
+                ChannelParams::from_config(config, channel_idx)
+            }),
+            buffers: Buffers {
+                value_a: zero_buffer(),
+                value_b: zero_buffer(),
+                mix: zero_buffer(),
+            },
+            audio_end,
+            ui_end: Some(ui_end),
+            inputs: Inputs::default(),
+            output_slot: usize::MAX,
+            voices: new_voices_layout(),
+        }
+    }
+
+    pub fn get_config(&self) -> MathConfig {
+        MathConfig {
+            id: self.id,
+            bypass: self.bypass,
+            operation: self.params.operation,
+            value_a: get_smoothed_param!(self, value_a),
+            value_b: get_smoothed_param!(self, value_b),
+            mix: get_smoothed_param!(self, mix),
+            range_min: self.params.range_min,
+            range_max: self.params.range_max,
+            slew_rise: self.params.slew_rise,
+            slew_fall: self.params.slew_fall,
+            quantize_grid: self.params.quantize_grid,
+            quantize_steps: self.params.quantize_steps,
+        }
+    }
+
+    // Its values depend on what the inputs carry, nothing to randomize
+    pub fn randomize(
+        _config: &mut MathConfig,
+        _randomizer: &mut Randomizer,
+        _unlocked: impl Fn(RandomParam) -> bool,
+    ) {
+    }
+
+    set_mono_param!(set_operation, operation, MathOperation);
+
+    set_smoothed_param!(set_value_a, value_a);
+    set_smoothed_param!(set_value_b, value_b);
+    set_smoothed_param!(set_mix, mix, mix.clamp(0.0, 1.0));
+
+    pub fn set_range(&mut self, min: Sample, max: Sample) {
+        self.params.range_min = min;
+        self.params.range_max = max;
+    }
+
+    pub fn set_slew(&mut self, rise: Sample, fall: Sample) {
+        self.params.slew_rise = rise.max(0.0);
+        self.params.slew_fall = fall.max(0.0);
+    }
+
+    pub fn set_quantize(&mut self, grid: QuantizeGrid, steps: usize) {
+        self.params.quantize_grid = grid;
+        self.params.quantize_steps = steps.clamp(1, MAX_QUANTIZE_STEPS);
+    }
+
+    // Largest change per sample, a zero time follows the input immediately
+    fn slew_step(time: Sample, sample_rate: Sample) -> Sample {
+        if time > 0.0 {
+            (time * sample_rate).recip()
+        } else {
+            Sample::INFINITY
+        }
+    }
+
+    #[inline]
+    fn calc(params: &Params, a: Sample, b: Sample, mix: Sample) -> Sample {
+        match params.operation {
+            MathOperation::Add => a + b,
+            MathOperation::Multiply => a * b,
+            MathOperation::Min => a.min(b),
+            MathOperation::Max => a.max(b),
+            MathOperation::Crossfade => (b - a).mul_add(mix.clamp(0.0, 1.0), a),
+            MathOperation::Remap => {
+                (params.range_max - params.range_min).mul_add(a, params.range_min)
+            }
+            MathOperation::Quantize => {
+                let steps = match params.quantize_grid {
+                    QuantizeGrid::Steps => params.quantize_steps as Sample,
+                    QuantizeGrid::Semitones => SEMITONE_STEPS,
+                };
+
+                (a * steps).round() / steps
+            }
+            // Stateful, handled per voice
+            MathOperation::Slew => a,
+        }
+    }
+
+    fn process_voice(
+        &mut self,
+        output_slot: &mut VoicesLayout<SamplesOutput>,
+        mut router: Router<'_, '_, '_>,
+    ) {
+        let channel_idx = router.channel_idx();
+        let voice_idx = router.voice_idx();
+        let inputs = &self.inputs;
+        let params = &self.params;
+        let channel = &mut self.channel_params[channel_idx];
+        let voice = &mut self.voices[channel_idx][voice_idx];
+        let samples = router.samples();
+        let sample_rate = router.sample_rate();
+        let skip = usize::from(!voice.triggered);
+
+        router.buff_param(
+            &inputs.value_a,
+            &mut channel.value_a,
+            &mut self.buffers.value_a,
+            voice.triggered,
+        );
+        router.buff_param(
+            &inputs.value_b,
+            &mut channel.value_b,
+            &mut self.buffers.value_b,
+            voice.triggered,
+        );
+        router.buff_param(
+            &inputs.mix,
+            &mut channel.mix,
+            &mut self.buffers.mix,
+            voice.triggered,
+        );
+
+        let mut control_output =
+            output_slot[channel_idx][voice_idx].control_output(samples, voice.triggered);
+
+        if params.operation == MathOperation::Slew {
+            let rise = Self::slew_step(params.slew_rise, sample_rate);
+            let fall = Self::slew_step(params.slew_fall, sample_rate);
+
+            // A new note starts at the input value instead of gliding from the previous one
+            if voice.triggered {
+                voice.slewed = self.buffers.value_a[0];
+            }
+
+            for (out, a) in control_output
+                .output()
+                .iter_mut()
+                .zip(&self.buffers.value_a[skip..])
+            {
+                voice.slewed += (*a - voice.slewed).clamp(-fall, rise);
+                *out = voice.slewed;
+            }
+        } else {
+            for (out, a, b, mix) in izip!(
+                control_output.output().iter_mut(),
+                &self.buffers.value_a[skip..],
+                &self.buffers.value_b[skip..],
+                &self.buffers.mix[skip..],
+            ) {
+                *out = Self::calc(params, *a, *b, *mix);
+            }
+        }
+
+        voice.triggered = false;
+    }
+}
+
+impl SynthModule for Math {
+    fn id(&self) -> ModuleId {
+        self.id
+    }
+
+    fn bypass(&self) -> bool {
+        self.bypass
+    }
+
+    fn set_bypass(&mut self, bypass: bool) {
+        self.bypass = bypass;
+    }
+
+    fn inputs(&self) -> &'static [InputMeta] {
+        static INPUTS: &[InputMeta] = &[
+            InputMeta::control(Input::ValueA),
+            InputMeta::control(Input::ValueB),
+            InputMeta::control(Input::Mix),
+        ];
+
+        INPUTS
+    }
+
+    fn output_type(&self) -> DataType {
+        DataType::Control
+    }
+
+    fn output_slot(&self) -> usize {
+        self.output_slot
+    }
+
+    fn set_output_slot(&mut self, slot: usize) {
+        self.output_slot = slot;
+    }
+
+    fn set_input_slots(&mut self, inputs: &[InputSlots], spectral_inputs: &[SpectralInputSlot]) {
+        self.inputs = Inputs::from_slots(inputs, spectral_inputs);
+    }
+
+    fn update_input_source(&mut self, input_type: Input, src_slot: usize, source: &InputSource) {
+        self.inputs.update_source(input_type, src_slot, source);
+    }
+
+    fn process_events(&mut self, events: &[VoiceEvent]) {
+        for channel in self.voices.iter_mut() {
+            for event in events {
+                if let VoiceEvent::Trigger { voice_idx, .. } = event {
+                    channel[*voice_idx].triggered = true;
+                }
+            }
+        }
+    }
+
+    fn set_input_param(&mut self, input: Input, value: StereoSample) {
+        match input {
+            Input::ValueA => self.set_value_a(value),
+            Input::ValueB => self.set_value_b(value),
+            Input::Mix => self.set_mix(value),
+            _ => (),
+        }
+    }
+
+    fn process_ui_events(&mut self) {
+        while let Some(event) = self.audio_end.pop_event() {
+            match event {
+                UiEvent::InputParam { input, value } => self.set_input_param(input, value),
+                UiEvent::Operation(operation) => self.set_operation(operation),
+                UiEvent::Range(min, max) => self.set_range(min, max),
+                UiEvent::Slew(rise, fall) => self.set_slew(rise, fall),
+                UiEvent::Quantize(grid, steps) => self.set_quantize(grid, steps),
+            }
+        }
+    }
+
+    fn process(&mut self, ctx: &mut ProcessContext) {
+        ctx.for_control(self.id, self.output_slot, |router, output| {
+            let num_active_voices = router.params().active_voices.len();
+
+            for channel_idx in 0..NUM_CHANNELS {
+                for seq_idx in 0..num_active_voices {
+                    let voice_idx = router.params().active_voices[seq_idx];
+
+                    self.process_voice(output, router.for_voice(channel_idx, voice_idx, seq_idx));
+                }
+            }
+        });
+    }
+}
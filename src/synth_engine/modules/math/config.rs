@@ -0,0 +1,74 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    synth_engine::{ModuleId, Sample, StereoSample},
+    utils::from_ms,
+};
+
+#[derive(Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum MathOperation {
+    #[default]
+    Add,
+    Multiply,
+    Min,
+    Max,
+    Crossfade,
+    Remap,
+    Slew,
+    Quantize,
+}
+
+#[derive(Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum QuantizeGrid {
+    #[default]
+    Steps,
+    Semitones, // Of pitch modulation in octaves
+}
+
+fn default_slew_time() -> Sample {
+    from_ms(100.0)
+}
+
+fn default_quantize_steps() -> usize {
+    4
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct MathConfig {
+    pub id: ModuleId,
+    #[serde(default)]
+    pub bypass: bool,
+    pub operation: MathOperation,
+    pub value_a: StereoSample,
+    pub value_b: StereoSample,
+    pub mix: StereoSample,
+    pub range_min: Sample, // Remap target of 0.0
+    pub range_max: Sample, // Remap target of 1.0
+    #[serde(default = "default_slew_time")]
+    pub slew_rise: Sample, // Seconds to rise by 1.0
+    #[serde(default = "default_slew_time")]
+    pub slew_fall: Sample, // Seconds to fall by 1.0
+    #[serde(default)]
+    pub quantize_grid: QuantizeGrid,
+    #[serde(default = "default_quantize_steps")]
+    pub quantize_steps: usize, // Per 1.0
+}
+
+impl Default for MathConfig {
+    fn default() -> Self {
+        Self {
+            id: -1,
+            bypass: false,
+            operation: MathOperation::default(),
+            value_a: 0.0.into(),
+            value_b: 0.0.into(),
+            mix: 0.5.into(),
+            range_min: 0.0,
+            range_max: 1.0,
+            slew_rise: default_slew_time(),
+            slew_fall: default_slew_time(),
+            quantize_grid: QuantizeGrid::default(),
+            quantize_steps: default_quantize_steps(),
+        }
+    }
+}
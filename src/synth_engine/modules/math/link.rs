@@ -0,0 +1,67 @@
+use crate::synth_engine::{Input, Sample, StereoSample, edit_sender::EditSender};
+
+use super::config::{MathOperation, QuantizeGrid};
+
+pub enum UiEvent {
+    InputParam { input: Input, value: StereoSample },
+    Operation(MathOperation),
+    Range(Sample, Sample),
+    Slew(Sample, Sample),
+    Quantize(QuantizeGrid, usize),
+}
+
+pub struct UiEnd {
+    tx: EditSender<UiEvent>,
+}
+
+impl UiEnd {
+    pub fn new(tx: rtrb::Producer<UiEvent>) -> Self {
+        Self {
+            tx: EditSender::new(tx),
+        }
+    }
+
+    pub fn edits(&self) -> u32 {
+        self.tx.sent()
+    }
+
+    pub fn set_param(&mut self, input: Input, value: StereoSample) -> bool {
+        self.tx.push(UiEvent::InputParam { input, value }).is_ok()
+    }
+
+    pub fn set_operation(&mut self, operation: MathOperation) -> bool {
+        self.tx.push(UiEvent::Operation(operation)).is_ok()
+    }
+
+    pub fn set_range(&mut self, min: Sample, max: Sample) -> bool {
+        self.tx.push(UiEvent::Range(min, max)).is_ok()
+    }
+
+    pub fn set_slew(&mut self, rise: Sample, fall: Sample) -> bool {
+        self.tx.push(UiEvent::Slew(rise, fall)).is_ok()
+    }
+
+    pub fn set_quantize(&mut self, grid: QuantizeGrid, steps: usize) -> bool {
+        self.tx.push(UiEvent::Quantize(grid, steps)).is_ok()
+    }
+}
+
+pub struct AudioEnd {
+    rx: rtrb::Consumer<UiEvent>,
+}
+
+impl AudioEnd {
+    pub fn new(rx: rtrb::Consumer<UiEvent>) -> Self {
+        Self { rx }
+    }
+
+    pub fn pop_event(&mut self) -> Option<UiEvent> {
+        self.rx.pop().ok()
+    }
+}
+
+pub fn create_link_pair() -> (AudioEnd, UiEnd) {
+    let (to_audio_tx, from_ui_rx) = rtrb::RingBuffer::<UiEvent>::new(128);
+
+    (AudioEnd::new(from_ui_rx), UiEnd::new(to_audio_tx))
+}
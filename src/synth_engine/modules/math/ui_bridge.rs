@@ -0,0 +1,70 @@
+use crate::synth_engine::{Input, Sample, StereoSample, synth_module::ModuleUiBridge};
+
+use super::link::UiEnd;
+use super::{Math, MathConfig, MathOperation, QuantizeGrid};
+
+pub struct MathUiBridge {
+    ui_end: UiEnd,
+    config: MathConfig,
+}
+
+impl MathUiBridge {
+    pub fn try_new(math: &mut Math) -> Option<Self> {
+        Some(Self {
+            ui_end: math.ui_end.take()?,
+            config: math.get_config(),
+        })
+    }
+
+    pub fn config(&self) -> &MathConfig {
+        &self.config
+    }
+
+    pub fn set_param(&mut self, input: Input, value: StereoSample) {
+        if !self.ui_end.set_param(input, value) {
+            return;
+        }
+
+        match input {
+            Input::ValueA => self.config.value_a = value,
+            Input::ValueB => self.config.value_b = value,
+            Input::Mix => self.config.mix = value,
+            _ => (),
+        }
+    }
+
+    pub fn set_operation(&mut self, operation: MathOperation) {
+        if self.ui_end.set_operation(operation) {
+            self.config.operation = operation;
+        }
+    }
+
+    pub fn set_range(&mut self, min: Sample, max: Sample) {
+        if self.ui_end.set_range(min, max) {
+            self.config.range_min = min;
+            self.config.range_max = max;
+        }
+    }
+
+    pub fn set_slew(&mut self, rise: Sample, fall: Sample) {
+        if self.ui_end.set_slew(rise, fall) {
+            self.config.slew_rise = rise;
+            self.config.slew_fall = fall;
+        }
+    }
+
+    pub fn set_quantize(&mut self, grid: QuantizeGrid, steps: usize) {
+        if self.ui_end.set_quantize(grid, steps) {
+            self.config.quantize_grid = grid;
+            self.config.quantize_steps = steps;
+        }
+    }
+}
+
+impl ModuleUiBridge for MathUiBridge {
+    fn update(&mut self) {}
+
+    fn edits(&self) -> u32 {
+        self.ui_end.edits()
+    }
+}
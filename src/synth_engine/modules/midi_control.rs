@@ -0,0 +1,200 @@
+mod config;
+mod link;
+mod ui_bridge;
+
+pub use config::{MidiControlConfig, MidiSource};
+use link::{AudioEnd, UiEnd, UiEvent, create_link_pair};
+pub use ui_bridge::MidiControlUiBridge;
+
+use crate::synth_engine::{
+    ModuleId, RandomParam, Randomizer, Sample,
+    buffer::{VoicesLayout, new_voices_layout},
+    midi_state::{MOD_WHEEL_CC, MidiState},
+    routing::{
+        ControlRouterType, DataType, Input, InputMeta, InputSlots, InputSource, NUM_CHANNELS,
+        ProcessContext, SamplesOutput, SpectralInputSlot, VoiceEvent, VoiceRouter,
+    },
+    smooth::Smoother,
+    synth_module::SynthModule,
+};
+
+struct Params {
+    source: MidiSource,
+    smooth: Sample,
+}
+
+impl Params {
+    fn from_config(c: &config::MidiControlConfig) -> Self {
+        Self {
+            source: c.source,
+            smooth: c.smooth,
+        }
+    }
+}
+
+#[derive(Default)]
+struct VoiceState {
+    triggered: bool,
+    smoother: Smoother,
+}
+
+type Router<'v, 'f, 'c> = VoiceRouter<'v, 'f, 'c, ControlRouterType>;
+
+pub struct MidiControl {
+    id: ModuleId,
+    bypass: bool,
+    params: Params,
+    audio_end: AudioEnd,
+    ui_end: Option<UiEnd>,
+    output_slot: usize,
+    voices: VoicesLayout<VoiceState>,
+}
+
+impl MidiControl {
+    pub fn new(id: ModuleId) -> Self {
+        Self::from_config(&MidiControlConfig {
+            id,
+            ..MidiControlConfig::default()
+        })
+    }
+
+    pub fn from_config(config: &config::MidiControlConfig) -> Self {
+        let (audio_end, ui_end) = create_link_pair();
+
+        Self {
+            id: config.id,
+            bypass: config.bypass,
+            params: Params::from_config(config),
+            audio_end,
+            ui_end: Some(ui_end),
+            output_slot: usize::MAX,
+            voices: new_voices_layout(),
+        }
+    }
+
+    pub fn get_config(&self) -> MidiControlConfig {
+        MidiControlConfig {
+            id: self.id,
+            bypass: self.bypass,
+            source: self.params.source,
+            smooth: self.params.smooth,
+        }
+    }
+
+    // Controls follow the player, nothing to randomize
+    pub fn randomize(
+        _config: &mut MidiControlConfig,
+        _randomizer: &mut Randomizer,
+        _unlocked: impl Fn(RandomParam) -> bool,
+    ) {
+    }
+
+    set_mono_param!(set_source, source, MidiSource);
+    set_mono_param!(set_smooth, smooth, Sample);
+
+    fn source_value(source: MidiSource, midi_state: &MidiState) -> Sample {
+        match source {
+            MidiSource::PitchBend => midi_state.pitch_bend(),
+            MidiSource::ModWheel => midi_state.cc(MOD_WHEEL_CC),
+            MidiSource::Cc(cc) => midi_state.cc(cc),
+        }
+    }
+
+    fn process_voice(
+        &mut self,
+        output_slot: &mut VoicesLayout<SamplesOutput>,
+        router: Router<'_, '_, '_>,
+    ) {
+        let channel_idx = router.channel_idx();
+        let voice_idx = router.voice_idx();
+        let voice = &mut self.voices[channel_idx][voice_idx];
+        let samples = router.samples();
+        let voice_output = &mut output_slot[channel_idx][voice_idx];
+        let value = Self::source_value(self.params.source, router.midi_state());
+
+        if voice.triggered {
+            voice.smoother.reset(value);
+        }
+
+        let mut control_output = voice_output.control_output(samples, voice.triggered);
+        control_output.output().fill(value);
+        drop(control_output);
+
+        voice.triggered = false;
+
+        voice.smoother.apply_if_needed(
+            samples,
+            router.sample_rate(),
+            self.params.smooth,
+            voice_output.output(samples),
+        );
+    }
+}
+
+impl SynthModule for MidiControl {
+    fn id(&self) -> ModuleId {
+        self.id
+    }
+
+    fn bypass(&self) -> bool {
+        self.bypass
+    }
+
+    fn set_bypass(&mut self, bypass: bool) {
+        self.bypass = bypass;
+    }
+
+    fn inputs(&self) -> &'static [InputMeta] {
+        &[]
+    }
+
+    fn output_type(&self) -> DataType {
+        DataType::Control
+    }
+
+    fn output_slot(&self) -> usize {
+        self.output_slot
+    }
+
+    fn set_output_slot(&mut self, slot: usize) {
+        self.output_slot = slot;
+    }
+
+    fn set_input_slots(&mut self, _inputs: &[InputSlots], _spectral_inputs: &[SpectralInputSlot]) {}
+
+    fn update_input_source(&mut self, _input_type: Input, _src_slot: usize, _source: &InputSource) {
+    }
+
+    fn process_events(&mut self, events: &[VoiceEvent]) {
+        for channel in self.voices.iter_mut() {
+            for event in events {
+                if let VoiceEvent::Trigger { voice_idx, .. } = event {
+                    channel[*voice_idx].triggered = true;
+                }
+            }
+        }
+    }
+
+    fn process_ui_events(&mut self) {
+        while let Some(event) = self.audio_end.pop_event() {
+            match event {
+                UiEvent::Source(source) => self.set_source(source),
+                UiEvent::Smooth(value) => self.set_smooth(value),
+            }
+        }
+    }
+
+    fn process(&mut self, ctx: &mut ProcessContext) {
+        ctx.for_control(self.id, self.output_slot, |router, output| {
+            let num_active_voices = router.params().active_voices.len();
+
+            for channel_idx in 0..NUM_CHANNELS {
+                for seq_idx in 0..num_active_voices {
+                    let voice_idx = router.params().active_voices[seq_idx];
+
+                    self.process_voice(output, router.for_voice(channel_idx, voice_idx, seq_idx));
+                }
+            }
+        });
+    }
+}
@@ -0,0 +1,33 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    synth_engine::{ModuleId, Sample},
+    utils::from_ms,
+};
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum MidiSource {
+    PitchBend,
+    ModWheel,
+    Cc(u8),
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct MidiControlConfig {
+    pub id: ModuleId,
+    #[serde(default)]
+    pub bypass: bool,
+    pub source: MidiSource,
+    pub smooth: Sample,
+}
+
+impl Default for MidiControlConfig {
+    fn default() -> Self {
+        Self {
+            id: -1,
+            bypass: false,
+            source: MidiSource::PitchBend,
+            smooth: from_ms(2.0),
+        }
+    }
+}
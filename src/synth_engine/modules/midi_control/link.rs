@@ -0,0 +1,52 @@
+use crate::synth_engine::{Sample, edit_sender::EditSender};
+
+use super::config::MidiSource;
+
+pub enum UiEvent {
+    Source(MidiSource),
+    Smooth(Sample),
+}
+
+pub struct UiEnd {
+    tx: EditSender<UiEvent>,
+}
+
+impl UiEnd {
+    pub fn new(tx: rtrb::Producer<UiEvent>) -> Self {
+        Self {
+            tx: EditSender::new(tx),
+        }
+    }
+
+    pub fn edits(&self) -> u32 {
+        self.tx.sent()
+    }
+
+    pub fn set_source(&mut self, source: MidiSource) -> bool {
+        self.tx.push(UiEvent::Source(source)).is_ok()
+    }
+
+    pub fn set_smooth(&mut self, value: Sample) -> bool {
+        self.tx.push(UiEvent::Smooth(value)).is_ok()
+    }
+}
+
+pub struct AudioEnd {
+    rx: rtrb::Consumer<UiEvent>,
+}
+
+impl AudioEnd {
+    pub fn new(rx: rtrb::Consumer<UiEvent>) -> Self {
+        Self { rx }
+    }
+
+    pub fn pop_event(&mut self) -> Option<UiEvent> {
+        self.rx.pop().ok()
+    }
+}
+
+pub fn create_link_pair() -> (AudioEnd, UiEnd) {
+    let (to_audio_tx, from_ui_rx) = rtrb::RingBuffer::<UiEvent>::new(128);
+
+    (AudioEnd::new(from_ui_rx), UiEnd::new(to_audio_tx))
+}
@@ -0,0 +1,42 @@
+use crate::synth_engine::{Sample, synth_module::ModuleUiBridge};
+
+use super::link::UiEnd;
+use super::{MidiControl, MidiControlConfig, MidiSource};
+
+pub struct MidiControlUiBridge {
+    ui_end: UiEnd,
+    config: MidiControlConfig,
+}
+
+impl MidiControlUiBridge {
+    pub fn try_new(control: &mut MidiControl) -> Option<Self> {
+        Some(Self {
+            ui_end: control.ui_end.take()?,
+            config: control.get_config(),
+        })
+    }
+
+    pub fn config(&self) -> &MidiControlConfig {
+        &self.config
+    }
+
+    pub fn set_source(&mut self, source: MidiSource) {
+        if self.ui_end.set_source(source) {
+            self.config.source = source;
+        }
+    }
+
+    pub fn set_smooth(&mut self, value: Sample) {
+        if self.ui_end.set_smooth(value) {
+            self.config.smooth = value;
+        }
+    }
+}
+
+impl ModuleUiBridge for MidiControlUiBridge {
+    fn update(&mut self) {}
+
+    fn edits(&self) -> u32 {
+        self.ui_end.edits()
+    }
+}
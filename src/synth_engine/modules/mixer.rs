@@ -11,11 +11,11 @@ use link::{AudioEnd, UiEnd, UiEvent, create_link_pair};
 pub use ui_bridge::MixerUiBridge;
 
 use crate::synth_engine::{
-    StereoSample,
+    RandomParam, Randomizer, StereoSample,
     buffer::{Buffer, VoicesLayout, copy_or_add_to_buffer, zero_buffer},
     routing::{
-        AudioRouterType, DataType, Input, InputMeta, InputSlots, ModuleId, NUM_CHANNELS,
-        ProcessContext, SamplesOutput, SpectralInputSlot, VoiceRouter, VolumeType,
+        AudioRouterType, DataType, Input, InputMeta, InputSlots, InputSource, ModuleId,
+        NUM_CHANNELS, ProcessContext, SamplesOutput, SpectralInputSlot, VoiceRouter, VolumeType,
     },
     smooth::SmoothedSample,
     synth_module::SynthModule,
@@ -114,18 +114,18 @@ impl Inputs {
         result
     }
 
-    fn update_amount(&mut self, input_type: Input, src_slot: usize, amount: StereoSample) {
+    fn update_source(&mut self, input_type: Input, src_slot: usize, source: &InputSource) {
         match input_type {
-            Input::Gain => self.gain.update_amount(src_slot, amount),
-            Input::Level => self.level.update_amount(src_slot, amount),
+            Input::Gain => self.gain.update_source(src_slot, source),
+            Input::Level => self.level.update_source(src_slot, source),
             Input::GainMix(idx) if idx < MAX_INPUTS => {
-                self.gain_mix[idx as usize].update_amount(src_slot, amount);
+                self.gain_mix[idx as usize].update_source(src_slot, source);
             }
             Input::LevelMix(idx) if idx < MAX_INPUTS => {
-                self.level_mix[idx as usize].update_amount(src_slot, amount);
+                self.level_mix[idx as usize].update_source(src_slot, source);
             }
             Input::AudioMix(idx) if idx < MAX_INPUTS => {
-                self.audio_mix[idx as usize].update_amount(src_slot, amount);
+                self.audio_mix[idx as usize].update_source(src_slot, source);
             }
             _ => (),
         }
@@ -148,6 +148,7 @@ impl Default for Buffers {
 
 pub struct Mixer {
     id: ModuleId,
+    bypass: bool,
     params: Params,
     channel_params: [ChannelParams; NUM_CHANNELS],
     buffers: Buffers,
@@ -172,6 +173,7 @@ impl Mixer {
 
         Self {
             id: config.id,
+            bypass: config.bypass,
             params: Params::from_config(config),
             channel_params: array::from_fn(|channel_idx| {
                 ChannelParams::from_config(config, channel_idx)
@@ -187,6 +189,7 @@ impl Mixer {
     pub fn get_config(&self) -> MixerConfig {
         MixerConfig {
             id: self.id,
+            bypass: self.bypass,
             num_inputs: self.params.num_inputs,
             inputs: array::from_fn(|input_idx| config::InputConfig {
                 volume_type: self.params.inputs[input_idx].volume_type,
@@ -207,6 +210,14 @@ impl Mixer {
         }
     }
 
+    // Levels stay where the patch balanced them
+    pub fn randomize(
+        _config: &mut MixerConfig,
+        _randomizer: &mut Randomizer,
+        _unlocked: impl Fn(RandomParam) -> bool,
+    ) {
+    }
+
     set_mono_param!(
         set_num_inputs,
         num_inputs,
@@ -354,6 +365,14 @@ impl SynthModule for Mixer {
         self.id
     }
 
+    fn bypass(&self) -> bool {
+        self.bypass
+    }
+
+    fn set_bypass(&mut self, bypass: bool) {
+        self.bypass = bypass;
+    }
+
     fn inputs(&self) -> &'static [InputMeta] {
         static INPUTS: &[InputMeta] = &[
             InputMeta::audio(Input::Gain),
@@ -397,20 +416,24 @@ impl SynthModule for Mixer {
         self.inputs = Inputs::from_slots(inputs, spectral_inputs);
     }
 
-    fn update_input_amount(&mut self, input_type: Input, src_slot: usize, amount: StereoSample) {
-        self.inputs.update_amount(input_type, src_slot, amount);
+    fn update_input_source(&mut self, input_type: Input, src_slot: usize, source: &InputSource) {
+        self.inputs.update_source(input_type, src_slot, source);
+    }
+
+    fn set_input_param(&mut self, input: Input, value: StereoSample) {
+        match input {
+            Input::Gain => self.set_output_gain(value),
+            Input::Level => self.set_output_level(value),
+            Input::GainMix(idx) => self.set_input_gain(idx, value),
+            Input::LevelMix(idx) => self.set_input_level(idx, value),
+            _ => (),
+        }
     }
 
     fn process_ui_events(&mut self) {
         while let Some(event) = self.audio_end.pop_event() {
             match event {
-                UiEvent::InputParam { input, value } => match input {
-                    Input::Gain => self.set_output_gain(value),
-                    Input::Level => self.set_output_level(value),
-                    Input::GainMix(idx) => self.set_input_gain(idx, value),
-                    Input::LevelMix(idx) => self.set_input_level(idx, value),
-                    _ => (),
-                },
+                UiEvent::InputParam { input, value } => self.set_input_param(input, value),
                 UiEvent::NumInputs(num_inputs) => self.set_num_inputs(num_inputs),
                 UiEvent::InputVolumeType {
                     input_idx,
@@ -55,4 +55,8 @@ impl MixerUiBridge {
 
 impl ModuleUiBridge for MixerUiBridge {
     fn update(&mut self) {}
+
+    fn edits(&self) -> u32 {
+        self.ui_end.edits()
+    }
 }
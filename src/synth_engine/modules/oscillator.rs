@@ -1,4 +1,9 @@
-use std::{array, convert::identity, f32, sync::Arc};
+use std::{
+    array,
+    convert::identity,
+    f32,
+    sync::{Arc, LazyLock},
+};
 
 use itertools::izip;
 use nih_plug::util::db_to_gain;
@@ -9,45 +14,70 @@ use wide::f32x4;
 
 use crate::{
     synth_engine::{
-        StereoSample,
+        RandomParam, Randomizer, SpectralSize, StereoSample,
         buffer::{
-            Buffer, SPECTRUM_BITS, SpectralBuffer, VoicesLayout, add_buffer_value,
-            new_voices_layout, zero_buffer,
+            Buffer, SpectralBuffer, VoicesLayout, add_buffer_value, new_voices_layout, zero_buffer,
+            zero_spectral_buffer,
         },
-        oscillator::link::{AudioEnd, UiEnd, UiEvent, create_link_pair},
+        oscillator::link::{AudioEnd, PreviewFrame, UiEnd, UiEvent, create_link_pair},
         phase::Phase,
         routing::{
-            AudioRouterType, DataType, Input, InputMeta, InputSlots, ModuleId, NUM_CHANNELS,
-            ProcessContext, SamplesOutput, SpectralInputSlot, VoiceEvent, VoiceRouter,
+            AudioRouterType, DataType, Input, InputMeta, InputSlots, InputSource, ModuleId,
+            NUM_CHANNELS, ProcessContext, SamplesOutput, SpectralInputSlot, VoiceEvent,
+            VoiceRouter,
         },
         smooth::SmoothedSample,
         synth_module::SynthModule,
         types::{ComplexSample, Sample},
     },
-    utils::{from_ms, pitch_to_freq, power_scale, st_to_octave},
+    utils::{freq_to_pitch, from_ms, pitch_to_freq, power_scale, st_to_octave},
 };
 
 mod config;
 mod link;
+mod table_builder;
 mod ui_bridge;
 
 #[cfg(test)]
 mod tests;
 
-pub use config::OscillatorConfig;
-pub use ui_bridge::OscillatorUiBridge;
+pub use config::{OscillatorConfig, TableUpdate, WaveQuality};
+pub use ui_bridge::{OscillatorUiBridge, WavePreview};
+
+use table_builder::TableJob;
 
-const WAVEFORM_BITS: usize = SPECTRUM_BITS + 1;
-const WAVEFORM_SIZE: usize = 1 << WAVEFORM_BITS;
+// Buffers fit the largest table, the selected quality and spectral size use a prefix of them
+const MIN_WAVEFORM_BITS: usize = WaveQuality::Low.bits(SpectralSize::S256);
+const MAX_WAVEFORM_BITS: usize = WaveQuality::High.bits(SpectralSize::S1024);
+const MAX_WAVEFORM_SIZE: usize = 1 << MAX_WAVEFORM_BITS;
 const WAVEFORM_PAD_LEFT: usize = 1;
 const WAVEFORM_PAD_RIGHT: usize = 2;
-const WAVEFORM_BUFFER_SIZE: usize = WAVEFORM_SIZE + WAVEFORM_PAD_LEFT + WAVEFORM_PAD_RIGHT;
-const DFT_BUFFER_SIZE: usize = (1 << (WAVEFORM_BITS - 1)) + 1;
+const WAVEFORM_BUFFER_SIZE: usize = MAX_WAVEFORM_SIZE + WAVEFORM_PAD_LEFT + WAVEFORM_PAD_RIGHT;
+const DFT_BUFFER_SIZE: usize = MAX_WAVEFORM_SIZE / 2 + 1;
 
 pub const MAX_UNISON_VOICES: usize = 16;
 const MAX_GLIDE: Sample = 5.0;
+const MAX_FM_INDEX: Sample = 10.0; // Radians of phase deviation per unit of modulator
+const MAX_FEEDBACK: Sample = 1.5; // Radians, the output turns to noise above it
+const MAX_DRIFT: Sample = st_to_octave(1.0);
+const MIN_DRIFT_RATE: Sample = 0.01; // Hz
+const MAX_DRIFT_RATE: Sample = 20.0; // Hz
+pub const MAX_PITCH_RANGE: Sample = st_to_octave(96.0);
+pub const MIN_FREQUENCY: Sample = 1.0; // Hz
+pub const MAX_FREQUENCY: Sample = 20_000.0; // Hz
 
 type WaveformBuffer = [Sample; WAVEFORM_BUFFER_SIZE];
+type InverseFft = Arc<dyn ComplexToReal<Sample>>;
+
+// Planned once for all the oscillators, one for every table size. Holding a plan here keeps
+// switching the quality or the spectral size on the audio thread from freeing the one the
+// oscillators dropped.
+static INVERSE_FFTS: LazyLock<[InverseFft; MAX_WAVEFORM_BITS - MIN_WAVEFORM_BITS + 1]> =
+    LazyLock::new(|| {
+        let mut planner = RealFftPlanner::<Sample>::new();
+
+        array::from_fn(|idx| planner.plan_fft_inverse(1 << (MIN_WAVEFORM_BITS + idx)))
+    });
 type DftBuffer = [ComplexSample; DFT_BUFFER_SIZE];
 
 const fn make_zero_wave_buffer() -> WaveformBuffer {
@@ -61,13 +91,37 @@ const fn zero_dft_buffer() -> DftBuffer {
 struct Params {
     unison: usize,
     steal_phase: bool,
+    random_phase: bool,
+    stereo_spread: Sample,
+    unison_blend: Sample,
+    pitch_range: Sample,
+    min_frequency: Sample,
+    max_frequency: Sample,
 }
 
 impl Params {
     fn from_config(c: &config::OscillatorConfig) -> Self {
+        let min_frequency = c.min_frequency.clamp(MIN_FREQUENCY, MAX_FREQUENCY);
+
         Self {
             unison: c.unison_voices,
             steal_phase: c.steal_phase,
+            random_phase: c.random_phase,
+            stereo_spread: c.stereo_spread,
+            unison_blend: c.unison_blend,
+            pitch_range: c.pitch_range.clamp(0.0, MAX_PITCH_RANGE),
+            min_frequency,
+            max_frequency: c.max_frequency.clamp(min_frequency, MAX_FREQUENCY),
+        }
+    }
+
+    // Keeps the played pitch within the frequency limits
+    fn clamp_pitch(&self, pitch: &mut [Sample]) {
+        let min_pitch = freq_to_pitch(self.min_frequency);
+        let max_pitch = freq_to_pitch(self.max_frequency);
+
+        for pitch in pitch {
+            *pitch = pitch.clamp(min_pitch, max_pitch);
         }
     }
 }
@@ -101,6 +155,10 @@ struct ChannelParams {
     glide_slope: Sample,
     phase_shift: SmoothedSample,
     frequency_shift: SmoothedSample,
+    fm_index: SmoothedSample,
+    feedback: SmoothedSample,
+    drift: Sample,      //Octaves
+    drift_rate: Sample, //Hz
     phases_blend: Sample,
     gains_blend: Sample,
     unison: [UnisonParams; MAX_UNISON_VOICES],
@@ -117,6 +175,10 @@ impl ChannelParams {
             glide_slope: c.glide_slope[channel_idx],
             phase_shift: c.phase_shift[channel_idx].into(),
             frequency_shift: c.frequency_shift[channel_idx].into(),
+            fm_index: c.fm_index[channel_idx].into(),
+            feedback: c.feedback[channel_idx].into(),
+            drift: c.drift[channel_idx],
+            drift_rate: c.drift_rate[channel_idx],
             phases_blend: c.phases_blend[channel_idx],
             gains_blend: c.gains_blend[channel_idx],
             unison: array::from_fn(|i| UnisonParams {
@@ -130,6 +192,7 @@ impl ChannelParams {
     }
 }
 
+#[derive(Clone, Copy)]
 struct Interpolated {
     from: Sample,
     to: Sample,
@@ -186,6 +249,11 @@ struct VoiceState {
     unison_gain: Interpolated,
     unison: [UnisonVoice; MAX_UNISON_VOICES],
     phases: [Phase; MAX_UNISON_VOICES],
+    random_phases: [Sample; MAX_UNISON_VOICES],
+    // Last two output samples, averaged before feeding back to damp the period-two oscillation
+    feedback: [Sample; 2],
+    // Lowpassed noise stepped once per block, shared by both channels
+    drift: Interpolated,
 }
 
 impl Default for VoiceState {
@@ -195,6 +263,9 @@ impl Default for VoiceState {
             pitch: 0.0,
             glide: None,
             phases: Default::default(),
+            random_phases: [0.0; MAX_UNISON_VOICES],
+            feedback: [0.0; 2],
+            drift: Interpolated { from: 0.0, to: 0.0 },
             unison_gain: Interpolated { from: 1.0, to: 1.0 },
             unison: Default::default(),
         }
@@ -204,6 +275,17 @@ impl Default for VoiceState {
 struct VoiceBuffers {
     wave_buffers: (WaveformBuffer, WaveformBuffer),
     wave_buffers_swapped: bool,
+    // False when the latest table was reused, the block then reads it without a crossfade
+    wave_changed: bool,
+    // Cutoff and bins the latest table was built from, zero cutoff forces a rebuild
+    built_cutoff: usize,
+    built_spectrum: SpectralBuffer,
+    // Upstream revision the bins were last matched against, lets unchanged spectra skip the compare
+    built_revision: Option<u32>,
+    // Economy updates skipped the rebuild of the last changed spectrum
+    skipped: bool,
+    // Builds held notes' tables on the worker thread, None builds them inline
+    job: Option<TableJob>,
 }
 
 impl Default for VoiceBuffers {
@@ -211,6 +293,73 @@ impl Default for VoiceBuffers {
         Self {
             wave_buffers_swapped: false,
             wave_buffers: (make_zero_wave_buffer(), make_zero_wave_buffer()),
+            wave_changed: true,
+            built_cutoff: 0,
+            built_spectrum: zero_spectral_buffer(),
+            built_revision: None,
+            skipped: false,
+            job: None,
+        }
+    }
+}
+
+impl VoiceBuffers {
+    fn invalidate(&mut self) {
+        self.wave_buffers.0.fill(0.0);
+        self.wave_buffers.1.fill(0.0);
+        self.wave_changed = true;
+        self.built_cutoff = 0;
+        self.built_revision = None;
+        self.skipped = false;
+        self.cancel_build();
+    }
+
+    fn cancel_build(&mut self) {
+        if let Some(job) = &mut self.job {
+            job.cancel();
+        }
+    }
+
+    // Swaps in the table built on the worker once it's done
+    fn receive_built(&mut self, max_cutoff: usize) -> bool {
+        let Some(job) = &mut self.job else {
+            return false;
+        };
+
+        let wave_to = if self.wave_buffers_swapped {
+            &mut self.wave_buffers.0
+        } else {
+            &mut self.wave_buffers.1
+        };
+
+        let Some((cutoff, revision)) = job.receive(max_cutoff, wave_to, &mut self.built_spectrum)
+        else {
+            return false;
+        };
+
+        self.wave_buffers_swapped = !self.wave_buffers_swapped;
+        self.built_cutoff = cutoff;
+        self.built_revision = Some(revision);
+        true
+    }
+
+    fn is_built_from(&self, cutoff_index: usize, revision: u32, spectrum: &SpectralBuffer) -> bool {
+        self.built_cutoff == cutoff_index
+            && (self.built_revision == Some(revision)
+                || self.built_spectrum[..cutoff_index] == spectrum[..cutoff_index])
+    }
+
+    fn waves(&self) -> (&WaveformBuffer, &WaveformBuffer) {
+        let (previous, latest) = if self.wave_buffers_swapped {
+            (&self.wave_buffers.0, &self.wave_buffers.1)
+        } else {
+            (&self.wave_buffers.1, &self.wave_buffers.0)
+        };
+
+        if self.wave_changed {
+            (previous, latest)
+        } else {
+            (latest, latest)
         }
     }
 }
@@ -222,6 +371,9 @@ struct Buffers {
     pitch: Buffer,
     phase_shift: Buffer,
     frequency_shift: Buffer,
+    fm_index: Buffer,
+    phase_mod: Buffer, // Normalized phase offset
+    feedback: Buffer,
 }
 
 impl Default for Buffers {
@@ -233,6 +385,9 @@ impl Default for Buffers {
             pitch: zero_buffer(),
             phase_shift: zero_buffer(),
             frequency_shift: zero_buffer(),
+            fm_index: zero_buffer(),
+            phase_mod: zero_buffer(),
+            feedback: zero_buffer(),
         }
     }
 }
@@ -274,6 +429,11 @@ pub struct Inputs {
     pitch_shift: InputSlots,
     phase_shift: InputSlots,
     freq_shift: InputSlots,
+    phase_mod: Option<usize>,
+    fm_index: InputSlots,
+    feedback: InputSlots,
+    drift: InputSlots,
+    drift_rate: InputSlots,
     detune: InputSlots,
     detune_power: InputSlots,
     glide: InputSlots,
@@ -290,6 +450,11 @@ impl Default for Inputs {
             pitch_shift: InputSlots::empty(Input::PitchShift),
             phase_shift: InputSlots::empty(Input::PhaseShift),
             freq_shift: InputSlots::empty(Input::FrequencyShift),
+            phase_mod: None,
+            fm_index: InputSlots::empty(Input::FmIndex),
+            feedback: InputSlots::empty(Input::Feedback),
+            drift: InputSlots::empty(Input::Drift),
+            drift_rate: InputSlots::empty(Input::DriftRate),
             detune: InputSlots::empty(Input::Detune),
             detune_power: InputSlots::empty(Input::DetunePower),
             glide: InputSlots::empty(Input::Glide),
@@ -310,6 +475,11 @@ impl Inputs {
                 Input::PitchShift => result.pitch_shift = input.clone(),
                 Input::PhaseShift => result.phase_shift = input.clone(),
                 Input::FrequencyShift => result.freq_shift = input.clone(),
+                Input::PhaseMod => result.phase_mod = input.slots.first().map(|s| s.src_slot),
+                Input::FmIndex => result.fm_index = input.clone(),
+                Input::Feedback => result.feedback = input.clone(),
+                Input::Drift => result.drift = input.clone(),
+                Input::DriftRate => result.drift_rate = input.clone(),
                 Input::Detune => result.detune = input.clone(),
                 Input::DetunePower => result.detune_power = input.clone(),
                 Input::Glide => result.glide = input.clone(),
@@ -329,18 +499,22 @@ impl Inputs {
         result
     }
 
-    fn update_amount(&mut self, input_type: Input, src_slot: usize, amount: StereoSample) {
+    fn update_source(&mut self, input_type: Input, src_slot: usize, source: &InputSource) {
         match input_type {
-            Input::Gain => self.gain.update_amount(src_slot, amount),
-            Input::PitchShift => self.pitch_shift.update_amount(src_slot, amount),
-            Input::PhaseShift => self.phase_shift.update_amount(src_slot, amount),
-            Input::FrequencyShift => self.freq_shift.update_amount(src_slot, amount),
-            Input::Detune => self.detune.update_amount(src_slot, amount),
-            Input::DetunePower => self.detune_power.update_amount(src_slot, amount),
-            Input::Glide => self.glide.update_amount(src_slot, amount),
-            Input::GlideSlope => self.glide_slope.update_amount(src_slot, amount),
-            Input::PhasesBlend => self.phases_blend.update_amount(src_slot, amount),
-            Input::GainsBlend => self.gains_blend.update_amount(src_slot, amount),
+            Input::Gain => self.gain.update_source(src_slot, source),
+            Input::PitchShift => self.pitch_shift.update_source(src_slot, source),
+            Input::PhaseShift => self.phase_shift.update_source(src_slot, source),
+            Input::FrequencyShift => self.freq_shift.update_source(src_slot, source),
+            Input::FmIndex => self.fm_index.update_source(src_slot, source),
+            Input::Feedback => self.feedback.update_source(src_slot, source),
+            Input::Drift => self.drift.update_source(src_slot, source),
+            Input::DriftRate => self.drift_rate.update_source(src_slot, source),
+            Input::Detune => self.detune.update_source(src_slot, source),
+            Input::DetunePower => self.detune_power.update_source(src_slot, source),
+            Input::Glide => self.glide.update_source(src_slot, source),
+            Input::GlideSlope => self.glide_slope.update_source(src_slot, source),
+            Input::PhasesBlend => self.phases_blend.update_source(src_slot, source),
+            Input::GainsBlend => self.gains_blend.update_source(src_slot, source),
             _ => (),
         }
     }
@@ -350,13 +524,21 @@ type Router<'v, 'f, 'c> = VoiceRouter<'v, 'f, 'c, AudioRouterType>;
 
 pub struct Oscillator {
     buffers: Buffers,
-    inverse_fft: Arc<dyn ComplexToReal<Sample>>,
+    wave_quality: WaveQuality,
+    spectral_size: SpectralSize,
+    table_update: TableUpdate,
+    inverse_fft: InverseFft,
     random: Pcg32,
+    // Separate from the phases, turning drift on doesn't change the random phases of a patch
+    drift_random: Pcg32,
     id: ModuleId,
+    bypass: bool,
     params: Params,
     channel_params: [ChannelParams; NUM_CHANNELS],
     audio_end: AudioEnd,
     ui_end: Option<UiEnd>,
+    // Lent by the editor while it shows the played waveform
+    preview_frame: Option<Box<PreviewFrame>>,
     inputs: Inputs,
     output_slot: usize,
     voices: VoicesLayout<VoiceState>,
@@ -364,27 +546,48 @@ pub struct Oscillator {
 }
 
 impl Oscillator {
-    pub fn new(id: ModuleId) -> Self {
-        Self::from_config(&OscillatorConfig {
-            id,
-            ..OscillatorConfig::default()
-        })
+    pub fn new(
+        id: ModuleId,
+        wave_quality: WaveQuality,
+        spectral_size: SpectralSize,
+        table_update: TableUpdate,
+    ) -> Self {
+        Self::from_config(
+            &OscillatorConfig {
+                id,
+                ..OscillatorConfig::default()
+            },
+            wave_quality,
+            spectral_size,
+            table_update,
+        )
     }
 
-    pub fn from_config(config: &config::OscillatorConfig) -> Self {
-        let (audio_end, ui_end) = create_link_pair();
+    pub fn from_config(
+        config: &config::OscillatorConfig,
+        wave_quality: WaveQuality,
+        spectral_size: SpectralSize,
+        table_update: TableUpdate,
+    ) -> Self {
+        let (audio_end, ui_end) = create_link_pair(config.unison.clone());
 
         Self {
             id: config.id,
+            bypass: config.bypass,
             params: Params::from_config(config),
             channel_params: array::from_fn(|channel_idx| {
                 ChannelParams::from_config(config, channel_idx)
             }),
             buffers: Buffers::default(),
-            inverse_fft: RealFftPlanner::<Sample>::new().plan_fft_inverse(WAVEFORM_SIZE),
+            wave_quality,
+            spectral_size,
+            table_update,
+            inverse_fft: Self::inverse_fft(wave_quality, spectral_size),
             random: Pcg32::new(420, 1337),
+            drift_random: Pcg32::new(421, 1337),
             audio_end,
             ui_end: Some(ui_end),
+            preview_frame: None,
             inputs: Inputs::default(),
             output_slot: usize::MAX,
             voices: new_voices_layout(),
@@ -395,25 +598,118 @@ impl Oscillator {
     pub fn get_config(&self) -> OscillatorConfig {
         OscillatorConfig {
             id: self.id,
+            bypass: self.bypass,
             unison_voices: self.params.unison,
             steal_phase: self.params.steal_phase,
+            random_phase: self.params.random_phase,
+            stereo_spread: self.params.stereo_spread,
+            unison_blend: self.params.unison_blend,
             gain: get_smoothed_param!(self, gain),
             pitch_shift: get_smoothed_param!(self, pitch_shift),
+            pitch_range: self.params.pitch_range,
+            min_frequency: self.params.min_frequency,
+            max_frequency: self.params.max_frequency,
             detune: get_stereo_param!(self, detune),
             detune_power: get_stereo_param!(self, detune_power),
             glide: get_stereo_param!(self, glide),
             glide_slope: get_stereo_param!(self, glide_slope),
             phase_shift: get_smoothed_param!(self, phase_shift),
             frequency_shift: get_smoothed_param!(self, frequency_shift),
+            fm_index: get_smoothed_param!(self, fm_index),
+            feedback: get_smoothed_param!(self, feedback),
+            drift: get_stereo_param!(self, drift),
+            drift_rate: get_stereo_param!(self, drift_rate),
             phases_blend: get_stereo_param!(self, phases_blend),
             gains_blend: get_stereo_param!(self, gains_blend),
-            unison: array::from_fn(|i| config::UnisonConfig {
-                initial_phase: get_unison_param!(self, initial_phase, i),
-                phase_shift: get_unison_param!(self, phase_shift, i),
-                phase_shift_to: get_unison_param!(self, phase_shift_to, i),
-                gain: get_unison_param!(self, gain, i),
-                gain_to: get_unison_param!(self, gain_to, i),
-            }),
+            unison: array::from_fn(|i| self.unison_config(i)),
+        }
+    }
+
+    pub fn randomize(
+        config: &mut OscillatorConfig,
+        randomizer: &mut Randomizer,
+        unlocked: impl Fn(RandomParam) -> bool,
+    ) {
+        if unlocked(RandomParam::Detune) {
+            config.detune = randomizer.stereo(config.detune, 0.0, st_to_octave(1.0));
+        }
+    }
+
+    fn unison_config(&self, voice_idx: usize) -> config::UnisonConfig {
+        config::UnisonConfig {
+            initial_phase: get_unison_param!(self, initial_phase, voice_idx),
+            phase_shift: get_unison_param!(self, phase_shift, voice_idx),
+            phase_shift_to: get_unison_param!(self, phase_shift_to, voice_idx),
+            gain: get_unison_param!(self, gain, voice_idx),
+            gain_to: get_unison_param!(self, gain_to, voice_idx),
+        }
+    }
+
+    // Hands the editor the unison state changed on the audio thread, doesn't allocate
+    fn publish_unison(&mut self) {
+        for voice_idx in 0..MAX_UNISON_VOICES {
+            let unison = self.unison_config(voice_idx);
+
+            self.audio_end.unison_snapshot()[voice_idx] = unison;
+        }
+
+        self.audio_end.publish_unison();
+    }
+
+    pub fn wave_quality(&self) -> WaveQuality {
+        self.wave_quality
+    }
+
+    fn inverse_fft(wave_quality: WaveQuality, spectral_size: SpectralSize) -> InverseFft {
+        INVERSE_FFTS[wave_quality.bits(spectral_size) - MIN_WAVEFORM_BITS].clone()
+    }
+
+    // Doesn't allocate, the plans are shared and made along with the first oscillator
+    pub fn set_wave_quality(&mut self, wave_quality: WaveQuality) {
+        if wave_quality == self.wave_quality {
+            return;
+        }
+
+        self.wave_quality = wave_quality;
+        self.inverse_fft = Self::inverse_fft(wave_quality, self.spectral_size);
+
+        for voice_buffers in self.voice_buffers.iter_mut().flatten() {
+            voice_buffers.invalidate();
+        }
+    }
+
+    pub fn spectral_size(&self) -> SpectralSize {
+        self.spectral_size
+    }
+
+    // Doesn't allocate either, the tables are resized along with the spectra they're built from
+    pub fn set_spectral_size(&mut self, spectral_size: SpectralSize) {
+        if spectral_size == self.spectral_size {
+            return;
+        }
+
+        self.spectral_size = spectral_size;
+        self.inverse_fft = Self::inverse_fft(self.wave_quality, spectral_size);
+
+        for voice_buffers in self.voice_buffers.iter_mut().flatten() {
+            voice_buffers.invalidate();
+        }
+    }
+
+    pub fn table_update(&self) -> TableUpdate {
+        self.table_update
+    }
+
+    pub fn set_table_update(&mut self, table_update: TableUpdate) {
+        self.table_update = table_update;
+    }
+
+    // Allocates, the engine sets it before handing the oscillator to the audio thread
+    pub fn set_background_tables(&mut self, enabled: bool) {
+        for voice_buffers in self.voice_buffers.iter_mut().flatten() {
+            if voice_buffers.job.is_some() != enabled {
+                voice_buffers.job = enabled.then(TableJob::new);
+            }
         }
     }
 
@@ -424,12 +720,36 @@ impl Oscillator {
         unison.clamp(1, MAX_UNISON_VOICES)
     );
     set_mono_param!(set_steal_phase, steal_phase, bool);
+    set_mono_param!(set_random_phase, random_phase, bool);
+    set_mono_param!(
+        set_stereo_spread,
+        stereo_spread,
+        Sample,
+        stereo_spread.clamp(0.0, 1.0)
+    );
+    set_mono_param!(
+        set_unison_blend,
+        unison_blend,
+        Sample,
+        unison_blend.clamp(0.0, 1.0)
+    );
+    set_mono_param!(
+        set_pitch_range,
+        pitch_range,
+        Sample,
+        pitch_range.clamp(0.0, MAX_PITCH_RANGE)
+    );
+
+    pub fn set_frequency_range(&mut self, min: Sample, max: Sample) {
+        self.params.min_frequency = min.clamp(MIN_FREQUENCY, MAX_FREQUENCY);
+        self.params.max_frequency = max.clamp(self.params.min_frequency, MAX_FREQUENCY);
+    }
 
     set_smoothed_param!(set_gain, gain, gain.clamp(0.0, 1.0));
     set_smoothed_param!(
         set_pitch_shift,
         pitch_shift,
-        pitch_shift.clamp(st_to_octave(-60.0), st_to_octave(60.0))
+        pitch_shift.clamp(-MAX_PITCH_RANGE, MAX_PITCH_RANGE)
     );
     set_stereo_param!(set_detune, detune, detune.clamp(0.0, st_to_octave(1.0)));
     set_stereo_param!(
@@ -443,6 +763,14 @@ impl Oscillator {
 
     set_smoothed_param!(set_phase_shift, phase_shift, phase_shift.clamp(-1.0, 1.0));
     set_smoothed_param!(set_frequency_shift, frequency_shift);
+    set_smoothed_param!(set_fm_index, fm_index, fm_index.clamp(0.0, MAX_FM_INDEX));
+    set_smoothed_param!(set_feedback, feedback, feedback.clamp(0.0, 1.0));
+    set_stereo_param!(set_drift, drift, drift.clamp(0.0, MAX_DRIFT));
+    set_stereo_param!(
+        set_drift_rate,
+        drift_rate,
+        drift_rate.clamp(MIN_DRIFT_RATE, MAX_DRIFT_RATE)
+    );
 
     set_stereo_param!(set_phases_blend, phases_blend, phases_blend.clamp(0.0, 1.0));
     set_stereo_param!(set_gains_blend, gains_blend, gains_blend.clamp(0.0, 1.0));
@@ -504,7 +832,7 @@ impl Oscillator {
             }
         }
 
-        self.audio_end.push_refresh_state();
+        self.publish_unison();
     }
 
     pub fn randomize_phases(
@@ -536,12 +864,12 @@ impl Oscillator {
             }
         }
 
-        self.audio_end.push_refresh_state();
+        self.publish_unison();
     }
 
     #[inline(always)]
-    fn get_wave_slice_mut(wave_buff: &mut WaveformBuffer) -> &mut [Sample] {
-        &mut wave_buff[WAVEFORM_PAD_LEFT..(WAVEFORM_BUFFER_SIZE - WAVEFORM_PAD_RIGHT)]
+    fn get_wave_slice_mut(wave_buff: &mut WaveformBuffer, wave_size: usize) -> &mut [Sample] {
+        &mut wave_buff[WAVEFORM_PAD_LEFT..(WAVEFORM_PAD_LEFT + wave_size)]
     }
 
     #[inline(always)]
@@ -575,26 +903,47 @@ impl Oscillator {
     }
 
     #[inline(always)]
-    fn wrap_wave_buffer(wave_buff: &mut WaveformBuffer) {
-        wave_buff[0] = wave_buff[WAVEFORM_BUFFER_SIZE - WAVEFORM_PAD_RIGHT - 1];
-        wave_buff[WAVEFORM_BUFFER_SIZE - WAVEFORM_PAD_RIGHT] = wave_buff[WAVEFORM_PAD_LEFT];
-        wave_buff[WAVEFORM_BUFFER_SIZE - WAVEFORM_PAD_RIGHT + 1] = wave_buff[WAVEFORM_PAD_LEFT + 1];
+    fn wrap_wave_buffer(wave_buff: &mut WaveformBuffer, wave_size: usize) {
+        let end = WAVEFORM_PAD_LEFT + wave_size;
+
+        wave_buff[0] = wave_buff[end - 1];
+        wave_buff[end] = wave_buff[WAVEFORM_PAD_LEFT];
+        wave_buff[end + 1] = wave_buff[WAVEFORM_PAD_LEFT + 1];
     }
 
-    fn build_wave(
-        inverse_fft: &dyn ComplexToReal<Sample>,
+    // Highest frequency of the block, tables built for it stay alias-free for the whole sweep
+    fn max_frequency(buffers: &Buffers, samples: usize) -> Sample {
+        buffers.pitch[..samples]
+            .iter()
+            .zip(&buffers.frequency_shift[..samples])
+            .map(|(pitch, freq_shift)| (pitch_to_freq(*pitch) + freq_shift).abs())
+            .fold(0.0, Sample::max)
+    }
+
+    // Number of bins below Nyquist for the played frequency, the rest of the table is silent
+    fn cutoff_index(
+        wave_size: usize,
+        spectral_size: SpectralSize,
         frequency: f32,
         sample_rate: f32,
+    ) -> usize {
+        let max_frequency = 0.5 * sample_rate;
+
+        ((max_frequency / frequency.abs()).floor() as usize + 1)
+            .min(spectral_size.bins())
+            .min(wave_size / 2 + 1)
+    }
+
+    fn build_wave(
+        inverse_fft: &dyn ComplexToReal<Sample>,
+        wave_size: usize,
+        cutoff_index: usize,
         spectral_buff: &SpectralBuffer,
         tmp_spectral_buff: &mut DftBuffer,
         scratch_buff: &mut DftBuffer,
         out_wave_buff: &mut WaveformBuffer,
     ) {
-        let frequency = frequency.abs();
-        let max_frequency = 0.5 * sample_rate;
-
-        let cutoff_index =
-            ((max_frequency / frequency).floor() as usize + 1).min(spectral_buff.len());
+        let tmp_spectral_buff = &mut tmp_spectral_buff[..wave_size / 2 + 1];
 
         tmp_spectral_buff[..cutoff_index].copy_from_slice(&spectral_buff[..cutoff_index]);
         tmp_spectral_buff[cutoff_index..].fill(ComplexSample::ZERO);
@@ -602,28 +951,39 @@ impl Oscillator {
         inverse_fft
             .process_with_scratch(
                 tmp_spectral_buff,
-                Self::get_wave_slice_mut(out_wave_buff),
-                scratch_buff,
+                Self::get_wave_slice_mut(out_wave_buff, wave_size),
+                &mut scratch_buff[..inverse_fft.get_scratch_len()],
             )
             .unwrap();
-        Self::wrap_wave_buffer(out_wave_buff);
+        Self::wrap_wave_buffer(out_wave_buff, wave_size);
     }
 
+    // Note starts and rising pitches build their tables inline, the block needs them right away.
+    // Other spectrum changes of held notes go to the worker when the voice has a job, the voice
+    // keeps its latest table and crossfades to the new one in the block it arrives. Offline
+    // renders have no jobs and build everything inline, their output doesn't depend on timing.
     fn build_waveforms(
         inverse_fft: &dyn ComplexToReal<Sample>,
+        (wave_size, spectral_size, table_update): (usize, SpectralSize, TableUpdate),
         inputs: &Inputs,
         voice_buffers: &mut VoiceBuffers,
         buffers: &mut Buffers,
         triggered: bool,
         router: &Router<'_, '_, '_>,
     ) {
+        let frequency = Self::max_frequency(buffers, router.samples());
+        let cutoff_index =
+            Self::cutoff_index(wave_size, spectral_size, frequency, router.sample_rate());
+
         if triggered {
+            voice_buffers.cancel_build();
+
             let spectrum_from = router.spectral(inputs.spectrum, true);
 
             Self::build_wave(
                 inverse_fft,
-                pitch_to_freq(buffers.pitch[0]) + buffers.frequency_shift[0],
-                router.sample_rate(),
+                wave_size,
+                cutoff_index,
                 spectrum_from,
                 &mut buffers.tmp_spectral,
                 &mut buffers.scratch,
@@ -633,7 +993,59 @@ impl Oscillator {
             voice_buffers.wave_buffers_swapped = false;
         }
 
+        let received = !triggered && voice_buffers.receive_built(cutoff_index);
         let spectrum = router.spectral(inputs.spectrum, false);
+        let revision = router.spectral_revision(inputs.spectrum);
+
+        // Held notes with a static spectrum keep playing the latest table
+        if !triggered && voice_buffers.is_built_from(cutoff_index, revision, spectrum) {
+            voice_buffers.wave_changed = received;
+            voice_buffers.built_revision = Some(revision);
+            return;
+        }
+
+        // Fewer bins than the pitch allows stay alias-free, only a rising pitch can't wait
+        if table_update == TableUpdate::Economy
+            && !triggered
+            && !voice_buffers.skipped
+            && voice_buffers.built_cutoff != 0
+            && cutoff_index >= voice_buffers.built_cutoff
+        {
+            voice_buffers.skipped = true;
+            voice_buffers.wave_changed = received;
+            return;
+        }
+
+        voice_buffers.skipped = false;
+
+        // A rising pitch would push the top bins of the table fading out past Nyquist
+        if !triggered && cutoff_index < voice_buffers.built_cutoff {
+            let latest = if voice_buffers.wave_buffers_swapped {
+                &mut voice_buffers.wave_buffers.1
+            } else {
+                &mut voice_buffers.wave_buffers.0
+            };
+
+            Self::build_wave(
+                inverse_fft,
+                wave_size,
+                cutoff_index,
+                &voice_buffers.built_spectrum,
+                &mut buffers.tmp_spectral,
+                &mut buffers.scratch,
+                latest,
+            );
+            voice_buffers.built_cutoff = cutoff_index;
+        }
+
+        if !triggered && let Some(job) = &mut voice_buffers.job {
+            if !job.is_requested(cutoff_index, revision, spectrum) {
+                job.request(wave_size, cutoff_index, revision, spectrum);
+            }
+
+            voice_buffers.wave_changed = received;
+            return;
+        }
 
         let wave_to = if voice_buffers.wave_buffers_swapped {
             &mut voice_buffers.wave_buffers.0
@@ -643,19 +1055,22 @@ impl Oscillator {
 
         Self::build_wave(
             inverse_fft,
-            pitch_to_freq(buffers.pitch[router.samples() - 1])
-                + buffers.frequency_shift[router.samples() - 1],
-            router.sample_rate(),
+            wave_size,
+            cutoff_index,
             spectrum,
             &mut buffers.tmp_spectral,
             &mut buffers.scratch,
             wave_to,
         );
         voice_buffers.wave_buffers_swapped = !voice_buffers.wave_buffers_swapped;
+        voice_buffers.wave_changed = true;
+        voice_buffers.built_cutoff = cutoff_index;
+        voice_buffers.built_revision = Some(revision);
+        voice_buffers.built_spectrum[..cutoff_index].copy_from_slice(&spectrum[..cutoff_index]);
     }
 
     fn process_unison(
-        unison: usize,
+        params: &Params,
         channel: &ChannelParams,
         inputs: &Inputs,
         voice: &mut VoiceState,
@@ -664,6 +1079,8 @@ impl Oscillator {
         const MAX_DETUNE: Sample = 1.0;
         const MAX_DETUNE_POWER: Sample = 5.0;
 
+        let unison = params.unison;
+
         if unison < 2 {
             voice.unison[0] = UnisonVoice::default();
             voice.unison_gain = Interpolated { from: 1.0, to: 1.0 };
@@ -676,8 +1093,21 @@ impl Oscillator {
             gain: Sample,
         }
 
+        // Outer voices are faded by the blend and panned towards their side by the spread
+        fn unison_level(spread: Sample, params: &Params, channel_idx: usize) -> Sample {
+            let blend_level = (params.unison_blend - 1.0).mul_add(spread.abs(), 1.0);
+            let pan = spread * params.stereo_spread;
+            let pan_level = if channel_idx == 0 {
+                (1.0 - pan).min(1.0)
+            } else {
+                (1.0 + pan).min(1.0)
+            };
+
+            blend_level * pan_level
+        }
+
         fn calc_update(
-            unison: usize,
+            params: &Params,
             triggered: bool,
             channel: &ChannelParams,
             inputs: &Inputs,
@@ -699,6 +1129,8 @@ impl Oscillator {
                 .scalar_param(&inputs.gains_blend, channel.gains_blend, triggered)
                 .clamp(0.0, 1.0);
 
+            let unison = params.unison;
+            let channel_idx = router.channel_idx();
             let center = 0.5 * (unison - 1) as Sample;
             let center_recip = center.recip();
 
@@ -715,7 +1147,8 @@ impl Oscillator {
                             .exp2(),
                         phase_shift: (param.phase_shift_to - param.phase_shift)
                             .mul_add(phases_blend, param.phase_shift),
-                        gain: (param.gain_to - param.gain).mul_add(gains_blend, param.gain),
+                        gain: (param.gain_to - param.gain).mul_add(gains_blend, param.gain)
+                            * unison_level(spread, params, channel_idx),
                     }
                 })
         }
@@ -732,7 +1165,7 @@ impl Oscillator {
         if voice.triggered {
             for (state, update) in izip!(
                 &mut voice.unison,
-                calc_update(unison, true, channel, inputs, router)
+                calc_update(params, true, channel, inputs, router)
             ) {
                 state.rate.from = update.rate;
                 state.phase_shift.from = update.phase_shift;
@@ -758,7 +1191,7 @@ impl Oscillator {
 
         for (state, update) in izip!(
             &mut voice.unison,
-            calc_update(unison, false, channel, inputs, router)
+            calc_update(params, false, channel, inputs, router)
         ) {
             state.rate.to = update.rate;
             state.phase_shift.to = update.phase_shift;
@@ -842,6 +1275,45 @@ impl Oscillator {
         }
     }
 
+    // Slow random pitch wander, noise through a one-pole lowpass at the drift rate. The gain is
+    // normalized, the deviation stays the same at any rate.
+    fn process_drift(
+        channel: &ChannelParams,
+        inputs: &Inputs,
+        buffers: &mut Buffers,
+        voice: &mut VoiceState,
+        random: &mut Pcg32,
+        router: &mut Router<'_, '_, '_>,
+    ) {
+        let samples = router.samples();
+        let triggered = voice.triggered;
+
+        if router.channel_idx() == 0 {
+            let rate = router
+                .scalar_param(&inputs.drift_rate, channel.drift_rate, triggered)
+                .clamp(MIN_DRIFT_RATE, MAX_DRIFT_RATE);
+            let pole = (-f32::consts::TAU * rate * samples as Sample / router.sample_rate()).exp();
+            let noise: Sample = random.random_range(-1.0..=1.0);
+
+            voice.drift.advance();
+            voice.drift.to = pole.mul_add(voice.drift.to, (1.0 - pole * pole).sqrt() * noise);
+        }
+
+        let drift = router
+            .scalar_param(&inputs.drift, channel.drift, triggered)
+            .clamp(0.0, MAX_DRIFT);
+
+        if drift == 0.0 {
+            return;
+        }
+
+        let t_mult = (samples as Sample).recip();
+
+        for (idx, pitch) in buffers.pitch[..samples].iter_mut().enumerate() {
+            *pitch += drift * voice.drift.interpolate(idx as Sample * t_mult);
+        }
+    }
+
     fn process_voice(
         &mut self,
         mono_spectrum: bool,
@@ -853,6 +1325,11 @@ impl Oscillator {
         let inputs = &self.inputs;
         let buffers = &mut self.buffers;
         let channel = &mut self.channel_params[channel_idx];
+
+        if channel_idx != 0 {
+            self.voices[channel_idx][voice_idx].drift = self.voices[0][voice_idx].drift;
+        }
+
         let voice = &mut self.voices[channel_idx][voice_idx];
         let output = output[channel_idx][voice_idx].output(router.samples());
         let samples = router.samples();
@@ -865,7 +1342,23 @@ impl Oscillator {
             &mut buffers.pitch,
         );
 
+        let pitch_range = self.params.pitch_range;
+
+        for pitch in &mut buffers.pitch[..samples] {
+            *pitch = pitch.clamp(-pitch_range, pitch_range);
+        }
+
         add_buffer_value(&mut buffers.pitch[..samples], voice.pitch);
+        Self::process_glide(channel, inputs, buffers, voice, &mut router);
+        Self::process_drift(
+            channel,
+            inputs,
+            buffers,
+            voice,
+            &mut self.drift_random,
+            &mut router,
+        );
+        self.params.clamp_pitch(&mut buffers.pitch[..samples]);
 
         router.buff_param(
             &inputs.phase_shift,
@@ -879,6 +1372,40 @@ impl Oscillator {
             &mut buffers.frequency_shift,
         );
 
+        router.buff_param(
+            &inputs.fm_index,
+            &mut channel.fm_index,
+            &mut buffers.fm_index,
+        );
+
+        for (phase_mod, modulator, fm_index) in izip!(
+            &mut buffers.phase_mod[..samples],
+            router.buff(inputs.phase_mod),
+            &buffers.fm_index
+        ) {
+            *phase_mod =
+                modulator * fm_index.clamp(0.0, MAX_FM_INDEX) * (0.5 * f32::consts::FRAC_1_PI);
+        }
+
+        router.buff_param(
+            &inputs.feedback,
+            &mut channel.feedback,
+            &mut buffers.feedback,
+        );
+
+        let triggered = voice.triggered;
+
+        Self::process_unison(&self.params, channel, inputs, voice, &mut router);
+        voice.triggered = false;
+
+        let render = match MAX_WAVEFORM_BITS - self.wave_quality.bits(self.spectral_size) {
+            0 => Self::render_voice::<MAX_WAVEFORM_BITS>,
+            1 => Self::render_voice::<{ MAX_WAVEFORM_BITS - 1 }>,
+            2 => Self::render_voice::<{ MAX_WAVEFORM_BITS - 2 }>,
+            3 => Self::render_voice::<{ MAX_WAVEFORM_BITS - 3 }>,
+            _ => Self::render_voice::<MIN_WAVEFORM_BITS>,
+        };
+
         let voice_buffers = if mono_spectrum && channel_idx != 0 {
             &self.voice_buffers[0][voice_idx]
         } else {
@@ -886,42 +1413,82 @@ impl Oscillator {
 
             Self::build_waveforms(
                 self.inverse_fft.as_ref(),
+                (
+                    self.wave_quality.size(self.spectral_size),
+                    self.spectral_size,
+                    self.table_update,
+                ),
                 inputs,
                 vb,
                 buffers,
-                voice.triggered,
+                triggered,
                 &router,
             );
             vb
         };
 
-        let (wave_from, wave_to) = if voice_buffers.wave_buffers_swapped {
-            (&voice_buffers.wave_buffers.0, &voice_buffers.wave_buffers.1)
-        } else {
-            (&voice_buffers.wave_buffers.1, &voice_buffers.wave_buffers.0)
-        };
+        render(
+            output,
+            buffers,
+            voice,
+            self.params.unison,
+            voice_buffers.waves(),
+            router.sample_rate(),
+            samples,
+        );
+    }
 
-        Self::process_unison(self.params.unison, channel, inputs, voice, &mut router);
-        Self::process_glide(channel, inputs, buffers, voice, &mut router);
+    // Sends the spectrum and cutoff of the voice back in the frame the editor lent
+    fn publish_preview(&mut self, router: Router<'_, '_, '_>) {
+        let voice_buffers = &self.voice_buffers[0][router.voice_idx()];
 
-        if voice.triggered {
-            voice.triggered = false;
+        if voice_buffers.built_cutoff == 0 {
+            return;
         }
 
-        let freq_phase_mult = Phase::freq_phase_mult(router.sample_rate());
+        let Some(mut frame) = self.preview_frame.take() else {
+            return;
+        };
+
+        frame.wave_quality = self.wave_quality;
+        frame.spectral_size = self.spectral_size;
+        frame.cutoff = voice_buffers.built_cutoff;
+        frame
+            .spectrum
+            .copy_from_slice(router.spectral(self.inputs.spectrum, false));
+        self.preview_frame = self.audio_end.push_preview(frame);
+    }
+
+    #[inline(always)]
+    fn render_voice<const WAVEFORM_BITS: usize>(
+        output: &mut [Sample],
+        buffers: &Buffers,
+        voice: &mut VoiceState,
+        unison: usize,
+        (wave_from, wave_to): (&WaveformBuffer, &WaveformBuffer),
+        sample_rate: Sample,
+        samples: usize,
+    ) {
+        let freq_phase_mult = Phase::freq_phase_mult(sample_rate);
         let buff_t_mult = (samples as f32).recip();
 
-        for (out, pitch, phase_shift, freq_shift, gain, sample_idx) in izip!(
+        const FEEDBACK_MULT: Sample = 0.25 * MAX_FEEDBACK * f32::consts::FRAC_1_PI;
+
+        for (out, pitch, phase_shift, phase_mod, feedback, freq_shift, gain, sample_idx) in izip!(
             output,
             &buffers.pitch,
             &buffers.phase_shift,
+            &buffers.phase_mod,
+            &buffers.feedback,
             &buffers.frequency_shift,
             &buffers.gain,
             0..samples
         ) {
             let mut sample_acc = f32x4::splat(0.0);
             let buff_t = sample_idx as Sample * buff_t_mult;
-            let phase_shift = Phase::from_normalized(*phase_shift);
+            let feedback_shift =
+                feedback.clamp(0.0, 1.0) * FEEDBACK_MULT * (voice.feedback[0] + voice.feedback[1]);
+            let phase_shift = Phase::from_normalized(phase_shift + phase_mod + feedback_shift);
             let pitch_phase_inc = pitch_to_freq(*pitch) * freq_phase_mult;
             let freq_phase_inc = freq_shift * freq_phase_mult;
 
@@ -929,7 +1496,7 @@ impl Oscillator {
                 .phases
                 .iter_mut()
                 .zip(voice.unison.iter())
-                .take(self.params.unison)
+                .take(unison)
             {
                 let read_phase = *phase
                     + phase_shift
@@ -942,7 +1509,11 @@ impl Oscillator {
                 *phase += pitch_phase_inc.mul_add(uv.rate.interpolate(buff_t), freq_phase_inc);
             }
 
-            *out = sample_acc.reduce_add() * gain * voice.unison_gain.interpolate(buff_t);
+            let sample = sample_acc.reduce_add() * voice.unison_gain.interpolate(buff_t);
+
+            // Loud spectra would overshoot the index, the clamp keeps the loop bounded
+            voice.feedback = [sample.clamp(-1.0, 1.0), voice.feedback[0]];
+            *out = sample * gain;
         }
     }
 
@@ -952,11 +1523,30 @@ impl Oscillator {
         prev_voice_idx: Option<usize>,
         voice_idx: usize,
         pitch: Sample,
+        glide: bool,
     ) {
+        // Both channels share the same random offsets to keep the voice coherent in stereo
+        let random_phases = if !self.params.random_phase {
+            [0.0; MAX_UNISON_VOICES]
+        } else if channel_idx == 0 {
+            array::from_fn(|_| self.random.random::<Sample>())
+        } else {
+            self.voices[0][voice_idx].random_phases
+        };
+
+        // Each note starts the drift at a different point, the second channel copies it later
+        let drift = if channel_idx == 0 {
+            self.drift_random.random_range(-1.0..=1.0)
+        } else {
+            0.0
+        };
+
         let channel = &self.channel_params[channel_idx];
         let voices = &mut self.voices[channel_idx];
 
-        if let Some(prev_voice_idx) = prev_voice_idx {
+        if let Some(prev_voice_idx) = prev_voice_idx
+            && glide
+        {
             let prev_voice_state = &voices[prev_voice_idx];
             let prev_pitch = prev_voice_state
                 .glide
@@ -972,27 +1562,37 @@ impl Oscillator {
 
         voice.pitch = pitch;
         voice.triggered = true;
+        voice.random_phases = random_phases;
+        voice.feedback = [0.0; 2];
+        voice.drift = Interpolated {
+            from: drift,
+            to: drift,
+        };
 
         if let Some(prev_voice_idx) = prev_voice_idx
             && self.params.steal_phase
         {
             voices[voice_idx].phases = voices[prev_voice_idx].phases;
         } else {
-            for (phase, unison_voice) in voice.phases.iter_mut().zip(&channel.unison) {
-                *phase = Phase::from_normalized(unison_voice.initial_phase);
+            for (phase, unison_voice, random_phase) in
+                izip!(&mut voice.phases, &channel.unison, &random_phases)
+            {
+                *phase = Phase::from_normalized(unison_voice.initial_phase + random_phase);
             }
         }
     }
 
-    fn handle_update(&mut self, channel_idx: usize, voice_idx: usize, pitch: Sample) {
+    fn handle_update(&mut self, channel_idx: usize, voice_idx: usize, pitch: Sample, glide: bool) {
         let voice = &mut self.voices[channel_idx][voice_idx];
 
-        voice.glide = Some(Glide::new(
-            voice
-                .glide
-                .as_ref()
-                .map_or(voice.pitch, |g| g.current_pitch),
-        ));
+        voice.glide = glide.then(|| {
+            Glide::new(
+                voice
+                    .glide
+                    .as_ref()
+                    .map_or(voice.pitch, |g| g.current_pitch),
+            )
+        });
         voice.pitch = pitch;
     }
 }
@@ -1002,6 +1602,14 @@ impl SynthModule for Oscillator {
         self.id
     }
 
+    fn bypass(&self) -> bool {
+        self.bypass
+    }
+
+    fn set_bypass(&mut self, bypass: bool) {
+        self.bypass = bypass;
+    }
+
     fn inputs(&self) -> &'static [InputMeta] {
         static INPUTS: &[InputMeta] = &[
             InputMeta::spectral(Input::Spectrum),
@@ -1009,6 +1617,11 @@ impl SynthModule for Oscillator {
             InputMeta::control(Input::PitchShift),
             InputMeta::audio_mixed(Input::PhaseShift),
             InputMeta::audio_mixed(Input::FrequencyShift),
+            InputMeta::audio(Input::PhaseMod),
+            InputMeta::control(Input::FmIndex),
+            InputMeta::control(Input::Feedback),
+            InputMeta::control(Input::Drift),
+            InputMeta::control(Input::DriftRate),
             InputMeta::control(Input::Detune),
             InputMeta::control(Input::DetunePower),
             InputMeta::control(Input::Glide),
@@ -1034,10 +1647,16 @@ impl SynthModule for Oscillator {
 
     fn set_input_slots(&mut self, inputs: &[InputSlots], spectral_inputs: &[SpectralInputSlot]) {
         self.inputs = Inputs::from_slots(inputs, spectral_inputs);
+
+        // Revisions of a reconnected slot are unrelated to the cached ones
+        for voice_buffers in self.voice_buffers.iter_mut().flatten() {
+            voice_buffers.built_revision = None;
+            voice_buffers.cancel_build();
+        }
     }
 
-    fn update_input_amount(&mut self, input_type: Input, src_slot: usize, amount: StereoSample) {
-        self.inputs.update_amount(input_type, src_slot, amount);
+    fn update_input_source(&mut self, input_type: Input, src_slot: usize, source: &InputSource) {
+        self.inputs.update_source(input_type, src_slot, source);
     }
 
     fn process_events(&mut self, events: &[VoiceEvent]) {
@@ -1048,33 +1667,54 @@ impl SynthModule for Oscillator {
                         voice_idx,
                         prev_voice_idx,
                         pitch,
+                        glide,
                         ..
-                    } => self.handle_trigger(channel_idx, *prev_voice_idx, *voice_idx, *pitch),
+                    } => self.handle_trigger(
+                        channel_idx,
+                        *prev_voice_idx,
+                        *voice_idx,
+                        *pitch,
+                        *glide,
+                    ),
                     VoiceEvent::Update {
-                        voice_idx, pitch, ..
-                    } => self.handle_update(channel_idx, *voice_idx, *pitch),
+                        voice_idx,
+                        pitch,
+                        glide,
+                        ..
+                    } => self.handle_update(channel_idx, *voice_idx, *pitch, *glide),
+                    VoiceEvent::Retune { voice_idx, pitch } => {
+                        self.voices[channel_idx][*voice_idx].pitch = *pitch;
+                    }
                     _ => (),
                 }
             }
         }
     }
 
+    fn set_input_param(&mut self, input: Input, value: StereoSample) {
+        match input {
+            Input::Gain => self.set_gain(value),
+            Input::PitchShift => self.set_pitch_shift(value),
+            Input::PhaseShift => self.set_phase_shift(value),
+            Input::FrequencyShift => self.set_frequency_shift(value),
+            Input::FmIndex => self.set_fm_index(value),
+            Input::Feedback => self.set_feedback(value),
+            Input::Drift => self.set_drift(value),
+            Input::DriftRate => self.set_drift_rate(value),
+            Input::Detune => self.set_detune(value),
+            Input::DetunePower => self.set_detune_power(value),
+            Input::Glide => self.set_glide(value),
+            Input::GlideSlope => self.set_glide_slope(value),
+            Input::PhasesBlend => self.set_phases_blend(value),
+            Input::GainsBlend => self.set_gains_blend(value),
+            _ => (),
+        }
+    }
+
     fn process_ui_events(&mut self) {
         while let Some(event) = self.audio_end.pop_event() {
             match event {
-                UiEvent::InputParam { input, value } => match input {
-                    Input::Gain => self.set_gain(value),
-                    Input::PitchShift => self.set_pitch_shift(value),
-                    Input::PhaseShift => self.set_phase_shift(value),
-                    Input::FrequencyShift => self.set_frequency_shift(value),
-                    Input::Detune => self.set_detune(value),
-                    Input::DetunePower => self.set_detune_power(value),
-                    Input::Glide => self.set_glide(value),
-                    Input::GlideSlope => self.set_glide_slope(value),
-                    Input::PhasesBlend => self.set_phases_blend(value),
-                    Input::GainsBlend => self.set_gains_blend(value),
-                    _ => (),
-                },
+                UiEvent::InputParam { input, value } => self.set_input_param(input, value),
                 UiEvent::Unison(unison) => self.set_unison(unison),
                 UiEvent::UnisonInitialPhase { idx, value } => self.set_initial_phase(idx, value),
                 UiEvent::UnisonPhaseShift { idx, value } => self.set_unison_phase(idx, value),
@@ -1082,6 +1722,11 @@ impl SynthModule for Oscillator {
                 UiEvent::UnisonGain { idx, value } => self.set_unison_gain(idx, value),
                 UiEvent::UnisonGainTo { idx, value } => self.set_unison_gain_to(idx, value),
                 UiEvent::StealPhase(steal_phase) => self.set_steal_phase(steal_phase),
+                UiEvent::RandomPhase(random_phase) => self.set_random_phase(random_phase),
+                UiEvent::StereoSpread(stereo_spread) => self.set_stereo_spread(stereo_spread),
+                UiEvent::UnisonBlend(unison_blend) => self.set_unison_blend(unison_blend),
+                UiEvent::PitchRange(pitch_range) => self.set_pitch_range(pitch_range),
+                UiEvent::FrequencyRange(min, max) => self.set_frequency_range(min, max),
                 UiEvent::ApplyUnisonLevelShape { center, level, to } => {
                     self.apply_unison_level_shape(center, level, to);
                 }
@@ -1093,6 +1738,7 @@ impl SynthModule for Oscillator {
                 } => {
                     self.randomize_phases(from, to, stereo_spread, dst);
                 }
+                UiEvent::PreviewFrame(frame) => self.preview_frame = Some(frame),
             }
         }
     }
@@ -1113,6 +1759,10 @@ impl SynthModule for Oscillator {
                     );
                 }
             }
+
+            if let Some(&voice_idx) = router.params().active_voices.first() {
+                self.publish_preview(router.for_voice(0, voice_idx, 0));
+            }
         });
     }
 }
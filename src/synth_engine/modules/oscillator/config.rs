@@ -1,10 +1,76 @@
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    synth_engine::{ModuleId, Sample, StereoSample, oscillator::MAX_UNISON_VOICES},
+    synth_engine::{
+        ModuleId, Sample, SpectralSize, StereoSample,
+        oscillator::{MAX_FREQUENCY, MAX_UNISON_VOICES, MIN_FREQUENCY},
+    },
     utils::st_to_octave,
 };
 
+// Size of the single cycle tables the spectrum is rendered into relative to the spectral size,
+// set for the whole patch. Low tables hold half the bins.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WaveQuality {
+    Low,
+    #[default]
+    Standard,
+    High,
+}
+
+impl WaveQuality {
+    pub const fn bits(self, spectral_size: SpectralSize) -> usize {
+        match self {
+            Self::Low => spectral_size.bits(),
+            Self::Standard => spectral_size.bits() + 1,
+            Self::High => spectral_size.bits() + 2,
+        }
+    }
+
+    pub const fn size(self, spectral_size: SpectralSize) -> usize {
+        1 << self.bits(spectral_size)
+    }
+}
+
+// How often held voices rebuild their tables from a changing spectrum, set for the whole patch.
+// Economy rebuilds at most every second block. Precise renders the engine in half blocks, so the
+// spectra and the tables follow changes and pitch sweeps twice as often.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TableUpdate {
+    Economy,
+    #[default]
+    Standard,
+    Precise,
+}
+
+fn default_unison_blend() -> Sample {
+    1.0
+}
+
+fn default_fm_index() -> StereoSample {
+    StereoSample::ZERO
+}
+
+fn default_feedback() -> StereoSample {
+    StereoSample::ZERO
+}
+
+fn default_drift_rate() -> StereoSample {
+    StereoSample::splat(0.5)
+}
+
+fn default_pitch_range() -> Sample {
+    st_to_octave(60.0)
+}
+
+fn default_min_frequency() -> Sample {
+    MIN_FREQUENCY
+}
+
+fn default_max_frequency() -> Sample {
+    MAX_FREQUENCY
+}
+
 #[derive(Clone, Serialize, Deserialize)]
 pub struct UnisonConfig {
     pub initial_phase: StereoSample,
@@ -29,16 +95,38 @@ impl Default for UnisonConfig {
 #[derive(Clone, Serialize, Deserialize)]
 pub struct OscillatorConfig {
     pub id: ModuleId,
+    #[serde(default)]
+    pub bypass: bool,
     pub unison_voices: usize,
     pub steal_phase: bool,
+    #[serde(default)]
+    pub random_phase: bool,
+    #[serde(default)]
+    pub stereo_spread: Sample,
+    #[serde(default = "default_unison_blend")]
+    pub unison_blend: Sample,
     pub gain: StereoSample,
     pub pitch_shift: StereoSample,
+    #[serde(default = "default_pitch_range")]
+    pub pitch_range: Sample, // Octaves, limits the modulated pitch shift both ways
+    #[serde(default = "default_min_frequency")]
+    pub min_frequency: Sample,
+    #[serde(default = "default_max_frequency")]
+    pub max_frequency: Sample,
     pub detune: StereoSample,
     pub detune_power: StereoSample,
     pub glide: StereoSample,
     pub glide_slope: StereoSample,
     pub phase_shift: StereoSample,
     pub frequency_shift: StereoSample,
+    #[serde(default = "default_fm_index")]
+    pub fm_index: StereoSample,
+    #[serde(default = "default_feedback")]
+    pub feedback: StereoSample,
+    #[serde(default)]
+    pub drift: StereoSample, // Octaves
+    #[serde(default = "default_drift_rate")]
+    pub drift_rate: StereoSample, // Hz
     pub phases_blend: StereoSample,
     pub gains_blend: StereoSample,
     pub unison: [UnisonConfig; MAX_UNISON_VOICES],
@@ -60,16 +148,27 @@ impl Default for OscillatorConfig {
 
         Self {
             id: -1,
+            bypass: false,
             unison_voices: 1,
             steal_phase: false,
+            random_phase: false,
+            stereo_spread: 0.0,
+            unison_blend: default_unison_blend(),
             gain: 1.0.into(),
             pitch_shift: 0.0.into(),
+            pitch_range: default_pitch_range(),
+            min_frequency: default_min_frequency(),
+            max_frequency: default_max_frequency(),
             detune: st_to_octave(0.2).into(),
             detune_power: 0.0.into(),
             glide: 0.0.into(),
             glide_slope: 0.0.into(),
             phase_shift: 0.0.into(),
             frequency_shift: 0.0.into(),
+            fm_index: default_fm_index(),
+            feedback: default_feedback(),
+            drift: StereoSample::ZERO,
+            drift_rate: default_drift_rate(),
             phases_blend: 0.0.into(),
             gains_blend: 0.0.into(),
             unison,
@@ -1,4 +1,33 @@
-use crate::synth_engine::{Input, Sample, StereoSample, oscillator::PhasesDst};
+use crate::synth_engine::{
+    Input, Sample, SpectralBuffer, SpectralSize, StereoSample,
+    buffer::zero_spectral_buffer,
+    edit_sender::EditSender,
+    oscillator::{MAX_UNISON_VOICES, PhasesDst, WaveQuality, config::UnisonConfig},
+    triple_buffer::{TripleReader, TripleWriter, create_triple_buffer},
+};
+
+// Unison levels and phases the audio thread generates itself, read by the editor without locking
+pub type UnisonSnapshot = [UnisonConfig; MAX_UNISON_VOICES];
+
+// Lent by the editor and sent back filled with the spectrum the first playing voice reads
+pub struct PreviewFrame {
+    pub wave_quality: WaveQuality,
+    pub spectral_size: SpectralSize,
+    // Bins the voice table was built from
+    pub cutoff: usize,
+    pub spectrum: SpectralBuffer,
+}
+
+impl PreviewFrame {
+    pub fn new() -> Box<Self> {
+        Box::new(Self {
+            wave_quality: WaveQuality::default(),
+            spectral_size: SpectralSize::default(),
+            cutoff: 0,
+            spectrum: zero_spectral_buffer(),
+        })
+    }
+}
 
 pub enum UiEvent {
     InputParam {
@@ -27,6 +56,11 @@ pub enum UiEvent {
         value: StereoSample,
     },
     StealPhase(bool),
+    RandomPhase(bool),
+    StereoSpread(Sample),
+    UnisonBlend(Sample),
+    PitchRange(Sample),
+    FrequencyRange(Sample, Sample),
     ApplyUnisonLevelShape {
         center: StereoSample,
         level: StereoSample,
@@ -38,20 +72,34 @@ pub enum UiEvent {
         stereo_spread: Sample,
         dst: PhasesDst,
     },
+    PreviewFrame(Box<PreviewFrame>),
 }
 
 pub enum UiUpdate {
-    RefreshState,
+    Preview(Box<PreviewFrame>),
 }
 
 pub struct UiEnd {
     rx: rtrb::Consumer<UiUpdate>,
-    tx: rtrb::Producer<UiEvent>,
+    tx: EditSender<UiEvent>,
+    unison: TripleReader<UnisonSnapshot>,
 }
 
 impl UiEnd {
-    pub fn new(rx: rtrb::Consumer<UiUpdate>, tx: rtrb::Producer<UiEvent>) -> Self {
-        Self { rx, tx }
+    pub fn new(
+        rx: rtrb::Consumer<UiUpdate>,
+        tx: rtrb::Producer<UiEvent>,
+        unison: TripleReader<UnisonSnapshot>,
+    ) -> Self {
+        Self {
+            rx,
+            tx: EditSender::new(tx),
+            unison,
+        }
+    }
+
+    pub fn edits(&self) -> u32 {
+        self.tx.sent()
     }
 
     pub fn set_param(&mut self, input: Input, value: StereoSample) -> bool {
@@ -66,6 +114,26 @@ impl UiEnd {
         self.tx.push(UiEvent::StealPhase(steal_phase)).is_ok()
     }
 
+    pub fn set_random_phase(&mut self, random_phase: bool) -> bool {
+        self.tx.push(UiEvent::RandomPhase(random_phase)).is_ok()
+    }
+
+    pub fn set_stereo_spread(&mut self, stereo_spread: Sample) -> bool {
+        self.tx.push(UiEvent::StereoSpread(stereo_spread)).is_ok()
+    }
+
+    pub fn set_unison_blend(&mut self, unison_blend: Sample) -> bool {
+        self.tx.push(UiEvent::UnisonBlend(unison_blend)).is_ok()
+    }
+
+    pub fn set_pitch_range(&mut self, pitch_range: Sample) -> bool {
+        self.tx.push(UiEvent::PitchRange(pitch_range)).is_ok()
+    }
+
+    pub fn set_frequency_range(&mut self, min: Sample, max: Sample) -> bool {
+        self.tx.push(UiEvent::FrequencyRange(min, max)).is_ok()
+    }
+
     pub fn set_unison_initial_phase(&mut self, idx: usize, value: StereoSample) -> bool {
         self.tx
             .push(UiEvent::UnisonInitialPhase { idx, value })
@@ -120,36 +188,64 @@ impl UiEnd {
             .is_ok()
     }
 
+    pub fn request_preview(&mut self, frame: Box<PreviewFrame>) -> bool {
+        self.tx.push(UiEvent::PreviewFrame(frame)).is_ok()
+    }
+
     pub fn pop_update(&mut self) -> Option<UiUpdate> {
         self.rx.pop().ok()
     }
+
+    // The latest unison snapshot when one was published since the last call
+    pub fn updated_unison(&mut self) -> Option<&UnisonSnapshot> {
+        self.unison.update().then(|| self.unison.output())
+    }
 }
 
 pub struct AudioEnd {
     rx: rtrb::Consumer<UiEvent>,
     tx: rtrb::Producer<UiUpdate>,
+    unison: TripleWriter<UnisonSnapshot>,
 }
 
 impl AudioEnd {
-    pub fn new(rx: rtrb::Consumer<UiEvent>, tx: rtrb::Producer<UiUpdate>) -> Self {
-        Self { rx, tx }
+    pub fn new(
+        rx: rtrb::Consumer<UiEvent>,
+        tx: rtrb::Producer<UiUpdate>,
+        unison: TripleWriter<UnisonSnapshot>,
+    ) -> Self {
+        Self { rx, tx, unison }
     }
 
     pub fn pop_event(&mut self) -> Option<UiEvent> {
         self.rx.pop().ok()
     }
 
-    pub fn push_refresh_state(&mut self) -> bool {
-        self.tx.push(UiUpdate::RefreshState).is_ok()
+    // Has to be filled in completely before publish_unison
+    pub fn unison_snapshot(&mut self) -> &mut UnisonSnapshot {
+        self.unison.input()
+    }
+
+    pub fn publish_unison(&mut self) {
+        self.unison.publish();
+    }
+
+    // Gives the frame back when the queue is full, it must not be freed on the audio thread
+    pub fn push_preview(&mut self, frame: Box<PreviewFrame>) -> Option<Box<PreviewFrame>> {
+        match self.tx.push(UiUpdate::Preview(frame)) {
+            Err(rtrb::PushError::Full(UiUpdate::Preview(frame))) => Some(frame),
+            _ => None,
+        }
     }
 }
 
-pub fn create_link_pair() -> (AudioEnd, UiEnd) {
+pub fn create_link_pair(unison: UnisonSnapshot) -> (AudioEnd, UiEnd) {
     let (to_audio_tx, from_ui_rx) = rtrb::RingBuffer::<UiEvent>::new(512);
     let (to_ui_tx, from_audio_rx) = rtrb::RingBuffer::<UiUpdate>::new(128);
+    let (unison_tx, unison_rx) = create_triple_buffer(unison);
 
     (
-        AudioEnd::new(from_ui_rx, to_ui_tx),
-        UiEnd::new(from_audio_rx, to_audio_tx),
+        AudioEnd::new(from_ui_rx, to_ui_tx, unison_tx),
+        UiEnd::new(from_audio_rx, to_audio_tx, unison_rx),
     )
 }
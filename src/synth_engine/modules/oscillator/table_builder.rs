@@ -0,0 +1,213 @@
+use std::{
+    sync::{LazyLock, mpsc},
+    thread::{self, Thread},
+};
+
+use crate::synth_engine::{
+    SpectralBuffer,
+    buffer::zero_spectral_buffer,
+    triple_buffer::{TripleReader, TripleWriter, create_triple_buffer},
+};
+
+use super::{
+    INVERSE_FFTS, Oscillator, WAVEFORM_PAD_LEFT, WAVEFORM_PAD_RIGHT, WaveformBuffer,
+    make_zero_wave_buffer, zero_dft_buffer,
+};
+
+#[derive(Clone)]
+struct TableRequest {
+    serial: u32,
+    wave_size: usize,
+    cutoff: usize,
+    spectrum: SpectralBuffer,
+}
+
+#[derive(Clone)]
+struct BuiltTable {
+    serial: u32,
+    cutoff: usize,
+    wave: WaveformBuffer,
+}
+
+// Worker side of a voice, the requests and the tables both keep only the latest value
+struct WorkerJob {
+    requests: TripleReader<TableRequest>,
+    tables: TripleWriter<BuiltTable>,
+}
+
+struct Worker {
+    jobs: mpsc::Sender<WorkerJob>,
+    thread: Thread,
+}
+
+// One thread builds the tables of all the live oscillators, started along with the first of them
+static WORKER: LazyLock<Worker> = LazyLock::new(|| {
+    let (jobs, received) = mpsc::channel();
+    let handle = thread::Builder::new()
+        .name("additizer-tables".to_string())
+        .spawn(move || run_worker(received))
+        .expect("table builder thread");
+
+    Worker {
+        jobs,
+        thread: handle.thread().clone(),
+    }
+});
+
+fn run_worker(received: mpsc::Receiver<WorkerJob>) {
+    let mut jobs: Vec<WorkerJob> = Vec::new();
+    let mut tmp_spectral = zero_dft_buffer();
+    let mut scratch = zero_dft_buffer();
+
+    loop {
+        jobs.extend(received.try_iter());
+        jobs.retain(|job| !job.requests.writer_dropped());
+
+        for job in &mut jobs {
+            if !job.requests.update() {
+                continue;
+            }
+
+            let request = job.requests.output();
+            let Some(inverse_fft) = INVERSE_FFTS
+                .iter()
+                .find(|fft| fft.len() == request.wave_size)
+            else {
+                continue;
+            };
+            let table = job.tables.input();
+
+            Oscillator::build_wave(
+                inverse_fft.as_ref(),
+                request.wave_size,
+                request.cutoff,
+                &request.spectrum,
+                &mut tmp_spectral,
+                &mut scratch,
+                &mut table.wave,
+            );
+            table.serial = request.serial;
+            table.cutoff = request.cutoff;
+            job.tables.publish();
+        }
+
+        // Requests unpark the thread, one arriving meanwhile makes this return right away
+        thread::park();
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Requested {
+    serial: u32,
+    wave_size: usize,
+    cutoff: usize,
+    revision: u32,
+}
+
+/// Audio side of the table builds of a voice. Posting a request and picking the table up never
+/// blocks or allocates, the voice keeps playing its latest table until the new one arrives.
+pub struct TableJob {
+    requests: TripleWriter<TableRequest>,
+    tables: TripleReader<BuiltTable>,
+    serial: u32,
+    // The request on the way, None once its table is received or it's cancelled
+    pending: Option<Requested>,
+    spectrum: SpectralBuffer,
+}
+
+impl TableJob {
+    pub fn new() -> Self {
+        let (requests, worker_requests) = create_triple_buffer(TableRequest {
+            serial: 0,
+            wave_size: 0,
+            cutoff: 0,
+            spectrum: zero_spectral_buffer(),
+        });
+        let (worker_tables, tables) = create_triple_buffer(BuiltTable {
+            serial: 0,
+            cutoff: 0,
+            wave: make_zero_wave_buffer(),
+        });
+
+        // The worker never hangs up, its receiver lives as long as the process
+        let _ = WORKER.jobs.send(WorkerJob {
+            requests: worker_requests,
+            tables: worker_tables,
+        });
+
+        Self {
+            requests,
+            tables,
+            serial: 0,
+            pending: None,
+            spectrum: zero_spectral_buffer(),
+        }
+    }
+
+    pub fn is_requested(&self, cutoff: usize, revision: u32, spectrum: &SpectralBuffer) -> bool {
+        self.pending.is_some_and(|pending| {
+            pending.cutoff == cutoff
+                && (pending.revision == revision || self.spectrum[..cutoff] == spectrum[..cutoff])
+        })
+    }
+
+    // Replaces the request on the way, if any
+    pub fn request(
+        &mut self,
+        wave_size: usize,
+        cutoff: usize,
+        revision: u32,
+        spectrum: &SpectralBuffer,
+    ) {
+        self.serial = self.serial.wrapping_add(1);
+
+        let request = self.requests.input();
+
+        request.serial = self.serial;
+        request.wave_size = wave_size;
+        request.cutoff = cutoff;
+        request.spectrum[..cutoff].copy_from_slice(&spectrum[..cutoff]);
+        self.requests.publish();
+
+        self.spectrum[..cutoff].copy_from_slice(&spectrum[..cutoff]);
+        self.pending = Some(Requested {
+            serial: self.serial,
+            wave_size,
+            cutoff,
+            revision,
+        });
+        WORKER.thread.unpark();
+    }
+
+    pub fn cancel(&mut self) {
+        self.pending = None;
+    }
+
+    /// Copies the table of the pending request into `wave` and its bins into `spectrum` once it's
+    /// built, returns its cutoff and revision. A table with more bins than `max_cutoff` would
+    /// alias at the current pitch and is dropped, the voice requests another one.
+    pub fn receive(
+        &mut self,
+        max_cutoff: usize,
+        wave: &mut WaveformBuffer,
+        spectrum: &mut SpectralBuffer,
+    ) -> Option<(usize, u32)> {
+        let pending = self.pending?;
+
+        if !self.tables.update() || self.tables.output().serial != pending.serial {
+            return None;
+        }
+
+        self.pending = None;
+
+        if pending.cutoff > max_cutoff {
+            return None;
+        }
+
+        let len = WAVEFORM_PAD_LEFT + pending.wave_size + WAVEFORM_PAD_RIGHT;
+
+        wave[..len].copy_from_slice(&self.tables.output().wave[..len]);
+        spectrum[..pending.cutoff].copy_from_slice(&self.spectrum[..pending.cutoff]);
+        Some((pending.cutoff, pending.revision))
+    }
+}
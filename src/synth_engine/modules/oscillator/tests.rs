@@ -1 +1,69 @@
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
 
+use super::*;
+
+#[test]
+fn tables_follow_the_spectral_size() {
+    for spectral_size in [SpectralSize::S256, SpectralSize::S512, SpectralSize::S1024] {
+        for wave_quality in [WaveQuality::Low, WaveQuality::Standard, WaveQuality::High] {
+            let size = wave_quality.size(spectral_size);
+
+            assert_eq!(
+                Oscillator::inverse_fft(wave_quality, spectral_size).len(),
+                size
+            );
+            assert!(size <= MAX_WAVEFORM_SIZE);
+        }
+    }
+
+    assert_eq!(WaveQuality::Standard.size(SpectralSize::S256), 512);
+    assert_eq!(WaveQuality::High.size(SpectralSize::S1024), 4096);
+}
+
+#[test]
+fn table_builder_matches_inline_build() {
+    let wave_size = WaveQuality::Standard.size(SpectralSize::default());
+    let cutoff = 32;
+    let mut spectrum = zero_spectral_buffer();
+
+    for (idx, bin) in spectrum.iter_mut().enumerate().take(cutoff).skip(1) {
+        *bin = ComplexSample::new(0.0, -1.0 / idx as Sample);
+    }
+
+    let mut expected = make_zero_wave_buffer();
+
+    Oscillator::build_wave(
+        Oscillator::inverse_fft(WaveQuality::Standard, SpectralSize::default()).as_ref(),
+        wave_size,
+        cutoff,
+        &spectrum,
+        &mut zero_dft_buffer(),
+        &mut zero_dft_buffer(),
+        &mut expected,
+    );
+
+    let mut job = TableJob::new();
+    let mut wave = make_zero_wave_buffer();
+    let mut built_spectrum = zero_spectral_buffer();
+
+    job.request(wave_size, cutoff, 1, &spectrum);
+    assert!(job.is_requested(cutoff, 1, &spectrum));
+
+    let started = Instant::now();
+    let received = loop {
+        if let Some(received) = job.receive(cutoff, &mut wave, &mut built_spectrum) {
+            break received;
+        }
+
+        assert!(started.elapsed().as_secs() < 5, "table wasn't built");
+        thread::sleep(Duration::from_millis(1));
+    };
+
+    assert_eq!(received, (cutoff, 1));
+    assert_eq!(wave, expected);
+    assert_eq!(built_spectrum[..cutoff], spectrum[..cutoff]);
+    assert!(!job.is_requested(cutoff, 1, &spectrum));
+}
@@ -1,47 +1,121 @@
 use std::sync::Arc;
 
-use parking_lot::Mutex;
+use nih_plug::util::gain_to_db;
+use realfft::{ComplexToReal, RealFftPlanner};
 
-use crate::synth_engine::{
-    Input, ModuleHandle, ModuleId, Sample, StereoSample, SynthEngine, synth_module::ModuleUiBridge,
-};
+use crate::synth_engine::{Input, Sample, StereoSample, synth_module::ModuleUiBridge};
 
 use super::{
-    Oscillator, OscillatorConfig, PhasesDst,
-    link::{UiEnd, UiUpdate},
+    DftBuffer, Oscillator, OscillatorConfig, PhasesDst, WAVEFORM_PAD_LEFT, WaveformBuffer,
+    link::{PreviewFrame, UiEnd, UiUpdate},
+    make_zero_wave_buffer, zero_dft_buffer,
 };
 
+// Table the editor rebuilds from the played spectrum, planned for the patch table size
+struct PreviewBuffers {
+    inverse_fft: Arc<dyn ComplexToReal<Sample>>,
+    tmp_spectral: DftBuffer,
+    scratch: DftBuffer,
+    wave: WaveformBuffer,
+}
+
+impl PreviewBuffers {
+    fn new(wave_size: usize) -> Self {
+        Self {
+            inverse_fft: RealFftPlanner::<Sample>::new().plan_fft_inverse(wave_size),
+            tmp_spectral: zero_dft_buffer(),
+            scratch: zero_dft_buffer(),
+            wave: make_zero_wave_buffer(),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct WavePreview {
+    // Bins the voice plays, the ones above are cut from its table
+    pub cutoff: usize,
+    // Magnitudes in dB from the first harmonic up to the last bin of the spectral size
+    pub magnitudes: Vec<Sample>,
+    // Single cycle of the played table
+    pub wave: Vec<Sample>,
+}
+
 pub struct OscillatorUiBridge {
-    synth: Arc<Mutex<SynthEngine>>,
-    module_id: ModuleId,
     ui_end: UiEnd,
     config: OscillatorConfig,
+    preview: Option<WavePreview>,
+    preview_buffers: Option<Box<PreviewBuffers>>,
+    preview_frame: Option<Box<PreviewFrame>>,
+    preview_lent: bool,
+    received_unison: u32,
 }
 
 impl OscillatorUiBridge {
-    pub fn try_new(
-        module_id: ModuleId,
-        synth: Arc<Mutex<SynthEngine>>,
-        osc: &mut Oscillator,
-    ) -> Option<Self> {
+    pub fn try_new(osc: &mut Oscillator) -> Option<Self> {
         Some(Self {
-            synth,
-            module_id,
             ui_end: osc.ui_end.take()?,
             config: osc.get_config(),
+            preview: None,
+            preview_buffers: None,
+            preview_frame: None,
+            preview_lent: false,
+            received_unison: 0,
         })
     }
 
-    pub fn sync(&mut self) {
-        let synth_lock = self.synth.lock();
+    pub fn config(&self) -> &OscillatorConfig {
+        &self.config
+    }
+
+    pub fn preview(&self) -> Option<&WavePreview> {
+        self.preview.as_ref()
+    }
 
-        if let Some(ModuleHandle::Oscillator(osc)) = synth_lock.get_module(self.module_id) {
-            self.config = osc.get_config();
+    // Lends the audio thread a frame to send the played spectrum back in, one at a time
+    pub fn request_preview(&mut self) {
+        if self.preview_lent {
+            return;
         }
+
+        let frame = self.preview_frame.take().unwrap_or_else(PreviewFrame::new);
+
+        self.preview_lent = self.ui_end.request_preview(frame);
     }
 
-    pub fn config(&self) -> &OscillatorConfig {
-        &self.config
+    // Rebuilds the played table from the published spectrum through the same path and cutoff
+    fn update_preview(&mut self, frame: &PreviewFrame) {
+        let wave_size = frame.wave_quality.size(frame.spectral_size);
+        let buffers = self
+            .preview_buffers
+            .get_or_insert_with(|| Box::new(PreviewBuffers::new(wave_size)));
+
+        if buffers.inverse_fft.len() != wave_size {
+            **buffers = PreviewBuffers::new(wave_size);
+        }
+
+        Oscillator::build_wave(
+            buffers.inverse_fft.as_ref(),
+            wave_size,
+            frame.cutoff,
+            &frame.spectrum,
+            &mut buffers.tmp_spectral,
+            &mut buffers.scratch,
+            &mut buffers.wave,
+        );
+
+        let preview = self.preview.get_or_insert_with(WavePreview::default);
+
+        preview.cutoff = frame.cutoff;
+        preview.magnitudes.clear();
+        preview.magnitudes.extend(
+            frame.spectrum[1..frame.spectral_size.bins()]
+                .iter()
+                .map(|bin| gain_to_db(bin.norm())),
+        );
+        preview.wave.clear();
+        preview
+            .wave
+            .extend_from_slice(&buffers.wave[WAVEFORM_PAD_LEFT..WAVEFORM_PAD_LEFT + wave_size]);
     }
 
     pub fn set_param(&mut self, input: Input, value: StereoSample) {
@@ -51,8 +125,12 @@ impl OscillatorUiBridge {
                 Input::PitchShift => self.config.pitch_shift = value,
                 Input::PhaseShift => self.config.phase_shift = value,
                 Input::FrequencyShift => self.config.frequency_shift = value,
+                Input::FmIndex => self.config.fm_index = value,
+                Input::Feedback => self.config.feedback = value,
                 Input::Detune => self.config.detune = value,
                 Input::DetunePower => self.config.detune_power = value,
+                Input::Drift => self.config.drift = value,
+                Input::DriftRate => self.config.drift_rate = value,
                 Input::Glide => self.config.glide = value,
                 Input::GlideSlope => self.config.glide_slope = value,
                 Input::PhasesBlend => self.config.phases_blend = value,
@@ -74,6 +152,37 @@ impl OscillatorUiBridge {
         }
     }
 
+    pub fn set_random_phase(&mut self, random_phase: bool) {
+        if self.ui_end.set_random_phase(random_phase) {
+            self.config.random_phase = random_phase;
+        }
+    }
+
+    pub fn set_stereo_spread(&mut self, stereo_spread: Sample) {
+        if self.ui_end.set_stereo_spread(stereo_spread) {
+            self.config.stereo_spread = stereo_spread;
+        }
+    }
+
+    pub fn set_unison_blend(&mut self, unison_blend: Sample) {
+        if self.ui_end.set_unison_blend(unison_blend) {
+            self.config.unison_blend = unison_blend;
+        }
+    }
+
+    pub fn set_pitch_range(&mut self, pitch_range: Sample) {
+        if self.ui_end.set_pitch_range(pitch_range) {
+            self.config.pitch_range = pitch_range;
+        }
+    }
+
+    pub fn set_frequency_range(&mut self, min: Sample, max: Sample) {
+        if self.ui_end.set_frequency_range(min, max) {
+            self.config.min_frequency = min;
+            self.config.max_frequency = max;
+        }
+    }
+
     pub fn set_unison_initial_phase(&mut self, idx: usize, value: StereoSample) {
         if self.ui_end.set_unison_initial_phase(idx, value) {
             self.config.unison[idx].initial_phase = value;
@@ -128,10 +237,22 @@ impl ModuleUiBridge for OscillatorUiBridge {
     fn update(&mut self) {
         while let Some(update) = self.ui_end.pop_update() {
             match update {
-                UiUpdate::RefreshState => {
-                    self.sync();
+                UiUpdate::Preview(frame) => {
+                    self.preview_lent = false;
+                    self.update_preview(&frame);
+                    self.preview_frame = Some(frame);
                 }
             }
         }
+
+        // Level shapes and random phases are generated on the audio thread
+        if let Some(unison) = self.ui_end.updated_unison() {
+            self.config.unison.clone_from(unison);
+            self.received_unison = self.received_unison.wrapping_add(1);
+        }
+    }
+
+    fn edits(&self) -> u32 {
+        self.ui_end.edits().wrapping_add(self.received_unison)
     }
 }
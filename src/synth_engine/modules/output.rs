@@ -7,11 +7,17 @@ use crate::{
     synth_engine::{
         Input, ModuleId, OUTPUT_MODULE_ID, Sample, StereoSample, SynthModule,
         buffer::{Buffer, copy_or_add_to_buffer, copy_to_buffer, zero_buffer},
-        iir_decimator::IirDecimator,
+        capture::CaptureTap,
+        dc_blocker::DcBlocker,
+        declicker::Declicker,
+        limiter::{Limiter, LimiterConfig},
+        output_mode::{CorrelationMeter, OutputMode},
+        oversampling::{Decimator, Oversampling},
         routing::{
-            DataType, InputMeta, InputSlots, MAX_VOICES, NUM_CHANNELS, ProcessContext,
-            SpectralInputSlot, VoiceEvent,
+            DataType, InputMeta, InputSlots, InputSource, MAX_VOICES, NUM_CHANNELS, ProcessContext,
+            ProcessParams, SpectralInputSlot, VoiceEvent,
         },
+        scope::ScopeTap,
         smooth::{InfiniteSmoothed, SmoothedSample},
         voices_handler::DecayingVoice,
     },
@@ -34,6 +40,12 @@ impl Default for Voice {
     }
 }
 
+#[derive(Clone, Copy, Default)]
+pub struct VoiceLevel {
+    pub peak: Sample,
+    pub rms: Sample,
+}
+
 #[derive(Default)]
 struct Channel {
     voices: [Voice; MAX_VOICES],
@@ -49,11 +61,30 @@ pub struct Output {
     input_buffer: Buffer,
     ext_gain_buffer: Buffer,
     output: [Buffer; NUM_CHANNELS],
-    decimator: IirDecimator,
+    decimator: Decimator,
+    capture_tap: CaptureTap,
+    scope_tap: ScopeTap,
+    analyzer_tap: ScopeTap,
+    declicker: Declicker,
+    dc_blocker: DcBlocker,
+    output_mode: OutputMode,
+    correlation: CorrelationMeter,
+    limiter: Limiter,
+    voice_levels: [VoiceLevel; MAX_VOICES],
 }
 
 impl Output {
-    pub fn new(gain: StereoSample, kill_time: Sample, level_param: Arc<FloatParam>) -> Self {
+    pub fn new(
+        gain: StereoSample,
+        kill_time: Sample,
+        level_param: Arc<FloatParam>,
+        capture_tap: CaptureTap,
+        scope_tap: ScopeTap,
+        analyzer_tap: ScopeTap,
+        dc_blocker: bool,
+        output_mode: OutputMode,
+        limiter: LimiterConfig,
+    ) -> Self {
         let ext_gain = db_to_gain_fast(level_param.value());
 
         Self {
@@ -69,7 +100,16 @@ impl Output {
             input_buffer: zero_buffer(),
             ext_gain_buffer: zero_buffer(),
             output: [zero_buffer(), zero_buffer()],
-            decimator: IirDecimator::new(),
+            decimator: Decimator::new(),
+            capture_tap,
+            scope_tap,
+            analyzer_tap,
+            declicker: Declicker::new(),
+            dc_blocker: DcBlocker::new(dc_blocker),
+            output_mode,
+            correlation: CorrelationMeter::new(),
+            limiter: Limiter::new(limiter),
+            voice_levels: [VoiceLevel::default(); MAX_VOICES],
         }
     }
 
@@ -99,23 +139,138 @@ impl Output {
         self.kill_time = Self::clamp_kill_time(kill_time)
     }
 
+    pub fn get_dc_blocker(&self) -> bool {
+        self.dc_blocker.enabled()
+    }
+
+    pub fn set_dc_blocker(&mut self, enabled: bool) {
+        self.dc_blocker.set_enabled(enabled);
+    }
+
+    pub fn get_output_mode(&self) -> OutputMode {
+        self.output_mode
+    }
+
+    pub fn set_output_mode(&mut self, output_mode: OutputMode) {
+        self.output_mode = output_mode;
+    }
+
+    // Smooths over the jump in the output when the graph changes while notes play
+    pub fn declick(&mut self) {
+        self.declicker.arm();
+    }
+
+    pub fn get_limiter(&self) -> LimiterConfig {
+        self.limiter.config()
+    }
+
+    pub fn set_limiter(&mut self, limiter: LimiterConfig) {
+        self.limiter.set_config(limiter);
+    }
+
+    pub fn gain_reduction(&self) -> Sample {
+        self.limiter.gain_reduction()
+    }
+
+    // Of the stereo signal, before the output mode folds it
+    pub fn correlation(&self) -> Sample {
+        self.correlation.correlation()
+    }
+
+    pub fn level_param(&self) -> Arc<FloatParam> {
+        self.ext_level_param.clone()
+    }
+
+    // Voices source, the modules between it and the output are processed as a bus
+    pub fn set_voices_input(&mut self, slot: Option<usize>) {
+        self.audio_input = slot;
+    }
+
+    // Levels of the last processed block, the voices that didn't play are zeroed
+    pub fn voice_levels(&self) -> &[VoiceLevel; MAX_VOICES] {
+        &self.voice_levels
+    }
+
+    pub fn bus_mut(&mut self) -> &mut [Buffer; NUM_CHANNELS] {
+        &mut self.output
+    }
+
+    fn apply_gain(&mut self, params: &ProcessParams) {
+        let samples = params.samples;
+
+        self.ext_gain_smoothed
+            .set(db_to_gain_fast(self.ext_level_param.value()));
+
+        copy_to_buffer(
+            &mut self.ext_gain_buffer,
+            self.ext_gain_smoothed
+                .iter(InfiniteSmoothed::smooth_mult(
+                    params.sample_rate,
+                    from_ms(4.0),
+                ))
+                .take(samples),
+        );
+
+        fn apply_volume<'a>(
+            output: impl Iterator<Item = &'a mut Sample>,
+            gain: impl Iterator<Item = Sample>,
+            ext_gain: impl Iterator<Item = &'a Sample>,
+            samples: usize,
+        ) {
+            for (out, gain, gain_ext) in izip!(output, gain, ext_gain).take(samples) {
+                *out *= gain * gain_ext;
+            }
+        }
+
+        for (output, gain) in self.output.iter_mut().zip(self.gain.iter_mut()) {
+            if gain.check_needs_smoothing(&params.smooth_params) {
+                apply_volume(
+                    output.iter_mut(),
+                    gain.smoothed_iter(&params.smooth_params),
+                    self.ext_gain_buffer.iter(),
+                    samples,
+                );
+            } else {
+                apply_volume(
+                    output.iter_mut(),
+                    std::iter::repeat(gain.get()),
+                    self.ext_gain_buffer.iter(),
+                    samples,
+                );
+            }
+        }
+    }
+
     pub fn read_output<'a>(
         &mut self,
-        oversampling: bool,
+        params: &ProcessParams,
+        oversampling: Oversampling,
         mut outputs: impl Iterator<Item = &'a mut [f32]>,
     ) {
-        if oversampling {
-            self.decimator.process(
-                [&self.output[0], &self.output[1]],
-                [outputs.next().unwrap(), outputs.next().unwrap()],
-            );
-        } else {
-            for (out, aggregated) in outputs.zip(self.output.iter()) {
-                for (out, aggregated) in out.iter_mut().zip(aggregated.iter()) {
-                    *out = *aggregated;
-                }
-            }
-        }
+        let (left, right) = (outputs.next().unwrap(), outputs.next().unwrap());
+
+        let samples = params.samples;
+
+        self.apply_gain(params);
+        self.decimator.process(
+            oversampling,
+            [&self.output[0][..samples], &self.output[1][..samples]],
+            [&mut *left, &mut *right],
+        );
+
+        let host_sample_rate = params.sample_rate / oversampling.factor() as Sample;
+
+        self.declicker
+            .process(host_sample_rate, [&mut *left, &mut *right]);
+        self.dc_blocker
+            .process(host_sample_rate, [&mut *left, &mut *right]);
+        self.correlation.process(host_sample_rate, left, right);
+        self.output_mode.process(left, right);
+        self.limiter.process(host_sample_rate, left, right);
+
+        self.capture_tap.write(left, right);
+        self.scope_tap.write(left, right);
+        self.analyzer_tap.write(left, right);
     }
 }
 
@@ -125,7 +280,7 @@ impl SynthModule for Output {
     }
 
     fn inputs(&self) -> &'static [InputMeta] {
-        static INPUTS: &[InputMeta] = &[InputMeta::audio(Input::Audio)];
+        static INPUTS: &[InputMeta] = &[InputMeta::bus(Input::Audio)];
 
         INPUTS
     }
@@ -142,11 +297,10 @@ impl SynthModule for Output {
         panic!("Output module doesn't have output slot.")
     }
 
-    fn set_input_slots(&mut self, inputs: &[InputSlots], _spectral_inputs: &[SpectralInputSlot]) {
-        self.audio_input = inputs.first().and_then(|s| s.first_slot());
-    }
+    // Audio input is resolved by the engine through the bus chain
+    fn set_input_slots(&mut self, _inputs: &[InputSlots], _spectral_inputs: &[SpectralInputSlot]) {}
 
-    fn update_input_amount(&mut self, _input_type: Input, _src_slot: usize, _amount: StereoSample) {
+    fn update_input_source(&mut self, _input_type: Input, _src_slot: usize, _source: &InputSource) {
     }
 
     fn process_events(&mut self, events: &[VoiceEvent]) {
@@ -188,6 +342,8 @@ impl SynthModule for Output {
         let mut rf = ctx.for_output(self.id());
         let num_active_voices = rf.params().active_voices.len();
 
+        self.voice_levels.fill(VoiceLevel::default());
+
         if num_active_voices == 0 {
             self.output.iter_mut().for_each(|output| output.fill(0.0));
             return;
@@ -196,19 +352,7 @@ impl SynthModule for Output {
         let sample_rate = rf.params().sample_rate;
         let samples = rf.params().samples;
 
-        self.ext_gain_smoothed
-            .set(db_to_gain_fast(self.ext_level_param.value()));
-
-        copy_to_buffer(
-            &mut self.ext_gain_buffer,
-            self.ext_gain_smoothed
-                .iter(InfiniteSmoothed::smooth_mult(sample_rate, from_ms(4.0)))
-                .take(samples),
-        );
-
-        for (channel_idx, (output, gain)) in
-            self.output.iter_mut().zip(self.gain.iter_mut()).enumerate()
-        {
+        for (channel_idx, output) in self.output.iter_mut().enumerate() {
             for seq_idx in 0..num_active_voices {
                 let voice_idx = rf.params().active_voices[seq_idx];
                 let mut router = rf.for_voice(channel_idx, voice_idx, seq_idx);
@@ -235,39 +379,28 @@ impl SynthModule for Output {
                     }
                 }
 
+                let level = &mut self.voice_levels[voice_idx];
+
+                // Sum of squares for now, turned into RMS below
+                for sample in &self.input_buffer[..samples] {
+                    level.peak = level.peak.max(sample.abs());
+                    level.rms += sample * sample;
+                }
+
                 copy_or_add_to_buffer(
                     seq_idx == 0,
                     output,
                     self.input_buffer.iter().copied().take(samples),
                 );
             }
+        }
 
-            fn apply_volume<'a>(
-                output: impl Iterator<Item = &'a mut Sample>,
-                gain: impl Iterator<Item = Sample>,
-                ext_gain: impl Iterator<Item = &'a Sample>,
-                samples: usize,
-            ) {
-                for (out, gain, gain_ext) in izip!(output, gain, ext_gain).take(samples) {
-                    *out *= gain * gain_ext;
-                }
-            }
+        let norm = ((NUM_CHANNELS * samples) as Sample).recip();
 
-            if gain.check_needs_smoothing(&rf.params().smooth_params) {
-                apply_volume(
-                    output.iter_mut(),
-                    gain.smoothed_iter(&rf.params().smooth_params),
-                    self.ext_gain_buffer.iter(),
-                    samples,
-                );
-            } else {
-                apply_volume(
-                    output.iter_mut(),
-                    std::iter::repeat(gain.get()),
-                    self.ext_gain_buffer.iter(),
-                    samples,
-                );
-            }
+        for &voice_idx in rf.params().active_voices.iter() {
+            let level = &mut self.voice_levels[voice_idx];
+
+            level.rms = (level.rms * norm).sqrt();
         }
     }
 }
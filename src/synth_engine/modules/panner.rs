@@ -0,0 +1,283 @@
+use std::{
+    array,
+    f32::consts::{FRAC_PI_4, SQRT_2},
+};
+
+use itertools::izip;
+
+mod config;
+mod link;
+mod ui_bridge;
+
+pub use config::PannerConfig;
+use link::{AudioEnd, UiEnd, UiEvent, create_link_pair};
+pub use ui_bridge::PannerUiBridge;
+
+use crate::synth_engine::{
+    RandomParam, Randomizer, Sample, StereoSample,
+    buffer::{Buffer, VoicesLayout, zero_buffer},
+    routing::{
+        AudioRouterType, DataType, Input, InputMeta, InputSlots, InputSource, ModuleId,
+        NUM_CHANNELS, ProcessContext, SamplesOutput, SpectralInputSlot, VoiceRouter,
+    },
+    smooth::SmoothedSample,
+    synth_module::SynthModule,
+};
+
+const MAX_WIDTH: Sample = 2.0;
+
+struct ChannelParams {
+    pan: SmoothedSample,
+    width: SmoothedSample,
+}
+
+impl ChannelParams {
+    fn from_config(c: &PannerConfig, channel_idx: usize) -> Self {
+        Self {
+            pan: c.pan[channel_idx].into(),
+            width: c.width[channel_idx].into(),
+        }
+    }
+}
+
+pub struct Inputs {
+    audio: Option<usize>,
+    pan: InputSlots,
+    width: InputSlots,
+}
+
+impl Default for Inputs {
+    fn default() -> Self {
+        Self {
+            audio: None,
+            pan: InputSlots::empty(Input::Pan),
+            width: InputSlots::empty(Input::Width),
+        }
+    }
+}
+
+impl Inputs {
+    fn from_slots(inputs: &[InputSlots], _spectral_inputs: &[SpectralInputSlot]) -> Self {
+        let mut result = Self::default();
+
+        for input in inputs {
+            match input.input_type {
+                Input::Audio => result.audio = input.slots.first().map(|s| s.src_slot),
+                Input::Pan => result.pan = input.clone(),
+                Input::Width => result.width = input.clone(),
+                _ => (),
+            }
+        }
+
+        result
+    }
+
+    fn update_source(&mut self, input_type: Input, src_slot: usize, source: &InputSource) {
+        match input_type {
+            Input::Pan => self.pan.update_source(src_slot, source),
+            Input::Width => self.width.update_source(src_slot, source),
+            _ => (),
+        }
+    }
+}
+
+type Router<'v, 'f, 'c> = VoiceRouter<'v, 'f, 'c, AudioRouterType>;
+
+struct ChannelBuffers {
+    audio: Buffer,
+    pan: Buffer,
+    width: Buffer,
+}
+
+impl Default for ChannelBuffers {
+    fn default() -> Self {
+        Self {
+            audio: zero_buffer(),
+            pan: zero_buffer(),
+            width: zero_buffer(),
+        }
+    }
+}
+
+// Both channels of a voice are read before either output is written, width mixes them through mid/side
+pub struct Panner {
+    id: ModuleId,
+    bypass: bool,
+    channel_params: [ChannelParams; NUM_CHANNELS],
+    buffers: [ChannelBuffers; NUM_CHANNELS],
+    audio_end: AudioEnd,
+    ui_end: Option<UiEnd>,
+    inputs: Inputs,
+    output_slot: usize,
+}
+
+impl Panner {
+    pub fn new(id: ModuleId) -> Self {
+        Self::from_config(&PannerConfig {
+            id,
+            ..PannerConfig::default()
+        })
+    }
+
+    pub fn from_config(config: &PannerConfig) -> Self {
+        let (audio_end, ui_end) = create_link_pair();
+
+        Self {
+            id: config.id,
+            bypass: config.bypass,
+            channel_params: array::from_fn(|channel_idx| {
+                ChannelParams::from_config(config, channel_idx)
+            }),
+            buffers: Default::default(),
+            audio_end,
+            ui_end: Some(ui_end),
+            inputs: Inputs::default(),
+            output_slot: usize::MAX,
+        }
+    }
+
+    pub fn get_config(&self) -> PannerConfig {
+        PannerConfig {
+            id: self.id,
+            bypass: self.bypass,
+            pan: get_smoothed_param!(self, pan),
+            width: get_smoothed_param!(self, width),
+        }
+    }
+
+    pub fn randomize(
+        config: &mut PannerConfig,
+        randomizer: &mut Randomizer,
+        unlocked: impl Fn(RandomParam) -> bool,
+    ) {
+        if unlocked(RandomParam::Width) {
+            config.width = randomizer.stereo(config.width, 0.0, MAX_WIDTH);
+        }
+    }
+
+    set_smoothed_param!(set_pan, pan, pan.clamp(-1.0, 1.0));
+    set_smoothed_param!(set_width, width, width.clamp(0.0, MAX_WIDTH));
+
+    // Equal-power law scaled to unity at the center, a hard pan raises the kept side by 3 dB
+    #[inline(always)]
+    pub(super) fn pan_gain(pan: Sample) -> Sample {
+        SQRT_2 * ((pan.clamp(-1.0, 1.0) + 1.0) * FRAC_PI_4).sin()
+    }
+
+    fn read_channel(&mut self, mut router: Router<'_, '_, '_>) {
+        let channel_idx = router.channel_idx();
+        let samples = router.samples();
+        let inputs = &self.inputs;
+        let channel = &mut self.channel_params[channel_idx];
+        let buffers = &mut self.buffers[channel_idx];
+
+        router.buff_param(&inputs.pan, &mut channel.pan, &mut buffers.pan);
+        router.buff_param(&inputs.width, &mut channel.width, &mut buffers.width);
+        buffers.audio[..samples].copy_from_slice(&router.buff(inputs.audio)[..samples]);
+    }
+
+    fn process_voice(
+        &self,
+        voice_idx: usize,
+        samples: usize,
+        output: &mut VoicesLayout<SamplesOutput>,
+    ) {
+        let [left_buffers, right_buffers] = &self.buffers;
+
+        for (channel_idx, buffers) in self.buffers.iter().enumerate() {
+            let output = output[channel_idx][voice_idx].output(samples);
+            // The left channel takes mid + side and the mirrored pan
+            let sign = if channel_idx == 0 { 1.0 } else { -1.0 };
+
+            for (out, left, right, pan, width) in izip!(
+                output,
+                &left_buffers.audio,
+                &right_buffers.audio,
+                &buffers.pan,
+                &buffers.width
+            ) {
+                let mid = 0.5 * (left + right);
+                let side = 0.5 * (left - right) * width.clamp(0.0, MAX_WIDTH);
+
+                *out = side.mul_add(sign, mid) * Self::pan_gain(-sign * pan);
+            }
+        }
+    }
+}
+
+impl SynthModule for Panner {
+    fn id(&self) -> ModuleId {
+        self.id
+    }
+
+    fn bypass(&self) -> bool {
+        self.bypass
+    }
+
+    fn set_bypass(&mut self, bypass: bool) {
+        self.bypass = bypass;
+    }
+
+    fn inputs(&self) -> &'static [InputMeta] {
+        static INPUTS: &[InputMeta] = &[
+            InputMeta::audio(Input::Audio),
+            InputMeta::control(Input::Pan),
+            InputMeta::control(Input::Width),
+        ];
+
+        INPUTS
+    }
+
+    fn output_type(&self) -> DataType {
+        DataType::Audio
+    }
+
+    fn output_slot(&self) -> usize {
+        self.output_slot
+    }
+
+    fn set_output_slot(&mut self, slot: usize) {
+        self.output_slot = slot;
+    }
+
+    fn set_input_slots(&mut self, inputs: &[InputSlots], spectral_inputs: &[SpectralInputSlot]) {
+        self.inputs = Inputs::from_slots(inputs, spectral_inputs);
+    }
+
+    fn update_input_source(&mut self, input_type: Input, src_slot: usize, source: &InputSource) {
+        self.inputs.update_source(input_type, src_slot, source);
+    }
+
+    fn set_input_param(&mut self, input: Input, value: StereoSample) {
+        match input {
+            Input::Pan => self.set_pan(value),
+            Input::Width => self.set_width(value),
+            _ => (),
+        }
+    }
+
+    fn process_ui_events(&mut self) {
+        while let Some(event) = self.audio_end.pop_event() {
+            match event {
+                UiEvent::InputParam { input, value } => self.set_input_param(input, value),
+            }
+        }
+    }
+
+    fn process(&mut self, ctx: &mut ProcessContext) {
+        ctx.for_audio(self.id, self.output_slot, |router, output| {
+            let num_active_voices = router.params().active_voices.len();
+            let samples = router.params().samples;
+
+            for seq_idx in 0..num_active_voices {
+                let voice_idx = router.params().active_voices[seq_idx];
+
+                for channel_idx in 0..NUM_CHANNELS {
+                    self.read_channel(router.for_voice(channel_idx, voice_idx, seq_idx));
+                }
+
+                self.process_voice(voice_idx, samples, output);
+            }
+        });
+    }
+}
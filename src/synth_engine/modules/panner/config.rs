@@ -0,0 +1,23 @@
+use serde::{Deserialize, Serialize};
+
+use crate::synth_engine::{ModuleId, StereoSample};
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct PannerConfig {
+    pub id: ModuleId,
+    #[serde(default)]
+    pub bypass: bool,
+    pub pan: StereoSample,
+    pub width: StereoSample,
+}
+
+impl Default for PannerConfig {
+    fn default() -> Self {
+        Self {
+            id: -1,
+            bypass: false,
+            pan: 0.0.into(),
+            width: 1.0.into(),
+        }
+    }
+}
@@ -0,0 +1,42 @@
+use crate::synth_engine::{Input, StereoSample, synth_module::ModuleUiBridge};
+
+use super::link::UiEnd;
+use super::{Panner, PannerConfig};
+
+pub struct PannerUiBridge {
+    ui_end: UiEnd,
+    config: PannerConfig,
+}
+
+impl PannerUiBridge {
+    pub fn try_new(panner: &mut Panner) -> Option<Self> {
+        Some(Self {
+            ui_end: panner.ui_end.take()?,
+            config: panner.get_config(),
+        })
+    }
+
+    pub fn config(&self) -> &PannerConfig {
+        &self.config
+    }
+
+    pub fn set_param(&mut self, input: Input, value: StereoSample) {
+        if !self.ui_end.set_param(input, value) {
+            return;
+        }
+
+        match input {
+            Input::Pan => self.config.pan = value,
+            Input::Width => self.config.width = value,
+            _ => (),
+        }
+    }
+}
+
+impl ModuleUiBridge for PannerUiBridge {
+    fn update(&mut self) {}
+
+    fn edits(&self) -> u32 {
+        self.ui_end.edits()
+    }
+}
@@ -0,0 +1,418 @@
+use std::array;
+
+use itertools::izip;
+
+mod config;
+mod link;
+mod ui_bridge;
+
+pub use config::ReverbConfig;
+use link::{AudioEnd, UiEnd, UiEvent, create_link_pair};
+pub use ui_bridge::ReverbUiBridge;
+
+use crate::{
+    synth_engine::{
+        RandomParam, Randomizer, Sample, StereoSample,
+        buffer::{Buffer, zero_buffer},
+        delay_line::{DelayLine, MAX_BUS_SAMPLE_RATE},
+        routing::{
+            DataType, Input, InputMeta, InputSlots, InputSource, ModuleId, NUM_CHANNELS,
+            ProcessContext, ProcessParams, SpectralInputSlot,
+        },
+        synth_module::SynthModule,
+    },
+    utils::from_ms,
+};
+
+pub const MAX_PRE_DELAY: Sample = from_ms(200.0);
+
+// Freeverb tunings, given in samples at 44.1 kHz
+const REFERENCE_SAMPLE_RATE: Sample = 44_100.0;
+const COMB_TUNINGS: [Sample; 8] = [
+    1116.0, 1188.0, 1277.0, 1356.0, 1422.0, 1491.0, 1557.0, 1617.0,
+];
+const ALLPASS_TUNINGS: [Sample; 4] = [556.0, 441.0, 341.0, 225.0];
+const STEREO_SPREAD: Sample = 23.0;
+
+const INPUT_GAIN: Sample = 0.015;
+const WET_GAIN: Sample = 3.0;
+const ROOM_SCALE: Sample = 0.28;
+const ROOM_OFFSET: Sample = 0.7;
+const DAMPING_SCALE: Sample = 0.4;
+const ALLPASS_FEEDBACK: Sample = 0.5;
+
+#[derive(Default, Clone, Copy)]
+struct ChannelParams {
+    size: Sample,
+    damping: Sample,
+    pre_delay: Sample,
+    dry_wet: Sample,
+}
+
+impl ChannelParams {
+    fn from_config(c: &ReverbConfig, channel_idx: usize) -> Self {
+        Self {
+            size: c.size[channel_idx],
+            damping: c.damping[channel_idx],
+            pre_delay: c.pre_delay[channel_idx],
+            dry_wet: c.dry_wet[channel_idx],
+        }
+    }
+
+    fn zip_with(&self, other: &Self, f: impl Fn(Sample, Sample) -> Sample) -> Self {
+        Self {
+            size: f(self.size, other.size),
+            damping: f(self.damping, other.damping),
+            pre_delay: f(self.pre_delay, other.pre_delay),
+            dry_wet: f(self.dry_wet, other.dry_wet),
+        }
+    }
+
+    fn clamped(&self) -> Self {
+        Self {
+            size: self.size.clamp(0.0, 1.0),
+            damping: self.damping.clamp(0.0, 1.0),
+            pre_delay: self.pre_delay.clamp(0.0, MAX_PRE_DELAY),
+            dry_wet: self.dry_wet.clamp(0.0, 1.0),
+        }
+    }
+}
+
+pub struct Inputs {
+    size: InputSlots,
+    damping: InputSlots,
+    pre_delay: InputSlots,
+    dry_wet: InputSlots,
+}
+
+impl Default for Inputs {
+    fn default() -> Self {
+        Self {
+            size: InputSlots::empty(Input::Size),
+            damping: InputSlots::empty(Input::Damping),
+            pre_delay: InputSlots::empty(Input::PreDelay),
+            dry_wet: InputSlots::empty(Input::DryWet),
+        }
+    }
+}
+
+impl Inputs {
+    fn from_slots(inputs: &[InputSlots], _spectral_inputs: &[SpectralInputSlot]) -> Self {
+        let mut result = Self::default();
+
+        for input in inputs {
+            match input.input_type {
+                Input::Size => result.size = input.clone(),
+                Input::Damping => result.damping = input.clone(),
+                Input::PreDelay => result.pre_delay = input.clone(),
+                Input::DryWet => result.dry_wet = input.clone(),
+                _ => (),
+            }
+        }
+
+        result
+    }
+
+    fn update_source(&mut self, input_type: Input, src_slot: usize, source: &InputSource) {
+        match input_type {
+            Input::Size => self.size.update_source(src_slot, source),
+            Input::Damping => self.damping.update_source(src_slot, source),
+            Input::PreDelay => self.pre_delay.update_source(src_slot, source),
+            Input::DryWet => self.dry_wet.update_source(src_slot, source),
+            _ => (),
+        }
+    }
+}
+
+fn scaled_length(tuning: Sample, sample_rate: Sample) -> Sample {
+    tuning * sample_rate / REFERENCE_SAMPLE_RATE
+}
+
+fn max_length(tuning: Sample) -> usize {
+    scaled_length(tuning + STEREO_SPREAD, MAX_BUS_SAMPLE_RATE).ceil() as usize
+}
+
+// Lowpass feedback comb
+struct Comb {
+    line: DelayLine,
+    filter_state: Sample,
+}
+
+impl Comb {
+    fn new(tuning: Sample) -> Self {
+        Self {
+            line: DelayLine::new(max_length(tuning)),
+            filter_state: 0.0,
+        }
+    }
+
+    fn process(
+        &mut self,
+        input: Sample,
+        length: Sample,
+        feedback: Sample,
+        damping: Sample,
+    ) -> Sample {
+        // Reading before the push, so one sample less
+        let output = self.line.read(length - 1.0);
+
+        self.filter_state = output + (self.filter_state - output) * damping;
+        self.line.push(input + self.filter_state * feedback);
+        output
+    }
+}
+
+struct Allpass {
+    line: DelayLine,
+}
+
+impl Allpass {
+    fn new(tuning: Sample) -> Self {
+        Self {
+            line: DelayLine::new(max_length(tuning)),
+        }
+    }
+
+    fn process(&mut self, input: Sample, length: Sample) -> Sample {
+        let delayed = self.line.read(length - 1.0);
+
+        self.line.push(input + delayed * ALLPASS_FEEDBACK);
+        delayed - input
+    }
+}
+
+struct Channel {
+    pre_delay_line: DelayLine,
+    combs: [Comb; COMB_TUNINGS.len()],
+    allpasses: [Allpass; ALLPASS_TUNINGS.len()],
+    pre_delay: Sample,
+    dry_wet: Sample,
+}
+
+pub struct Reverb {
+    id: ModuleId,
+    bypass: bool,
+    channel_params: [ChannelParams; NUM_CHANNELS],
+    modulation: [ChannelParams; NUM_CHANNELS], // Offsets from the params
+    channels: [Channel; NUM_CHANNELS],
+    input_buffer: Buffer,
+    audio_end: AudioEnd,
+    ui_end: Option<UiEnd>,
+    inputs: Inputs,
+}
+
+impl Reverb {
+    pub fn new(id: ModuleId) -> Self {
+        Self::from_config(&ReverbConfig {
+            id,
+            ..ReverbConfig::default()
+        })
+    }
+
+    pub fn from_config(config: &ReverbConfig) -> Self {
+        let (audio_end, ui_end) = create_link_pair();
+        let channel_params: [ChannelParams; NUM_CHANNELS] =
+            array::from_fn(|channel_idx| ChannelParams::from_config(config, channel_idx));
+        let max_pre_delay_samples = (MAX_PRE_DELAY * MAX_BUS_SAMPLE_RATE).ceil() as usize;
+
+        Self {
+            id: config.id,
+            bypass: config.bypass,
+            modulation: Default::default(),
+            channels: array::from_fn(|channel_idx| {
+                let params = channel_params[channel_idx].clamped();
+
+                Channel {
+                    pre_delay_line: DelayLine::new(max_pre_delay_samples),
+                    combs: COMB_TUNINGS.map(Comb::new),
+                    allpasses: ALLPASS_TUNINGS.map(Allpass::new),
+                    pre_delay: params.pre_delay,
+                    dry_wet: params.dry_wet,
+                }
+            }),
+            channel_params,
+            input_buffer: zero_buffer(),
+            audio_end,
+            ui_end: Some(ui_end),
+            inputs: Inputs::default(),
+        }
+    }
+
+    pub fn get_config(&self) -> ReverbConfig {
+        ReverbConfig {
+            id: self.id,
+            bypass: self.bypass,
+            size: get_stereo_param!(self, size),
+            damping: get_stereo_param!(self, damping),
+            pre_delay: get_stereo_param!(self, pre_delay),
+            dry_wet: get_stereo_param!(self, dry_wet),
+        }
+    }
+
+    pub fn randomize(
+        config: &mut ReverbConfig,
+        randomizer: &mut Randomizer,
+        unlocked: impl Fn(RandomParam) -> bool,
+    ) {
+        if unlocked(RandomParam::Size) {
+            config.size = randomizer.stereo(config.size, 0.0, 1.0);
+        }
+        if unlocked(RandomParam::Damping) {
+            config.damping = randomizer.stereo(config.damping, 0.0, 1.0);
+        }
+        if unlocked(RandomParam::Mix) {
+            config.dry_wet = randomizer.stereo(config.dry_wet, 0.0, 1.0);
+        }
+    }
+
+    set_stereo_param!(set_size, size);
+    set_stereo_param!(set_damping, damping);
+    set_stereo_param!(set_pre_delay, pre_delay);
+    set_stereo_param!(set_dry_wet, dry_wet);
+}
+
+impl SynthModule for Reverb {
+    fn id(&self) -> ModuleId {
+        self.id
+    }
+
+    fn bypass(&self) -> bool {
+        self.bypass
+    }
+
+    fn set_bypass(&mut self, bypass: bool) {
+        self.bypass = bypass;
+    }
+
+    fn inputs(&self) -> &'static [InputMeta] {
+        static INPUTS: &[InputMeta] = &[
+            InputMeta::bus(Input::Audio),
+            InputMeta::control(Input::Size),
+            InputMeta::control(Input::Damping),
+            InputMeta::control(Input::PreDelay),
+            InputMeta::control(Input::DryWet),
+        ];
+
+        INPUTS
+    }
+
+    fn output_type(&self) -> DataType {
+        DataType::Bus
+    }
+
+    fn output_slot(&self) -> usize {
+        usize::MAX
+    }
+
+    fn set_output_slot(&mut self, _slot: usize) {}
+
+    fn set_input_slots(&mut self, inputs: &[InputSlots], spectral_inputs: &[SpectralInputSlot]) {
+        self.inputs = Inputs::from_slots(inputs, spectral_inputs);
+    }
+
+    fn update_input_source(&mut self, input_type: Input, src_slot: usize, source: &InputSource) {
+        self.inputs.update_source(input_type, src_slot, source);
+    }
+
+    fn set_input_param(&mut self, input: Input, value: StereoSample) {
+        match input {
+            Input::Size => self.set_size(value),
+            Input::Damping => self.set_damping(value),
+            Input::PreDelay => self.set_pre_delay(value),
+            Input::DryWet => self.set_dry_wet(value),
+            _ => (),
+        }
+    }
+
+    fn process_ui_events(&mut self) {
+        while let Some(event) = self.audio_end.pop_event() {
+            match event {
+                UiEvent::InputParam { input, value } => self.set_input_param(input, value),
+            }
+        }
+    }
+
+    // The bus runs once for all voices, so modulation is taken from the first playing one.
+    // Without voices the last modulation is kept, the tail shouldn't jump when they stop.
+    fn process(&mut self, ctx: &mut ProcessContext) {
+        let mut rf = ctx.for_output(self.id);
+
+        let Some(&voice_idx) = rf.params().active_voices.first() else {
+            return;
+        };
+
+        for (channel_idx, (modulation, params)) in self
+            .modulation
+            .iter_mut()
+            .zip(&self.channel_params)
+            .enumerate()
+        {
+            let mut router = rf.for_voice(channel_idx, voice_idx, 0);
+            let inputs = &self.inputs;
+            let modulated = ChannelParams {
+                size: router.scalar_param(&inputs.size, params.size, false),
+                damping: router.scalar_param(&inputs.damping, params.damping, false),
+                pre_delay: router.scalar_param(&inputs.pre_delay, params.pre_delay, false),
+                dry_wet: router.scalar_param(&inputs.dry_wet, params.dry_wet, false),
+            };
+
+            *modulation = modulated.zip_with(params, |modulated, param| modulated - param);
+        }
+    }
+
+    fn process_bus(&mut self, bus: &mut [Buffer; NUM_CHANNELS], params: &ProcessParams) {
+        let samples = params.samples;
+        let sample_rate = params.sample_rate;
+        let [left_bus, right_bus] = &*bus;
+
+        for (input, left, right) in izip!(&mut self.input_buffer, left_bus, right_bus).take(samples)
+        {
+            *input = (left + right) * INPUT_GAIN;
+        }
+
+        for (channel_idx, (output, channel, channel_params, modulation)) in izip!(
+            bus.iter_mut(),
+            &mut self.channels,
+            &self.channel_params,
+            &self.modulation
+        )
+        .enumerate()
+        {
+            let target = channel_params
+                .zip_with(modulation, |param, modulation| param + modulation)
+                .clamped();
+            let spread = STEREO_SPREAD * channel_idx as Sample;
+            let comb_lengths =
+                COMB_TUNINGS.map(|tuning| scaled_length(tuning + spread, sample_rate));
+            let allpass_lengths =
+                ALLPASS_TUNINGS.map(|tuning| scaled_length(tuning + spread, sample_rate));
+            let feedback = target.size * ROOM_SCALE + ROOM_OFFSET;
+            let damping = target.damping * DAMPING_SCALE;
+
+            // Pre-delay and dry/wet are ramped over the block to avoid clicks
+            let samples_recip = (samples as Sample).recip();
+            let pre_delay_step = (target.pre_delay - channel.pre_delay) * samples_recip;
+            let dry_wet_step = (target.dry_wet - channel.dry_wet) * samples_recip;
+
+            for (out, input) in izip!(output.iter_mut(), &self.input_buffer).take(samples) {
+                channel.pre_delay += pre_delay_step;
+                channel.dry_wet += dry_wet_step;
+                channel.pre_delay_line.push(*input);
+
+                let delayed = channel.pre_delay_line.read(channel.pre_delay * sample_rate);
+                let mut wet: Sample = izip!(&mut channel.combs, &comb_lengths)
+                    .map(|(comb, length)| comb.process(delayed, *length, feedback, damping))
+                    .sum();
+
+                for (allpass, length) in izip!(&mut channel.allpasses, &allpass_lengths) {
+                    wet = allpass.process(wet, *length);
+                }
+
+                *out += (wet * WET_GAIN - *out) * channel.dry_wet;
+            }
+
+            channel.pre_delay = target.pre_delay;
+            channel.dry_wet = target.dry_wet;
+        }
+    }
+}
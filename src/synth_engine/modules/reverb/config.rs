@@ -0,0 +1,30 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    synth_engine::{ModuleId, StereoSample},
+    utils::from_ms,
+};
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ReverbConfig {
+    pub id: ModuleId,
+    #[serde(default)]
+    pub bypass: bool,
+    pub size: StereoSample,
+    pub damping: StereoSample,
+    pub pre_delay: StereoSample,
+    pub dry_wet: StereoSample,
+}
+
+impl Default for ReverbConfig {
+    fn default() -> Self {
+        Self {
+            id: -1,
+            bypass: false,
+            size: 0.5.into(),
+            damping: 0.5.into(),
+            pre_delay: from_ms(20.0).into(),
+            dry_wet: 0.25.into(),
+        }
+    }
+}
@@ -0,0 +1,44 @@
+use crate::synth_engine::{Input, StereoSample, synth_module::ModuleUiBridge};
+
+use super::link::UiEnd;
+use super::{Reverb, ReverbConfig};
+
+pub struct ReverbUiBridge {
+    ui_end: UiEnd,
+    config: ReverbConfig,
+}
+
+impl ReverbUiBridge {
+    pub fn try_new(reverb: &mut Reverb) -> Option<Self> {
+        Some(Self {
+            ui_end: reverb.ui_end.take()?,
+            config: reverb.get_config(),
+        })
+    }
+
+    pub fn config(&self) -> &ReverbConfig {
+        &self.config
+    }
+
+    pub fn set_param(&mut self, input: Input, value: StereoSample) {
+        if !self.ui_end.set_param(input, value) {
+            return;
+        }
+
+        match input {
+            Input::Size => self.config.size = value,
+            Input::Damping => self.config.damping = value,
+            Input::PreDelay => self.config.pre_delay = value,
+            Input::DryWet => self.config.dry_wet = value,
+            _ => (),
+        }
+    }
+}
+
+impl ModuleUiBridge for ReverbUiBridge {
+    fn update(&mut self) {}
+
+    fn edits(&self) -> u32 {
+        self.ui_end.edits()
+    }
+}
@@ -0,0 +1,250 @@
+use std::array;
+
+use itertools::izip;
+
+mod config;
+mod link;
+mod ui_bridge;
+
+pub use config::RingModConfig;
+use link::{AudioEnd, UiEnd, UiEvent, create_link_pair};
+pub use ui_bridge::RingModUiBridge;
+
+use crate::synth_engine::{
+    RandomParam, Randomizer, StereoSample,
+    buffer::{Buffer, VoicesLayout, zero_buffer},
+    routing::{
+        AudioRouterType, DataType, Input, InputMeta, InputSlots, InputSource, ModuleId,
+        NUM_CHANNELS, ProcessContext, SamplesOutput, SpectralInputSlot, VoiceRouter,
+    },
+    smooth::SmoothedSample,
+    synth_module::SynthModule,
+};
+
+pub const CARRIER_INPUT: Input = Input::AudioMix(0);
+pub const MODULATOR_INPUT: Input = Input::AudioMix(1);
+
+struct ChannelParams {
+    mix: SmoothedSample,
+}
+
+impl ChannelParams {
+    fn from_config(c: &RingModConfig, channel_idx: usize) -> Self {
+        Self {
+            mix: c.mix[channel_idx].into(),
+        }
+    }
+}
+
+pub struct Inputs {
+    carrier: Option<usize>,
+    modulator: Option<usize>,
+    mix: InputSlots,
+}
+
+impl Default for Inputs {
+    fn default() -> Self {
+        Self {
+            carrier: None,
+            modulator: None,
+            mix: InputSlots::empty(Input::Mix),
+        }
+    }
+}
+
+impl Inputs {
+    fn from_slots(inputs: &[InputSlots], _spectral_inputs: &[SpectralInputSlot]) -> Self {
+        let mut result = Self::default();
+
+        for input in inputs {
+            match input.input_type {
+                CARRIER_INPUT => result.carrier = input.slots.first().map(|s| s.src_slot),
+                MODULATOR_INPUT => result.modulator = input.slots.first().map(|s| s.src_slot),
+                Input::Mix => result.mix = input.clone(),
+                _ => (),
+            }
+        }
+
+        result
+    }
+
+    fn update_source(&mut self, input_type: Input, src_slot: usize, source: &InputSource) {
+        if input_type == Input::Mix {
+            self.mix.update_source(src_slot, source);
+        }
+    }
+}
+
+type Router<'v, 'f, 'c> = VoiceRouter<'v, 'f, 'c, AudioRouterType>;
+
+struct Buffers {
+    carrier: Buffer,
+    mix_mod_input: Buffer,
+}
+
+// Multiplies two audio inputs, the mix crossfades from the first input to the product
+pub struct RingMod {
+    id: ModuleId,
+    bypass: bool,
+    channel_params: [ChannelParams; NUM_CHANNELS],
+    buffers: Buffers,
+    audio_end: AudioEnd,
+    ui_end: Option<UiEnd>,
+    inputs: Inputs,
+    output_slot: usize,
+}
+
+impl RingMod {
+    pub fn new(id: ModuleId) -> Self {
+        Self::from_config(&RingModConfig {
+            id,
+            ..RingModConfig::default()
+        })
+    }
+
+    pub fn from_config(config: &RingModConfig) -> Self {
+        let (audio_end, ui_end) = create_link_pair();
+
+        Self {
+            id: config.id,
+            bypass: config.bypass,
+            channel_params: array::from_fn(|channel_idx| {
+                ChannelParams::from_config(config, channel_idx)
+            }),
+            buffers: Buffers {
+                carrier: zero_buffer(),
+                mix_mod_input: zero_buffer(),
+            },
+            audio_end,
+            ui_end: Some(ui_end),
+            inputs: Inputs::default(),
+            output_slot: usize::MAX,
+        }
+    }
+
+    pub fn get_config(&self) -> RingModConfig {
+        RingModConfig {
+            id: self.id,
+            bypass: self.bypass,
+            mix: get_smoothed_param!(self, mix),
+        }
+    }
+
+    pub fn randomize(
+        config: &mut RingModConfig,
+        randomizer: &mut Randomizer,
+        unlocked: impl Fn(RandomParam) -> bool,
+    ) {
+        if unlocked(RandomParam::Mix) {
+            config.mix = randomizer.stereo(config.mix, 0.0, 1.0);
+        }
+    }
+
+    set_smoothed_param!(set_mix, mix);
+
+    fn process_voice(
+        &mut self,
+        output: &mut VoicesLayout<SamplesOutput>,
+        mut router: Router<'_, '_, '_>,
+    ) {
+        let channel_idx = router.channel_idx();
+        let voice_idx = router.voice_idx();
+        let samples = router.samples();
+        let inputs = &self.inputs;
+        let channel = &mut self.channel_params[channel_idx];
+        let output = output[channel_idx][voice_idx].output(samples);
+
+        router.buff_param(
+            &inputs.mix,
+            &mut channel.mix,
+            &mut self.buffers.mix_mod_input,
+        );
+
+        self.buffers.carrier[..samples].copy_from_slice(&router.buff(inputs.carrier)[..samples]);
+
+        let modulator = router.buff(inputs.modulator);
+
+        for (out, carrier, modulator, mix) in izip!(
+            output,
+            &self.buffers.carrier,
+            modulator,
+            &self.buffers.mix_mod_input
+        ) {
+            let mix = mix.clamp(0.0, 1.0);
+
+            *out = carrier * (1.0 - mix + mix * modulator);
+        }
+    }
+}
+
+impl SynthModule for RingMod {
+    fn id(&self) -> ModuleId {
+        self.id
+    }
+
+    fn bypass(&self) -> bool {
+        self.bypass
+    }
+
+    fn set_bypass(&mut self, bypass: bool) {
+        self.bypass = bypass;
+    }
+
+    fn inputs(&self) -> &'static [InputMeta] {
+        static INPUTS: &[InputMeta] = &[
+            InputMeta::audio(CARRIER_INPUT),
+            InputMeta::audio(MODULATOR_INPUT),
+            InputMeta::control(Input::Mix),
+        ];
+
+        INPUTS
+    }
+
+    fn output_type(&self) -> DataType {
+        DataType::Audio
+    }
+
+    fn output_slot(&self) -> usize {
+        self.output_slot
+    }
+
+    fn set_output_slot(&mut self, slot: usize) {
+        self.output_slot = slot;
+    }
+
+    fn set_input_slots(&mut self, inputs: &[InputSlots], spectral_inputs: &[SpectralInputSlot]) {
+        self.inputs = Inputs::from_slots(inputs, spectral_inputs);
+    }
+
+    fn update_input_source(&mut self, input_type: Input, src_slot: usize, source: &InputSource) {
+        self.inputs.update_source(input_type, src_slot, source);
+    }
+
+    fn set_input_param(&mut self, input: Input, value: StereoSample) {
+        if input == Input::Mix {
+            self.set_mix(value);
+        }
+    }
+
+    fn process_ui_events(&mut self) {
+        while let Some(event) = self.audio_end.pop_event() {
+            match event {
+                UiEvent::InputParam { input, value } => self.set_input_param(input, value),
+            }
+        }
+    }
+
+    fn process(&mut self, ctx: &mut ProcessContext) {
+        ctx.for_audio(self.id, self.output_slot, |router, output| {
+            let num_active_voices = router.params().active_voices.len();
+
+            for channel_idx in 0..NUM_CHANNELS {
+                for seq_idx in 0..num_active_voices {
+                    let voice_idx = router.params().active_voices[seq_idx];
+
+                    self.process_voice(output, router.for_voice(channel_idx, voice_idx, seq_idx));
+                }
+            }
+        });
+    }
+}
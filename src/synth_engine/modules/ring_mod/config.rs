@@ -0,0 +1,21 @@
+use serde::{Deserialize, Serialize};
+
+use crate::synth_engine::{ModuleId, StereoSample};
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct RingModConfig {
+    pub id: ModuleId,
+    #[serde(default)]
+    pub bypass: bool,
+    pub mix: StereoSample,
+}
+
+impl Default for RingModConfig {
+    fn default() -> Self {
+        Self {
+            id: -1,
+            bypass: false,
+            mix: 1.0.into(),
+        }
+    }
+}
@@ -0,0 +1,36 @@
+use crate::synth_engine::{Input, StereoSample, synth_module::ModuleUiBridge};
+
+use super::link::UiEnd;
+use super::{RingMod, RingModConfig};
+
+pub struct RingModUiBridge {
+    ui_end: UiEnd,
+    config: RingModConfig,
+}
+
+impl RingModUiBridge {
+    pub fn try_new(ring_mod: &mut RingMod) -> Option<Self> {
+        Some(Self {
+            ui_end: ring_mod.ui_end.take()?,
+            config: ring_mod.get_config(),
+        })
+    }
+
+    pub fn config(&self) -> &RingModConfig {
+        &self.config
+    }
+
+    pub fn set_param(&mut self, input: Input, value: StereoSample) {
+        if self.ui_end.set_param(input, value) && input == Input::Mix {
+            self.config.mix = value;
+        }
+    }
+}
+
+impl ModuleUiBridge for RingModUiBridge {
+    fn update(&mut self) {}
+
+    fn edits(&self) -> u32 {
+        self.ui_end.edits()
+    }
+}
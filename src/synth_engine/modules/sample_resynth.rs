@@ -0,0 +1,330 @@
+use std::{array, mem, path::PathBuf};
+
+use itertools::izip;
+use nih_plug::nih_log;
+
+mod config;
+mod link;
+mod ui_bridge;
+
+pub use config::SampleResynthConfig;
+use link::{AudioEnd, LoadedSample, UiEnd, UiEvent, create_link_pair};
+pub use ui_bridge::SampleResynthUiBridge;
+
+use crate::synth_engine::{
+    RandomParam, Randomizer, Sample, StereoSample,
+    buffer::{HARMONIC_SERIES_BUFFER, VoicesLayout, new_voices_layout},
+    routing::{
+        DataType, Input, InputMeta, InputSlots, InputSource, ModuleId, NUM_CHANNELS,
+        ProcessContext, SpectralInputSlot, SpectralOutput, SpectralRouterType, VoiceEvent,
+        VoiceRouter,
+    },
+    sample_analysis::{HarmonicFrames, analyze_harmonic_frames},
+    synth_module::SynthModule,
+    types::ComplexSample,
+};
+
+pub struct SampleInfo {
+    pub fundamental: Sample, // Hz
+    pub num_frames: usize,
+    pub duration: Sample,
+}
+
+impl SampleInfo {
+    fn new(frames: &HarmonicFrames) -> Self {
+        Self {
+            fundamental: frames.fundamental,
+            num_frames: frames.num_frames(),
+            duration: frames.num_frames() as Sample * frames.frame_duration,
+        }
+    }
+}
+
+struct ChannelParams {
+    position: Sample,
+}
+
+impl ChannelParams {
+    fn from_config(c: &SampleResynthConfig, channel_idx: usize) -> Self {
+        Self {
+            position: c.position[channel_idx],
+        }
+    }
+}
+
+// Everything the latest output was computed from
+#[derive(Clone, Copy, PartialEq)]
+struct AppliedState {
+    sample_revision: u32,
+    bins: usize,
+    position: Sample,
+}
+
+#[derive(Default)]
+struct VoiceState {
+    triggered: bool,
+    applied: Option<AppliedState>,
+}
+
+pub struct Inputs {
+    position: InputSlots,
+}
+
+impl Default for Inputs {
+    fn default() -> Self {
+        Self {
+            position: InputSlots::empty(Input::Position),
+        }
+    }
+}
+
+impl Inputs {
+    fn from_slots(inputs: &[InputSlots]) -> Self {
+        let mut result = Self::default();
+
+        for input in inputs {
+            if input.input_type == Input::Position {
+                result.position = input.clone();
+            }
+        }
+
+        result
+    }
+
+    fn update_source(&mut self, input_type: Input, src_slot: usize, source: &InputSource) {
+        if input_type == Input::Position {
+            self.position.update_source(src_slot, source);
+        }
+    }
+}
+
+type Router<'v, 'f, 'c> = VoiceRouter<'v, 'f, 'c, SpectralRouterType>;
+
+// Spectral source playing back the harmonic frames analyzed from a sample,
+// the position scans through the sample from the first frame to the last one.
+pub struct SampleResynth {
+    id: ModuleId,
+    bypass: bool,
+    path: Option<PathBuf>,
+    frames: Option<Box<HarmonicFrames>>,
+    sample_revision: u32,
+    channel_params: [ChannelParams; NUM_CHANNELS],
+    audio_end: AudioEnd,
+    ui_end: Option<UiEnd>,
+    inputs: Inputs,
+    output_slot: usize,
+    voices: VoicesLayout<VoiceState>,
+}
+
+impl SampleResynth {
+    pub fn new(id: ModuleId) -> Self {
+        Self::from_config(&SampleResynthConfig {
+            id,
+            ..SampleResynthConfig::default()
+        })
+    }
+
+    pub fn from_config(config: &SampleResynthConfig) -> Self {
+        let (audio_end, ui_end) = create_link_pair();
+        let frames = config.path.as_ref().and_then(|path| {
+            analyze_harmonic_frames(path)
+                .inspect_err(|err| nih_log!("Failed to analyze {path:?}: {err}"))
+                .ok()
+        });
+
+        Self {
+            id: config.id,
+            bypass: config.bypass,
+            path: config.path.clone(),
+            frames: frames.map(Box::new),
+            sample_revision: 0,
+            channel_params: array::from_fn(|channel_idx| {
+                ChannelParams::from_config(config, channel_idx)
+            }),
+            audio_end,
+            ui_end: Some(ui_end),
+            inputs: Inputs::default(),
+            output_slot: usize::MAX,
+            voices: new_voices_layout(),
+        }
+    }
+
+    pub fn get_config(&self) -> SampleResynthConfig {
+        SampleResynthConfig {
+            id: self.id,
+            bypass: self.bypass,
+            path: self.path.clone(),
+            position: get_stereo_param!(self, position),
+        }
+    }
+
+    pub fn randomize(
+        config: &mut SampleResynthConfig,
+        randomizer: &mut Randomizer,
+        unlocked: impl Fn(RandomParam) -> bool,
+    ) {
+        if unlocked(RandomParam::Position) {
+            config.position = randomizer.stereo(config.position, 0.0, 1.0);
+        }
+    }
+
+    pub fn sample_info(&self) -> Option<SampleInfo> {
+        self.frames.as_deref().map(SampleInfo::new)
+    }
+
+    // Swaps the loaded sample in, the previous one goes back to the editor to be freed
+    fn set_sample(&mut self, mut sample: Box<LoadedSample>) {
+        mem::swap(&mut self.path, &mut sample.path);
+        mem::swap(&mut self.frames, &mut sample.frames);
+        self.sample_revision = self.sample_revision.wrapping_add(1);
+        self.audio_end.release(sample);
+    }
+
+    set_stereo_param!(set_position, position, position.clamp(0.0, 1.0));
+
+    fn process_voice(
+        &mut self,
+        output: &mut VoicesLayout<SpectralOutput>,
+        mut router: Router<'_, '_, '_>,
+    ) {
+        let channel_idx = router.channel_idx();
+        let voice_idx = router.voice_idx();
+        let channel = &self.channel_params[channel_idx];
+        let voice = &mut self.voices[channel_idx][voice_idx];
+
+        let position = router
+            .scalar_param(&self.inputs.position, channel.position, voice.triggered)
+            .clamp(0.0, 1.0);
+
+        let bins = router.params().spectral_bins;
+        let applied = Some(AppliedState {
+            sample_revision: self.sample_revision,
+            bins,
+            position,
+        });
+
+        // Same frame, downstream modules keep reading the latest spectrum
+        if !voice.triggered && voice.applied == applied {
+            return;
+        }
+
+        voice.applied = applied;
+
+        let voice_output = &mut output[channel_idx][voice_idx].advance()[..bins];
+
+        match &self.frames {
+            Some(frames) => {
+                let frame_pos = position * (frames.num_frames() - 1) as Sample;
+                let frame_idx = frame_pos as usize;
+                let t = frame_pos - frame_idx as Sample;
+
+                voice_output[0] = ComplexSample::ZERO;
+
+                for (out, from, to, harmonic) in izip!(
+                    voice_output.iter_mut(),
+                    frames.frame(frame_idx),
+                    frames.frame(frame_idx + 1),
+                    &HARMONIC_SERIES_BUFFER
+                )
+                .skip(1)
+                {
+                    let from = from[channel_idx];
+
+                    *out = harmonic * (from + (to[channel_idx] - from) * t);
+                }
+            }
+            None => voice_output.fill(ComplexSample::ZERO),
+        }
+
+        if voice.triggered {
+            voice.triggered = false;
+            voice.applied = None;
+
+            self.process_voice(output, router);
+        }
+    }
+}
+
+impl SynthModule for SampleResynth {
+    fn id(&self) -> ModuleId {
+        self.id
+    }
+
+    fn bypass(&self) -> bool {
+        self.bypass
+    }
+
+    fn set_bypass(&mut self, bypass: bool) {
+        self.bypass = bypass;
+    }
+
+    fn inputs(&self) -> &'static [InputMeta] {
+        static INPUTS: &[InputMeta] = &[InputMeta::control(Input::Position)];
+
+        INPUTS
+    }
+
+    fn output_type(&self) -> DataType {
+        DataType::Spectral
+    }
+
+    fn output_slot(&self) -> usize {
+        self.output_slot
+    }
+
+    fn set_output_slot(&mut self, slot: usize) {
+        self.output_slot = slot;
+    }
+
+    fn set_input_slots(&mut self, inputs: &[InputSlots], _spectral_inputs: &[SpectralInputSlot]) {
+        self.inputs = Inputs::from_slots(inputs);
+
+        for voice in self.voices.iter_mut().flatten() {
+            voice.applied = None;
+        }
+    }
+
+    fn process_events(&mut self, events: &[VoiceEvent]) {
+        for channel in self.voices.iter_mut() {
+            for event in events {
+                if let VoiceEvent::Trigger { voice_idx, .. } = event {
+                    channel[*voice_idx].triggered = true;
+                }
+            }
+        }
+    }
+
+    fn update_input_source(&mut self, input_type: Input, src_slot: usize, source: &InputSource) {
+        self.inputs.update_source(input_type, src_slot, source);
+    }
+
+    fn set_input_param(&mut self, input: Input, value: StereoSample) {
+        if input == Input::Position {
+            self.set_position(value);
+        }
+    }
+
+    fn process_ui_events(&mut self) {
+        while let Some(event) = self.audio_end.pop_event() {
+            match event {
+                UiEvent::InputParam { input, value } => self.set_input_param(input, value),
+                UiEvent::Sample(sample) => self.set_sample(sample),
+            }
+        }
+    }
+
+    fn process(&mut self, ctx: &mut ProcessContext) {
+        ctx.for_spectral(self.id, self.output_slot, |router, output| {
+            let num_active_voices = router.params().active_voices.len();
+            let spectrum_channels = router.params().spectrum_channels;
+
+            for channel_idx in 0..spectrum_channels {
+                for seq_idx in 0..num_active_voices {
+                    let voice_idx = router.params().active_voices[seq_idx];
+
+                    self.process_voice(output, router.for_voice(channel_idx, voice_idx, seq_idx));
+                }
+            }
+        });
+    }
+}
@@ -0,0 +1,26 @@
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use crate::synth_engine::{ModuleId, StereoSample};
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SampleResynthConfig {
+    pub id: ModuleId,
+    #[serde(default)]
+    pub bypass: bool,
+    // The sample is analyzed again when the patch loads
+    pub path: Option<PathBuf>,
+    pub position: StereoSample,
+}
+
+impl Default for SampleResynthConfig {
+    fn default() -> Self {
+        Self {
+            id: -1,
+            bypass: false,
+            path: None,
+            position: 0.0.into(),
+        }
+    }
+}
@@ -0,0 +1,82 @@
+use std::path::PathBuf;
+
+use crate::synth_engine::{
+    Input, StereoSample, edit_sender::EditSender, sample_analysis::HarmonicFrames,
+};
+
+// Analyzed on the editor thread, the sample it replaces comes back to be freed there
+pub struct LoadedSample {
+    pub path: Option<PathBuf>,
+    pub frames: Option<Box<HarmonicFrames>>,
+}
+
+pub enum UiEvent {
+    InputParam { input: Input, value: StereoSample },
+    Sample(Box<LoadedSample>),
+}
+
+pub enum UiUpdate {
+    Released(Box<LoadedSample>),
+}
+
+pub struct UiEnd {
+    rx: rtrb::Consumer<UiUpdate>,
+    tx: EditSender<UiEvent>,
+}
+
+impl UiEnd {
+    pub fn new(rx: rtrb::Consumer<UiUpdate>, tx: rtrb::Producer<UiEvent>) -> Self {
+        Self {
+            rx,
+            tx: EditSender::new(tx),
+        }
+    }
+
+    pub fn edits(&self) -> u32 {
+        self.tx.sent()
+    }
+
+    pub fn set_param(&mut self, input: Input, value: StereoSample) -> bool {
+        self.tx.push(UiEvent::InputParam { input, value }).is_ok()
+    }
+
+    pub fn set_sample(&mut self, sample: Box<LoadedSample>) -> bool {
+        self.tx.push(UiEvent::Sample(sample)).is_ok()
+    }
+
+    pub fn pop_update(&mut self) -> Option<UiUpdate> {
+        self.rx.pop().ok()
+    }
+}
+
+pub struct AudioEnd {
+    rx: rtrb::Consumer<UiEvent>,
+    tx: rtrb::Producer<UiUpdate>,
+}
+
+impl AudioEnd {
+    pub fn new(rx: rtrb::Consumer<UiEvent>, tx: rtrb::Producer<UiUpdate>) -> Self {
+        Self { rx, tx }
+    }
+
+    pub fn pop_event(&mut self) -> Option<UiEvent> {
+        self.rx.pop().ok()
+    }
+
+    // Leaking beats freeing on the audio thread when the editor stopped reading
+    pub fn release(&mut self, sample: Box<LoadedSample>) {
+        if let Err(rtrb::PushError::Full(update)) = self.tx.push(UiUpdate::Released(sample)) {
+            std::mem::forget(update);
+        }
+    }
+}
+
+pub fn create_link_pair() -> (AudioEnd, UiEnd) {
+    let (to_audio_tx, from_ui_rx) = rtrb::RingBuffer::<UiEvent>::new(128);
+    let (to_ui_tx, from_audio_rx) = rtrb::RingBuffer::<UiUpdate>::new(8);
+
+    (
+        AudioEnd::new(from_ui_rx, to_ui_tx),
+        UiEnd::new(from_audio_rx, to_audio_tx),
+    )
+}
@@ -0,0 +1,73 @@
+use std::path::Path;
+
+use crate::synth_engine::{
+    Input, StereoSample, sample_analysis::analyze_harmonic_frames, synth_module::ModuleUiBridge,
+};
+
+use super::{
+    SampleInfo, SampleResynth, SampleResynthConfig,
+    link::{LoadedSample, UiEnd, UiUpdate},
+};
+
+pub struct SampleResynthUiBridge {
+    ui_end: UiEnd,
+    config: SampleResynthConfig,
+    info: Option<SampleInfo>,
+}
+
+impl SampleResynthUiBridge {
+    pub fn try_new(resynth: &mut SampleResynth) -> Option<Self> {
+        Some(Self {
+            ui_end: resynth.ui_end.take()?,
+            config: resynth.get_config(),
+            info: resynth.sample_info(),
+        })
+    }
+
+    pub fn config(&self) -> &SampleResynthConfig {
+        &self.config
+    }
+
+    pub fn info(&self) -> Option<&SampleInfo> {
+        self.info.as_ref()
+    }
+
+    pub fn set_param(&mut self, input: Input, value: StereoSample) {
+        if !self.ui_end.set_param(input, value) {
+            return;
+        }
+
+        if input == Input::Position {
+            self.config.position = value;
+        }
+    }
+
+    /// Analysis runs on the calling thread, the frames reach the engine through the event queue.
+    pub fn load_sample(&mut self, path: &Path) -> Result<(), String> {
+        let frames = analyze_harmonic_frames(path)?;
+        let info = SampleInfo::new(&frames);
+        let sample = Box::new(LoadedSample {
+            path: Some(path.into()),
+            frames: Some(Box::new(frames)),
+        });
+
+        if !self.ui_end.set_sample(sample) {
+            return Err("Too many pending edits.".to_string());
+        }
+
+        self.config.path = Some(path.into());
+        self.info = Some(info);
+        Ok(())
+    }
+}
+
+impl ModuleUiBridge for SampleResynthUiBridge {
+    fn update(&mut self) {
+        // Dropped here on the editor thread
+        while let Some(UiUpdate::Released(_)) = self.ui_end.pop_update() {}
+    }
+
+    fn edits(&self) -> u32 {
+        self.ui_end.edits()
+    }
+}
@@ -0,0 +1,438 @@
+use std::{array, f32::consts::PI, sync::Arc};
+
+use nih_plug::util::db_to_gain_fast;
+use realfft::{RealFftPlanner, RealToComplex};
+
+mod config;
+mod link;
+mod ui_bridge;
+
+pub use config::SpectralAnalyzerConfig;
+use link::{AudioEnd, UiEnd, UiEvent, create_link_pair};
+pub use ui_bridge::SpectralAnalyzerUiBridge;
+
+use crate::{
+    synth_engine::{
+        RandomParam, Randomizer,
+        buffer::{HARMONIC_SERIES_BUFFER, VoicesLayout, new_voices_layout},
+        routing::{
+            DataType, Input, InputMeta, InputSlots, InputSource, ModuleId, NUM_CHANNELS,
+            ProcessContext, SpectralInputSlot, SpectralOutput, SpectralRouterType, VoiceEvent,
+            VoiceRouter,
+        },
+        synth_module::SynthModule,
+        types::{ComplexSample, Sample},
+    },
+    utils::pitch_to_freq,
+};
+
+pub const MAX_ANALYZER_GAIN: Sample = 24.0; // dB
+pub const MAX_SMOOTHING: Sample = 2.0; // Octaves
+
+const FFT_SIZE: usize = 1024;
+const NUM_BINS: usize = FFT_SIZE / 2 + 1;
+const HOP_SIZE: usize = 256;
+
+struct Params {
+    gain: Sample,
+    smoothing: Sample,
+}
+
+impl Params {
+    fn from_config(c: &SpectralAnalyzerConfig) -> Self {
+        Self {
+            gain: c.gain.clamp(-MAX_ANALYZER_GAIN, MAX_ANALYZER_GAIN),
+            smoothing: c.smoothing.clamp(0.0, MAX_SMOOTHING),
+        }
+    }
+}
+
+// Everything the latest output was computed from
+#[derive(Clone, Copy, PartialEq)]
+struct AppliedState {
+    revision: u32,
+    bins: usize,
+    pitch: Sample,
+    gain: Sample,
+}
+
+#[derive(Default)]
+struct VoiceState {
+    triggered: bool,
+    pitch: Sample,
+    applied: Option<AppliedState>,
+}
+
+// Spectral envelope of the latest sidechain window, shared by all voices
+struct Analysis {
+    fft: Arc<dyn RealToComplex<Sample>>,
+    window: Vec<Sample>,
+    norm: Sample,
+    history: [Vec<Sample>; NUM_CHANNELS],
+    write_pos: usize,
+    pending: usize,
+    input: Vec<Sample>,
+    spectrum: Vec<ComplexSample>,
+    scratch: Vec<ComplexSample>,
+    sums: Vec<Sample>,
+    envelopes: [Vec<Sample>; NUM_CHANNELS],
+    revision: u32,
+}
+
+impl Analysis {
+    fn new() -> Self {
+        let fft = RealFftPlanner::<Sample>::new().plan_fft_forward(FFT_SIZE);
+        let window: Vec<Sample> = (0..FFT_SIZE)
+            .map(|idx| 0.5 - 0.5 * (2.0 * PI * idx as Sample / FFT_SIZE as Sample).cos())
+            .collect();
+
+        Self {
+            input: fft.make_input_vec(),
+            spectrum: fft.make_output_vec(),
+            scratch: fft.make_scratch_vec(),
+            fft,
+            // Full scale sine in the middle of a bin reads as 1.0
+            norm: 2.0 / window.iter().sum::<Sample>(),
+            window,
+            history: array::from_fn(|_| vec![0.0; FFT_SIZE]),
+            write_pos: 0,
+            pending: 0,
+            sums: vec![0.0; NUM_BINS + 1],
+            envelopes: array::from_fn(|_| vec![0.0; NUM_BINS]),
+            revision: 0,
+        }
+    }
+
+    fn push(&mut self, sidechain: [&[Sample]; NUM_CHANNELS]) {
+        for (history, samples) in self.history.iter_mut().zip(sidechain) {
+            for (idx, sample) in samples.iter().enumerate() {
+                history[(self.write_pos + idx) % FFT_SIZE] = *sample;
+            }
+        }
+
+        let len = sidechain
+            .iter()
+            .map(|samples| samples.len())
+            .min()
+            .unwrap_or(0);
+
+        self.write_pos = (self.write_pos + len) % FFT_SIZE;
+        self.pending += len;
+    }
+
+    // A single channel analyzes the mid of the sidechain. Each bin of the envelope is the average
+    // magnitude of the bins within `smoothing` octaves around it.
+    fn analyze(&mut self, channels: usize, smoothing: Sample) {
+        let half_width = (0.5 * smoothing).exp2();
+
+        self.pending = 0;
+
+        for channel_idx in 0..channels {
+            for (idx, (input, window)) in self.input.iter_mut().zip(&self.window).enumerate() {
+                let pos = (self.write_pos + idx) % FFT_SIZE;
+                let sample = if channels == 1 {
+                    0.5 * (self.history[0][pos] + self.history[1][pos])
+                } else {
+                    self.history[channel_idx][pos]
+                };
+
+                *input = sample * window;
+            }
+
+            if self
+                .fft
+                .process_with_scratch(&mut self.input, &mut self.spectrum, &mut self.scratch)
+                .is_err()
+            {
+                continue;
+            }
+
+            for (bin_idx, bin) in self.spectrum.iter().enumerate() {
+                self.sums[bin_idx + 1] = bin.norm().mul_add(self.norm, self.sums[bin_idx]);
+            }
+
+            for (bin_idx, level) in self.envelopes[channel_idx].iter_mut().enumerate() {
+                let from = ((bin_idx as Sample / half_width) as usize).min(bin_idx);
+                let to =
+                    ((bin_idx as Sample * half_width).ceil() as usize).clamp(bin_idx, NUM_BINS - 1);
+
+                *level = (self.sums[to + 1] - self.sums[from]) / (to + 1 - from) as Sample;
+            }
+        }
+
+        self.revision = self.revision.wrapping_add(1);
+    }
+}
+
+type Router<'v, 'f, 'c> = VoiceRouter<'v, 'f, 'c, SpectralRouterType>;
+
+// Shapes the harmonic series of each voice with the spectral envelope of the host sidechain input,
+// read at the frequencies of the voice harmonics. Multiplied with another spectrum it works like a
+// vocoder.
+pub struct SpectralAnalyzer {
+    id: ModuleId,
+    bypass: bool,
+    params: Params,
+    analysis: Analysis,
+    audio_end: AudioEnd,
+    ui_end: Option<UiEnd>,
+    output_slot: usize,
+    voices: VoicesLayout<VoiceState>,
+}
+
+impl SpectralAnalyzer {
+    pub fn new(id: ModuleId) -> Self {
+        Self::from_config(&SpectralAnalyzerConfig {
+            id,
+            ..SpectralAnalyzerConfig::default()
+        })
+    }
+
+    pub fn from_config(config: &SpectralAnalyzerConfig) -> Self {
+        let (audio_end, ui_end) = create_link_pair();
+
+        Self {
+            id: config.id,
+            bypass: config.bypass,
+            params: Params::from_config(config),
+            analysis: Analysis::new(),
+            audio_end,
+            ui_end: Some(ui_end),
+            output_slot: usize::MAX,
+            voices: new_voices_layout(),
+        }
+    }
+
+    pub fn get_config(&self) -> SpectralAnalyzerConfig {
+        SpectralAnalyzerConfig {
+            id: self.id,
+            bypass: self.bypass,
+            gain: self.params.gain,
+            smoothing: self.params.smoothing,
+        }
+    }
+
+    // Only displays its input, nothing to randomize
+    pub fn randomize(
+        _config: &mut SpectralAnalyzerConfig,
+        _randomizer: &mut Randomizer,
+        _unlocked: impl Fn(RandomParam) -> bool,
+    ) {
+    }
+
+    set_mono_param!(
+        set_gain,
+        gain,
+        Sample,
+        gain.clamp(-MAX_ANALYZER_GAIN, MAX_ANALYZER_GAIN)
+    );
+    set_mono_param!(
+        set_smoothing,
+        smoothing,
+        Sample,
+        smoothing.clamp(0.0, MAX_SMOOTHING)
+    );
+
+    /// `fundamental_bins` is the frequency of the fundamental in analysis bins.
+    fn generate(
+        output: &mut [ComplexSample],
+        envelope: &[Sample],
+        fundamental_bins: Sample,
+        gain: Sample,
+    ) {
+        output[0] = ComplexSample::ZERO;
+
+        for (harmonic, (out, series)) in output
+            .iter_mut()
+            .zip(&HARMONIC_SERIES_BUFFER)
+            .enumerate()
+            .skip(1)
+        {
+            let position = harmonic as Sample * fundamental_bins;
+            let bin_idx = position as usize;
+
+            *out = if bin_idx + 1 < NUM_BINS {
+                let level = (envelope[bin_idx + 1] - envelope[bin_idx])
+                    .mul_add(position - bin_idx as Sample, envelope[bin_idx]);
+
+                *series * (level * gain)
+            } else {
+                ComplexSample::ZERO
+            };
+        }
+    }
+
+    fn process_voice(
+        &mut self,
+        output: &mut VoicesLayout<SpectralOutput>,
+        router: Router<'_, '_, '_>,
+        host_sample_rate: Sample,
+    ) {
+        let channel_idx = router.channel_idx();
+        let voice_idx = router.voice_idx();
+        let voice = &mut self.voices[channel_idx][voice_idx];
+
+        let bins = router.params().spectral_bins;
+        let applied = Some(AppliedState {
+            revision: self.analysis.revision,
+            bins,
+            pitch: voice.pitch,
+            gain: self.params.gain,
+        });
+
+        // Same analysis and pitch, downstream modules keep reading the latest spectrum
+        if !voice.triggered && voice.applied == applied {
+            return;
+        }
+
+        voice.applied = applied;
+
+        Self::generate(
+            &mut output[channel_idx][voice_idx].advance()[..bins],
+            &self.analysis.envelopes[channel_idx],
+            pitch_to_freq(voice.pitch) * FFT_SIZE as Sample / host_sample_rate,
+            db_to_gain_fast(self.params.gain),
+        );
+
+        if voice.triggered {
+            voice.triggered = false;
+            voice.applied = None;
+
+            self.process_voice(output, router, host_sample_rate);
+        }
+    }
+}
+
+impl SynthModule for SpectralAnalyzer {
+    fn id(&self) -> ModuleId {
+        self.id
+    }
+
+    fn bypass(&self) -> bool {
+        self.bypass
+    }
+
+    fn set_bypass(&mut self, bypass: bool) {
+        self.bypass = bypass;
+    }
+
+    fn inputs(&self) -> &'static [InputMeta] {
+        &[]
+    }
+
+    fn output_type(&self) -> DataType {
+        DataType::Spectral
+    }
+
+    fn output_slot(&self) -> usize {
+        self.output_slot
+    }
+
+    fn set_output_slot(&mut self, slot: usize) {
+        self.output_slot = slot;
+    }
+
+    fn set_input_slots(&mut self, _inputs: &[InputSlots], _spectral_inputs: &[SpectralInputSlot]) {}
+
+    fn update_input_source(&mut self, _input_type: Input, _src_slot: usize, _source: &InputSource) {
+    }
+
+    fn process_events(&mut self, events: &[VoiceEvent]) {
+        for channel in self.voices.iter_mut() {
+            for event in events {
+                match event {
+                    VoiceEvent::Trigger {
+                        voice_idx, pitch, ..
+                    } => {
+                        channel[*voice_idx] = VoiceState {
+                            triggered: true,
+                            pitch: *pitch,
+                            applied: None,
+                        };
+                    }
+                    VoiceEvent::Update {
+                        voice_idx, pitch, ..
+                    }
+                    | VoiceEvent::Retune { voice_idx, pitch } => {
+                        channel[*voice_idx].pitch = *pitch;
+                    }
+                    _ => (),
+                }
+            }
+        }
+    }
+
+    fn process_ui_events(&mut self) {
+        while let Some(event) = self.audio_end.pop_event() {
+            match event {
+                UiEvent::Gain(value) => self.set_gain(value),
+                UiEvent::Smoothing(value) => self.set_smoothing(value),
+            }
+        }
+    }
+
+    fn process(&mut self, ctx: &mut ProcessContext) {
+        ctx.for_spectral(self.id, self.output_slot, |router, output| {
+            let params = router.params();
+            let num_active_voices = params.active_voices.len();
+            let spectrum_channels = params.spectrum_channels;
+            let host_sample_rate = params.host_sample_rate;
+
+            self.analysis.push(params.sidechain);
+
+            if self.analysis.pending >= HOP_SIZE {
+                self.analysis
+                    .analyze(spectrum_channels, self.params.smoothing);
+            }
+
+            for channel_idx in 0..spectrum_channels {
+                for seq_idx in 0..num_active_voices {
+                    let voice_idx = router.params().active_voices[seq_idx];
+
+                    self.process_voice(
+                        output,
+                        router.for_voice(channel_idx, voice_idx, seq_idx),
+                        host_sample_rate,
+                    );
+                }
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::synth_engine::buffer::zero_spectral_buffer;
+
+    #[test]
+    fn envelope_follows_sidechain_spectrum() {
+        const BIN: Sample = 10.0;
+
+        let mut analysis = Analysis::new();
+        let sine: Vec<Sample> = (0..FFT_SIZE)
+            .map(|idx| (2.0 * PI * BIN * idx as Sample / FFT_SIZE as Sample).sin())
+            .collect();
+
+        analysis.push([&sine, &sine]);
+        assert_eq!(analysis.pending, FFT_SIZE);
+
+        analysis.analyze(NUM_CHANNELS, 0.0);
+        assert_eq!(analysis.pending, 0);
+
+        let mut output = zero_spectral_buffer();
+
+        // The fundamental sits on the sine, the other harmonics miss it
+        SpectralAnalyzer::generate(&mut output, &analysis.envelopes[0], BIN, 1.0);
+        assert!((output[1] - HARMONIC_SERIES_BUFFER[1]).norm() < 1e-3);
+        assert!(output[3].norm() < 1e-3 * HARMONIC_SERIES_BUFFER[3].norm());
+        assert_eq!(output[52], ComplexSample::ZERO);
+
+        // Smoothing spreads the sine over the neighbouring harmonics of a lower voice
+        analysis.analyze(NUM_CHANNELS, 1.0);
+        SpectralAnalyzer::generate(&mut output, &analysis.envelopes[0], 0.5 * BIN, 1.0);
+        assert!(output[2].norm() > output[3].norm());
+        assert!(output[3].norm() > 1e-3 * HARMONIC_SERIES_BUFFER[3].norm());
+        assert!(output[2].norm() < HARMONIC_SERIES_BUFFER[2].norm());
+    }
+}
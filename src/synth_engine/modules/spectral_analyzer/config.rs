@@ -0,0 +1,23 @@
+use serde::{Deserialize, Serialize};
+
+use crate::synth_engine::{ModuleId, Sample};
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SpectralAnalyzerConfig {
+    pub id: ModuleId,
+    #[serde(default)]
+    pub bypass: bool,
+    pub gain: Sample,      // dB
+    pub smoothing: Sample, // Octaves each band averages over
+}
+
+impl Default for SpectralAnalyzerConfig {
+    fn default() -> Self {
+        Self {
+            id: -1,
+            bypass: false,
+            gain: 0.0,
+            smoothing: 1.0 / 3.0,
+        }
+    }
+}
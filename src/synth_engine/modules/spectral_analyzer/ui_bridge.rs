@@ -0,0 +1,42 @@
+use crate::synth_engine::{Sample, synth_module::ModuleUiBridge};
+
+use super::link::UiEnd;
+use super::{SpectralAnalyzer, SpectralAnalyzerConfig};
+
+pub struct SpectralAnalyzerUiBridge {
+    ui_end: UiEnd,
+    config: SpectralAnalyzerConfig,
+}
+
+impl SpectralAnalyzerUiBridge {
+    pub fn try_new(analyzer: &mut SpectralAnalyzer) -> Option<Self> {
+        Some(Self {
+            ui_end: analyzer.ui_end.take()?,
+            config: analyzer.get_config(),
+        })
+    }
+
+    pub fn config(&self) -> &SpectralAnalyzerConfig {
+        &self.config
+    }
+
+    pub fn set_gain(&mut self, value: Sample) {
+        if self.ui_end.set_gain(value) {
+            self.config.gain = value;
+        }
+    }
+
+    pub fn set_smoothing(&mut self, value: Sample) {
+        if self.ui_end.set_smoothing(value) {
+            self.config.smoothing = value;
+        }
+    }
+}
+
+impl ModuleUiBridge for SpectralAnalyzerUiBridge {
+    fn update(&mut self) {}
+
+    fn edits(&self) -> u32 {
+        self.ui_end.edits()
+    }
+}
@@ -6,42 +6,105 @@ mod config;
 mod link;
 mod ui_bridge;
 
-pub use config::SpectralBlendConfig;
+pub use config::{BlendMode, SpectralBlendConfig};
 use link::{AudioEnd, UiEnd, UiEvent, create_link_pair};
 pub use ui_bridge::SpectralBlendUiBridge;
 
 use crate::synth_engine::{
-    StereoSample,
-    buffer::{VoicesLayout, new_voices_layout},
+    RandomParam, Randomizer, StereoSample,
+    buffer::{SPECTRAL_BUFFER_SIZE, VoicesLayout, new_voices_layout},
     routing::{
-        DataType, Input, InputMeta, InputSlots, ModuleId, NUM_CHANNELS, ProcessContext,
-        SpectralInputSlot, SpectralOutput, SpectralRouterType, VoiceEvent, VoiceRouter,
+        DataType, Input, InputMeta, InputSlots, InputSource, ModuleId, NUM_CHANNELS,
+        ProcessContext, SpectralInputSlot, SpectralOutput, SpectralRouterType, VoiceEvent,
+        VoiceRouter,
     },
     synth_module::SynthModule,
-    types::Sample,
+    types::{ComplexSample, Sample},
 };
 
+pub const MAX_SPLIT_HARMONIC: Sample = (SPECTRAL_BUFFER_SIZE - 1) as Sample;
+pub const MAX_CROSSOVER_WIDTH: Sample = 128.0; // Harmonics
+
+// -120 dB, keeps missing harmonics from collapsing the whole log-magnitude blend to zero
+const MIN_MAGNITUDE: Sample = 1e-6;
+
+#[derive(Clone, Copy, PartialEq)]
+struct Params {
+    mode: BlendMode,
+    split: bool,
+}
+
+impl Params {
+    fn from_config(c: &config::SpectralBlendConfig) -> Self {
+        Self {
+            mode: c.mode,
+            split: c.split,
+        }
+    }
+}
+
 struct ChannelParams {
     blend: Sample,
+    split_harmonic: Sample,
+    crossover_width: Sample,
 }
 
 impl ChannelParams {
     fn from_config(c: &config::SpectralBlendConfig, channel_idx: usize) -> Self {
         Self {
             blend: c.blend[channel_idx],
+            split_harmonic: c.split_harmonic[channel_idx],
+            crossover_width: c.crossover_width[channel_idx],
         }
     }
 }
 
+// Harmonics below the split come from the first input, the ones above it from the second
+#[derive(Clone, Copy, PartialEq)]
+struct Crossover {
+    harmonic: Sample,
+    width: Sample,
+}
+
+impl Crossover {
+    // Smoothstep over `width` harmonics centered at the split, a hard switch without width
+    #[inline(always)]
+    fn blend(&self, harmonic: usize) -> Sample {
+        let harmonic = harmonic as Sample;
+
+        if self.width <= 0.0 {
+            return if harmonic < self.harmonic { 0.0 } else { 1.0 };
+        }
+
+        let x = ((harmonic - self.harmonic) / self.width + 0.5).clamp(0.0, 1.0);
+
+        x * x * (3.0 - 2.0 * x)
+    }
+}
+
+// Everything the latest output was computed from
+#[derive(Clone, Copy, PartialEq)]
+struct AppliedState {
+    from_revision: u32,
+    to_revision: u32,
+    bins: usize,
+    params: Params,
+    blend: Sample,
+    crossover: Crossover,
+}
+
 #[derive(Default)]
 struct VoiceState {
     triggered: bool,
+    applied: Option<AppliedState>,
 }
 
 pub struct Inputs {
     spectrum: Option<usize>,
     spectrum_to: Option<usize>,
     blend: InputSlots,
+    split_harmonic: InputSlots,
+    crossover_width: InputSlots,
 }
 
 impl Default for Inputs {
@@ -50,6 +113,8 @@ impl Default for Inputs {
             spectrum: None,
             spectrum_to: None,
             blend: InputSlots::empty(Input::Blend),
+            split_harmonic: InputSlots::empty(Input::SplitHarmonic),
+            crossover_width: InputSlots::empty(Input::CrossoverWidth),
         }
     }
 }
@@ -59,8 +124,11 @@ impl Inputs {
         let mut result = Self::default();
 
         for input in inputs {
-            if input.input_type == Input::Blend {
-                result.blend = input.clone();
+            match input.input_type {
+                Input::Blend => result.blend = input.clone(),
+                Input::SplitHarmonic => result.split_harmonic = input.clone(),
+                Input::CrossoverWidth => result.crossover_width = input.clone(),
+                _ => (),
             }
         }
 
@@ -75,9 +143,12 @@ impl Inputs {
         result
     }
 
-    fn update_amount(&mut self, input_type: Input, src_slot: usize, amount: StereoSample) {
-        if input_type == Input::Blend {
-            self.blend.update_amount(src_slot, amount);
+    fn update_source(&mut self, input_type: Input, src_slot: usize, source: &InputSource) {
+        match input_type {
+            Input::Blend => self.blend.update_source(src_slot, source),
+            Input::SplitHarmonic => self.split_harmonic.update_source(src_slot, source),
+            Input::CrossoverWidth => self.crossover_width.update_source(src_slot, source),
+            _ => (),
         }
     }
 }
@@ -86,6 +157,8 @@ type Router<'v, 'f, 'c> = VoiceRouter<'v, 'f, 'c, SpectralRouterType>;
 
 pub struct SpectralBlend {
     id: ModuleId,
+    bypass: bool,
+    params: Params,
     channel_params: [ChannelParams; NUM_CHANNELS],
     audio_end: AudioEnd,
     ui_end: Option<UiEnd>,
@@ -107,6 +180,8 @@ impl SpectralBlend {
 
         Self {
             id: config.id,
+            bypass: config.bypass,
+            params: Params::from_config(config),
             channel_params: array::from_fn(|channel_idx| {
                 ChannelParams::from_config(config, channel_idx)
             }),
@@ -121,11 +196,58 @@ impl SpectralBlend {
     pub fn get_config(&self) -> SpectralBlendConfig {
         SpectralBlendConfig {
             id: self.id,
+            bypass: self.bypass,
+            mode: self.params.mode,
             blend: get_stereo_param!(self, blend),
+            split: self.params.split,
+            split_harmonic: get_stereo_param!(self, split_harmonic),
+            crossover_width: get_stereo_param!(self, crossover_width),
         }
     }
 
+    pub fn randomize(
+        config: &mut SpectralBlendConfig,
+        randomizer: &mut Randomizer,
+        unlocked: impl Fn(RandomParam) -> bool,
+    ) {
+        if unlocked(RandomParam::Blend) {
+            config.blend = randomizer.stereo(config.blend, 0.0, 1.0);
+        }
+    }
+
+    set_mono_param!(set_mode, mode, BlendMode);
+    set_mono_param!(set_split, split, bool);
     set_stereo_param!(set_blend, blend, blend.clamp(0.0, 1.0));
+    set_stereo_param!(
+        set_split_harmonic,
+        split_harmonic,
+        split_harmonic.clamp(0.0, MAX_SPLIT_HARMONIC)
+    );
+    set_stereo_param!(
+        set_crossover_width,
+        crossover_width,
+        crossover_width.clamp(0.0, MAX_CROSSOVER_WIDTH)
+    );
+
+    #[inline(always)]
+    fn blend_log_magnitude(from: ComplexSample, to: ComplexSample, blend: Sample) -> ComplexSample {
+        let linear = from + (to - from) * blend;
+        let linear_norm = linear.norm();
+
+        if linear_norm < MIN_MAGNITUDE {
+            return ComplexSample::ZERO;
+        }
+
+        let from_log = from.norm().max(MIN_MAGNITUDE).ln();
+        let to_log = to.norm().max(MIN_MAGNITUDE).ln();
+        let magnitude = (to_log - from_log).mul_add(blend, from_log).exp();
+
+        if magnitude <= MIN_MAGNITUDE {
+            ComplexSample::ZERO
+        } else {
+            linear * (magnitude / linear_norm)
+        }
+    }
 
     fn process_voice(
         &mut self,
@@ -137,20 +259,78 @@ impl SpectralBlend {
         let inputs = &self.inputs;
         let channel = &self.channel_params[channel_idx];
         let voice = &mut self.voices[channel_idx][voice_idx];
-        let voice_output = output[channel_idx][voice_idx].advance();
 
         let blend = router
             .scalar_param(&inputs.blend, channel.blend, voice.triggered)
             .clamp(0.0, 1.0);
+        let crossover = Crossover {
+            harmonic: router
+                .scalar_param(
+                    &inputs.split_harmonic,
+                    channel.split_harmonic,
+                    voice.triggered,
+                )
+                .clamp(0.0, MAX_SPLIT_HARMONIC),
+            width: router
+                .scalar_param(
+                    &inputs.crossover_width,
+                    channel.crossover_width,
+                    voice.triggered,
+                )
+                .clamp(0.0, MAX_CROSSOVER_WIDTH),
+        };
+
+        let bins = router.params().spectral_bins;
+        let applied = Some(AppliedState {
+            from_revision: router.spectral_revision(inputs.spectrum),
+            to_revision: router.spectral_revision(inputs.spectrum_to),
+            bins,
+            params: self.params,
+            blend,
+            crossover,
+        });
+
+        // Unchanged inputs and blend, downstream modules keep reading the latest spectrum
+        if !voice.triggered && voice.applied == applied {
+            return;
+        }
+
+        voice.applied = applied;
+
+        let voice_output = &mut output[channel_idx][voice_idx].advance()[..bins];
         let spectrum_from = router.spectral(inputs.spectrum, voice.triggered);
         let spectrum_to = router.spectral(inputs.spectrum_to, voice.triggered);
 
-        for (out, from, to) in izip!(voice_output, spectrum_from, spectrum_to) {
-            *out = from + (to - from) * blend;
+        match (self.params.mode, self.params.split) {
+            (BlendMode::Linear, false) => {
+                for (out, from, to) in izip!(voice_output, spectrum_from, spectrum_to) {
+                    *out = from + (to - from) * blend;
+                }
+            }
+            (BlendMode::LogMagnitude, false) => {
+                for (out, from, to) in izip!(voice_output, spectrum_from, spectrum_to) {
+                    *out = Self::blend_log_magnitude(*from, *to, blend);
+                }
+            }
+            (BlendMode::Linear, true) => {
+                for (harmonic, (out, from, to)) in
+                    izip!(voice_output, spectrum_from, spectrum_to).enumerate()
+                {
+                    *out = from + (to - from) * crossover.blend(harmonic);
+                }
+            }
+            (BlendMode::LogMagnitude, true) => {
+                for (harmonic, (out, from, to)) in
+                    izip!(voice_output, spectrum_from, spectrum_to).enumerate()
+                {
+                    *out = Self::blend_log_magnitude(*from, *to, crossover.blend(harmonic));
+                }
+            }
         }
 
         if voice.triggered {
             voice.triggered = false;
+            voice.applied = None;
 
             self.process_voice(output, router);
         }
@@ -162,11 +342,21 @@ impl SynthModule for SpectralBlend {
         self.id
     }
 
+    fn bypass(&self) -> bool {
+        self.bypass
+    }
+
+    fn set_bypass(&mut self, bypass: bool) {
+        self.bypass = bypass;
+    }
+
     fn inputs(&self) -> &'static [InputMeta] {
         static INPUTS: &[InputMeta] = &[
             InputMeta::spectral(Input::Spectrum),
             InputMeta::spectral(Input::SpectrumTo),
             InputMeta::control(Input::Blend),
+            InputMeta::control(Input::SplitHarmonic),
+            InputMeta::control(Input::CrossoverWidth),
         ];
 
         INPUTS
@@ -186,10 +376,14 @@ impl SynthModule for SpectralBlend {
 
     fn set_input_slots(&mut self, inputs: &[InputSlots], spectral_inputs: &[SpectralInputSlot]) {
         self.inputs = Inputs::from_slots(inputs, spectral_inputs);
+
+        for voice in self.voices.iter_mut().flatten() {
+            voice.applied = None;
+        }
     }
 
-    fn update_input_amount(&mut self, input_type: Input, src_slot: usize, amount: StereoSample) {
-        self.inputs.update_amount(input_type, src_slot, amount);
+    fn update_input_source(&mut self, input_type: Input, src_slot: usize, source: &InputSource) {
+        self.inputs.update_source(input_type, src_slot, source);
     }
 
     fn process_events(&mut self, events: &[VoiceEvent]) {
@@ -202,14 +396,21 @@ impl SynthModule for SpectralBlend {
         }
     }
 
+    fn set_input_param(&mut self, input: Input, value: StereoSample) {
+        match input {
+            Input::Blend => self.set_blend(value),
+            Input::SplitHarmonic => self.set_split_harmonic(value),
+            Input::CrossoverWidth => self.set_crossover_width(value),
+            _ => (),
+        }
+    }
+
     fn process_ui_events(&mut self) {
         while let Some(event) = self.audio_end.pop_event() {
-            if let UiEvent::InputParam {
-                input: Input::Blend,
-                value,
-            } = event
-            {
-                self.set_blend(value);
+            match event {
+                UiEvent::InputParam { input, value } => self.set_input_param(input, value),
+                UiEvent::Mode(mode) => self.set_mode(mode),
+                UiEvent::Split(split) => self.set_split(split),
             }
         }
     }
@@ -229,3 +430,29 @@ impl SynthModule for SpectralBlend {
         });
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn crossover_takes_lows_from_first_input_and_highs_from_second() {
+        let hard = Crossover {
+            harmonic: 8.0,
+            width: 0.0,
+        };
+
+        assert_eq!(hard.blend(7), 0.0);
+        assert_eq!(hard.blend(8), 1.0);
+
+        let smooth = Crossover {
+            harmonic: 8.0,
+            width: 4.0,
+        };
+
+        assert_eq!(smooth.blend(6), 0.0);
+        assert_eq!(smooth.blend(8), 0.5);
+        assert_eq!(smooth.blend(10), 1.0);
+        assert!(smooth.blend(7) > 0.0 && smooth.blend(7) < 0.5);
+    }
+}
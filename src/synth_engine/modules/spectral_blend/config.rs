@@ -2,17 +2,47 @@ use serde::{Deserialize, Serialize};
 
 use crate::synth_engine::{ModuleId, StereoSample};
 
+#[derive(Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum BlendMode {
+    #[default]
+    Linear,
+    LogMagnitude,
+}
+
+fn default_split_harmonic() -> StereoSample {
+    16.0.into()
+}
+
+fn default_crossover_width() -> StereoSample {
+    4.0.into()
+}
+
 #[derive(Clone, Serialize, Deserialize)]
 pub struct SpectralBlendConfig {
     pub id: ModuleId,
+    #[serde(default)]
+    pub bypass: bool,
+    #[serde(default)]
+    pub mode: BlendMode,
     pub blend: StereoSample,
+    #[serde(default)]
+    pub split: bool,
+    #[serde(default = "default_split_harmonic")]
+    pub split_harmonic: StereoSample,
+    #[serde(default = "default_crossover_width")]
+    pub crossover_width: StereoSample,
 }
 
 impl Default for SpectralBlendConfig {
     fn default() -> Self {
         Self {
             id: -1,
+            bypass: false,
+            mode: BlendMode::default(),
             blend: 0.0.into(),
+            split: false,
+            split_harmonic: default_split_harmonic(),
+            crossover_width: default_crossover_width(),
         }
     }
 }
@@ -1,21 +1,39 @@
-use crate::synth_engine::{Input, StereoSample};
+use crate::synth_engine::{
+    Input, StereoSample, edit_sender::EditSender, spectral_blend::BlendMode,
+};
 
 pub enum UiEvent {
     InputParam { input: Input, value: StereoSample },
+    Mode(BlendMode),
+    Split(bool),
 }
 
 pub struct UiEnd {
-    tx: rtrb::Producer<UiEvent>,
+    tx: EditSender<UiEvent>,
 }
 
 impl UiEnd {
     pub fn new(tx: rtrb::Producer<UiEvent>) -> Self {
-        Self { tx }
+        Self {
+            tx: EditSender::new(tx),
+        }
+    }
+
+    pub fn edits(&self) -> u32 {
+        self.tx.sent()
     }
 
     pub fn set_param(&mut self, input: Input, value: StereoSample) -> bool {
         self.tx.push(UiEvent::InputParam { input, value }).is_ok()
     }
+
+    pub fn set_mode(&mut self, mode: BlendMode) -> bool {
+        self.tx.push(UiEvent::Mode(mode)).is_ok()
+    }
+
+    pub fn set_split(&mut self, split: bool) -> bool {
+        self.tx.push(UiEvent::Split(split)).is_ok()
+    }
 }
 
 pub struct AudioEnd {
@@ -1,7 +1,7 @@
 use crate::synth_engine::{Input, StereoSample, synth_module::ModuleUiBridge};
 
 use super::link::UiEnd;
-use super::{SpectralBlend, SpectralBlendConfig};
+use super::{BlendMode, SpectralBlend, SpectralBlendConfig};
 
 pub struct SpectralBlendUiBridge {
     ui_end: UiEnd,
@@ -21,12 +21,35 @@ impl SpectralBlendUiBridge {
     }
 
     pub fn set_param(&mut self, input: Input, value: StereoSample) {
-        if self.ui_end.set_param(input, value) && input == Input::Blend {
-            self.config.blend = value;
+        if !self.ui_end.set_param(input, value) {
+            return;
+        }
+
+        match input {
+            Input::Blend => self.config.blend = value,
+            Input::SplitHarmonic => self.config.split_harmonic = value,
+            Input::CrossoverWidth => self.config.crossover_width = value,
+            _ => (),
+        }
+    }
+
+    pub fn set_mode(&mut self, mode: BlendMode) {
+        if self.ui_end.set_mode(mode) {
+            self.config.mode = mode;
+        }
+    }
+
+    pub fn set_split(&mut self, split: bool) {
+        if self.ui_end.set_split(split) {
+            self.config.split = split;
         }
     }
 }
 
 impl ModuleUiBridge for SpectralBlendUiBridge {
     fn update(&mut self) {}
+
+    fn edits(&self) -> u32 {
+        self.ui_end.edits()
+    }
 }
@@ -0,0 +1,378 @@
+use std::array;
+
+use nih_plug::util::{db_to_gain_fast, gain_to_db_fast};
+
+mod config;
+mod link;
+mod ui_bridge;
+
+pub use config::SpectralDynamicsConfig;
+use link::{AudioEnd, UiEnd, UiEvent, create_link_pair};
+pub use ui_bridge::SpectralDynamicsUiBridge;
+
+use crate::synth_engine::{
+    RandomParam, Randomizer, StereoSample,
+    buffer::{SpectralBuffer, VoicesLayout, new_voices_layout},
+    routing::{
+        DataType, Input, InputMeta, InputSlots, InputSource, ModuleId, NUM_CHANNELS,
+        ProcessContext, SpectralInputSlot, SpectralOutput, SpectralRouterType, VoiceEvent,
+        VoiceRouter,
+    },
+    synth_module::SynthModule,
+    types::Sample,
+};
+
+const MIN_THRESHOLD: Sample = -60.0;
+const MAX_RATIO: Sample = 20.0;
+const MAX_MAKEUP: Sample = 24.0;
+const MIN_GAIN_DB: Sample = -120.0;
+// Below this the bin is treated as silent and left alone
+const SILENCE: Sample = 1e-6;
+
+struct ChannelParams {
+    threshold: Sample,
+    ratio: Sample,
+    makeup: Sample,
+}
+
+impl ChannelParams {
+    fn from_config(c: &SpectralDynamicsConfig, channel_idx: usize) -> Self {
+        Self {
+            threshold: c.threshold[channel_idx],
+            ratio: c.ratio[channel_idx],
+            makeup: c.makeup[channel_idx],
+        }
+    }
+}
+
+// Everything the latest output was computed from
+#[derive(Clone, Copy, PartialEq)]
+struct AppliedState {
+    spectrum_revision: u32,
+    bins: usize,
+    threshold: Sample,
+    ratio: Sample,
+    makeup: Sample,
+}
+
+#[derive(Default)]
+struct VoiceState {
+    triggered: bool,
+    applied: Option<AppliedState>,
+}
+
+pub struct Inputs {
+    spectrum: Option<usize>,
+    threshold: InputSlots,
+    ratio: InputSlots,
+    makeup: InputSlots,
+}
+
+impl Default for Inputs {
+    fn default() -> Self {
+        Self {
+            spectrum: None,
+            threshold: InputSlots::empty(Input::Threshold),
+            ratio: InputSlots::empty(Input::Ratio),
+            makeup: InputSlots::empty(Input::Makeup),
+        }
+    }
+}
+
+impl Inputs {
+    fn from_slots(inputs: &[InputSlots], spectral_inputs: &[SpectralInputSlot]) -> Self {
+        let mut result = Self::default();
+
+        for input in inputs {
+            match input.input_type {
+                Input::Threshold => result.threshold = input.clone(),
+                Input::Ratio => result.ratio = input.clone(),
+                Input::Makeup => result.makeup = input.clone(),
+                _ => (),
+            }
+        }
+
+        for input in spectral_inputs {
+            if matches!(input.input_type, Input::Spectrum) {
+                result.spectrum = Some(input.slot);
+            }
+        }
+
+        result
+    }
+
+    fn update_source(&mut self, input_type: Input, src_slot: usize, source: &InputSource) {
+        match input_type {
+            Input::Threshold => self.threshold.update_source(src_slot, source),
+            Input::Ratio => self.ratio.update_source(src_slot, source),
+            Input::Makeup => self.makeup.update_source(src_slot, source),
+            _ => (),
+        }
+    }
+}
+
+type Router<'v, 'f, 'c> = VoiceRouter<'v, 'f, 'c, SpectralRouterType>;
+
+// Per-harmonic dynamics: harmonics above the threshold are compressed by the ratio,
+// the ones below it are expanded downwards by the same ratio.
+pub struct SpectralDynamics {
+    id: ModuleId,
+    bypass: bool,
+    channel_params: [ChannelParams; NUM_CHANNELS],
+    audio_end: AudioEnd,
+    ui_end: Option<UiEnd>,
+    inputs: Inputs,
+    output_slot: usize,
+    voices: VoicesLayout<VoiceState>,
+}
+
+impl SpectralDynamics {
+    pub fn new(id: ModuleId) -> Self {
+        Self::from_config(&SpectralDynamicsConfig {
+            id,
+            ..SpectralDynamicsConfig::default()
+        })
+    }
+
+    pub fn from_config(config: &SpectralDynamicsConfig) -> Self {
+        let (audio_end, ui_end) = create_link_pair();
+
+        Self {
+            id: config.id,
+            bypass: config.bypass,
+            channel_params: array::from_fn(|channel_idx| {
+                ChannelParams::from_config(config, channel_idx)
+            }),
+            audio_end,
+            ui_end: Some(ui_end),
+            inputs: Inputs::default(),
+            output_slot: usize::MAX,
+            voices: new_voices_layout(),
+        }
+    }
+
+    pub fn get_config(&self) -> SpectralDynamicsConfig {
+        SpectralDynamicsConfig {
+            id: self.id,
+            bypass: self.bypass,
+            threshold: get_stereo_param!(self, threshold),
+            ratio: get_stereo_param!(self, ratio),
+            makeup: get_stereo_param!(self, makeup),
+        }
+    }
+
+    pub fn randomize(
+        config: &mut SpectralDynamicsConfig,
+        randomizer: &mut Randomizer,
+        unlocked: impl Fn(RandomParam) -> bool,
+    ) {
+        if unlocked(RandomParam::Threshold) {
+            config.threshold = randomizer.stereo(config.threshold, MIN_THRESHOLD, 0.0);
+        }
+        if unlocked(RandomParam::Ratio) {
+            config.ratio = randomizer.log(config.ratio, 1.0, MAX_RATIO);
+        }
+    }
+
+    set_stereo_param!(set_threshold, threshold, Self::clamp_threshold(*threshold));
+    set_stereo_param!(set_ratio, ratio, Self::clamp_ratio(*ratio));
+    set_stereo_param!(set_makeup, makeup, Self::clamp_makeup(*makeup));
+
+    fn clamp_threshold(threshold: Sample) -> Sample {
+        threshold.clamp(MIN_THRESHOLD, 0.0)
+    }
+
+    fn clamp_ratio(ratio: Sample) -> Sample {
+        ratio.clamp(1.0, MAX_RATIO)
+    }
+
+    fn clamp_makeup(makeup: Sample) -> Sample {
+        makeup.clamp(0.0, MAX_MAKEUP)
+    }
+
+    // Gain change in dB for a harmonic at the given level
+    fn gain_db(level: Sample, threshold: Sample, ratio: Sample) -> Sample {
+        let over = level - threshold;
+
+        if over > 0.0 {
+            over * (1.0 / ratio - 1.0)
+        } else {
+            over * (ratio - 1.0)
+        }
+    }
+
+    fn apply_dynamics(output: &mut SpectralBuffer, input: &SpectralBuffer, state: &AppliedState) {
+        output[0] = input[0];
+
+        for (out, input) in output[..state.bins].iter_mut().zip(input).skip(1) {
+            let magnitude = input.norm();
+
+            if magnitude < SILENCE {
+                *out = *input;
+                continue;
+            }
+
+            let db = Self::gain_db(gain_to_db_fast(magnitude), state.threshold, state.ratio)
+                + state.makeup;
+
+            *out = *input * db_to_gain_fast(db.clamp(MIN_GAIN_DB, MAX_MAKEUP));
+        }
+    }
+
+    fn process_voice(
+        &mut self,
+        output: &mut VoicesLayout<SpectralOutput>,
+        mut router: Router<'_, '_, '_>,
+    ) {
+        let channel_idx = router.channel_idx();
+        let voice_idx = router.voice_idx();
+        let inputs = &self.inputs;
+        let channel = &self.channel_params[channel_idx];
+        let voice = &mut self.voices[channel_idx][voice_idx];
+
+        let threshold = Self::clamp_threshold(router.scalar_param(
+            &inputs.threshold,
+            channel.threshold,
+            voice.triggered,
+        ));
+        let ratio =
+            Self::clamp_ratio(router.scalar_param(&inputs.ratio, channel.ratio, voice.triggered));
+        let makeup = Self::clamp_makeup(router.scalar_param(
+            &inputs.makeup,
+            channel.makeup,
+            voice.triggered,
+        ));
+
+        let state = AppliedState {
+            spectrum_revision: router.spectral_revision(inputs.spectrum),
+            bins: router.params().spectral_bins,
+            threshold,
+            ratio,
+            makeup,
+        };
+
+        // Unchanged input and parameters, downstream modules keep reading the latest spectrum
+        if !voice.triggered && voice.applied == Some(state) {
+            return;
+        }
+
+        voice.applied = Some(state);
+
+        let voice_output = output[channel_idx][voice_idx].advance();
+        let input = router.spectral(inputs.spectrum, voice.triggered);
+
+        Self::apply_dynamics(voice_output, input, &state);
+
+        if voice.triggered {
+            voice.triggered = false;
+            voice.applied = None;
+
+            self.process_voice(output, router);
+        }
+    }
+}
+
+impl SynthModule for SpectralDynamics {
+    fn id(&self) -> ModuleId {
+        self.id
+    }
+
+    fn bypass(&self) -> bool {
+        self.bypass
+    }
+
+    fn set_bypass(&mut self, bypass: bool) {
+        self.bypass = bypass;
+    }
+
+    fn inputs(&self) -> &'static [InputMeta] {
+        static INPUTS: &[InputMeta] = &[
+            InputMeta::spectral(Input::Spectrum),
+            InputMeta::control(Input::Threshold),
+            InputMeta::control(Input::Ratio),
+            InputMeta::control(Input::Makeup),
+        ];
+
+        INPUTS
+    }
+
+    fn output_type(&self) -> DataType {
+        DataType::Spectral
+    }
+
+    fn output_slot(&self) -> usize {
+        self.output_slot
+    }
+
+    fn set_output_slot(&mut self, slot: usize) {
+        self.output_slot = slot;
+    }
+
+    fn set_input_slots(&mut self, inputs: &[InputSlots], spectral_inputs: &[SpectralInputSlot]) {
+        self.inputs = Inputs::from_slots(inputs, spectral_inputs);
+
+        for voice in self.voices.iter_mut().flatten() {
+            voice.applied = None;
+        }
+    }
+
+    fn process_events(&mut self, events: &[VoiceEvent]) {
+        for channel in self.voices.iter_mut() {
+            for event in events {
+                if let VoiceEvent::Trigger { voice_idx, .. } = event {
+                    channel[*voice_idx].triggered = true;
+                }
+            }
+        }
+    }
+
+    fn update_input_source(&mut self, input_type: Input, src_slot: usize, source: &InputSource) {
+        self.inputs.update_source(input_type, src_slot, source);
+    }
+
+    fn set_input_param(&mut self, input: Input, value: StereoSample) {
+        match input {
+            Input::Threshold => self.set_threshold(value),
+            Input::Ratio => self.set_ratio(value),
+            Input::Makeup => self.set_makeup(value),
+            _ => (),
+        }
+    }
+
+    fn process_ui_events(&mut self) {
+        while let Some(event) = self.audio_end.pop_event() {
+            match event {
+                UiEvent::InputParam { input, value } => self.set_input_param(input, value),
+            }
+        }
+    }
+
+    fn process(&mut self, ctx: &mut ProcessContext) {
+        ctx.for_spectral(self.id, self.output_slot, |router, output| {
+            let num_active_voices = router.params().active_voices.len();
+            let spectrum_channels = router.params().spectrum_channels;
+
+            for channel_idx in 0..spectrum_channels {
+                for seq_idx in 0..num_active_voices {
+                    let voice_idx = router.params().active_voices[seq_idx];
+
+                    self.process_voice(output, router.for_voice(channel_idx, voice_idx, seq_idx));
+                }
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn loud_harmonics_are_compressed_and_quiet_ones_expanded() {
+        // 10 dB over a -20 dB threshold at 2:1 comes out 5 dB over it
+        assert!((SpectralDynamics::gain_db(-10.0, -20.0, 2.0) + 5.0).abs() < 1e-5);
+        // 10 dB under it is pushed another 10 dB down
+        assert!((SpectralDynamics::gain_db(-30.0, -20.0, 2.0) + 10.0).abs() < 1e-5);
+        assert_eq!(SpectralDynamics::gain_db(-30.0, -20.0, 1.0), 0.0);
+    }
+}
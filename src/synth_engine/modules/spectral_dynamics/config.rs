@@ -0,0 +1,25 @@
+use serde::{Deserialize, Serialize};
+
+use crate::synth_engine::{ModuleId, StereoSample};
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SpectralDynamicsConfig {
+    pub id: ModuleId,
+    #[serde(default)]
+    pub bypass: bool,
+    pub threshold: StereoSample,
+    pub ratio: StereoSample,
+    pub makeup: StereoSample,
+}
+
+impl Default for SpectralDynamicsConfig {
+    fn default() -> Self {
+        Self {
+            id: -1,
+            bypass: false,
+            threshold: StereoSample::splat(-20.0),
+            ratio: 2.0.into(),
+            makeup: 0.0.into(),
+        }
+    }
+}
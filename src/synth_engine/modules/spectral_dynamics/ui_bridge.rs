@@ -0,0 +1,43 @@
+use crate::synth_engine::{Input, StereoSample, synth_module::ModuleUiBridge};
+
+use super::link::UiEnd;
+use super::{SpectralDynamics, SpectralDynamicsConfig};
+
+pub struct SpectralDynamicsUiBridge {
+    ui_end: UiEnd,
+    config: SpectralDynamicsConfig,
+}
+
+impl SpectralDynamicsUiBridge {
+    pub fn try_new(dynamics: &mut SpectralDynamics) -> Option<Self> {
+        Some(Self {
+            ui_end: dynamics.ui_end.take()?,
+            config: dynamics.get_config(),
+        })
+    }
+
+    pub fn config(&self) -> &SpectralDynamicsConfig {
+        &self.config
+    }
+
+    pub fn set_param(&mut self, input: Input, value: StereoSample) {
+        if !self.ui_end.set_param(input, value) {
+            return;
+        }
+
+        match input {
+            Input::Threshold => self.config.threshold = value,
+            Input::Ratio => self.config.ratio = value,
+            Input::Makeup => self.config.makeup = value,
+            _ => (),
+        }
+    }
+}
+
+impl ModuleUiBridge for SpectralDynamicsUiBridge {
+    fn update(&mut self) {}
+
+    fn edits(&self) -> u32 {
+        self.ui_end.edits()
+    }
+}
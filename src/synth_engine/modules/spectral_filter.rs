@@ -11,22 +11,33 @@ pub use config::{SpectralFilterConfig, SpectralFilterType};
 use link::{AudioEnd, UiEnd, UiEvent, create_link_pair};
 pub use ui_bridge::SpectralFilterUiBridge;
 
-use crate::synth_engine::{
-    StereoSample,
-    biquad_filter::BiquadFilter,
-    buffer::{SpectralBuffer, VoicesLayout, new_voices_layout},
-    routing::{
-        DataType, Input, InputMeta, InputSlots, ModuleId, NUM_CHANNELS, ProcessContext,
-        SpectralInputSlot, SpectralOutput, SpectralRouterType, VoiceEvent, VoiceRouter,
+use crate::{
+    synth_engine::{
+        RandomParam, Randomizer, StereoSample,
+        biquad_filter::BiquadFilter,
+        buffer::{VoicesLayout, new_voices_layout},
+        routing::{
+            DataType, Input, InputMeta, InputSlots, InputSource, ModuleId, NUM_CHANNELS,
+            ProcessContext, SpectralInputSlot, SpectralOutput, SpectralRouterType, VoiceEvent,
+            VoiceRouter,
+        },
+        synth_module::SynthModule,
+        types::{ComplexSample, Sample},
     },
-    synth_module::SynthModule,
-    types::{ComplexSample, Sample},
+    utils::note_to_pitch,
 };
 
+pub const MAX_TRACKING: Sample = 12.0; // dB/octave
+const TRACKING_PITCH: Sample = note_to_pitch(60.0); // Middle C
+const MIN_GAIN_DB: Sample = -120.0;
+
+#[derive(Clone, Copy, PartialEq)]
 struct Params {
     filter_type: SpectralFilterType,
     fourth_order: bool,
     linear_phase: bool,
+    key_tracking: Sample,
+    velocity_tracking: Sample,
 }
 
 impl Params {
@@ -35,8 +46,16 @@ impl Params {
             filter_type: c.filter_type,
             fourth_order: c.fourth_order,
             linear_phase: c.linear_phase,
+            key_tracking: c.key_tracking.clamp(0.0, MAX_TRACKING),
+            velocity_tracking: c.velocity_tracking.clamp(0.0, MAX_TRACKING),
         }
     }
+
+    // Rolloff of the voice in dB/octave, only high notes and soft velocities are darkened
+    fn brightness_rolloff(&self, pitch: Sample, velocity: Sample) -> Sample {
+        self.key_tracking * (pitch - TRACKING_PITCH).max(0.0)
+            + self.velocity_tracking * (1.0 - velocity.clamp(0.0, 1.0))
+    }
 }
 
 struct ChannelParams {
@@ -55,9 +74,24 @@ impl ChannelParams {
     }
 }
 
+// Everything the latest output was computed from
+#[derive(Clone, Copy, PartialEq)]
+struct AppliedState {
+    spectrum_revision: u32,
+    bins: usize,
+    params: Params,
+    cutoff: Sample,
+    q: Sample,
+    drive: Sample,
+    rolloff: Sample,
+}
+
 #[derive(Default)]
 struct VoiceState {
     triggered: bool,
+    applied: Option<AppliedState>,
+    pitch: Sample,
+    velocity: Sample,
 }
 
 pub struct Inputs {
@@ -100,11 +134,11 @@ impl Inputs {
         result
     }
 
-    fn update_amount(&mut self, input_type: Input, src_slot: usize, amount: StereoSample) {
+    fn update_source(&mut self, input_type: Input, src_slot: usize, source: &InputSource) {
         match input_type {
-            Input::Cutoff => self.cutoff.update_amount(src_slot, amount),
-            Input::Q => self.q.update_amount(src_slot, amount),
-            Input::Drive => self.drive.update_amount(src_slot, amount),
+            Input::Cutoff => self.cutoff.update_source(src_slot, source),
+            Input::Q => self.q.update_source(src_slot, source),
+            Input::Drive => self.drive.update_source(src_slot, source),
             _ => (),
         }
     }
@@ -114,6 +148,7 @@ type Router<'v, 'f, 'c> = VoiceRouter<'v, 'f, 'c, SpectralRouterType>;
 
 pub struct SpectralFilter {
     id: ModuleId,
+    bypass: bool,
     params: Params,
     channel_params: [ChannelParams; NUM_CHANNELS],
     audio_end: AudioEnd,
@@ -136,6 +171,7 @@ impl SpectralFilter {
 
         Self {
             id: config.id,
+            bypass: config.bypass,
             params: Params::from_config(config),
             channel_params: array::from_fn(|channel_idx| {
                 ChannelParams::from_config(config, channel_idx)
@@ -151,33 +187,61 @@ impl SpectralFilter {
     pub fn get_config(&self) -> SpectralFilterConfig {
         SpectralFilterConfig {
             id: self.id,
+            bypass: self.bypass,
             filter_type: self.params.filter_type,
             fourth_order: self.params.fourth_order,
             linear_phase: self.params.linear_phase,
+            key_tracking: self.params.key_tracking,
+            velocity_tracking: self.params.velocity_tracking,
             cutoff: get_stereo_param!(self, cutoff),
             q: get_stereo_param!(self, q),
             drive: get_stereo_param!(self, drive),
         }
     }
 
+    pub fn randomize(
+        config: &mut SpectralFilterConfig,
+        randomizer: &mut Randomizer,
+        unlocked: impl Fn(RandomParam) -> bool,
+    ) {
+        if unlocked(RandomParam::Cutoff) {
+            config.cutoff = randomizer.stereo(config.cutoff, 0.0, 8.0);
+        }
+        if unlocked(RandomParam::Resonance) {
+            config.q = randomizer.log(config.q, 0.5, 5.0);
+        }
+    }
+
     set_mono_param!(set_filter_type, filter_type, SpectralFilterType);
     set_mono_param!(set_fourth_order, fourth_order, bool);
     set_mono_param!(set_linear_phase, linear_phase, bool);
+    set_mono_param!(
+        set_key_tracking,
+        key_tracking,
+        Sample,
+        key_tracking.clamp(0.0, MAX_TRACKING)
+    );
+    set_mono_param!(
+        set_velocity_tracking,
+        velocity_tracking,
+        Sample,
+        velocity_tracking.clamp(0.0, MAX_TRACKING)
+    );
 
     set_stereo_param!(set_cutoff, cutoff, cutoff.clamp(-4.0, 10.0));
     set_stereo_param!(set_q, q, q.clamp(0.1, 10.0));
     set_stereo_param!(set_drive, drive);
 
     fn apply_response(
-        output: &mut SpectralBuffer,
-        input: &SpectralBuffer,
+        output: &mut [ComplexSample],
+        input: &[ComplexSample],
         response: impl Iterator<Item = ComplexSample>,
         fourth_order: bool,
         linear_phase: bool,
     ) {
         fn apply(
-            output: &mut SpectralBuffer,
-            input: &SpectralBuffer,
+            output: &mut [ComplexSample],
+            input: &[ComplexSample],
             response: impl Iterator<Item = ComplexSample>,
             transform: impl Fn(ComplexSample, ComplexSample) -> ComplexSample,
         ) {
@@ -204,8 +268,8 @@ impl SpectralFilter {
     }
 
     fn apply_biquad(
-        output: &mut SpectralBuffer,
-        input: &SpectralBuffer,
+        output: &mut [ComplexSample],
+        input: &[ComplexSample],
         filter_type: SpectralFilterType,
         biquad: &BiquadFilter,
         fourth_order: bool,
@@ -242,6 +306,14 @@ impl SpectralFilter {
         }
     }
 
+    fn apply_rolloff(output: &mut [ComplexSample], rolloff: Sample) {
+        for (harmonic, out) in output.iter_mut().enumerate().skip(2) {
+            let db = -rolloff * (harmonic as Sample).log2();
+
+            *out *= db_to_gain_fast(db.max(MIN_GAIN_DB));
+        }
+    }
+
     fn process_voice(
         &mut self,
         output: &mut VoicesLayout<SpectralOutput>,
@@ -252,7 +324,6 @@ impl SpectralFilter {
         let inputs = &self.inputs;
         let channel = &self.channel_params[channel_idx];
         let voice = &mut self.voices[channel_idx][voice_idx];
-        let voice_output = output[channel_idx][voice_idx].advance();
 
         let cutoff = router
             .scalar_param(&inputs.cutoff, channel.cutoff, voice.triggered)
@@ -263,7 +334,29 @@ impl SpectralFilter {
         let drive = router
             .scalar_param(&inputs.drive, channel.drive, voice.triggered)
             .min(24.0);
-        let input = router.spectral(inputs.spectrum, voice.triggered);
+
+        let rolloff = self.params.brightness_rolloff(voice.pitch, voice.velocity);
+
+        let bins = router.params().spectral_bins;
+        let applied = Some(AppliedState {
+            spectrum_revision: router.spectral_revision(inputs.spectrum),
+            bins,
+            params: self.params,
+            cutoff,
+            q,
+            drive,
+            rolloff,
+        });
+
+        // Unchanged input and parameters, downstream modules keep reading the latest spectrum
+        if !voice.triggered && voice.applied == applied {
+            return;
+        }
+
+        voice.applied = applied;
+
+        let voice_output = &mut output[channel_idx][voice_idx].advance()[..bins];
+        let input = &router.spectral(inputs.spectrum, voice.triggered)[..bins];
 
         let biquad = BiquadFilter::new(db_to_gain_fast(drive), cutoff.exp2(), q);
 
@@ -276,8 +369,13 @@ impl SpectralFilter {
             self.params.linear_phase,
         );
 
+        if rolloff > 0.0 {
+            Self::apply_rolloff(voice_output, rolloff);
+        }
+
         if voice.triggered {
             voice.triggered = false;
+            voice.applied = None;
 
             self.process_voice(output, router);
         }
@@ -289,6 +387,14 @@ impl SynthModule for SpectralFilter {
         self.id
     }
 
+    fn bypass(&self) -> bool {
+        self.bypass
+    }
+
+    fn set_bypass(&mut self, bypass: bool) {
+        self.bypass = bypass;
+    }
+
     fn inputs(&self) -> &'static [InputMeta] {
         static INPUTS: &[InputMeta] = &[
             InputMeta::spectral(Input::Spectrum),
@@ -314,34 +420,66 @@ impl SynthModule for SpectralFilter {
 
     fn set_input_slots(&mut self, inputs: &[InputSlots], spectral_inputs: &[SpectralInputSlot]) {
         self.inputs = Inputs::from_slots(inputs, spectral_inputs);
+
+        for voice in self.voices.iter_mut().flatten() {
+            voice.applied = None;
+        }
     }
 
     fn process_events(&mut self, events: &[VoiceEvent]) {
         for channel in self.voices.iter_mut() {
             for event in events {
-                if let VoiceEvent::Trigger { voice_idx, .. } = event {
-                    channel[*voice_idx].triggered = true;
+                match event {
+                    VoiceEvent::Trigger {
+                        voice_idx,
+                        pitch,
+                        velocity,
+                        ..
+                    } => {
+                        channel[*voice_idx].triggered = true;
+                        channel[*voice_idx].pitch = *pitch;
+                        channel[*voice_idx].velocity = *velocity;
+                    }
+                    VoiceEvent::Update {
+                        voice_idx,
+                        pitch,
+                        velocity,
+                        ..
+                    } => {
+                        channel[*voice_idx].pitch = *pitch;
+                        channel[*voice_idx].velocity = *velocity;
+                    }
+                    VoiceEvent::Retune { voice_idx, pitch } => {
+                        channel[*voice_idx].pitch = *pitch;
+                    }
+                    _ => (),
                 }
             }
         }
     }
 
-    fn update_input_amount(&mut self, input_type: Input, src_slot: usize, amount: StereoSample) {
-        self.inputs.update_amount(input_type, src_slot, amount);
+    fn update_input_source(&mut self, input_type: Input, src_slot: usize, source: &InputSource) {
+        self.inputs.update_source(input_type, src_slot, source);
+    }
+
+    fn set_input_param(&mut self, input: Input, value: StereoSample) {
+        match input {
+            Input::Cutoff => self.set_cutoff(value),
+            Input::Q => self.set_q(value),
+            Input::Drive => self.set_drive(value),
+            _ => (),
+        }
     }
 
     fn process_ui_events(&mut self) {
         while let Some(event) = self.audio_end.pop_event() {
             match event {
-                UiEvent::InputParam { input, value } => match input {
-                    Input::Cutoff => self.set_cutoff(value),
-                    Input::Q => self.set_q(value),
-                    Input::Drive => self.set_drive(value),
-                    _ => (),
-                },
+                UiEvent::InputParam { input, value } => self.set_input_param(input, value),
                 UiEvent::FilterType(filter_type) => self.set_filter_type(filter_type),
                 UiEvent::FourthOrder(value) => self.set_fourth_order(value),
                 UiEvent::LinearPhase(value) => self.set_linear_phase(value),
+                UiEvent::KeyTracking(value) => self.set_key_tracking(value),
+                UiEvent::VelocityTracking(value) => self.set_velocity_tracking(value),
             }
         }
     }
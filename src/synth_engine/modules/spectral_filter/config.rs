@@ -1,6 +1,6 @@
 use serde::{Deserialize, Serialize};
 
-use crate::synth_engine::{ModuleId, StereoSample};
+use crate::synth_engine::{ModuleId, Sample, StereoSample};
 
 #[derive(Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum SpectralFilterType {
@@ -15,24 +15,35 @@ pub enum SpectralFilterType {
 #[derive(Clone, Serialize, Deserialize)]
 pub struct SpectralFilterConfig {
     pub id: ModuleId,
+    #[serde(default)]
+    pub bypass: bool,
     pub filter_type: SpectralFilterType,
     pub fourth_order: bool,
     pub linear_phase: bool,
     pub cutoff: StereoSample,
     pub q: StereoSample,
     pub drive: StereoSample,
+    // dB/octave rolloff of the harmonics per octave the note is above middle C
+    #[serde(default)]
+    pub key_tracking: Sample,
+    // dB/octave rolloff of the harmonics at zero velocity, none at full velocity
+    #[serde(default)]
+    pub velocity_tracking: Sample,
 }
 
 impl Default for SpectralFilterConfig {
     fn default() -> Self {
         Self {
             id: -1,
+            bypass: false,
             filter_type: SpectralFilterType::default(),
             fourth_order: false,
             linear_phase: true,
             cutoff: 1.0.into(),
             q: 0.7.into(),
             drive: 0.0.into(),
+            key_tracking: 0.0,
+            velocity_tracking: 0.0,
         }
     }
 }
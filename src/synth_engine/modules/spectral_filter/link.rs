@@ -1,4 +1,4 @@
-use crate::synth_engine::{Input, StereoSample};
+use crate::synth_engine::{Input, Sample, StereoSample, edit_sender::EditSender};
 
 use super::config::SpectralFilterType;
 
@@ -7,15 +7,23 @@ pub enum UiEvent {
     FilterType(SpectralFilterType),
     FourthOrder(bool),
     LinearPhase(bool),
+    KeyTracking(Sample),
+    VelocityTracking(Sample),
 }
 
 pub struct UiEnd {
-    tx: rtrb::Producer<UiEvent>,
+    tx: EditSender<UiEvent>,
 }
 
 impl UiEnd {
     pub fn new(tx: rtrb::Producer<UiEvent>) -> Self {
-        Self { tx }
+        Self {
+            tx: EditSender::new(tx),
+        }
+    }
+
+    pub fn edits(&self) -> u32 {
+        self.tx.sent()
     }
 
     pub fn set_param(&mut self, input: Input, value: StereoSample) -> bool {
@@ -33,6 +41,14 @@ impl UiEnd {
     pub fn set_linear_phase(&mut self, value: bool) -> bool {
         self.tx.push(UiEvent::LinearPhase(value)).is_ok()
     }
+
+    pub fn set_key_tracking(&mut self, value: Sample) -> bool {
+        self.tx.push(UiEvent::KeyTracking(value)).is_ok()
+    }
+
+    pub fn set_velocity_tracking(&mut self, value: Sample) -> bool {
+        self.tx.push(UiEvent::VelocityTracking(value)).is_ok()
+    }
 }
 
 pub struct AudioEnd {
@@ -1,4 +1,4 @@
-use crate::synth_engine::{Input, StereoSample, synth_module::ModuleUiBridge};
+use crate::synth_engine::{Input, Sample, StereoSample, synth_module::ModuleUiBridge};
 
 use super::link::UiEnd;
 use super::{SpectralFilter, SpectralFilterConfig, SpectralFilterType};
@@ -50,8 +50,24 @@ impl SpectralFilterUiBridge {
             self.config.linear_phase = value;
         }
     }
+
+    pub fn set_key_tracking(&mut self, value: Sample) {
+        if self.ui_end.set_key_tracking(value) {
+            self.config.key_tracking = value;
+        }
+    }
+
+    pub fn set_velocity_tracking(&mut self, value: Sample) {
+        if self.ui_end.set_velocity_tracking(value) {
+            self.config.velocity_tracking = value;
+        }
+    }
 }
 
 impl ModuleUiBridge for SpectralFilterUiBridge {
     fn update(&mut self) {}
+
+    fn edits(&self) -> u32 {
+        self.ui_end.edits()
+    }
 }
@@ -12,11 +12,12 @@ use link::{AudioEnd, UiEnd, UiEvent, create_link_pair};
 pub use ui_bridge::SpectralMixerUiBridge;
 
 use crate::synth_engine::{
-    StereoSample,
+    RandomParam, Randomizer, StereoSample,
     buffer::{VoicesLayout, new_voices_layout},
     routing::{
-        DataType, Input, InputMeta, InputSlots, MixType, ModuleId, NUM_CHANNELS, ProcessContext,
-        SpectralInputSlot, SpectralOutput, SpectralRouterType, VoiceEvent, VoiceRouter, VolumeType,
+        DataType, Input, InputMeta, InputSlots, InputSource, MixType, ModuleId, NUM_CHANNELS,
+        ProcessContext, SpectralInputSlot, SpectralOutput, SpectralRouterType, VoiceEvent,
+        VoiceRouter, VolumeType,
     },
     synth_module::SynthModule,
     types::{ComplexSample, Sample},
@@ -126,15 +127,15 @@ impl Inputs {
         result
     }
 
-    fn update_amount(&mut self, input_type: Input, src_slot: usize, amount: StereoSample) {
+    fn update_source(&mut self, input_type: Input, src_slot: usize, source: &InputSource) {
         match input_type {
-            Input::Gain => self.gain.update_amount(src_slot, amount),
-            Input::Level => self.level.update_amount(src_slot, amount),
+            Input::Gain => self.gain.update_source(src_slot, source),
+            Input::Level => self.level.update_source(src_slot, source),
             Input::GainMix(idx) if idx < MAX_INPUTS => {
-                self.gain_mix[idx as usize].update_amount(src_slot, amount);
+                self.gain_mix[idx as usize].update_source(src_slot, source);
             }
             Input::LevelMix(idx) if idx < MAX_INPUTS => {
-                self.level_mix[idx as usize].update_amount(src_slot, amount);
+                self.level_mix[idx as usize].update_source(src_slot, source);
             }
             _ => (),
         }
@@ -145,6 +146,7 @@ type Router<'v, 'f, 'c> = VoiceRouter<'v, 'f, 'c, SpectralRouterType>;
 
 pub struct SpectralMixer {
     id: ModuleId,
+    bypass: bool,
     params: Params,
     channel_params: [ChannelParams; NUM_CHANNELS],
     audio_end: AudioEnd,
@@ -169,6 +171,7 @@ impl SpectralMixer {
 
         Self {
             id: config.id,
+            bypass: config.bypass,
             params: Params::from_config(config),
             channel_params: array::from_fn(|channel_idx| {
                 ChannelParams::from_config(config, channel_idx)
@@ -184,6 +187,7 @@ impl SpectralMixer {
     pub fn get_config(&self) -> SpectralMixerConfig {
         SpectralMixerConfig {
             id: self.id,
+            bypass: self.bypass,
             num_inputs: self.params.num_inputs,
             inputs: array::from_fn(|input_idx| config::InputConfig {
                 mix_type: self.params.inputs[input_idx].mix_type,
@@ -205,6 +209,14 @@ impl SpectralMixer {
         }
     }
 
+    // Levels stay where the patch balanced them
+    pub fn randomize(
+        _config: &mut SpectralMixerConfig,
+        _randomizer: &mut Randomizer,
+        _unlocked: impl Fn(RandomParam) -> bool,
+    ) {
+    }
+
     set_mono_param!(
         set_num_inputs,
         num_inputs,
@@ -258,7 +270,8 @@ impl SpectralMixer {
         let inputs = &self.inputs;
         let channel = &self.channel_params[channel_idx];
         let voice = &mut self.voices[channel_idx][voice_idx];
-        let voice_output = output[channel_idx][voice_idx].advance();
+        let voice_output =
+            &mut output[channel_idx][voice_idx].advance()[..router.params().spectral_bins];
 
         voice_output.fill(ComplexSample::ZERO);
 
@@ -284,7 +297,7 @@ impl SpectralMixer {
 
             let iter = voice_output
                 .iter_mut()
-                .zip(spectrum.map(|input| input * gain));
+                .zip(spectrum.iter().map(|input| *input * gain));
 
             if input_idx == 0 {
                 iter.for_each(|(out, input)| *out = input);
@@ -333,6 +346,14 @@ impl SynthModule for SpectralMixer {
         self.id
     }
 
+    fn bypass(&self) -> bool {
+        self.bypass
+    }
+
+    fn set_bypass(&mut self, bypass: bool) {
+        self.bypass = bypass;
+    }
+
     fn inputs(&self) -> &'static [InputMeta] {
         static INPUTS: &[InputMeta] = &[
             InputMeta::control(Input::Gain),
@@ -376,8 +397,8 @@ impl SynthModule for SpectralMixer {
         self.inputs = Inputs::from_slots(inputs, spectral_inputs);
     }
 
-    fn update_input_amount(&mut self, input_type: Input, src_slot: usize, amount: StereoSample) {
-        self.inputs.update_amount(input_type, src_slot, amount);
+    fn update_input_source(&mut self, input_type: Input, src_slot: usize, source: &InputSource) {
+        self.inputs.update_source(input_type, src_slot, source);
     }
 
     fn process_events(&mut self, events: &[VoiceEvent]) {
@@ -390,16 +411,20 @@ impl SynthModule for SpectralMixer {
         }
     }
 
+    fn set_input_param(&mut self, input: Input, value: StereoSample) {
+        match input {
+            Input::Gain => self.set_output_gain(value),
+            Input::Level => self.set_output_level(value),
+            Input::GainMix(idx) => self.set_input_gain(idx, value),
+            Input::LevelMix(idx) => self.set_input_level(idx, value),
+            _ => (),
+        }
+    }
+
     fn process_ui_events(&mut self) {
         while let Some(event) = self.audio_end.pop_event() {
             match event {
-                UiEvent::InputParam { input, value } => match input {
-                    Input::Gain => self.set_output_gain(value),
-                    Input::Level => self.set_output_level(value),
-                    Input::GainMix(idx) => self.set_input_gain(idx, value),
-                    Input::LevelMix(idx) => self.set_input_level(idx, value),
-                    _ => (),
-                },
+                UiEvent::InputParam { input, value } => self.set_input_param(input, value),
                 UiEvent::NumInputs(num_inputs) => self.set_num_inputs(num_inputs),
                 UiEvent::MixType {
                     input_idx,
@@ -26,6 +26,8 @@ impl Default for InputConfig {
 #[derive(Clone, Serialize, Deserialize)]
 pub struct SpectralMixerConfig {
     pub id: ModuleId,
+    #[serde(default)]
+    pub bypass: bool,
     pub num_inputs: u8,
     pub inputs: [InputConfig; MAX_INPUTS as usize],
     pub output_volume_type: VolumeType,
@@ -37,6 +39,7 @@ impl Default for SpectralMixerConfig {
     fn default() -> Self {
         Self {
             id: -1,
+            bypass: false,
             num_inputs: 2,
             inputs: Default::default(),
             output_volume_type: VolumeType::Gain,
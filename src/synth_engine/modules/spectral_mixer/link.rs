@@ -1,20 +1,35 @@
-use crate::synth_engine::{Input, MixType, StereoSample, VolumeType};
+use crate::synth_engine::{Input, MixType, StereoSample, VolumeType, edit_sender::EditSender};
 
 pub enum UiEvent {
-    InputParam { input: Input, value: StereoSample },
+    InputParam {
+        input: Input,
+        value: StereoSample,
+    },
     NumInputs(u8),
-    MixType { input_idx: u8, mix_type: MixType },
-    VolumeType { input_idx: u8, volume_type: VolumeType },
+    MixType {
+        input_idx: u8,
+        mix_type: MixType,
+    },
+    VolumeType {
+        input_idx: u8,
+        volume_type: VolumeType,
+    },
     OutputVolumeType(VolumeType),
 }
 
 pub struct UiEnd {
-    tx: rtrb::Producer<UiEvent>,
+    tx: EditSender<UiEvent>,
 }
 
 impl UiEnd {
     pub fn new(tx: rtrb::Producer<UiEvent>) -> Self {
-        Self { tx }
+        Self {
+            tx: EditSender::new(tx),
+        }
+    }
+
+    pub fn edits(&self) -> u32 {
+        self.tx.sent()
     }
 
     pub fn set_param(&mut self, input: Input, value: StereoSample) -> bool {
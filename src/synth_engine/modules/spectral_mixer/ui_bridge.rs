@@ -1,6 +1,4 @@
-use crate::synth_engine::{
-    Input, MixType, StereoSample, VolumeType, synth_module::ModuleUiBridge,
-};
+use crate::synth_engine::{Input, MixType, StereoSample, VolumeType, synth_module::ModuleUiBridge};
 
 use super::link::UiEnd;
 use super::{SpectralMixer, SpectralMixerConfig};
@@ -63,4 +61,8 @@ impl SpectralMixerUiBridge {
 
 impl ModuleUiBridge for SpectralMixerUiBridge {
     fn update(&mut self) {}
+
+    fn edits(&self) -> u32 {
+        self.ui_end.edits()
+    }
 }
@@ -0,0 +1,322 @@
+use std::array;
+
+use nih_plug::util::db_to_gain_fast;
+
+mod config;
+mod link;
+mod ui_bridge;
+
+pub use config::SpectralTiltConfig;
+use link::{AudioEnd, UiEnd, UiEvent, create_link_pair};
+pub use ui_bridge::SpectralTiltUiBridge;
+
+use crate::synth_engine::{
+    RandomParam, Randomizer, StereoSample,
+    buffer::{SPECTRUM_BITS, SpectralBuffer, VoicesLayout, new_voices_layout},
+    routing::{
+        DataType, Input, InputMeta, InputSlots, InputSource, ModuleId, NUM_CHANNELS,
+        ProcessContext, SpectralInputSlot, SpectralOutput, SpectralRouterType, VoiceEvent,
+        VoiceRouter,
+    },
+    synth_module::SynthModule,
+    types::Sample,
+};
+
+const MAX_TILT: Sample = 24.0; // dB/octave
+// Octaves above the fundamental, the top of the spectrum
+const MAX_PIVOT: Sample = SPECTRUM_BITS as Sample;
+// Keeps steep upward slopes from blowing up the top harmonics
+const MAX_BOOST: Sample = 24.0;
+const MIN_GAIN_DB: Sample = -120.0;
+
+struct ChannelParams {
+    tilt: Sample,
+    pivot: Sample,
+}
+
+impl ChannelParams {
+    fn from_config(c: &SpectralTiltConfig, channel_idx: usize) -> Self {
+        Self {
+            tilt: c.tilt[channel_idx],
+            pivot: c.pivot[channel_idx],
+        }
+    }
+}
+
+// Everything the latest output was computed from
+#[derive(Clone, Copy, PartialEq)]
+struct AppliedState {
+    spectrum_revision: u32,
+    bins: usize,
+    tilt: Sample,
+    pivot: Sample,
+}
+
+#[derive(Default)]
+struct VoiceState {
+    triggered: bool,
+    applied: Option<AppliedState>,
+}
+
+pub struct Inputs {
+    spectrum: Option<usize>,
+    tilt: InputSlots,
+    pivot: InputSlots,
+}
+
+impl Default for Inputs {
+    fn default() -> Self {
+        Self {
+            spectrum: None,
+            tilt: InputSlots::empty(Input::Tilt),
+            pivot: InputSlots::empty(Input::Pivot),
+        }
+    }
+}
+
+impl Inputs {
+    fn from_slots(inputs: &[InputSlots], spectral_inputs: &[SpectralInputSlot]) -> Self {
+        let mut result = Self::default();
+
+        for input in inputs {
+            match input.input_type {
+                Input::Tilt => result.tilt = input.clone(),
+                Input::Pivot => result.pivot = input.clone(),
+                _ => (),
+            }
+        }
+
+        for input in spectral_inputs {
+            if matches!(input.input_type, Input::Spectrum) {
+                result.spectrum = Some(input.slot);
+            }
+        }
+
+        result
+    }
+
+    fn update_source(&mut self, input_type: Input, src_slot: usize, source: &InputSource) {
+        match input_type {
+            Input::Tilt => self.tilt.update_source(src_slot, source),
+            Input::Pivot => self.pivot.update_source(src_slot, source),
+            _ => (),
+        }
+    }
+}
+
+type Router<'v, 'f, 'c> = VoiceRouter<'v, 'f, 'c, SpectralRouterType>;
+
+// dB/octave slope around a pivot harmonic, a cheap brightness control
+pub struct SpectralTilt {
+    id: ModuleId,
+    bypass: bool,
+    channel_params: [ChannelParams; NUM_CHANNELS],
+    audio_end: AudioEnd,
+    ui_end: Option<UiEnd>,
+    inputs: Inputs,
+    output_slot: usize,
+    voices: VoicesLayout<VoiceState>,
+}
+
+impl SpectralTilt {
+    pub fn new(id: ModuleId) -> Self {
+        Self::from_config(&SpectralTiltConfig {
+            id,
+            ..SpectralTiltConfig::default()
+        })
+    }
+
+    pub fn from_config(config: &SpectralTiltConfig) -> Self {
+        let (audio_end, ui_end) = create_link_pair();
+
+        Self {
+            id: config.id,
+            bypass: config.bypass,
+            channel_params: array::from_fn(|channel_idx| {
+                ChannelParams::from_config(config, channel_idx)
+            }),
+            audio_end,
+            ui_end: Some(ui_end),
+            inputs: Inputs::default(),
+            output_slot: usize::MAX,
+            voices: new_voices_layout(),
+        }
+    }
+
+    pub fn get_config(&self) -> SpectralTiltConfig {
+        SpectralTiltConfig {
+            id: self.id,
+            bypass: self.bypass,
+            tilt: get_stereo_param!(self, tilt),
+            pivot: get_stereo_param!(self, pivot),
+        }
+    }
+
+    pub fn randomize(
+        config: &mut SpectralTiltConfig,
+        randomizer: &mut Randomizer,
+        unlocked: impl Fn(RandomParam) -> bool,
+    ) {
+        if unlocked(RandomParam::Tilt) {
+            config.tilt = randomizer.stereo(config.tilt, -MAX_TILT, MAX_TILT);
+        }
+        if unlocked(RandomParam::Pivot) {
+            config.pivot = randomizer.stereo(config.pivot, 0.0, MAX_PIVOT);
+        }
+    }
+
+    set_stereo_param!(set_tilt, tilt, (*tilt).clamp(-MAX_TILT, MAX_TILT));
+    set_stereo_param!(set_pivot, pivot, (*pivot).clamp(0.0, MAX_PIVOT));
+
+    /// `pivot` is in octaves above the fundamental, the pivot harmonic keeps its level.
+    fn apply_tilt(
+        output: &mut SpectralBuffer,
+        input: &SpectralBuffer,
+        bins: usize,
+        tilt: Sample,
+        pivot: Sample,
+    ) {
+        output[0] = input[0];
+
+        for (harmonic, (out, input)) in output[..bins].iter_mut().zip(input).enumerate().skip(1) {
+            let db = tilt * ((harmonic as Sample).log2() - pivot);
+
+            *out = *input * db_to_gain_fast(db.clamp(MIN_GAIN_DB, MAX_BOOST));
+        }
+    }
+
+    fn process_voice(
+        &mut self,
+        output: &mut VoicesLayout<SpectralOutput>,
+        mut router: Router<'_, '_, '_>,
+    ) {
+        let channel_idx = router.channel_idx();
+        let voice_idx = router.voice_idx();
+        let inputs = &self.inputs;
+        let channel = &self.channel_params[channel_idx];
+        let voice = &mut self.voices[channel_idx][voice_idx];
+
+        let tilt = router
+            .scalar_param(&inputs.tilt, channel.tilt, voice.triggered)
+            .clamp(-MAX_TILT, MAX_TILT);
+        let pivot = router
+            .scalar_param(&inputs.pivot, channel.pivot, voice.triggered)
+            .clamp(0.0, MAX_PIVOT);
+
+        let bins = router.params().spectral_bins;
+        let applied = Some(AppliedState {
+            spectrum_revision: router.spectral_revision(inputs.spectrum),
+            bins,
+            tilt,
+            pivot,
+        });
+
+        // Unchanged input and parameters, downstream modules keep reading the latest spectrum
+        if !voice.triggered && voice.applied == applied {
+            return;
+        }
+
+        voice.applied = applied;
+
+        let voice_output = output[channel_idx][voice_idx].advance();
+        let input = router.spectral(inputs.spectrum, voice.triggered);
+
+        Self::apply_tilt(voice_output, input, bins, tilt, pivot);
+
+        if voice.triggered {
+            voice.triggered = false;
+            voice.applied = None;
+
+            self.process_voice(output, router);
+        }
+    }
+}
+
+impl SynthModule for SpectralTilt {
+    fn id(&self) -> ModuleId {
+        self.id
+    }
+
+    fn bypass(&self) -> bool {
+        self.bypass
+    }
+
+    fn set_bypass(&mut self, bypass: bool) {
+        self.bypass = bypass;
+    }
+
+    fn inputs(&self) -> &'static [InputMeta] {
+        static INPUTS: &[InputMeta] = &[
+            InputMeta::spectral(Input::Spectrum),
+            InputMeta::control(Input::Tilt),
+            InputMeta::control(Input::Pivot),
+        ];
+
+        INPUTS
+    }
+
+    fn output_type(&self) -> DataType {
+        DataType::Spectral
+    }
+
+    fn output_slot(&self) -> usize {
+        self.output_slot
+    }
+
+    fn set_output_slot(&mut self, slot: usize) {
+        self.output_slot = slot;
+    }
+
+    fn set_input_slots(&mut self, inputs: &[InputSlots], spectral_inputs: &[SpectralInputSlot]) {
+        self.inputs = Inputs::from_slots(inputs, spectral_inputs);
+
+        for voice in self.voices.iter_mut().flatten() {
+            voice.applied = None;
+        }
+    }
+
+    fn process_events(&mut self, events: &[VoiceEvent]) {
+        for channel in self.voices.iter_mut() {
+            for event in events {
+                if let VoiceEvent::Trigger { voice_idx, .. } = event {
+                    channel[*voice_idx].triggered = true;
+                }
+            }
+        }
+    }
+
+    fn update_input_source(&mut self, input_type: Input, src_slot: usize, source: &InputSource) {
+        self.inputs.update_source(input_type, src_slot, source);
+    }
+
+    fn set_input_param(&mut self, input: Input, value: StereoSample) {
+        match input {
+            Input::Tilt => self.set_tilt(value),
+            Input::Pivot => self.set_pivot(value),
+            _ => (),
+        }
+    }
+
+    fn process_ui_events(&mut self) {
+        while let Some(event) = self.audio_end.pop_event() {
+            match event {
+                UiEvent::InputParam { input, value } => self.set_input_param(input, value),
+            }
+        }
+    }
+
+    fn process(&mut self, ctx: &mut ProcessContext) {
+        ctx.for_spectral(self.id, self.output_slot, |router, output| {
+            let num_active_voices = router.params().active_voices.len();
+            let spectrum_channels = router.params().spectrum_channels;
+
+            for channel_idx in 0..spectrum_channels {
+                for seq_idx in 0..num_active_voices {
+                    let voice_idx = router.params().active_voices[seq_idx];
+
+                    self.process_voice(output, router.for_voice(channel_idx, voice_idx, seq_idx));
+                }
+            }
+        });
+    }
+}
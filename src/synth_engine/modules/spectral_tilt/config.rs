@@ -0,0 +1,23 @@
+use serde::{Deserialize, Serialize};
+
+use crate::synth_engine::{ModuleId, StereoSample};
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SpectralTiltConfig {
+    pub id: ModuleId,
+    #[serde(default)]
+    pub bypass: bool,
+    pub tilt: StereoSample,
+    pub pivot: StereoSample,
+}
+
+impl Default for SpectralTiltConfig {
+    fn default() -> Self {
+        Self {
+            id: -1,
+            bypass: false,
+            tilt: 0.0.into(),
+            pivot: 0.0.into(),
+        }
+    }
+}
@@ -0,0 +1,42 @@
+use crate::synth_engine::{Input, StereoSample, synth_module::ModuleUiBridge};
+
+use super::link::UiEnd;
+use super::{SpectralTilt, SpectralTiltConfig};
+
+pub struct SpectralTiltUiBridge {
+    ui_end: UiEnd,
+    config: SpectralTiltConfig,
+}
+
+impl SpectralTiltUiBridge {
+    pub fn try_new(tilt: &mut SpectralTilt) -> Option<Self> {
+        Some(Self {
+            ui_end: tilt.ui_end.take()?,
+            config: tilt.get_config(),
+        })
+    }
+
+    pub fn config(&self) -> &SpectralTiltConfig {
+        &self.config
+    }
+
+    pub fn set_param(&mut self, input: Input, value: StereoSample) {
+        if !self.ui_end.set_param(input, value) {
+            return;
+        }
+
+        match input {
+            Input::Tilt => self.config.tilt = value,
+            Input::Pivot => self.config.pivot = value,
+            _ => (),
+        }
+    }
+}
+
+impl ModuleUiBridge for SpectralTiltUiBridge {
+    fn update(&mut self) {}
+
+    fn edits(&self) -> u32 {
+        self.ui_end.edits()
+    }
+}
@@ -12,24 +12,31 @@ use link::{AudioEnd, UiEnd, UiEvent, create_link_pair};
 pub use ui_bridge::WaveShaperUiBridge;
 
 use crate::synth_engine::{
-    StereoSample,
-    buffer::{Buffer, VoicesLayout, zero_buffer},
+    RandomParam, Randomizer, Sample, StereoSample,
+    buffer::{BUFFER_SIZE, Buffer, VoicesLayout, new_voices_layout, zero_buffer},
+    half_band::HalfBand,
     routing::{
-        AudioRouterType, DataType, Input, InputMeta, InputSlots, ModuleId, NUM_CHANNELS,
-        ProcessContext, SamplesOutput, SpectralInputSlot, VoiceRouter,
+        AudioRouterType, DataType, Input, InputMeta, InputSlots, InputSource, ModuleId,
+        NUM_CHANNELS, ProcessContext, SamplesOutput, SpectralInputSlot, VoiceRouter,
     },
     smooth::SmoothedSample,
     synth_module::SynthModule,
 };
 
+const MAX_LEVEL: Sample = 24.0; // dB
+const MAX_DISTORTION: Sample = 48.0; // dB
+const ASYMMETRY_BIAS: Sample = 0.5;
+
 struct Params {
     shaper_type: ShaperType,
+    oversampling: bool,
 }
 
 impl Params {
     fn from_config(c: &config::WaveShaperConfig) -> Self {
         Self {
             shaper_type: c.shaper_type,
+            oversampling: c.oversampling,
         }
     }
 }
@@ -37,6 +44,7 @@ impl Params {
 struct ChannelParams {
     distortion: SmoothedSample,
     clipping_level: SmoothedSample,
+    level: SmoothedSample,
 }
 
 impl ChannelParams {
@@ -44,6 +52,7 @@ impl ChannelParams {
         Self {
             distortion: c.distortion[channel_idx].into(),
             clipping_level: c.clipping_level[channel_idx].into(),
+            level: c.level[channel_idx].into(),
         }
     }
 }
@@ -52,6 +61,7 @@ pub struct Inputs {
     audio: Option<usize>,
     distortion: InputSlots,
     clipping_level: InputSlots,
+    level: InputSlots,
 }
 
 impl Default for Inputs {
@@ -60,6 +70,7 @@ impl Default for Inputs {
             audio: None,
             distortion: InputSlots::empty(Input::Distortion),
             clipping_level: InputSlots::empty(Input::ClippingLevel),
+            level: InputSlots::empty(Input::Level),
         }
     }
 }
@@ -73,6 +84,7 @@ impl Inputs {
                 Input::Audio => result.audio = input.slots.first().map(|s| s.src_slot),
                 Input::Distortion => result.distortion = input.clone(),
                 Input::ClippingLevel => result.clipping_level = input.clone(),
+                Input::Level => result.level = input.clone(),
                 _ => (),
             }
         }
@@ -80,10 +92,11 @@ impl Inputs {
         result
     }
 
-    fn update_amount(&mut self, input_type: Input, src_slot: usize, amount: StereoSample) {
+    fn update_source(&mut self, input_type: Input, src_slot: usize, source: &InputSource) {
         match input_type {
-            Input::Distortion => self.distortion.update_amount(src_slot, amount),
-            Input::ClippingLevel => self.clipping_level.update_amount(src_slot, amount),
+            Input::Distortion => self.distortion.update_source(src_slot, source),
+            Input::ClippingLevel => self.clipping_level.update_source(src_slot, source),
+            Input::Level => self.level.update_source(src_slot, source),
             _ => (),
         }
     }
@@ -94,13 +107,17 @@ type Router<'v, 'f, 'c> = VoiceRouter<'v, 'f, 'c, AudioRouterType>;
 struct Buffers {
     distortion_mod_input: Buffer,
     clipping_level_mod_input: Buffer,
+    level_mod_input: Buffer,
+    oversampled: [Sample; 2 * BUFFER_SIZE],
 }
 
 pub struct WaveShaper {
     id: ModuleId,
+    bypass: bool,
     params: Params,
     channel_params: [ChannelParams; NUM_CHANNELS],
     buffers: Buffers,
+    half_bands: VoicesLayout<HalfBand>,
     audio_end: AudioEnd,
     ui_end: Option<UiEnd>,
     inputs: Inputs,
@@ -120,6 +137,7 @@ impl WaveShaper {
 
         Self {
             id: config.id,
+            bypass: config.bypass,
             params: Params::from_config(config),
             channel_params: array::from_fn(|channel_idx| {
                 ChannelParams::from_config(config, channel_idx)
@@ -127,7 +145,10 @@ impl WaveShaper {
             buffers: Buffers {
                 distortion_mod_input: zero_buffer(),
                 clipping_level_mod_input: zero_buffer(),
+                level_mod_input: zero_buffer(),
+                oversampled: [0.0; 2 * BUFFER_SIZE],
             },
+            half_bands: new_voices_layout(),
             audio_end,
             ui_end: Some(ui_end),
             inputs: Inputs::default(),
@@ -138,9 +159,22 @@ impl WaveShaper {
     pub fn get_config(&self) -> WaveShaperConfig {
         WaveShaperConfig {
             id: self.id,
+            bypass: self.bypass,
             shaper_type: self.params.shaper_type,
             distortion: get_smoothed_param!(self, distortion),
             clipping_level: get_smoothed_param!(self, clipping_level),
+            level: get_smoothed_param!(self, level),
+            oversampling: self.params.oversampling,
+        }
+    }
+
+    pub fn randomize(
+        config: &mut WaveShaperConfig,
+        randomizer: &mut Randomizer,
+        unlocked: impl Fn(RandomParam) -> bool,
+    ) {
+        if unlocked(RandomParam::Drive) {
+            config.distortion = randomizer.stereo(config.distortion, 0.0, MAX_DISTORTION);
         }
     }
 
@@ -148,6 +182,36 @@ impl WaveShaper {
 
     set_smoothed_param!(set_distortion, distortion);
     set_smoothed_param!(set_clipping_level, clipping_level);
+    set_smoothed_param!(set_level, level);
+
+    pub fn set_oversampling(&mut self, oversampling: bool) {
+        if self.params.oversampling != oversampling {
+            self.params.oversampling = oversampling;
+            self.half_bands
+                .iter_mut()
+                .flatten()
+                .for_each(|half_band| *half_band = HalfBand::default());
+        }
+    }
+
+    #[inline]
+    fn shape(
+        shaper_type: ShaperType,
+        input: Sample,
+        gain: Sample,
+        clipping_gain: Sample,
+    ) -> Sample {
+        let x = input * gain / clipping_gain;
+
+        let shaped = match shaper_type {
+            ShaperType::HardClip => x.clamp(-1.0, 1.0),
+            ShaperType::Sigmoid => x.tanh(),
+            ShaperType::Fold => 1.0 - ((x + 1.0).rem_euclid(4.0) - 2.0).abs(),
+            ShaperType::Asymmetric => (x + ASYMMETRY_BIAS).tanh() - ASYMMETRY_BIAS.tanh(),
+        };
+
+        clipping_gain * shaped
+    }
 
     fn process_voice(
         &mut self,
@@ -156,35 +220,62 @@ impl WaveShaper {
     ) {
         let channel_idx = router.channel_idx();
         let voice_idx = router.voice_idx();
+        let samples = router.samples();
+        let shaper_type = self.params.shaper_type;
         let inputs = &self.inputs;
+        let buffers = &mut self.buffers;
         let channel = &mut self.channel_params[channel_idx];
-        let output = output[channel_idx][voice_idx].output(router.samples());
+        let output = output[channel_idx][voice_idx].output(samples);
 
         router.buff_param(
             &inputs.clipping_level,
             &mut channel.clipping_level,
-            &mut self.buffers.clipping_level_mod_input,
+            &mut buffers.clipping_level_mod_input,
         );
         router.buff_param(
             &inputs.distortion,
             &mut channel.distortion,
-            &mut self.buffers.distortion_mod_input,
+            &mut buffers.distortion_mod_input,
+        );
+        router.buff_param(
+            &inputs.level,
+            &mut channel.level,
+            &mut buffers.level_mod_input,
         );
 
-        for (out, input, clipping_level_mod, distortion_mod) in izip!(
-            output.iter_mut(),
-            router.buff(inputs.audio),
-            &self.buffers.clipping_level_mod_input,
-            &self.buffers.distortion_mod_input
-        ) {
-            let clipping_gain = db_to_gain_fast(clipping_level_mod.min(24.0));
-            let gain = db_to_gain_fast(distortion_mod.clamp(0.0, 48.0));
-
-            match self.params.shaper_type {
-                ShaperType::HardClip => {
-                    *out = (input * gain).clamp(-clipping_gain, clipping_gain);
+        let gains = izip!(
+            &buffers.clipping_level_mod_input,
+            &buffers.distortion_mod_input,
+            &buffers.level_mod_input
+        )
+        .map(|(clipping_level_mod, distortion_mod, level_mod)| {
+            (
+                db_to_gain_fast(distortion_mod.clamp(0.0, MAX_DISTORTION)),
+                db_to_gain_fast(clipping_level_mod.min(24.0)),
+                db_to_gain_fast(level_mod.min(MAX_LEVEL)),
+            )
+        });
+
+        if self.params.oversampling {
+            let half_band = &mut self.half_bands[channel_idx][voice_idx];
+            let oversampled = &mut buffers.oversampled[..2 * samples];
+
+            half_band.upsample(&router.buff(inputs.audio)[..samples], oversampled);
+
+            for (pair, (gain, clipping_gain, level_gain)) in
+                oversampled.chunks_exact_mut(2).zip(gains)
+            {
+                for sample in pair {
+                    *sample = level_gain * Self::shape(shaper_type, *sample, gain, clipping_gain);
                 }
-                ShaperType::Sigmoid => *out = clipping_gain * (input * gain / clipping_gain).tanh(),
+            }
+
+            half_band.downsample(oversampled, output);
+        } else {
+            for (out, input, (gain, clipping_gain, level_gain)) in
+                izip!(output.iter_mut(), router.buff(inputs.audio), gains)
+            {
+                *out = level_gain * Self::shape(shaper_type, *input, gain, clipping_gain);
             }
         }
     }
@@ -195,11 +286,20 @@ impl SynthModule for WaveShaper {
         self.id
     }
 
+    fn bypass(&self) -> bool {
+        self.bypass
+    }
+
+    fn set_bypass(&mut self, bypass: bool) {
+        self.bypass = bypass;
+    }
+
     fn inputs(&self) -> &'static [InputMeta] {
         static INPUTS: &[InputMeta] = &[
             InputMeta::audio(Input::Audio),
             InputMeta::control(Input::ClippingLevel),
             InputMeta::control(Input::Distortion),
+            InputMeta::control(Input::Level),
         ];
 
         INPUTS
@@ -221,19 +321,25 @@ impl SynthModule for WaveShaper {
         self.inputs = Inputs::from_slots(inputs, spectral_inputs);
     }
 
-    fn update_input_amount(&mut self, input_type: Input, src_slot: usize, amount: StereoSample) {
-        self.inputs.update_amount(input_type, src_slot, amount);
+    fn update_input_source(&mut self, input_type: Input, src_slot: usize, source: &InputSource) {
+        self.inputs.update_source(input_type, src_slot, source);
+    }
+
+    fn set_input_param(&mut self, input: Input, value: StereoSample) {
+        match input {
+            Input::Distortion => self.set_distortion(value),
+            Input::ClippingLevel => self.set_clipping_level(value),
+            Input::Level => self.set_level(value),
+            _ => (),
+        }
     }
 
     fn process_ui_events(&mut self) {
         while let Some(event) = self.audio_end.pop_event() {
             match event {
-                UiEvent::InputParam { input, value } => match input {
-                    Input::Distortion => self.set_distortion(value),
-                    Input::ClippingLevel => self.set_clipping_level(value),
-                    _ => (),
-                },
+                UiEvent::InputParam { input, value } => self.set_input_param(input, value),
                 UiEvent::ShaperType(shaper_type) => self.set_shaper_type(shaper_type),
+                UiEvent::Oversampling(oversampling) => self.set_oversampling(oversampling),
             }
         }
     }
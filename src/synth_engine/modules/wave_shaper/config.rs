@@ -7,23 +7,34 @@ pub enum ShaperType {
     #[default]
     HardClip,
     Sigmoid,
+    Fold,
+    Asymmetric,
 }
 
 #[derive(Clone, Serialize, Deserialize)]
 pub struct WaveShaperConfig {
     pub id: ModuleId,
+    #[serde(default)]
+    pub bypass: bool,
     pub shaper_type: ShaperType,
     pub distortion: StereoSample,
     pub clipping_level: StereoSample,
+    #[serde(default)]
+    pub level: StereoSample,
+    #[serde(default)]
+    pub oversampling: bool,
 }
 
 impl Default for WaveShaperConfig {
     fn default() -> Self {
         Self {
             id: -1,
+            bypass: false,
             shaper_type: ShaperType::default(),
             distortion: 0.0.into(),
             clipping_level: 0.0.into(),
+            level: 0.0.into(),
+            oversampling: false,
         }
     }
 }
@@ -1,19 +1,26 @@
-use crate::synth_engine::{Input, StereoSample};
+use crate::synth_engine::{Input, StereoSample, edit_sender::EditSender};
 
 use super::config::ShaperType;
 
 pub enum UiEvent {
     InputParam { input: Input, value: StereoSample },
     ShaperType(ShaperType),
+    Oversampling(bool),
 }
 
 pub struct UiEnd {
-    tx: rtrb::Producer<UiEvent>,
+    tx: EditSender<UiEvent>,
 }
 
 impl UiEnd {
     pub fn new(tx: rtrb::Producer<UiEvent>) -> Self {
-        Self { tx }
+        Self {
+            tx: EditSender::new(tx),
+        }
+    }
+
+    pub fn edits(&self) -> u32 {
+        self.tx.sent()
     }
 
     pub fn set_param(&mut self, input: Input, value: StereoSample) -> bool {
@@ -23,6 +30,10 @@ impl UiEnd {
     pub fn set_shaper_type(&mut self, shaper_type: ShaperType) -> bool {
         self.tx.push(UiEvent::ShaperType(shaper_type)).is_ok()
     }
+
+    pub fn set_oversampling(&mut self, oversampling: bool) -> bool {
+        self.tx.push(UiEvent::Oversampling(oversampling)).is_ok()
+    }
 }
 
 pub struct AudioEnd {
@@ -28,6 +28,7 @@ impl WaveShaperUiBridge {
         match input {
             Input::Distortion => self.config.distortion = value,
             Input::ClippingLevel => self.config.clipping_level = value,
+            Input::Level => self.config.level = value,
             _ => (),
         }
     }
@@ -37,8 +38,18 @@ impl WaveShaperUiBridge {
             self.config.shaper_type = shaper_type;
         }
     }
+
+    pub fn set_oversampling(&mut self, oversampling: bool) {
+        if self.ui_end.set_oversampling(oversampling) {
+            self.config.oversampling = oversampling;
+        }
+    }
 }
 
 impl ModuleUiBridge for WaveShaperUiBridge {
     fn update(&mut self) {}
+
+    fn edits(&self) -> u32 {
+        self.ui_end.edits()
+    }
 }
@@ -0,0 +1,111 @@
+use std::ffi::c_char;
+
+use libloading::Library;
+use nih_plug::nih_log;
+
+use crate::synth_engine::Sample;
+
+#[cfg(target_os = "windows")]
+const LIBRARY_PATH: &str = r"C:\Program Files\Common Files\MTS-ESP\LIBMTS.dll";
+#[cfg(target_os = "macos")]
+const LIBRARY_PATH: &str = "/Library/Application Support/MTS-ESP/libMTS.dylib";
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+const LIBRARY_PATH: &str = "/usr/local/lib/libMTS.so";
+
+const A4_FREQUENCY: f64 = 440.0;
+
+type VoidFn = unsafe extern "C" fn();
+type HasMasterFn = unsafe extern "C" fn() -> bool;
+type ShouldFilterNoteFn = unsafe extern "C" fn(c_char, c_char) -> bool;
+type TuningTableFn = unsafe extern "C" fn() -> *const f64;
+type ChannelTuningTableFn = unsafe extern "C" fn(c_char) -> *const f64;
+type UseMultiChannelFn = unsafe extern "C" fn(c_char) -> bool;
+
+// Client of the MTS-ESP tuning master, talks to the shared libMTS the same way the reference C++ client does.
+// The query functions are lock-free and safe to call on the audio thread.
+pub struct MtsClient {
+    deregister: VoidFn,
+    has_master: HasMasterFn,
+    should_filter_note: ShouldFilterNoteFn,
+    tuning_table: TuningTableFn,
+    channel_tuning_table: ChannelTuningTableFn,
+    use_multi_channel: UseMultiChannelFn,
+    _library: Library,
+}
+
+impl MtsClient {
+    /// Loads libMTS and registers as a client, None when MTS-ESP isn't installed.
+    pub fn connect() -> Option<Self> {
+        let result = unsafe { Self::load() };
+
+        if let Err(err) = &result {
+            nih_log!("MTS-ESP is not available: {err}");
+        }
+
+        result.ok()
+    }
+
+    unsafe fn load() -> Result<Self, libloading::Error> {
+        unsafe {
+            let library = Library::new(LIBRARY_PATH)?;
+            let register = *library.get::<VoidFn>(b"MTS_RegisterClient\0")?;
+            let deregister = *library.get(b"MTS_DeregisterClient\0")?;
+            let has_master = *library.get(b"MTS_HasMaster\0")?;
+            let should_filter_note = *library.get(b"MTS_ShouldFilterNoteMultiChannel\0")?;
+            let tuning_table = *library.get(b"MTS_GetTuningTable\0")?;
+            let channel_tuning_table = *library.get(b"MTS_GetMultiChannelTuningTable\0")?;
+            let use_multi_channel = *library.get(b"MTS_UseMultiChannelTuning\0")?;
+            let client = Self {
+                deregister,
+                has_master,
+                should_filter_note,
+                tuning_table,
+                channel_tuning_table,
+                use_multi_channel,
+                _library: library,
+            };
+
+            register();
+            Ok(client)
+        }
+    }
+
+    pub fn has_master(&self) -> bool {
+        unsafe { (self.has_master)() }
+    }
+
+    /// The master may leave some notes out of its scale, those shouldn't play.
+    pub fn should_filter_note(&self, note: u8, channel: u8) -> bool {
+        self.has_master() && unsafe { (self.should_filter_note)(note as c_char, channel as c_char) }
+    }
+
+    /// Pitch in octaves relative to A4, None without a connected master.
+    pub fn note_pitch(&self, note: u8, channel: u8) -> Option<Sample> {
+        if !self.has_master() || note > 127 {
+            return None;
+        }
+
+        let channel = channel as c_char;
+        let table = unsafe {
+            if (self.use_multi_channel)(channel) {
+                (self.channel_tuning_table)(channel)
+            } else {
+                (self.tuning_table)()
+            }
+        };
+
+        if table.is_null() {
+            return None;
+        }
+
+        let frequency = unsafe { *table.add(note as usize) };
+
+        (frequency > 0.0).then(|| (frequency / A4_FREQUENCY).log2() as Sample)
+    }
+}
+
+impl Drop for MtsClient {
+    fn drop(&mut self) {
+        unsafe { (self.deregister)() };
+    }
+}
@@ -0,0 +1,74 @@
+use std::f32::consts::FRAC_1_SQRT_2;
+
+use serde::{Deserialize, Serialize};
+
+use crate::synth_engine::Sample;
+
+const CORRELATION_TIME: Sample = 0.3;
+// Below about -100 dB the channels are treated as silent
+const MIN_POWER: Sample = 1e-10;
+
+// Folds the output to check a patch for mono compatibility, applied before the limiter
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OutputMode {
+    #[default]
+    Stereo,
+    // Both channels summed at -3 dB, as a mono system with a constant power pan law plays them
+    MonoSum,
+    // Only the mid signal (L + R) / 2, centered sounds keep their level
+    Mid,
+}
+
+impl OutputMode {
+    pub fn process(self, left: &mut [Sample], right: &mut [Sample]) {
+        let gain = match self {
+            Self::Stereo => return,
+            Self::MonoSum => FRAC_1_SQRT_2,
+            Self::Mid => 0.5,
+        };
+
+        for (left, right) in left.iter_mut().zip(right.iter_mut()) {
+            let mono = gain * (*left + *right);
+
+            *left = mono;
+            *right = mono;
+        }
+    }
+}
+
+// Phase correlation of the channels: +1 is mono, 0 unrelated and -1 cancels out when summed
+pub struct CorrelationMeter {
+    product: Sample,
+    left_power: Sample,
+    right_power: Sample,
+}
+
+impl CorrelationMeter {
+    pub fn new() -> Self {
+        Self {
+            product: 0.0,
+            left_power: 0.0,
+            right_power: 0.0,
+        }
+    }
+
+    pub fn process(&mut self, sample_rate: Sample, left: &[Sample], right: &[Sample]) {
+        let coeff = 1.0 - (-(CORRELATION_TIME * sample_rate).recip()).exp();
+
+        for (left, right) in left.iter().zip(right) {
+            self.product += coeff * (left * right - self.product);
+            self.left_power += coeff * (left * left - self.left_power);
+            self.right_power += coeff * (right * right - self.right_power);
+        }
+    }
+
+    pub fn correlation(&self) -> Sample {
+        let power = (self.left_power * self.right_power).sqrt();
+
+        if power < MIN_POWER {
+            return 0.0;
+        }
+
+        (self.product / power).clamp(-1.0, 1.0)
+    }
+}
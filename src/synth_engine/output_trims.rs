@@ -0,0 +1,90 @@
+use nih_plug::util::db_to_gain_fast;
+
+use crate::synth_engine::{MAX_MODULES, Sample, config::OutputTrimConfig, routing::ModuleId};
+
+pub const MAX_OUTPUT_TRIM: Sample = 24.0; // dB
+
+struct Trim {
+    config: OutputTrimConfig,
+    applied: Sample,
+}
+
+impl Trim {
+    fn target(&self) -> Sample {
+        let gain = db_to_gain_fast(self.config.gain);
+
+        if self.config.invert { -gain } else { gain }
+    }
+}
+
+// Output gain and polarity of the modules, a module without a trim keeps its output as is
+pub struct OutputTrims {
+    trims: Vec<Trim>,
+}
+
+impl OutputTrims {
+    // Trims set from the editor are applied on the audio thread, the room is reserved up front
+    pub fn new() -> Self {
+        Self {
+            trims: Vec::with_capacity(MAX_MODULES),
+        }
+    }
+
+    pub fn get_config(&self) -> Vec<OutputTrimConfig> {
+        self.trims.iter().map(|trim| trim.config.clone()).collect()
+    }
+
+    pub fn get(&self, module_id: ModuleId) -> Option<&OutputTrimConfig> {
+        self.trims
+            .iter()
+            .map(|trim| &trim.config)
+            .find(|config| config.module_id == module_id)
+    }
+
+    pub fn set(&mut self, mut config: OutputTrimConfig) {
+        config.gain = config.gain.clamp(-MAX_OUTPUT_TRIM, MAX_OUTPUT_TRIM);
+
+        match self
+            .trims
+            .iter_mut()
+            .find(|trim| trim.config.module_id == config.module_id)
+        {
+            Some(trim) => trim.config = config,
+            None => {
+                let mut trim = Trim {
+                    config,
+                    applied: 0.0,
+                };
+
+                // New trims start at their gain, changes of an existing one ramp over a block
+                trim.applied = trim.target();
+                self.trims.push(trim);
+            }
+        }
+    }
+
+    pub fn remove_module(&mut self, module_id: ModuleId) {
+        self.trims.retain(|trim| trim.config.module_id != module_id);
+    }
+
+    // Gains at the start and the end of the block, None for modules left as is. A trim back at
+    // unity is dropped once its last ramp is done.
+    pub fn next_gains(&mut self, module_id: ModuleId) -> Option<(Sample, Sample)> {
+        let idx = self
+            .trims
+            .iter()
+            .position(|trim| trim.config.module_id == module_id)?;
+        let trim = &mut self.trims[idx];
+        let from = trim.applied;
+        let to = trim.target();
+
+        trim.applied = to;
+
+        if from == 1.0 && to == 1.0 {
+            self.trims.swap_remove(idx);
+            return None;
+        }
+
+        Some((from, to))
+    }
+}
@@ -0,0 +1,105 @@
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::synth_engine::{
+    Sample,
+    buffer::{BUFFER_SIZE, Buffer, zero_buffer},
+    iir_decimator::IirDecimator,
+    routing::NUM_CHANNELS,
+};
+
+// Modules process at most this many samples per block, longer oversampled blocks are split
+pub const MAX_OVERSAMPLED_BLOCK: usize = BUFFER_SIZE - 1;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum Oversampling {
+    #[default]
+    Off,
+    X2,
+    X4,
+}
+
+impl Oversampling {
+    pub fn factor(self) -> usize {
+        match self {
+            Self::Off => 1,
+            Self::X2 => 2,
+            Self::X4 => 4,
+        }
+    }
+
+    // Group delay of the decimator at low frequencies in host samples, rounded. Each half-band
+    // stage delays about 2.4 samples of its output rate.
+    pub fn latency(self) -> u32 {
+        match self {
+            Self::Off => 0,
+            Self::X2 => 2,
+            Self::X4 => 4,
+        }
+    }
+}
+
+// Patches saved before 4x was added store a bool
+impl<'de> Deserialize<'de> for Oversampling {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        enum Mode {
+            Off,
+            X2,
+            X4,
+        }
+
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Repr {
+            Enabled(bool),
+            Mode(Mode),
+        }
+
+        Ok(match Repr::deserialize(deserializer)? {
+            Repr::Enabled(false) | Repr::Mode(Mode::Off) => Self::Off,
+            Repr::Enabled(true) | Repr::Mode(Mode::X2) => Self::X2,
+            Repr::Mode(Mode::X4) => Self::X4,
+        })
+    }
+}
+
+// Cascade of half-band stages, each one halves the rate
+pub struct Decimator {
+    stages: [IirDecimator; 2],
+    intermediate: [Buffer; NUM_CHANNELS],
+}
+
+impl Decimator {
+    pub fn new() -> Self {
+        Self {
+            stages: [IirDecimator::new(), IirDecimator::new()],
+            intermediate: [zero_buffer(), zero_buffer()],
+        }
+    }
+
+    /// `input` holds `output.len() * oversampling.factor()` samples per channel.
+    pub fn process(
+        &mut self,
+        oversampling: Oversampling,
+        input: [&[Sample]; NUM_CHANNELS],
+        output: [&mut [Sample]; NUM_CHANNELS],
+    ) {
+        let [first, second] = &mut self.stages;
+
+        match oversampling {
+            Oversampling::Off => {
+                for (output, input) in output.into_iter().zip(input) {
+                    output.copy_from_slice(&input[..output.len()]);
+                }
+            }
+            Oversampling::X2 => first.process(input, output),
+            Oversampling::X4 => {
+                let samples = 2 * output[0].len();
+                let [left, right] = &mut self.intermediate;
+
+                first.process(input, [&mut left[..samples], &mut right[..samples]]);
+                second.process([&left[..samples], &right[..samples]], output);
+            }
+        }
+    }
+}
@@ -0,0 +1,31 @@
+use std::time::{Duration, Instant};
+
+use crate::synth_engine::{Sample, routing::ModuleId};
+
+// Processing time per module, only the blocks reported to the UI are measured
+pub struct ModuleProfiler {
+    timings: Vec<(ModuleId, Duration)>,
+}
+
+impl ModuleProfiler {
+    /// Allocates, built with the routing tables from the modules in execution order.
+    pub fn new(modules: &[ModuleId]) -> Self {
+        Self {
+            timings: modules.iter().map(|id| (*id, Duration::ZERO)).collect(),
+        }
+    }
+
+    pub fn add(&mut self, module_id: ModuleId, started: Instant) {
+        if let Some((_, time)) = self.timings.iter_mut().find(|(id, _)| *id == module_id) {
+            *time += started.elapsed();
+        }
+    }
+
+    /// Reports the share of the `block_duration` (seconds) real-time budget per module.
+    pub fn flush(&mut self, block_duration: Sample, mut report: impl FnMut(ModuleId, Sample)) {
+        for (module_id, time) in &mut self.timings {
+            report(*module_id, time.as_secs_f32() / block_duration);
+            *time = Duration::ZERO;
+        }
+    }
+}
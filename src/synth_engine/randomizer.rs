@@ -0,0 +1,331 @@
+use rand::RngExt;
+use rand_pcg::Pcg32;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    synth_engine::{
+        Amplifier, Chorus, Delay, EngineConfig, Envelope, EnvelopeFollower, Expressions,
+        ExternalParam, Filter, HARMONIC_SERIES_BUFFER, HarmonicComb, HarmonicEditor,
+        HarmonicEnvelopes, HarmonicShift, HarmonicStretch, Lfo, Math, MidiControl, Mixer,
+        ModuleConfig, ModuleId, ModuleType, Oscillator, Panner, Reverb, RingMod, Sample,
+        SampleResynth, SpectralAnalyzer, SpectralBlend, SpectralDynamics, SpectralFilter,
+        SpectralMixer, SpectralSize, SpectralTilt, StereoSample, WaveShaper,
+        harmonic_editor::{ComplexCfg, HarmonicEditorConfig},
+    },
+    utils::from_ms,
+};
+
+const MIN_TIME: Sample = from_ms(1.0);
+const MAX_TIME: Sample = 2.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RandomParam {
+    Harmonics,
+    Attack,
+    Decay,
+    Sustain,
+    Release,
+    Detune,
+    Drive,
+    Rate,
+    Depth,
+    Mix,
+    Time,
+    Feedback,
+    Cutoff,
+    Resonance,
+    Rolloff,
+    Spacing,
+    EvenOdd,
+    Spread,
+    Shift,
+    Stretch,
+    Skew,
+    Width,
+    Size,
+    Damping,
+    Position,
+    Blend,
+    Threshold,
+    Ratio,
+    Tilt,
+    Pivot,
+}
+
+impl RandomParam {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Harmonics => "Harmonics",
+            Self::Attack => "Attack",
+            Self::Decay => "Decay",
+            Self::Sustain => "Sustain",
+            Self::Release => "Release",
+            Self::Detune => "Detune",
+            Self::Drive => "Drive",
+            Self::Rate => "Rate",
+            Self::Depth => "Depth",
+            Self::Mix => "Mix",
+            Self::Time => "Time",
+            Self::Feedback => "Feedback",
+            Self::Cutoff => "Cutoff",
+            Self::Resonance => "Resonance",
+            Self::Rolloff => "Rolloff",
+            Self::Spacing => "Spacing",
+            Self::EvenOdd => "Even/Odd",
+            Self::Spread => "Spread",
+            Self::Shift => "Shift",
+            Self::Stretch => "Stretch",
+            Self::Skew => "Skew",
+            Self::Width => "Width",
+            Self::Size => "Size",
+            Self::Damping => "Damping",
+            Self::Position => "Position",
+            Self::Blend => "Blend",
+            Self::Threshold => "Threshold",
+            Self::Ratio => "Ratio",
+            Self::Tilt => "Tilt",
+            Self::Pivot => "Pivot",
+        }
+    }
+
+    // Has to list what the module's randomize hook touches
+    pub fn for_module(module_type: ModuleType) -> &'static [Self] {
+        match module_type {
+            ModuleType::Oscillator => &[Self::Detune],
+            ModuleType::Envelope => &[Self::Attack, Self::Decay, Self::Sustain, Self::Release],
+            ModuleType::Lfo => &[Self::Rate, Self::Skew],
+            ModuleType::Amplifier => &[Self::Drive],
+            ModuleType::WaveShaper => &[Self::Drive],
+            ModuleType::SpectralFilter => &[Self::Cutoff, Self::Resonance],
+            ModuleType::SpectralBlend => &[Self::Blend],
+            ModuleType::SpectralTilt => &[Self::Tilt, Self::Pivot],
+            ModuleType::HarmonicStretch => &[Self::Stretch],
+            ModuleType::HarmonicShift => &[Self::Shift],
+            ModuleType::SpectralDynamics => &[Self::Threshold, Self::Ratio],
+            ModuleType::HarmonicEnvelopes => &[Self::Attack, Self::Decay, Self::Spread],
+            ModuleType::SampleResynth => &[Self::Position],
+            ModuleType::HarmonicEditor => &[Self::Harmonics],
+            ModuleType::Chorus => &[Self::Rate, Self::Depth, Self::Mix],
+            ModuleType::Delay => &[Self::Time, Self::Feedback, Self::Mix],
+            ModuleType::Reverb => &[Self::Size, Self::Damping, Self::Mix],
+            ModuleType::RingMod => &[Self::Mix],
+            ModuleType::Panner => &[Self::Width],
+            ModuleType::Filter => &[Self::Cutoff, Self::Resonance],
+            ModuleType::HarmonicComb => &[Self::Rolloff, Self::Spacing, Self::EvenOdd],
+            ModuleType::EnvelopeFollower => &[Self::Attack, Self::Release],
+            _ => &[],
+        }
+    }
+}
+
+// Moves parameters towards random targets, amount 0 keeps the patch and 1 replaces the values.
+// Each module picks its parameters and ranges in its own randomize hook.
+pub struct Randomizer {
+    random: Pcg32,
+    amount: Sample,
+    spectral_size: SpectralSize,
+}
+
+impl Randomizer {
+    pub fn new(seed: u64, amount: Sample) -> Self {
+        Self {
+            random: Pcg32::new(seed, 1337),
+            amount: amount.clamp(0.0, 1.0),
+            spectral_size: SpectralSize::default(),
+        }
+    }
+
+    // Both channels move towards the same target
+    pub fn stereo(&mut self, value: StereoSample, min: Sample, max: Sample) -> StereoSample {
+        let target = self.random.random_range(min..=max);
+
+        value.map(|value| value + (target - value) * self.amount)
+    }
+
+    // Moves in log space, for ranges spanning decades
+    pub fn log(&mut self, value: StereoSample, min: Sample, max: Sample) -> StereoSample {
+        let log = value.map(|value| value.max(min).ln());
+
+        self.stereo(log, min.ln(), max.ln()).map(Sample::exp)
+    }
+
+    // Short and long times are equally likely
+    pub fn time(&mut self, value: StereoSample) -> StereoSample {
+        self.log(value, MIN_TIME, MAX_TIME)
+    }
+
+    // Harmonics the oscillators leave out keep their values
+    pub fn harmonics(&mut self, config: &mut HarmonicEditorConfig) {
+        for idx in 1..=self.spectral_size.harmonics() {
+            // Squared, most harmonics stay quiet
+            let target = self.random.random::<Sample>().powi(2);
+
+            for (spectrum, phases) in config.spectrum.iter_mut().zip(&config.phases) {
+                let Some(harmonic) = spectrum.get_mut(idx) else {
+                    continue;
+                };
+
+                let gain = harmonic.complex().norm() / HARMONIC_SERIES_BUFFER[idx].norm();
+                let gain = gain + (target - gain) * self.amount;
+                let phase = phases.get(idx).copied().unwrap_or(0.0);
+
+                *harmonic =
+                    ComplexCfg::from_complex(HarmonicEditor::harmonic_value(idx, gain, phase));
+            }
+        }
+    }
+
+    pub fn randomize_patch(
+        &mut self,
+        config: &mut EngineConfig,
+        locked: impl Fn(ModuleId, RandomParam) -> bool,
+    ) {
+        self.spectral_size = config.engine.spectral_size;
+
+        for module in &mut config.modules {
+            let id = module.id();
+            let unlocked = |param| !locked(id, param);
+
+            match module {
+                ModuleConfig::Oscillator(config) => Oscillator::randomize(config, self, unlocked),
+                ModuleConfig::Envelope(config) => Envelope::randomize(config, self, unlocked),
+                ModuleConfig::Lfo(config) => Lfo::randomize(config, self, unlocked),
+                ModuleConfig::Amplifier(config) => Amplifier::randomize(config, self, unlocked),
+                ModuleConfig::Mixer(config) => Mixer::randomize(config, self, unlocked),
+                ModuleConfig::WaveShaper(config) => WaveShaper::randomize(config, self, unlocked),
+                ModuleConfig::SpectralFilter(config) => {
+                    SpectralFilter::randomize(config, self, unlocked)
+                }
+                ModuleConfig::SpectralBlend(config) => {
+                    SpectralBlend::randomize(config, self, unlocked)
+                }
+                ModuleConfig::SpectralMixer(config) => {
+                    SpectralMixer::randomize(config, self, unlocked)
+                }
+                ModuleConfig::SpectralTilt(config) => {
+                    SpectralTilt::randomize(config, self, unlocked)
+                }
+                ModuleConfig::HarmonicStretch(config) => {
+                    HarmonicStretch::randomize(config, self, unlocked)
+                }
+                ModuleConfig::HarmonicShift(config) => {
+                    HarmonicShift::randomize(config, self, unlocked)
+                }
+                ModuleConfig::SpectralDynamics(config) => {
+                    SpectralDynamics::randomize(config, self, unlocked)
+                }
+                ModuleConfig::HarmonicEnvelopes(config) => {
+                    HarmonicEnvelopes::randomize(config, self, unlocked)
+                }
+                ModuleConfig::SampleResynth(config) => {
+                    SampleResynth::randomize(config, self, unlocked)
+                }
+                ModuleConfig::HarmonicEditor(config) => {
+                    HarmonicEditor::randomize(config, self, unlocked)
+                }
+                ModuleConfig::Expressions(config) => Expressions::randomize(config, self, unlocked),
+                ModuleConfig::ExternalParam(config) => {
+                    ExternalParam::randomize(config, self, unlocked)
+                }
+                ModuleConfig::MidiControl(config) => MidiControl::randomize(config, self, unlocked),
+                ModuleConfig::Chorus(config) => Chorus::randomize(config, self, unlocked),
+                ModuleConfig::Delay(config) => Delay::randomize(config, self, unlocked),
+                ModuleConfig::Reverb(config) => Reverb::randomize(config, self, unlocked),
+                ModuleConfig::RingMod(config) => RingMod::randomize(config, self, unlocked),
+                ModuleConfig::Panner(config) => Panner::randomize(config, self, unlocked),
+                ModuleConfig::Filter(config) => Filter::randomize(config, self, unlocked),
+                ModuleConfig::Math(config) => Math::randomize(config, self, unlocked),
+                ModuleConfig::HarmonicComb(config) => {
+                    HarmonicComb::randomize(config, self, unlocked)
+                }
+                ModuleConfig::EnvelopeFollower(config) => {
+                    EnvelopeFollower::randomize(config, self, unlocked)
+                }
+                ModuleConfig::SpectralAnalyzer(config) => {
+                    SpectralAnalyzer::randomize(config, self, unlocked)
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::synth_engine::{EngineParams, SPECTRAL_BUFFER_SIZE, envelope::EnvelopeConfig};
+
+    fn envelope_patch() -> EngineConfig {
+        EngineConfig {
+            modules: vec![ModuleConfig::Envelope(Box::new(EnvelopeConfig {
+                id: 1,
+                ..Default::default()
+            }))],
+            ..Default::default()
+        }
+    }
+
+    fn envelope(config: &EngineConfig) -> &EnvelopeConfig {
+        match &config.modules[0] {
+            ModuleConfig::Envelope(config) => config,
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn zero_amount_keeps_the_patch() {
+        let mut config = envelope_patch();
+        let attack = envelope(&config).attack;
+
+        Randomizer::new(1, 0.0).randomize_patch(&mut config, |_, _| false);
+
+        assert!((envelope(&config).attack.left() - attack.left()).abs() < 1e-6);
+    }
+
+    #[test]
+    fn locked_params_are_unchanged() {
+        let mut config = envelope_patch();
+        let before = envelope(&config).clone();
+
+        Randomizer::new(7, 1.0)
+            .randomize_patch(&mut config, |_, param| param != RandomParam::Sustain);
+
+        let after = envelope(&config);
+
+        assert_eq!(after.attack, before.attack);
+        assert_eq!(after.decay, before.decay);
+        assert_eq!(after.release, before.release);
+        assert_ne!(after.sustain, before.sustain);
+        assert_eq!(after.sustain.left(), after.sustain.right());
+    }
+
+    #[test]
+    fn harmonics_above_the_limit_are_unchanged() {
+        let mut config = EngineConfig {
+            modules: vec![ModuleConfig::HarmonicEditor(Box::new(
+                HarmonicEditorConfig {
+                    id: 1,
+                    ..Default::default()
+                },
+            ))],
+            engine: EngineParams {
+                spectral_size: SpectralSize::S256,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let before = HarmonicEditorConfig::default().spectrum;
+
+        Randomizer::new(3, 1.0).randomize_patch(&mut config, |_, _| false);
+
+        let ModuleConfig::HarmonicEditor(after) = &config.modules[0] else {
+            unreachable!();
+        };
+        let changed = |idx: usize| {
+            (after.spectrum[0][idx].complex() - before[0][idx].complex()).norm() > 1e-6
+        };
+        let limit = SpectralSize::S256.harmonics();
+
+        assert!((1..=limit).any(changed));
+        assert!(!(limit + 1..SPECTRAL_BUFFER_SIZE).any(changed));
+    }
+}
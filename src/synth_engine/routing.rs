@@ -1,7 +1,10 @@
 use serde::{Deserialize, Serialize};
 use smallvec::SmallVec;
 
-use crate::synth_engine::{Sample, StereoSample};
+use crate::synth_engine::{
+    Sample, StereoSample,
+    curves::{CurveFunction, Exponential},
+};
 
 mod outputs;
 mod outputs_arena;
@@ -9,7 +12,7 @@ mod process_context;
 mod voice_router;
 
 pub use outputs::{SamplesOutput, SpectralOutput};
-pub use outputs_arena::OutputsArena;
+pub use outputs_arena::{ModuleOutput, OutputsArena};
 pub use process_context::{ProcessContext, ProcessParams};
 pub use voice_router::{
     AudioRouterType, ControlRouterType, OutputRouterType, RouterFactory, SpectralRouterType,
@@ -22,15 +25,18 @@ pub const MAX_VOICES: usize = 24;
 pub const NUM_CHANNELS: usize = 2;
 pub const OUTPUT_MODULE_ID: ModuleId = 0;
 pub const MIN_MODULE_ID: ModuleId = 1;
+// Sources linked into one input, the slots are kept inline and copied without allocating
+pub const MAX_INPUT_SOURCES: usize = 8;
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub enum DataType {
     Audio,
     Control,
     Spectral,
+    Bus, // Summed voices, processed after the per-voice graph
 }
 
-#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash, Serialize, Deserialize)]
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash, Serialize, Deserialize)]
 pub enum Input {
     Audio,
     AudioMix(u8),
@@ -64,6 +70,59 @@ pub enum Input {
     Decay,
     Sustain,
     Release,
+    Size,
+    Damping,
+    PreDelay,
+    DryWet,
+    Mix,
+    Tilt,    // dB/octave
+    Pivot,   // Octaves above the fundamental
+    Stretch, // Harmonic n moves to n^(1 + stretch)
+    HarmonicShift,
+    Threshold, // dB
+    Ratio,
+    Makeup,      // dB
+    DecaySpread, // Decay octaves per harmonic octave
+    Position,    // 0.0 - 1.0
+    PhaseMod,
+    FmIndex,   // Radians
+    Feedback,  // 0.0 - 1.0
+    Pan,       // -1.0 - 1.0
+    Width,     // 0.0 - 2.0
+    Drift,     // Octaves
+    DriftRate, // Hz
+    ValueA,
+    ValueB,
+    Partials,
+    Rolloff, // dB/octave
+    CombSpacing,
+    EvenOdd, // -1.0 - 1.0
+    SplitHarmonic,
+    CrossoverWidth, // Harmonics
+    FadeIn,
+}
+
+impl Input {
+    // Signal inputs only take links, everything else has a base value
+    pub fn has_value(&self) -> bool {
+        !matches!(
+            self,
+            Self::Audio
+                | Self::AudioMix(_)
+                | Self::PhaseMod
+                | Self::Spectrum
+                | Self::SpectrumMix(_)
+                | Self::SpectrumTo
+        )
+    }
+
+    // Inputs where scaling the value by a link makes sense as well as adding to it
+    pub fn supports_link_mode(&self) -> bool {
+        matches!(
+            self,
+            Self::Gain | Self::GainMix(_) | Self::Level | Self::LevelMix(_) | Self::Cutoff
+        )
+    }
 }
 
 #[derive(Clone, Copy)]
@@ -71,6 +130,7 @@ pub struct InputMeta {
     pub input_type: Input,
     pub data_type: DataType,
     pub is_direct: bool,
+    pub audio_rate: bool, // Modulation is followed per sample rather than once per block
 }
 
 impl InputMeta {
@@ -79,6 +139,7 @@ impl InputMeta {
             input_type: input,
             data_type: DataType::Audio,
             is_direct: true,
+            audio_rate: false,
         }
     }
 
@@ -87,6 +148,7 @@ impl InputMeta {
             input_type: input,
             data_type: DataType::Audio,
             is_direct: false,
+            audio_rate: false,
         }
     }
 
@@ -95,6 +157,23 @@ impl InputMeta {
             input_type: input,
             data_type: DataType::Control,
             is_direct: false,
+            audio_rate: false,
+        }
+    }
+
+    pub const fn control_audio_rate(input: Input) -> Self {
+        Self {
+            audio_rate: true,
+            ..Self::control(input)
+        }
+    }
+
+    pub const fn bus(input: Input) -> Self {
+        Self {
+            input_type: input,
+            data_type: DataType::Bus,
+            is_direct: true,
+            audio_rate: false,
         }
     }
 
@@ -103,6 +182,7 @@ impl InputMeta {
             input_type: input,
             data_type: DataType::Spectral,
             is_direct: true,
+            audio_rate: false,
         }
     }
 }
@@ -116,6 +196,8 @@ pub enum Expression {
     Pitch,
     Timbre,
     Pressure,
+    Vibrato, // CLAP vibrato depth
+    Swell,   // CLAP expression, the per-note counterpart of CC 11
 }
 
 #[derive(Debug)]
@@ -125,11 +207,18 @@ pub enum VoiceEvent {
         prev_voice_idx: Option<usize>,
         pitch: Sample,
         velocity: Sample,
+        glide: bool,
     },
     Update {
         voice_idx: usize,
         pitch: Sample,
         velocity: Sample,
+        glide: bool,
+    },
+    // Pitch change of a sounding voice without touching anything else
+    Retune {
+        voice_idx: usize,
+        pitch: Sample,
     },
     Release {
         voice_idx: usize,
@@ -169,6 +258,112 @@ pub enum MixType {
     Multiply,
 }
 
+// Response curve of a link, shapes the source before the link amount is applied
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum LinkCurve {
+    #[default]
+    Linear,
+    Exponential(Sample), // Curvature -1..1
+    SCurve(Sample),      // Steepness 0..1
+    Steps(u8),           // Number of steps between 0 and 1
+}
+
+impl LinkCurve {
+    pub const MAX_STEPS: u8 = 32;
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Linear => "Linear",
+            Self::Exponential(_) => "Exp",
+            Self::SCurve(_) => "S-Curve",
+            Self::Steps(_) => "Steps",
+        }
+    }
+
+    // Shapes the magnitude so bipolar sources stay symmetric, values beyond 1 pass through
+    #[inline]
+    pub fn apply(&self, value: Sample) -> Sample {
+        let magnitude = value.abs();
+
+        if magnitude > 1.0 {
+            return value;
+        }
+
+        let shaped = match *self {
+            Self::Linear => return value,
+            Self::Exponential(curvature) => Exponential::new(curvature).calc(magnitude),
+            Self::SCurve(steepness) => {
+                let smooth = magnitude * magnitude * (3.0 - 2.0 * magnitude);
+                magnitude + (smooth - magnitude) * steepness.clamp(0.0, 1.0)
+            }
+            Self::Steps(steps) => {
+                let steps = steps.max(1) as Sample;
+                (magnitude * steps).round() / steps
+            }
+        };
+
+        shaped.copysign(value)
+    }
+}
+
+// Range conversion of a link source, applied before the response curve
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum LinkPolarity {
+    #[default]
+    Unchanged,
+    Unipolar, // -1..1 mapped to 0..1
+    Bipolar,  // 0..1 mapped to -1..1
+    Rectified,
+}
+
+impl LinkPolarity {
+    pub const ALL: [Self; 4] = [
+        Self::Unchanged,
+        Self::Unipolar,
+        Self::Bipolar,
+        Self::Rectified,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Unchanged => "As Is",
+            Self::Unipolar => "Unipolar",
+            Self::Bipolar => "Bipolar",
+            Self::Rectified => "Rectified",
+        }
+    }
+
+    #[inline]
+    pub fn apply(&self, value: Sample) -> Sample {
+        match self {
+            Self::Unchanged => value,
+            Self::Unipolar => value * 0.5 + 0.5,
+            Self::Bipolar => value * 2.0 - 1.0,
+            Self::Rectified => value.abs(),
+        }
+    }
+}
+
+// How a link applies to the input value. Multiplying links scale the value, additive links
+// included, by one plus the link output.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum LinkMode {
+    #[default]
+    Add,
+    Multiply,
+}
+
+impl LinkMode {
+    pub const ALL: [Self; 2] = [Self::Add, Self::Multiply];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Add => "Add",
+            Self::Multiply => "Multiply",
+        }
+    }
+}
+
 #[derive(Debug, PartialEq, Eq, Clone, Copy, Hash, Serialize, Deserialize)]
 pub struct InputId {
     pub input_type: Input,
@@ -189,6 +384,10 @@ pub(super) struct ModuleLink {
     pub src: ModuleId,
     pub dst: InputId,
     pub amount: StereoSample,
+    pub curve: LinkCurve,
+    pub polarity: LinkPolarity,
+    pub offset: Sample,
+    pub mode: LinkMode,
     pub modulation: Option<ModuleId>,
 }
 
@@ -198,6 +397,10 @@ impl ModuleLink {
             src,
             dst,
             amount: StereoSample::ONE,
+            curve: LinkCurve::Linear,
+            polarity: LinkPolarity::Unchanged,
+            offset: 0.0,
+            mode: LinkMode::Add,
             modulation: None,
         }
     }
@@ -207,15 +410,36 @@ impl ModuleLink {
             src,
             dst,
             amount: amount.into(),
+            curve: LinkCurve::Linear,
+            polarity: LinkPolarity::Unchanged,
+            offset: 0.0,
+            mode: LinkMode::Add,
             modulation: None,
         }
     }
+
+    // The link as seen from its destination input
+    pub fn source(&self) -> InputSource {
+        InputSource {
+            module_id: self.src,
+            amount: self.amount,
+            curve: self.curve,
+            polarity: self.polarity,
+            offset: self.offset,
+            mode: self.mode,
+            modulation: self.modulation,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy)]
 pub struct InputSource {
     pub module_id: ModuleId,
     pub amount: StereoSample,
+    pub curve: LinkCurve,
+    pub polarity: LinkPolarity,
+    pub offset: Sample,
+    pub mode: LinkMode,
     pub modulation: Option<ModuleId>,
 }
 
@@ -234,7 +458,9 @@ impl InputSource {
 }
 
 pub fn data_types_compatible(src: DataType, dst: DataType) -> bool {
-    src == dst || (dst == DataType::Audio && src == DataType::Control)
+    src == dst
+        || (dst == DataType::Audio && src == DataType::Control)
+        || (dst == DataType::Bus && matches!(src, DataType::Audio | DataType::Control))
 }
 
 #[derive(Clone)]
@@ -242,19 +468,31 @@ pub struct InputSlot {
     pub src_slot: usize,
     pub modulation_slot: Option<usize>,
     pub amount: StereoSample,
+    pub curve: LinkCurve,
+    pub polarity: LinkPolarity,
+    pub offset: Sample,
+    pub mode: LinkMode,
+}
+
+impl InputSlot {
+    // Source value as seen by the input, before the link amount
+    #[inline]
+    pub fn shape(&self, value: Sample) -> Sample {
+        self.curve.apply(self.polarity.apply(value)) + self.offset
+    }
 }
 
 #[derive(Clone)]
 pub struct InputSlots {
     pub input_type: Input,
-    pub slots: Vec<InputSlot>,
+    pub slots: SmallVec<[InputSlot; MAX_INPUT_SOURCES]>,
 }
 
 impl InputSlots {
     pub fn empty(input_type: Input) -> Self {
         Self {
             input_type,
-            slots: Vec::new(),
+            slots: SmallVec::new(),
         }
     }
 
@@ -262,9 +500,13 @@ impl InputSlots {
         self.slots.first().map(|s| s.src_slot)
     }
 
-    pub fn update_amount(&mut self, slot: usize, amount: StereoSample) {
+    pub fn update_source(&mut self, slot: usize, source: &InputSource) {
         if let Some(src) = self.slots.iter_mut().find(|src| src.src_slot == slot) {
-            src.amount = amount
+            src.amount = source.amount;
+            src.curve = source.curve;
+            src.polarity = source.polarity;
+            src.offset = source.offset;
+            src.mode = source.mode;
         }
     }
 }
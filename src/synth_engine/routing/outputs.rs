@@ -1,6 +1,6 @@
 use crate::synth_engine::{
     Buffer, Sample, SpectralBuffer,
-    buffer::{zero_buffer, zero_spectral_buffer},
+    buffer::{ZEROES_SPECTRAL_BUFFER, zero_buffer, zero_spectral_buffer},
 };
 
 pub struct SamplesOutput {
@@ -75,8 +75,12 @@ impl<'a> Drop for ControlRateAdapter<'a> {
     }
 }
 
+// Modules that skip `advance` leave the latest buffer and the revision untouched,
+// consumers compare revisions to find out whether the spectrum has changed
 pub struct SpectralOutput {
     swapped: bool,
+    revision: u32,
+    passed: Option<(u32, usize)>, // Input revision and bins forwarded by a bypassed module
     output: [SpectralBuffer; 2],
 }
 
@@ -84,6 +88,8 @@ impl Default for SpectralOutput {
     fn default() -> Self {
         Self {
             swapped: false,
+            revision: 0,
+            passed: None,
             output: [zero_spectral_buffer(), zero_spectral_buffer()],
         }
     }
@@ -94,8 +100,32 @@ impl SpectralOutput {
         &self.output[(!triggered ^ self.swapped) as usize]
     }
 
+    pub(super) fn revision(&self) -> u32 {
+        self.revision
+    }
+
     pub fn advance(&mut self) -> &mut SpectralBuffer {
         self.swapped = !self.swapped;
+        self.revision = self.revision.wrapping_add(1);
+        self.passed = None;
         &mut self.output[!self.swapped as usize]
     }
+
+    // Both buffers get the input, so voices triggered in this block read it too
+    pub(super) fn pass_through(&mut self, input: Option<&SpectralOutput>, bins: usize) {
+        let input_revision = input.map_or(0, SpectralOutput::revision);
+
+        if self.passed == Some((input_revision, bins)) {
+            return;
+        }
+
+        let spectrum = input.map_or(&ZEROES_SPECTRAL_BUFFER, |input| input.get(false));
+
+        for output in &mut self.output {
+            output[..bins].copy_from_slice(&spectrum[..bins]);
+        }
+
+        self.revision = self.revision.wrapping_add(1);
+        self.passed = Some((input_revision, bins));
+    }
 }
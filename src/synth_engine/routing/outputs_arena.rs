@@ -5,7 +5,7 @@ use crate::synth_engine::{
     buffer::{VoicesLayout, VoicesLayoutArray, add_to_buffer, new_voices_layout},
     module_handle::ModuleHandle,
     routing::{
-        DataType, InputSlot,
+        DataType, InputSlot, LinkMode, NUM_CHANNELS, ProcessParams,
         outputs::{SamplesOutput, SpectralOutput},
     },
 };
@@ -14,14 +14,6 @@ pub(super) struct ArenaSlot<T: Default + Send> {
     pub(super) slot: Option<VoicesLayout<T>>,
 }
 
-impl<T: Default + Send> Default for ArenaSlot<T> {
-    fn default() -> Self {
-        Self {
-            slot: Some(new_voices_layout()),
-        }
-    }
-}
-
 impl<T: Default + Send> Deref for ArenaSlot<T> {
     type Target = VoicesLayoutArray<T>;
 
@@ -40,76 +32,228 @@ impl<T: Default + Send> DerefMut for ArenaSlot<T> {
     }
 }
 
+// Output buffers of a module while it's outside of the arena, allocated and freed off the
+// audio thread
+pub enum ModuleOutput {
+    Samples(VoicesLayout<SamplesOutput>),
+    Spectral(VoicesLayout<SpectralOutput>),
+    None,
+}
+
+impl ModuleOutput {
+    pub fn new(data_type: DataType) -> Self {
+        match data_type {
+            DataType::Audio | DataType::Control => Self::Samples(new_voices_layout()),
+            DataType::Spectral => Self::Spectral(new_voices_layout()),
+            DataType::Bus => Self::None,
+        }
+    }
+}
+
 pub struct OutputsArena {
     pub(super) samples: Vec<ArenaSlot<SamplesOutput>>,
     pub(super) spectral: Vec<ArenaSlot<SpectralOutput>>,
-    free_samples_slots: Vec<usize>,
-    free_spectral_slots: Vec<usize>,
 }
 
 impl OutputsArena {
-    pub fn new() -> Self {
+    // Slots are only pushed up to the capacity, inserting a staged module never reallocates
+    pub fn new(capacity: usize) -> Self {
         Self {
-            samples: Vec::new(),
-            spectral: Vec::new(),
-            free_samples_slots: Vec::new(),
-            free_spectral_slots: Vec::new(),
+            samples: Vec::with_capacity(capacity),
+            spectral: Vec::with_capacity(capacity),
         }
     }
 
-    fn allocate_impl<T: Default + Send>(
+    // The lowest free slot, the editor picks the slots of staged modules by the same rule
+    fn free_index<T: Default + Send>(slots: &[ArenaSlot<T>]) -> usize {
+        slots
+            .iter()
+            .position(|slot| slot.slot.is_none())
+            .unwrap_or(slots.len())
+    }
+
+    fn insert_impl<T: Default + Send>(
         slots: &mut Vec<ArenaSlot<T>>,
-        free_slots: &mut Vec<usize>,
-    ) -> usize {
-        if let Some(slot_idx) = free_slots.pop() {
-            slots[slot_idx].slot = Some(new_voices_layout());
-            slot_idx
-        } else {
-            slots.push(ArenaSlot::default());
-            slots.len() - 1
+        slot: usize,
+        layout: VoicesLayout<T>,
+    ) {
+        debug_assert!(
+            slot < slots.capacity(),
+            "output slot {slot} is past the preallocated arena"
+        );
+
+        if slot >= slots.len() {
+            slots.resize_with(slot + 1, || ArenaSlot { slot: None });
         }
+
+        slots[slot].slot = Some(layout);
     }
 
-    fn allocate_samples_slot(&mut self) -> usize {
-        Self::allocate_impl(&mut self.samples, &mut self.free_samples_slots)
+    // Fills the capacity with empty slots, a module added live only takes one of them
+    pub fn preallocate(&mut self) {
+        Self::preallocate_impl(&mut self.samples);
+        Self::preallocate_impl(&mut self.spectral);
     }
 
-    fn allocate_spectral_slot(&mut self) -> usize {
-        Self::allocate_impl(&mut self.spectral, &mut self.free_spectral_slots)
+    fn preallocate_impl<T: Default + Send>(slots: &mut Vec<ArenaSlot<T>>) {
+        slots.resize_with(slots.capacity(), || ArenaSlot { slot: None });
     }
 
-    fn free_impl<T: Default + Send>(
-        slots: &mut [ArenaSlot<T>],
-        free_slots: &mut Vec<usize>,
-        slot: usize,
-    ) {
-        assert!(slot < slots.len());
+    pub fn allocate_slot(&mut self, module: &mut ModuleHandle) {
+        let data_type = module.output_type();
 
-        slots[slot].slot = None;
-        free_slots.push(slot);
+        match data_type {
+            DataType::Audio | DataType::Control => {
+                module.set_output_slot(Self::free_index(&self.samples))
+            }
+            DataType::Spectral => module.set_output_slot(Self::free_index(&self.spectral)),
+            DataType::Bus => module.set_output_slot(usize::MAX),
+        }
+
+        self.insert_output(module, ModuleOutput::new(data_type));
+    }
+
+    pub fn free_slot(&mut self, module: &ModuleHandle) {
+        drop(self.take_output(module));
     }
 
-    fn free_samples_slot(&mut self, slot: usize) {
-        Self::free_impl(&mut self.samples, &mut self.free_samples_slots, slot);
+    // Puts the buffers at the output slot already set on the module
+    pub fn insert_output(&mut self, module: &ModuleHandle, output: ModuleOutput) {
+        let slot = module.output_slot();
+
+        match output {
+            ModuleOutput::Samples(layout) => Self::insert_impl(&mut self.samples, slot, layout),
+            ModuleOutput::Spectral(layout) => Self::insert_impl(&mut self.spectral, slot, layout),
+            ModuleOutput::None => (),
+        }
     }
 
-    fn free_spectral_slot(&mut self, slot: usize) {
-        Self::free_impl(&mut self.spectral, &mut self.free_spectral_slots, slot);
+    pub fn has_output(&self, module: &ModuleHandle) -> bool {
+        let slot = module.output_slot();
+
+        match module.output_type() {
+            DataType::Audio | DataType::Control => self
+                .samples
+                .get(slot)
+                .is_some_and(|slot| slot.slot.is_some()),
+            DataType::Spectral => self
+                .spectral
+                .get(slot)
+                .is_some_and(|slot| slot.slot.is_some()),
+            DataType::Bus => true,
+        }
     }
 
-    pub fn allocate_slot(&mut self, module: &mut ModuleHandle) {
+    pub fn take_output(&mut self, module: &ModuleHandle) -> ModuleOutput {
+        let slot = module.output_slot();
+
         match module.output_type() {
+            DataType::Audio | DataType::Control => self
+                .samples
+                .get_mut(slot)
+                .and_then(|slot| slot.slot.take())
+                .map_or(ModuleOutput::None, ModuleOutput::Samples),
+            DataType::Spectral => self
+                .spectral
+                .get_mut(slot)
+                .and_then(|slot| slot.slot.take())
+                .map_or(ModuleOutput::None, ModuleOutput::Spectral),
+            DataType::Bus => ModuleOutput::None,
+        }
+    }
+
+    // Audio and spectral outputs of a bypassed module copy the input slot, control outputs are zero
+    pub(super) fn bypass(
+        &mut self,
+        data_type: DataType,
+        output_slot: usize,
+        input_slot: Option<usize>,
+        params: &ProcessParams,
+    ) {
+        let samples = params.samples;
+
+        match data_type {
             DataType::Audio | DataType::Control => {
-                module.set_output_slot(self.allocate_samples_slot())
+                let input_slot = input_slot.filter(|_| data_type == DataType::Audio);
+                let Some((output, input)) =
+                    Self::output_and_input(&mut self.samples, output_slot, input_slot)
+                else {
+                    return;
+                };
+
+                for channel_idx in 0..NUM_CHANNELS {
+                    for &voice_idx in params.active_voices {
+                        let voice_output = &mut output[channel_idx][voice_idx];
+
+                        if let Some(input) = input {
+                            voice_output.output(samples).copy_from_slice(
+                                &input[channel_idx][voice_idx].buffer()[..samples],
+                            );
+                        } else {
+                            voice_output
+                                .control_output(samples, true)
+                                .output()
+                                .fill(0.0);
+                        }
+                    }
+                }
             }
-            DataType::Spectral => module.set_output_slot(self.allocate_spectral_slot()),
+            DataType::Spectral => {
+                let Some((output, input)) =
+                    Self::output_and_input(&mut self.spectral, output_slot, input_slot)
+                else {
+                    return;
+                };
+
+                for channel_idx in 0..params.spectrum_channels {
+                    for &voice_idx in params.active_voices {
+                        output[channel_idx][voice_idx].pass_through(
+                            input.map(|input| &input[channel_idx][voice_idx]),
+                            params.spectral_bins,
+                        );
+                    }
+                }
+            }
+            DataType::Bus => (),
         }
     }
 
-    pub fn free_slot(&mut self, module: &ModuleHandle) {
-        match module.output_type() {
-            DataType::Audio | DataType::Control => self.free_samples_slot(module.output_slot()),
-            DataType::Spectral => self.free_spectral_slot(module.output_slot()),
+    pub(super) fn trim(
+        &mut self,
+        output_slot: usize,
+        from: Sample,
+        to: Sample,
+        params: &ProcessParams,
+    ) {
+        let Some(output) = self.samples.get_mut(output_slot) else {
+            return;
+        };
+
+        let samples = params.samples;
+        let step = (to - from) / samples.max(1) as Sample;
+
+        for channel_idx in 0..NUM_CHANNELS {
+            for &voice_idx in params.active_voices {
+                let voice_output = output[channel_idx][voice_idx].output(samples);
+
+                for (idx, sample) in voice_output.iter_mut().enumerate() {
+                    *sample *= step.mul_add((idx + 1) as Sample, from);
+                }
+            }
+        }
+    }
+
+    fn output_and_input<T: Default + Send>(
+        slots: &mut [ArenaSlot<T>],
+        output_slot: usize,
+        input_slot: Option<usize>,
+    ) -> Option<(&mut ArenaSlot<T>, Option<&ArenaSlot<T>>)> {
+        match input_slot {
+            Some(input_slot) => slots
+                .get_disjoint_mut([output_slot, input_slot])
+                .ok()
+                .map(|[output, input]| (output, Some(&*input))),
+            None => slots.get_mut(output_slot).map(|output| (output, None)),
         }
     }
 
@@ -122,6 +266,7 @@ impl OutputsArena {
         slot.map(|slot| self.samples[slot][channel_idx][voice_idx].buffer())
     }
 
+    // Applies the links to the param already in the buffer, additive links first
     pub(super) fn add_buff_to(
         &self,
         slots: &[InputSlot],
@@ -134,37 +279,52 @@ impl OutputsArena {
             return false;
         }
 
-        for slot in slots {
-            let amount = slot.amount[channel_idx];
-            let input = self.samples[slot.src_slot][channel_idx][voice_idx]
-                .buffer()
-                .iter()
-                .skip(skip)
-                .map(|sample| sample * amount);
-
-            if let Some(modulation_slot) = slot.modulation_slot {
-                let input_mod = self.samples[modulation_slot][channel_idx][voice_idx]
+        for mode in LinkMode::ALL {
+            for slot in slots.iter().filter(|slot| slot.mode == mode) {
+                let amount = slot.amount[channel_idx];
+                let input = self.samples[slot.src_slot][channel_idx][voice_idx]
                     .buffer()
                     .iter()
-                    .skip(skip);
-
-                add_to_buffer(
-                    result,
-                    input
-                        .zip(input_mod)
-                        .map(|(input, input_mod)| input * input_mod),
-                );
-            } else {
-                add_to_buffer(result, input);
+                    .skip(skip)
+                    .map(|sample| slot.shape(*sample) * amount);
+
+                if let Some(modulation_slot) = slot.modulation_slot {
+                    let input_mod = self.samples[modulation_slot][channel_idx][voice_idx]
+                        .buffer()
+                        .iter()
+                        .skip(skip);
+
+                    Self::apply_to_buffer(
+                        mode,
+                        result,
+                        input
+                            .zip(input_mod)
+                            .map(|(input, input_mod)| input * input_mod),
+                    );
+                } else {
+                    Self::apply_to_buffer(mode, result, input);
+                }
             }
         }
 
         true
     }
 
+    fn apply_to_buffer(mode: LinkMode, result: &mut [Sample], input: impl Iterator<Item = Sample>) {
+        match mode {
+            LinkMode::Add => add_to_buffer(result, input),
+            LinkMode::Multiply => result
+                .iter_mut()
+                .zip(input)
+                .for_each(|(result, value)| *result *= 1.0 + value),
+        }
+    }
+
+    // Param with the links applied, additive links first
     pub(super) fn get_scalar(
         &self,
         slots: &[InputSlot],
+        param: Sample,
         channel_idx: usize,
         voice_idx: usize,
         triggered: bool,
@@ -173,20 +333,34 @@ impl OutputsArena {
             return None;
         }
 
-        let mut result: Sample = 0.0;
+        let mut sum: Sample = 0.0;
+        let mut scale: Sample = 1.0;
 
         for slot in slots {
-            let mut value = self.samples[slot.src_slot][channel_idx][voice_idx].scalar(triggered)
+            let mut value = slot
+                .shape(self.samples[slot.src_slot][channel_idx][voice_idx].scalar(triggered))
                 * slot.amount[channel_idx];
 
             if let Some(modulated_slot) = slot.modulation_slot {
                 value *= self.samples[modulated_slot][channel_idx][voice_idx].scalar(triggered);
             }
 
-            result += value;
+            match slot.mode {
+                LinkMode::Add => sum += value,
+                LinkMode::Multiply => scale *= 1.0 + value,
+            }
         }
 
-        Some(result)
+        Some((param + sum) * scale)
+    }
+
+    pub fn latest_spectral(
+        &self,
+        slot: usize,
+        channel_idx: usize,
+        voice_idx: usize,
+    ) -> &SpectralBuffer {
+        self.spectral[slot][channel_idx][voice_idx].get(false)
     }
 
     pub(super) fn get_spectral(
@@ -198,4 +372,15 @@ impl OutputsArena {
     ) -> Option<&SpectralBuffer> {
         slot.map(|slot| self.spectral[slot][channel_idx][voice_idx].get(triggered))
     }
+
+    pub(super) fn get_spectral_revision(
+        &self,
+        slot: Option<usize>,
+        channel_idx: usize,
+        voice_idx: usize,
+    ) -> u32 {
+        slot.map_or(0, |slot| {
+            self.spectral[slot][channel_idx][voice_idx].revision()
+        })
+    }
 }
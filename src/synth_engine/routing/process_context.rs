@@ -1,9 +1,10 @@
 use crate::synth_engine::{
     ModuleId, Sample, SmoothedSampleParams,
     buffer::VoicesLayout,
+    midi_state::MidiState,
     routing::{
-        AudioRouterType, ControlRouterType, OutputRouterType, OutputsArena, RouterFactory,
-        SamplesOutput, SpectralOutput, SpectralRouterType,
+        AudioRouterType, ControlRouterType, DataType, NUM_CHANNELS, OutputRouterType, OutputsArena,
+        RouterFactory, SamplesOutput, SpectralOutput, SpectralRouterType,
     },
     ui_bridge::AudioEnd,
 };
@@ -15,7 +16,14 @@ pub struct ProcessParams<'a> {
     pub needs_update_ui: bool,
     pub smooth_params: SmoothedSampleParams,
     pub spectrum_channels: usize,
+    // Bins of the spectral size, the ones above are left out of spectral outputs
+    pub spectral_bins: usize,
+    pub tempo: Sample, // BPM
+    // The engine may run oversampled, the sidechain is at the host rate
+    pub host_sample_rate: Sample,
+    pub sidechain: [&'a [Sample]; NUM_CHANNELS],
     pub active_voices: &'a [usize],
+    pub midi_state: &'a MidiState,
 }
 
 pub struct ProcessContext<'c> {
@@ -82,6 +90,15 @@ impl<'c> ProcessContext<'c> {
         .with_output_slot(f);
     }
 
+    pub fn bypass(&mut self, data_type: DataType, output_slot: usize, input_slot: Option<usize>) {
+        self.outputs_arena
+            .bypass(data_type, output_slot, input_slot, &self.params);
+    }
+
+    pub fn trim(&mut self, output_slot: usize, from: Sample, to: Sample) {
+        self.outputs_arena.trim(output_slot, from, to, &self.params);
+    }
+
     pub fn for_output<'f>(
         &'f mut self,
         module_id: ModuleId,
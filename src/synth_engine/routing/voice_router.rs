@@ -1,6 +1,7 @@
 use crate::synth_engine::{
     Buffer, ModuleId, ProcessParams, Sample, SpectralBuffer,
     buffer::{VoicesLayout, ZEROES_BUFFER, ZEROES_SPECTRAL_BUFFER},
+    midi_state::MidiState,
     routing::{InputSlots, ProcessContext, SamplesOutput, SpectralOutput},
     smooth::SmoothedSample,
 };
@@ -128,6 +129,10 @@ impl<'v, 'f, 'c, S: RouterDataType> VoiceRouter<'v, 'f, 'c, S> {
         self.factory.ctx.params.sample_rate
     }
 
+    pub fn midi_state(&self) -> &MidiState {
+        self.factory.ctx.params.midi_state
+    }
+
     pub fn channel_idx(&self) -> usize {
         self.channel_idx
     }
@@ -147,12 +152,11 @@ impl<'v, 'f, 'c, S: RouterDataType> VoiceRouter<'v, 'f, 'c, S> {
     fn scalar_param_impl(&mut self, input: &InputSlots, param: Sample, triggered: bool) -> Sample {
         if let Some(value) = self.factory.ctx.outputs_arena.get_scalar(
             &input.slots,
+            param,
             self.channel_idx,
             self.voice_idx,
             triggered,
         ) {
-            let value = value + param;
-
             if self.factory.ctx.params.needs_update_ui && self.seq_idx == 0 {
                 self.factory.ctx.audio_end.update_modulated_input(
                     self.factory.module_id,
@@ -175,6 +179,13 @@ impl<'v, 'f, 'c, S: RouterDataType> VoiceRouter<'v, 'f, 'c, S> {
             .get_spectral(slot, self.channel_idx, self.voice_idx, triggered)
             .unwrap_or(&ZEROES_SPECTRAL_BUFFER)
     }
+
+    fn spectral_revision_impl(&self, slot: Option<usize>) -> u32 {
+        self.factory
+            .ctx
+            .outputs_arena
+            .get_spectral_revision(slot, self.channel_idx, self.voice_idx)
+    }
 }
 
 impl<'v, 'f, 'c> VoiceRouter<'v, 'f, 'c, AudioRouterType> {
@@ -220,6 +231,10 @@ impl<'v, 'f, 'c> VoiceRouter<'v, 'f, 'c, AudioRouterType> {
     pub fn spectral(&self, slot: Option<usize>, triggered: bool) -> &SpectralBuffer {
         self.spectral_impl(slot, triggered)
     }
+
+    pub fn spectral_revision(&self, slot: Option<usize>) -> u32 {
+        self.spectral_revision_impl(slot)
+    }
 }
 
 impl<'v, 'f, 'c> VoiceRouter<'v, 'f, 'c, ControlRouterType> {
@@ -259,6 +274,38 @@ impl<'v, 'f, 'c> VoiceRouter<'v, 'f, 'c, ControlRouterType> {
     pub fn scalar_param(&mut self, input: &InputSlots, param: Sample, triggered: bool) -> Sample {
         self.scalar_param_impl(input, param, triggered)
     }
+
+    // Per-sample counterpart of `scalar_param` for the inputs marked as audio rate,
+    // the buffer is laid out like `buff_param` fills it
+    pub fn scalar_buff_param(
+        &mut self,
+        input: &InputSlots,
+        param: Sample,
+        buff: &mut Buffer,
+        triggered: bool,
+    ) {
+        let skip = usize::from(!triggered);
+        let buff = &mut buff[skip..self.factory.ctx.params.samples + 1];
+
+        buff.fill(param);
+
+        if self.factory.ctx.outputs_arena.add_buff_to(
+            &input.slots,
+            self.channel_idx,
+            self.voice_idx,
+            skip,
+            buff,
+        ) && self.factory.ctx.params.needs_update_ui
+            && self.seq_idx == 0
+        {
+            self.factory.ctx.audio_end.update_modulated_input(
+                self.factory.module_id,
+                input.input_type,
+                self.channel_idx as u8,
+                buff[0],
+            );
+        }
+    }
 }
 
 impl<'v, 'f, 'c> VoiceRouter<'v, 'f, 'c, SpectralRouterType> {
@@ -269,10 +316,19 @@ impl<'v, 'f, 'c> VoiceRouter<'v, 'f, 'c, SpectralRouterType> {
     pub fn spectral(&self, slot: Option<usize>, triggered: bool) -> &SpectralBuffer {
         self.spectral_impl(slot, triggered)
     }
+
+    /// Bumped every time the source module writes a new spectrum, unconnected inputs stay at zero.
+    pub fn spectral_revision(&self, slot: Option<usize>) -> u32 {
+        self.spectral_revision_impl(slot)
+    }
 }
 
 impl<'v, 'f, 'c> VoiceRouter<'v, 'f, 'c, OutputRouterType> {
     pub fn buff(&mut self, slot: Option<usize>) -> &[Sample] {
         self.buff_impl(slot)
     }
+
+    pub fn scalar_param(&mut self, input: &InputSlots, param: Sample, triggered: bool) -> Sample {
+        self.scalar_param_impl(input, param, triggered)
+    }
 }
@@ -0,0 +1,289 @@
+use std::{f32, path::Path};
+
+use hound::{SampleFormat, WavReader};
+use realfft::RealFftPlanner;
+
+use crate::synth_engine::{
+    Sample, StereoSample,
+    buffer::{HARMONIC_SERIES_BUFFER, SPECTRAL_BUFFER_SIZE},
+    routing::NUM_CHANNELS,
+};
+
+pub const DETECTION_WINDOW: usize = 8192;
+const MIN_PERIOD: usize = 8;
+const PEAK_THRESHOLD: Sample = 0.9;
+const STFT_SIZE: usize = 4096;
+const STFT_HOP: usize = 1024;
+const MIN_GAIN: Sample = 1e-6;
+
+pub struct WavChannels {
+    pub sample_rate: Sample,
+    pub channels: [Vec<Sample>; NUM_CHANNELS],
+}
+
+impl WavChannels {
+    pub fn mono(&self) -> Vec<Sample> {
+        self.channels[0]
+            .iter()
+            .zip(&self.channels[1])
+            .map(|(left, right)| 0.5 * (left + right))
+            .collect()
+    }
+}
+
+// Harmonic levels of a sample over time, relative to the harmonic series like the Harmonic Editor gains
+pub struct HarmonicFrames {
+    pub fundamental: Sample, // Hz
+    pub frame_duration: Sample,
+    num_frames: usize,
+    gains: Vec<StereoSample>,
+}
+
+impl HarmonicFrames {
+    pub fn num_frames(&self) -> usize {
+        self.num_frames
+    }
+
+    pub fn frame(&self, idx: usize) -> &[StereoSample] {
+        let from = idx.min(self.num_frames - 1) * SPECTRAL_BUFFER_SIZE;
+
+        &self.gains[from..from + SPECTRAL_BUFFER_SIZE]
+    }
+}
+
+pub fn read_channels(path: &Path) -> Option<WavChannels> {
+    let mut reader = WavReader::open(path).ok()?;
+    let spec = reader.spec();
+    let num_channels = usize::from(spec.channels);
+
+    let interleaved: Vec<Sample> = match spec.sample_format {
+        SampleFormat::Float => reader.samples::<f32>().collect::<Result<_, _>>().ok()?,
+        SampleFormat::Int => {
+            let scale = ((1_i64 << (spec.bits_per_sample - 1)) as Sample).recip();
+
+            reader
+                .samples::<i32>()
+                .map(|sample| sample.map(|sample| sample as Sample * scale))
+                .collect::<Result<_, _>>()
+                .ok()?
+        }
+    };
+
+    if num_channels == 0 || interleaved.len() < num_channels {
+        return None;
+    }
+
+    let mut channels: [Vec<Sample>; NUM_CHANNELS] = Default::default();
+
+    for (channel_idx, channel) in channels.iter_mut().enumerate() {
+        let src_idx = channel_idx.min(num_channels - 1);
+
+        channel.extend(interleaved.iter().skip(src_idx).step_by(num_channels));
+    }
+
+    Some(WavChannels {
+        sample_rate: spec.sample_rate as Sample,
+        channels,
+    })
+}
+
+fn autocorrelation(window: &[Sample]) -> Vec<Sample> {
+    (0..window.len() / 2)
+        .map(|lag| {
+            let sum: Sample = window[lag..].iter().zip(window).map(|(a, b)| a * b).sum();
+
+            sum / (window.len() - lag) as Sample
+        })
+        .collect()
+}
+
+// Skips the main lobe of the autocorrelation, then takes the first peak close to the strongest one
+fn find_period(correlation: &[Sample]) -> Option<usize> {
+    let lobe_end = (MIN_PERIOD..correlation.len()).find(|lag| correlation[*lag] <= 0.0)?;
+    let max_peak = correlation[lobe_end..]
+        .iter()
+        .copied()
+        .fold(0.0, Sample::max);
+
+    if max_peak <= 0.0 {
+        return None;
+    }
+
+    (lobe_end..correlation.len())
+        .filter(|lag| correlation[*lag] >= PEAK_THRESHOLD * max_peak)
+        .find(|lag| {
+            correlation[*lag] >= correlation[*lag - 1]
+                && correlation
+                    .get(*lag + 1)
+                    .is_none_or(|next| correlation[*lag] >= *next)
+        })
+}
+
+/// Period of a pitched signal in whole samples.
+pub fn detect_period(window: &[Sample]) -> Option<usize> {
+    find_period(&autocorrelation(window))
+}
+
+// Period with a fractional part from a parabola through the peak and its neighbours
+fn detect_fractional_period(window: &[Sample]) -> Option<Sample> {
+    let correlation = autocorrelation(window);
+    let period = find_period(&correlation)?;
+    let (Some(prev), Some(next)) = (correlation.get(period - 1), correlation.get(period + 1))
+    else {
+        return Some(period as Sample);
+    };
+    let peak = correlation[period];
+    let curvature = prev - 2.0 * peak + next;
+
+    if curvature >= 0.0 {
+        return Some(period as Sample);
+    }
+
+    Some(period as Sample + 0.5 * (prev - next) / curvature)
+}
+
+// The loudest part of the sample gives the most reliable pitch
+fn loudest_window(samples: &[Sample]) -> &[Sample] {
+    let len = samples.len().min(DETECTION_WINDOW);
+    let energy = |from: usize| {
+        samples[from..from + len]
+            .iter()
+            .map(|s| s * s)
+            .sum::<Sample>()
+    };
+    let from = (0..=samples.len() - len)
+        .step_by(STFT_HOP)
+        .max_by(|a, b| energy(*a).total_cmp(&energy(*b)))
+        .unwrap_or(0);
+
+    &samples[from..from + len]
+}
+
+fn hann_window(size: usize) -> Vec<Sample> {
+    (0..size)
+        .map(|idx| 0.5 - 0.5 * (f32::consts::TAU * idx as Sample / size as Sample).cos())
+        .collect()
+}
+
+/// Detects the fundamental of a WAV file, then measures every harmonic frame by frame with an STFT.
+pub fn analyze_harmonic_frames(path: &Path) -> Result<HarmonicFrames, String> {
+    let wav = read_channels(path).ok_or("Failed to read the WAV file")?;
+    let mono = wav.mono();
+
+    if mono.len() < 2 * MIN_PERIOD {
+        return Err("The sample is too short".to_string());
+    }
+
+    let period = detect_fractional_period(loudest_window(&mono))
+        .ok_or("Failed to detect the pitch of the sample")?;
+    let fundamental = wav.sample_rate / period;
+
+    let window = hann_window(STFT_SIZE);
+    let window_sum: Sample = window.iter().sum();
+    let fft = RealFftPlanner::<Sample>::new().plan_fft_forward(STFT_SIZE);
+    let mut input = fft.make_input_vec();
+    let mut spectrum = fft.make_output_vec();
+
+    let bin_width = wav.sample_rate / STFT_SIZE as Sample;
+    let harmonic_bins = fundamental / bin_width;
+    // Slightly detuned harmonics are still found, the search never reaches the neighbouring ones
+    let search_radius = (0.5 * harmonic_bins).floor().max(1.0) as isize;
+    let num_harmonics =
+        ((0.5 * wav.sample_rate / fundamental) as usize).min(SPECTRAL_BUFFER_SIZE - 1);
+
+    let num_frames = mono.len().div_ceil(STFT_HOP);
+    let mut gains = vec![StereoSample::ZERO; num_frames * SPECTRAL_BUFFER_SIZE];
+
+    for (frame_idx, frame_gains) in gains.chunks_exact_mut(SPECTRAL_BUFFER_SIZE).enumerate() {
+        // Frames are centered on their hop position
+        let center = frame_idx * STFT_HOP;
+
+        for (channel_idx, channel) in wav.channels.iter().enumerate() {
+            for (idx, (sample, weight)) in input.iter_mut().zip(&window).enumerate() {
+                let src_idx = (center + idx).checked_sub(STFT_SIZE / 2);
+
+                *sample = src_idx.and_then(|idx| channel.get(idx)).unwrap_or(&0.0) * weight;
+            }
+
+            fft.process(&mut input, &mut spectrum)
+                .map_err(|err| err.to_string())?;
+
+            for (harmonic, gain) in frame_gains
+                .iter_mut()
+                .enumerate()
+                .take(num_harmonics + 1)
+                .skip(1)
+            {
+                let center_bin = (harmonic as Sample * harmonic_bins).round() as isize;
+                let peak = (center_bin - search_radius..=center_bin + search_radius)
+                    .filter_map(|bin| spectrum.get(usize::try_from(bin).ok()?))
+                    .map(|bin| bin.norm())
+                    .fold(0.0, Sample::max);
+                // Amplitude of the harmonic in the spectral buffer scale
+                let level = peak / window_sum / HARMONIC_SERIES_BUFFER[harmonic].norm();
+
+                if level > MIN_GAIN {
+                    gain[channel_idx] = level;
+                }
+            }
+        }
+    }
+
+    Ok(HarmonicFrames {
+        fundamental,
+        frame_duration: STFT_HOP as Sample / wav.sample_rate,
+        num_frames,
+        gains,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use hound::{WavSpec, WavWriter};
+
+    use super::*;
+
+    #[test]
+    fn harmonic_frames_follow_the_sample() {
+        const SAMPLE_RATE: u32 = 44100;
+        // Harmonics land right on the STFT bins
+        let fundamental = SAMPLE_RATE as Sample * 20.0 / STFT_SIZE as Sample;
+        let path = std::env::temp_dir().join("additizer-resynth.wav");
+        let mut writer = WavWriter::create(
+            &path,
+            WavSpec {
+                channels: 1,
+                sample_rate: SAMPLE_RATE,
+                bits_per_sample: 32,
+                sample_format: SampleFormat::Float,
+            },
+        )
+        .unwrap();
+
+        for idx in 0..SAMPLE_RATE / 2 {
+            let t = f32::consts::TAU * fundamental * idx as Sample / SAMPLE_RATE as Sample;
+
+            writer
+                .write_sample(0.5 * t.sin() + 0.25 * (3.0 * t).sin())
+                .unwrap();
+        }
+
+        writer.finalize().unwrap();
+
+        let frames = analyze_harmonic_frames(&path).expect("analysis");
+        let _ = std::fs::remove_file(path);
+
+        assert!((frames.fundamental - fundamental).abs() < 1.0);
+        assert!(frames.num_frames() > 10);
+
+        // Relative to the harmonic series: amplitude * PI * harmonic / 2
+        let frame = frames.frame(frames.num_frames() / 2);
+        let expected_1 = 0.5 * f32::consts::PI / 2.0;
+        let expected_3 = 0.25 * 3.0 * f32::consts::PI / 2.0;
+
+        assert!((frame[1][0] - expected_1).abs() < 0.05 * expected_1);
+        assert!((frame[3][0] - expected_3).abs() < 0.05 * expected_3);
+        assert!(frame[2][0] < 0.01 * expected_1);
+        assert_eq!(frame[1][0], frame[1][1]);
+    }
+}
@@ -0,0 +1,93 @@
+use std::{
+    collections::VecDeque,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+};
+
+use crate::synth_engine::{Sample, routing::NUM_CHANNELS};
+
+// A few editor frames worth of audio, the editor drains it on every repaint
+const SCOPE_BUFFER_SIZE: usize = 1 << 14;
+
+pub type ScopeFrame = [Sample; NUM_CHANNELS];
+
+pub struct ScopeTap {
+    producer: rtrb::Producer<ScopeFrame>,
+    active: Arc<AtomicBool>,
+}
+
+impl ScopeTap {
+    pub fn write(&mut self, left: &[Sample], right: &[Sample]) {
+        if !self.active.load(Ordering::Acquire) {
+            return;
+        }
+
+        for (left, right) in left.iter().zip(right) {
+            // Frames are dropped while the editor isn't reading
+            if self.producer.push([*left, *right]).is_err() {
+                break;
+            }
+        }
+    }
+}
+
+pub struct Scope {
+    consumer: rtrb::Consumer<ScopeFrame>,
+    active: Arc<AtomicBool>,
+    sample_rate: Sample,
+}
+
+impl Scope {
+    pub fn sample_rate(&self) -> Sample {
+        self.sample_rate
+    }
+
+    pub fn set_active(&mut self, active: bool) {
+        if active == self.active.load(Ordering::Acquire) {
+            return;
+        }
+
+        // Leftovers from the last time the scope was shown
+        if active {
+            while self.consumer.pop().is_ok() {}
+        }
+
+        self.active.store(active, Ordering::Release);
+    }
+
+    /// Appends the pending frames to `history`, keeping at most `capacity` of the latest ones.
+    /// Returns the number of frames read.
+    pub fn read(&mut self, history: &mut VecDeque<ScopeFrame>, capacity: usize) -> usize {
+        let mut count = 0;
+
+        while let Ok(frame) = self.consumer.pop() {
+            if history.len() >= capacity {
+                history.pop_front();
+            }
+
+            history.push_back(frame);
+            count += 1;
+        }
+
+        count
+    }
+}
+
+pub fn create_scope_pair(sample_rate: Sample) -> (ScopeTap, Scope) {
+    let (producer, consumer) = rtrb::RingBuffer::<ScopeFrame>::new(SCOPE_BUFFER_SIZE);
+    let active = Arc::new(AtomicBool::new(false));
+
+    (
+        ScopeTap {
+            producer,
+            active: active.clone(),
+        },
+        Scope {
+            consumer,
+            active,
+            sample_rate,
+        },
+    )
+}
@@ -0,0 +1,34 @@
+use serde::{Deserialize, Serialize};
+
+use crate::synth_engine::buffer::{SPECTRAL_BUFFER_SIZE, SPECTRUM_BITS};
+
+// Bins the spectra of a patch use. The spectral buffers fit the largest size, the spectral modules
+// process a prefix of them and the oscillator tables are planned for it.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SpectralSize {
+    S256,
+    S512,
+    #[default]
+    S1024,
+}
+
+impl SpectralSize {
+    pub const fn bits(self) -> usize {
+        match self {
+            Self::S256 => SPECTRUM_BITS - 2,
+            Self::S512 => SPECTRUM_BITS - 1,
+            Self::S1024 => SPECTRUM_BITS,
+        }
+    }
+
+    pub const fn bins(self) -> usize {
+        1 << self.bits()
+    }
+
+    // The first bin holds DC
+    pub const fn harmonics(self) -> usize {
+        self.bins() - 1
+    }
+}
+
+const _: () = assert!(SpectralSize::S1024.bins() == SPECTRAL_BUFFER_SIZE);
@@ -0,0 +1,213 @@
+use std::collections::{HashMap, HashSet};
+
+use rustc_hash::FxHashMap;
+use smallvec::SmallVec;
+use topo_sort::{SortResults, TopoSort};
+
+use crate::synth_engine::{
+    RoutingMap,
+    module_handle::ModuleHandle,
+    profiler::ModuleProfiler,
+    routing::{
+        DataType, InputSlot, InputSlots, MAX_INPUT_SOURCES, ModuleId, ModuleOutput,
+        OUTPUT_MODULE_ID, SpectralInputSlot,
+    },
+    synth_module::SynthModule,
+};
+
+// Output type and output slot of every module the routing refers to
+pub type ModuleOutputs = FxHashMap<ModuleId, (DataType, usize)>;
+
+// The slot the audio thread's arena has free for a new output, the lowest one not taken
+pub fn free_output_slot(outputs: &ModuleOutputs, data_type: DataType) -> usize {
+    // Audio and control outputs share the samples arena
+    let is_spectral = match data_type {
+        DataType::Audio | DataType::Control => false,
+        DataType::Spectral => true,
+        DataType::Bus => return usize::MAX,
+    };
+
+    let used: Vec<_> = outputs
+        .values()
+        .filter(|(other, _)| {
+            *other != DataType::Bus && (*other == DataType::Spectral) == is_spectral
+        })
+        .map(|(_, slot)| *slot)
+        .collect();
+
+    (0..used.len())
+        .find(|slot| !used.contains(slot))
+        .unwrap_or(used.len())
+}
+
+pub struct ModuleSlots {
+    pub(super) module_id: ModuleId,
+    pub(super) inputs: Vec<InputSlots>,
+    pub(super) spectral_inputs: Vec<SpectralInputSlot>,
+}
+
+// Everything the audio thread needs to switch to a new routing, built up front
+pub struct RoutingTables {
+    pub(super) input_sources: RoutingMap,
+    pub(super) execution_order: Vec<ModuleId>,
+    pub(super) module_slots: Vec<ModuleSlots>,
+    pub(super) profiler: ModuleProfiler,
+}
+
+impl RoutingTables {
+    /// Allocates, fails on cycles and on links the modules can't take.
+    pub fn build(input_sources: RoutingMap, outputs: &ModuleOutputs) -> Result<Self, String> {
+        let execution_order = Self::execution_order(&input_sources)?;
+        let module_slots = Self::module_slots(&input_sources, outputs)?;
+        let profiler = ModuleProfiler::new(&execution_order);
+
+        Ok(Self {
+            input_sources,
+            execution_order,
+            module_slots,
+            profiler,
+        })
+    }
+
+    pub(super) fn execution_order(input_sources: &RoutingMap) -> Result<Vec<ModuleId>, String> {
+        let mut dependents: HashMap<ModuleId, HashSet<ModuleId>> = HashMap::new();
+
+        for (dst, sources) in input_sources {
+            for src_id in sources.iter().flat_map(|src| src.source_ids()) {
+                dependents.entry(dst.module_id).or_default().insert(src_id);
+                dependents.entry(src_id).or_default();
+            }
+        }
+
+        let topo_sort = TopoSort::from_map(dependents);
+
+        match topo_sort.into_vec_nodes() {
+            SortResults::Full(nodes) => {
+                let mut order: Vec<_> = nodes
+                    .into_iter()
+                    .filter(|id| *id != OUTPUT_MODULE_ID)
+                    .collect();
+
+                order.push(OUTPUT_MODULE_ID);
+                Ok(order)
+            }
+            SortResults::Partial(_) => Err("Cycles detected!".to_string()),
+        }
+    }
+
+    fn module_slots(
+        input_sources: &RoutingMap,
+        outputs: &ModuleOutputs,
+    ) -> Result<Vec<ModuleSlots>, String> {
+        let output_of = |module_id: &ModuleId| {
+            outputs
+                .get(module_id)
+                .copied()
+                .ok_or_else(|| "Invalid node.".to_string())
+        };
+
+        let mut modules_slots: FxHashMap<_, _> = outputs
+            .keys()
+            .map(|&module_id| {
+                (
+                    module_id,
+                    ModuleSlots {
+                        module_id,
+                        inputs: Vec::new(),
+                        spectral_inputs: Vec::new(),
+                    },
+                )
+            })
+            .collect();
+
+        for (input, sources) in input_sources {
+            let mut src_outputs = SmallVec::<[_; MAX_INPUT_SOURCES]>::new();
+
+            for src in sources {
+                src_outputs.push(output_of(&src.module_id)?);
+            }
+
+            // Bus links are resolved by setup_bus_chain
+            if src_outputs
+                .iter()
+                .any(|(data_type, _)| *data_type == DataType::Bus)
+            {
+                continue;
+            }
+
+            if sources.len() > MAX_INPUT_SOURCES {
+                return Err("Too many links to the input.".to_string());
+            }
+
+            let dst_module = modules_slots
+                .get_mut(&input.module_id)
+                .ok_or_else(|| "Invalid node.".to_string())?;
+
+            if let [(DataType::Spectral, slot)] = src_outputs[..] {
+                dst_module.spectral_inputs.push(SpectralInputSlot {
+                    input_type: input.input_type,
+                    slot,
+                });
+
+                continue;
+            }
+
+            let mut input_slots = InputSlots::empty(input.input_type);
+
+            for (src, (data_type, src_slot)) in sources.iter().zip(src_outputs) {
+                let modulation_slot = src.modulation.map(|id| output_of(&id)).transpose()?;
+
+                let is_valid = matches!(data_type, DataType::Audio | DataType::Control)
+                    && modulation_slot.is_none_or(|(data_type, _)| {
+                        matches!(data_type, DataType::Audio | DataType::Control)
+                    });
+
+                if !is_valid {
+                    return Err("Data types mismatch.".to_string());
+                }
+
+                input_slots.slots.push(InputSlot {
+                    src_slot,
+                    modulation_slot: modulation_slot.map(|(_, slot)| slot),
+                    amount: src.amount,
+                    curve: src.curve,
+                    polarity: src.polarity,
+                    offset: src.offset,
+                    mode: src.mode,
+                });
+            }
+
+            dst_module.inputs.push(input_slots);
+        }
+
+        Ok(modules_slots.into_values().collect())
+    }
+}
+
+// A structural edit from the editor: the new routing with the modules to add and to remove.
+// The audio thread swaps it in between blocks and sends it back holding the replaced data.
+pub struct StagedRouting {
+    pub(super) tables: RoutingTables,
+    pub(super) added: Vec<(ModuleHandle, ModuleOutput)>,
+    pub(super) removed: Vec<ModuleId>,
+    pub(super) released: Vec<(ModuleHandle, ModuleOutput)>,
+}
+
+impl StagedRouting {
+    /// Allocates the outputs of the added modules and the room for the removed ones.
+    pub fn new(tables: RoutingTables, added: Vec<ModuleHandle>, removed: Vec<ModuleId>) -> Self {
+        Self {
+            tables,
+            added: added
+                .into_iter()
+                .map(|module| {
+                    let output = ModuleOutput::new(module.output_type());
+
+                    (module, output)
+                })
+                .collect(),
+            released: Vec::with_capacity(removed.len()),
+            removed,
+        }
+    }
+}
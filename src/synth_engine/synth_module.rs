@@ -1,10 +1,11 @@
 use enum_dispatch::enum_dispatch;
 
 use crate::synth_engine::{
-    StereoSample,
+    Sample, StereoSample,
+    buffer::Buffer,
     routing::{
-        DataType, Input, InputMeta, InputSlots, ModuleId, ProcessContext, SpectralInputSlot,
-        VoiceEvent,
+        DataType, Input, InputMeta, InputSlots, InputSource, ModuleId, NUM_CHANNELS,
+        ProcessContext, ProcessParams, SpectralInputSlot, VoiceEvent,
     },
     voices_handler::DecayingVoice,
 };
@@ -15,6 +16,13 @@ use crate::synth_engine::{
 pub(super) trait SynthModule: Send {
     fn id(&self) -> ModuleId;
 
+    // A bypassed module isn't processed, the routing forwards its input or outputs zero instead
+    fn bypass(&self) -> bool {
+        false
+    }
+
+    fn set_bypass(&mut self, _bypass: bool) {}
+
     fn inputs(&self) -> &'static [InputMeta];
     fn output_type(&self) -> DataType;
 
@@ -23,19 +31,36 @@ pub(super) trait SynthModule: Send {
 
     fn set_input_slots(&mut self, inputs: &[InputSlots], spectral_inputs: &[SpectralInputSlot]);
 
-    fn update_input_amount(&mut self, input_type: Input, src_slot: usize, amount: StereoSample);
+    fn update_input_source(&mut self, input_type: Input, src_slot: usize, source: &InputSource);
 
     fn process_events(&mut self, events: &[VoiceEvent]) {}
     fn process_ui_events(&mut self);
+
+    // Sets the base value of a modulatable input, both from the UI and from host automation
+    fn set_input_param(&mut self, input: Input, value: StereoSample) {}
     fn poll_decaying_voices(&self, decaying_voices: &mut [DecayingVoice]) {}
 
     fn process(&mut self, ctx: &mut ProcessContext);
+
+    // Output trim of the engine, the gain ramps from `from` to `to` over the block and a negative
+    // gain inverts the polarity
+    fn trim_output(&self, ctx: &mut ProcessContext, from: Sample, to: Sample) {
+        if self.output_type() == DataType::Audio {
+            ctx.trim(self.output_slot(), from, to);
+        }
+    }
+
+    // Called for bus modules only, after the voices are summed
+    fn process_bus(&mut self, bus: &mut [Buffer; NUM_CHANNELS], params: &ProcessParams) {}
 }
 
 #[enum_dispatch]
 #[auto_impl::auto_impl(Box)]
 pub trait ModuleUiBridge: Send {
     fn update(&mut self);
+
+    // Grows with every change of the module config, the undo history only records when it moved
+    fn edits(&self) -> u32;
 }
 
 macro_rules! set_mono_param {
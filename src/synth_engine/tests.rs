@@ -1,4 +1,9 @@
-use std::sync::Arc;
+use std::{
+    array,
+    sync::Arc,
+    thread,
+    time::{Duration, Instant},
+};
 
 use nih_plug::prelude::*;
 
@@ -11,8 +16,16 @@ use crate::{
         spectral_filter::SpectralFilterConfig, spectral_mixer::SpectralMixerConfig,
         wave_shaper::WaveShaperConfig,
     },
-    utils::from_ms,
+    utils::{from_ms, st_to_octave},
 };
+use delay::DelayConfig;
+use filter::FilterConfig;
+use harmonic_comb::HarmonicCombConfig;
+use harmonic_editor::{ComplexCfg, SetAction, SetParams, SnapshotSlot};
+use lfo::LfoShape;
+use math::MathConfig;
+use panner::PannerConfig;
+use ring_mod::RingModConfig;
 
 const SAMPLE_RATE: Sample = 48_000.0;
 const HARMONIC_EDITOR_ID: ModuleId = 1;
@@ -51,12 +64,15 @@ fn test_deps() -> (Arc<FloatParam>, Arc<ExternalParamsBlock>) {
     };
 
     let external_params = Arc::new(ExternalParamsBlock {
-        float_params: [
-            float_param("Float Param 1"),
-            float_param("Float Param 2"),
-            float_param("Float Param 3"),
-            float_param("Float Param 4"),
-        ],
+        float_params: array::from_fn(|idx| float_param(&format!("Float Param {}", idx + 1))),
+        float_param_names: Arc::default(),
+        automation_params: array::from_fn(|idx| float_param(&format!("Automation {}", idx + 1))),
+        master_tune: float_param("Master Tune"),
+        transpose: Arc::new(IntParam::new(
+            "Transpose",
+            0,
+            IntRange::Linear { min: -24, max: 24 },
+        )),
     });
 
     (volume, external_params)
@@ -79,6 +95,10 @@ fn minimal_engine_config(engine: EngineParams, osc: OscillatorConfig) -> EngineC
                 dst_input: Input::Spectrum,
                 amount: StereoSample::ONE,
                 modulator_id: None,
+                curve: LinkCurve::Linear,
+                polarity: LinkPolarity::Unchanged,
+                offset: 0.0,
+                mode: LinkMode::Add,
             },
             LinkConfig {
                 src_id: OSCILLATOR_ID,
@@ -86,8 +106,15 @@ fn minimal_engine_config(engine: EngineParams, osc: OscillatorConfig) -> EngineC
                 dst_input: Input::Audio,
                 amount: StereoSample::ONE,
                 modulator_id: None,
+                curve: LinkCurve::Linear,
+                polarity: LinkPolarity::Unchanged,
+                offset: 0.0,
+                mode: LinkMode::Add,
             },
         ],
+        automation: vec![],
+        midi_mappings: vec![],
+        output_trims: vec![],
     }
 }
 
@@ -120,6 +147,18 @@ fn process_block_with_ui(
     (left, right)
 }
 
+// The same sidechain on both channels, handed over a block at a time like the plugin does
+fn process_with_sidechain(engine: &mut SynthEngine, sidechain: &[Sample]) -> Vec<Sample> {
+    let mut left = Vec::with_capacity(sidechain.len());
+
+    for block in sidechain.chunks(MAX_BLOCK_SIZE) {
+        engine.set_sidechain([block, block].into_iter());
+        left.extend(process_block(engine, block.len()).0);
+    }
+
+    left
+}
+
 fn rms(samples: &[Sample]) -> Sample {
     (samples.iter().map(|s| s * s).sum::<Sample>() / samples.len() as Sample).sqrt()
 }
@@ -131,6 +170,10 @@ fn link(src_id: ModuleId, dst_id: ModuleId, dst_input: Input) -> LinkConfig {
         dst_input,
         amount: StereoSample::ONE,
         modulator_id: None,
+        curve: LinkCurve::Linear,
+        polarity: LinkPolarity::Unchanged,
+        offset: 0.0,
+        mode: LinkMode::Add,
     }
 }
 
@@ -222,6 +265,9 @@ fn full_patch_engine_config(engine: EngineParams) -> EngineConfig {
             link(EXPRESSIONS_ID, WAVE_SHAPER_ID, Input::Distortion),
             link(WAVE_SHAPER_ID, OUTPUT_MODULE_ID, Input::Audio),
         ],
+        automation: vec![],
+        midi_mappings: vec![],
+        output_trims: vec![],
     }
 }
 
@@ -379,6 +425,9 @@ fn try_new_rejects_duplicate_module_id() {
             })),
         ],
         links: vec![],
+        automation: vec![],
+        midi_mappings: vec![],
+        output_trims: vec![],
     };
 
     assert!(SynthEngine::try_new(&config, volume, external_params, SAMPLE_RATE).is_none());
@@ -406,7 +455,14 @@ fn try_new_rejects_invalid_link() {
             dst_input: Input::Audio,
             amount: StereoSample::ONE,
             modulator_id: None,
+            curve: LinkCurve::Linear,
+            polarity: LinkPolarity::Unchanged,
+            offset: 0.0,
+            mode: LinkMode::Add,
         }],
+        automation: vec![],
+        midi_mappings: vec![],
+        output_trims: vec![],
     };
 
     assert!(SynthEngine::try_new(&config, volume, external_params, SAMPLE_RATE).is_none());
@@ -510,6 +566,184 @@ fn output_gain_setters() {
     );
 }
 
+#[test]
+fn limiter_keeps_output_under_ceiling() {
+    let limiter = limiter::LimiterConfig {
+        enabled: true,
+        threshold: -12.0,
+        ceiling: -1.0,
+        ..limiter::LimiterConfig::default()
+    };
+    let mut engine = make_full_patch_engine(EngineParams {
+        num_voices: 4,
+        output_gain: 4.0.into(),
+        limiter,
+        ..EngineParams::default()
+    });
+
+    for note in [48, 55, 60, 64] {
+        engine.handle_note_on(0, note, 1.0);
+    }
+
+    let ceiling = nih_plug::util::db_to_gain(-1.0) + 1e-6;
+
+    for _ in 0..8 {
+        let (left, right) = process_block(&mut engine, 64);
+
+        assert!(left.iter().chain(&right).all(|s| s.abs() <= ceiling));
+    }
+
+    assert!(rms(&process_block(&mut engine, 64).0) > 1e-6);
+    assert!(engine.get_config().engine.limiter == limiter);
+}
+
+#[test]
+fn limiter_setters_clamp_threshold_to_ceiling() {
+    let mut engine = make_engine(
+        EngineParams::default(),
+        OscillatorConfig {
+            id: OSCILLATOR_ID,
+            ..OscillatorConfig::default()
+        },
+    );
+
+    assert!(!engine.get_limiter().enabled);
+
+    engine.set_limiter(limiter::LimiterConfig {
+        enabled: true,
+        threshold: 0.0,
+        ceiling: -3.0,
+        ..limiter::LimiterConfig::default()
+    });
+
+    let limiter = engine.get_config().engine.limiter;
+
+    assert!(limiter.enabled);
+    assert_eq!(limiter.ceiling, -3.0);
+    assert_eq!(limiter.threshold, -3.0);
+}
+
+#[test]
+fn dc_blocker_removes_offset() {
+    let mut blocker = dc_blocker::DcBlocker::new(true);
+    let mut left = vec![0.5; SAMPLE_RATE as usize];
+    let mut right = vec![-0.25; SAMPLE_RATE as usize];
+
+    blocker.process(SAMPLE_RATE, [&mut left[..], &mut right[..]]);
+
+    assert!(left.last().unwrap().abs() < 1e-3);
+    assert!(right.last().unwrap().abs() < 1e-3);
+
+    let mut engine = make_engine(
+        EngineParams::default(),
+        OscillatorConfig {
+            id: OSCILLATOR_ID,
+            ..OscillatorConfig::default()
+        },
+    );
+
+    assert!(!engine.get_config().engine.dc_blocker);
+    engine.set_dc_blocker(true);
+    assert!(engine.get_config().engine.dc_blocker);
+}
+
+#[test]
+fn output_mode_folds_and_correlation_follows() {
+    let sine: Vec<Sample> = (0..SAMPLE_RATE as usize)
+        .map(|idx| (idx as Sample * 0.05).sin())
+        .collect();
+    let inverted: Vec<Sample> = sine.iter().map(|s| -s).collect();
+    let correlation = |left: &[Sample], right: &[Sample]| {
+        let mut meter = output_mode::CorrelationMeter::new();
+
+        meter.process(SAMPLE_RATE, left, right);
+        meter.correlation()
+    };
+
+    assert!(correlation(&sine, &sine) > 0.99);
+    assert!(correlation(&sine, &inverted) < -0.99);
+    assert_eq!(correlation(&[0.0; 64], &[0.0; 64]), 0.0);
+
+    let (mut left, mut right) = (sine.clone(), inverted.clone());
+
+    OutputMode::Mid.process(&mut left, &mut right);
+    assert!(left.iter().chain(right.iter()).all(|s| *s == 0.0));
+
+    let mut engine = make_engine(
+        EngineParams::default(),
+        OscillatorConfig {
+            id: OSCILLATOR_ID,
+            ..OscillatorConfig::default()
+        },
+    );
+
+    engine.set_output_mode(OutputMode::MonoSum);
+    assert_eq!(engine.get_config().engine.output_mode, OutputMode::MonoSum);
+
+    engine.handle_note_on(0, 60, 1.0);
+
+    let (left, right) = process_block(&mut engine, 128);
+
+    assert!(rms(&left) > 1e-3);
+    assert_eq!(left, right);
+}
+
+#[test]
+fn declicker_smooths_routing_change() {
+    let mut declicker = declicker::Declicker::new();
+    let mut left = vec![0.5; 64];
+    let mut right = vec![-0.25; 64];
+
+    declicker.process(SAMPLE_RATE, [&mut left[..], &mut right[..]]);
+    assert_eq!(left[63], 0.5);
+
+    let mut left = vec![0.0; SAMPLE_RATE as usize];
+    let mut right = vec![0.0; SAMPLE_RATE as usize];
+
+    declicker.arm();
+    declicker.process(SAMPLE_RATE, [&mut left[..], &mut right[..]]);
+
+    assert_eq!(left[0], 0.5);
+    assert_eq!(right[0], -0.25);
+    assert!(left.last().unwrap().abs() < 1e-6);
+    assert!(right.last().unwrap().abs() < 1e-6);
+
+    let mut engine = make_engine(
+        EngineParams::default(),
+        OscillatorConfig {
+            id: OSCILLATOR_ID,
+            ..OscillatorConfig::default()
+        },
+    );
+
+    engine.handle_note_on(0, 60, 1.0);
+
+    let mut before = Vec::new();
+
+    for _ in 0..8 {
+        before = process_block(&mut engine, 64).0;
+    }
+
+    let peak = before.iter().fold(0.0, |peak: Sample, s| peak.max(s.abs()));
+
+    // Disconnecting the voices from the output mid note fades them instead of cutting them off
+    engine.remove_link(
+        &OSCILLATOR_ID,
+        &InputId::new(Input::Audio, OUTPUT_MODULE_ID),
+    );
+
+    let (after, _) = process_block(&mut engine, 64);
+
+    assert!(peak > 1e-3);
+    assert!((after[0] - before[63]).abs() < 0.25 * peak);
+
+    for _ in 0..40 {
+        process_block(&mut engine, 64);
+    }
+
+    assert!(rms(&process_block(&mut engine, 64).0) < 1e-4);
+}
+
 // ---- Routing ----
 
 #[test]
@@ -793,48 +1027,271 @@ fn note_on_off_and_retrigger_processes() {
 }
 
 #[test]
-fn polyphonic_notes_mix_to_output() {
-    let mut engine = make_engine(
-        EngineParams {
-            num_voices: 4,
-            ..EngineParams::default()
-        },
-        OscillatorConfig {
-            id: OSCILLATOR_ID,
-            ..OscillatorConfig::default()
-        },
-    );
+fn release_velocity_scales_envelope_release() {
+    let level_after_release = |velocity: Sample| {
+        let mut engine = make_engine(
+            EngineParams::default(),
+            OscillatorConfig {
+                id: OSCILLATOR_ID,
+                ..OscillatorConfig::default()
+            },
+        );
 
-    engine.handle_note_on(0, 60, 1.0);
-    engine.handle_note_on(0, 64, 1.0);
-    engine.handle_note_on(0, 67, 1.0);
+        let amp_id = engine.add_amplifier();
+        let env_id = engine.add_envelope();
+        let osc_to_out = InputId::new(Input::Audio, OUTPUT_MODULE_ID);
 
-    let (left, _) = process_block(&mut engine, 64);
-    assert!(rms(&left) > 1e-6);
+        engine.remove_link(&OSCILLATOR_ID, &osc_to_out);
+        engine
+            .set_direct_link(OSCILLATOR_ID, InputId::new(Input::Audio, amp_id))
+            .expect("osc -> amp");
+        engine
+            .set_direct_link(amp_id, osc_to_out)
+            .expect("amp -> output");
+        engine
+            .add_link(
+                env_id,
+                InputId::new(Input::Gain, amp_id),
+                StereoSample::ONE,
+            )
+            .expect("env -> amp gain");
+
+        match engine.get_module_mut(env_id) {
+            Some(ModuleHandle::Envelope(env)) => {
+                env.set_keep_voice_alive(true);
+                env.set_release(StereoSample::splat(from_ms(100.0)));
+                env.set_release_velocity(1.0);
+            }
+            _ => panic!("envelope"),
+        }
+
+        engine.handle_note_on(0, 60, 1.0);
+        process_block(&mut engine, 64);
+        engine.handle_note_off(0, 60, velocity);
+
+        // 80 ms, past the halved release but within the doubled one
+        for _ in 0..60 {
+            process_block(&mut engine, 64);
+        }
+
+        rms(&process_block(&mut engine, 64).0)
+    };
+
+    assert!(level_after_release(1.0) < 1e-6);
+    assert!(level_after_release(0.0) > 1e-4);
 }
 
-// ---- Extended coverage ----
+#[test]
+fn note_velocity_scales_envelope_times() {
+    let level_after_decay = |velocity: Sample| {
+        let mut engine = make_engine(
+            EngineParams::default(),
+            OscillatorConfig {
+                id: OSCILLATOR_ID,
+                ..OscillatorConfig::default()
+            },
+        );
+
+        let amp_id = engine.add_amplifier();
+        let env_id = engine.add_envelope();
+        let osc_to_out = InputId::new(Input::Audio, OUTPUT_MODULE_ID);
+
+        engine.remove_link(&OSCILLATOR_ID, &osc_to_out);
+        engine
+            .set_direct_link(OSCILLATOR_ID, InputId::new(Input::Audio, amp_id))
+            .expect("osc -> amp");
+        engine
+            .set_direct_link(amp_id, osc_to_out)
+            .expect("amp -> output");
+        engine
+            .add_link(env_id, InputId::new(Input::Gain, amp_id), StereoSample::ONE)
+            .expect("env -> amp gain");
+
+        match engine.get_module_mut(env_id) {
+            Some(ModuleHandle::Envelope(env)) => {
+                env.set_decay(StereoSample::splat(from_ms(100.0)));
+                env.set_sustain(StereoSample::splat(0.0));
+                env.set_velocity_time(1.0);
+            }
+            _ => panic!("envelope"),
+        }
+
+        engine.handle_note_on(0, 60, velocity);
+
+        // 80 ms, past the halved decay but within the doubled one
+        for _ in 0..60 {
+            process_block(&mut engine, 64);
+        }
+
+        rms(&process_block(&mut engine, 64).0)
+    };
+
+    assert!(level_after_decay(1.0) < 1e-6);
+    assert!(level_after_decay(0.0) > 1e-4);
+}
 
 #[test]
-fn full_patch_config_round_trips() {
-    let engine = make_full_patch_engine(EngineParams {
-        num_voices: 2,
-        block_size: 64,
-        ..EngineParams::default()
-    });
-    let cfg = engine.get_config();
-    let (volume, external_params) = test_deps();
+fn envelope_loop_repeats_while_held() {
+    let level_after = |loop_mode: EnvelopeLoop, loop_count: usize, blocks: usize| {
+        let mut engine = make_engine(
+            EngineParams::default(),
+            OscillatorConfig {
+                id: OSCILLATOR_ID,
+                ..OscillatorConfig::default()
+            },
+        );
 
-    let rebuilt = SynthEngine::try_new(&cfg, volume, external_params, SAMPLE_RATE)
-        .expect("full patch config deserializes");
+        let amp_id = engine.add_amplifier();
+        let env_id = engine.add_envelope();
+        let osc_to_out = InputId::new(Input::Audio, OUTPUT_MODULE_ID);
 
-    assert_eq!(rebuilt.get_config().modules.len(), cfg.modules.len());
-    assert_eq!(rebuilt.get_config().links.len(), cfg.links.len());
-    assert_eq!(rebuilt.get_config().engine.block_size, 64);
+        engine.remove_link(&OSCILLATOR_ID, &osc_to_out);
+        engine
+            .set_direct_link(OSCILLATOR_ID, InputId::new(Input::Audio, amp_id))
+            .expect("osc -> amp");
+        engine
+            .set_direct_link(amp_id, osc_to_out)
+            .expect("amp -> output");
+        engine
+            .add_link(env_id, InputId::new(Input::Gain, amp_id), StereoSample::ONE)
+            .expect("env -> amp gain");
+
+        // A 20 ms cycle from silence to the peak and back
+        match engine.get_module_mut(env_id) {
+            Some(ModuleHandle::Envelope(env)) => {
+                env.set_attack(StereoSample::splat(from_ms(10.0)));
+                env.set_decay(StereoSample::splat(from_ms(10.0)));
+                env.set_sustain(StereoSample::splat(0.0));
+                env.set_loop_mode(loop_mode);
+                env.set_loop_count(loop_count);
+                env.set_loop_fade(0.0);
+            }
+            _ => panic!("envelope"),
+        }
+
+        engine.handle_note_on(0, 60, 1.0);
+
+        for _ in 0..blocks {
+            process_block(&mut engine, 64);
+        }
+
+        rms(&process_block(&mut engine, 240).0)
+    };
+
+    // 100 ms in, the 5 ms analysed span the attack of the sixth cycle
+    assert!(level_after(EnvelopeLoop::Off, 0, 75) < 1e-6);
+    assert!(level_after(EnvelopeLoop::AttackDecay, 0, 75) > 1e-4);
+    assert!(level_after(EnvelopeLoop::AttackHoldDecay, 0, 75) > 1e-4);
+    assert!(level_after(EnvelopeLoop::AttackDecay, 2, 75) < 1e-6);
+    assert!(level_after(EnvelopeLoop::AttackDecay, 2, 30) > 1e-4);
 }
 
 #[test]
-fn engine_extended_setters_round_trip() {
+fn envelope_output_range_and_invert() {
+    let sustained_level = |sustain: Sample, output_min: Sample, invert: bool| {
+        let mut engine = make_engine(
+            EngineParams::default(),
+            OscillatorConfig {
+                id: OSCILLATOR_ID,
+                ..OscillatorConfig::default()
+            },
+        );
+
+        let amp_id = engine.add_amplifier();
+        let env_id = engine.add_envelope();
+        let osc_to_out = InputId::new(Input::Audio, OUTPUT_MODULE_ID);
+
+        engine.remove_link(&OSCILLATOR_ID, &osc_to_out);
+        engine
+            .set_direct_link(OSCILLATOR_ID, InputId::new(Input::Audio, amp_id))
+            .expect("osc -> amp");
+        engine
+            .set_direct_link(amp_id, osc_to_out)
+            .expect("amp -> output");
+        engine
+            .add_link(env_id, InputId::new(Input::Gain, amp_id), StereoSample::ONE)
+            .expect("env -> amp gain");
+
+        match engine.get_module_mut(env_id) {
+            Some(ModuleHandle::Envelope(env)) => {
+                env.set_decay(StereoSample::splat(0.0));
+                env.set_sustain(StereoSample::splat(sustain));
+                env.set_output_min(output_min);
+                env.set_invert(invert);
+            }
+            _ => panic!("envelope"),
+        }
+
+        engine.handle_note_on(0, 60, 1.0);
+
+        for _ in 0..8 {
+            process_block(&mut engine, 64);
+        }
+
+        rms(&process_block(&mut engine, 256).0)
+    };
+
+    let full = sustained_level(1.0, 0.0, false);
+
+    assert!(full > 1e-4);
+    assert!(sustained_level(0.0, 0.0, false) < 1e-6);
+    assert!(sustained_level(1.0, 0.0, true) < 1e-6);
+    assert!((sustained_level(0.0, 0.0, true) - full).abs() < 0.01 * full);
+    assert!((sustained_level(0.0, 0.5, false) - 0.5 * full).abs() < 0.01 * full);
+}
+
+#[test]
+fn global_lfo_keeps_running_between_notes() {
+    let level_after_note_on = |global: bool| {
+        let mut engine = make_engine(
+            EngineParams::default(),
+            OscillatorConfig {
+                id: OSCILLATOR_ID,
+                ..OscillatorConfig::default()
+            },
+        );
+
+        let amp_id = engine.add_amplifier();
+        let lfo_id = engine.add_lfo();
+        let osc_to_out = InputId::new(Input::Audio, OUTPUT_MODULE_ID);
+
+        engine.remove_link(&OSCILLATOR_ID, &osc_to_out);
+        engine
+            .set_direct_link(OSCILLATOR_ID, InputId::new(Input::Audio, amp_id))
+            .expect("osc -> amp");
+        engine
+            .set_direct_link(amp_id, osc_to_out)
+            .expect("amp -> output");
+        engine
+            .add_link(lfo_id, InputId::new(Input::Gain, amp_id), StereoSample::ONE)
+            .expect("lfo -> amp gain");
+
+        // Open for the first half of each 100 ms cycle
+        match engine.get_module_mut(lfo_id) {
+            Some(ModuleHandle::Lfo(lfo)) => {
+                lfo.set_shape(LfoShape::Square);
+                lfo.set_frequency(StereoSample::splat(10.0));
+                lfo.set_global(global);
+            }
+            _ => panic!("lfo"),
+        }
+
+        // 60 ms without notes, the global phase is in the closed half by then
+        for _ in 0..45 {
+            process_block(&mut engine, 64);
+        }
+
+        engine.handle_note_on(0, 60, 1.0);
+
+        rms(&process_block(&mut engine, 480).0)
+    };
+
+    assert!(level_after_note_on(false) > 1e-4);
+    assert!(level_after_note_on(true) < 1e-6);
+}
+
+#[test]
+fn envelope_follower_tracks_sidechain_level() {
     let mut engine = make_engine(
         EngineParams::default(),
         OscillatorConfig {
@@ -843,37 +1300,50 @@ fn engine_extended_setters_round_trip() {
         },
     );
 
-    let kill_time = from_ms(20.0);
-    engine.set_num_voices(3);
-    engine.set_legato(true);
-    engine.set_block_size(32);
-    engine.set_voice_kill_time(kill_time);
-    engine.set_oversampling(true);
-    engine.set_stereo_spectrum(false);
-    engine.set_output_gain(StereoSample::splat(0.5));
+    let amp_id = engine.add_amplifier();
+    let follower_id = engine.add_envelope_follower();
+    let osc_to_out = InputId::new(Input::Audio, OUTPUT_MODULE_ID);
 
-    let dst = InputId::new(Input::Spectrum, OSCILLATOR_ID);
-    engine.update_link_amount(&HARMONIC_EDITOR_ID, &dst, StereoSample::splat(0.25));
+    engine.remove_link(&OSCILLATOR_ID, &osc_to_out);
+    engine
+        .set_direct_link(OSCILLATOR_ID, InputId::new(Input::Audio, amp_id))
+        .expect("osc -> amp");
+    engine
+        .set_direct_link(amp_id, osc_to_out)
+        .expect("amp -> output");
+    engine
+        .add_link(
+            follower_id,
+            InputId::new(Input::Gain, amp_id),
+            StereoSample::ONE,
+        )
+        .expect("follower -> amp gain");
 
-    let cfg = engine.get_config();
-    assert_eq!(cfg.engine.num_voices, 3);
-    assert!(cfg.engine.legato);
-    assert_eq!(cfg.engine.block_size, 32);
-    assert_eq!(engine.get_voice_kill_time(), kill_time);
-    assert!(cfg.engine.oversampling);
-    assert!(!cfg.engine.stereo_spectrum);
-    assert_eq!(cfg.engine.output_gain, StereoSample::splat(0.5));
+    let follow = |engine: &mut SynthEngine, level: Sample, samples: usize| {
+        rms(&process_with_sidechain(engine, &vec![level; samples]))
+    };
 
-    let link = cfg
-        .links
-        .iter()
-        .find(|link| link.src_id == HARMONIC_EDITOR_ID && link.dst_id == OSCILLATOR_ID)
-        .expect("harmonic -> osc link");
-    assert_eq!(link.amount, StereoSample::splat(0.25));
+    // The follower settles before the note starts
+    follow(&mut engine, 1.0, 9600);
+    engine.handle_note_on(0, 60, 1.0);
+
+    let onset = follow(&mut engine, 1.0, 4800);
+    let full = follow(&mut engine, 1.0, 4800);
+
+    follow(&mut engine, 0.5, 48000);
+
+    let half = follow(&mut engine, 0.5, 4800);
+
+    follow(&mut engine, 0.0, 96000);
+
+    assert!(full > 1e-3);
+    assert!((onset - full).abs() < full * 0.02);
+    assert!((half * 2.0 - full).abs() < full * 0.02);
+    assert!(follow(&mut engine, 0.0, 4800) < full * 1e-3);
 }
 
 #[test]
-fn process_with_update_ui_runs() {
+fn spectral_analyzer_follows_sidechain_spectrum() {
     let mut engine = make_engine(
         EngineParams::default(),
         OscillatorConfig {
@@ -882,13 +1352,28 @@ fn process_with_update_ui_runs() {
         },
     );
 
-    engine.handle_note_on(0, 60, 1.0);
-    let (left, _) = process_block_with_ui(&mut engine, 64, true);
-    assert!(rms(&left) > 1e-6);
+    let analyzer_id = engine.add_spectral_analyzer();
+
+    engine
+        .set_direct_link(analyzer_id, InputId::new(Input::Spectrum, OSCILLATOR_ID))
+        .expect("analyzer -> osc spectrum");
+    engine.handle_note_on(0, 69, 1.0);
+
+    let sine: Vec<Sample> = (0..9600)
+        .map(|idx| (std::f32::consts::TAU * 440.0 * idx as Sample / SAMPLE_RATE).sin())
+        .collect();
+
+    // Only the fundamental of the note matches the sidechain
+    let voiced = rms(&process_with_sidechain(&mut engine, &sine)[4800..]);
+
+    assert!(voiced > 1e-3);
+
+    process_with_sidechain(&mut engine, &[0.0; 4800]);
+    assert!(rms(&process_with_sidechain(&mut engine, &[0.0; 4800])) < voiced * 1e-3);
 }
 
 #[test]
-fn add_link_connects_new_modules() {
+fn lfo_delay_and_fade_in_swell_after_note_start() {
     let mut engine = make_engine(
         EngineParams::default(),
         OscillatorConfig {
@@ -898,7 +1383,7 @@ fn add_link_connects_new_modules() {
     );
 
     let amp_id = engine.add_amplifier();
-    let env_id = engine.add_envelope();
+    let lfo_id = engine.add_lfo();
     let osc_to_out = InputId::new(Input::Audio, OUTPUT_MODULE_ID);
 
     engine.remove_link(&OSCILLATOR_ID, &osc_to_out);
@@ -909,25 +1394,243 @@ fn add_link_connects_new_modules() {
         .set_direct_link(amp_id, osc_to_out)
         .expect("amp -> output");
     engine
-        .add_link(
-            env_id,
-            InputId::new(Input::Gain, amp_id),
-            StereoSample::ONE,
-        )
-        .expect("env -> amp gain");
-
-    match engine.get_module_mut(amp_id) {
-        Some(ModuleHandle::Amplifier(amp)) => amp.set_gain(StereoSample::ONE),
-        _ => panic!("amplifier"),
+        .add_link(lfo_id, InputId::new(Input::Gain, amp_id), StereoSample::ONE)
+        .expect("lfo -> amp gain");
+
+    // Stays at 1.0 for the first half second, so only the onset shapes the level
+    match engine.get_module_mut(lfo_id) {
+        Some(ModuleHandle::Lfo(lfo)) => {
+            lfo.set_shape(LfoShape::Square);
+            lfo.set_delay(StereoSample::splat(from_ms(50.0)));
+            lfo.set_fade_in(StereoSample::splat(from_ms(100.0)));
+        }
+        _ => panic!("lfo"),
     }
 
-    assert!(
-        engine
-            .get_config()
-            .links
-            .iter()
-            .any(|link| link.src_id == env_id && link.dst_id == amp_id)
-    );
+    engine.handle_note_on(0, 60, 1.0);
+
+    // 0 - 40 ms, 100 - 110 ms and 200 - 210 ms after the note start
+    let delayed = rms(&process_block(&mut engine, 1920).0);
+
+    process_block(&mut engine, 2880);
+    let fading = rms(&process_block(&mut engine, 480).0);
+
+    process_block(&mut engine, 4320);
+    let full = rms(&process_block(&mut engine, 480).0);
+
+    assert!(delayed < 1e-6);
+    assert!(full > 1e-4);
+    assert!(fading > 0.4 * full && fading < 0.65 * full);
+}
+
+#[test]
+fn audio_rate_sustain_follows_modulation_within_block() {
+    const ENVELOPE_ID: ModuleId = 3;
+    const AMP_ID: ModuleId = 4;
+    const SQUARE_ID: ModuleId = 5;
+
+    let render = |modulated: bool| {
+        let mut config = minimal_engine_config(
+            EngineParams::default(),
+            OscillatorConfig {
+                id: OSCILLATOR_ID,
+                ..OscillatorConfig::default()
+            },
+        );
+
+        config.modules.extend([
+            ModuleConfig::Envelope(Box::new(EnvelopeConfig {
+                id: ENVELOPE_ID,
+                decay: 0.0.into(),
+                sustain: 0.5.into(),
+                ..EnvelopeConfig::default()
+            })),
+            ModuleConfig::Amplifier(Box::new(AmplifierConfig {
+                id: AMP_ID,
+                ..AmplifierConfig::default()
+            })),
+            // Flips every 64 samples, twice within a block of 128
+            ModuleConfig::Lfo(Box::new(LfoConfig {
+                id: SQUARE_ID,
+                shape: LfoShape::Square,
+                frequency: 375.0.into(),
+                ..LfoConfig::default()
+            })),
+        ]);
+        config.links[1] = link(OSCILLATOR_ID, AMP_ID, Input::Audio);
+        config.links.extend([
+            link(AMP_ID, OUTPUT_MODULE_ID, Input::Audio),
+            link(ENVELOPE_ID, AMP_ID, Input::Gain),
+        ]);
+
+        if modulated {
+            config.links.push(LinkConfig {
+                amount: StereoSample::splat(0.5),
+                ..link(SQUARE_ID, ENVELOPE_ID, Input::Sustain)
+            });
+        }
+
+        let (volume, external_params) = test_deps();
+        let mut engine = SynthEngine::try_new(&config, volume, external_params, SAMPLE_RATE)
+            .expect("valid engine config");
+
+        engine.handle_note_on(0, 60, 1.0);
+        process_block(&mut engine, 128);
+        process_block(&mut engine, 128).0
+    };
+
+    let plain = render(false);
+    let modulated = render(true);
+    let ratios: Vec<Sample> = plain
+        .iter()
+        .zip(&modulated)
+        .filter(|(plain, _)| plain.abs() > 1e-3)
+        .map(|(plain, modulated)| modulated / plain)
+        .collect();
+    let min = ratios.iter().copied().fold(Sample::INFINITY, Sample::min);
+    let max = ratios.iter().copied().fold(0.0, Sample::max);
+
+    // Sustain of 0.5 and 1.0 within the same block, not one value per block
+    assert!((min - 1.0).abs() < 1e-3);
+    assert!((max - 2.0).abs() < 1e-3);
+}
+
+#[test]
+fn polyphonic_notes_mix_to_output() {
+    let mut engine = make_engine(
+        EngineParams {
+            num_voices: 4,
+            ..EngineParams::default()
+        },
+        OscillatorConfig {
+            id: OSCILLATOR_ID,
+            ..OscillatorConfig::default()
+        },
+    );
+
+    engine.handle_note_on(0, 60, 1.0);
+    engine.handle_note_on(0, 64, 1.0);
+    engine.handle_note_on(0, 67, 1.0);
+
+    let (left, _) = process_block(&mut engine, 64);
+    assert!(rms(&left) > 1e-6);
+}
+
+// ---- Extended coverage ----
+
+#[test]
+fn full_patch_config_round_trips() {
+    let engine = make_full_patch_engine(EngineParams {
+        num_voices: 2,
+        block_size: 64,
+        ..EngineParams::default()
+    });
+    let cfg = engine.get_config();
+    let (volume, external_params) = test_deps();
+
+    let rebuilt = SynthEngine::try_new(&cfg, volume, external_params, SAMPLE_RATE)
+        .expect("full patch config deserializes");
+
+    assert_eq!(rebuilt.get_config().modules.len(), cfg.modules.len());
+    assert_eq!(rebuilt.get_config().links.len(), cfg.links.len());
+    assert_eq!(rebuilt.get_config().engine.block_size, 64);
+}
+
+#[test]
+fn engine_extended_setters_round_trip() {
+    let mut engine = make_engine(
+        EngineParams::default(),
+        OscillatorConfig {
+            id: OSCILLATOR_ID,
+            ..OscillatorConfig::default()
+        },
+    );
+
+    let kill_time = from_ms(20.0);
+    engine.set_num_voices(3);
+    engine.set_legato(true);
+    engine.set_block_size(32);
+    engine.set_voice_kill_time(kill_time);
+    engine.set_oversampling(Oversampling::X2);
+    engine.set_stereo_spectrum(false);
+    engine.set_output_gain(StereoSample::splat(0.5));
+
+    let dst = InputId::new(Input::Spectrum, OSCILLATOR_ID);
+    engine.update_link_amount(&HARMONIC_EDITOR_ID, &dst, StereoSample::splat(0.25));
+
+    let cfg = engine.get_config();
+    assert_eq!(cfg.engine.num_voices, 3);
+    assert!(cfg.engine.legato);
+    assert_eq!(cfg.engine.block_size, 32);
+    assert_eq!(engine.get_voice_kill_time(), kill_time);
+    assert_eq!(cfg.engine.oversampling, Oversampling::X2);
+    assert!(!cfg.engine.stereo_spectrum);
+    assert_eq!(cfg.engine.output_gain, StereoSample::splat(0.5));
+
+    let link = cfg
+        .links
+        .iter()
+        .find(|link| link.src_id == HARMONIC_EDITOR_ID && link.dst_id == OSCILLATOR_ID)
+        .expect("harmonic -> osc link");
+    assert_eq!(link.amount, StereoSample::splat(0.25));
+}
+
+#[test]
+fn process_with_update_ui_runs() {
+    let mut engine = make_engine(
+        EngineParams::default(),
+        OscillatorConfig {
+            id: OSCILLATOR_ID,
+            ..OscillatorConfig::default()
+        },
+    );
+
+    engine.handle_note_on(0, 60, 1.0);
+    let (left, _) = process_block_with_ui(&mut engine, 64, true);
+    assert!(rms(&left) > 1e-6);
+}
+
+#[test]
+fn add_link_connects_new_modules() {
+    let mut engine = make_engine(
+        EngineParams::default(),
+        OscillatorConfig {
+            id: OSCILLATOR_ID,
+            ..OscillatorConfig::default()
+        },
+    );
+
+    let amp_id = engine.add_amplifier();
+    let env_id = engine.add_envelope();
+    let osc_to_out = InputId::new(Input::Audio, OUTPUT_MODULE_ID);
+
+    engine.remove_link(&OSCILLATOR_ID, &osc_to_out);
+    engine
+        .set_direct_link(OSCILLATOR_ID, InputId::new(Input::Audio, amp_id))
+        .expect("osc -> amp");
+    engine
+        .set_direct_link(amp_id, osc_to_out)
+        .expect("amp -> output");
+    engine
+        .add_link(
+            env_id,
+            InputId::new(Input::Gain, amp_id),
+            StereoSample::ONE,
+        )
+        .expect("env -> amp gain");
+
+    match engine.get_module_mut(amp_id) {
+        Some(ModuleHandle::Amplifier(amp)) => amp.set_gain(StereoSample::ONE),
+        _ => panic!("amplifier"),
+    }
+
+    assert!(
+        engine
+            .get_config()
+            .links
+            .iter()
+            .any(|link| link.src_id == env_id && link.dst_id == amp_id)
+    );
 
     engine.handle_note_on(0, 60, 1.0);
     let (left, _) = process_block(&mut engine, 64);
@@ -962,6 +1665,72 @@ fn link_modulation_round_trips_in_config() {
     assert!(link.modulator_id.is_none());
 }
 
+#[test]
+fn link_curve_shapes_source_and_round_trips_in_config() {
+    assert_eq!(LinkCurve::Linear.apply(0.3), 0.3);
+    assert_eq!(LinkCurve::Steps(4).apply(0.3), 0.25);
+    assert_eq!(LinkCurve::Steps(4).apply(-0.3), -0.25);
+    assert!(LinkCurve::Exponential(0.5).apply(0.5) > 0.5);
+    assert!(LinkCurve::Exponential(-0.5).apply(0.5) < 0.5);
+    assert!(LinkCurve::SCurve(1.0).apply(0.25) < 0.25);
+    assert_eq!(LinkCurve::SCurve(1.0).apply(1.0), 1.0);
+    assert_eq!(LinkCurve::Exponential(0.5).apply(2.0), 2.0);
+
+    let mut engine = make_full_patch_engine(EngineParams::default());
+    let gain_dst = InputId::new(Input::Gain, AMPLIFIER_ID);
+
+    engine.set_link_curve(ENVELOPE_AMP_ID, &gain_dst, LinkCurve::Steps(8));
+
+    let cfg = engine.get_config();
+    let link = cfg
+        .links
+        .iter()
+        .find(|link| link.src_id == ENVELOPE_AMP_ID && link.dst_id == AMPLIFIER_ID)
+        .expect("env -> amp gain");
+    assert_eq!(link.curve, LinkCurve::Steps(8));
+
+    let (volume, external_params) = test_deps();
+    let mut engine = SynthEngine::try_new(&cfg, volume, external_params, SAMPLE_RATE)
+        .expect("engine with a shaped link");
+
+    engine.handle_note_on(0, 60, 1.0);
+    let (left, right) = process_block(&mut engine, 128);
+    assert!(left.iter().chain(&right).all(|s| s.is_finite()));
+}
+
+#[test]
+fn link_polarity_and_offset_reshape_source_and_round_trip() {
+    let slot = InputSlot {
+        src_slot: 0,
+        modulation_slot: None,
+        amount: StereoSample::ONE,
+        curve: LinkCurve::Linear,
+        polarity: LinkPolarity::Unipolar,
+        offset: -0.25,
+        mode: LinkMode::Add,
+    };
+
+    assert_eq!(slot.shape(-1.0), -0.25);
+    assert_eq!(slot.shape(1.0), 0.75);
+    assert_eq!(LinkPolarity::Bipolar.apply(0.5), 0.0);
+    assert_eq!(LinkPolarity::Rectified.apply(-0.5), 0.5);
+
+    let mut engine = make_full_patch_engine(EngineParams::default());
+    let gain_dst = InputId::new(Input::Gain, AMPLIFIER_ID);
+
+    engine.set_link_polarity(ENVELOPE_AMP_ID, &gain_dst, LinkPolarity::Rectified);
+    engine.set_link_offset(ENVELOPE_AMP_ID, &gain_dst, 0.1);
+
+    let cfg = engine.get_config();
+    let link = cfg
+        .links
+        .iter()
+        .find(|link| link.src_id == ENVELOPE_AMP_ID && link.dst_id == AMPLIFIER_ID)
+        .expect("env -> amp gain");
+    assert_eq!(link.polarity, LinkPolarity::Rectified);
+    assert_eq!(link.offset, 0.1);
+}
+
 #[test]
 fn link_modulation_in_preset_builds() {
     let mut config = full_patch_engine_config(EngineParams::default());
@@ -971,6 +1740,10 @@ fn link_modulation_in_preset_builds() {
         dst_input: Input::PitchShift,
         amount: StereoSample::splat(0.5),
         modulator_id: None,
+        curve: LinkCurve::Linear,
+        polarity: LinkPolarity::Unchanged,
+        offset: 0.0,
+        mode: LinkMode::Add,
     });
 
     let modulated = config
@@ -1096,9 +1869,10 @@ fn runtime_add_all_module_types() {
         engine.add_spectral_mixer(),
         engine.add_expressions(),
         engine.add_external_param(),
+        engine.add_midi_control(),
     ];
 
-    assert_eq!(ids.len(), 12);
+    assert_eq!(ids.len(), 13);
     assert!(matches!(
         engine.get_module(ids[0]),
         Some(ModuleHandle::HarmonicEditor(_))
@@ -1141,62 +1915,2494 @@ fn runtime_add_all_module_types() {
         engine.get_module(ids[11]),
         Some(ModuleHandle::ExternalParam(_))
     ));
+    assert!(matches!(
+        engine.get_module(ids[12]),
+        Some(ModuleHandle::MidiControl(_))
+    ));
 }
 
 #[test]
-fn remove_missing_module_is_noop() {
+fn pitch_bend_modulates_oscillator() {
     let mut engine = make_engine(
-        EngineParams::default(),
+        EngineParams {
+            pitch_bend_range: 12.0,
+            ..EngineParams::default()
+        },
         OscillatorConfig {
             id: OSCILLATOR_ID,
             ..OscillatorConfig::default()
         },
     );
 
-    let links_before = engine.get_config().links.len();
-    engine.remove_module(9999);
-    assert_eq!(engine.get_config().links.len(), links_before);
-}
+    let midi_id = engine.add_midi_control();
 
-#[test]
-fn link_rejects_invalid_module_id() {
-    let mut engine = make_engine(
-        EngineParams::default(),
-        OscillatorConfig {
-            id: OSCILLATOR_ID,
-            ..OscillatorConfig::default()
-        },
-    );
+    engine
+        .add_link(
+            midi_id,
+            InputId::new(Input::PitchShift, OSCILLATOR_ID),
+            StereoSample::ONE,
+        )
+        .expect("midi control -> pitch shift");
 
-    let dst = InputId::new(Input::Spectrum, OSCILLATOR_ID);
-    let err = engine
-        .add_link(9999, dst, StereoSample::ONE)
-        .expect_err("unknown source module");
+    engine.handle_pitch_bend(0, 1.0);
+    engine.handle_midi_cc(1, 0.5);
+    engine.handle_note_on(0, 60, 1.0);
 
-    assert!(err.contains("Invalid") || err.contains("mismatch"));
+    let (left, right) = process_block(&mut engine, 64);
+    assert!(rms(&left) > 1e-6);
+    assert!(left.iter().chain(right.iter()).all(|s| s.is_finite()));
+
+    let cfg = engine.get_config();
+    assert_eq!(cfg.engine.pitch_bend_range, 12.0);
+    assert!(
+        cfg.modules
+            .iter()
+            .any(|m| matches!(m, ModuleConfig::MidiControl(c) if c.id == midi_id))
+    );
 }
 
 #[test]
-fn handle_note_expression_and_choke_process() {
-    let mut engine = make_full_patch_engine(EngineParams {
-        num_voices: 2,
-        ..EngineParams::default()
-    });
+fn oscillator_pitch_range_and_frequency_limits_clamp_pitch() {
+    let render = |osc: OscillatorConfig| {
+        let mut engine = make_engine(
+            EngineParams::default(),
+            OscillatorConfig {
+                id: OSCILLATOR_ID,
+                ..osc
+            },
+        );
 
-    engine.handle_note_on(0, 60, 0.5);
-    engine.handle_note_expression(0, 60, Expression::Velocity, 1.0);
-    process_block(&mut engine, 64);
+        engine.handle_note_on(0, 69, 1.0);
+        process_block(&mut engine, 256).0
+    };
+    let assert_same = |a: Vec<Sample>, b: Vec<Sample>| {
+        assert!(rms(&a) > 1e-3);
+        assert!(a.iter().zip(&b).all(|(a, b)| (a - b).abs() < 1e-3));
+    };
 
-    engine.handle_choke(0, 60);
-    let (left, _) = process_block(&mut engine, 64);
-    assert!(left.iter().all(|s| s.is_finite()));
+    assert_same(
+        render(OscillatorConfig {
+            pitch_shift: 4.0.into(),
+            pitch_range: 1.0,
+            ..OscillatorConfig::default()
+        }),
+        render(OscillatorConfig {
+            pitch_shift: 1.0.into(),
+            ..OscillatorConfig::default()
+        }),
+    );
+
+    // A4 clamped to 220 Hz plays an octave down
+    assert_same(
+        render(OscillatorConfig {
+            max_frequency: 220.0,
+            ..OscillatorConfig::default()
+        }),
+        render(OscillatorConfig {
+            pitch_shift: (-1.0).into(),
+            ..OscillatorConfig::default()
+        }),
+    );
 }
 
 #[test]
-fn oversampling_and_mono_spectrum_process() {
+fn pitch_sweep_past_nyquist_does_not_alias() {
+    const ENVELOPE_ID: ModuleId = 3;
+
+    let render = |sweep: bool| {
+        let mut config = minimal_engine_config(
+            EngineParams::default(),
+            OscillatorConfig {
+                id: OSCILLATOR_ID,
+                ..OscillatorConfig::default()
+            },
+        );
+
+        // Only harmonic 8, A4 plays it at 3520 Hz and three octaves up it's past Nyquist
+        if let ModuleConfig::HarmonicEditor(editor) = &mut config.modules[0] {
+            for channel in &mut editor.spectrum {
+                for (harmonic, bin) in channel.iter_mut().enumerate() {
+                    if harmonic != 8 {
+                        *bin = ComplexCfg::default();
+                    }
+                }
+            }
+        }
+
+        // The attack sweeps the pitch up within the first block
+        config
+            .modules
+            .push(ModuleConfig::Envelope(Box::new(EnvelopeConfig {
+                id: ENVELOPE_ID,
+                attack: from_ms(1.0).into(),
+                sustain: 1.0.into(),
+                ..EnvelopeConfig::default()
+            })));
+
+        if sweep {
+            config.links.push(LinkConfig {
+                amount: StereoSample::splat(3.0),
+                ..link(ENVELOPE_ID, OSCILLATOR_ID, Input::PitchShift)
+            });
+        }
+
+        let (volume, external_params) = test_deps();
+        let mut engine = SynthEngine::try_new(&config, volume, external_params, SAMPLE_RATE)
+            .expect("valid engine config");
+        let mut left = Vec::new();
+
+        engine.handle_note_on(0, 69, 1.0);
+
+        for _ in 0..4 {
+            left.extend(process_block(&mut engine, 128).0);
+        }
+
+        rms(&left)
+    };
+
+    assert!(render(false) > 1e-3);
+    assert!(render(true) < 1e-6);
+}
+
+#[test]
+fn unison_stereo_spread_and_random_phase_process() {
+    let mut engine = make_engine(
+        EngineParams::default(),
+        OscillatorConfig {
+            id: OSCILLATOR_ID,
+            unison_voices: 3,
+            random_phase: true,
+            stereo_spread: 1.0,
+            unison_blend: 0.5,
+            ..OscillatorConfig::default()
+        },
+    );
+
+    engine.handle_note_on(0, 60, 1.0);
+
+    let (left, right) = process_block(&mut engine, 64);
+    assert!(rms(&left) > 1e-6);
+    assert!(left.iter().chain(right.iter()).all(|s| s.is_finite()));
+    assert!(left.iter().zip(&right).any(|(l, r)| (l - r).abs() > 1e-6));
+
+    let cfg = engine.get_config();
+    let osc = cfg
+        .modules
+        .iter()
+        .find_map(|m| match m {
+            ModuleConfig::Oscillator(c) => Some(c),
+            _ => None,
+        })
+        .expect("oscillator config");
+
+    assert!(osc.random_phase);
+    assert_eq!(osc.stereo_spread, 1.0);
+    assert_eq!(osc.unison_blend, 0.5);
+}
+
+#[test]
+fn harmonic_editor_sets_harmonics_range() {
+    let mut editor = HarmonicEditor::new(HARMONIC_EDITOR_ID);
+
+    editor.set_harmonics_range(
+        2,
+        &[
+            StereoSample::splat(0.5),
+            StereoSample::new(0.25, 0.75),
+            StereoSample::ZERO,
+        ],
+    );
+
+    let harmonics = HarmonicEditor::harmonics_from_config(&editor.get_config());
+    let close = |a: Sample, b: Sample| (a - b).abs() < 1e-5;
+
+    assert!(close(harmonics[1].left(), 1.0));
+    assert!(close(harmonics[2].left(), 0.5));
+    assert!(close(harmonics[3].left(), 0.25));
+    assert!(close(harmonics[3].right(), 0.75));
+    assert!(close(harmonics[4].left(), 0.0));
+    assert!(close(harmonics[5].left(), 1.0));
+}
+
+#[test]
+fn harmonic_editor_phases_keep_levels_and_round_trip() {
+    let mut editor = HarmonicEditor::new(HARMONIC_EDITOR_ID);
+    let close = |a: Sample, b: Sample| (a - b).abs() < 1e-5;
+
+    editor.set_harmonic(3, StereoSample::splat(0.5));
+    editor.set_phase(3, StereoSample::new(0.25, 0.5));
+
+    let cfg = editor.get_config();
+    let harmonics = HarmonicEditor::harmonics_from_config(&cfg);
+    let phases = HarmonicEditor::phases_from_config(&cfg);
+
+    assert!(close(harmonics[3].left(), 0.5));
+    assert!(close(harmonics[3].right(), 0.5));
+    assert!(close(phases[3].left(), 0.25));
+    assert!(close(phases[3].right(), 0.5));
+
+    let restored = HarmonicEditor::from_config(&cfg);
+    let restored_phases = HarmonicEditor::phases_from_config(&restored.get_config());
+    assert!(close(restored_phases[3].left(), 0.25));
+
+    editor.randomize_phases();
+    editor.zero_phases();
+
+    let cfg = editor.get_config();
+    let harmonics = HarmonicEditor::harmonics_from_config(&cfg);
+
+    assert!(close(harmonics[3].left(), 0.5));
+    assert!(
+        HarmonicEditor::phases_from_config(&cfg)
+            .iter()
+            .all(|phase| phase.left() == 0.0 && phase.right() == 0.0)
+    );
+}
+
+#[test]
+fn harmonic_editor_reads_waveform_spectrum() {
+    let write_wav = |name: &str, len: usize, period: usize| {
+        let path = std::env::temp_dir().join(name);
+        let spec = hound::WavSpec {
+            channels: 1,
+            sample_rate: 48_000,
+            bits_per_sample: 32,
+            sample_format: hound::SampleFormat::Float,
+        };
+        let mut writer = hound::WavWriter::create(&path, spec).unwrap();
+
+        for idx in 0..len {
+            let t = f32::consts::TAU * (idx % period) as Sample / period as Sample;
+            writer.write_sample(t.sin() + 0.5 * (3.0 * t).cos()).unwrap();
+        }
+
+        writer.finalize().unwrap();
+        path
+    };
+
+    let expected_gain = |idx: usize, amplitude: Sample| {
+        0.5 * amplitude / HARMONIC_SERIES_BUFFER[idx].norm()
+    };
+    let close = |a: Sample, b: Sample| (a - b).abs() < 1e-3;
+
+    let single_cycle = write_wav("additizer-single-cycle.wav", 512, 512);
+    let spectrum = harmonic_editor::read_waveform_spectrum(&single_cycle).unwrap();
+
+    assert!(close(spectrum.gains[1].left(), expected_gain(1, 1.0)));
+    assert!(close(spectrum.gains[1].right(), expected_gain(1, 1.0)));
+    assert!(close(spectrum.gains[3].left(), expected_gain(3, 0.5)));
+    assert!(spectrum.gains[2].left() < 1e-3);
+    assert!(close(spectrum.phases[1].left(), 0.0));
+
+    let periodic = write_wav("additizer-periodic.wav", 10_000, 300);
+    let spectrum = harmonic_editor::read_waveform_spectrum(&periodic).unwrap();
+
+    assert!(close(spectrum.gains[1].left(), expected_gain(1, 1.0)));
+    assert!(close(spectrum.gains[3].left(), expected_gain(3, 0.5)));
+    assert!(spectrum.gains[2].left() < 1e-3);
+
+    let _ = std::fs::remove_file(single_cycle);
+    let _ = std::fs::remove_file(periodic);
+}
+
+#[test]
+fn harmonic_editor_import_needs_room_for_every_chunk() {
+    let path = std::env::temp_dir().join("additizer-import-chunks.wav");
+    let spec = hound::WavSpec {
+        channels: 1,
+        sample_rate: 48_000,
+        bits_per_sample: 32,
+        sample_format: hound::SampleFormat::Float,
+    };
+    let mut writer = hound::WavWriter::create(&path, spec).unwrap();
+
+    for idx in 0..512 {
+        let t = f32::consts::TAU * idx as Sample / 512.0;
+        writer.write_sample(0.5 * (2.0 * t).sin()).unwrap();
+    }
+
+    writer.finalize().unwrap();
+
+    let mut editor = HarmonicEditor::new(HARMONIC_EDITOR_ID);
+    let mut bridge = harmonic_editor::HarmonicEditorUiBridge::try_new(&mut editor).unwrap();
+    let harmonics = bridge.harmonics();
+
+    // The audio thread hasn't drained the queue yet
+    for _ in 0..100 {
+        bridge.set_morph(true);
+    }
+
+    assert!(bridge.import_waveform(&path).is_err());
+    assert_eq!(bridge.harmonics(), harmonics);
+
+    let mut editor = HarmonicEditor::new(HARMONIC_EDITOR_ID);
+    let mut bridge = harmonic_editor::HarmonicEditorUiBridge::try_new(&mut editor).unwrap();
+
+    bridge.import_waveform(&path).expect("waveform import");
+    assert!(bridge.harmonics()[1].left() < 1e-3);
+    assert!(bridge.harmonics()[2].left() > 0.1);
+
+    let _ = std::fs::remove_file(path);
+}
+
+#[test]
+fn harmonic_editor_snapshots_store_recall_and_round_trip() {
+    let mut editor = HarmonicEditor::new(HARMONIC_EDITOR_ID);
+    let close = |a: Sample, b: Sample| (a - b).abs() < 1e-5;
+
+    editor.store_snapshot(SnapshotSlot::A);
+    editor.set_harmonic(3, StereoSample::splat(0.5));
+    editor.store_snapshot(SnapshotSlot::B);
+    editor.recall_snapshot(SnapshotSlot::A);
+
+    let cfg = editor.get_config();
+    assert!(close(HarmonicEditor::harmonics_from_config(&cfg)[3].left(), 1.0));
+    assert!(cfg.snapshots.iter().all(Option::is_some));
+
+    let mut restored = HarmonicEditor::from_config(&cfg);
+    restored.recall_snapshot(SnapshotSlot::B);
+
+    let harmonics = HarmonicEditor::harmonics_from_config(&restored.get_config());
+    assert!(close(harmonics[3].left(), 0.5));
+    assert!(close(harmonics[3].right(), 0.5));
+}
+
+#[test]
+fn harmonic_editor_morphs_between_snapshots() {
+    let mut engine = make_engine(
+        EngineParams::default(),
+        OscillatorConfig {
+            id: OSCILLATOR_ID,
+            ..OscillatorConfig::default()
+        },
+    );
+
+    match engine.get_module_mut(HARMONIC_EDITOR_ID) {
+        Some(ModuleHandle::HarmonicEditor(editor)) => {
+            editor.store_snapshot(SnapshotSlot::B);
+            editor.set_selected(&SetParams {
+                from: 1,
+                to: SPECTRAL_BUFFER_SIZE - 1,
+                n_th: None,
+                action: SetAction::Set,
+                gain: StereoSample::ZERO,
+            });
+            editor.store_snapshot(SnapshotSlot::A);
+            editor.recall_snapshot(SnapshotSlot::B);
+            editor.set_morph(true);
+        }
+        _ => panic!("harmonic editor"),
+    }
+
+    engine.handle_note_on(0, 60, 1.0);
+
+    let (left, _) = process_block(&mut engine, 64);
+    assert!(rms(&left) < 1e-6);
+
+    match engine.get_module_mut(HARMONIC_EDITOR_ID) {
+        Some(ModuleHandle::HarmonicEditor(editor)) => editor.set_blend(StereoSample::ONE),
+        _ => panic!("harmonic editor"),
+    }
+
+    let (left, right) = process_block(&mut engine, 64);
+    assert!(rms(&left) > 1e-6);
+    assert!(rms(&right) > 1e-6);
+}
+
+#[test]
+fn held_note_rebuilds_wave_after_spectrum_change() {
+    let mut engine = make_engine(
+        EngineParams::default(),
+        OscillatorConfig {
+            id: OSCILLATOR_ID,
+            ..OscillatorConfig::default()
+        },
+    );
+
+    engine.handle_note_on(0, 60, 1.0);
+
+    let first = process_block(&mut engine, 64).0;
+
+    // The static spectrum reuses the table, the level doesn't drift
+    for _ in 0..4 {
+        let (left, _) = process_block(&mut engine, 64);
+        assert!((rms(&left) - rms(&first)).abs() < 0.05 * rms(&first));
+    }
+
+    match engine.get_module_mut(HARMONIC_EDITOR_ID) {
+        Some(ModuleHandle::HarmonicEditor(editor)) => editor.set_selected(&SetParams {
+            from: 1,
+            to: SPECTRAL_BUFFER_SIZE - 1,
+            n_th: None,
+            action: SetAction::Set,
+            gain: StereoSample::ZERO,
+        }),
+        _ => panic!("harmonic editor"),
+    }
+
+    process_block(&mut engine, 64);
+
+    let (left, right) = process_block(&mut engine, 64);
+    assert!(rms(&left) < 1e-6);
+    assert!(rms(&right) < 1e-6);
+}
+
+#[test]
+fn background_tables_keep_the_old_wave_until_built() {
+    let mut engine = make_engine(
+        EngineParams::default(),
+        OscillatorConfig {
+            id: OSCILLATOR_ID,
+            ..OscillatorConfig::default()
+        },
+    );
+
+    engine.set_background_tables(true);
+    engine.handle_note_on(0, 60, 1.0);
+
+    // Note starts build inline
+    assert!(rms(&process_block(&mut engine, 64).0) > 1e-6);
+
+    match engine.get_module_mut(HARMONIC_EDITOR_ID) {
+        Some(ModuleHandle::HarmonicEditor(editor)) => editor.set_selected(&SetParams {
+            from: 1,
+            to: SPECTRAL_BUFFER_SIZE - 1,
+            n_th: None,
+            action: SetAction::Set,
+            gain: StereoSample::ZERO,
+        }),
+        _ => panic!("harmonic editor"),
+    }
+
+    // The block that requests the silent table still plays the old one
+    assert!(rms(&process_block(&mut engine, 64).0) > 1e-6);
+
+    let started = Instant::now();
+
+    while rms(&process_block(&mut engine, 64).0) > 1e-6 {
+        assert!(started.elapsed().as_secs() < 5, "table wasn't built");
+        thread::sleep(Duration::from_millis(1));
+    }
+}
+
+#[test]
+fn spectrum_change_propagates_through_idle_spectral_filter() {
+    let mut engine = make_engine(
+        EngineParams::default(),
+        OscillatorConfig {
+            id: OSCILLATOR_ID,
+            ..OscillatorConfig::default()
+        },
+    );
+
+    let filter_id = engine.add_spectral_filter();
+
+    engine
+        .add_link(
+            HARMONIC_EDITOR_ID,
+            InputId::new(Input::Spectrum, filter_id),
+            StereoSample::ONE,
+        )
+        .expect("editor to filter");
+    engine
+        .set_direct_link(filter_id, InputId::new(Input::Spectrum, OSCILLATOR_ID))
+        .expect("filter to oscillator");
+
+    engine.handle_note_on(0, 60, 1.0);
+
+    let first = process_block(&mut engine, 64).0;
+    assert!(rms(&first) > 1e-6);
+
+    // Nothing changes upstream, the filter keeps its output and the level holds
+    for _ in 0..4 {
+        let (left, _) = process_block(&mut engine, 64);
+        assert!((rms(&left) - rms(&first)).abs() < 0.05 * rms(&first));
+    }
+
+    match engine.get_module_mut(HARMONIC_EDITOR_ID) {
+        Some(ModuleHandle::HarmonicEditor(editor)) => editor.set_selected(&SetParams {
+            from: 1,
+            to: SPECTRAL_BUFFER_SIZE - 1,
+            n_th: None,
+            action: SetAction::Set,
+            gain: StereoSample::ZERO,
+        }),
+        _ => panic!("harmonic editor"),
+    }
+
+    process_block(&mut engine, 64);
+
+    let (left, right) = process_block(&mut engine, 64);
+    assert!(rms(&left) < 1e-6);
+    assert!(rms(&right) < 1e-6);
+}
+
+#[test]
+fn negative_spectral_tilt_darkens_spectrum() {
+    let render = |tilt: Sample| {
+        let mut engine = make_engine(
+            EngineParams::default(),
+            OscillatorConfig {
+                id: OSCILLATOR_ID,
+                ..OscillatorConfig::default()
+            },
+        );
+        let tilt_id = engine.add_spectral_tilt();
+
+        engine
+            .add_link(
+                HARMONIC_EDITOR_ID,
+                InputId::new(Input::Spectrum, tilt_id),
+                StereoSample::ONE,
+            )
+            .expect("editor to tilt");
+        engine
+            .set_direct_link(tilt_id, InputId::new(Input::Spectrum, OSCILLATOR_ID))
+            .expect("tilt to oscillator");
+
+        match engine.get_module_mut(tilt_id) {
+            Some(ModuleHandle::SpectralTilt(module)) => module.set_tilt(StereoSample::splat(tilt)),
+            _ => panic!("spectral tilt"),
+        }
+
+        engine.handle_note_on(0, 60, 1.0);
+        process_block(&mut engine, 64);
+        rms(&process_block(&mut engine, 512).0)
+    };
+
+    let flat = render(0.0);
+    let dark = render(-24.0);
+
+    assert!(flat > 1e-6);
+    assert!(dark > 1e-6);
+    assert!(dark < flat);
+}
+
+#[test]
+fn spectral_filter_brightness_tracks_note_and_velocity() {
+    let render = |note: u8, velocity: Sample, key_tracking: Sample, velocity_tracking: Sample| {
+        let mut engine = make_engine(
+            EngineParams::default(),
+            OscillatorConfig {
+                id: OSCILLATOR_ID,
+                ..OscillatorConfig::default()
+            },
+        );
+        let filter_id = engine.add_spectral_filter();
+
+        engine
+            .add_link(
+                HARMONIC_EDITOR_ID,
+                InputId::new(Input::Spectrum, filter_id),
+                StereoSample::ONE,
+            )
+            .expect("editor to filter");
+        engine
+            .set_direct_link(filter_id, InputId::new(Input::Spectrum, OSCILLATOR_ID))
+            .expect("filter to oscillator");
+
+        match engine.get_module_mut(filter_id) {
+            Some(ModuleHandle::SpectralFilter(filter)) => {
+                filter.set_cutoff(StereoSample::splat(10.0));
+                filter.set_key_tracking(key_tracking);
+                filter.set_velocity_tracking(velocity_tracking);
+            }
+            _ => panic!("spectral filter"),
+        }
+
+        engine.handle_note_on(0, note, velocity);
+        process_block(&mut engine, 64);
+        rms(&process_block(&mut engine, 512).0)
+    };
+
+    // Notes up to middle C and full velocity keep their brightness
+    let low = render(48, 1.0, 0.0, 0.0);
+
+    assert!((render(48, 1.0, 12.0, 12.0) - low).abs() < 1e-3 * low);
+
+    let high = render(84, 1.0, 0.0, 0.0);
+
+    assert!(render(84, 1.0, 6.0, 0.0) < high);
+
+    let soft = render(60, 0.2, 0.0, 0.0);
+
+    assert!(render(60, 0.2, 0.0, 6.0) < soft);
+}
+
+#[test]
+fn export_wavetable_writes_frames() {
+    let engine = make_engine(
+        EngineParams::default(),
+        OscillatorConfig {
+            id: OSCILLATOR_ID,
+            ..OscillatorConfig::default()
+        },
+    );
+    let path = std::env::temp_dir().join("additizer-wavetable.wav");
+
+    assert!(
+        engine
+            .offline_copy()
+            .unwrap()
+            .export_wavetable(HARMONIC_EDITOR_ID, &path, 4, 0.1)
+            .is_err()
+    );
+
+    engine
+        .offline_copy()
+        .unwrap()
+        .export_wavetable(OSCILLATOR_ID, &path, 4, 0.1)
+        .expect("wavetable export");
+
+    let samples: Vec<Sample> = hound::WavReader::open(&path)
+        .unwrap()
+        .samples::<f32>()
+        .map(Result::unwrap)
+        .collect();
+
+    assert_eq!(samples.len(), 4 * 2048);
+    assert!(samples.iter().all(|sample| sample.abs() <= 1.0));
+    assert!(rms(&samples[..2048]) > 0.1);
+
+    let _ = std::fs::remove_file(path);
+}
+
+#[test]
+fn oscillator_publishes_the_played_preview() {
+    let engine = Arc::new(parking_lot::Mutex::new(make_engine(
+        EngineParams::default(),
+        OscillatorConfig {
+            id: OSCILLATOR_ID,
+            ..OscillatorConfig::default()
+        },
+    )));
+    let mut bridge = {
+        let mut engine_lock = engine.lock();
+        let Some(ModuleHandle::Oscillator(osc)) = engine_lock.get_module_mut(OSCILLATOR_ID) else {
+            panic!("oscillator should be in place");
+        };
+
+        oscillator::OscillatorUiBridge::try_new(osc).unwrap()
+    };
+
+    bridge.request_preview();
+    process_block(&mut engine.lock(), 128);
+    bridge.update();
+    assert!(bridge.preview().is_none());
+
+    engine.lock().handle_note_on(0, 108, 1.0);
+    process_block(&mut engine.lock(), 128);
+    bridge.update();
+
+    let preview = bridge.preview().expect("preview");
+
+    // C8 leaves room for a few harmonics below Nyquist
+    assert!(preview.cutoff > 1 && preview.cutoff < 8);
+    assert!(preview.magnitudes[0] > -100.0);
+    assert!(preview.wave.iter().any(|sample| sample.abs() > 0.0));
+}
+
+#[test]
+fn capture_writes_output_to_wav() {
+    let mut engine = make_engine(
+        EngineParams::default(),
+        OscillatorConfig {
+            id: OSCILLATOR_ID,
+            ..OscillatorConfig::default()
+        },
+    );
+    let mut capture = engine.capture.take().unwrap();
+    let path = std::env::temp_dir().join("additizer-capture.wav");
+
+    engine.handle_note_on(0, 60, 1.0);
+    process_block(&mut engine, 256);
+
+    capture.start(&path).expect("capture start");
+    assert!(capture.is_recording());
+    assert!(capture.start(&path).is_err());
+
+    let (left, right) = process_block(&mut engine, 512);
+
+    capture.stop().expect("capture stop");
+    assert!(!capture.is_recording());
+
+    process_block(&mut engine, 256);
+
+    let mut reader = hound::WavReader::open(&path).unwrap();
+    let samples: Vec<Sample> = reader.samples::<f32>().map(Result::unwrap).collect();
+
+    assert_eq!(reader.spec().channels, 2);
+    assert_eq!(samples.len(), 2 * 512);
+    assert_eq!(samples[0], left[0]);
+    assert_eq!(samples[1], right[0]);
+    assert!(rms(&samples) > 0.0);
+
+    let _ = std::fs::remove_file(path);
+}
+
+#[test]
+fn chorus_processes_summed_voices_as_bus() {
+    let mut dry = make_engine(
+        EngineParams::default(),
+        OscillatorConfig {
+            id: OSCILLATOR_ID,
+            ..OscillatorConfig::default()
+        },
+    );
+    let mut engine = make_engine(
+        EngineParams::default(),
+        OscillatorConfig {
+            id: OSCILLATOR_ID,
+            ..OscillatorConfig::default()
+        },
+    );
+    let chorus_id = engine.add_chorus();
+    let amp_id = engine.add_amplifier();
+    let output_input = InputId::new(Input::Audio, OUTPUT_MODULE_ID);
+
+    engine
+        .set_direct_link(OSCILLATOR_ID, InputId::new(Input::Audio, chorus_id))
+        .expect("voices into chorus");
+    engine
+        .set_direct_link(chorus_id, output_input)
+        .expect("chorus into output");
+
+    assert!(
+        engine
+            .set_direct_link(chorus_id, InputId::new(Input::Audio, amp_id))
+            .is_err()
+    );
+    assert_eq!(engine.bus_chain, vec![chorus_id]);
+
+    dry.handle_note_on(0, 60, 1.0);
+    engine.handle_note_on(0, 60, 1.0);
+
+    let mut diff: Sample = 0.0;
+
+    for _ in 0..8 {
+        let (dry_left, _) = process_block(&mut dry, 128);
+        let (left, right) = process_block(&mut engine, 128);
+
+        assert!(left.iter().chain(&right).all(|sample| sample.is_finite()));
+        diff += dry_left
+            .iter()
+            .zip(&left)
+            .map(|(dry, wet)| (dry - wet).abs())
+            .sum::<Sample>();
+    }
+
+    assert!(diff > 0.1);
+
+    let restored = SynthEngine::try_new(
+        &engine.get_config(),
+        test_deps().0,
+        test_deps().1,
+        SAMPLE_RATE,
+    )
+    .expect("restored engine");
+
+    assert_eq!(restored.bus_chain, vec![chorus_id]);
+
+    engine.remove_module(chorus_id);
+    assert!(engine.bus_chain.is_empty());
+}
+
+#[test]
+fn delay_repeats_on_tempo_synced_time() {
+    const DELAY_ID: ModuleId = 3;
+
+    let mut config = minimal_engine_config(
+        EngineParams::default(),
+        OscillatorConfig {
+            id: OSCILLATOR_ID,
+            ..OscillatorConfig::default()
+        },
+    );
+
+    config.modules.push(ModuleConfig::Delay(Box::new(DelayConfig {
+        id: DELAY_ID,
+        sync: true,
+        divisions: [NoteDivision::Quarter; NUM_CHANNELS],
+        feedback: StereoSample::ZERO,
+        mix: StereoSample::ONE,
+        ..DelayConfig::default()
+    })));
+    config.links[1].dst_id = DELAY_ID;
+    config.links.push(LinkConfig {
+        src_id: DELAY_ID,
+        dst_id: OUTPUT_MODULE_ID,
+        dst_input: Input::Audio,
+        amount: StereoSample::ONE,
+        modulator_id: None,
+        curve: LinkCurve::Linear,
+        polarity: LinkPolarity::Unchanged,
+        offset: 0.0,
+        mode: LinkMode::Add,
+    });
+
+    let (volume, external_params) = test_deps();
+    let mut engine = SynthEngine::try_new(&config, volume, external_params, SAMPLE_RATE)
+        .expect("valid engine config");
+
+    assert_eq!(engine.bus_chain, vec![DELAY_ID]);
+
+    // A quarter note at the default 120 BPM, unsynced time would be 375 ms
+    let delay_samples = (0.5 * SAMPLE_RATE) as usize;
+    let mut left = Vec::new();
+
+    engine.handle_note_on(0, 60, 1.0);
+
+    while left.len() < delay_samples + 2048 {
+        left.extend(process_block(&mut engine, 128).0);
+    }
+
+    assert!(left.iter().all(|sample| sample.is_finite()));
+    assert!(rms(&left[..delay_samples - 128]) < 1e-6);
+    assert!(rms(&left[delay_samples + 128..]) > 1e-3);
+}
+
+#[test]
+fn reverb_follows_modulation_and_rings_after_voices() {
+    let mut dry = make_engine(
+        EngineParams::default(),
+        OscillatorConfig {
+            id: OSCILLATOR_ID,
+            ..OscillatorConfig::default()
+        },
+    );
+    let mut engine = make_engine(
+        EngineParams::default(),
+        OscillatorConfig {
+            id: OSCILLATOR_ID,
+            ..OscillatorConfig::default()
+        },
+    );
+    let reverb_id = engine.add_reverb();
+    let lfo_id = engine.add_lfo();
+
+    engine
+        .set_direct_link(OSCILLATOR_ID, InputId::new(Input::Audio, reverb_id))
+        .expect("voices into reverb");
+    engine
+        .set_direct_link(reverb_id, InputId::new(Input::Audio, OUTPUT_MODULE_ID))
+        .expect("reverb into output");
+    engine
+        .add_link(lfo_id, InputId::new(Input::DryWet, reverb_id), StereoSample::ONE)
+        .expect("lfo modulates dry/wet");
+
+    // Only the modulation brings the reverb in
+    match engine.get_module_mut(reverb_id) {
+        Some(ModuleHandle::Reverb(reverb)) => reverb.set_dry_wet(StereoSample::ZERO),
+        _ => panic!("reverb module"),
+    }
+
+    dry.handle_note_on(0, 60, 1.0);
+    engine.handle_note_on(0, 60, 1.0);
+
+    let mut diff: Sample = 0.0;
+
+    for _ in 0..32 {
+        let (dry_left, _) = process_block(&mut dry, 128);
+        let (left, _) = process_block(&mut engine, 128);
+
+        diff += dry_left
+            .iter()
+            .zip(&left)
+            .map(|(dry, wet)| (dry - wet).abs())
+            .sum::<Sample>();
+    }
+
+    assert!(diff > 0.1);
+
+    dry.handle_choke(0, 60);
+    engine.handle_choke(0, 60);
+
+    for _ in 0..32 {
+        process_block(&mut dry, 128);
+        process_block(&mut engine, 128);
+    }
+
+    let (dry_left, _) = process_block(&mut dry, 128);
+    let (left, right) = process_block(&mut engine, 128);
+
+    assert_eq!(rms(&dry_left), 0.0);
+    assert!(left.iter().chain(&right).all(|sample| sample.is_finite()));
+    assert!(rms(&left) > 1e-4);
+}
+
+#[test]
+fn wave_shaper_curves_apply_output_level_with_oversampling() {
+    const SHAPER_ID: ModuleId = 3;
+
+    let render = |shaper_type: ShaperType, oversampling: bool, level: Sample| {
+        let mut config = minimal_engine_config(
+            EngineParams::default(),
+            OscillatorConfig {
+                id: OSCILLATOR_ID,
+                ..OscillatorConfig::default()
+            },
+        );
+
+        config
+            .modules
+            .push(ModuleConfig::WaveShaper(Box::new(WaveShaperConfig {
+                id: SHAPER_ID,
+                shaper_type,
+                distortion: 12.0.into(),
+                level: level.into(),
+                oversampling,
+                ..WaveShaperConfig::default()
+            })));
+        config.links[1].dst_id = SHAPER_ID;
+        config.links.push(link(SHAPER_ID, OUTPUT_MODULE_ID, Input::Audio));
+
+        let (volume, external_params) = test_deps();
+        let mut engine = SynthEngine::try_new(&config, volume, external_params, SAMPLE_RATE)
+            .expect("valid engine config");
+        let mut left = Vec::new();
+
+        engine.handle_note_on(0, 60, 1.0);
+
+        for _ in 0..8 {
+            left.extend(process_block(&mut engine, 128).0);
+        }
+
+        assert!(left.iter().all(|sample| sample.is_finite()));
+        left
+    };
+
+    for shaper_type in [
+        ShaperType::HardClip,
+        ShaperType::Sigmoid,
+        ShaperType::Fold,
+        ShaperType::Asymmetric,
+    ] {
+        for oversampling in [false, true] {
+            let loud = rms(&render(shaper_type, oversampling, 0.0));
+            let quiet = rms(&render(shaper_type, oversampling, -12.0));
+
+            assert!(loud > 1e-3);
+            assert!((quiet / loud - util::db_to_gain_fast(-12.0)).abs() < 0.01);
+        }
+    }
+
+    let plain = render(ShaperType::Fold, false, 0.0);
+    let oversampled = render(ShaperType::Fold, true, 0.0);
+
+    assert!(
+        plain
+            .iter()
+            .zip(&oversampled)
+            .any(|(plain, oversampled)| (plain - oversampled).abs() > 1e-3)
+    );
+}
+
+#[test]
+fn ring_mod_multiplies_inputs_by_mix() {
+    const RING_MOD_ID: ModuleId = 3;
+
+    let render = |mix: Sample| {
+        let mut config = minimal_engine_config(
+            EngineParams::default(),
+            OscillatorConfig {
+                id: OSCILLATOR_ID,
+                ..OscillatorConfig::default()
+            },
+        );
+
+        config.modules.push(ModuleConfig::RingMod(Box::new(RingModConfig {
+            id: RING_MOD_ID,
+            mix: mix.into(),
+            ..RingModConfig::default()
+        })));
+        config.links[1] = link(OSCILLATOR_ID, RING_MOD_ID, ring_mod::CARRIER_INPUT);
+        config.links.extend([
+            link(OSCILLATOR_ID, RING_MOD_ID, ring_mod::MODULATOR_INPUT),
+            link(RING_MOD_ID, OUTPUT_MODULE_ID, Input::Audio),
+        ]);
+
+        let (volume, external_params) = test_deps();
+        let mut engine = SynthEngine::try_new(&config, volume, external_params, SAMPLE_RATE)
+            .expect("valid engine config");
+        let mut left = Vec::new();
+
+        engine.handle_note_on(0, 60, 1.0);
+
+        for _ in 0..8 {
+            left.extend(process_block(&mut engine, 128).0);
+        }
+
+        left
+    };
+
+    let mut dry = make_engine(
+        EngineParams::default(),
+        OscillatorConfig {
+            id: OSCILLATOR_ID,
+            ..OscillatorConfig::default()
+        },
+    );
+    let mut dry_left = Vec::new();
+
+    dry.handle_note_on(0, 60, 1.0);
+
+    for _ in 0..8 {
+        dry_left.extend(process_block(&mut dry, 128).0);
+    }
+
+    assert_eq!(render(0.0), dry_left);
+
+    // The oscillator is multiplied by itself, so the product never goes negative
+    let squared = render(1.0);
+
+    assert!(rms(&squared) > 1e-3);
+    assert!(squared.iter().all(|sample| *sample >= 0.0));
+}
+
+#[test]
+fn filter_tracks_note_and_attenuates_above_cutoff() {
+    const FILTER_ID: ModuleId = 3;
+
+    let render = |filter_type: FilterType, cutoff: Sample| {
+        let mut config = minimal_engine_config(
+            EngineParams::default(),
+            OscillatorConfig {
+                id: OSCILLATOR_ID,
+                ..OscillatorConfig::default()
+            },
+        );
+
+        config.modules.push(ModuleConfig::Filter(Box::new(FilterConfig {
+            id: FILTER_ID,
+            filter_type,
+            cutoff: cutoff.into(),
+            ..FilterConfig::default()
+        })));
+        config.links[1].dst_id = FILTER_ID;
+        config.links.push(link(FILTER_ID, OUTPUT_MODULE_ID, Input::Audio));
+
+        let (volume, external_params) = test_deps();
+        let mut engine = SynthEngine::try_new(&config, volume, external_params, SAMPLE_RATE)
+            .expect("valid engine config");
+        let mut left = Vec::new();
+
+        engine.handle_note_on(0, 60, 1.0);
+
+        for _ in 0..8 {
+            left.extend(process_block(&mut engine, 128).0);
+        }
+
+        assert!(left.iter().all(|sample| sample.is_finite()));
+        rms(&left[512..])
+    };
+
+    // Cutoff is relative to the played note with the default full keytracking
+    let open = render(FilterType::LowPass, 8.0);
+    let closed = render(FilterType::LowPass, -2.0);
+
+    assert!(open > 1e-3);
+    assert!(closed < 0.3 * open);
+    assert!(render(FilterType::HighPass, -2.0) > 0.5 * open);
+    assert!(render(FilterType::BandPass, 0.0) > 1e-3);
+    assert!(render(FilterType::Notch, 0.0) > 1e-3);
+}
+
+#[test]
+fn automation_drives_module_input_and_follows_removal() {
+    let mut config = minimal_engine_config(
+        EngineParams::default(),
+        OscillatorConfig {
+            id: OSCILLATOR_ID,
+            ..OscillatorConfig::default()
+        },
+    );
+
+    // Host params sit at 0.0, so the input lands on the range minimum
+    config.automation.push(AutomationConfig {
+        slot: 3,
+        module_id: OSCILLATOR_ID,
+        input: Input::Gain,
+        min: 0.25,
+        max: 0.75,
+    });
+
+    let (volume, external_params) = test_deps();
+    let mut engine = SynthEngine::try_new(&config, volume, external_params, SAMPLE_RATE)
+        .expect("engine with automation");
+
+    process_block(&mut engine, 64);
+
+    let saved = engine.get_config();
+    let gain = saved.modules.iter().find_map(|m| match m {
+        ModuleConfig::Oscillator(osc) => Some(osc.gain),
+        _ => None,
+    });
+
+    assert_eq!(gain, Some(StereoSample::splat(0.25)));
+    assert!(saved.automation == config.automation);
+
+    // Signal inputs have no base value to drive
+    let invalid = AutomationConfig {
+        input: Input::Spectrum,
+        ..config.automation[0].clone()
+    };
+
+    assert!(engine.set_automation(&[invalid]).is_err());
+    assert_eq!(engine.get_automation().len(), 1);
+
+    engine.remove_module(OSCILLATOR_ID);
+    assert!(engine.get_automation().is_empty());
+}
+
+#[test]
+fn bridge_config_includes_queued_edits() {
+    use crate::{engine_factory::EngineFactory, preset::Preset};
+
+    let (volume, external_params) = test_deps();
+    let factory = EngineFactory::new(volume, external_params);
+
+    assert!(factory.load_preset(&Preset {
+        engine: full_patch_engine_config(EngineParams::default()),
+        ..Preset::default()
+    }));
+
+    let mut bridge = factory.take_ui_bridge().expect("ui bridge");
+
+    assert!(factory.take_ui_bridge().is_none());
+
+    let saved = |config: &EngineConfig| serde_json::to_string(config).unwrap();
+
+    assert_eq!(
+        saved(&bridge.engine_config()),
+        saved(&factory.get_preset().engine)
+    );
+
+    bridge.set_block_size(32);
+    assert_eq!(bridge.engine_config().engine.block_size, 32);
+    assert_eq!(factory.get_preset().engine.block_size, MAX_BLOCK_SIZE);
+}
+
+#[test]
+fn bridge_edits_move_only_with_the_patch() {
+    use crate::{
+        engine_factory::EngineFactory,
+        preset::Preset,
+        synth_engine::ui_bridge::{GridVec, ModuleBridge, UiBridge},
+    };
+
+    let (volume, external_params) = test_deps();
+    let factory = EngineFactory::new(volume, external_params);
+
+    assert!(factory.load_preset(&Preset {
+        engine: full_patch_engine_config(EngineParams::default()),
+        ..Preset::default()
+    }));
+
+    let mut bridge = factory.take_ui_bridge().expect("ui bridge");
+    let mut edits = bridge.edits();
+    let mut edited = |bridge: &UiBridge| {
+        let moved = bridge.edits() != edits;
+
+        edits = bridge.edits();
+        moved
+    };
+
+    bridge.update();
+    assert!(!edited(&bridge));
+
+    bridge.set_block_size(32);
+    assert!(edited(&bridge));
+
+    bridge.with_module_bridge(OSCILLATOR_ID, |_, module_bridge| {
+        if let ModuleBridge::Oscillator(osc) = module_bridge {
+            osc.set_param(Input::Gain, StereoSample::splat(0.5));
+        }
+    });
+    assert!(edited(&bridge));
+
+    let position = bridge.get_module_position(OSCILLATOR_ID);
+
+    bridge.set_module_position(OSCILLATOR_ID, position);
+    assert!(!edited(&bridge));
+
+    bridge.set_module_position(OSCILLATOR_ID, GridVec { x: 3, y: 4 });
+    assert!(edited(&bridge));
+
+    // The edits of a removed module stay counted, the total never comes back to an older value
+    let before_removal = bridge.edits();
+
+    bridge.remove_module(OSCILLATOR_ID);
+    assert_ne!(bridge.edits(), before_removal);
+}
+
+#[test]
+fn bridge_copies_keep_module_bypass() {
+    use crate::{engine_factory::EngineFactory, preset::Preset};
+
+    let (volume, external_params) = test_deps();
+    let factory = EngineFactory::new(volume, external_params);
+
+    assert!(factory.load_preset(&Preset {
+        engine: full_patch_engine_config(EngineParams::default()),
+        ..Preset::default()
+    }));
+
+    let mut bridge = factory.take_ui_bridge().expect("ui bridge");
+    let bypassed = |config: &ModuleConfig| {
+        matches!(config, ModuleConfig::WaveShaper(shaper) if shaper.bypass)
+    };
+
+    bridge.set_module_bypass(WAVE_SHAPER_ID, true);
+
+    let copy_id = bridge
+        .duplicate_module(WAVE_SHAPER_ID, false)
+        .expect("duplicate");
+
+    assert!(bridge.is_bypassed(copy_id));
+    assert_eq!(
+        bridge
+            .engine_config()
+            .modules
+            .iter()
+            .filter(|config| bypassed(config))
+            .count(),
+        2
+    );
+
+    let group = bridge
+        .create_group("Shaper", &[WAVE_SHAPER_ID])
+        .expect("group");
+
+    assert!(group.engine.modules.iter().all(bypassed));
+}
+
+// The host saves the persisted fields for CLAP and VST3 alike, restoring them rebuilds the patch
+#[test]
+fn host_state_round_trips_the_patch() {
+    use crate::{Additizer, engine_factory::EngineFactory, params::AdditizerParams, preset::Preset};
+
+    let plugin_factory = |params: &AdditizerParams| {
+        let factory = Arc::new(EngineFactory::new(
+            params.volume.clone(),
+            Additizer::external_params(params),
+        ));
+
+        params.config.set_factory(factory.clone());
+        factory
+    };
+    let engine_json = |factory: &EngineFactory| {
+        serde_json::to_string(&factory.get_preset().engine).expect("serializable config")
+    };
+
+    let saved = AdditizerParams::default();
+    let factory = plugin_factory(&saved);
+
+    assert!(factory.load_preset(&Preset {
+        engine: full_patch_engine_config(EngineParams::default()),
+        ..Preset::default()
+    }));
+
+    let state = saved.serialize_fields();
+    let restored = AdditizerParams::default();
+
+    restored.deserialize_fields(&state);
+    assert_eq!(engine_json(&plugin_factory(&restored)), engine_json(&factory));
+}
+
+#[test]
+fn compare_slots_keep_separate_patches() {
+    use crate::engine_factory::{CompareSlot, EngineFactory};
+
+    let (volume, external_params) = test_deps();
+    let factory = EngineFactory::new(volume, external_params);
+    let modules_count = |factory: &EngineFactory| factory.get_preset().engine.modules.len();
+
+    let initial = modules_count(&factory);
+
+    assert_eq!(factory.compare_slot(), CompareSlot::A);
+    assert!(factory.switch_compare_slot(&factory.get_preset()));
+    assert_eq!(factory.compare_slot(), CompareSlot::B);
+    assert_eq!(modules_count(&factory), initial);
+
+    factory.get_engine().lock().add_oscillator();
+    assert_eq!(modules_count(&factory), initial + 1);
+
+    assert!(factory.switch_compare_slot(&factory.get_preset()));
+    assert_eq!(factory.compare_slot(), CompareSlot::A);
+    assert_eq!(modules_count(&factory), initial);
+
+    assert!(factory.switch_compare_slot(&factory.get_preset()));
+    assert_eq!(modules_count(&factory), initial + 1);
+
+    assert!(factory.copy_a_to_b(&factory.get_preset()));
+    assert_eq!(factory.compare_slot(), CompareSlot::B);
+    assert_eq!(modules_count(&factory), initial);
+}
+
+#[test]
+fn remove_missing_module_is_noop() {
+    let mut engine = make_engine(
+        EngineParams::default(),
+        OscillatorConfig {
+            id: OSCILLATOR_ID,
+            ..OscillatorConfig::default()
+        },
+    );
+
+    let links_before = engine.get_config().links.len();
+    engine.remove_module(9999);
+    assert_eq!(engine.get_config().links.len(), links_before);
+}
+
+#[test]
+fn link_rejects_invalid_module_id() {
+    let mut engine = make_engine(
+        EngineParams::default(),
+        OscillatorConfig {
+            id: OSCILLATOR_ID,
+            ..OscillatorConfig::default()
+        },
+    );
+
+    let dst = InputId::new(Input::Spectrum, OSCILLATOR_ID);
+    let err = engine
+        .add_link(9999, dst, StereoSample::ONE)
+        .expect_err("unknown source module");
+
+    assert!(err.contains("Invalid") || err.contains("mismatch"));
+}
+
+#[test]
+fn handle_note_expression_and_choke_process() {
+    let mut engine = make_full_patch_engine(EngineParams {
+        num_voices: 2,
+        ..EngineParams::default()
+    });
+
+    engine.handle_note_on(0, 60, 0.5);
+    engine.handle_note_expression(0, 60, Expression::Velocity, 1.0);
+    process_block(&mut engine, 64);
+
+    engine.handle_choke(0, 60);
+    let (left, _) = process_block(&mut engine, 64);
+    assert!(left.iter().all(|s| s.is_finite()));
+}
+
+// Oscillator -> Amplifier -> Output, the gain of the amplifier follows the per-note `expression`
+fn expression_gain_engine(expression: Expression) -> SynthEngine {
+    let mut engine = make_engine(
+        EngineParams::default(),
+        OscillatorConfig {
+            id: OSCILLATOR_ID,
+            ..OscillatorConfig::default()
+        },
+    );
+
+    let amp_id = engine.add_amplifier();
+    let expressions_id = engine.add_expressions();
+    let osc_to_out = InputId::new(Input::Audio, OUTPUT_MODULE_ID);
+
+    engine.remove_link(&OSCILLATOR_ID, &osc_to_out);
+    engine
+        .set_direct_link(OSCILLATOR_ID, InputId::new(Input::Audio, amp_id))
+        .expect("osc -> amp");
+    engine
+        .set_direct_link(amp_id, osc_to_out)
+        .expect("amp -> output");
+    engine
+        .add_link(
+            expressions_id,
+            InputId::new(Input::Gain, amp_id),
+            StereoSample::ONE,
+        )
+        .expect("expressions -> amp gain");
+
+    match engine.get_module_mut(expressions_id) {
+        Some(ModuleHandle::Expressions(expressions)) => expressions.set_expression(expression),
+        _ => panic!("expressions"),
+    }
+
+    engine
+}
+
+#[test]
+fn per_note_vibrato_expression_drives_its_voice() {
+    let mut engine = expression_gain_engine(Expression::Vibrato);
+
+    engine.handle_note_on(0, 60, 1.0);
+    assert!(rms(&process_block(&mut engine, 64).0) < 1e-6);
+
+    // Other notes and other expressions leave the voice alone
+    engine.handle_note_expression(0, 61, Expression::Vibrato, 1.0);
+    engine.handle_note_expression(0, 60, Expression::Swell, 1.0);
+    assert!(rms(&process_block(&mut engine, 64).0) < 1e-6);
+
+    engine.handle_note_expression(0, 60, Expression::Vibrato, 1.0);
+    assert!(rms(&process_block(&mut engine, 64).0) > 1e-4);
+}
+
+// The host note expressions arrive the same way for CLAP and VST3
+#[test]
+fn host_note_expressions_drive_their_voice() {
+    let poly_event = |expression: Expression, note: u8| {
+        let (timing, voice_id, channel) = (0, None, 0);
+
+        match expression {
+            Expression::Pitch => NoteEvent::PolyTuning {
+                timing,
+                voice_id,
+                channel,
+                note,
+                tuning: 12.0,
+            },
+            Expression::Vibrato => NoteEvent::PolyVibrato {
+                timing,
+                voice_id,
+                channel,
+                note,
+                vibrato: 1.0,
+            },
+            Expression::Swell => NoteEvent::PolyExpression {
+                timing,
+                voice_id,
+                channel,
+                note,
+                expression: 1.0,
+            },
+            Expression::Timbre => NoteEvent::PolyBrightness {
+                timing,
+                voice_id,
+                channel,
+                note,
+                brightness: 1.0,
+            },
+            Expression::Pressure => NoteEvent::PolyPressure {
+                timing,
+                voice_id,
+                channel,
+                note,
+                pressure: 1.0,
+            },
+            _ => unreachable!("not a host note expression"),
+        }
+    };
+
+    for expression in [
+        Expression::Pitch,
+        Expression::Vibrato,
+        Expression::Swell,
+        Expression::Timbre,
+        Expression::Pressure,
+    ] {
+        let mut engine = expression_gain_engine(expression);
+
+        engine.handle_note_on(0, 60, 1.0);
+        crate::Additizer::process_event(&mut engine, poly_event(expression, 61));
+        assert!(rms(&process_block(&mut engine, 64).0) < 1e-6);
+
+        crate::Additizer::process_event(&mut engine, poly_event(expression, 60));
+        assert!(
+            rms(&process_block(&mut engine, 64).0) > 1e-4,
+            "{expression:?} didn't reach the voice"
+        );
+    }
+}
+
+#[test]
+fn oversampling_and_mono_spectrum_process() {
+    let mut engine = make_engine(
+        EngineParams {
+            stereo_spectrum: false,
+            ..EngineParams::default()
+        },
+        OscillatorConfig {
+            id: OSCILLATOR_ID,
+            ..OscillatorConfig::default()
+        },
+    );
+
+    for oversampling in [Oversampling::X2, Oversampling::X4] {
+        engine.set_oversampling(oversampling);
+        engine.handle_note_on(0, 60, 1.0);
+
+        // 4x splits the host block to fit the module buffers
+        let (left, right) = process_block(&mut engine, MAX_BLOCK_SIZE);
+        assert!(rms(&left) > 1e-6);
+        assert!(left.iter().chain(right.iter()).all(|s| s.is_finite()));
+    }
+}
+
+#[test]
+fn latency_follows_oversampling() {
+    let mut engine = make_engine(
+        EngineParams::default(),
+        OscillatorConfig {
+            id: OSCILLATOR_ID,
+            ..OscillatorConfig::default()
+        },
+    );
+
+    assert_eq!(engine.latency_samples(), 0);
+
+    engine.set_oversampling(Oversampling::X2);
+    assert_eq!(engine.latency_samples(), 2);
+
+    engine.set_oversampling(Oversampling::X4);
+    assert_eq!(engine.latency_samples(), 4);
+}
+
+#[test]
+fn wave_quality_switch_keeps_level() {
+    let render = |wave_quality: WaveQuality| {
+        let mut engine = make_engine(
+            EngineParams::default(),
+            OscillatorConfig {
+                id: OSCILLATOR_ID,
+                ..OscillatorConfig::default()
+            },
+        );
+
+        engine.set_wave_quality(wave_quality);
+        engine.handle_note_on(0, 60, 1.0);
+        process_block(&mut engine, 64);
+
+        assert_eq!(engine.get_config().engine.wave_quality, wave_quality);
+        rms(&process_block(&mut engine, 64).0)
+    };
+
+    let standard = render(WaveQuality::Standard);
+
+    assert!(standard > 1e-6);
+
+    for wave_quality in [WaveQuality::Low, WaveQuality::High] {
+        assert!((render(wave_quality) - standard).abs() < 0.05 * standard);
+    }
+}
+
+#[test]
+fn table_update_modes_keep_level() {
+    let render = |table_update: TableUpdate| {
+        let mut engine = make_full_patch_engine(EngineParams::default());
+
+        engine.set_table_update(table_update);
+        engine.handle_note_on(0, 60, 1.0);
+
+        for _ in 0..8 {
+            process_block(&mut engine, 64);
+        }
+
+        assert_eq!(engine.get_config().engine.table_update, table_update);
+        rms(&process_block(&mut engine, 256).0)
+    };
+
+    let standard = render(TableUpdate::Standard);
+
+    assert!(standard > 1e-6);
+
+    for table_update in [TableUpdate::Economy, TableUpdate::Precise] {
+        assert!((render(table_update) - standard).abs() < 0.1 * standard);
+    }
+}
+
+#[test]
+fn spectral_size_caps_the_played_harmonics() {
+    // Level of the sample to sample difference, grows with the high harmonics
+    let render = |spectral_size: SpectralSize| {
+        let mut engine = make_engine(
+            EngineParams::default(),
+            OscillatorConfig {
+                id: OSCILLATOR_ID,
+                ..OscillatorConfig::default()
+            },
+        );
+
+        engine.set_spectral_size(spectral_size);
+        engine.handle_note_on(0, 30, 1.0);
+        process_block(&mut engine, 64);
+
+        assert_eq!(engine.get_config().engine.spectral_size, spectral_size);
+
+        // About two cycles of a note with more than 256 harmonics below Nyquist
+        let left: Vec<_> = (0..32)
+            .flat_map(|_| process_block(&mut engine, 64).0)
+            .collect();
+        let diff: Vec<_> = left.windows(2).map(|pair| pair[1] - pair[0]).collect();
+
+        (rms(&left), rms(&diff))
+    };
+
+    let (level_256, diff_256) = render(SpectralSize::S256);
+    let (level_1024, diff_1024) = render(SpectralSize::S1024);
+
+    assert!(level_256 > 1e-6);
+    assert!((level_256 - level_1024).abs() < 0.1 * level_1024);
+    assert!(diff_256 < 0.85 * diff_1024);
+}
+
+#[test]
+fn oversampling_reads_legacy_bool() {
+    let params: EngineParams = serde_json::from_str(
+        r#"{"num_voices":1,"legato":false,"block_size":128,"oversampling":true,
+            "stereo_spectrum":true,"voice_kill_time":0.03,"output_gain":{"channels":[1.0,1.0]}}"#,
+    )
+    .unwrap();
+
+    assert_eq!(params.oversampling, Oversampling::X2);
+
+    let json = serde_json::to_string(&EngineParams {
+        oversampling: Oversampling::X4,
+        ..EngineParams::default()
+    })
+    .unwrap();
+    let params: EngineParams = serde_json::from_str(&json).unwrap();
+
+    assert_eq!(params.oversampling, Oversampling::X4);
+}
+
+#[test]
+fn execution_order_accounts_for_link_modulation() {
+    let links = vec![
+        ModuleLink::link(LFO_ID, InputId::new(Input::Gain, AMPLIFIER_ID)),
+        ModuleLink {
+            src: ENVELOPE_AMP_ID,
+            dst: InputId::new(Input::Gain, AMPLIFIER_ID),
+            amount: StereoSample::ONE,
+            curve: LinkCurve::Linear,
+            polarity: LinkPolarity::Unchanged,
+            offset: 0.0,
+            mode: LinkMode::Add,
+            modulation: Some(LFO_ID),
+        },
+        ModuleLink::link(
+            AMPLIFIER_ID,
+            InputId::new(Input::Audio, OUTPUT_MODULE_ID),
+        ),
+    ];
+
+    let order = SynthEngine::calc_execution_order(&links).expect("valid order");
+    let lfo_pos = order.iter().position(|&id| id == LFO_ID).unwrap();
+    let amp_pos = order.iter().position(|&id| id == AMPLIFIER_ID).unwrap();
+    assert!(lfo_pos < amp_pos);
+}
+
+#[test]
+fn phase_mod_input_scales_with_fm_index() {
+    let render = |fm_index: Sample| {
+        let mut engine = make_engine(
+            EngineParams::default(),
+            OscillatorConfig {
+                id: OSCILLATOR_ID,
+                fm_index: fm_index.into(),
+                ..OscillatorConfig::default()
+            },
+        );
+        let modulator = engine.add_oscillator();
+
+        engine
+            .set_direct_link(HARMONIC_EDITOR_ID, InputId::new(Input::Spectrum, modulator))
+            .expect("harmonic editor -> modulator");
+        engine
+            .set_direct_link(modulator, InputId::new(Input::PhaseMod, OSCILLATOR_ID))
+            .expect("modulator -> carrier phase");
+
+        let mut left = Vec::new();
+
+        engine.handle_note_on(0, 60, 1.0);
+
+        for _ in 0..8 {
+            left.extend(process_block(&mut engine, 128).0);
+        }
+
+        left
+    };
+
+    let mut dry = make_engine(
+        EngineParams::default(),
+        OscillatorConfig {
+            id: OSCILLATOR_ID,
+            ..OscillatorConfig::default()
+        },
+    );
+    let mut dry_left = Vec::new();
+
+    dry.handle_note_on(0, 60, 1.0);
+
+    for _ in 0..8 {
+        dry_left.extend(process_block(&mut dry, 128).0);
+    }
+
+    assert_eq!(render(0.0), dry_left);
+
+    let modulated = render(2.0);
+    let diff: Vec<Sample> = modulated
+        .iter()
+        .zip(&dry_left)
+        .map(|(a, b)| a - b)
+        .collect();
+
+    assert!(rms(&modulated) > 1e-3);
+    assert!(rms(&diff) > 1e-3);
+}
+
+#[test]
+fn oscillator_feedback_changes_waveform_and_stays_bounded() {
+    let render = |feedback: Sample| {
+        let mut engine = make_engine(
+            EngineParams::default(),
+            OscillatorConfig {
+                id: OSCILLATOR_ID,
+                feedback: feedback.into(),
+                ..OscillatorConfig::default()
+            },
+        );
+        let mut left = Vec::new();
+
+        engine.handle_note_on(0, 60, 1.0);
+
+        for _ in 0..16 {
+            left.extend(process_block(&mut engine, 128).0);
+        }
+
+        left
+    };
+
+    let dry = render(0.0);
+    let fed_back = render(1.0);
+    let diff: Vec<Sample> = fed_back.iter().zip(&dry).map(|(a, b)| a - b).collect();
+
+    assert!(rms(&diff) > 1e-3);
+    assert!(fed_back.iter().all(|sample| sample.is_finite()));
+    assert!(rms(&fed_back) < 2.0 * rms(&dry));
+}
+
+#[test]
+fn oscillator_drift_wanders_the_pitch_and_is_persisted() {
+    let render = |drift: Sample| {
+        let mut engine = make_engine(
+            EngineParams::default(),
+            OscillatorConfig {
+                id: OSCILLATOR_ID,
+                drift: drift.into(),
+                drift_rate: 5.0.into(),
+                ..OscillatorConfig::default()
+            },
+        );
+        let mut left = Vec::new();
+
+        engine.handle_note_on(0, 60, 1.0);
+
+        for _ in 0..16 {
+            left.extend(process_block(&mut engine, 128).0);
+        }
+
+        (engine.get_config(), left)
+    };
+
+    let (_, steady) = render(0.0);
+    let (config, drifting) = render(st_to_octave(0.5));
+    let diff: Vec<Sample> = drifting.iter().zip(&steady).map(|(a, b)| a - b).collect();
+
+    assert!(rms(&diff) > 1e-3);
+    assert!(drifting.iter().all(|sample| sample.is_finite()));
+
+    let osc = config
+        .modules
+        .iter()
+        .find_map(|m| match m {
+            ModuleConfig::Oscillator(c) => Some(c.as_ref()),
+            _ => None,
+        })
+        .expect("oscillator config");
+
+    assert_eq!(osc.drift, st_to_octave(0.5).into());
+    assert_eq!(osc.drift_rate, 5.0.into());
+}
+
+#[test]
+fn panner_pans_with_equal_power_law() {
+    const PANNER_ID: ModuleId = 3;
+
+    let render = |pan: Sample| {
+        let mut config = minimal_engine_config(
+            EngineParams::default(),
+            OscillatorConfig {
+                id: OSCILLATOR_ID,
+                ..OscillatorConfig::default()
+            },
+        );
+
+        config
+            .modules
+            .push(ModuleConfig::Panner(Box::new(PannerConfig {
+                id: PANNER_ID,
+                pan: pan.into(),
+                ..PannerConfig::default()
+            })));
+        config.links[1] = link(OSCILLATOR_ID, PANNER_ID, Input::Audio);
+        config
+            .links
+            .push(link(PANNER_ID, OUTPUT_MODULE_ID, Input::Audio));
+
+        let (volume, external_params) = test_deps();
+        let mut engine = SynthEngine::try_new(&config, volume, external_params, SAMPLE_RATE)
+            .expect("valid engine config");
+        let (mut left, mut right) = (Vec::new(), Vec::new());
+
+        engine.handle_note_on(0, 60, 1.0);
+
+        for _ in 0..8 {
+            let (l, r) = process_block(&mut engine, 128);
+
+            left.extend(l);
+            right.extend(r);
+        }
+
+        (left, right)
+    };
+
+    let (center_left, center_right) = render(0.0);
+    let (hard_left, hard_right) = render(-1.0);
+
+    assert!(rms(&center_left) > 1e-3);
+    assert!((rms(&center_left) - rms(&center_right)).abs() < 1e-5);
+    assert!(rms(&hard_right) < 1e-6);
+    assert!((rms(&hard_left) - rms(&center_left) * f32::consts::SQRT_2).abs() < 1e-3);
+}
+
+#[test]
+fn amplifier_saturates_and_pans() {
+    const AMP_ID: ModuleId = 3;
+
+    let render = |amp: AmplifierConfig| {
+        let mut config = minimal_engine_config(
+            EngineParams::default(),
+            OscillatorConfig {
+                id: OSCILLATOR_ID,
+                ..OscillatorConfig::default()
+            },
+        );
+
+        config
+            .modules
+            .push(ModuleConfig::Amplifier(Box::new(AmplifierConfig {
+                id: AMP_ID,
+                gain: 1.0.into(),
+                ..amp
+            })));
+        config.links[1] = link(OSCILLATOR_ID, AMP_ID, Input::Audio);
+        config
+            .links
+            .push(link(AMP_ID, OUTPUT_MODULE_ID, Input::Audio));
+
+        let (volume, external_params) = test_deps();
+        let mut engine = SynthEngine::try_new(&config, volume, external_params, SAMPLE_RATE)
+            .expect("valid engine config");
+        let (mut left, mut right) = (Vec::new(), Vec::new());
+
+        engine.handle_note_on(0, 60, 1.0);
+
+        for _ in 0..8 {
+            let (l, r) = process_block(&mut engine, 128);
+
+            left.extend(l);
+            right.extend(r);
+        }
+
+        (left, right)
+    };
+
+    let peak = |samples: &[Sample]| samples.iter().fold(0.0, |p: Sample, s| p.max(s.abs()));
+    let (clean, _) = render(AmplifierConfig::default());
+    let (driven, _) = render(AmplifierConfig {
+        saturation: true,
+        drive: 24.0.into(),
+        ..AmplifierConfig::default()
+    });
+    let (hard_left, hard_right) = render(AmplifierConfig {
+        pan: (-1.0).into(),
+        ..AmplifierConfig::default()
+    });
+
+    // Heavy drive squares the wave off, the peak gets closer to the rms
+    assert!(rms(&clean) > 1e-3);
+    assert!(peak(&driven) <= 1.0);
+    assert!(peak(&driven) / rms(&driven) < 0.9 * peak(&clean) / rms(&clean));
+    assert!(rms(&hard_right) < 1e-6);
+    assert!((rms(&hard_left) - rms(&clean) * f32::consts::SQRT_2).abs() < 1e-3);
+}
+
+#[test]
+fn math_module_combines_control_inputs() {
+    const AMP_ID: ModuleId = 3;
+    const MATH_ID: ModuleId = 4;
+
+    let render = |operation: MathOperation| {
+        let mut config = minimal_engine_config(
+            EngineParams::default(),
+            OscillatorConfig {
+                id: OSCILLATOR_ID,
+                ..OscillatorConfig::default()
+            },
+        );
+
+        config.modules.extend([
+            ModuleConfig::Amplifier(Box::new(AmplifierConfig {
+                id: AMP_ID,
+                ..AmplifierConfig::default()
+            })),
+            ModuleConfig::Math(Box::new(MathConfig {
+                id: MATH_ID,
+                operation,
+                value_a: 0.5.into(),
+                value_b: 0.5.into(),
+                ..MathConfig::default()
+            })),
+        ]);
+        config.links[1] = link(OSCILLATOR_ID, AMP_ID, Input::Audio);
+        config.links.extend([
+            link(MATH_ID, AMP_ID, Input::Gain),
+            link(AMP_ID, OUTPUT_MODULE_ID, Input::Audio),
+        ]);
+
+        let (volume, external_params) = test_deps();
+        let mut engine = SynthEngine::try_new(&config, volume, external_params, SAMPLE_RATE)
+            .expect("valid engine config");
+        let mut left = Vec::new();
+
+        engine.handle_note_on(0, 60, 1.0);
+
+        for _ in 0..8 {
+            left.extend(process_block(&mut engine, 128).0);
+        }
+
+        rms(&left)
+    };
+
+    let sum = render(MathOperation::Add);
+    let product = render(MathOperation::Multiply);
+
+    assert!(sum > 1e-3);
+    assert!((product * 4.0 - sum).abs() < sum * 1e-3);
+    assert!((render(MathOperation::Min) * 2.0 - sum).abs() < sum * 1e-3);
+}
+
+#[test]
+fn math_module_slews_and_quantizes() {
+    const AMP_ID: ModuleId = 3;
+    const MATH_ID: ModuleId = 4;
+
+    let build = |math: MathConfig| {
+        let mut config = minimal_engine_config(
+            EngineParams::default(),
+            OscillatorConfig {
+                id: OSCILLATOR_ID,
+                ..OscillatorConfig::default()
+            },
+        );
+
+        config.modules.extend([
+            ModuleConfig::Amplifier(Box::new(AmplifierConfig {
+                id: AMP_ID,
+                ..AmplifierConfig::default()
+            })),
+            ModuleConfig::Math(Box::new(MathConfig {
+                id: MATH_ID,
+                ..math
+            })),
+        ]);
+        config.links[1] = link(OSCILLATOR_ID, AMP_ID, Input::Audio);
+        config.links.extend([
+            link(MATH_ID, AMP_ID, Input::Gain),
+            link(AMP_ID, OUTPUT_MODULE_ID, Input::Audio),
+        ]);
+
+        let (volume, external_params) = test_deps();
+        let mut engine = SynthEngine::try_new(&config, volume, external_params, SAMPLE_RATE)
+            .expect("valid engine config");
+
+        engine.handle_note_on(0, 60, 1.0);
+        engine
+    };
+    let level = |math: MathConfig| {
+        let mut engine = build(math);
+
+        rms(&process_block(&mut engine, 1024).0)
+    };
+
+    // 0.3 snaps to 0.25 on a grid of quarters and to 4 semitones in octaves
+    let quarter = level(MathConfig {
+        value_a: 0.25.into(),
+        ..MathConfig::default()
+    });
+    let semitones = level(MathConfig {
+        value_a: (4.0 / 12.0).into(),
+        ..MathConfig::default()
+    });
+    let quantize = |quantize_grid: QuantizeGrid| {
+        level(MathConfig {
+            operation: MathOperation::Quantize,
+            value_a: 0.3.into(),
+            quantize_grid,
+            quantize_steps: 4,
+            ..MathConfig::default()
+        })
+    };
+
+    assert!(quarter > 1e-3);
+    assert!((quantize(QuantizeGrid::Steps) - quarter).abs() < quarter * 1e-3);
+    assert!((quantize(QuantizeGrid::Semitones) - semitones).abs() < semitones * 1e-3);
+
+    // Rises by 1.0 in 100 ms and falls at once
+    let mut engine = build(MathConfig {
+        operation: MathOperation::Slew,
+        slew_rise: 0.1,
+        slew_fall: 0.0,
+        ..MathConfig::default()
+    });
+    let set_value_a = |engine: &mut SynthEngine, value: Sample| {
+        let Some(ModuleHandle::Math(math)) = engine.get_module_mut(MATH_ID) else {
+            panic!("math module expected");
+        };
+
+        math.set_value_a(value.into());
+    };
+
+    process_block(&mut engine, 256);
+    set_value_a(&mut engine, 1.0);
+
+    let rising = rms(&process_block(&mut engine, 480).0);
+
+    process_block(&mut engine, 9600);
+
+    let settled = rms(&process_block(&mut engine, 960).0);
+
+    set_value_a(&mut engine, 0.0);
+    process_block(&mut engine, 960);
+
+    assert!(settled > 1e-3);
+    assert!(rising < settled * 0.1);
+    assert!(rms(&process_block(&mut engine, 960).0) < settled * 1e-3);
+}
+
+#[test]
+fn harmonic_comb_drives_oscillator_spectrum() {
+    const COMB_ID: ModuleId = 3;
+
+    let render = |partials: Sample| {
+        let mut config = minimal_engine_config(
+            EngineParams::default(),
+            OscillatorConfig {
+                id: OSCILLATOR_ID,
+                ..OscillatorConfig::default()
+            },
+        );
+
+        config
+            .modules
+            .push(ModuleConfig::HarmonicComb(Box::new(HarmonicCombConfig {
+                id: COMB_ID,
+                partials: partials.into(),
+                ..HarmonicCombConfig::default()
+            })));
+        config.links[0] = link(COMB_ID, OSCILLATOR_ID, Input::Spectrum);
+
+        let (volume, external_params) = test_deps();
+        let mut engine = SynthEngine::try_new(&config, volume, external_params, SAMPLE_RATE)
+            .expect("valid engine config");
+        let mut left = Vec::new();
+
+        engine.handle_note_on(0, 60, 1.0);
+
+        for _ in 0..8 {
+            left.extend(process_block(&mut engine, 128).0);
+        }
+
+        rms(&left)
+    };
+
+    let fundamental = render(1.0);
+    let rich = render(64.0);
+
+    assert!(fundamental > 1e-3);
+    assert!(rich > fundamental);
+}
+
+#[test]
+fn chord_mode_strums_and_releases_chord_notes() {
+    let mut engine = make_engine(
+        EngineParams {
+            num_voices: 4,
+            chord: ChordConfig {
+                enabled: true,
+                strum: from_ms(5.0),
+                ..ChordConfig::default()
+            },
+            ..EngineParams::default()
+        },
+        OscillatorConfig {
+            id: OSCILLATOR_ID,
+            ..OscillatorConfig::default()
+        },
+    );
+    let playing = |engine: &SynthEngine| engine.voices_handler.get_ui_state().playing;
+
+    engine.handle_note_on(0, 60, 1.0);
+    assert_eq!(playing(&engine), 1);
+
+    // 128 samples are 2.67 ms, the second note is due at the start of the second block
+    process_block(&mut engine, 128);
+    assert_eq!(playing(&engine), 1);
+
+    process_block(&mut engine, 128);
+    assert_eq!(playing(&engine), 2);
+
+    engine.handle_note_off(0, 60, 0.0);
+    assert_eq!(playing(&engine), 0);
+
+    for _ in 0..8 {
+        process_block(&mut engine, 128);
+    }
+
+    assert_eq!(playing(&engine), 0);
+}
+
+#[test]
+fn midi_mapping_drives_input_and_learns_next_cc() {
+    let (volume, external_params) = test_deps();
+    let mut config = minimal_engine_config(
+        EngineParams::default(),
+        OscillatorConfig {
+            id: OSCILLATOR_ID,
+            ..OscillatorConfig::default()
+        },
+    );
+
+    config.midi_mappings.push(MidiMappingConfig {
+        cc: 20,
+        module_id: OSCILLATOR_ID,
+        input: Input::Detune,
+        min: 0.0,
+        max: 0.1,
+        curve: 0.0,
+    });
+
+    let mut engine = SynthEngine::try_new(&config, volume, external_params, SAMPLE_RATE)
+        .expect("valid engine config");
+    let detune = |engine: &SynthEngine| {
+        engine
+            .get_config()
+            .modules
+            .iter()
+            .find_map(|m| match m {
+                ModuleConfig::Oscillator(c) => Some(c.detune.left()),
+                _ => None,
+            })
+            .expect("oscillator config")
+    };
+
+    engine.handle_midi_cc(21, 1.0);
+    assert!(detune(&engine) < 0.1);
+
+    engine.handle_midi_cc(20, 0.5);
+    assert!((detune(&engine) - 0.05).abs() < 1e-6);
+
+    engine.set_midi_learn(true);
+    engine.handle_midi_cc(64, 1.0);
+    engine.handle_midi_cc(65, 1.0);
+
+    let ui_end = engine.ui_end.as_mut().unwrap();
+
+    assert!(matches!(
+        ui_end.pop_update(),
+        Some(ui_bridge::UiUpdate::MidiLearned(64))
+    ));
+    assert!(ui_end.pop_update().is_none());
+}
+
+#[test]
+fn bypassed_modules_pass_audio_and_mute_sources() {
+    const PANNER_ID: ModuleId = 3;
+
+    let mut config = minimal_engine_config(
+        EngineParams::default(),
+        OscillatorConfig {
+            id: OSCILLATOR_ID,
+            ..OscillatorConfig::default()
+        },
+    );
+
+    config
+        .modules
+        .push(ModuleConfig::Panner(Box::new(PannerConfig {
+            id: PANNER_ID,
+            bypass: true,
+            pan: (-1.0).into(),
+            ..PannerConfig::default()
+        })));
+    config.links[1] = link(OSCILLATOR_ID, PANNER_ID, Input::Audio);
+    config
+        .links
+        .push(link(PANNER_ID, OUTPUT_MODULE_ID, Input::Audio));
+
+    let (volume, external_params) = test_deps();
+    let mut engine = SynthEngine::try_new(&config, volume, external_params, SAMPLE_RATE)
+        .expect("valid engine config");
+
+    engine.handle_note_on(0, 60, 1.0);
+
+    let (left, right) = process_block(&mut engine, 128);
+
+    // Hard left pan would silence the right channel
+    assert!(rms(&left) > 1e-3);
+    assert!(
+        left.iter()
+            .zip(&right)
+            .all(|(left, right)| (left - right).abs() < 1e-6)
+    );
+    assert!(
+        engine
+            .get_config()
+            .modules
+            .iter()
+            .any(|module| matches!(module, ModuleConfig::Panner(panner) if panner.bypass))
+    );
+
+    engine.set_module_bypass(OSCILLATOR_ID, true);
+
+    let (left, right) = process_block(&mut engine, 128);
+
+    assert!(rms(&left) < 1e-6);
+    assert!(rms(&right) < 1e-6);
+}
+
+#[test]
+fn solo_sends_module_output_straight_to_output() {
+    const PANNER_ID: ModuleId = 3;
+
+    let mut config = minimal_engine_config(
+        EngineParams::default(),
+        OscillatorConfig {
+            id: OSCILLATOR_ID,
+            ..OscillatorConfig::default()
+        },
+    );
+
+    config
+        .modules
+        .push(ModuleConfig::Panner(Box::new(PannerConfig {
+            id: PANNER_ID,
+            pan: (-1.0).into(),
+            ..PannerConfig::default()
+        })));
+    config.links[1] = link(OSCILLATOR_ID, PANNER_ID, Input::Audio);
+    config
+        .links
+        .push(link(PANNER_ID, OUTPUT_MODULE_ID, Input::Audio));
+
+    let (volume, external_params) = test_deps();
+    let mut engine = SynthEngine::try_new(&config, volume, external_params, SAMPLE_RATE)
+        .expect("valid engine config");
+
+    engine.handle_note_on(0, 60, 1.0);
+
+    let (_, right) = process_block(&mut engine, 128);
+
+    assert!(rms(&right) < 1e-6);
+
+    engine.set_solo(Some(OSCILLATOR_ID));
+
+    let (left, right) = process_block(&mut engine, 128);
+
+    assert_eq!(engine.get_solo(), Some(OSCILLATOR_ID));
+    assert!(rms(&right) > 1e-3);
+    assert!((rms(&left) - rms(&right)).abs() < 1e-5);
+
+    engine.set_solo(None);
+
+    let (_, right) = process_block(&mut engine, 128);
+
+    assert!(rms(&right) < 1e-6);
+}
+
+#[test]
+fn duplicate_module_copies_config_and_input_links() {
+    let mut engine = make_engine(
+        EngineParams::default(),
+        OscillatorConfig {
+            id: OSCILLATOR_ID,
+            ..OscillatorConfig::default()
+        },
+    );
+
+    let bare_id = engine
+        .duplicate_module(OSCILLATOR_ID, false)
+        .expect("duplicate without links");
+    let linked_id = engine
+        .duplicate_module(OSCILLATOR_ID, true)
+        .expect("duplicate with links");
+
+    assert!(engine.duplicate_module(999, false).is_err());
+    assert!(matches!(
+        engine.get_module(linked_id),
+        Some(ModuleHandle::Oscillator(_))
+    ));
+
+    let links = engine.get_config().links;
+    let has_link = |src_id: ModuleId, dst_id: ModuleId| {
+        links
+            .iter()
+            .any(|link| link.src_id == src_id && link.dst_id == dst_id)
+    };
+
+    assert!(has_link(HARMONIC_EDITOR_ID, linked_id));
+    assert!(!has_link(HARMONIC_EDITOR_ID, bare_id));
+    assert!(!has_link(linked_id, OUTPUT_MODULE_ID));
+}
+
+#[test]
+fn sub_patch_round_trips_with_internal_links() {
+    let mut engine = make_engine(
+        EngineParams::default(),
+        OscillatorConfig {
+            id: OSCILLATOR_ID,
+            ..OscillatorConfig::default()
+        },
+    );
+
+    let amp_id = engine.add_amplifier();
+
+    engine
+        .add_link(
+            OSCILLATOR_ID,
+            InputId::new(Input::Audio, amp_id),
+            StereoSample::ONE,
+        )
+        .expect("osc -> amp");
+
+    let sub_patch = engine
+        .extract_sub_patch(&[OSCILLATOR_ID, amp_id])
+        .expect("sub-patch");
+
+    assert_eq!(sub_patch.modules.len(), 2);
+    assert_eq!(sub_patch.links.len(), 1);
+    assert!(
+        sub_patch
+            .exposed_inputs
+            .iter()
+            .any(|input| input.module_id == OSCILLATOR_ID && input.input == Input::Spectrum)
+    );
+    assert!(engine.extract_sub_patch(&[OUTPUT_MODULE_ID]).is_err());
+
+    let new_ids = engine.insert_sub_patch(&sub_patch).expect("inserted");
+    let (new_osc, new_amp) = (new_ids[&OSCILLATOR_ID], new_ids[&amp_id]);
+
+    assert!(matches!(
+        engine.get_module(new_osc),
+        Some(ModuleHandle::Oscillator(_))
+    ));
+    assert!(
+        engine
+            .get_config()
+            .links
+            .iter()
+            .any(|link| link.src_id == new_osc && link.dst_id == new_amp)
+    );
+}
+
+#[test]
+fn editor_keyboard_notes_play_through_the_ui_queue() {
+    let mut engine = make_engine(
+        EngineParams::default(),
+        OscillatorConfig {
+            id: OSCILLATOR_ID,
+            ..OscillatorConfig::default()
+        },
+    );
+    let mut ui_end = engine.ui_end.take().expect("ui end");
+    let playing = |engine: &SynthEngine| engine.voices_handler.get_ui_state().playing;
+
+    assert!(ui_end.note_on(60, 0.8));
+    assert_eq!(playing(&engine), 0);
+
+    let (left, _) = process_block(&mut engine, 64);
+
+    assert_eq!(playing(&engine), 1);
+    assert!(rms(&left) > 1e-6);
+
+    assert!(ui_end.note_off(60, 0.5));
+    process_block(&mut engine, 64);
+    assert_eq!(playing(&engine), 0);
+}
+
+#[test]
+fn editor_keyboard_note_is_released_when_the_editor_closes() {
+    let mut engine = make_engine(
+        EngineParams::default(),
+        OscillatorConfig {
+            id: OSCILLATOR_ID,
+            ..OscillatorConfig::default()
+        },
+    );
+    let mut ui_end = engine.ui_end.take().expect("ui end");
+    let playing = |engine: &SynthEngine| engine.voices_handler.get_ui_state().playing;
+
+    engine.handle_note_on(0, 64, 1.0);
+    assert!(ui_end.note_on(60, 0.8));
+    process_block(&mut engine, 64);
+    assert_eq!(playing(&engine), 2);
+
+    engine.release_keyboard_note();
+    assert_eq!(playing(&engine), 1);
+
+    // The stale release from the reopened editor leaves MIDI notes alone
+    assert!(ui_end.note_off(60, 0.5));
+    engine.handle_note_on(0, 60, 1.0);
+    process_block(&mut engine, 64);
+    assert_eq!(playing(&engine), 2);
+}
+
+// Runs `f` the way the audio thread does, counting the allocations made by it
+#[cfg(debug_assertions)]
+fn allocations_in(f: impl FnOnce()) -> u32 {
+    assert_no_alloc::reset_violation_count();
+    assert_no_alloc::assert_no_alloc(f);
+    assert_no_alloc::violation_count()
+}
+
+#[cfg(debug_assertions)]
+fn process_in_place(engine: &mut SynthEngine, left: &mut [Sample], right: &mut [Sample]) {
+    engine.process(left.len(), true, [left, right].into_iter());
+}
+
+#[cfg(debug_assertions)]
+#[test]
+fn process_does_not_allocate() {
+    let mut engine = make_full_patch_engine(EngineParams {
+        num_voices: 4,
+        ..EngineParams::default()
+    });
+    let mut left = vec![0.0; 256];
+    let mut right = vec![0.0; 256];
+
+    let allocations = allocations_in(|| {
+        for note in [60, 64, 67] {
+            engine.handle_note_on(0, note, 1.0);
+            process_in_place(&mut engine, &mut left, &mut right);
+        }
+
+        engine.handle_note_off(0, 64, 0.5);
+        process_in_place(&mut engine, &mut left, &mut right);
+    });
+
+    assert_eq!(allocations, 0);
+}
+
+#[cfg(debug_assertions)]
+#[test]
+fn process_does_not_allocate_with_modules_added_live() {
     let mut engine = make_engine(
         EngineParams {
-            stereo_spectrum: false,
+            num_voices: 4,
             ..EngineParams::default()
         },
         OscillatorConfig {
@@ -1204,33 +4410,372 @@ fn oversampling_and_mono_spectrum_process() {
             ..OscillatorConfig::default()
         },
     );
+    let mut left = vec![0.0; 256];
+    let mut right = vec![0.0; 256];
+
+    let output_input = InputId::new(Input::Audio, OUTPUT_MODULE_ID);
+    let mut check = |engine: &mut SynthEngine, added: &str| {
+        let allocations = allocations_in(|| {
+            engine.handle_note_on(0, 64, 1.0);
+            process_in_place(engine, &mut left, &mut right);
+            engine.handle_note_off(0, 64, 0.5);
+            process_in_place(engine, &mut left, &mut right);
+        });
+
+        assert_eq!(allocations, 0, "allocated after adding {added}");
+    };
 
-    engine.set_oversampling(true);
+    // Added one by one while a note plays, the way the editor adds them
     engine.handle_note_on(0, 60, 1.0);
 
-    let (left, right) = process_block(&mut engine, 64);
-    assert!(rms(&left) > 1e-6);
-    assert!(left.iter().chain(right.iter()).all(|s| s.is_finite()));
+    let amp_id = engine.add_amplifier();
+
+    engine
+        .set_direct_link(OSCILLATOR_ID, InputId::new(Input::Audio, amp_id))
+        .expect("voices into amplifier");
+    engine
+        .set_direct_link(amp_id, output_input)
+        .expect("amplifier into output");
+    check(&mut engine, "amplifier");
+
+    let lfo_id = engine.add_lfo();
+
+    engine
+        .add_link(lfo_id, InputId::new(Input::Gain, amp_id), StereoSample::ONE)
+        .expect("lfo modulates gain");
+    check(&mut engine, "lfo");
+
+    let delay_id = engine.add_delay();
+
+    engine
+        .set_direct_link(amp_id, InputId::new(Input::Audio, delay_id))
+        .expect("amplifier into delay");
+    engine
+        .set_direct_link(delay_id, output_input)
+        .expect("delay into output");
+    check(&mut engine, "delay");
+
+    let reverb_id = engine.add_reverb();
+
+    engine
+        .set_direct_link(delay_id, InputId::new(Input::Audio, reverb_id))
+        .expect("delay into reverb");
+    engine
+        .set_direct_link(reverb_id, output_input)
+        .expect("reverb into output");
+    check(&mut engine, "reverb");
 }
 
+#[cfg(debug_assertions)]
 #[test]
-fn execution_order_accounts_for_link_modulation() {
-    let links = vec![
-        ModuleLink::link(LFO_ID, InputId::new(Input::Gain, AMPLIFIER_ID)),
-        ModuleLink {
-            src: ENVELOPE_AMP_ID,
-            dst: InputId::new(Input::Gain, AMPLIFIER_ID),
-            amount: StereoSample::ONE,
-            modulation: Some(LFO_ID),
+fn holding_more_notes_than_voices_does_not_allocate() {
+    let mut engine = make_engine(
+        EngineParams {
+            num_voices: 4,
+            ..EngineParams::default()
         },
-        ModuleLink::link(
-            AMPLIFIER_ID,
-            InputId::new(Input::Audio, OUTPUT_MODULE_ID),
-        ),
-    ];
+        OscillatorConfig {
+            id: OSCILLATOR_ID,
+            ..OscillatorConfig::default()
+        },
+    );
+    let mut left = vec![0.0; 256];
+    let mut right = vec![0.0; 256];
 
-    let order = SynthEngine::calc_execution_order(&links).expect("valid order");
-    let lfo_pos = order.iter().position(|&id| id == LFO_ID).unwrap();
-    let amp_pos = order.iter().position(|&id| id == AMPLIFIER_ID).unwrap();
-    assert!(lfo_pos < amp_pos);
+    // The notes pushed out of the voices wait in the room made at init
+    let allocations = allocations_in(|| {
+        for note in 20..80 {
+            engine.handle_note_on(0, note, 1.0);
+        }
+
+        process_in_place(&mut engine, &mut left, &mut right);
+    });
+
+    assert_eq!(allocations, 0);
+}
+
+#[cfg(debug_assertions)]
+#[test]
+#[should_panic(expected = "has no output slot")]
+fn module_without_output_slot_is_caught() {
+    let mut engine = make_engine(
+        EngineParams::default(),
+        OscillatorConfig {
+            id: OSCILLATOR_ID,
+            ..OscillatorConfig::default()
+        },
+    );
+    let amp_id = engine.add_amplifier();
+
+    engine
+        .set_direct_link(OSCILLATOR_ID, InputId::new(Input::Audio, amp_id))
+        .expect("voices into amplifier");
+    engine
+        .set_direct_link(amp_id, InputId::new(Input::Audio, OUTPUT_MODULE_ID))
+        .expect("amplifier into output");
+    engine.outputs_arena.free_slot(&engine.modules[&amp_id]);
+    engine.handle_note_on(0, 60, 1.0);
+    process_block(&mut engine, 64);
+}
+
+#[cfg(debug_assertions)]
+#[test]
+fn ui_edits_apply_through_the_queue_without_allocating() {
+    let mut engine = make_full_patch_engine(EngineParams {
+        num_voices: 4,
+        ..EngineParams::default()
+    });
+    let mut ui_end = engine.ui_end.take().expect("ui end");
+    let mut left = vec![0.0; 256];
+    let mut right = vec![0.0; 256];
+    let link = engine.get_links()[0];
+    let trim = OutputTrimConfig {
+        module_id: OSC0_ID,
+        gain: -6.0,
+        invert: true,
+    };
+
+    engine.handle_note_on(0, 60, 1.0);
+    process_in_place(&mut engine, &mut left, &mut right);
+
+    assert!(ui_end.set_link_curve(link.src, link.dst, LinkCurve::Steps(4)));
+    assert!(ui_end.set_link_offset(link.src, link.dst, 0.25));
+    assert!(ui_end.set_module_bypass(WAVE_SHAPER_ID, true));
+    assert!(ui_end.set_output_trim(trim.clone()));
+    assert!(ui_end.set_solo(Some(OSC0_ID)));
+    assert!(ui_end.set_tuning(Some(TuningConfig::edo(24))));
+
+    let allocations = allocations_in(|| process_in_place(&mut engine, &mut left, &mut right));
+
+    assert_eq!(allocations, 0);
+
+    let source = engine.input_sources[&link.dst]
+        .iter()
+        .find(|source| source.module_id == link.src)
+        .expect("link in place");
+
+    assert!(matches!(source.curve, LinkCurve::Steps(4)));
+    assert_eq!(source.offset, 0.25);
+    assert!(engine.get_module(WAVE_SHAPER_ID).unwrap().bypass());
+    assert!(engine.get_output_trim(OSC0_ID) == Some(trim));
+    assert_eq!(engine.get_solo(), Some(OSC0_ID));
+    assert!(engine.get_engine_params().tuning.is_some());
+
+    // The replaced tuning is freed on the editor side
+    let mut released = false;
+
+    while let Some(update) = ui_end.pop_update() {
+        released |= matches!(update, ui_bridge::UiUpdate::Released(_));
+    }
+
+    assert!(released);
+}
+
+#[cfg(debug_assertions)]
+#[test]
+fn staged_routing_swaps_in_without_allocating() {
+    let mut engine = make_engine(
+        EngineParams::default(),
+        OscillatorConfig {
+            id: OSCILLATOR_ID,
+            ..OscillatorConfig::default()
+        },
+    );
+    let mut ui_end = engine.ui_end.take().expect("ui end");
+    let mut left = vec![0.0; 256];
+    let mut right = vec![0.0; 256];
+    let amp_id = engine.next_id;
+    let output = InputId::new(Input::Audio, OUTPUT_MODULE_ID);
+
+    engine.handle_note_on(0, 60, 1.0);
+    process_in_place(&mut engine, &mut left, &mut right);
+
+    // Oscillator -> Amplifier -> Output, prepared the way the editor does it
+    let mut amp = SynthEngine::new_module(
+        ModuleType::Amplifier,
+        amp_id,
+        engine.wave_quality(),
+        engine.spectral_size(),
+        engine.table_update(),
+        engine.background_tables(),
+        &engine.get_external_params(),
+    )
+    .expect("amplifier");
+    let mut outputs = engine.module_outputs();
+
+    amp.set_output_slot(staged_routing::free_output_slot(&outputs, DataType::Audio));
+    outputs.insert(amp_id, (DataType::Audio, amp.output_slot()));
+
+    let mut routing = engine.input_sources.clone();
+
+    routing.insert(InputId::new(Input::Audio, amp_id), routing[&output].clone());
+    routing.insert(output, vec![ModuleLink::link(amp_id, output).source()]);
+
+    let tables = RoutingTables::build(routing, &outputs).expect("valid routing");
+
+    assert!(ui_end.stage_routing(StagedRouting::new(tables, vec![amp], Vec::new())));
+
+    let allocations = allocations_in(|| process_in_place(&mut engine, &mut left, &mut right));
+
+    assert_eq!(allocations, 0);
+    assert!(engine.get_module(amp_id).is_some());
+    assert!(engine.execution_order.contains(&amp_id));
+    assert_eq!(engine.input_sources[&output][0].module_id, amp_id);
+
+    // The replaced tables are freed on the editor side
+    let mut released = false;
+
+    while let Some(update) = ui_end.pop_update() {
+        released |= matches!(update, ui_bridge::UiUpdate::Released(_));
+    }
+
+    assert!(released);
+}
+
+#[test]
+fn output_trim_scales_and_inverts_audio() {
+    let render = |trim: Option<OutputTrimConfig>| {
+        let config = minimal_engine_config(
+            EngineParams::default(),
+            OscillatorConfig {
+                id: OSCILLATOR_ID,
+                ..OscillatorConfig::default()
+            },
+        );
+        let (volume, external_params) = test_deps();
+        let mut engine = SynthEngine::try_new(&config, volume, external_params, SAMPLE_RATE)
+            .expect("valid engine config");
+        let mut left = Vec::new();
+
+        if let Some(trim) = trim {
+            engine.set_output_trim(trim);
+        }
+
+        engine.handle_note_on(0, 60, 1.0);
+
+        for _ in 0..4 {
+            left.extend(process_block(&mut engine, 128).0);
+        }
+
+        (left, engine.get_config())
+    };
+
+    let trim = OutputTrimConfig {
+        module_id: OSCILLATOR_ID,
+        gain: -6.0,
+        invert: true,
+    };
+    let (clean, _) = render(None);
+    let (trimmed, config) = render(Some(trim.clone()));
+    let gain = -util::db_to_gain_fast(-6.0);
+
+    assert!(rms(&clean) > 1e-3);
+    assert!(
+        clean
+            .iter()
+            .zip(&trimmed)
+            .all(|(c, t)| (c * gain - t).abs() < 1e-4)
+    );
+    assert!(config.output_trims == vec![trim]);
+
+    let (_, config) = render(Some(OutputTrimConfig {
+        module_id: OUTPUT_MODULE_ID,
+        gain: 6.0,
+        invert: false,
+    }));
+
+    assert!(config.output_trims.is_empty());
+}
+
+#[test]
+fn multiply_link_mode_scales_the_value() {
+    const ENVELOPE_ID: ModuleId = 3;
+    const AMP_ID: ModuleId = 4;
+
+    let render = |amount: Sample, mode: LinkMode| {
+        let mut config = minimal_engine_config(
+            EngineParams::default(),
+            OscillatorConfig {
+                id: OSCILLATOR_ID,
+                ..OscillatorConfig::default()
+            },
+        );
+
+        config.modules.extend([
+            ModuleConfig::Envelope(Box::new(EnvelopeConfig {
+                id: ENVELOPE_ID,
+                attack: from_ms(1.0).into(),
+                sustain: 1.0.into(),
+                ..EnvelopeConfig::default()
+            })),
+            ModuleConfig::Amplifier(Box::new(AmplifierConfig {
+                id: AMP_ID,
+                gain: 0.5.into(),
+                ..AmplifierConfig::default()
+            })),
+        ]);
+        config.links[1] = link(OSCILLATOR_ID, AMP_ID, Input::Audio);
+        config.links.extend([
+            link(AMP_ID, OUTPUT_MODULE_ID, Input::Audio),
+            LinkConfig {
+                amount: StereoSample::splat(amount),
+                mode,
+                ..link(ENVELOPE_ID, AMP_ID, Input::Gain)
+            },
+        ]);
+
+        let (volume, external_params) = test_deps();
+        let mut engine = SynthEngine::try_new(&config, volume, external_params, SAMPLE_RATE)
+            .expect("valid engine config");
+        let mut left = Vec::new();
+
+        engine.handle_note_on(0, 60, 1.0);
+
+        for _ in 0..4 {
+            process_block(&mut engine, 128);
+        }
+
+        for _ in 0..4 {
+            left.extend(process_block(&mut engine, 128).0);
+        }
+
+        (rms(&left), engine.get_config())
+    };
+
+    let (plain, _) = render(0.0, LinkMode::Add);
+    let (added, _) = render(0.5, LinkMode::Add);
+    let (scaled, config) = render(-0.5, LinkMode::Multiply);
+
+    assert!(plain > 1e-3);
+    assert!((added / plain - 2.0).abs() < 1e-3);
+    assert!((scaled / plain - 0.5).abs() < 1e-3);
+    assert!(
+        config
+            .links
+            .iter()
+            .any(|link| link.dst_input == Input::Gain && link.mode == LinkMode::Multiply)
+    );
+}
+
+#[test]
+fn float_param_names_round_trip_through_config() {
+    let mut engine = make_full_patch_engine(EngineParams::default());
+
+    engine.set_float_param_name(12, "Brightness");
+
+    let config = engine.get_config();
+
+    assert_eq!(
+        config.float_param_names.len(),
+        external_param::NUM_FLOAT_PARAMS
+    );
+    assert_eq!(config.float_param_names[12], "Brightness");
+    assert!(config.float_param_names[0].is_empty());
+
+    let (volume, external_params) = test_deps();
+    let rebuilt = SynthEngine::try_new(&config, volume, external_params.clone(), SAMPLE_RATE)
+        .expect("valid engine config");
+
+    assert_eq!(*external_params.float_param_names[12].lock(), "Brightness");
+    assert_eq!(rebuilt.float_param_names(), config.float_param_names);
 }
@@ -0,0 +1,127 @@
+use std::{
+    cell::UnsafeCell,
+    sync::{
+        Arc,
+        atomic::{AtomicU8, Ordering},
+    },
+};
+
+// Set on the back slot index when it holds a value the reader hasn't picked up yet
+const FRESH: u8 = 0b100;
+
+struct Slots<T> {
+    slots: [UnsafeCell<T>; 3],
+    back: AtomicU8,
+}
+
+// Each side only touches the slot it owns, the back slot changes hands through `back`
+unsafe impl<T: Send> Sync for Slots<T> {}
+
+// Publishes the latest value without waiting for the reader
+pub struct TripleWriter<T> {
+    slots: Arc<Slots<T>>,
+    write: u8,
+}
+
+impl<T> TripleWriter<T> {
+    // Holds an outdated value, it has to be fully overwritten before publishing
+    pub fn input(&mut self) -> &mut T {
+        unsafe { &mut *self.slots.slots[self.write as usize].get() }
+    }
+
+    pub fn publish(&mut self) {
+        let back = self.slots.back.swap(self.write | FRESH, Ordering::AcqRel);
+
+        self.write = back & !FRESH;
+    }
+}
+
+// Reads the latest published value without waiting for the writer
+pub struct TripleReader<T> {
+    slots: Arc<Slots<T>>,
+    read: u8,
+}
+
+impl<T> TripleReader<T> {
+    /// Picks up the value published since the last call, false when there's nothing new.
+    pub fn update(&mut self) -> bool {
+        if self.slots.back.load(Ordering::Relaxed) & FRESH == 0 {
+            return false;
+        }
+
+        let back = self.slots.back.swap(self.read, Ordering::AcqRel);
+
+        self.read = back & !FRESH;
+        true
+    }
+
+    pub fn output(&self) -> &T {
+        unsafe { &*self.slots.slots[self.read as usize].get() }
+    }
+
+    // Nothing more is coming once the reader holds the only reference
+    pub fn writer_dropped(&self) -> bool {
+        Arc::strong_count(&self.slots) == 1
+    }
+}
+
+/// Allocates all three slots up front, publishing never allocates.
+pub fn create_triple_buffer<T: Clone>(initial: T) -> (TripleWriter<T>, TripleReader<T>) {
+    let slots = Arc::new(Slots {
+        slots: [
+            UnsafeCell::new(initial.clone()),
+            UnsafeCell::new(initial.clone()),
+            UnsafeCell::new(initial),
+        ],
+        back: AtomicU8::new(1),
+    });
+
+    (
+        TripleWriter {
+            slots: slots.clone(),
+            write: 0,
+        },
+        TripleReader { slots, read: 2 },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reader_gets_the_latest_value() {
+        let (mut writer, mut reader) = create_triple_buffer(0);
+
+        assert!(!reader.update());
+        assert_eq!(*reader.output(), 0);
+
+        *writer.input() = 1;
+        writer.publish();
+        *writer.input() = 2;
+        writer.publish();
+
+        assert!(reader.update());
+        assert_eq!(*reader.output(), 2);
+        assert!(!reader.update());
+        assert_eq!(*reader.output(), 2);
+    }
+
+    #[test]
+    fn writer_never_touches_the_read_slot() {
+        let (mut writer, mut reader) = create_triple_buffer(0);
+
+        *writer.input() = 1;
+        writer.publish();
+        assert!(reader.update());
+
+        for value in 2..10 {
+            *writer.input() = value;
+            writer.publish();
+            assert_eq!(*reader.output(), 1);
+        }
+
+        assert!(reader.update());
+        assert_eq!(*reader.output(), 9);
+    }
+}
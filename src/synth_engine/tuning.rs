@@ -0,0 +1,323 @@
+use std::{fs, path::Path};
+
+use serde::{Deserialize, Serialize};
+
+use crate::synth_engine::Sample;
+
+const NUM_MIDI_NOTES: usize = 128;
+const A4_FREQUENCY: f64 = 440.0;
+
+pub static EDO_PRESETS: &[u16] = &[19, 22, 24, 31, 53];
+
+// Pitch of every MIDI note in octaves relative to A4, unmapped keys don't play
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TuningConfig {
+    pub name: String,
+    pub pitches: Vec<Option<Sample>>,
+}
+
+impl TuningConfig {
+    pub fn edo(divisions: u16) -> Self {
+        let divisions = divisions.max(1);
+        let scale = Scale {
+            description: String::new(),
+            cents: (0..=divisions)
+                .map(|step| 1200.0 * step as f64 / divisions as f64)
+                .collect(),
+        };
+
+        Self::from_scale(
+            format!("{divisions}-EDO"),
+            &scale,
+            &KeyboardMapping::linear(&scale),
+        )
+        .expect("linear mapping always maps the reference note")
+    }
+
+    /// Builds the table from a Scala scale and an optional keyboard mapping file.
+    pub fn from_scala(scl_path: &Path, kbm_path: Option<&Path>) -> Result<Self, String> {
+        let read = |path: &Path| {
+            fs::read_to_string(path).map_err(|err| format!("Failed to read {path:?}: {err}"))
+        };
+        let name = scl_path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let scl = read(scl_path)?;
+        let kbm = kbm_path.map(read).transpose()?;
+
+        Self::from_scala_str(name, &scl, kbm.as_deref())
+    }
+
+    pub fn from_scala_str(name: String, scl: &str, kbm: Option<&str>) -> Result<Self, String> {
+        let scale = Scale::parse(scl)?;
+        let mapping = match kbm {
+            Some(kbm) => KeyboardMapping::parse(kbm)?,
+            None => KeyboardMapping::linear(&scale),
+        };
+        let name = if scale.description.is_empty() {
+            name
+        } else {
+            scale.description.clone()
+        };
+
+        Self::from_scale(name, &scale, &mapping)
+    }
+
+    fn from_scale(name: String, scale: &Scale, mapping: &KeyboardMapping) -> Result<Self, String> {
+        let reference = mapping
+            .note_cents(scale, mapping.reference_note)
+            .ok_or("The reference note is unmapped")?;
+        let reference_pitch = (mapping.reference_frequency / A4_FREQUENCY).log2();
+
+        let pitches = (0..NUM_MIDI_NOTES as i32)
+            .map(|note| {
+                mapping
+                    .note_cents(scale, note)
+                    .map(|cents| ((cents - reference) / 1200.0 + reference_pitch) as Sample)
+            })
+            .collect();
+
+        Ok(Self { name, pitches })
+    }
+
+    pub fn pitch(&self, note: u8) -> Option<Sample> {
+        self.pitches.get(note as usize).copied().flatten()
+    }
+}
+
+// Lines of a Scala file without the `!` comments
+fn data_lines(text: &str) -> impl Iterator<Item = &str> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.starts_with('!'))
+}
+
+fn first_token(line: &str) -> &str {
+    line.split_whitespace().next().unwrap_or_default()
+}
+
+fn parse_number<T: std::str::FromStr>(line: Option<&str>, what: &str) -> Result<T, String> {
+    line.map(first_token)
+        .and_then(|token| token.parse().ok())
+        .ok_or_else(|| format!("Invalid {what}"))
+}
+
+struct Scale {
+    description: String,
+    // Starts with the implicit 0 cents degree, the last entry is the period
+    cents: Vec<f64>,
+}
+
+impl Scale {
+    fn parse(text: &str) -> Result<Self, String> {
+        let mut lines = data_lines(text);
+        let description = lines.next().ok_or("Empty scale file")?.to_string();
+        let size: usize = parse_number(lines.next(), "scale size")?;
+
+        if size == 0 {
+            return Err("The scale has no notes".to_string());
+        }
+
+        let mut cents = Vec::with_capacity(size + 1);
+
+        cents.push(0.0);
+
+        for line in lines.filter(|line| !line.is_empty()).take(size) {
+            cents.push(Self::parse_pitch(first_token(line))?);
+        }
+
+        if cents.len() != size + 1 {
+            return Err(format!("Expected {size} notes, found {}", cents.len() - 1));
+        }
+
+        Ok(Self { description, cents })
+    }
+
+    // Values with a period are cents, everything else is a ratio or a whole number
+    fn parse_pitch(token: &str) -> Result<f64, String> {
+        let invalid = || format!("Invalid pitch {token:?}");
+
+        if token.contains('.') {
+            return token.parse().map_err(|_| invalid());
+        }
+
+        let (numerator, denominator) = token.split_once('/').unwrap_or((token, "1"));
+        let numerator: f64 = numerator.parse().map_err(|_| invalid())?;
+        let denominator: f64 = denominator.parse().map_err(|_| invalid())?;
+
+        if numerator <= 0.0 || denominator <= 0.0 {
+            return Err(invalid());
+        }
+
+        Ok(1200.0 * (numerator / denominator).log2())
+    }
+
+    fn size(&self) -> usize {
+        self.cents.len() - 1
+    }
+
+    fn degree_cents(&self, degree: i32) -> f64 {
+        let size = self.size() as i32;
+        let period = self.cents[self.size()];
+
+        degree.div_euclid(size) as f64 * period + self.cents[degree.rem_euclid(size) as usize]
+    }
+}
+
+struct KeyboardMapping {
+    first_note: i32,
+    last_note: i32,
+    middle_note: i32,
+    reference_note: i32,
+    reference_frequency: f64,
+    octave_degree: i32,
+    // Empty for a linear mapping, None marks keys that don't play
+    keys: Vec<Option<i32>>,
+}
+
+impl KeyboardMapping {
+    // What Scala uses without a .kbm file: every key is the next degree, 60 is the first one, A4 is 440 Hz
+    fn linear(scale: &Scale) -> Self {
+        Self {
+            first_note: 0,
+            last_note: NUM_MIDI_NOTES as i32 - 1,
+            middle_note: 60,
+            reference_note: 69,
+            reference_frequency: A4_FREQUENCY,
+            octave_degree: scale.size() as i32,
+            keys: Vec::new(),
+        }
+    }
+
+    fn parse(text: &str) -> Result<Self, String> {
+        let mut lines = data_lines(text).filter(|line| !line.is_empty());
+        let size: usize = parse_number(lines.next(), "map size")?;
+        let first_note = parse_number(lines.next(), "first note")?;
+        let last_note = parse_number(lines.next(), "last note")?;
+        let middle_note = parse_number(lines.next(), "middle note")?;
+        let reference_note = parse_number(lines.next(), "reference note")?;
+        let reference_frequency: f64 = parse_number(lines.next(), "reference frequency")?;
+        let octave_degree = parse_number(lines.next(), "octave degree")?;
+
+        if reference_frequency <= 0.0 {
+            return Err("Invalid reference frequency".to_string());
+        }
+
+        let keys = lines
+            .take(size)
+            .map(|line| match first_token(line) {
+                "x" | "X" => Ok(None),
+                token => token
+                    .parse()
+                    .map(Some)
+                    .map_err(|_| format!("Invalid mapping entry {token:?}")),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if keys.len() != size {
+            return Err(format!(
+                "Expected {size} mapping entries, found {}",
+                keys.len()
+            ));
+        }
+
+        Ok(Self {
+            first_note,
+            last_note,
+            middle_note,
+            reference_note,
+            reference_frequency,
+            octave_degree,
+            keys,
+        })
+    }
+
+    fn note_cents(&self, scale: &Scale, note: i32) -> Option<f64> {
+        if note < self.first_note || note > self.last_note {
+            return None;
+        }
+
+        let offset = note - self.middle_note;
+
+        if self.keys.is_empty() {
+            return Some(scale.degree_cents(offset));
+        }
+
+        let size = self.keys.len() as i32;
+        let degree = self.keys[offset.rem_euclid(size) as usize]?;
+
+        Some(
+            offset.div_euclid(size) as f64 * scale.degree_cents(self.octave_degree)
+                + scale.degree_cents(degree),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::note_to_pitch;
+
+    fn close(a: Sample, b: Sample) -> bool {
+        (a - b).abs() < 1e-5
+    }
+
+    #[test]
+    fn twelve_edo_matches_equal_temperament() {
+        let tuning = TuningConfig::edo(12);
+
+        for note in 0..NUM_MIDI_NOTES as u8 {
+            assert!(close(
+                tuning.pitch(note).unwrap(),
+                note_to_pitch(note as Sample)
+            ));
+        }
+    }
+
+    #[test]
+    fn scala_scale_parses_ratios_and_cents() {
+        let scl = "! just.scl\n!\nJust major\n 7\n!\n9/8\n5/4\n4/3\n3/2\n5/3\n15/8\n2/1\n";
+        let tuning = TuningConfig::from_scala_str("just".to_string(), scl, None).unwrap();
+
+        assert_eq!(tuning.name, "Just major");
+        assert!(close(tuning.pitch(69).unwrap(), 0.0));
+        // Note 60 is the first degree, 69 is nine steps up: an octave and a third (5/4)
+        assert!(close(
+            tuning.pitch(60).unwrap(),
+            -1.0 - (5.0f32 / 4.0).log2()
+        ));
+        assert!(close(
+            tuning.pitch(67).unwrap() - tuning.pitch(60).unwrap(),
+            1.0
+        ));
+
+        let cents = "Cents\n2\n600.0\n1200.0\n";
+        let tuning = TuningConfig::from_scala_str(String::new(), cents, None).unwrap();
+        assert!(close(tuning.pitch(70).unwrap(), 0.5));
+    }
+
+    #[test]
+    fn keyboard_mapping_skips_unmapped_keys() {
+        let scl = "Whole tone\n6\n200.0\n400.0\n600.0\n800.0\n1000.0\n2/1\n";
+        let kbm = "! whole.kbm\n12\n0\n127\n60\n60\n432.0\n6\n0\nx\n1\nx\n2\n3\nx\n4\nx\n5\nx\nx\n";
+        let tuning = TuningConfig::from_scala_str(String::new(), scl, Some(kbm)).unwrap();
+
+        assert!(tuning.pitch(61).is_none());
+        assert!(close(tuning.pitch(60).unwrap(), (432.0f32 / 440.0).log2()));
+        assert!(close(
+            tuning.pitch(72).unwrap() - tuning.pitch(60).unwrap(),
+            1.0
+        ));
+        assert!(close(
+            tuning.pitch(62).unwrap() - tuning.pitch(60).unwrap(),
+            200.0 / 1200.0
+        ));
+    }
+
+    #[test]
+    fn invalid_scale_is_rejected() {
+        assert!(TuningConfig::from_scala_str(String::new(), "Bad\n2\n100.0\n", None).is_err());
+        assert!(TuningConfig::from_scala_str(String::new(), "Bad\n1\n-3/2\n", None).is_err());
+    }
+}
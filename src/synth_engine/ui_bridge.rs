@@ -1,26 +1,71 @@
-use std::ops::DerefMut;
+use std::{
+    collections::VecDeque,
+    mem,
+    path::Path,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    thread::{self, JoinHandle},
+};
 
 use enum_dispatch::enum_dispatch;
+use nih_plug::params::FloatParam;
 
 use crate::{
     engine_factory::{EngineHandle, UiConfigHandle},
+    preset::{Preset, PresetInfo, SubPatch},
     synth_engine::{
-        InputId, ModuleHandle, ModuleId, ModuleType, ModuleUiBridge, OUTPUT_MODULE_ID, Sample,
-        StereoSample,
+        EngineConfig, ExternalParamsBlock, InputId, KEYBOARD_RELEASE_VELOCITY, LinkCurve, LinkMode,
+        LinkPolarity, MAX_MODULES, MAX_OUTPUT_TRIM, ModuleHandle, ModuleId, ModuleType,
+        ModuleUiBridge, MtsEspMode, OUTPUT_MODULE_ID, OutputMode, OutputTrimConfig, Oversampling,
+        PortamentoMode, RandomParam, RoutingMap, Sample, SpectralSize, StealPriority, StereoSample,
+        SynthEngine, TuningConfig,
         amplifier::AmplifierUiBridge,
-        config::EngineParams,
+        analyzer::Analyzer,
+        automation::Automation,
+        capture::Capture,
+        chord_memory::ChordConfig,
+        chorus::ChorusUiBridge,
+        config::{AutomationConfig, EngineParams, MidiMappingConfig, ModuleConfig},
+        delay::DelayUiBridge,
         envelope::EnvelopeUiBridge,
+        envelope_follower::EnvelopeFollowerUiBridge,
         expressions::ExpressionsUiBridge,
         external_param::ExternalParamUiBridge,
+        filter::FilterUiBridge,
+        harmonic_comb::HarmonicCombUiBridge,
         harmonic_editor::HarmonicEditorUiBridge,
+        harmonic_envelopes::HarmonicEnvelopesUiBridge,
+        harmonic_shift::HarmonicShiftUiBridge,
+        harmonic_stretch::HarmonicStretchUiBridge,
         lfo::LfoUiBridge,
+        limiter::LimiterConfig,
+        math::MathUiBridge,
+        midi_control::MidiControlUiBridge,
         mixer::MixerUiBridge,
-        oscillator::OscillatorUiBridge,
-        routing::{DataType, Input, InputMeta, data_types_compatible},
+        oscillator::{OscillatorUiBridge, TableUpdate, WaveQuality},
+        panner::PannerUiBridge,
+        reverb::ReverbUiBridge,
+        ring_mod::RingModUiBridge,
+        routing::{
+            DataType, Input, InputMeta, InputSource, MAX_VOICES, ModuleLink, data_types_compatible,
+        },
+        sample_resynth::SampleResynthUiBridge,
+        scope::{Scope, ScopeFrame},
+        spectral_analyzer::SpectralAnalyzerUiBridge,
         spectral_blend::SpectralBlendUiBridge,
+        spectral_dynamics::SpectralDynamicsUiBridge,
         spectral_filter::SpectralFilterUiBridge,
         spectral_mixer::SpectralMixerUiBridge,
-        ui_bridge::{routing_state::ModuleIo, ui_config::UiModuleConfig},
+        spectral_tilt::SpectralTiltUiBridge,
+        staged_routing::{ModuleOutputs, RoutingTables, StagedRouting, free_output_slot},
+        synth_module::SynthModule,
+        ui_bridge::{
+            routing_state::{Module, ModuleIo},
+            ui_config::{ModuleGroup, UiConfig, UiModuleConfig},
+        },
+        voices_handler::{VoiceState, VoicesHandler},
         wave_shaper::WaveShaperUiBridge,
     },
 };
@@ -29,12 +74,15 @@ mod link;
 pub mod routing_state;
 pub mod ui_config;
 
-pub use ui_config::GridVec;
+pub use ui_config::{GridVec, ModuleColor};
 
 pub use link::{AudioEnd, UiEnd, UiEvent, UiUpdate, create_link_pair};
 pub use routing_state::{AvailableInputSource, ConnectedInputSource, RoutingState};
 use rustc_hash::FxHashMap;
 
+// Module loads jitter from block to block, the UI shows a running average
+const MODULE_LOAD_SMOOTHING: Sample = 0.1;
+
 #[enum_dispatch(ModuleUiBridge)]
 pub enum ModuleBridge {
     Oscillator(Box<OscillatorUiBridge>),
@@ -49,6 +97,30 @@ pub enum ModuleBridge {
     HarmonicEditor(Box<HarmonicEditorUiBridge>),
     Expressions(Box<ExpressionsUiBridge>),
     ExternalParam(Box<ExternalParamUiBridge>),
+    MidiControl(Box<MidiControlUiBridge>),
+    Chorus(Box<ChorusUiBridge>),
+    Delay(Box<DelayUiBridge>),
+    Reverb(Box<ReverbUiBridge>),
+    RingMod(Box<RingModUiBridge>),
+    Panner(Box<PannerUiBridge>),
+    Filter(Box<FilterUiBridge>),
+    SpectralTilt(Box<SpectralTiltUiBridge>),
+    HarmonicStretch(Box<HarmonicStretchUiBridge>),
+    HarmonicShift(Box<HarmonicShiftUiBridge>),
+    SpectralDynamics(Box<SpectralDynamicsUiBridge>),
+    HarmonicEnvelopes(Box<HarmonicEnvelopesUiBridge>),
+    SampleResynth(Box<SampleResynthUiBridge>),
+    Math(Box<MathUiBridge>),
+    HarmonicComb(Box<HarmonicCombUiBridge>),
+    EnvelopeFollower(Box<EnvelopeFollowerUiBridge>),
+    SpectralAnalyzer(Box<SpectralAnalyzerUiBridge>),
+}
+
+#[derive(Clone, Copy, Default)]
+pub struct VoiceMeter {
+    pub state: VoiceState,
+    pub peak: Sample,
+    pub rms: Sample,
 }
 
 #[derive(Clone, Copy, Default)]
@@ -81,57 +153,144 @@ pub struct LinkableInput {
     pub modulations: Vec<LinkableModulation>,
 }
 
+// Receives the next incoming CC, an input gets a mapping scaled to min..max
+#[derive(Clone, Copy, PartialEq)]
+pub enum MidiLearnTarget {
+    Input {
+        input: InputId,
+        min: Sample,
+        max: Sample,
+    },
+    ExternalParam(ModuleId),
+}
+
+// Host parameter changes made in the editor, the editor hands them to the host after drawing
+#[derive(Clone, Copy, PartialEq)]
+pub enum HostParamEdit {
+    MasterTune(Sample),
+    Transpose(i32),
+}
+
+// The module config as the editor last set it, the editor makes configs from its bridges
+// instead of reading them from the engine
+macro_rules! bridge_config_fn {
+    ($($variant:ident),* $(,)?) => {
+        fn bridge_config(bridge: &ModuleBridge) -> ModuleConfig {
+            match bridge {
+                $(ModuleBridge::$variant(b) => ModuleConfig::$variant(Box::new(b.config().clone())),)*
+            }
+        }
+    };
+}
+
 pub struct UiBridge {
     engine: EngineHandle,
     ui_config: UiConfigHandle,
     ui_end: UiEnd,
+    external_params: Arc<ExternalParamsBlock>,
+    output_level_param: Arc<FloatParam>,
+    host_sample_rate: Sample,
+    // Shared with the factory, the host switching to offline renders turns it off
+    background_tables: Arc<AtomicBool>,
+    next_id: ModuleId,
+    capture: Option<Capture>,
+    scope: Option<Scope>,
+    analyzer: Option<Analyzer>,
     routing: RoutingState,
     engine_params: EngineParams,
+    automation: Vec<AutomationConfig>,
+    midi_mappings: Vec<MidiMappingConfig>,
+    float_param_names: Vec<String>,
+    midi_learn: Option<MidiLearnTarget>,
+    solo: Option<ModuleId>,
     voices: VoicesStatus,
+    voice_meters: [VoiceMeter; MAX_VOICES],
+    gain_reduction: Sample,
+    correlation: Sample,
+    module_loads: FxHashMap<ModuleId, Sample>,
     modulated_inputs: FxHashMap<InputId, StereoSample>,
     module_bridges: FxHashMap<ModuleId, Option<ModuleBridge>>,
+    pending_duplicate: Option<(ModuleId, bool)>,
+    wavetable_export: Option<JoinHandle<Result<(), String>>>,
+    host_edits: Vec<HostParamEdit>,
+    // Layout edits and the edits of removed modules, they count towards `edits` along with the queues
+    ui_edits: u32,
 }
 
 impl UiBridge {
-    pub fn create(engine: EngineHandle, ui_config: UiConfigHandle) -> Option<Self> {
+    // The engine factory makes the bridge before the audio thread gets the engine, so this is
+    // the only time it's locked on the editor side
+    pub fn create(
+        engine: EngineHandle,
+        ui_config: UiConfigHandle,
+        background_tables: Arc<AtomicBool>,
+    ) -> Option<Self> {
         let mut engine_lock = engine.lock();
 
         let ui_end = engine_lock.ui_end.take()?;
+        let capture = engine_lock.capture.take();
+        let scope = engine_lock.scope.take();
+        let analyzer = engine_lock.analyzer.take();
         let routing = engine_lock.get_routing_state();
         let engine_params = engine_lock.get_engine_params();
-
-        drop(engine_lock);
+        let automation = engine_lock.get_automation();
+        let midi_mappings = engine_lock.get_midi_mappings();
+        let float_param_names = engine_lock.float_param_names();
+        let solo = engine_lock.get_solo();
+        let external_params = engine_lock.get_external_params();
+        let output_level_param = engine_lock.output_level_param()?;
+        let host_sample_rate = engine_lock.host_sample_rate;
+        let next_id = engine_lock.next_id;
 
         let mut bridges: FxHashMap<ModuleId, Option<ModuleBridge>> = FxHashMap::default();
 
-        for m in routing.modules.values() {
-            Self::insert_module_bridge(m.id, &engine, &mut bridges)?;
+        for &id in routing.modules.keys().filter(|id| **id != OUTPUT_MODULE_ID) {
+            let bridge = Self::module_bridge(engine_lock.get_module_mut(id)?)?;
+
+            bridges.insert(id, Some(bridge));
         }
 
+        drop(engine_lock);
+
         Some(Self {
             engine,
             ui_config,
             ui_end,
+            external_params,
+            output_level_param,
+            host_sample_rate,
+            background_tables,
+            next_id,
+            capture,
+            scope,
+            analyzer,
             routing,
             engine_params,
+            automation,
+            midi_mappings,
+            float_param_names,
+            midi_learn: None,
+            solo,
             voices: VoicesStatus::default(),
+            voice_meters: [VoiceMeter::default(); MAX_VOICES],
+            gain_reduction: 0.0,
+            correlation: 0.0,
+            module_loads: FxHashMap::default(),
             modulated_inputs: FxHashMap::default(),
             module_bridges: bridges,
+            pending_duplicate: None,
+            wavetable_export: None,
+            host_edits: Vec::new(),
+            ui_edits: 0,
         })
     }
 
-    fn insert_module_bridge(
-        id: ModuleId,
-        engine: &EngineHandle,
-        bridges: &mut FxHashMap<ModuleId, Option<ModuleBridge>>,
-    ) -> Option<()> {
-        let mut engine_lock = engine.lock();
-        let engine_ref = engine_lock.deref_mut();
-
-        let bridge = match engine_ref.get_module_mut(id)? {
-            ModuleHandle::Oscillator(m) => ModuleBridge::Oscillator(Box::new(
-                OscillatorUiBridge::try_new(id, engine.clone(), m)?,
-            )),
+    // Takes the UI end of the module, None for the Output module
+    fn module_bridge(module: &mut ModuleHandle) -> Option<ModuleBridge> {
+        let bridge = match module {
+            ModuleHandle::Oscillator(m) => {
+                ModuleBridge::Oscillator(Box::new(OscillatorUiBridge::try_new(m)?))
+            }
             ModuleHandle::Envelope(m) => {
                 ModuleBridge::Envelope(Box::new(EnvelopeUiBridge::try_new(m)?))
             }
@@ -152,27 +311,164 @@ impl UiBridge {
             ModuleHandle::SpectralMixer(m) => {
                 ModuleBridge::SpectralMixer(Box::new(SpectralMixerUiBridge::try_new(m)?))
             }
-            ModuleHandle::HarmonicEditor(m) => ModuleBridge::HarmonicEditor(Box::new(
-                HarmonicEditorUiBridge::try_new(id, engine.clone(), m)?,
-            )),
+            ModuleHandle::HarmonicEditor(m) => {
+                ModuleBridge::HarmonicEditor(Box::new(HarmonicEditorUiBridge::try_new(m)?))
+            }
             ModuleHandle::Expressions(m) => {
                 ModuleBridge::Expressions(Box::new(ExpressionsUiBridge::try_new(m)?))
             }
             ModuleHandle::ExternalParam(m) => {
                 ModuleBridge::ExternalParam(Box::new(ExternalParamUiBridge::try_new(m)?))
             }
-            ModuleHandle::Output(_) => return Some(()),
+            ModuleHandle::MidiControl(m) => {
+                ModuleBridge::MidiControl(Box::new(MidiControlUiBridge::try_new(m)?))
+            }
+            ModuleHandle::Chorus(m) => ModuleBridge::Chorus(Box::new(ChorusUiBridge::try_new(m)?)),
+            ModuleHandle::Delay(m) => ModuleBridge::Delay(Box::new(DelayUiBridge::try_new(m)?)),
+            ModuleHandle::Reverb(m) => ModuleBridge::Reverb(Box::new(ReverbUiBridge::try_new(m)?)),
+            ModuleHandle::RingMod(m) => {
+                ModuleBridge::RingMod(Box::new(RingModUiBridge::try_new(m)?))
+            }
+            ModuleHandle::Panner(m) => ModuleBridge::Panner(Box::new(PannerUiBridge::try_new(m)?)),
+            ModuleHandle::Filter(m) => ModuleBridge::Filter(Box::new(FilterUiBridge::try_new(m)?)),
+            ModuleHandle::Math(m) => ModuleBridge::Math(Box::new(MathUiBridge::try_new(m)?)),
+            ModuleHandle::HarmonicComb(m) => {
+                ModuleBridge::HarmonicComb(Box::new(HarmonicCombUiBridge::try_new(m)?))
+            }
+            ModuleHandle::SpectralTilt(m) => {
+                ModuleBridge::SpectralTilt(Box::new(SpectralTiltUiBridge::try_new(m)?))
+            }
+            ModuleHandle::HarmonicStretch(m) => {
+                ModuleBridge::HarmonicStretch(Box::new(HarmonicStretchUiBridge::try_new(m)?))
+            }
+            ModuleHandle::HarmonicShift(m) => {
+                ModuleBridge::HarmonicShift(Box::new(HarmonicShiftUiBridge::try_new(m)?))
+            }
+            ModuleHandle::SpectralDynamics(m) => {
+                ModuleBridge::SpectralDynamics(Box::new(SpectralDynamicsUiBridge::try_new(m)?))
+            }
+            ModuleHandle::HarmonicEnvelopes(m) => {
+                ModuleBridge::HarmonicEnvelopes(Box::new(HarmonicEnvelopesUiBridge::try_new(m)?))
+            }
+            ModuleHandle::SampleResynth(m) => {
+                ModuleBridge::SampleResynth(Box::new(SampleResynthUiBridge::try_new(m)?))
+            }
+            ModuleHandle::EnvelopeFollower(m) => {
+                ModuleBridge::EnvelopeFollower(Box::new(EnvelopeFollowerUiBridge::try_new(m)?))
+            }
+            ModuleHandle::SpectralAnalyzer(m) => {
+                ModuleBridge::SpectralAnalyzer(Box::new(SpectralAnalyzerUiBridge::try_new(m)?))
+            }
+            ModuleHandle::Output(_) => return None,
         };
 
-        bridges.insert(id, Some(bridge));
+        Some(bridge)
+    }
 
-        Some(())
+    bridge_config_fn!(
+        Oscillator,
+        Envelope,
+        Amplifier,
+        Lfo,
+        Mixer,
+        WaveShaper,
+        SpectralFilter,
+        SpectralBlend,
+        SpectralMixer,
+        HarmonicEditor,
+        Expressions,
+        ExternalParam,
+        MidiControl,
+        Chorus,
+        Delay,
+        Reverb,
+        RingMod,
+        Panner,
+        Filter,
+        SpectralTilt,
+        HarmonicStretch,
+        HarmonicShift,
+        SpectralDynamics,
+        HarmonicEnvelopes,
+        SampleResynth,
+        Math,
+        HarmonicComb,
+        EnvelopeFollower,
+        SpectralAnalyzer,
+    );
+
+    // The bridge config doesn't follow bypass changes, the routing mirror has the current one
+    fn module_config(&self, module_id: ModuleId) -> Option<ModuleConfig> {
+        let mut config = self
+            .module_bridges
+            .get(&module_id)?
+            .as_ref()
+            .map(Self::bridge_config)?;
+
+        config.set_bypass(
+            self.routing
+                .modules
+                .get(&module_id)
+                .is_some_and(|module| module.bypass),
+        );
+        Some(config)
     }
 
+    // Compared against the factory's engine to notice a new patch, never locked
     pub fn engine(&self) -> &EngineHandle {
         &self.engine
     }
 
+    /// Changes with every edit of the patch. Equal counts of the same bridge mean an unchanged
+    /// patch, the undo history checks it before serializing anything.
+    pub fn edits(&self) -> u32 {
+        self.module_bridges.values().flatten().fold(
+            self.ui_end.edits().wrapping_add(self.ui_edits),
+            |edits, bridge| edits.wrapping_add(bridge.edits()),
+        )
+    }
+
+    fn ui_edited(&mut self) {
+        self.ui_edits = self.ui_edits.wrapping_add(1);
+    }
+
+    /// The patch as the editor has set it, edits still queued for the audio thread included.
+    pub fn engine_config(&self) -> EngineConfig {
+        let mut module_ids: Vec<_> = self.module_bridges.keys().copied().collect();
+
+        module_ids.sort_unstable();
+
+        let mut output_trims: Vec<_> = self
+            .routing
+            .modules
+            .values()
+            .filter_map(|module| module.output_trim.clone())
+            .collect();
+
+        output_trims.sort_unstable_by_key(|trim| trim.module_id);
+
+        EngineConfig {
+            engine: self.engine_params.clone(),
+            modules: module_ids
+                .into_iter()
+                .filter_map(|id| self.module_config(id))
+                .collect(),
+            links: SynthEngine::link_configs(&self.routing.routing),
+            automation: self.automation.clone(),
+            midi_mappings: self.midi_mappings.clone(),
+            output_trims,
+            float_param_names: self.float_param_names.clone(),
+        }
+    }
+
+    pub fn preset(&self) -> Preset {
+        Preset {
+            info: PresetInfo::default(),
+            engine: self.engine_config(),
+            ui: self.ui_config.lock().clone(),
+        }
+    }
+
     pub fn engine_params(&self) -> &EngineParams {
         &self.engine_params
     }
@@ -181,6 +477,24 @@ impl UiBridge {
         &self.voices
     }
 
+    pub fn voice_meters(&self) -> &[VoiceMeter; MAX_VOICES] {
+        &self.voice_meters
+    }
+
+    // Limiter gain reduction of the last reported block in dB
+    pub fn gain_reduction(&self) -> Sample {
+        self.gain_reduction
+    }
+
+    pub fn correlation(&self) -> Sample {
+        self.correlation
+    }
+
+    /// Smoothed share of the real-time budget the module takes, 1.0 is a full block.
+    pub fn module_load(&self, module_id: ModuleId) -> Sample {
+        self.module_loads.get(&module_id).copied().unwrap_or(0.0)
+    }
+
     fn module_label(ui_config: &ui_config::UiConfig, module_id: ModuleId) -> String {
         ui_config
             .modules
@@ -203,10 +517,99 @@ impl UiBridge {
             .collect()
     }
 
+    // Inputs with a base value, those can be driven by host automation
+    pub fn get_automatable_inputs(&self, module_id: ModuleId) -> Vec<Input> {
+        self.routing
+            .modules
+            .get(&module_id)
+            .map(|m| {
+                m.inputs
+                    .iter()
+                    .map(|input| input.input_type)
+                    .filter(Input::has_value)
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    pub fn is_audio_rate_input(&self, input: InputId) -> bool {
+        self.routing.modules.get(&input.module_id).is_some_and(|m| {
+            m.inputs
+                .iter()
+                .any(|meta| meta.input_type == input.input_type && meta.audio_rate)
+        })
+    }
+
     pub fn has_module_id(&self, module_id: ModuleId) -> bool {
         self.routing.modules.contains_key(&module_id)
     }
 
+    pub fn is_bypassed(&self, module_id: ModuleId) -> bool {
+        self.routing
+            .modules
+            .get(&module_id)
+            .is_some_and(|module| module.bypass)
+    }
+
+    pub fn set_module_bypass(&mut self, module_id: ModuleId, bypass: bool) {
+        if self.ui_end.set_module_bypass(module_id, bypass)
+            && let Some(module) = self.routing.modules.get_mut(&module_id)
+        {
+            module.bypass = bypass;
+        }
+    }
+
+    pub fn can_trim_output(&self, module_id: ModuleId) -> bool {
+        self.routing.modules.get(&module_id).is_some_and(|module| {
+            module.output_type == DataType::Audio && module.module_type != ModuleType::Output
+        })
+    }
+
+    pub fn output_trim(&self, module_id: ModuleId) -> OutputTrimConfig {
+        self.routing
+            .modules
+            .get(&module_id)
+            .and_then(|module| module.output_trim.clone())
+            .unwrap_or(OutputTrimConfig {
+                module_id,
+                gain: 0.0,
+                invert: false,
+            })
+    }
+
+    pub fn set_output_trim(&mut self, mut trim: OutputTrimConfig) {
+        if !self.can_trim_output(trim.module_id) {
+            return;
+        }
+
+        trim.gain = trim.gain.clamp(-MAX_OUTPUT_TRIM, MAX_OUTPUT_TRIM);
+
+        if self.ui_end.set_output_trim(trim.clone())
+            && let Some(module) = self.routing.modules.get_mut(&trim.module_id)
+        {
+            module.output_trim = Some(trim);
+        }
+    }
+
+    pub fn can_solo(&self, module_id: ModuleId) -> bool {
+        self.routing
+            .modules
+            .get(&module_id)
+            .is_some_and(|module| module.output_type == DataType::Audio)
+    }
+
+    pub fn solo(&self) -> Option<ModuleId> {
+        self.solo
+    }
+
+    pub fn set_solo(&mut self, module_id: Option<ModuleId>) {
+        let module_id = module_id.filter(|id| self.can_solo(*id));
+
+        if self.ui_end.set_solo(module_id) {
+            self.solo = module_id;
+        }
+    }
+
     pub fn with_module_bridge(
         &mut self,
         module_id: ModuleId,
@@ -241,8 +644,12 @@ impl UiBridge {
 
     pub fn set_module_position(&mut self, module_id: ModuleId, position: GridVec) {
         let mut ui_config = self.ui_config.lock();
-        if let Some(module) = ui_config.modules.get_mut(&module_id) {
+        if let Some(module) = ui_config.modules.get_mut(&module_id)
+            && module.position != position
+        {
             module.position = position;
+            drop(ui_config);
+            self.ui_edited();
         }
     }
 
@@ -259,6 +666,88 @@ impl UiBridge {
         };
 
         module.label = label;
+        drop(ui_config);
+        self.ui_edited();
+    }
+
+    pub fn module_color(&self, module_id: ModuleId) -> ModuleColor {
+        self.ui_config
+            .lock()
+            .modules
+            .get(&module_id)
+            .map_or(ModuleColor::None, |module| module.color)
+    }
+
+    pub fn set_module_color(&mut self, module_id: ModuleId, color: ModuleColor) {
+        if let Some(module) = self.ui_config.lock().modules.get_mut(&module_id) {
+            module.color = color;
+        }
+
+        self.ui_edited();
+    }
+
+    pub fn channels_linked(&self, module_id: ModuleId, param: &str) -> bool {
+        self.ui_config
+            .lock()
+            .modules
+            .get(&module_id)
+            .is_none_or(|module| !module.unlinked_channels.iter().any(|p| p == param))
+    }
+
+    pub fn set_channels_linked(&mut self, module_id: ModuleId, param: &str, linked: bool) {
+        let mut ui_config = self.ui_config.lock();
+        let Some(module) = ui_config.modules.get_mut(&module_id) else {
+            return;
+        };
+
+        module.unlinked_channels.retain(|p| p != param);
+
+        if !linked {
+            module.unlinked_channels.push(param.to_string());
+        }
+
+        drop(ui_config);
+        self.ui_edited();
+    }
+
+    pub fn randomize_locked(&self, module_id: ModuleId) -> bool {
+        self.ui_config
+            .lock()
+            .modules
+            .get(&module_id)
+            .is_some_and(|module| module.randomize_locked)
+    }
+
+    pub fn set_randomize_locked(&mut self, module_id: ModuleId, locked: bool) {
+        if let Some(module) = self.ui_config.lock().modules.get_mut(&module_id) {
+            module.randomize_locked = locked;
+        }
+
+        self.ui_edited();
+    }
+
+    pub fn param_locked(&self, module_id: ModuleId, param: RandomParam) -> bool {
+        self.ui_config
+            .lock()
+            .modules
+            .get(&module_id)
+            .is_some_and(|module| module.locked_params.contains(&param))
+    }
+
+    pub fn set_param_locked(&mut self, module_id: ModuleId, param: RandomParam, locked: bool) {
+        let mut ui_config = self.ui_config.lock();
+        let Some(module) = ui_config.modules.get_mut(&module_id) else {
+            return;
+        };
+
+        module.locked_params.retain(|p| *p != param);
+
+        if locked {
+            module.locked_params.push(param);
+        }
+
+        drop(ui_config);
+        self.ui_edited();
     }
 
     pub fn has_active_voices(&self) -> bool {
@@ -339,7 +828,7 @@ impl UiBridge {
 
     pub fn create_link(&mut self, src: ModuleId, dst: InputId) {
         let meta = if dst.module_id == OUTPUT_MODULE_ID && dst.input_type == Input::Audio {
-            InputMeta::audio(Input::Audio)
+            InputMeta::bus(Input::Audio)
         } else if let Some(module) = self.routing.modules.get(&dst.module_id)
             && let Some(meta) = module
                 .inputs
@@ -368,7 +857,7 @@ impl UiBridge {
 
         let dst_data_type =
             if input.module_id == OUTPUT_MODULE_ID && input.input_type == Input::Audio {
-                DataType::Audio
+                DataType::Bus
             } else if let Some(input_module) = self.routing.modules.get(&input.module_id)
                 && let Some(input_info) = input_module
                     .inputs
@@ -413,6 +902,10 @@ impl UiBridge {
             .map(|(_module, source)| ConnectedInputSource {
                 src: source.module_id,
                 amount: source.amount,
+                curve: source.curve,
+                polarity: source.polarity,
+                offset: source.offset,
+                mode: source.mode,
                 label: Self::module_label(&ui_config, source.module_id),
                 modulation: source
                     .modulation
@@ -489,95 +982,690 @@ impl UiBridge {
                         .or_insert(StereoSample::ZERO)[channel as usize] = value;
                 }
                 UiUpdate::VoicesStatus(status) => self.voices = status,
+                UiUpdate::VoiceMeters(meters) => self.voice_meters = meters,
+                UiUpdate::GainReduction(gain_reduction) => self.gain_reduction = gain_reduction,
+                UiUpdate::Correlation(correlation) => self.correlation = correlation,
+                UiUpdate::ModuleLoad { module_id, load } => {
+                    let smoothed = self.module_loads.entry(module_id).or_insert(load);
+
+                    *smoothed += (load - *smoothed) * MODULE_LOAD_SMOOTHING;
+                }
+                UiUpdate::MidiLearned(cc) => self.apply_midi_learned(cc),
+                // Dropped here on the editor thread
+                UiUpdate::Released(_) => (),
             }
         }
 
         for module in self.module_bridges.values_mut().filter_map(|m| m.as_mut()) {
             module.update();
         }
+
+        if let Some((module_id, with_links)) = self.pending_duplicate.take() {
+            self.duplicate_module(module_id, with_links);
+        }
     }
 
-    pub fn add_module(&mut self, module_type: ModuleType) -> ModuleId {
-        let mut synth = self.engine.lock();
-
-        let (id, label) = match module_type {
-            ModuleType::Output => (OUTPUT_MODULE_ID, "Output"),
-            ModuleType::Amplifier => (synth.add_amplifier(), "Amplifier"),
-            ModuleType::Envelope => (synth.add_envelope(), "Envelope"),
-            ModuleType::Mixer => (synth.add_mixer(), "Mixer"),
-            ModuleType::Oscillator => (synth.add_oscillator(), "Oscillator"),
-            ModuleType::SpectralFilter => (synth.add_spectral_filter(), "SpectralFilter"),
-            ModuleType::SpectralBlend => (synth.add_spectral_blend(), "SpectralBlend"),
-            ModuleType::SpectralMixer => (synth.add_spectral_mixer(), "SpectralMixer"),
-            ModuleType::HarmonicEditor => (synth.add_harmonic_editor(), "HarmonicEditor"),
-            ModuleType::ExternalParam => (synth.add_external_param(), "ExternalParam"),
-            ModuleType::Lfo => (synth.add_lfo(), "Lfo"),
-            ModuleType::WaveShaper => (synth.add_wave_shaper(), "WaveShaper"),
-            ModuleType::Expressions => (synth.add_expressions(), "Expressions"),
-        };
+    // Structural edits are made here, the audio thread swaps the prepared routing in between
+    // blocks. The mirrors follow once the edit is queued.
+    fn stage_routing(
+        &mut self,
+        mut routing: RoutingMap,
+        mut added: Vec<ModuleHandle>,
+        removed: &[ModuleId],
+    ) -> Result<(), String> {
+        if self.routing.modules.len() + added.len() > MAX_MODULES + removed.len() {
+            return Err("Too many modules.".to_string());
+        }
+
+        routing.retain(|_, sources| !sources.is_empty());
+
+        let mut outputs: ModuleOutputs = self
+            .routing
+            .modules
+            .values()
+            .filter(|module| !removed.contains(&module.id))
+            .map(|module| (module.id, (module.output_type, module.output_slot)))
+            .collect();
+
+        for module in &mut added {
+            let data_type = module.output_type();
+
+            module.set_output_slot(free_output_slot(&outputs, data_type));
+            outputs.insert(module.id(), (data_type, module.output_slot()));
+        }
 
-        self.routing = synth.get_routing_state();
-        drop(synth);
+        let tables = RoutingTables::build(routing.clone(), &outputs)?;
+        let mut mirrors = Vec::with_capacity(added.len());
+        let mut bridges = Vec::with_capacity(added.len());
 
-        Self::insert_module_bridge(id, &self.engine, &mut self.module_bridges);
+        for module in &mut added {
+            mirrors.push(Module::new(module, None));
+            bridges.push((module.id(), Self::module_bridge(module)));
+        }
+
+        if !self
+            .ui_end
+            .stage_routing(StagedRouting::new(tables, added, removed.to_vec()))
+        {
+            return Err("Too many pending edits.".to_string());
+        }
+
+        let mut modules = mem::take(&mut self.routing.modules);
+
+        for module_id in removed {
+            modules.remove(module_id);
+            self.module_loads.remove(module_id);
+
+            if let Some(Some(bridge)) = self.module_bridges.remove(module_id) {
+                self.ui_edits = self.ui_edits.wrapping_add(bridge.edits());
+            }
+        }
+
+        for module in mirrors {
+            self.next_id = self.next_id.max(module.id + 1);
+            modules.insert(module.id, module);
+        }
+
+        self.module_bridges.extend(bridges);
+        self.automation
+            .retain(|assignment| modules.contains_key(&assignment.module_id));
+        self.midi_mappings
+            .retain(|mapping| modules.contains_key(&mapping.module_id));
+        self.routing = RoutingState::new(modules, routing);
+
+        Ok(())
+    }
+
+    fn edit_routing(&mut self, edit: impl FnOnce(&mut RoutingMap)) -> Result<(), String> {
+        let mut routing = self.routing.routing.clone();
+
+        edit(&mut routing);
+        self.stage_routing(routing, Vec::new(), &[])
+    }
+
+    fn new_module(&self, module_type: ModuleType, id: ModuleId) -> Option<ModuleHandle> {
+        SynthEngine::new_module(
+            module_type,
+            id,
+            self.engine_params.wave_quality,
+            self.engine_params.spectral_size,
+            self.engine_params.table_update,
+            self.background_tables.load(Ordering::Relaxed),
+            &self.external_params,
+        )
+    }
+
+    fn module_from_config(&self, config: &ModuleConfig) -> ModuleHandle {
+        SynthEngine::module_from_config(
+            config,
+            self.engine_params.wave_quality,
+            self.engine_params.spectral_size,
+            self.engine_params.table_update,
+            self.background_tables.load(Ordering::Relaxed),
+            &self.external_params,
+        )
+    }
+
+    pub fn add_module(&mut self, module_type: ModuleType) -> Option<ModuleId> {
+        let id = self.next_id;
+        let module = self.new_module(module_type, id)?;
+
+        if let Err(err) = self.stage_routing(self.routing.routing.clone(), vec![module], &[]) {
+            println!("Failed to add module: {err}");
+            return None;
+        }
+
+        let mut ui_config = self.ui_config.lock();
+
+        ui_config.modules.insert(
+            id,
+            UiModuleConfig {
+                id,
+                label: format!("{module_type:?}"),
+                position: GridVec { x: -1, y: 0 },
+                unlinked_channels: Vec::new(),
+                randomize_locked: false,
+                locked_params: Vec::new(),
+                color: ModuleColor::None,
+            },
+        );
+
+        Some(id)
+    }
+
+    pub fn duplicate_module(&mut self, module_id: ModuleId, with_links: bool) -> Option<ModuleId> {
+        // The module panel holds the bridge while it's drawn, duplicated with the next update
+        if let Some(None) = self.module_bridges.get(&module_id) {
+            self.pending_duplicate = Some((module_id, with_links));
+            return None;
+        }
+
+        let id = self.next_id;
+
+        if let Err(err) = self.stage_duplicate(module_id, id, with_links) {
+            println!("Failed to duplicate module: {err}");
+            return None;
+        }
+
+        let output_trim = self
+            .routing
+            .modules
+            .get(&module_id)
+            .and_then(|module| module.output_trim.clone());
+
+        if let Some(trim) = output_trim {
+            self.set_output_trim(OutputTrimConfig {
+                module_id: id,
+                ..trim
+            });
+        }
 
         let mut ui_config = self.ui_config.lock();
+        let label = Self::module_label(&ui_config, module_id);
+        let source = ui_config
+            .modules
+            .get(&module_id)
+            .cloned()
+            .unwrap_or_default();
 
         ui_config.modules.insert(
             id,
             UiModuleConfig {
                 id,
-                label: label.into(),
+                label,
                 position: GridVec { x: -1, y: 0 },
+                ..source
             },
         );
 
-        id
+        Some(id)
+    }
+
+    // Copies the module config into a new module, `with_links` also copies the links into its inputs
+    fn stage_duplicate(
+        &mut self,
+        module_id: ModuleId,
+        id: ModuleId,
+        with_links: bool,
+    ) -> Result<(), String> {
+        let mut config = self
+            .module_config(module_id)
+            .ok_or_else(|| "Invalid module.".to_string())?;
+
+        config.set_id(id);
+
+        let module = self.module_from_config(&config);
+        let mut routing = self.routing.routing.clone();
+
+        if with_links {
+            let input_links: Vec<_> = routing
+                .iter()
+                .filter(|(dst, _)| dst.module_id == module_id)
+                .map(|(dst, sources)| (InputId::new(dst.input_type, id), sources.clone()))
+                .collect();
+
+            routing.extend(input_links);
+        }
+
+        self.stage_routing(routing, vec![module], &[])
     }
 
     pub fn remove_module(&mut self, module_id: ModuleId) {
-        let mut synth = self.engine.lock();
+        if !self.has_module_id(module_id) {
+            return;
+        }
+
+        let mut routing = self.routing.routing.clone();
+
+        routing.retain(|input, _| input.module_id != module_id);
+
+        for sources in routing.values_mut() {
+            sources.retain(|source| source.module_id != module_id);
+
+            for source in sources
+                .iter_mut()
+                .filter(|source| source.modulation == Some(module_id))
+            {
+                source.modulation = None;
+            }
+        }
 
-        synth.remove_module(module_id);
-        self.routing = synth.get_routing_state();
-        self.module_bridges.remove(&module_id);
+        if let Err(err) = self.stage_routing(routing, Vec::new(), &[module_id]) {
+            println!("Failed to remove module: {err}");
+            return;
+        }
+
+        Self::remove_from_groups(&mut self.ui_config.lock(), &[module_id]);
+    }
+
+    // A module belongs to one group at most, the groups left empty are dropped
+    fn remove_from_groups(ui_config: &mut UiConfig, module_ids: &[ModuleId]) {
+        for group in &mut ui_config.groups {
+            group.modules.retain(|id| !module_ids.contains(id));
+        }
+
+        ui_config.groups.retain(|group| !group.modules.is_empty());
+    }
+
+    pub fn groups(&self) -> Vec<ModuleGroup> {
+        self.ui_config.lock().groups.clone()
+    }
+
+    // Groups the modules and returns them as a sub-patch ready to be saved
+    pub fn create_group(
+        &mut self,
+        name: &str,
+        module_ids: &[ModuleId],
+    ) -> Result<SubPatch, String> {
+        let engine = SynthEngine::sub_patch_config(module_ids, &self.routing.routing, |id| {
+            self.module_config(id)
+        })?;
+        let mut ui_config = self.ui_config.lock();
+        let modules = module_ids
+            .iter()
+            .filter_map(|id| Some((*id, ui_config.modules.get(id)?.clone())))
+            .collect();
+
+        Self::remove_from_groups(&mut ui_config, module_ids);
+        ui_config.groups.push(ModuleGroup {
+            name: name.to_string(),
+            modules: module_ids.to_vec(),
+        });
+        drop(ui_config);
+        self.ui_edited();
+
+        Ok(SubPatch {
+            name: name.to_string(),
+            engine,
+            ui: UiConfig {
+                modules,
+                groups: Vec::new(),
+            },
+        })
+    }
+
+    pub fn ungroup(&mut self, group_idx: usize) {
+        let mut ui_config = self.ui_config.lock();
+
+        if group_idx < ui_config.groups.len() {
+            ui_config.groups.remove(group_idx);
+        }
+
+        drop(ui_config);
+        self.ui_edited();
+    }
+
+    // The inserted modules keep their labels and form a new group named after the sub-patch
+    pub fn insert_sub_patch(&mut self, sub_patch: &SubPatch) -> Result<(), String> {
+        let new_ids = SynthEngine::sub_patch_ids(&sub_patch.engine, self.next_id)?;
+        let links = SynthEngine::sub_patch_links(&sub_patch.engine, &new_ids)?;
+
+        let added: Vec<_> = sub_patch
+            .engine
+            .modules
+            .iter()
+            .map(|config| {
+                let mut config = config.clone();
+
+                config.set_id(new_ids[&config.id()]);
+                self.module_from_config(&config)
+            })
+            .collect();
+
+        let mut routing = self.routing.routing.clone();
+
+        // The links only join the sub-patch modules
+        for link in links.iter().map(SynthEngine::config_link) {
+            let src = added.iter().find(|module| module.id() == link.src);
+            let dst = added
+                .iter()
+                .find(|module| module.id() == link.dst.module_id);
+
+            SynthEngine::check_link(
+                src.map(|module| module.output_type()),
+                &link.dst,
+                dst.map(|module| module.inputs()),
+            )
+            .map_err(|_| "Invalid sub-patch links.".to_string())?;
+
+            let sources = routing.entry(link.dst).or_default();
+
+            if !sources.iter().any(|source| source.module_id == link.src) {
+                sources.push(link.source());
+            }
+        }
+
+        self.stage_routing(routing, added, &[])?;
+
+        let mut ui_config = self.ui_config.lock();
+        let mut group = ModuleGroup {
+            name: sub_patch.name.clone(),
+            modules: Vec::new(),
+        };
+
+        for module in &sub_patch.engine.modules {
+            let id = new_ids[&module.id()];
+            let saved = sub_patch.ui.modules.get(&module.id());
+
+            ui_config.modules.insert(
+                id,
+                UiModuleConfig {
+                    id,
+                    position: GridVec { x: -1, y: 0 },
+                    ..saved.cloned().unwrap_or_default()
+                },
+            );
+            group.modules.push(id);
+        }
+
+        ui_config.groups.push(group);
+        Ok(())
+    }
+
+    // User name of a float param slot, the slot number when it isn't named
+    pub fn float_param_label(&self, idx: usize) -> String {
+        match self.float_param_names.get(idx) {
+            Some(name) if !name.is_empty() => name.clone(),
+            _ => format!("Param #{}", idx + 1),
+        }
+    }
+
+    pub fn float_param_name(&self, idx: usize) -> &str {
+        self.float_param_names
+            .get(idx)
+            .map(String::as_str)
+            .unwrap_or_default()
+    }
+
+    pub fn set_float_param_name(&mut self, idx: usize, name: String) {
+        self.external_params.set_float_param_name(idx, &name);
+        self.ui_edited();
+
+        if let Some(slot) = self.float_param_names.get_mut(idx) {
+            *slot = name;
+        }
+    }
+
+    pub fn automation(&self) -> &[AutomationConfig] {
+        &self.automation
+    }
+
+    pub fn set_automation(&mut self, automation: Vec<AutomationConfig>) {
+        let checked = automation.iter().try_for_each(|assignment| {
+            SynthEngine::check_automation(assignment, self.module_inputs(assignment.module_id))
+        });
+
+        if let Err(err) = checked {
+            println!("Failed to set automation: {err}");
+        } else if self
+            .ui_end
+            .set_automation(Automation::assignments(&automation))
+        {
+            self.automation = automation;
+        }
+    }
+
+    pub fn midi_mappings(&self) -> &[MidiMappingConfig] {
+        &self.midi_mappings
+    }
+
+    pub fn midi_mapping(&self, input: InputId) -> Option<&MidiMappingConfig> {
+        self.midi_mappings
+            .iter()
+            .find(|m| m.module_id == input.module_id && m.input == input.input_type)
+    }
+
+    pub fn set_midi_mappings(&mut self, mappings: Vec<MidiMappingConfig>) {
+        let checked = mappings.iter().try_for_each(|mapping| {
+            SynthEngine::check_midi_mapping(mapping, self.module_inputs(mapping.module_id))
+        });
+
+        if let Err(err) = checked {
+            println!("Failed to set MIDI mappings: {err}");
+        } else if self.ui_end.set_midi_mappings(mappings.clone()) {
+            self.midi_mappings = mappings;
+        }
+    }
+
+    pub fn midi_learn_target(&self) -> Option<MidiLearnTarget> {
+        self.midi_learn
+    }
+
+    pub fn arm_midi_learn(&mut self, target: MidiLearnTarget) {
+        if self.ui_end.set_midi_learn(true) {
+            self.midi_learn = Some(target);
+        }
+    }
+
+    pub fn cancel_midi_learn(&mut self) {
+        if self.ui_end.set_midi_learn(false) {
+            self.midi_learn = None;
+        }
+    }
+
+    // Auditioning from the editor keyboard, the notes reach the engine with the next block
+    pub fn note_on(&mut self, note: u8, velocity: Sample) -> bool {
+        self.ui_end.note_on(note, velocity)
+    }
+
+    pub fn note_off(&mut self, note: u8) -> bool {
+        self.ui_end.note_off(note, KEYBOARD_RELEASE_VELOCITY)
+    }
+
+    fn apply_midi_learned(&mut self, cc: u8) {
+        match self.midi_learn.take() {
+            Some(MidiLearnTarget::Input { input, min, max }) => {
+                let mut mappings = self.midi_mappings.clone();
+
+                mappings
+                    .retain(|m| !(m.module_id == input.module_id && m.input == input.input_type));
+                mappings.push(MidiMappingConfig {
+                    cc,
+                    module_id: input.module_id,
+                    input: input.input_type,
+                    min,
+                    max,
+                    curve: 0.0,
+                });
+                self.set_midi_mappings(mappings);
+            }
+            Some(MidiLearnTarget::ExternalParam(module_id)) => {
+                if let Some(Some(ModuleBridge::ExternalParam(bridge))) =
+                    self.module_bridges.get_mut(&module_id)
+                {
+                    bridge.set_midi_cc(Some(cc));
+                }
+            }
+            None => {}
+        }
+    }
+
+    pub fn is_capturing(&self) -> bool {
+        self.capture.as_ref().is_some_and(Capture::is_recording)
+    }
+
+    pub fn start_capture(&mut self, path: &Path) -> Result<(), String> {
+        self.capture
+            .as_mut()
+            .ok_or_else(|| "Capture isn't available.".to_string())?
+            .start(path)
+    }
+
+    pub fn stop_capture(&mut self) -> Result<(), String> {
+        self.capture.as_mut().map_or(Ok(()), Capture::stop)
+    }
+
+    pub fn set_scope_active(&mut self, active: bool) {
+        if let Some(scope) = self.scope.as_mut() {
+            scope.set_active(active);
+        }
+    }
+
+    pub fn scope_sample_rate(&self) -> Option<Sample> {
+        self.scope.as_ref().map(Scope::sample_rate)
+    }
+
+    pub fn read_scope(&mut self, history: &mut VecDeque<ScopeFrame>, capacity: usize) {
+        if let Some(scope) = self.scope.as_mut() {
+            scope.read(history, capacity);
+        }
+    }
+
+    pub fn set_analyzer_active(&mut self, active: bool) {
+        if let Some(analyzer) = self.analyzer.as_mut() {
+            analyzer.set_active(active);
+        }
+    }
+
+    pub fn analyzer_sample_rate(&self) -> Option<Sample> {
+        self.analyzer.as_ref().map(Analyzer::sample_rate)
+    }
+
+    pub fn read_spectrum(&self, spectrum: &mut Vec<Sample>) {
+        if let Some(analyzer) = self.analyzer.as_ref() {
+            analyzer.read_spectrum(spectrum);
+        }
+    }
+
+    // Rendering runs on a copy in its own thread, neither the audio thread nor the editor waits
+    // for it. The result is picked up with finished_wavetable_export.
+    pub fn export_wavetable(
+        &mut self,
+        oscillator_id: ModuleId,
+        path: &Path,
+        num_frames: usize,
+        duration: Sample,
+    ) -> Result<(), String> {
+        // A finished export nobody picked up is dropped with its handle
+        if self
+            .wavetable_export
+            .as_ref()
+            .is_some_and(|export| !export.is_finished())
+        {
+            return Err("Export is already running.".to_string());
+        }
+
+        let offline = SynthEngine::try_new(
+            &self.engine_config(),
+            self.output_level_param.clone(),
+            self.external_params.clone(),
+            self.host_sample_rate,
+        )
+        .ok_or_else(|| "Failed to copy the engine.".to_string())?;
+        let path = path.to_path_buf();
+
+        self.wavetable_export = Some(thread::spawn(move || {
+            offline.export_wavetable(oscillator_id, &path, num_frames, duration)
+        }));
+        Ok(())
+    }
+
+    pub fn finished_wavetable_export(&mut self) -> Option<Result<(), String>> {
+        if !self
+            .wavetable_export
+            .as_ref()
+            .is_some_and(JoinHandle::is_finished)
+        {
+            return None;
+        }
+
+        let result = self
+            .wavetable_export
+            .take()?
+            .join()
+            .unwrap_or_else(|_| Err("Export thread panicked.".to_string()));
+
+        Some(result)
+    }
+
+    pub fn set_wave_quality(&mut self, wave_quality: WaveQuality) {
+        if self.ui_end.set_wave_quality(wave_quality) {
+            self.engine_params.wave_quality = wave_quality;
+        }
+    }
+
+    pub fn set_spectral_size(&mut self, spectral_size: SpectralSize) {
+        if self.ui_end.set_spectral_size(spectral_size) {
+            self.engine_params.spectral_size = spectral_size;
+        }
+    }
+
+    pub fn set_table_update(&mut self, table_update: TableUpdate) {
+        if self.ui_end.set_table_update(table_update) {
+            self.engine_params.table_update = table_update;
+        }
     }
 
     pub fn set_direct_link(&mut self, src: ModuleId, dst: InputId) {
-        let mut synth = self.engine.lock();
+        let result = self.check_link(src, &dst).and_then(|()| {
+            self.edit_routing(|routing| {
+                routing.insert(dst, vec![ModuleLink::link(src, dst).source()]);
+            })
+        });
 
-        let _ = synth.set_direct_link(src, dst);
-        self.routing = synth.get_routing_state();
+        if let Err(err) = result {
+            println!("Failed to set link: {err}");
+        }
     }
 
     pub fn add_link(&mut self, src: ModuleId, dst: InputId, amount: StereoSample) {
-        let mut synth = self.engine.lock();
+        let result = self.check_link(src, &dst).and_then(|()| {
+            if self.already_linked(src, &dst) {
+                return Ok(());
+            }
 
-        if let Err(err) = synth.add_link(src, dst, amount) {
+            self.edit_routing(|routing| {
+                let sources = routing.entry(dst).or_default();
+
+                // Disconnect src from modulations
+                for source in sources
+                    .iter_mut()
+                    .filter(|source| source.modulation == Some(src))
+                {
+                    source.modulation = None;
+                }
+
+                sources.push(ModuleLink::scaled(src, dst, amount).source());
+            })
+        });
+
+        if let Err(err) = result {
             println!("Failed to add link: {err}");
         }
-        self.routing = synth.get_routing_state();
     }
 
     pub fn remove_link(&mut self, src: ModuleId, dst: InputId) {
-        let mut synth = self.engine.lock();
+        let result = self.edit_routing(|routing| {
+            if let Some(sources) = routing.get_mut(&dst) {
+                sources.retain(|source| source.module_id != src);
+            }
+        });
 
-        synth.remove_link(&src, &dst);
-        self.routing = synth.get_routing_state();
+        if let Err(err) = result {
+            println!("Failed to remove link: {err}");
+        }
     }
 
     pub fn remove_input_links(&mut self, dst: InputId) {
-        let mut synth = self.engine.lock();
+        let result = self.edit_routing(|routing| {
+            routing.remove(&dst);
+        });
 
-        synth.remove_input_links(&dst);
-        self.routing = synth.get_routing_state();
+        if let Err(err) = result {
+            println!("Failed to remove links: {err}");
+        }
     }
 
     pub fn remove_output_links(&mut self, src: ModuleId) {
-        let mut synth = self.engine.lock();
+        let result = self.edit_routing(|routing| {
+            for sources in routing.values_mut() {
+                sources.retain(|source| source.module_id != src);
+            }
+        });
 
-        synth.remove_output_links(src);
-        self.routing = synth.get_routing_state();
+        if let Err(err) = result {
+            println!("Failed to remove links: {err}");
+        }
     }
 
     pub fn set_link_modulation(
@@ -586,25 +1674,120 @@ impl UiBridge {
         dst_input: &InputId,
         modulator_id: ModuleId,
     ) {
-        let mut synth = self.engine.lock();
+        let result = self.check_link(modulator_id, dst_input).and_then(|()| {
+            if !self.already_linked(src_id, dst_input) {
+                return Err("Invalid node.".to_string());
+            }
 
-        let _ = synth.set_link_modulation(src_id, dst_input, modulator_id);
-        self.routing = synth.get_routing_state();
+            self.edit_routing(|routing| {
+                if let Some(source) = Self::link_source(routing, src_id, dst_input) {
+                    source.modulation = Some(modulator_id);
+                }
+            })
+        });
+
+        if let Err(err) = result {
+            println!("Failed to set link modulation: {err}");
+        }
     }
 
     pub fn remove_link_modulation(&mut self, src_id: ModuleId, dst_input: &InputId) {
-        let mut synth = self.engine.lock();
+        if !self.already_linked(src_id, dst_input) {
+            return;
+        }
+
+        let result = self.edit_routing(|routing| {
+            if let Some(source) = Self::link_source(routing, src_id, dst_input) {
+                source.modulation = None;
+            }
+        });
+
+        if let Err(err) = result {
+            println!("Failed to remove link modulation: {err}");
+        }
+    }
+
+    // The engine makes the same check, the editor checks against its copy of the modules
+    fn check_link(&self, src: ModuleId, dst: &InputId) -> Result<(), String> {
+        SynthEngine::check_link(
+            self.routing
+                .modules
+                .get(&src)
+                .map(|module| module.output_type),
+            dst,
+            self.module_inputs(dst.module_id),
+        )
+    }
+
+    fn already_linked(&self, src: ModuleId, dst: &InputId) -> bool {
+        self.routing
+            .routing
+            .get(dst)
+            .is_some_and(|sources| sources.iter().any(|source| source.module_id == src))
+    }
+
+    fn module_inputs(&self, module_id: ModuleId) -> Option<&'static [InputMeta]> {
+        self.routing
+            .modules
+            .get(&module_id)
+            .map(|module| module.inputs)
+    }
 
-        synth.remove_link_modulation(src_id, dst_input);
-        self.routing = synth.get_routing_state();
+    fn link_source<'a>(
+        routing: &'a mut RoutingMap,
+        src_id: ModuleId,
+        dst_input: &InputId,
+    ) -> Option<&'a mut InputSource> {
+        routing
+            .get_mut(dst_input)?
+            .iter_mut()
+            .find(|source| source.module_id == src_id)
+    }
+
+    pub fn set_link_curve(&mut self, src_id: ModuleId, dst_input: &InputId, curve: LinkCurve) {
+        if self.ui_end.set_link_curve(src_id, *dst_input, curve) {
+            self.update_link_source(src_id, dst_input, |source| source.curve = curve);
+        }
+    }
+
+    pub fn set_link_polarity(
+        &mut self,
+        src_id: ModuleId,
+        dst_input: &InputId,
+        polarity: LinkPolarity,
+    ) {
+        if self.ui_end.set_link_polarity(src_id, *dst_input, polarity) {
+            self.update_link_source(src_id, dst_input, |source| source.polarity = polarity);
+        }
+    }
+
+    pub fn set_link_mode(&mut self, src_id: ModuleId, dst_input: &InputId, mode: LinkMode) {
+        if self.ui_end.set_link_mode(src_id, *dst_input, mode) {
+            self.update_link_source(src_id, dst_input, |source| source.mode = mode);
+        }
+    }
+
+    pub fn set_link_offset(&mut self, src_id: ModuleId, dst_input: &InputId, offset: Sample) {
+        if self.ui_end.set_link_offset(src_id, *dst_input, offset) {
+            self.update_link_source(src_id, dst_input, |source| source.offset = offset);
+        }
+    }
+
+    // Mirrors an edit the engine applies in place, the link stays where it is
+    fn update_link_source(
+        &mut self,
+        src_id: ModuleId,
+        dst_input: &InputId,
+        update: impl FnOnce(&mut InputSource),
+    ) {
+        if let Some(source) = Self::link_source(&mut self.routing.routing, src_id, dst_input) {
+            update(source);
+        }
     }
 
     pub fn set_link_amount(&mut self, src: ModuleId, dst: InputId, amount: StereoSample) {
-        if self.ui_end.set_link_amount(src, dst, amount)
-            && let Some(sources) = self.routing.routing.get_mut(&dst)
-            && let Some(source) = sources.iter_mut().find(|s| s.module_id == src)
-        {
-            source.amount = amount;
+        if self.ui_end.set_link_amount(src, dst, amount) {
+            self.update_link_source(src, &dst, |source| source.amount = amount);
         }
     }
 
@@ -620,6 +1803,70 @@ impl UiBridge {
         }
     }
 
+    pub fn set_portamento(&mut self, portamento: PortamentoMode) {
+        if self.ui_end.set_portamento(portamento) {
+            self.engine_params.portamento = portamento;
+        }
+    }
+
+    pub fn set_steal_priority(&mut self, steal_priority: StealPriority) {
+        if self.ui_end.set_steal_priority(steal_priority) {
+            self.engine_params.steal_priority = steal_priority;
+        }
+    }
+
+    pub fn set_tuning(&mut self, tuning: Option<TuningConfig>) {
+        if self.ui_end.set_tuning(tuning.clone()) {
+            self.engine_params.tuning = tuning;
+        }
+    }
+
+    // Connecting blocks, it's done here before the mode goes to the audio thread
+    pub fn set_mts_esp(&mut self, mts_esp: MtsEspMode) {
+        let connection = VoicesHandler::connect_mts_esp(mts_esp, self.engine_params.mts_esp);
+
+        if self.ui_end.set_mts_esp(mts_esp, connection) {
+            self.engine_params.mts_esp = mts_esp;
+        }
+    }
+
+    pub fn set_mpe(&mut self, mpe: bool) {
+        if self.ui_end.set_mpe(mpe) {
+            self.engine_params.mpe = mpe;
+        }
+    }
+
+    // The host parameters are the only master tune and transpose, the engine reads them every block
+    pub fn master_tune(&self) -> Sample {
+        self.external_params.master_tune.value()
+    }
+
+    pub fn set_master_tune(&mut self, master_tune: Sample) {
+        self.host_edits.push(HostParamEdit::MasterTune(master_tune));
+    }
+
+    pub fn transpose(&self) -> i32 {
+        self.external_params.transpose.value()
+    }
+
+    pub fn set_transpose(&mut self, transpose: i32) {
+        self.host_edits.push(HostParamEdit::Transpose(transpose));
+    }
+
+    pub fn external_params(&self) -> &ExternalParamsBlock {
+        &self.external_params
+    }
+
+    pub fn take_host_edits(&mut self) -> Vec<HostParamEdit> {
+        mem::take(&mut self.host_edits)
+    }
+
+    pub fn set_pitch_bend_range(&mut self, pitch_bend_range: Sample) {
+        if self.ui_end.set_pitch_bend_range(pitch_bend_range) {
+            self.engine_params.pitch_bend_range = pitch_bend_range;
+        }
+    }
+
     pub fn set_block_size(&mut self, block_size: usize) {
         if self.ui_end.set_block_size(block_size) {
             self.engine_params.block_size = block_size;
@@ -632,7 +1879,7 @@ impl UiBridge {
         }
     }
 
-    pub fn set_oversampling(&mut self, oversampling: bool) {
+    pub fn set_oversampling(&mut self, oversampling: Oversampling) {
         if self.ui_end.set_oversampling(oversampling) {
             self.engine_params.oversampling = oversampling;
         }
@@ -649,4 +1896,28 @@ impl UiBridge {
             self.engine_params.output_gain = output_gain;
         }
     }
+
+    pub fn set_dc_blocker(&mut self, dc_blocker: bool) {
+        if self.ui_end.set_dc_blocker(dc_blocker) {
+            self.engine_params.dc_blocker = dc_blocker;
+        }
+    }
+
+    pub fn set_output_mode(&mut self, output_mode: OutputMode) {
+        if self.ui_end.set_output_mode(output_mode) {
+            self.engine_params.output_mode = output_mode;
+        }
+    }
+
+    pub fn set_limiter(&mut self, limiter: LimiterConfig) {
+        if self.ui_end.set_limiter(limiter) {
+            self.engine_params.limiter = limiter;
+        }
+    }
+
+    pub fn set_chord(&mut self, chord: ChordConfig) {
+        if self.ui_end.set_chord(chord) {
+            self.engine_params.chord = chord;
+        }
+    }
 }
@@ -1,6 +1,21 @@
 use crate::synth_engine::{
-    Input, ModuleId, InputId, Sample, StereoSample, ui_bridge::VoicesStatus,
-    voices_handler::VoicesHandlerUiState,
+    Input, InputId, LinkCurve, LinkMode, LinkPolarity, ModuleId, MtsEspMode, OutputTrimConfig,
+    Sample, SpectralSize, StereoSample, TuningConfig,
+    automation::Assignment,
+    chord_memory::ChordConfig,
+    config::MidiMappingConfig,
+    edit_sender::EditSender,
+    limiter::LimiterConfig,
+    modules::VoiceLevel,
+    oscillator::{TableUpdate, WaveQuality},
+    output_mode::OutputMode,
+    oversampling::Oversampling,
+    routing::MAX_VOICES,
+    staged_routing::StagedRouting,
+    ui_bridge::{VoiceMeter, VoicesStatus},
+    voices_handler::{
+        MtsConnection, PortamentoMode, StealPriority, VoiceState, VoicesHandlerUiState,
+    },
 };
 
 pub enum UiEvent {
@@ -9,13 +24,65 @@ pub enum UiEvent {
         dst: InputId,
         amount: StereoSample,
     },
+    LinkCurve {
+        src: ModuleId,
+        dst: InputId,
+        curve: LinkCurve,
+    },
+    LinkPolarity {
+        src: ModuleId,
+        dst: InputId,
+        polarity: LinkPolarity,
+    },
+    LinkMode {
+        src: ModuleId,
+        dst: InputId,
+        mode: LinkMode,
+    },
+    LinkOffset {
+        src: ModuleId,
+        dst: InputId,
+        offset: Sample,
+    },
+    ModuleBypass {
+        module_id: ModuleId,
+        bypass: bool,
+    },
+    OutputTrim(OutputTrimConfig),
+    Solo(Option<ModuleId>),
+    // The replaced data comes back in the same box
+    Tuning(Box<Option<TuningConfig>>),
+    Routing(Box<StagedRouting>),
+    Automation(Box<Vec<Assignment>>),
+    MidiMappings(Box<Vec<MidiMappingConfig>>),
+    MtsEsp(MtsEspMode, Box<MtsConnection>),
+    WaveQuality(WaveQuality),
     Voices(usize),
     Legato(bool),
+    Portamento(PortamentoMode),
+    StealPriority(StealPriority),
+    Mpe(bool),
+    PitchBendRange(Sample),
     BlockSize(usize),
     VoiceKillTime(Sample),
-    Oversampling(bool),
+    Oversampling(Oversampling),
     StereoSpectrum(bool),
+    SpectralSize(SpectralSize),
+    TableUpdate(TableUpdate),
     OutputGain(StereoSample),
+    DcBlocker(bool),
+    OutputMode(OutputMode),
+    Limiter(LimiterConfig),
+    Chord(ChordConfig),
+    MidiLearn(bool),
+    NoteOn {
+        note: u8,
+        velocity: Sample,
+    },
+    NoteOff {
+        note: u8,
+        velocity: Sample,
+    },
 }
 
 pub enum UiUpdate {
@@ -26,16 +93,62 @@ pub enum UiUpdate {
         value: Sample,
     },
     VoicesStatus(VoicesStatus),
+    VoiceMeters([VoiceMeter; MAX_VOICES]),
+    GainReduction(Sample),
+    Correlation(Sample),
+    ModuleLoad {
+        module_id: ModuleId,
+        load: Sample,
+    },
+    MidiLearned(u8),
+    Released(Released),
 }
 
+// Data replaced on the audio thread, sent back to be freed on the editor thread
+pub enum Released {
+    Tuning(Box<Option<TuningConfig>>),
+    Routing(Box<StagedRouting>),
+    Automation(Box<Vec<Assignment>>),
+    MidiMappings(Box<Vec<MidiMappingConfig>>),
+    MtsEsp(Box<MtsConnection>),
+}
+
+const MAX_STASHED: usize = 64;
+
 pub struct AudioEnd {
     rx: rtrb::Consumer<UiEvent>,
     tx: rtrb::Producer<UiUpdate>,
+    // Released data waiting for room in the update queue
+    stashed: Vec<Released>,
 }
 
 impl AudioEnd {
     pub fn new(rx: rtrb::Consumer<UiEvent>, tx: rtrb::Producer<UiUpdate>) -> Self {
-        Self { rx, tx }
+        Self {
+            rx,
+            tx,
+            stashed: Vec::with_capacity(MAX_STASHED),
+        }
+    }
+
+    // Keeps the data until the editor has room for it. Once the editor stopped reading the
+    // updates for good, leaking beats freeing on the audio thread.
+    pub fn release(&mut self, released: Released) {
+        if self.stashed.len() < self.stashed.capacity() {
+            self.stashed.push(released);
+        } else {
+            std::mem::forget(released);
+        }
+
+        self.flush_released();
+    }
+
+    pub fn flush_released(&mut self) {
+        while !self.tx.is_full()
+            && let Some(released) = self.stashed.pop()
+        {
+            let _ = self.tx.push(UiUpdate::Released(released));
+        }
     }
 
     pub fn update_modulated_input(
@@ -66,6 +179,42 @@ impl AudioEnd {
             .is_ok()
     }
 
+    pub fn update_voice_meters(
+        &mut self,
+        states: &[VoiceState; MAX_VOICES],
+        levels: &[VoiceLevel; MAX_VOICES],
+    ) -> bool {
+        self.tx
+            .push(UiUpdate::VoiceMeters(std::array::from_fn(|idx| {
+                VoiceMeter {
+                    state: states[idx],
+                    peak: levels[idx].peak,
+                    rms: levels[idx].rms,
+                }
+            })))
+            .is_ok()
+    }
+
+    pub fn update_gain_reduction(&mut self, gain_reduction: Sample) -> bool {
+        self.tx
+            .push(UiUpdate::GainReduction(gain_reduction))
+            .is_ok()
+    }
+
+    pub fn update_correlation(&mut self, correlation: Sample) -> bool {
+        self.tx.push(UiUpdate::Correlation(correlation)).is_ok()
+    }
+
+    pub fn update_module_load(&mut self, module_id: ModuleId, load: Sample) -> bool {
+        self.tx
+            .push(UiUpdate::ModuleLoad { module_id, load })
+            .is_ok()
+    }
+
+    pub fn update_midi_learned(&mut self, cc: u8) -> bool {
+        self.tx.push(UiUpdate::MidiLearned(cc)).is_ok()
+    }
+
     pub fn pop_event(&mut self) -> Option<UiEvent> {
         self.rx.pop().ok()
     }
@@ -73,25 +222,96 @@ impl AudioEnd {
 
 pub struct UiEnd {
     rx: rtrb::Consumer<UiUpdate>,
-    tx: rtrb::Producer<UiEvent>,
+    tx: EditSender<UiEvent>,
 }
 
 impl UiEnd {
     pub fn new(rx: rtrb::Consumer<UiUpdate>, tx: rtrb::Producer<UiEvent>) -> Self {
-        Self { rx, tx }
+        Self {
+            rx,
+            tx: EditSender::new(tx),
+        }
+    }
+
+    pub fn edits(&self) -> u32 {
+        self.tx.sent()
+    }
+
+    pub fn set_link_amount(&mut self, src: ModuleId, dst: InputId, amount: StereoSample) -> bool {
+        self.tx
+            .push(UiEvent::LinkAmount { src, dst, amount })
+            .is_ok()
+    }
+
+    pub fn set_link_curve(&mut self, src: ModuleId, dst: InputId, curve: LinkCurve) -> bool {
+        self.tx.push(UiEvent::LinkCurve { src, dst, curve }).is_ok()
     }
 
-    pub fn set_link_amount(
+    pub fn set_link_polarity(
         &mut self,
         src: ModuleId,
         dst: InputId,
-        amount: StereoSample,
+        polarity: LinkPolarity,
     ) -> bool {
         self.tx
-            .push(UiEvent::LinkAmount { src, dst, amount })
+            .push(UiEvent::LinkPolarity { src, dst, polarity })
+            .is_ok()
+    }
+
+    pub fn set_link_mode(&mut self, src: ModuleId, dst: InputId, mode: LinkMode) -> bool {
+        self.tx.push(UiEvent::LinkMode { src, dst, mode }).is_ok()
+    }
+
+    pub fn set_link_offset(&mut self, src: ModuleId, dst: InputId, offset: Sample) -> bool {
+        self.tx
+            .push(UiEvent::LinkOffset { src, dst, offset })
+            .is_ok()
+    }
+
+    pub fn set_module_bypass(&mut self, module_id: ModuleId, bypass: bool) -> bool {
+        self.tx
+            .push(UiEvent::ModuleBypass { module_id, bypass })
             .is_ok()
     }
 
+    pub fn set_output_trim(&mut self, trim: OutputTrimConfig) -> bool {
+        self.tx.push(UiEvent::OutputTrim(trim)).is_ok()
+    }
+
+    pub fn set_solo(&mut self, module_id: Option<ModuleId>) -> bool {
+        self.tx.push(UiEvent::Solo(module_id)).is_ok()
+    }
+
+    pub fn set_tuning(&mut self, tuning: Option<TuningConfig>) -> bool {
+        self.tx.push(UiEvent::Tuning(Box::new(tuning))).is_ok()
+    }
+
+    pub fn stage_routing(&mut self, staged: StagedRouting) -> bool {
+        self.tx.push(UiEvent::Routing(Box::new(staged))).is_ok()
+    }
+
+    pub fn set_automation(&mut self, assignments: Vec<Assignment>) -> bool {
+        self.tx
+            .push(UiEvent::Automation(Box::new(assignments)))
+            .is_ok()
+    }
+
+    pub fn set_midi_mappings(&mut self, mappings: Vec<MidiMappingConfig>) -> bool {
+        self.tx
+            .push(UiEvent::MidiMappings(Box::new(mappings)))
+            .is_ok()
+    }
+
+    pub fn set_mts_esp(&mut self, mts_esp: MtsEspMode, connection: MtsConnection) -> bool {
+        self.tx
+            .push(UiEvent::MtsEsp(mts_esp, Box::new(connection)))
+            .is_ok()
+    }
+
+    pub fn set_wave_quality(&mut self, wave_quality: WaveQuality) -> bool {
+        self.tx.push(UiEvent::WaveQuality(wave_quality)).is_ok()
+    }
+
     pub fn set_voices(&mut self, voices: usize) -> bool {
         self.tx.push(UiEvent::Voices(voices)).is_ok()
     }
@@ -100,6 +320,24 @@ impl UiEnd {
         self.tx.push(UiEvent::Legato(legato)).is_ok()
     }
 
+    pub fn set_portamento(&mut self, portamento: PortamentoMode) -> bool {
+        self.tx.push(UiEvent::Portamento(portamento)).is_ok()
+    }
+
+    pub fn set_steal_priority(&mut self, steal_priority: StealPriority) -> bool {
+        self.tx.push(UiEvent::StealPriority(steal_priority)).is_ok()
+    }
+
+    pub fn set_mpe(&mut self, mpe: bool) -> bool {
+        self.tx.push(UiEvent::Mpe(mpe)).is_ok()
+    }
+
+    pub fn set_pitch_bend_range(&mut self, pitch_bend_range: Sample) -> bool {
+        self.tx
+            .push(UiEvent::PitchBendRange(pitch_bend_range))
+            .is_ok()
+    }
+
     pub fn set_block_size(&mut self, block_size: usize) -> bool {
         self.tx.push(UiEvent::BlockSize(block_size)).is_ok()
     }
@@ -110,7 +348,7 @@ impl UiEnd {
             .is_ok()
     }
 
-    pub fn set_oversampling(&mut self, oversampling: bool) -> bool {
+    pub fn set_oversampling(&mut self, oversampling: Oversampling) -> bool {
         self.tx.push(UiEvent::Oversampling(oversampling)).is_ok()
     }
 
@@ -120,10 +358,46 @@ impl UiEnd {
             .is_ok()
     }
 
+    pub fn set_spectral_size(&mut self, spectral_size: SpectralSize) -> bool {
+        self.tx.push(UiEvent::SpectralSize(spectral_size)).is_ok()
+    }
+
+    pub fn set_table_update(&mut self, table_update: TableUpdate) -> bool {
+        self.tx.push(UiEvent::TableUpdate(table_update)).is_ok()
+    }
+
     pub fn set_output_gain(&mut self, output_gain: StereoSample) -> bool {
         self.tx.push(UiEvent::OutputGain(output_gain)).is_ok()
     }
 
+    pub fn set_dc_blocker(&mut self, dc_blocker: bool) -> bool {
+        self.tx.push(UiEvent::DcBlocker(dc_blocker)).is_ok()
+    }
+
+    pub fn set_output_mode(&mut self, output_mode: OutputMode) -> bool {
+        self.tx.push(UiEvent::OutputMode(output_mode)).is_ok()
+    }
+
+    pub fn set_limiter(&mut self, limiter: LimiterConfig) -> bool {
+        self.tx.push(UiEvent::Limiter(limiter)).is_ok()
+    }
+
+    pub fn set_chord(&mut self, chord: ChordConfig) -> bool {
+        self.tx.push(UiEvent::Chord(chord)).is_ok()
+    }
+
+    pub fn set_midi_learn(&mut self, learning: bool) -> bool {
+        self.tx.push(UiEvent::MidiLearn(learning)).is_ok()
+    }
+
+    pub fn note_on(&mut self, note: u8, velocity: Sample) -> bool {
+        self.tx.push(UiEvent::NoteOn { note, velocity }).is_ok()
+    }
+
+    pub fn note_off(&mut self, note: u8, velocity: Sample) -> bool {
+        self.tx.push(UiEvent::NoteOff { note, velocity }).is_ok()
+    }
+
     pub fn pop_update(&mut self) -> Option<UiUpdate> {
         self.rx.pop().ok()
     }
@@ -131,7 +405,7 @@ impl UiEnd {
 
 pub fn create_link_pair() -> (AudioEnd, UiEnd) {
     let (to_audio_tx, from_ui_rx) = rtrb::RingBuffer::<UiEvent>::new(512);
-    let (to_ui_tx, from_audio_rx) = rtrb::RingBuffer::<UiUpdate>::new(128);
+    let (to_ui_tx, from_audio_rx) = rtrb::RingBuffer::<UiUpdate>::new(512);
 
     (
         AudioEnd::new(from_ui_rx, to_ui_tx),
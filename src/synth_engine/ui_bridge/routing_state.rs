@@ -1,7 +1,8 @@
 use rustc_hash::{FxHashMap, FxHashSet};
 
 use crate::synth_engine::{
-    InputId, ModuleHandle, ModuleId, ModuleType, RoutingMap, StereoSample,
+    InputId, LinkCurve, LinkMode, LinkPolarity, ModuleHandle, ModuleId, ModuleType,
+    OutputTrimConfig, RoutingMap, Sample, StereoSample,
     routing::{DataType, InputMeta, InputSource},
     synth_module::SynthModule,
 };
@@ -11,15 +12,24 @@ pub struct Module {
     pub module_type: ModuleType,
     pub inputs: &'static [InputMeta],
     pub output_type: DataType,
+    pub output_slot: usize,
+    pub bypass: bool,
+    pub output_trim: Option<OutputTrimConfig>,
 }
 
 impl Module {
-    pub(in super::super) fn new(module: &ModuleHandle) -> Self {
+    pub(in super::super) fn new(
+        module: &ModuleHandle,
+        output_trim: Option<OutputTrimConfig>,
+    ) -> Self {
         Self {
             id: module.id(),
             module_type: module.module_type(),
             inputs: module.inputs(),
             output_type: module.output_type(),
+            output_slot: module.output_slot(),
+            bypass: module.bypass(),
+            output_trim,
         }
     }
 }
@@ -30,7 +40,6 @@ pub struct AvailableInputSource {
 }
 
 pub struct InputModulation {
-    #[allow(unused)]
     pub src: ModuleId,
     pub label: String,
 }
@@ -38,6 +47,10 @@ pub struct InputModulation {
 pub struct ConnectedInputSource {
     pub src: ModuleId,
     pub amount: StereoSample,
+    pub curve: LinkCurve,
+    pub polarity: LinkPolarity,
+    pub offset: Sample,
+    pub mode: LinkMode,
     pub label: String,
     pub modulation: Option<InputModulation>,
 }
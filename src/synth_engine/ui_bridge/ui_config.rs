@@ -3,7 +3,7 @@ use std::ops::Add;
 use rustc_hash::FxHashMap;
 use serde::{Deserialize, Serialize};
 
-use crate::synth_engine::ModuleId;
+use crate::synth_engine::{ModuleId, RandomParam};
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Default, Serialize, Deserialize)]
 pub struct GridVec {
@@ -37,15 +37,97 @@ impl Add for GridVec {
     }
 }
 
+// Tag painted on the module in the grid and next to its label, helps finding modules in big patches
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ModuleColor {
+    #[default]
+    None,
+    Red,
+    Orange,
+    Yellow,
+    Green,
+    Teal,
+    Blue,
+    Purple,
+    Pink,
+}
+
+impl ModuleColor {
+    pub const ALL: [Self; 9] = [
+        Self::None,
+        Self::Red,
+        Self::Orange,
+        Self::Yellow,
+        Self::Green,
+        Self::Teal,
+        Self::Blue,
+        Self::Purple,
+        Self::Pink,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::None => "None",
+            Self::Red => "Red",
+            Self::Orange => "Orange",
+            Self::Yellow => "Yellow",
+            Self::Green => "Green",
+            Self::Teal => "Teal",
+            Self::Blue => "Blue",
+            Self::Purple => "Purple",
+            Self::Pink => "Pink",
+        }
+    }
+}
+
 #[derive(Default, Clone, Serialize, Deserialize)]
 pub struct UiModuleConfig {
     pub id: ModuleId,
     pub label: String,
     #[serde(default)]
     pub position: GridVec,
+    // Stereo parameters edited one channel at a time, the rest move both channels together
+    #[serde(default)]
+    pub unlinked_channels: Vec<String>,
+    // Kept as is by the patch randomizer
+    #[serde(default)]
+    pub randomize_locked: bool,
+    #[serde(default)]
+    pub locked_params: Vec<RandomParam>,
+    #[serde(default)]
+    pub color: ModuleColor,
+}
+
+#[derive(Default, Clone, Serialize, Deserialize)]
+pub struct ModuleGroup {
+    pub name: String,
+    pub modules: Vec<ModuleId>,
 }
 
 #[derive(Default, Clone, Serialize, Deserialize)]
 pub struct UiConfig {
     pub modules: FxHashMap<ModuleId, UiModuleConfig>,
+    #[serde(default)]
+    pub groups: Vec<ModuleGroup>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn module_color_defaults_to_none_and_is_persisted() {
+        let module: UiModuleConfig = serde_json::from_str(r#"{"id":3,"label":"Osc"}"#).unwrap();
+
+        assert_eq!(module.color, ModuleColor::None);
+
+        let json = serde_json::to_string(&UiModuleConfig {
+            color: ModuleColor::Teal,
+            ..module
+        })
+        .unwrap();
+        let module: UiModuleConfig = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(module.color, ModuleColor::Teal);
+    }
 }
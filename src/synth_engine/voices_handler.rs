@@ -1,20 +1,80 @@
 use std::collections::VecDeque;
 
 use nih_plug::nih_log;
+use serde::{Deserialize, Serialize};
 use smallvec::SmallVec;
 
+#[cfg(feature = "mts-esp")]
+use crate::synth_engine::mts_esp::MtsClient;
 use crate::{
     synth_engine::{
         Expression, Sample,
         routing::{MAX_VOICES, VoiceEvent},
+        tuning::TuningConfig,
     },
-    utils::note_to_pitch,
+    utils::{note_to_pitch, st_to_octave},
 };
 
 pub const MAX_AVAILABLE_VOICES: usize = MAX_VOICES - 8;
+pub const MPE_PITCH_BEND_RANGE: Sample = 48.0;
+pub const MPE_MASTER_CHANNEL: u8 = 0;
+pub const MAX_MASTER_TUNE: Sample = 100.0;
+pub const MAX_TRANSPOSE: i32 = 24;
+
+const NUM_MIDI_CHANNELS: usize = 16;
+// Notes pushed out of their voices wait here, every key of a channel fits without growing
+const MAX_WAITING_NOTES: usize = 128;
 
 type VoiceIdx = u8;
 
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PortamentoMode {
+    Off,
+    Legato,
+    #[default]
+    Always,
+}
+
+impl PortamentoMode {
+    fn glide_from_released(self) -> bool {
+        self == Self::Always
+    }
+
+    fn glide_from_held(self) -> bool {
+        self != Self::Off
+    }
+}
+
+// Which voice is killed when a note comes in and all voices are taken.
+// Releasing voices are stolen before playing ones, except for Quietest where only the level counts.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StealPriority {
+    #[default]
+    Oldest,
+    Quietest,
+    LowestNote,
+    HighestNote,
+    // The quietest releasing voice, i.e. the one closest to the end of its release
+    Released,
+}
+
+// Following an MTS-ESP master, only available with the `mts-esp` feature.
+// Continuous also retunes sounding notes when the master changes its tuning.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MtsEspMode {
+    #[default]
+    Off,
+    NoteOn,
+    Continuous,
+}
+
+// Client of the tuning master, connected and dropped off the audio thread. Always empty
+// without MTS-ESP support.
+#[cfg(feature = "mts-esp")]
+pub type MtsConnection = Option<MtsClient>;
+#[cfg(not(feature = "mts-esp"))]
+pub type MtsConnection = Option<std::convert::Infallible>;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 struct NoteId {
     channel: u8,
@@ -34,6 +94,12 @@ struct PlayingNote {
     velocity: u8,
 }
 
+#[derive(Clone, Copy, Default)]
+struct ChannelExpressions {
+    pitch: Sample,
+    pressure: Sample,
+}
+
 struct ReleasingNote {
     id: NoteId,
     voice_idx: VoiceIdx,
@@ -72,8 +138,9 @@ impl DecayingVoice {
 pub type DecayingVoices = SmallVec<[DecayingVoice; MAX_VOICES]>;
 pub type PlayingVoices = SmallVec<[usize; MAX_VOICES]>;
 
+// Fits an event for every voice, retuning all the sounding notes doesn't spill to the heap
 pub struct VoiceEvents {
-    events: SmallVec<[VoiceEvent; 6]>,
+    events: SmallVec<[VoiceEvent; MAX_VOICES]>,
 }
 
 impl VoiceEvents {
@@ -99,22 +166,32 @@ impl VoiceEvents {
         &mut self,
         voice_idx: VoiceIdx,
         prev_voice_idx: Option<VoiceIdx>,
-        note_id: NoteId,
+        pitch: Sample,
         velocity: u8,
+        glide: bool,
     ) {
         self.events.push(VoiceEvent::Trigger {
             voice_idx: voice_idx as usize,
             prev_voice_idx: prev_voice_idx.map(|idx| idx as usize),
-            pitch: Self::note_to_pitch(note_id.note),
+            pitch,
             velocity: Self::to_float_velocity(velocity),
+            glide,
         });
     }
 
-    fn update(&mut self, voice_idx: VoiceIdx, note_id: NoteId, velocity: u8) {
+    fn update(&mut self, voice_idx: VoiceIdx, pitch: Sample, velocity: u8, glide: bool) {
         self.events.push(VoiceEvent::Update {
             voice_idx: voice_idx as usize,
-            pitch: Self::note_to_pitch(note_id.note),
+            pitch,
             velocity: Self::to_float_velocity(velocity),
+            glide,
+        });
+    }
+
+    fn retune(&mut self, voice_idx: VoiceIdx, pitch: Sample) {
+        self.events.push(VoiceEvent::Retune {
+            voice_idx: voice_idx as usize,
+            pitch,
         });
     }
 
@@ -140,9 +217,22 @@ impl VoiceEvents {
     }
 }
 
+#[derive(Clone, Copy, Default, PartialEq)]
+pub enum VoiceState {
+    #[default]
+    Idle,
+    Playing,
+    Releasing,
+    Killing,
+}
+
 pub struct VoicesHandlerUiState {
     pub num_voices: usize,
     pub legato: bool,
+    pub portamento: PortamentoMode,
+    pub steal_priority: StealPriority,
+    pub mts_esp: MtsEspMode,
+    pub mpe: bool,
     pub waiting: usize,
     pub playing: usize,
     pub releasing: usize,
@@ -152,6 +242,24 @@ pub struct VoicesHandlerUiState {
 pub struct VoicesHandler {
     num_voices: usize,
     legato: bool,
+    portamento: PortamentoMode,
+    steal_priority: StealPriority,
+    mpe: bool,
+    // None is 12-TET
+    tuning: Option<TuningConfig>,
+    mts_esp: MtsEspMode,
+    #[cfg(feature = "mts-esp")]
+    mts_client: Option<MtsClient>,
+    // Cents and semitones, follow the host parameters
+    master_tune: Sample,
+    transpose: i32,
+    // Sounding notes have to follow a changed master tune or transpose
+    retune_pending: bool,
+    // Last pitch sent to every voice slot
+    voice_pitches: [Sample; MAX_VOICES],
+    // RMS of every voice slot over the last processed block
+    voice_levels: [Sample; MAX_VOICES],
+    channel_expressions: [ChannelExpressions; NUM_MIDI_CHANNELS],
     waiting_notes: SmallVec<[WaitingNote; 32]>,
     playing_notes: VecDeque<PlayingNote>,
     releasing_notes: VecDeque<ReleasingNote>,
@@ -164,6 +272,19 @@ impl VoicesHandler {
         Self {
             num_voices: num_voices.clamp(1, MAX_AVAILABLE_VOICES),
             legato,
+            portamento: PortamentoMode::default(),
+            steal_priority: StealPriority::default(),
+            mpe: false,
+            tuning: None,
+            mts_esp: MtsEspMode::Off,
+            #[cfg(feature = "mts-esp")]
+            mts_client: None,
+            master_tune: 0.0,
+            transpose: 0,
+            retune_pending: false,
+            voice_pitches: [0.0; MAX_VOICES],
+            voice_levels: [0.0; MAX_VOICES],
+            channel_expressions: Default::default(),
             waiting_notes: SmallVec::new(),
             playing_notes: VecDeque::with_capacity(MAX_VOICES),
             releasing_notes: VecDeque::with_capacity(MAX_VOICES),
@@ -176,11 +297,63 @@ impl VoicesHandler {
         (velocity * 127.0).round().clamp(1.0, 127.0) as u8
     }
 
+    #[cfg(feature = "mts-esp")]
+    fn mts_pitch(&self, note: NoteId) -> Option<Sample> {
+        self.mts_client
+            .as_ref()
+            .and_then(|client| client.note_pitch(note.note, note.channel))
+    }
+
+    #[cfg(not(feature = "mts-esp"))]
+    fn mts_pitch(&self, _note: NoteId) -> Option<Sample> {
+        None
+    }
+
+    #[cfg(feature = "mts-esp")]
+    fn mts_filters_note(&self, note: NoteId) -> bool {
+        self.mts_client
+            .as_ref()
+            .is_some_and(|client| client.should_filter_note(note.note, note.channel))
+    }
+
+    #[cfg(not(feature = "mts-esp"))]
+    fn mts_filters_note(&self, _note: NoteId) -> bool {
+        false
+    }
+
+    fn pitch_offset(&self) -> Sample {
+        st_to_octave(self.transpose as Sample) + self.master_tune / 1200.0
+    }
+
+    // A connected MTS-ESP master wins over the tuning table.
+    // Falls back to 12-TET for keys unmapped after the note was pressed.
+    fn note_pitch(&self, note: NoteId) -> Sample {
+        let pitch = self
+            .mts_pitch(note)
+            .or_else(|| {
+                self.tuning
+                    .as_ref()
+                    .and_then(|tuning| tuning.pitch(note.note))
+            })
+            .unwrap_or_else(|| VoiceEvents::note_to_pitch(note.note));
+
+        pitch + self.pitch_offset()
+    }
+
+    fn is_note_filtered(&self, note: NoteId) -> bool {
+        self.mts_filters_note(note)
+            || self
+                .tuning
+                .as_ref()
+                .is_some_and(|tuning| tuning.pitch(note.note).is_none())
+    }
+
     fn grab_and_restart_voice(
         &mut self,
         prev_voice_idx: Option<VoiceIdx>,
         note: NoteId,
         velocity: u8,
+        glide: bool,
         events: &mut VoiceEvents,
     ) {
         let Some(voice_idx) = self
@@ -203,12 +376,18 @@ impl VoicesHandler {
             panic!("restart_voice(): Note processing error")
         };
 
+        // Not heard yet, keeps the new note from being the quietest one until the next block
+        self.voice_levels[voice_idx as usize] = Sample::INFINITY;
         self.playing_notes.push_front(PlayingNote {
             id: note,
             voice_idx,
             velocity,
         });
-        events.restart(voice_idx, prev_voice_idx, note, velocity);
+        let pitch = self.note_pitch(note);
+
+        self.voice_pitches[voice_idx as usize] = pitch;
+        events.restart(voice_idx, prev_voice_idx, pitch, velocity, glide);
+        self.apply_channel_expressions(voice_idx, note.channel, events);
     }
 
     fn apply_legato(
@@ -223,7 +402,39 @@ impl VoicesHandler {
             voice_idx,
             velocity,
         });
-        events.update(voice_idx, note_id, velocity);
+        let pitch = self.note_pitch(note_id);
+
+        self.voice_pitches[voice_idx as usize] = pitch;
+        events.update(
+            voice_idx,
+            pitch,
+            velocity,
+            self.portamento.glide_from_held(),
+        );
+        self.apply_channel_expressions(voice_idx, note_id.channel, events);
+    }
+
+    fn apply_channel_expressions(
+        &self,
+        voice_idx: VoiceIdx,
+        channel: u8,
+        events: &mut VoiceEvents,
+    ) {
+        if !self.mpe {
+            return;
+        }
+
+        let Some(expressions) = self.channel_expressions.get(channel as usize) else {
+            return;
+        };
+
+        if expressions.pitch != 0.0 {
+            events.expression(voice_idx, Expression::Pitch, expressions.pitch);
+        }
+
+        if expressions.pressure != 0.0 {
+            events.expression(voice_idx, Expression::Pressure, expressions.pressure);
+        }
     }
 
     fn kill_voice(&mut self, voice_idx: VoiceIdx, events: &mut VoiceEvents) {
@@ -245,7 +456,13 @@ impl VoicesHandler {
                 .voice_idx;
 
             self.kill_voice(voice_idx, events);
-            self.grab_and_restart_voice(Some(voice_idx), new_note, velocity, events);
+            self.grab_and_restart_voice(
+                Some(voice_idx),
+                new_note,
+                velocity,
+                self.portamento.glide_from_released(),
+                events,
+            );
 
         // Kill playing note on same channel
         } else if let Some(playing_idx) = self
@@ -264,10 +481,16 @@ impl VoicesHandler {
                 self.apply_legato(playing.voice_idx, new_note, velocity, events);
             } else {
                 self.kill_voice(playing.voice_idx, events);
-                self.grab_and_restart_voice(Some(playing.voice_idx), new_note, velocity, events);
+                self.grab_and_restart_voice(
+                    Some(playing.voice_idx),
+                    new_note,
+                    velocity,
+                    self.portamento.glide_from_held(),
+                    events,
+                );
             }
         } else {
-            self.grab_and_restart_voice(None, new_note, velocity, events);
+            self.grab_and_restart_voice(None, new_note, velocity, false, events);
         }
     }
 
@@ -283,31 +506,75 @@ impl VoicesHandler {
             self.kill_voice(voice_idx, events);
         }
 
-        // All available voices have been occupied, kill the oldest one
+        // All available voices have been occupied, kill one according to the steal priority
         if self.playing_notes.len() + self.releasing_notes.len() >= self.num_voices {
-            let Some(voice_idx) = self
-                .releasing_notes
-                .pop_back()
-                .map(|r| r.voice_idx)
-                .or_else(|| {
-                    self.playing_notes
-                        .pop_back()
-                        .inspect(|p| {
-                            self.waiting_notes.push(WaitingNote {
-                                id: p.id,
-                                velocity: p.velocity,
-                            });
-                        })
-                        .map(|p| p.voice_idx)
-                })
-            else {
+            let Some(voice_idx) = self.steal_voice() else {
                 panic!("note_on_polyphonic(): Note processing error")
             };
 
             self.kill_voice(voice_idx, events);
         }
 
-        self.grab_and_restart_voice(None, new_note, velocity, events);
+        self.grab_and_restart_voice(None, new_note, velocity, false, events);
+    }
+
+    // Lower scores are stolen first, `position` counts from the newest note
+    fn steal_score(
+        &self,
+        position: usize,
+        note: NoteId,
+        voice_idx: VoiceIdx,
+        releasing: bool,
+    ) -> Sample {
+        let oldest = -(position as Sample);
+        let level = self.voice_levels[voice_idx as usize];
+
+        match self.steal_priority {
+            StealPriority::Oldest => oldest,
+            StealPriority::Quietest => level,
+            StealPriority::LowestNote => note.note as Sample,
+            StealPriority::HighestNote => -(note.note as Sample),
+            StealPriority::Released if releasing => level,
+            StealPriority::Released => oldest,
+        }
+    }
+
+    // A stolen playing note goes back to the waiting list
+    fn steal_voice(&mut self) -> Option<VoiceIdx> {
+        let releasing = self
+            .releasing_notes
+            .iter()
+            .enumerate()
+            .map(|(pos, r)| (self.steal_score(pos, r.id, r.voice_idx, true), pos))
+            .min_by(|a, b| a.0.total_cmp(&b.0));
+        let playing = self
+            .playing_notes
+            .iter()
+            .enumerate()
+            .map(|(pos, p)| (self.steal_score(pos, p.id, p.voice_idx, false), pos))
+            .min_by(|a, b| a.0.total_cmp(&b.0));
+
+        let steal_playing = match (releasing, playing) {
+            (Some(releasing), Some(playing)) => {
+                self.steal_priority == StealPriority::Quietest && playing.0 < releasing.0
+            }
+            (None, Some(_)) => true,
+            (_, None) => false,
+        };
+
+        if steal_playing {
+            let playing = self.playing_notes.remove(playing?.1)?;
+
+            self.waiting_notes.push(WaitingNote {
+                id: playing.id,
+                velocity: playing.velocity,
+            });
+            Some(playing.voice_idx)
+        } else {
+            self.releasing_notes
+                .remove(releasing?.1)
+                .map(|releasing| releasing.voice_idx)
+        }
     }
 
     fn note_on_impl(&mut self, channel: u8, note: u8, velocity: u8, events: &mut VoiceEvents) {
@@ -328,6 +595,11 @@ impl VoicesHandler {
             return;
         }
 
+        // Keys left out by the keyboard mapping or the MTS-ESP master don't play
+        if self.is_note_filtered(new_note) {
+            return;
+        }
+
         if monophonic {
             self.note_on_monophonic(new_note, velocity, events);
         } else {
@@ -433,6 +705,21 @@ impl VoicesHandler {
         }
     }
 
+    // Allocates, done before the audio thread runs the handler
+    pub fn preallocate(&mut self) {
+        self.waiting_notes
+            .reserve(MAX_WAITING_NOTES.saturating_sub(self.waiting_notes.len()));
+    }
+
+    // Drops every note without a release, the voices are silent from the next block on
+    pub fn clear(&mut self) {
+        self.waiting_notes.clear();
+        self.playing_notes.clear();
+        self.releasing_notes.clear();
+        self.killing_voices.clear();
+        self.free_voices = SmallVec::from_iter((0..(MAX_VOICES as u8)).rev());
+    }
+
     pub fn handle_expression(
         &mut self,
         channel: u8,
@@ -453,6 +740,36 @@ impl VoicesHandler {
         }
     }
 
+    pub fn handle_channel_expression(
+        &mut self,
+        channel: u8,
+        expression: Expression,
+        value: Sample,
+        events: &mut VoiceEvents,
+    ) {
+        if !self.mpe {
+            return;
+        }
+
+        let Some(expressions) = self.channel_expressions.get_mut(channel as usize) else {
+            return;
+        };
+
+        match expression {
+            Expression::Pitch => expressions.pitch = value,
+            Expression::Pressure => expressions.pressure = value,
+            _ => return,
+        }
+
+        for playing in self
+            .playing_notes
+            .iter()
+            .filter(|p| p.id.channel == channel)
+        {
+            events.expression(playing.voice_idx, expression, value);
+        }
+    }
+
     pub fn set_num_voices(&mut self, num_voices: usize) {
         self.num_voices = num_voices.clamp(1, MAX_AVAILABLE_VOICES);
     }
@@ -461,10 +778,132 @@ impl VoicesHandler {
         self.legato = legato;
     }
 
+    pub fn set_portamento(&mut self, portamento: PortamentoMode) {
+        self.portamento = portamento;
+    }
+
+    pub fn set_steal_priority(&mut self, steal_priority: StealPriority) {
+        self.steal_priority = steal_priority;
+    }
+
+    pub fn update_voice_levels(&mut self, levels: impl Iterator<Item = Sample>) {
+        for (voice_level, level) in self.voice_levels.iter_mut().zip(levels) {
+            *voice_level = level;
+        }
+    }
+
+    pub fn tuning(&self) -> Option<&TuningConfig> {
+        self.tuning.as_ref()
+    }
+
+    /// Returns the replaced tuning, playing notes keep their pitch until retriggered.
+    pub fn set_tuning(&mut self, tuning: Option<TuningConfig>) -> Option<TuningConfig> {
+        std::mem::replace(&mut self.tuning, tuning)
+    }
+
+    pub fn mts_esp(&self) -> MtsEspMode {
+        self.mts_esp
+    }
+
+    /// Connecting loads libMTS, must not be called on the audio thread.
+    pub fn set_mts_esp(&mut self, mts_esp: MtsEspMode) {
+        #[cfg(feature = "mts-esp")]
+        if mts_esp == MtsEspMode::Off {
+            self.mts_client = None;
+        } else if self.mts_client.is_none() {
+            self.mts_client = MtsClient::connect();
+        }
+
+        self.mts_esp = mts_esp;
+    }
+
+    /// Blocks on libMTS when turned on from Off, the editor connects before it sends the mode over.
+    #[cfg(feature = "mts-esp")]
+    pub fn connect_mts_esp(mts_esp: MtsEspMode, from: MtsEspMode) -> MtsConnection {
+        (from == MtsEspMode::Off && mts_esp != MtsEspMode::Off)
+            .then(MtsClient::connect)
+            .flatten()
+    }
+
+    #[cfg(not(feature = "mts-esp"))]
+    pub fn connect_mts_esp(_mts_esp: MtsEspMode, _from: MtsEspMode) -> MtsConnection {
+        None
+    }
+
+    // Doesn't block, the client that's no longer used is left in `connection`
+    pub fn swap_mts_esp(&mut self, mts_esp: MtsEspMode, connection: &mut MtsConnection) {
+        #[cfg(feature = "mts-esp")]
+        if mts_esp == MtsEspMode::Off || self.mts_client.is_none() {
+            std::mem::swap(&mut self.mts_client, connection);
+        }
+
+        #[cfg(not(feature = "mts-esp"))]
+        let _ = connection;
+
+        self.mts_esp = mts_esp;
+    }
+
+    pub fn master_tune(&self) -> Sample {
+        self.master_tune
+    }
+
+    pub fn transpose(&self) -> i32 {
+        self.transpose
+    }
+
+    // Called every block with the values of the host parameters
+    pub fn set_host_tuning(&mut self, master_tune: Sample, transpose: i32) {
+        let master_tune = master_tune.clamp(-MAX_MASTER_TUNE, MAX_MASTER_TUNE);
+        let transpose = transpose.clamp(-MAX_TRANSPOSE, MAX_TRANSPOSE);
+
+        self.retune_pending |= master_tune != self.master_tune || transpose != self.transpose;
+        self.master_tune = master_tune;
+        self.transpose = transpose;
+    }
+
+    /// Sends new pitches to the sounding notes after a master tune or transpose change,
+    /// and re-queries them every block in the continuous MTS-ESP mode.
+    pub fn retune(&mut self, events: &mut VoiceEvents) {
+        if !self.retune_pending && self.mts_esp != MtsEspMode::Continuous {
+            return;
+        }
+
+        self.retune_pending = false;
+
+        let sounding = self
+            .playing_notes
+            .iter()
+            .map(|p| (p.id, p.voice_idx))
+            .chain(self.releasing_notes.iter().map(|r| (r.id, r.voice_idx)));
+
+        for (note, voice_idx) in sounding {
+            let pitch = self.note_pitch(note);
+            let voice_pitch = &mut self.voice_pitches[voice_idx as usize];
+
+            if pitch != *voice_pitch {
+                *voice_pitch = pitch;
+                events.retune(voice_idx, pitch);
+            }
+        }
+    }
+
+    pub fn mpe(&self) -> bool {
+        self.mpe
+    }
+
+    pub fn set_mpe(&mut self, mpe: bool) {
+        self.mpe = mpe;
+        self.channel_expressions = Default::default();
+    }
+
     pub fn get_ui_state(&self) -> VoicesHandlerUiState {
         VoicesHandlerUiState {
             num_voices: self.num_voices,
             legato: self.legato,
+            portamento: self.portamento,
+            steal_priority: self.steal_priority,
+            mts_esp: self.mts_esp,
+            mpe: self.mpe,
             waiting: self.waiting_notes.len(),
             playing: self.playing_notes.len(),
             releasing: self.releasing_notes.len(),
@@ -472,6 +911,22 @@ impl VoicesHandler {
         }
     }
 
+    pub fn get_voice_states(&self, states: &mut [VoiceState; MAX_VOICES]) {
+        states.fill(VoiceState::Idle);
+
+        for note in &self.playing_notes {
+            states[note.voice_idx as usize] = VoiceState::Playing;
+        }
+
+        for note in &self.releasing_notes {
+            states[note.voice_idx as usize] = VoiceState::Releasing;
+        }
+
+        for &voice_idx in &self.killing_voices {
+            states[voice_idx as usize] = VoiceState::Killing;
+        }
+    }
+
     pub fn get_decaying_voices(&self, decaying_voices: &mut DecayingVoices) {
         decaying_voices.extend(
             self.releasing_notes
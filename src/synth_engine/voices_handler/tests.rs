@@ -34,7 +34,7 @@ fn count_by_kind(ev: &VoiceEvents) -> (usize, usize, usize, usize, usize) {
     for e in ev.events() {
         match e {
             VoiceEvent::Trigger { .. } => trig += 1,
-            VoiceEvent::Update { .. } => upd += 1,
+            VoiceEvent::Update { .. } | VoiceEvent::Retune { .. } => upd += 1,
             VoiceEvent::Release { .. } => rel += 1,
             VoiceEvent::Kill { .. } => kill += 1,
             VoiceEvent::Expression { .. } => expr += 1,
@@ -199,6 +199,71 @@ fn poly_steals_releasing_before_playing() {
     assert_eq!(ui.waiting, 0);
 }
 
+fn kill_indices(ev: &VoiceEvents) -> Vec<usize> {
+    ev.events()
+        .iter()
+        .filter_map(|e| match e {
+            VoiceEvent::Kill { voice_idx } => Some(*voice_idx),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn poly_steal_lowest_note() {
+    let mut h = handler(2);
+    h.set_steal_priority(StealPriority::LowestNote);
+    let mut ev = events();
+
+    h.handle_note_on(0, 64, 1.0, &mut ev);
+    h.handle_note_on(0, 60, 1.0, &mut ev);
+    let low_voice = trigger_indices(&ev)[1];
+
+    let mut ev = events();
+    h.handle_note_on(0, 67, 1.0, &mut ev);
+
+    assert_eq!(kill_indices(&ev), vec![low_voice]);
+    assert_eq!(h.get_ui_state().waiting, 1);
+}
+
+#[test]
+fn poly_steal_quietest_ignores_note_age() {
+    let mut h = handler(2);
+    h.set_steal_priority(StealPriority::Quietest);
+    let mut ev = events();
+
+    h.handle_note_on(0, 60, 1.0, &mut ev);
+    h.handle_note_on(0, 64, 1.0, &mut ev);
+    let newest_voice = trigger_indices(&ev)[1];
+
+    let mut levels = [0.5; MAX_VOICES];
+    levels[newest_voice] = 0.1;
+    h.update_voice_levels(levels.into_iter());
+
+    let mut ev = events();
+    h.handle_note_on(0, 67, 1.0, &mut ev);
+
+    assert_eq!(kill_indices(&ev), vec![newest_voice]);
+}
+
+#[test]
+fn poly_steal_highest_note_prefers_releasing() {
+    let mut h = handler(2);
+    h.set_steal_priority(StealPriority::HighestNote);
+    let mut ev = events();
+
+    h.handle_note_on(0, 60, 1.0, &mut ev);
+    h.handle_note_on(0, 72, 1.0, &mut ev);
+    let released_voice = trigger_indices(&ev)[0];
+    h.handle_note_off(0, 60, 1.0, &mut ev);
+
+    let mut ev = events();
+    h.handle_note_on(0, 67, 1.0, &mut ev);
+
+    assert_eq!(kill_indices(&ev), vec![released_voice]);
+    assert_eq!(h.get_ui_state().waiting, 0);
+}
+
 // ---- Polyphonic note-off ----
 
 #[test]
@@ -306,6 +371,58 @@ fn mono_note_on_kills_releasing_on_same_channel() {
     assert_eq!(ui.releasing, 0);
 }
 
+fn trigger_glides(ev: &VoiceEvents) -> Vec<bool> {
+    ev.events()
+        .iter()
+        .filter_map(|e| match e {
+            VoiceEvent::Trigger { glide, .. } => Some(*glide),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn mono_portamento_always_glides_from_released_note() {
+    let mut h = handler(1);
+    let mut ev = events();
+
+    h.handle_note_on(0, 60, 1.0, &mut ev);
+    h.handle_note_off(0, 60, 1.0, &mut ev);
+    h.handle_note_on(0, 64, 1.0, &mut ev);
+
+    assert_eq!(trigger_glides(&ev), vec![false, true]);
+}
+
+#[test]
+fn mono_portamento_legato_glides_only_from_held_note() {
+    let mut h = handler(1);
+    h.set_portamento(PortamentoMode::Legato);
+    let mut ev = events();
+
+    h.handle_note_on(0, 60, 1.0, &mut ev);
+    h.handle_note_off(0, 60, 1.0, &mut ev);
+    h.handle_note_on(0, 64, 1.0, &mut ev);
+    h.handle_note_on(0, 67, 1.0, &mut ev);
+
+    assert_eq!(trigger_glides(&ev), vec![false, false, true]);
+}
+
+#[test]
+fn mono_portamento_off_never_glides() {
+    let mut h = handler(1);
+    h.set_legato(true);
+    h.set_portamento(PortamentoMode::Off);
+    let mut ev = events();
+
+    h.handle_note_on(0, 60, 1.0, &mut ev);
+    h.handle_note_on(0, 64, 1.0, &mut ev);
+
+    match ev.events().last().unwrap() {
+        VoiceEvent::Update { glide, .. } => assert!(!*glide),
+        _ => panic!("expected Update"),
+    }
+}
+
 // ---- Monophonic legato ----
 
 #[test]
@@ -439,6 +556,25 @@ fn choke_unknown_is_noop() {
     assert_eq!(h.free_voices.len(), free_before);
 }
 
+#[test]
+fn clear_frees_all_voices() {
+    let mut h = handler(2);
+    let mut ev = events();
+
+    h.handle_note_on(0, 60, 1.0, &mut ev);
+    h.handle_note_on(0, 62, 1.0, &mut ev);
+    h.handle_note_on(0, 64, 1.0, &mut ev);
+    h.handle_note_off(0, 60, 1.0, &mut ev);
+    h.clear();
+
+    let ui = h.get_ui_state();
+    assert_eq!(
+        (ui.waiting, ui.playing, ui.releasing, ui.killing),
+        (0, 0, 0, 0)
+    );
+    assert_eq!(h.free_voices.len(), MAX_VOICES);
+}
+
 // ---- handle_expression ----
 
 #[test]
@@ -685,3 +821,177 @@ fn get_ui_data_reflects_complex_state() {
     assert!(ui.legato);
     assert!(ui.playing + ui.waiting + ui.releasing + ui.killing > 0);
 }
+
+// ---- handle_channel_expression ----
+
+#[test]
+fn channel_expression_ignored_without_mpe() {
+    let mut h = handler(4);
+    let mut ev = events();
+
+    h.handle_note_on(1, 60, 1.0, &mut ev);
+
+    let len_before = ev.events().len();
+    h.handle_channel_expression(1, Expression::Pitch, 2.0, &mut ev);
+    assert_eq!(ev.events().len(), len_before);
+}
+
+#[test]
+fn channel_expression_applies_to_notes_on_channel() {
+    let mut h = handler(4);
+    h.set_mpe(true);
+    let mut ev = events();
+
+    h.handle_note_on(1, 60, 1.0, &mut ev);
+    h.handle_note_on(2, 64, 1.0, &mut ev);
+    let voice_idx = trigger_indices(&ev)[0];
+
+    let len_before = ev.events().len();
+    h.handle_channel_expression(1, Expression::Pressure, 0.7, &mut ev);
+
+    assert_eq!(ev.events().len(), len_before + 1);
+    match &ev.events()[len_before] {
+        VoiceEvent::Expression {
+            voice_idx: idx,
+            expression,
+            value,
+        } => {
+            assert_eq!(*idx, voice_idx);
+            assert_eq!(*expression, Expression::Pressure);
+            assert_eq!(*value, 0.7);
+        }
+        _ => panic!("expected Expression event"),
+    }
+}
+
+#[test]
+fn channel_expression_applied_on_note_on() {
+    let mut h = handler(4);
+    h.set_mpe(true);
+    let mut ev = events();
+
+    h.handle_channel_expression(3, Expression::Pitch, -2.0, &mut ev);
+    assert!(ev.events().is_empty());
+
+    h.handle_note_on(3, 60, 1.0, &mut ev);
+
+    let (trig, _, _, _, expr) = count_by_kind(&ev);
+    assert_eq!(trig, 1);
+    assert_eq!(expr, 1);
+    assert!(matches!(
+        ev.events().last(),
+        Some(VoiceEvent::Expression {
+            expression: Expression::Pitch,
+            ..
+        })
+    ));
+}
+
+#[test]
+fn tuning_sets_trigger_pitch_and_skips_unmapped_keys() {
+    let mut h = handler(4);
+    let scl = "Whole tone\n6\n200.0\n400.0\n600.0\n800.0\n1000.0\n2/1\n";
+    let kbm = "2\n0\n127\n60\n60\n440.0\n1\n0\nx\n";
+    h.set_tuning(Some(
+        TuningConfig::from_scala_str(String::new(), scl, Some(kbm)).unwrap(),
+    ));
+    let mut ev = events();
+
+    h.handle_note_on(0, 61, 1.0, &mut ev);
+    assert!(ev.events().is_empty());
+
+    h.handle_note_on(0, 62, 1.0, &mut ev);
+    match ev.events().first() {
+        Some(VoiceEvent::Trigger { pitch, .. }) => {
+            assert!((pitch - 200.0 / 1200.0).abs() < 1e-5)
+        }
+        _ => panic!("expected Trigger event"),
+    }
+
+    h.set_tuning(None);
+    let mut ev = events();
+    h.handle_note_on(0, 61, 1.0, &mut ev);
+    assert!(matches!(
+        ev.events().first(),
+        Some(VoiceEvent::Trigger { pitch, .. }) if *pitch == note_pitch(61)
+    ));
+}
+
+#[test]
+fn continuous_retune_follows_pitch_changes_once() {
+    let mut h = handler(4);
+    let mut ev = events();
+
+    h.handle_note_on(0, 60, 1.0, &mut ev);
+    h.retune(&mut ev);
+    assert_eq!(ev.events().len(), 1);
+
+    h.set_mts_esp(MtsEspMode::Continuous);
+    h.set_tuning(Some(TuningConfig::edo(24)));
+
+    let mut ev = events();
+    h.retune(&mut ev);
+    let expected = TuningConfig::edo(24).pitch(60).unwrap();
+    assert!(matches!(
+        ev.events(),
+        [VoiceEvent::Retune { pitch, .. }] if *pitch == expected
+    ));
+
+    let mut ev = events();
+    h.retune(&mut ev);
+    assert!(ev.events().is_empty());
+}
+
+#[test]
+fn transpose_and_master_tune_offset_note_pitch() {
+    let mut h = handler(4);
+    h.set_host_tuning(50.0, 12);
+    let mut ev = events();
+
+    h.handle_note_on(0, 60, 1.0, &mut ev);
+    match ev.events().first() {
+        Some(VoiceEvent::Trigger { pitch, .. }) => {
+            assert!((pitch - (note_pitch(72) + 50.0 / 1200.0)).abs() < 1e-5)
+        }
+        _ => panic!("expected Trigger event"),
+    }
+
+    // Held notes follow host automation of the transpose
+    h.retune(&mut ev);
+    let mut ev = events();
+    h.set_host_tuning(50.0, 0);
+    h.retune(&mut ev);
+    assert!(matches!(
+        ev.events(),
+        [VoiceEvent::Retune { pitch, .. }] if (pitch - (note_pitch(60) + 50.0 / 1200.0)).abs() < 1e-5
+    ));
+
+    h.set_host_tuning(500.0, 100);
+    assert_eq!(h.master_tune(), MAX_MASTER_TUNE);
+    assert_eq!(h.transpose(), MAX_TRANSPOSE);
+}
+
+#[test]
+fn retune_of_all_voices_stays_inline() {
+    // More voices than the old inline capacity
+    const _: () = assert!(MAX_AVAILABLE_VOICES > 8);
+
+    let mut h = handler(MAX_AVAILABLE_VOICES);
+    let mut ev = events();
+
+    for note in 0..MAX_AVAILABLE_VOICES as u8 {
+        h.handle_note_on(0, 48 + note, 1.0, &mut ev);
+    }
+
+    let mut ev = events();
+    h.set_host_tuning(0.0, 7);
+    h.retune(&mut ev);
+
+    assert_eq!(ev.events().len(), MAX_AVAILABLE_VOICES);
+    assert!(
+        ev.events()
+            .iter()
+            .all(|event| matches!(event, VoiceEvent::Retune { .. }))
+    );
+    assert!(!ev.events.spilled());
+}
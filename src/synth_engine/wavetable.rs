@@ -0,0 +1,80 @@
+use std::{path::Path, sync::Arc};
+
+use hound::{SampleFormat, WavSpec, WavWriter};
+use realfft::{ComplexToReal, RealFftPlanner};
+
+use crate::synth_engine::{
+    buffer::{SPECTRAL_BUFFER_SIZE, SpectralBuffer},
+    types::{ComplexSample, Sample},
+};
+
+// Serum-style frame size, one frame holds the whole spectrum
+pub const WAVETABLE_FRAME_SIZE: usize = 2 * SPECTRAL_BUFFER_SIZE;
+pub const MAX_WAVETABLE_FRAMES: usize = 256;
+const WAVETABLE_SAMPLE_RATE: u32 = 44_100;
+
+pub struct WavetableWriter {
+    inverse_fft: Arc<dyn ComplexToReal<Sample>>,
+    spectrum: Vec<ComplexSample>,
+    scratch: Vec<ComplexSample>,
+    samples: Vec<Sample>,
+}
+
+impl WavetableWriter {
+    pub fn new(num_frames: usize) -> Self {
+        let inverse_fft = RealFftPlanner::<Sample>::new().plan_fft_inverse(WAVETABLE_FRAME_SIZE);
+
+        Self {
+            spectrum: inverse_fft.make_input_vec(),
+            scratch: inverse_fft.make_scratch_vec(),
+            samples: Vec::with_capacity(num_frames * WAVETABLE_FRAME_SIZE),
+            inverse_fft,
+        }
+    }
+
+    pub fn add_frame(&mut self, spectrum: &SpectralBuffer) {
+        let from = self.samples.len();
+
+        self.spectrum[..SPECTRAL_BUFFER_SIZE].copy_from_slice(spectrum);
+        self.spectrum[SPECTRAL_BUFFER_SIZE..].fill(ComplexSample::ZERO);
+        self.spectrum[0] = ComplexSample::ZERO;
+        self.samples.resize(from + WAVETABLE_FRAME_SIZE, 0.0);
+
+        self.inverse_fft
+            .process_with_scratch(
+                &mut self.spectrum,
+                &mut self.samples[from..],
+                &mut self.scratch,
+            )
+            .unwrap();
+    }
+
+    pub fn write(mut self, path: &Path) -> Result<(), String> {
+        let peak = self
+            .samples
+            .iter()
+            .fold(0.0, |peak: Sample, sample| peak.max(sample.abs()));
+
+        // The whole table is scaled together so frame levels stay relative to each other
+        if peak > 0.0 {
+            self.samples.iter_mut().for_each(|sample| *sample /= peak);
+        }
+
+        let spec = WavSpec {
+            channels: 1,
+            sample_rate: WAVETABLE_SAMPLE_RATE,
+            bits_per_sample: 32,
+            sample_format: SampleFormat::Float,
+        };
+
+        let mut writer = WavWriter::create(path, spec).map_err(|err| err.to_string())?;
+
+        for sample in &self.samples {
+            writer
+                .write_sample(*sample)
+                .map_err(|err| err.to_string())?;
+        }
+
+        writer.finalize().map_err(|err| err.to_string())
+    }
+}
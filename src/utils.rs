@@ -18,6 +18,11 @@ pub fn pitch_to_freq(pitch: Sample) -> Sample {
     pitch.exp2() * 440.0
 }
 
+#[inline(always)]
+pub fn freq_to_pitch(freq: Sample) -> Sample {
+    (freq / 440.0).log2()
+}
+
 #[inline(always)]
 pub const fn st_to_octave(st: Sample) -> Sample {
     st * ST_TO_OCTAVE_MULT